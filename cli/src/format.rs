@@ -0,0 +1,281 @@
+use std::{
+    fs::{self, File},
+    io::{self, BufRead, BufWriter, Write},
+    path::Path,
+};
+
+use anyhow::Error;
+use clap::ValueEnum;
+
+use hbt_core::collection::Collection;
+#[cfg(feature = "buku")]
+use hbt_core::format::buku;
+#[cfg(feature = "firefox")]
+use hbt_core::format::firefox;
+#[cfg(feature = "netscape")]
+use hbt_core::format::netscape;
+#[cfg(feature = "pocket")]
+use hbt_core::format::pocket;
+#[cfg(feature = "raindrop")]
+use hbt_core::format::raindrop;
+#[cfg(feature = "pinboard")]
+use hbt_core::{
+    collection::Entity,
+    pinboard::{self, Post},
+};
+use hbt_core::{
+    format::{atom, chrome, dot, graphml, json, opml, org, safari},
+    markdown,
+};
+
+#[derive(ValueEnum, Debug, Clone, Copy, PartialEq, Eq)]
+pub enum Format {
+    /// hbt collection JSON
+    Json,
+    /// Markdown
+    Markdown,
+    /// Netscape bookmark file HTML
+    #[cfg(feature = "netscape")]
+    Netscape,
+    /// Pinboard HTML export
+    #[cfg(feature = "pinboard")]
+    PinboardHtml,
+    /// Pinboard JSON export
+    #[cfg(feature = "pinboard")]
+    PinboardJson,
+    /// Pinboard XML export
+    #[cfg(feature = "pinboard")]
+    PinboardXml,
+    /// Pocket HTML export
+    #[cfg(feature = "pocket")]
+    PocketHtml,
+    /// Pocket CSV export
+    #[cfg(feature = "pocket")]
+    PocketCsv,
+    /// Raindrop.io CSV export
+    #[cfg(feature = "raindrop")]
+    Raindrop,
+    /// Chrome or Chromium Bookmarks file (import only)
+    Chrome,
+    /// Safari Bookmarks.plist (import only)
+    Safari,
+    /// buku SQLite database
+    #[cfg(feature = "buku")]
+    Buku,
+    /// Firefox places.sqlite database (import only)
+    #[cfg(feature = "firefox")]
+    Firefox,
+    /// Org-mode outline
+    Org,
+    /// GraphViz DOT graph (export only)
+    Dot,
+    /// GraphML graph (export only)
+    Graphml,
+    /// Atom feed of the latest entries (export only)
+    Atom,
+    /// OPML list of entries as feed subscriptions (export only)
+    Opml,
+}
+
+impl Format {
+    /// Detects the format of a file to read from its extension, looking at the start of its
+    /// contents to tell apart formats that share an extension.
+    pub fn detect(path: &Path, contents: &str) -> Option<Format> {
+        let start = contents.trim_start();
+        // Chrome's bookmarks file has no extension
+        if path.file_name().is_some_and(|name| name == "Bookmarks") && start.starts_with('{') {
+            return Some(Format::Chrome);
+        }
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "md" | "markdown" => Some(Format::Markdown),
+            "org" => Some(Format::Org),
+            "plist" if contents.starts_with("bplist00") => Some(Format::Safari),
+            "json" if start.starts_with('{') && has_key(start, "roots") => Some(Format::Chrome),
+            "json" if start.starts_with('{') => Some(Format::Json),
+            #[cfg(feature = "pinboard")]
+            "json" if start.starts_with('[') => Some(Format::PinboardJson),
+            #[cfg(feature = "pinboard")]
+            "xml" => Some(Format::PinboardXml),
+            #[cfg(feature = "pocket")]
+            "html" | "htm" if contents.contains("<title>Pocket Export</title>") => {
+                Some(Format::PocketHtml)
+            }
+            #[cfg(feature = "netscape")]
+            "html" | "htm" => Some(Format::Netscape),
+            #[cfg(all(feature = "pinboard", not(feature = "netscape")))]
+            "html" | "htm" => Some(Format::PinboardHtml),
+            #[cfg(feature = "pocket")]
+            "csv" if first_line(contents).split(',').any(|column| column == "time_added") => {
+                Some(Format::PocketCsv)
+            }
+            #[cfg(feature = "raindrop")]
+            "csv" => Some(Format::Raindrop),
+            #[cfg(feature = "firefox")]
+            "sqlite"
+                if contents.starts_with("SQLite format 3") && contents.contains("moz_places") =>
+            {
+                Some(Format::Firefox)
+            }
+            #[cfg(feature = "buku")]
+            "db" if contents.starts_with("SQLite format 3") && is_buku(contents) => {
+                Some(Format::Buku)
+            }
+            _ => None,
+        }
+    }
+
+    /// Reads a collection in this format from a file.  Databases are opened where they are, and
+    /// Markdown entries record the lines they are on; the rest are read as by [`Format::read`].
+    pub fn read_file(self, path: &Path, reader: impl BufRead) -> Result<Collection, Error> {
+        match self {
+            #[cfg(feature = "buku")]
+            Format::Buku => Ok(buku::open(path)?),
+            #[cfg(feature = "firefox")]
+            Format::Firefox => Ok(firefox::open(path)?),
+            Format::Markdown => {
+                let path = fs::canonicalize(path)?;
+                Ok(markdown::parse_from(&io::read_to_string(reader)?, &path)?)
+            }
+            format => format.read(reader),
+        }
+    }
+
+    /// Writes a collection in this format to a file.  Databases are updated where they are; the
+    /// rest are written as by [`Format::write`].
+    pub fn write_file(self, collection: &Collection, path: &Path) -> Result<(), Error> {
+        match self {
+            #[cfg(feature = "buku")]
+            Format::Buku => buku::save(collection, path)?,
+            format => {
+                let mut writer = BufWriter::new(File::create(path)?);
+                format.write(collection, &mut writer)?;
+                writer.flush()?;
+            }
+        }
+        Ok(())
+    }
+
+    /// Detects the format of a file to write from its extension.
+    pub fn detect_output(path: &Path) -> Option<Format> {
+        let ext = path.extension()?.to_str()?.to_ascii_lowercase();
+        match ext.as_str() {
+            "json" => Some(Format::Json),
+            "md" | "markdown" => Some(Format::Markdown),
+            #[cfg(feature = "netscape")]
+            "html" | "htm" => Some(Format::Netscape),
+            #[cfg(feature = "raindrop")]
+            "csv" => Some(Format::Raindrop),
+            "org" => Some(Format::Org),
+            "dot" | "gv" => Some(Format::Dot),
+            "graphml" => Some(Format::Graphml),
+            "atom" => Some(Format::Atom),
+            "opml" => Some(Format::Opml),
+            _ => None,
+        }
+    }
+
+    /// Reads a collection in this format.  The formats that large exports come in are parsed as
+    /// they are read; the rest are read in full first.
+    pub fn read(self, mut reader: impl BufRead) -> Result<Collection, Error> {
+        let ret = match self {
+            Format::Json => json::from_reader(reader)?,
+            Format::Markdown => markdown::parse(&io::read_to_string(reader)?)?,
+            #[cfg(feature = "netscape")]
+            Format::Netscape => {
+                let mut ret = Collection::new();
+                for entity in netscape::Reader::new(reader) {
+                    ret.upsert(entity?);
+                }
+                ret
+            }
+            #[cfg(feature = "pinboard")]
+            Format::PinboardHtml => from_posts(Post::from_html(&io::read_to_string(reader)?)?)?,
+            #[cfg(feature = "pinboard")]
+            Format::PinboardJson => {
+                let mut ret = Collection::new();
+                for post in Post::read_json(reader) {
+                    ret.upsert(Entity::try_from(post?)?);
+                }
+                ret
+            }
+            #[cfg(feature = "pinboard")]
+            Format::PinboardXml => from_posts(Post::from_xml(&io::read_to_string(reader)?)?)?,
+            #[cfg(feature = "pocket")]
+            Format::PocketHtml => pocket::parse_html(&io::read_to_string(reader)?)?,
+            #[cfg(feature = "pocket")]
+            Format::PocketCsv => pocket::parse_csv(&io::read_to_string(reader)?)?,
+            #[cfg(feature = "raindrop")]
+            Format::Raindrop => raindrop::parse(&io::read_to_string(reader)?)?,
+            Format::Org => org::parse(&io::read_to_string(reader)?)?,
+            Format::Chrome => chrome::parse(&io::read_to_string(reader)?)?,
+            Format::Safari => {
+                let mut bytes = Vec::new();
+                reader.read_to_end(&mut bytes)?;
+                safari::parse(&bytes)?
+            }
+            #[cfg(feature = "buku")]
+            Format::Buku => return Err(Error::msg("buku databases can only be read from a file")),
+            #[cfg(feature = "firefox")]
+            Format::Firefox => {
+                return Err(Error::msg("Firefox databases can only be read from a file"));
+            }
+            format @ (Format::Dot | Format::Graphml | Format::Atom | Format::Opml) => {
+                let name = format.to_possible_value().expect("no skipped variants");
+                return Err(Error::msg(format!("No parser for format: {}", name.get_name())));
+            }
+        };
+        Ok(ret)
+    }
+
+    pub fn write(self, collection: &Collection, writer: impl Write) -> Result<(), Error> {
+        match self {
+            Format::Json => json::to_writer(collection, writer)?,
+            Format::Markdown => markdown::to_writer(collection, &Default::default(), writer)?,
+            #[cfg(feature = "netscape")]
+            Format::Netscape => netscape::to_writer(collection, writer)?,
+            #[cfg(feature = "raindrop")]
+            Format::Raindrop => raindrop::to_writer(collection, writer)?,
+            Format::Org => org::to_writer(collection, &Default::default(), writer)?,
+            Format::Dot => dot::to_writer(collection, &Default::default(), writer)?,
+            Format::Graphml => graphml::to_writer(collection, writer)?,
+            Format::Atom => atom::to_writer(collection, &Default::default(), writer)?,
+            Format::Opml => opml::to_writer(collection, &Default::default(), writer)?,
+            #[cfg(feature = "buku")]
+            Format::Buku => return Err(Error::msg("buku databases can only be written to a file")),
+            #[allow(unreachable_patterns)]
+            format => {
+                let name = format.to_possible_value().expect("no skipped variants");
+                return Err(Error::msg(format!("No writer for format: {}", name.get_name())));
+            }
+        }
+        Ok(())
+    }
+}
+
+/// Returns whether the start of a SQLite database holds buku's table, judging by its schema.
+#[cfg(feature = "buku")]
+fn is_buku(contents: &str) -> bool {
+    contents.contains("bookmarks (id integer PRIMARY KEY, URL text NOT NULL UNIQUE")
+}
+
+/// Returns whether the JSON text has an object key, judging only by the text.
+fn has_key(contents: &str, key: &str) -> bool {
+    let quoted = format!("\"{}\"", key);
+    contents.match_indices(&quoted).any(|(index, _)| {
+        !contents[..index].ends_with('\\')
+            && contents[index + quoted.len()..].trim_start().starts_with(':')
+    })
+}
+
+#[cfg(feature = "pocket")]
+fn first_line(contents: &str) -> &str {
+    contents.lines().next().unwrap_or_default().trim()
+}
+
+#[cfg(feature = "pinboard")]
+fn from_posts(posts: Vec<Post>) -> Result<Collection, Error> {
+    let mut ret = Collection::with_capacity(posts.len());
+    pinboard::import(posts, &mut ret)?;
+    Ok(ret)
+}
@@ -1,19 +1,78 @@
-use std::{collections::BTreeSet, fs, path::PathBuf, process::ExitCode};
+mod format;
+mod store;
+
+use std::{
+    collections::BTreeSet,
+    fs::{self, File},
+    io::{self, BufRead, BufReader, BufWriter, Write},
+    path::{Path, PathBuf},
+    process::ExitCode,
+};
 
 use anyhow::Error;
-use clap::Parser;
+use clap::{Parser, Subcommand, ValueEnum};
 
-use hbt_core::collection::Collection;
-#[cfg(feature = "pinboard")]
-use hbt_core::collection::Entity;
-use hbt_core::markdown;
+#[cfg(feature = "archive")]
+use hbt_core::archive;
+#[cfg(feature = "random")]
+use hbt_core::collection::Weight;
+use hbt_core::collection::{
+    Collection, Conflict, Entity, Id, Label, MergePolicy, Name, Note, PageRankOptions,
+    PreferExisting, PreferNewer, Side, Snapshot, SortKey, Time, Union, DEGREE_KEY, PAGERANK_KEY,
+};
+#[cfg(feature = "favicon")]
+use hbt_core::favicon;
+#[cfg(feature = "fetch")]
+use hbt_core::fetch;
+#[cfg(feature = "native-messaging")]
+use hbt_core::native_messaging;
 #[cfg(feature = "pinboard")]
-use hbt_core::pinboard::Post;
+use hbt_core::pinboard::{self, Post};
+#[cfg(feature = "rpc")]
+use hbt_core::rpc;
+#[cfg(feature = "serve")]
+use hbt_core::serve;
+#[cfg(feature = "linkding")]
+use hbt_core::sync::linkding;
+#[cfg(feature = "wallabag")]
+use hbt_core::sync::wallabag;
+#[cfg(feature = "wayback")]
+use hbt_core::wayback;
+#[cfg(feature = "check")]
+use hbt_core::{check, collection::LinkStatus};
+use hbt_core::{
+    dedupe,
+    import::{cache::Cache, Imported},
+    markdown,
+    query::Query,
+    url_norm::TrackingFilter,
+};
+use serde::Serialize;
 use serde_json::Value;
+use time::{macros::format_description, Date, OffsetDateTime};
+use url::Url;
+
+use crate::{format::Format, store::Store};
 
 #[derive(Parser, Debug)]
 #[command(version, about, long_about = None)]
+#[command(args_conflicts_with_subcommands = true, subcommand_negates_reqs = true)]
 struct Args {
+    #[command(subcommand)]
+    command: Option<Command>,
+    /// Read and write the collection in <FILE> [default: $HBT_STORE]
+    #[arg(short, long, value_name = "FILE", global = true)]
+    store: Option<PathBuf>,
+    /// Make the <FILE> directory a git repository if it is not one yet
+    #[cfg(feature = "git")]
+    #[arg(long, global = true)]
+    git_init: bool,
+    /// Strip utm_*, fbclid and gclid parameters from URLs as they are added
+    #[arg(long, global = true)]
+    strip_tracking: bool,
+    /// Strip the <PARAM> query parameter from URLs as they are added (repeatable)
+    #[arg(long = "strip-param", value_name = "PARAM", global = true)]
+    strip_params: Vec<String>,
     /// Dump all entries
     #[arg(short, long)]
     dump: bool,
@@ -22,19 +81,1429 @@ struct Args {
     tags: bool,
     /// File to read
     #[arg(required = true)]
-    file: PathBuf,
+    file: Option<PathBuf>,
     /// Read mappings from <FILE>
     #[arg(short, long, value_name = "FILE")]
     mappings: Option<PathBuf>,
 }
 
+#[derive(Subcommand, Debug)]
+enum Command {
+    /// Add a URL to the store, or update it if already present
+    Add {
+        /// URL to add
+        url: Url,
+        /// Name of the entry
+        #[arg(short, long)]
+        name: Option<String>,
+        /// Label for the entry (repeatable)
+        #[arg(short, long = "label", value_name = "LABEL")]
+        labels: Vec<String>,
+        /// Attach a note to the entry
+        #[arg(long)]
+        note: Option<String>,
+    },
+    /// Search the store for entries whose URL, names or labels contain <QUERY>
+    Search {
+        /// Text to search for, ignoring case
+        query: String,
+        /// Match whole words in names, labels and notes instead, best matches first
+        #[arg(long)]
+        ranked: bool,
+        /// Match approximately, tolerating typos, best matches first
+        #[arg(long, conflicts_with = "ranked")]
+        fuzzy: bool,
+        /// Print matches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Search the store for entries whose URL, names or labels match the regular expression <PATTERN>
+    #[cfg(feature = "regex")]
+    Grep {
+        /// Regular expression to match
+        pattern: String,
+        /// Print matches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List entries matching a query like 'label:rust AND (name:tokio OR created>2023-01-01)'
+    Query {
+        /// Query to evaluate
+        query: String,
+        /// Print matches as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List entries in the store
+    List(ListArgs),
+    /// Print an entry picked at random, to resurface a forgotten one
+    #[cfg(feature = "random")]
+    Random {
+        /// Only pick among entries with <LABEL> or a label nested under it
+        #[arg(long)]
+        label: Option<String>,
+        /// Favor older entries
+        #[arg(long, conflicts_with = "unread")]
+        older: bool,
+        /// Favor unread entries
+        #[arg(long)]
+        unread: bool,
+        /// Print the entry as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Mark an entry as read
+    Read {
+        /// URL of the entry
+        url: Url,
+        /// Mark the entry as archived instead
+        #[arg(long, conflicts_with = "unread")]
+        archive: bool,
+        /// Mark the entry as unread instead
+        #[arg(long)]
+        unread: bool,
+    },
+    /// Star an entry, so that it is listed first
+    Star {
+        /// URL of the entry
+        url: Url,
+        /// Remove the star instead
+        #[arg(long)]
+        unstar: bool,
+    },
+    /// Import entries from each <FILE> into the store
+    Import {
+        /// Files to import, or directories of Markdown files
+        #[arg(required = true)]
+        files: Vec<PathBuf>,
+        /// Read each <FILE> as <FORMAT> instead of detecting it
+        #[arg(short, long)]
+        format: Option<Format>,
+        /// Resolve differing names, labels and creation times of entries already in the store in
+        /// favor of <PREFER>, instead of keeping both
+        #[arg(long)]
+        prefer: Option<Prefer>,
+        /// Skip files unchanged since they were last imported into this store
+        #[arg(long)]
+        incremental: bool,
+    },
+    /// Compare the entries in <OLD> with those in <NEW>
+    Diff {
+        /// File to compare from
+        old: PathBuf,
+        /// File to compare to
+        new: PathBuf,
+        /// Read both files as <FORMAT> instead of detecting it
+        #[arg(short, long)]
+        format: Option<Format>,
+        /// Print differences as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Merge the changes made in <OURS> and <THEIRS> since their common ancestor <BASE>
+    Merge3 {
+        /// File both sides descend from
+        base: PathBuf,
+        /// File with our changes
+        ours: PathBuf,
+        /// File with their changes
+        theirs: PathBuf,
+        /// Read the files as <FORMAT> instead of detecting it
+        #[arg(short, long)]
+        format: Option<Format>,
+        /// Write to <OUTPUT>, in the format its extension implies, instead of standard output as JSON
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+    },
+    /// Export the store
+    Export {
+        /// Write as <FORMAT> instead of detecting it from <OUTPUT>
+        #[arg(short, long)]
+        format: Option<Format>,
+        /// Write to <OUTPUT> instead of standard output
+        #[arg(short, long)]
+        output: Option<PathBuf>,
+        /// Only write entries with <LABEL> or a label nested under it, and the edges between them
+        #[arg(long)]
+        label: Option<String>,
+    },
+    /// Save a snapshot of the store to <FILE>
+    Snapshot {
+        /// File to write the snapshot to
+        file: PathBuf,
+    },
+    /// Replace the contents of the store with a snapshot saved by `snapshot`
+    Restore {
+        /// Snapshot to restore
+        file: PathBuf,
+    },
+    /// Rename a label on every entry carrying it
+    RenameLabel {
+        /// Label to rename
+        old: String,
+        /// New name for the label
+        new: String,
+    },
+    /// Fold a label into another on every entry, and on entries added with it later
+    MergeLabel {
+        /// Label to fold away
+        from: String,
+        /// Label to fold it into
+        into: String,
+    },
+    /// Merge entries whose URLs differ only in scheme, trailing slash or tracking parameters
+    Dedupe {
+        /// Ask before merging each group of entries
+        #[arg(short, long)]
+        interactive: bool,
+        /// Print what would be merged without changing the store
+        #[arg(short = 'n', long)]
+        dry_run: bool,
+    },
+    /// List the entries most like an entry, by the labels and host they share, best first
+    Similar {
+        /// URL of the entry
+        url: Url,
+        /// List at most <COUNT> entries
+        #[arg(short = 'n', long, value_name = "COUNT", default_value_t = 5)]
+        count: usize,
+        /// Also link the entry to each of them with a "similar" edge
+        #[arg(long)]
+        link: bool,
+        /// Print entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Rank entries by their PageRank over the edges, most central first
+    Centrality {
+        /// Rank entries by the number of edges to and from them instead
+        #[arg(long)]
+        degree: bool,
+        /// List at most <COUNT> entries
+        #[arg(short = 'n', long, value_name = "COUNT")]
+        count: Option<usize>,
+        /// Also record each entry's score in its metadata
+        #[arg(long)]
+        save: bool,
+        /// Print scores as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the entries along the shortest chain of edges from one entry to another
+    Path {
+        /// URL of the entry to start from
+        from: Url,
+        /// URL of the entry to end at
+        to: Url,
+        /// Print entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List entries that are not linked to or from any other entry
+    Orphans {
+        /// List entries that no edge points to, whether or not they have edges of their own
+        #[arg(long, conflicts_with = "sinks")]
+        sources: bool,
+        /// List entries without edges of their own, whether or not edges point to them
+        #[arg(long)]
+        sinks: bool,
+        /// Print entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the pairs of labels carried together by number of entries, most common first
+    Cooccurrence {
+        /// List the labels carried alongside <LABEL> instead, most related first
+        #[arg(long)]
+        label: Option<String>,
+        /// Print counts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// List the domains in the store by number of entries, most common first
+    Domains {
+        /// List the entries on <DOMAIN> instead
+        #[arg(long)]
+        domain: Option<String>,
+        /// Print counts or entries as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Count entries per domain, label and year of creation
+    Stats {
+        /// Print counts as JSON
+        #[arg(long)]
+        json: bool,
+    },
+    /// Request every URL in the store and report dead or redirected links
+    #[cfg(feature = "check")]
+    Check {
+        /// Number of requests in flight at once
+        #[arg(short, long, default_value_t = 8)]
+        concurrency: usize,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Fetch page titles for entries without a name
+    #[cfg(feature = "fetch")]
+    FetchTitles {
+        /// Number of requests in flight at once
+        #[arg(short, long, default_value_t = 8)]
+        concurrency: usize,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Fetch favicons for the hosts of entries, caching them on disk
+    #[cfg(feature = "favicon")]
+    FetchFavicons {
+        /// Number of requests in flight at once
+        #[arg(short, long, default_value_t = 8)]
+        concurrency: usize,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 10)]
+        timeout: u64,
+        /// Cache favicons in <DIR> [default: $XDG_CACHE_HOME/hbt/favicons]
+        #[arg(long, value_name = "DIR")]
+        cache_dir: Option<PathBuf>,
+    },
+    /// Run as the native messaging host of a browser extension
+    #[cfg(feature = "native-messaging")]
+    NativeHost {
+        /// What the browser passes the host, such as the extension's origin
+        #[arg(trailing_var_arg = true, allow_hyphen_values = true, hide = true)]
+        browser_args: Vec<String>,
+    },
+    /// Answer JSON-RPC requests, one per line, on standard input
+    #[cfg(feature = "rpc")]
+    Rpc,
+    /// Serve the store over HTTP as a JSON API
+    #[cfg(feature = "serve")]
+    Serve {
+        /// Address to listen on
+        #[arg(short, long, default_value = "127.0.0.1:8080")]
+        addr: std::net::SocketAddr,
+    },
+    /// Write changed names and labels back into the Markdown files entries came from
+    WriteBack,
+    /// Merge Markdown files into the store as they change
+    Watch {
+        /// Directory of Markdown files, searched recursively
+        dir: PathBuf,
+        /// Seconds between looking for changes
+        #[arg(short, long, default_value_t = 1)]
+        interval: u64,
+    },
+    /// Download a local copy of every entry's page that has none
+    #[cfg(feature = "archive")]
+    ArchivePages {
+        /// Inline each page's assets into it rather than storing them beside it
+        #[arg(long)]
+        single_file: bool,
+        /// Number of pages downloaded at once
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 30)]
+        timeout: u64,
+        /// Keep copies in <DIR> [default: $XDG_DATA_HOME/hbt/archive]
+        #[arg(long, value_name = "DIR")]
+        archive_dir: Option<PathBuf>,
+    },
+    /// Sync the store with a linkding server
+    #[cfg(feature = "linkding")]
+    Linkding {
+        /// Base URL of the server
+        url: Url,
+        /// API token, from the server's settings page [default: $HBT_LINKDING_TOKEN]
+        #[arg(long)]
+        token: Option<String>,
+        /// Only sync changes made since <TIME>, as printed by the last sync (Unix timestamp,
+        /// RFC 3339 date-time or YYYY-MM-DD)
+        #[arg(long, value_name = "TIME", value_parser = Time::parse)]
+        since: Option<Time>,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 10)]
+        timeout: u64,
+    },
+    /// Look up the latest Wayback Machine snapshot of every entry
+    #[cfg(feature = "wayback")]
+    Wayback {
+        /// Also request new snapshots of entries found dead by the last check
+        #[arg(long)]
+        save_dead: bool,
+        /// Number of requests in flight at once
+        #[arg(short, long, default_value_t = 4)]
+        concurrency: usize,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 60)]
+        timeout: u64,
+    },
+    /// Save entries with a label to a Wallabag instance, recording their article ids
+    #[cfg(feature = "wallabag")]
+    Wallabag {
+        /// Base URL of the instance
+        url: Url,
+        /// Save entries with <LABEL> or a label nested under it
+        #[arg(short, long, default_value = wallabag::DEFAULT_LABEL)]
+        label: String,
+        /// API client id [default: $HBT_WALLABAG_CLIENT_ID]
+        #[arg(long)]
+        client_id: Option<String>,
+        /// API client secret [default: $HBT_WALLABAG_CLIENT_SECRET]
+        #[arg(long)]
+        client_secret: Option<String>,
+        /// User to log in as [default: $HBT_WALLABAG_USERNAME]
+        #[arg(long)]
+        username: Option<String>,
+        /// Password of the user [default: $HBT_WALLABAG_PASSWORD]
+        #[arg(long)]
+        password: Option<String>,
+        /// Seconds to wait for each response
+        #[arg(short, long, default_value_t = 30)]
+        timeout: u64,
+    },
+}
+
+#[derive(clap::Args, Debug)]
+struct ListArgs {
+    /// Only list entries with <LABEL> or a label nested under it, like <LABEL>/sub (repeatable)
+    #[arg(short, long = "label", value_name = "LABEL")]
+    labels: Vec<String>,
+    /// Only list entries created on or after <DATE> (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    since: Option<Date>,
+    /// Only list entries created on or before <DATE> (YYYY-MM-DD)
+    #[arg(long, value_name = "DATE", value_parser = parse_date)]
+    until: Option<Date>,
+    /// Only list entries on <DOMAIN> or its subdomains
+    #[arg(long)]
+    domain: Option<String>,
+    /// Only list unread entries
+    #[arg(long)]
+    unread: bool,
+    /// Only list starred entries
+    #[arg(long)]
+    starred: bool,
+    /// Order entries by <SORT> instead of by id
+    #[arg(long, value_enum)]
+    sort: Option<Sort>,
+    /// Print entries as JSON
+    #[arg(long)]
+    json: bool,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Prefer {
+    /// The entry already in the store
+    Existing,
+    /// Whichever entry was updated more recently
+    Newer,
+}
+
+#[derive(ValueEnum, Debug, Clone, Copy)]
+enum Sort {
+    Created,
+    Updated,
+    Name,
+    Url,
+    /// Most edges to and from first
+    Degree,
+}
+
+impl From<Sort> for SortKey {
+    fn from(sort: Sort) -> SortKey {
+        match sort {
+            Sort::Created => SortKey::CreatedAt,
+            Sort::Updated => SortKey::LastUpdated,
+            Sort::Name => SortKey::Name,
+            Sort::Url => SortKey::Url,
+            Sort::Degree => SortKey::Degree,
+        }
+    }
+}
+
+fn parse_date(s: &str) -> Result<Date, time::error::Parse> {
+    Date::parse(s, format_description!("[year]-[month]-[day]"))
+}
+
+#[derive(Serialize)]
+struct Entry<'a> {
+    id: Id,
+    entity: &'a Entity,
+}
+
+fn print_entities(
+    collection: &Collection,
+    ids: impl IntoIterator<Item = Id>,
+    json: bool,
+) -> Result<(), Error> {
+    if json {
+        let entries: Vec<Entry> =
+            ids.into_iter().map(|id| Entry { id, entity: collection.entity(id) }).collect();
+        println!("{}", serde_json::to_string_pretty(&entries)?);
+        return Ok(());
+    }
+    for id in ids {
+        let entity = collection.entity(id);
+        let created_at = OffsetDateTime::from(*entity.created_at()).date();
+        let names: Vec<&str> = entity.names().iter().map(Name::as_str).collect();
+        let labels: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+        println!(
+            "{}\t{}\t{}\t{}\t{}",
+            usize::from(id),
+            created_at,
+            entity.url(),
+            names.join(" | "),
+            labels.join(",")
+        );
+    }
+    Ok(())
+}
+
+/// Opens the store, first making a git repository of it if `--git-init` asks.
+fn open_store(args: &Args) -> Result<Store, Error> {
+    #[cfg(feature = "git")]
+    if args.git_init {
+        return Store::init_git(args.store.as_deref());
+    }
+    Store::open(args.store.as_deref())
+}
+
+/// Returns the filter for URLs entering the store, if one was asked for.
+fn url_filter(args: &Args) -> Option<TrackingFilter> {
+    if !args.strip_tracking && args.strip_params.is_empty() {
+        return None;
+    }
+    let filter =
+        if args.strip_tracking { TrackingFilter::default() } else { TrackingFilter::empty() };
+    Some(args.strip_params.iter().fold(filter, |filter, param| filter.with_param(param)))
+}
+
+fn add(
+    args: &Args,
+    url: Url,
+    name: Option<String>,
+    labels: Vec<String>,
+    note: Option<String>,
+) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    collection.set_url_filter(url_filter(args));
+    let existed = collection.contains(&url);
+    let labels = labels.into_iter().map(Label::new);
+    let now = Time::now();
+    let mut entity = Entity::builder(url).created(now).labels(labels);
+    if let Some(name) = name {
+        entity = entity.name(name);
+    }
+    if let Some(note) = note {
+        entity = entity.note(Note::new(now, note));
+    }
+    let id = collection.upsert(entity.build());
+    store.save(&collection)?;
+    let verb = if existed { "Updated" } else { "Added" };
+    println!("{}: {}", verb, collection.entity(id).url());
+    Ok(())
+}
+
+fn search(args: &Args, query: &str, ranked: bool, fuzzy: bool, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    if ranked || fuzzy {
+        let results =
+            if ranked { collection.search_ranked(query) } else { collection.search_fuzzy(query) };
+        let ids = results.into_iter().map(|(id, _)| id);
+        return print_entities(&collection, ids, json);
+    }
+    print_entities(&collection, collection.search(query), json)
+}
+
+#[cfg(feature = "regex")]
+fn grep(args: &Args, pattern: &str, json: bool) -> Result<(), Error> {
+    let regex = regex::Regex::new(pattern)?;
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    print_entities(&collection, collection.grep(&regex), json)
+}
+
+fn query(args: &Args, query: &str, json: bool) -> Result<(), Error> {
+    let query = Query::parse(query)?;
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    print_entities(&collection, query.select(&collection), json)
+}
+
+fn similar(args: &Args, url: &Url, count: usize, link: bool, json: bool) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let id = collection.id(url).ok_or_else(|| Error::msg(format!("Not in store: {}", url)))?;
+    let ids: Vec<Id> = collection.similar(id, count).into_iter().map(|(id, _)| id).collect();
+    if link && collection.link_similar(id, count) > 0 {
+        store.save(&collection)?;
+    }
+    print_entities(&collection, ids, json)
+}
+
+#[derive(Serialize)]
+struct Score<'a> {
+    id: Id,
+    url: &'a Url,
+    score: f64,
+}
+
+fn centrality(
+    args: &Args,
+    degree: bool,
+    count: Option<usize>,
+    save: bool,
+    json: bool,
+) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let (key, mut scores) = if degree {
+        (DEGREE_KEY, collection.degree_centrality())
+    } else {
+        (PAGERANK_KEY, collection.pagerank(&PageRankOptions::default()))
+    };
+    if save {
+        collection.record_scores(key, &scores);
+        store.save(&collection)?;
+    }
+    scores.truncate(count.unwrap_or(usize::MAX));
+    if json {
+        let scores: Vec<Score> = scores
+            .into_iter()
+            .map(|(id, score)| Score { id, url: collection.entity(id).url(), score })
+            .collect();
+        println!("{}", serde_json::to_string_pretty(&scores)?);
+        return Ok(());
+    }
+    for (id, score) in scores {
+        println!("{:.4}\t{}\t{}", score, id.index(), collection.entity(id).url());
+    }
+    Ok(())
+}
+
+fn path(args: &Args, from: &Url, to: &Url, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    let id =
+        |url: &Url| collection.id(url).ok_or_else(|| Error::msg(format!("Not in store: {}", url)));
+    let path = collection
+        .shortest_path(id(from)?, id(to)?)
+        .ok_or_else(|| Error::msg(format!("No path from {} to {}", from, to)))?;
+    print_entities(&collection, path, json)
+}
+
+fn orphans(args: &Args, sources: bool, sinks: bool, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    let ids = if sources {
+        collection.sources()
+    } else if sinks {
+        collection.sinks()
+    } else {
+        collection.orphans()
+    };
+    print_entities(&collection, ids, json)
+}
+
+fn on_domain(entity: &Entity, domain: &str) -> bool {
+    let Some(host) = entity.url().host_str() else {
+        return false;
+    };
+    let host = host.trim_end_matches('.');
+    let domain = domain.trim_end_matches('.');
+    host.eq_ignore_ascii_case(domain)
+        || host.len() > domain.len()
+            && host.as_bytes()[host.len() - domain.len() - 1] == b'.'
+            && host[host.len() - domain.len()..].eq_ignore_ascii_case(domain)
+}
+
+fn list(args: &Args, list_args: &ListArgs) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    let labels: Vec<Label> = list_args.labels.iter().cloned().map(Label::new).collect();
+    let ids = match list_args.sort {
+        Some(sort) => collection.sorted_ids(sort.into()),
+        None => collection.iter().map(|(id, _)| id).collect(),
+    };
+    let mut matches: Vec<(Id, &Entity)> = ids
+        .into_iter()
+        .map(|id| (id, collection.entity(id)))
+        .filter(|(_, entity)| {
+            labels.iter().all(|label| entity.labels().iter().any(|l| l.is_within(label)))
+        })
+        .filter(|(_, entity)| {
+            let date = OffsetDateTime::from(*entity.created_at()).date();
+            list_args.since.is_none_or(|since| since <= date)
+                && list_args.until.is_none_or(|until| date <= until)
+        })
+        .filter(|(_, entity)| list_args.domain.as_ref().is_none_or(|d| on_domain(entity, d)))
+        .filter(|(_, entity)| !list_args.unread || entity.read_status().is_unread())
+        .filter(|(_, entity)| !list_args.starred || entity.starred())
+        .collect();
+    // Starred entries come first, otherwise keeping the chosen order
+    matches.sort_by_key(|(_, entity)| !entity.starred());
+    print_entities(&collection, matches.into_iter().map(|(id, _)| id), list_args.json)
+}
+
+#[cfg(feature = "random")]
+fn random(
+    args: &Args,
+    label: Option<&str>,
+    older: bool,
+    unread: bool,
+    json: bool,
+) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    let label = label.map(Label::from);
+    let weight = if older {
+        Weight::Older
+    } else if unread {
+        Weight::Unread
+    } else {
+        Weight::Uniform
+    };
+    let id = collection
+        .random_with(&mut rand::thread_rng(), weight, |entity| {
+            label.as_ref().is_none_or(|label| entity.labels().iter().any(|l| l.is_within(label)))
+        })
+        .ok_or_else(|| Error::msg("No entries to pick from"))?;
+    print_entities(&collection, [id], json)
+}
+
+fn read(args: &Args, url: &Url, archive: bool, unread: bool) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let id = collection.id(url).ok_or_else(|| Error::msg(format!("Not in store: {}", url)))?;
+    let entity = collection.entity_mut(id);
+    let verb = if archive {
+        entity.archive();
+        "Archived"
+    } else if unread {
+        entity.mark_unread();
+        "Unread"
+    } else {
+        entity.mark_read(Time::now());
+        "Read"
+    };
+    let url = entity.url().clone();
+    store.save(&collection)?;
+    println!("{}: {}", verb, url);
+    Ok(())
+}
+
+fn star(args: &Args, url: &Url, unstar: bool) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let id = collection.id(url).ok_or_else(|| Error::msg(format!("Not in store: {}", url)))?;
+    let entity = collection.entity_mut(id);
+    entity.set_starred(!unstar);
+    let url = entity.url().clone();
+    store.save(&collection)?;
+    let verb = if unstar { "Unstarred" } else { "Starred" };
+    println!("{}: {}", verb, url);
+    Ok(())
+}
+
+/// How much of a file [`parse_file`] looks at to detect its format.
+const DETECT_LEN: usize = 64 * 1024;
+
+fn parse_file(file: &Path, format: Option<Format>) -> Result<Collection, Error> {
+    let mut reader = BufReader::with_capacity(DETECT_LEN, File::open(file)?);
+    let format = match format {
+        Some(format) => format,
+        None => {
+            let start = String::from_utf8_lossy(reader.fill_buf()?);
+            Format::detect(file, &start).ok_or_else(|| {
+                Error::msg(format!("Could not detect format of: {}", file.display()))
+            })?
+        }
+    };
+    format.read_file(file, reader)
+}
+
+/// Returns where the files imported into the store are remembered, under the cache directory, in
+/// a file named by a hash of the store's absolute path that stays the same from one build to the
+/// next.
+fn import_cache_path(args: &Args) -> Result<PathBuf, Error> {
+    let store = std::path::absolute(Store::resolve(args.store.as_deref())?)?;
+    let hash = hbt_core::hash::fnv1a(store.as_os_str().as_encoded_bytes());
+    let dir = default_cache_dir().ok_or_else(|| Error::msg("No cache directory: set HOME"))?;
+    Ok(dir.join("imports").join(format!("{:016x}.json", hash)))
+}
+
+fn import(
+    args: &Args,
+    files: &[PathBuf],
+    format: Option<Format>,
+    prefer: Option<Prefer>,
+    incremental: bool,
+) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    collection.set_url_filter(url_filter(args));
+    let mut policy: Box<dyn MergePolicy> = match prefer {
+        None => Box::new(Union),
+        Some(Prefer::Existing) => Box::new(PreferExisting),
+        Some(Prefer::Newer) => Box::new(PreferNewer),
+    };
+    let mut expanded = Vec::with_capacity(files.len());
+    for file in files {
+        if file.is_dir() {
+            expanded.extend(markdown::files(file)?);
+        } else {
+            expanded.push(file.clone());
+        }
+    }
+    let mut files = expanded;
+    let mut skipped = 0;
+    let cache = if incremental {
+        let path = import_cache_path(args)?;
+        let mut cache = Cache::load(&path)?;
+        let mut changed = Vec::with_capacity(files.len());
+        for file in files {
+            if cache.changed(&std::path::absolute(&file)?)? {
+                changed.push(file);
+            } else {
+                skipped += 1;
+            }
+        }
+        files = changed;
+        Some((path, cache))
+    } else {
+        None
+    };
+    let results = hbt_core::import::import_all(
+        &mut collection,
+        &files,
+        |file| parse_file(file, format),
+        |file| Some(file.display().to_string()),
+        policy.as_mut(),
+    );
+    // Save nothing unless every file was imported
+    let imported = results.into_iter().collect::<Result<Vec<_>, _>>()?;
+    if !files.is_empty() {
+        store.save(&collection)?;
+    }
+    if let Some((path, mut cache)) = cache {
+        for file in &files {
+            cache.record(&std::path::absolute(file)?)?;
+        }
+        cache.save(&path)?;
+    }
+    for (file, imported) in files.iter().zip(imported) {
+        let Imported { entities, added, merged } = imported;
+        println!("{}: {} entities, {} added, {} merged", file.display(), entities, added, merged);
+    }
+    if skipped > 0 {
+        println!("Skipped {} unchanged files", skipped);
+    }
+    Ok(())
+}
+
+fn export(
+    args: &Args,
+    format: Option<Format>,
+    output: Option<&Path>,
+    label: Option<&str>,
+) -> Result<(), Error> {
+    let format = format
+        .or_else(|| output.and_then(Format::detect_output))
+        .ok_or_else(|| Error::msg("No format given: use --format"))?;
+    let store = open_store(args)?;
+    let mut collection = store.load()?;
+    if let Some(label) = label {
+        collection = collection.subgraph_within(&Label::from(label)).0;
+    }
+    match output {
+        Some(path) => format.write_file(&collection, path)?,
+        None => format.write(&collection, io::stdout().lock())?,
+    }
+    Ok(())
+}
+
+fn confirm(prompt: &str) -> Result<bool, Error> {
+    print!("{} [y/N] ", prompt);
+    io::stdout().flush()?;
+    let mut answer = String::new();
+    io::stdin().read_line(&mut answer)?;
+    Ok(matches!(answer.trim(), "y" | "Y" | "yes"))
+}
+
+fn diff(old: &Path, new: &Path, format: Option<Format>, json: bool) -> Result<(), Error> {
+    let old = parse_file(old, format)?;
+    let new = parse_file(new, format)?;
+    let diff = old.diff(&new);
+    if json {
+        println!("{}", serde_json::to_string_pretty(&diff)?);
+        return Ok(());
+    }
+    for url in diff.added.iter() {
+        println!("+ {}", url);
+    }
+    for url in diff.removed.iter() {
+        println!("- {}", url);
+    }
+    for change in diff.changed.iter() {
+        println!("~ {}", change.url);
+        for name in change.names_added.iter() {
+            println!("  + name: {}", name.as_str());
+        }
+        for name in change.names_removed.iter() {
+            println!("  - name: {}", name.as_str());
+        }
+        for label in change.labels_added.iter() {
+            println!("  + label: {}", label.as_str());
+        }
+        for label in change.labels_removed.iter() {
+            println!("  - label: {}", label.as_str());
+        }
+    }
+    Ok(())
+}
+
+/// Returns whether the merge was free of conflicts.
+fn merge3(
+    base: &Path,
+    ours: &Path,
+    theirs: &Path,
+    format: Option<Format>,
+    output: Option<&Path>,
+) -> Result<bool, Error> {
+    let base = parse_file(base, format)?;
+    let ours = parse_file(ours, format)?;
+    let theirs = parse_file(theirs, format)?;
+    let merge = Collection::merge3(&base, &ours, &theirs);
+    for conflict in merge.conflicts.iter() {
+        match conflict {
+            Conflict::RemovedAndChanged { url, removed_by: Side::Ours } => {
+                eprintln!("CONFLICT\t{}: removed in ours, changed in theirs", url)
+            }
+            Conflict::RemovedAndChanged { url, removed_by: Side::Theirs } => {
+                eprintln!("CONFLICT\t{}: removed in theirs, changed in ours", url)
+            }
+        }
+    }
+    let format = output.and_then(Format::detect_output).unwrap_or(Format::Json);
+    match output {
+        Some(path) => {
+            let mut writer = BufWriter::new(File::create(path)?);
+            format.write(&merge.collection, &mut writer)?;
+            writer.flush()?;
+        }
+        None => format.write(&merge.collection, io::stdout().lock())?,
+    }
+    Ok(merge.conflicts.is_empty())
+}
+
+fn snapshot(args: &Args, file: &Path) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    collection.snapshot().save(file)?;
+    println!("{}: {} entities", file.display(), collection.len());
+    Ok(())
+}
+
+fn restore(args: &Args, file: &Path) -> Result<(), Error> {
+    let snapshot = Snapshot::load(file)?;
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    collection.restore(&snapshot);
+    store.save(&collection)?;
+    let taken_at = OffsetDateTime::from(*snapshot.taken_at()).date();
+    println!("Restored {} entities from snapshot taken {}", collection.len(), taken_at);
+    Ok(())
+}
+
+fn rename_label(args: &Args, old: &str, new: &str) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let count = collection.rename_label(&Label::from(old), Label::from(new));
+    if count > 0 {
+        store.save(&collection)?;
+    }
+    println!("Renamed {} to {} on {} entries", old, new, count);
+    Ok(())
+}
+
+fn merge_label(args: &Args, from: &str, into: &str) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let count = collection.merge_label(&Label::from(from), Label::from(into));
+    store.save(&collection)?;
+    println!("Merged {} into {} on {} entries", from, into, count);
+    Ok(())
+}
+
+fn cooccurrence(args: &Args, label: Option<&str>, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    if let Some(label) = label {
+        let related = collection.related_labels(&Label::from(label));
+        if json {
+            println!("{}", serde_json::to_string_pretty(&related)?);
+            return Ok(());
+        }
+        for related in related {
+            println!("{:.2}\t{}\t{}", related.score, related.together, related.label.as_str());
+        }
+        return Ok(());
+    }
+    let pairs = collection.label_pairs();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&pairs)?);
+        return Ok(());
+    }
+    for (a, b, count) in pairs {
+        println!("{}\t{}\t{}", count, a.as_str(), b.as_str());
+    }
+    Ok(())
+}
+
+fn domains(args: &Args, domain: Option<&str>, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    if let Some(domain) = domain {
+        let ids = collection.by_domain().remove(domain).unwrap_or_default();
+        return print_entities(&collection, ids, json);
+    }
+    let domains = collection.domains_by_count();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&domains)?);
+        return Ok(());
+    }
+    for (host, count) in domains {
+        println!("{}\t{}", count, host);
+    }
+    Ok(())
+}
+
+fn stats(args: &Args, json: bool) -> Result<(), Error> {
+    let store = open_store(args)?;
+    let collection = store.load()?;
+    let stats = collection.stats();
+    if json {
+        println!("{}", serde_json::to_string_pretty(&stats)?);
+        return Ok(());
+    }
+    println!("{} entries, {} edges", stats.entities, stats.edges);
+    // Most common first, ties in key order
+    fn section<K: std::fmt::Display>(title: &str, counts: impl IntoIterator<Item = (K, usize)>) {
+        let mut counts: Vec<(K, usize)> = counts.into_iter().collect();
+        counts.sort_by_key(|(_, count)| std::cmp::Reverse(*count));
+        if !counts.is_empty() {
+            println!("{}:", title);
+        }
+        for (key, count) in counts {
+            println!("  {}\t{}", count, key);
+        }
+    }
+    section("Domains", stats.domains);
+    section("Labels", stats.labels.iter().map(|(label, &count)| (label.as_str(), count)));
+    section("Years", stats.years);
+    Ok(())
+}
+
+fn dedupe(args: &Args, interactive: bool, dry_run: bool) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let mut collapsed = 0;
+    let mut groups = 0;
+    for group in dedupe::duplicates(&collection) {
+        let into = &group[0];
+        if interactive {
+            println!("{}", into);
+            for url in &group[1..] {
+                println!("  {}", url);
+            }
+            if !confirm("Merge?")? {
+                continue;
+            }
+        }
+        let merged = if dry_run {
+            group[1..].to_vec()
+        } else {
+            dedupe::merge_group(&mut collection, &group)
+        };
+        let verb = if dry_run { "Would merge into" } else { "Merged into" };
+        println!("{} {}:", verb, into);
+        for url in &merged {
+            println!("  {}", url);
+        }
+        collapsed += merged.len() + 1;
+        groups += 1;
+    }
+    if !dry_run && groups > 0 {
+        store.save(&collection)?;
+    }
+    println!("Collapsed {} entities into {}", collapsed, groups);
+    Ok(())
+}
+
+#[cfg(feature = "check")]
+fn check(args: &Args, concurrency: usize, timeout: u64) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options = check::Options { concurrency, timeout: std::time::Duration::from_secs(timeout) };
+    let results = check::Checker::new(&options).check(&mut collection);
+    store.save(&collection)?;
+    let (mut ok, mut redirected, mut dead, mut failed) = (0, 0, 0, 0);
+    for (id, result) in &results {
+        let url = collection.entity(*id).url();
+        match &result.status {
+            LinkStatus::Ok { .. } => ok += 1,
+            LinkStatus::Redirected { code, location } => {
+                redirected += 1;
+                println!("{}\t{} -> {}", code, url, location);
+            }
+            LinkStatus::Dead { code } => {
+                dead += 1;
+                println!("{}\t{}", code, url);
+            }
+            LinkStatus::Failed { error } => {
+                failed += 1;
+                println!("ERR\t{}: {}", url, error);
+            }
+        }
+    }
+    println!(
+        "Checked {} links: {} ok, {} redirected, {} dead, {} failed",
+        results.len(),
+        ok,
+        redirected,
+        dead,
+        failed
+    );
+    Ok(())
+}
+
+#[cfg(feature = "fetch")]
+fn fetch_titles(args: &Args, concurrency: usize, timeout: u64) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options = fetch::Options { concurrency, timeout: std::time::Duration::from_secs(timeout) };
+    let results = fetch::Fetcher::new(&options).fetch_titles(&mut collection);
+    let mut fetched = 0;
+    for (id, result) in &results {
+        let url = collection.entity(*id).url();
+        match result {
+            Ok(name) => {
+                fetched += 1;
+                println!("{}\t{}", url, name.as_str());
+            }
+            Err(err) => println!("ERR\t{}: {}", url, err),
+        }
+    }
+    if fetched > 0 {
+        store.save(&collection)?;
+    }
+    println!("Fetched {} of {} titles", fetched, results.len());
+    Ok(())
+}
+
+/// The environment variable holding the token for [`sync_linkding`].
+#[cfg(feature = "linkding")]
+const LINKDING_TOKEN_VAR: &str = "HBT_LINKDING_TOKEN";
+
+#[cfg(feature = "linkding")]
+fn sync_linkding(
+    args: &Args,
+    url: &Url,
+    token: Option<&str>,
+    since: Option<&Time>,
+    timeout: u64,
+) -> Result<(), Error> {
+    let token = match token {
+        Some(token) => token.to_string(),
+        None => std::env::var(LINKDING_TOKEN_VAR).map_err(|_| {
+            Error::msg(format!("No token given: use --token or set {}", LINKDING_TOKEN_VAR))
+        })?,
+    };
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options = linkding::Options {
+        timeout: std::time::Duration::from_secs(timeout),
+        ..linkding::Options::default()
+    };
+    let client = linkding::Client::new(url.clone(), token, &options);
+    let linkding::Synced { pulled, pushed, at } = client.sync(&mut collection, since)?;
+    store.save(&collection)?;
+    let Imported { entities, added, merged } = pulled;
+    println!("Pulled {} entities, {} added, {} merged", entities, added, merged);
+    println!("Pushed {} entities", pushed);
+    println!("Synced at {}", at.unix_timestamp());
+    Ok(())
+}
+
+#[cfg(feature = "wayback")]
+fn wayback(args: &Args, save_dead: bool, concurrency: usize, timeout: u64) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options = wayback::Options {
+        concurrency,
+        timeout: std::time::Duration::from_secs(timeout),
+        ..wayback::Options::default()
+    };
+    let archiver = wayback::Archiver::new(&options);
+    let results = archiver.lookup(&mut collection);
+    let mut found = 0;
+    for (id, result) in &results {
+        let url = collection.entity(*id).url();
+        match result {
+            Ok(Some(snapshot)) => {
+                found += 1;
+                println!("{}\t{}", url, snapshot.url);
+            }
+            Ok(None) => {}
+            Err(err) => println!("ERR\t{}: {}", url, err),
+        }
+    }
+    println!("Found {} of {} snapshots", found, results.len());
+    let mut saved = 0;
+    if save_dead {
+        let results = archiver.save_dead(&mut collection);
+        for (id, result) in &results {
+            let url = collection.entity(*id).url();
+            match result {
+                Ok(snapshot) => {
+                    saved += 1;
+                    println!("{}\t{}", url, snapshot);
+                }
+                Err(err) => println!("ERR\t{}: {}", url, err),
+            }
+        }
+        println!("Saved {} of {} snapshots", saved, results.len());
+    }
+    if found > 0 || saved > 0 {
+        store.save(&collection)?;
+    }
+    Ok(())
+}
+
+/// Returns the value of an option, or else of the environment variable standing in for it.
+#[cfg(feature = "wallabag")]
+fn arg_or_env(value: Option<&str>, flag: &str, var: &str) -> Result<String, Error> {
+    match value {
+        Some(value) => Ok(value.to_string()),
+        None => std::env::var(var).map_err(|_| {
+            Error::msg(format!("No {} given: use {} or set {}", &flag[2..], flag, var))
+        }),
+    }
+}
+
+#[cfg(feature = "wallabag")]
+fn save_wallabag(
+    args: &Args,
+    url: &Url,
+    label: &Label,
+    credentials: &wallabag::Credentials,
+    timeout: u64,
+) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options = wallabag::Options { timeout: std::time::Duration::from_secs(timeout) };
+    let client = wallabag::Client::login(url.clone(), credentials, &options)?;
+    let results = client.push(&mut collection, label);
+    let mut saved = 0;
+    for (id, result) in &results {
+        let url = collection.entity(*id).url();
+        match result {
+            Ok(article) => {
+                saved += 1;
+                println!("{}\t{}", url, article);
+            }
+            Err(err) => println!("ERR\t{}: {}", url, err),
+        }
+    }
+    if saved > 0 {
+        store.save(&collection)?;
+    }
+    println!("Saved {} of {} articles", saved, results.len());
+    Ok(())
+}
+
+#[cfg(feature = "favicon")]
+fn fetch_favicons(
+    args: &Args,
+    concurrency: usize,
+    timeout: u64,
+    cache_dir: Option<&Path>,
+) -> Result<(), Error> {
+    let cache_dir = match cache_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => default_cache_dir()
+            .ok_or_else(|| Error::msg("No cache directory: use --cache-dir"))?
+            .join("favicons"),
+    };
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let options =
+        favicon::Options { concurrency, timeout: std::time::Duration::from_secs(timeout) };
+    let fetcher = favicon::Fetcher::new(&options, favicon::Cache::new(cache_dir));
+    let results = fetcher.fetch_favicons(&mut collection);
+    let mut fetched = 0;
+    for (id, result) in &results {
+        let host = collection.entity(*id).url().host_str().unwrap_or_default();
+        match result {
+            Ok(path) => {
+                fetched += 1;
+                println!("{}\t{}", host, path.display());
+            }
+            Err(err) => println!("ERR\t{}: {}", host, err),
+        }
+    }
+    if fetched > 0 {
+        store.save(&collection)?;
+    }
+    println!("Fetched {} of {} favicons", fetched, results.len());
+    Ok(())
+}
+
+/// Returns `$XDG_CACHE_HOME/hbt`, falling back to `$HOME/.cache/hbt`.
+fn default_cache_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_CACHE_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".cache")))?;
+    Some(base.join("hbt"))
+}
+
+#[cfg(feature = "native-messaging")]
+fn native_host(args: &Args) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    native_messaging::run(
+        io::stdin().lock(),
+        io::stdout().lock(),
+        &mut collection,
+        |collection| store.save(collection).map_err(io::Error::other),
+    )?;
+    Ok(())
+}
+
+#[cfg(feature = "rpc")]
+fn serve_rpc(args: &Args) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    rpc::run(io::stdin().lock(), io::stdout().lock(), &mut collection, |collection| {
+        store.save(collection).map_err(io::Error::other)
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "serve")]
+fn serve(args: &Args, addr: std::net::SocketAddr) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let listener = std::net::TcpListener::bind(addr)?;
+    println!("Listening on http://{}/", listener.local_addr()?);
+    serve::serve(&listener, &mut collection, &serve::Options::default(), |collection| {
+        store.save(collection).map_err(io::Error::other)
+    })?;
+    Ok(())
+}
+
+fn write_back(args: &Args) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let written = markdown::write_back(&mut collection)?;
+    for path in &written {
+        println!("Wrote: {}", path.display());
+    }
+    if !written.is_empty() {
+        store.save(&collection)?;
+    }
+    Ok(())
+}
+
+fn watch(args: &Args, dir: &Path, interval: u64) -> Result<(), Error> {
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    collection.set_url_filter(url_filter(args));
+    let mut watcher = hbt_core::watch::Watcher::new(dir);
+    let interval = std::time::Duration::from_secs(interval);
+    let stop = std::sync::atomic::AtomicBool::new(false);
+    watcher.watch(&mut collection, interval, &stop, |collection, changes| {
+        for (path, result) in changes {
+            match result {
+                Ok(Imported { entities, added, merged }) => println!(
+                    "{}: {} entities, {} added, {} merged",
+                    path.display(),
+                    entities,
+                    added,
+                    merged
+                ),
+                Err(err) => println!("ERR\t{}: {}", path.display(), err),
+            }
+        }
+        if changes.iter().any(|(_, result)| result.is_ok()) {
+            store.save(collection).map_err(io::Error::other)?;
+        }
+        io::stdout().flush()
+    })?;
+    Ok(())
+}
+
+#[cfg(feature = "archive")]
+fn archive_pages(
+    args: &Args,
+    single_file: bool,
+    concurrency: usize,
+    timeout: u64,
+    archive_dir: Option<&Path>,
+) -> Result<(), Error> {
+    let archive_dir = match archive_dir {
+        Some(dir) => dir.to_path_buf(),
+        None => default_data_dir()
+            .ok_or_else(|| Error::msg("No data directory: use --archive-dir"))?
+            .join("archive"),
+    };
+    let mut store = open_store(args)?;
+    let mut collection = store.load()?;
+    let mode = if single_file { archive::Mode::SingleFile } else { archive::Mode::Assets };
+    let options =
+        archive::Options { concurrency, timeout: std::time::Duration::from_secs(timeout), mode };
+    let archive = archive::Archive::new(archive_dir);
+    let archiver = archive::Archiver::new(&options, archive.clone());
+    let results = archiver.archive_pages(&mut collection);
+    let mut archived = 0;
+    for (id, result) in &results {
+        let url = collection.entity(*id).url();
+        match result {
+            Ok(key) => {
+                archived += 1;
+                println!("{}\t{}", url, archive.path(key).display());
+            }
+            Err(err) => println!("ERR\t{}: {}", url, err),
+        }
+    }
+    if archived > 0 {
+        store.save(&collection)?;
+    }
+    println!("Archived {} of {} pages", archived, results.len());
+    Ok(())
+}
+
+#[cfg(feature = "archive")]
+fn default_data_dir() -> Option<PathBuf> {
+    let base = std::env::var_os("XDG_DATA_HOME")
+        .filter(|dir| !dir.is_empty())
+        .map(PathBuf::from)
+        .or_else(|| {
+        std::env::var_os("HOME").map(|home| PathBuf::from(home).join(".local").join("share"))
+    })?;
+    Some(base.join("hbt"))
+}
+
 #[cfg(feature = "pinboard")]
 fn create_collection(posts: Vec<Post>) -> Result<Collection, Error> {
     let mut ret = Collection::with_capacity(posts.len());
-    for post in posts {
-        let entity = Entity::try_from(post)?;
-        ret.insert(entity);
-    }
+    pinboard::import(posts, &mut ret)?;
     Ok(ret)
 }
 
@@ -52,16 +1521,17 @@ fn print_collection(args: &Args, collection: &Collection) -> Result<(), Error> {
         let json = serde_json::to_string_pretty(collection)?;
         println!("{}", json);
     } else if args.tags {
-        let mut all_tags = BTreeSet::new();
+        let mut all_tags: BTreeSet<Label> = BTreeSet::new();
         for entity in collection.entities() {
-            all_tags.extend(entity.labels())
+            all_tags.extend(entity.labels().iter().cloned())
         }
         for tag in all_tags {
             println!("{}", tag.as_str());
         }
     } else {
         let length = collection.len();
-        println!("{}: {} entities", args.file.to_string_lossy(), length)
+        let file = args.file.as_ref().map(|file| file.to_string_lossy()).unwrap_or_default();
+        println!("{}: {} entities", file, length)
     }
     Ok(())
 }
@@ -103,7 +1573,191 @@ fn markdown(args: &Args, input: &str) -> Result<(), Error> {
 fn main() -> Result<ExitCode, Error> {
     let args = Args::parse();
 
-    let file = &args.file;
+    match &args.command {
+        Some(Command::Add { url, name, labels, note }) => {
+            add(&args, url.clone(), name.clone(), labels.clone(), note.clone())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Search { query, ranked, fuzzy, json }) => {
+            search(&args, query, *ranked, *fuzzy, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "regex")]
+        Some(Command::Grep { pattern, json }) => {
+            grep(&args, pattern, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Query { query: q, json }) => {
+            query(&args, q, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::List(list_args)) => {
+            list(&args, list_args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "random")]
+        Some(Command::Random { label, older, unread, json }) => {
+            random(&args, label.as_deref(), *older, *unread, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Read { url, archive, unread }) => {
+            read(&args, url, *archive, *unread)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Star { url, unstar }) => {
+            star(&args, url, *unstar)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Import { files, format, prefer, incremental }) => {
+            import(&args, files, *format, *prefer, *incremental)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Diff { old, new, format, json }) => {
+            diff(old, new, *format, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Merge3 { base, ours, theirs, format, output }) => {
+            let clean = merge3(base, ours, theirs, *format, output.as_deref())?;
+            return Ok(if clean { ExitCode::SUCCESS } else { ExitCode::FAILURE });
+        }
+        Some(Command::Export { format, output, label }) => {
+            export(&args, *format, output.as_deref(), label.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Snapshot { file }) => {
+            snapshot(&args, file)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Restore { file }) => {
+            restore(&args, file)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::RenameLabel { old, new }) => {
+            rename_label(&args, old, new)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::MergeLabel { from, into }) => {
+            merge_label(&args, from, into)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Cooccurrence { label, json }) => {
+            cooccurrence(&args, label.as_deref(), *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Domains { domain, json }) => {
+            domains(&args, domain.as_deref(), *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Stats { json }) => {
+            stats(&args, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Dedupe { interactive, dry_run }) => {
+            dedupe(&args, *interactive, *dry_run)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Similar { url, count, link, json }) => {
+            similar(&args, url, *count, *link, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Centrality { degree, count, save, json }) => {
+            centrality(&args, *degree, *count, *save, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Path { from, to, json }) => {
+            path(&args, from, to, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Orphans { sources, sinks, json }) => {
+            orphans(&args, *sources, *sinks, *json)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "check")]
+        Some(Command::Check { concurrency, timeout }) => {
+            check(&args, *concurrency, *timeout)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "fetch")]
+        Some(Command::FetchTitles { concurrency, timeout }) => {
+            fetch_titles(&args, *concurrency, *timeout)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "favicon")]
+        Some(Command::FetchFavicons { concurrency, timeout, cache_dir }) => {
+            fetch_favicons(&args, *concurrency, *timeout, cache_dir.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "native-messaging")]
+        Some(Command::NativeHost { .. }) => {
+            native_host(&args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "rpc")]
+        Some(Command::Rpc) => {
+            serve_rpc(&args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "serve")]
+        Some(Command::Serve { addr }) => {
+            serve(&args, *addr)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::WriteBack) => {
+            write_back(&args)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        Some(Command::Watch { dir, interval }) => {
+            watch(&args, dir, *interval)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "archive")]
+        Some(Command::ArchivePages { single_file, concurrency, timeout, archive_dir }) => {
+            archive_pages(&args, *single_file, *concurrency, *timeout, archive_dir.as_deref())?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "linkding")]
+        Some(Command::Linkding { url, token, since, timeout }) => {
+            sync_linkding(&args, url, token.as_deref(), since.as_ref(), *timeout)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "wayback")]
+        Some(Command::Wayback { save_dead, concurrency, timeout }) => {
+            wayback(&args, *save_dead, *concurrency, *timeout)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        #[cfg(feature = "wallabag")]
+        Some(Command::Wallabag {
+            url,
+            label,
+            client_id,
+            client_secret,
+            username,
+            password,
+            timeout,
+        }) => {
+            let credentials = wallabag::Credentials {
+                client_id: arg_or_env(
+                    client_id.as_deref(),
+                    "--client-id",
+                    "HBT_WALLABAG_CLIENT_ID",
+                )?,
+                client_secret: arg_or_env(
+                    client_secret.as_deref(),
+                    "--client-secret",
+                    "HBT_WALLABAG_CLIENT_SECRET",
+                )?,
+                username: arg_or_env(username.as_deref(), "--username", "HBT_WALLABAG_USERNAME")?,
+                password: arg_or_env(password.as_deref(), "--password", "HBT_WALLABAG_PASSWORD")?,
+            };
+            save_wallabag(&args, url, &Label::new(label.clone()), &credentials, *timeout)?;
+            return Ok(ExitCode::SUCCESS);
+        }
+        None => {}
+    }
+
+    let Some(file) = &args.file else {
+        return Err(Error::msg("No file given"));
+    };
     let maybe_extension = file.extension();
     let contents = fs::read_to_string(file)?;
 
@@ -0,0 +1,153 @@
+use std::{
+    env,
+    fs::{self, File},
+    io::{BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+};
+
+use anyhow::Error;
+
+use hbt_core::collection::Collection;
+use hbt_core::format::json;
+#[cfg(feature = "encrypt")]
+use hbt_core::store::encrypted;
+#[cfg(feature = "git")]
+use hbt_core::store::git;
+#[cfg(feature = "sqlite")]
+use hbt_core::store::sqlite;
+
+const STORE_VAR: &str = "HBT_STORE";
+#[cfg(feature = "encrypt")]
+const PASSPHRASE_VAR: &str = "HBT_PASSPHRASE";
+
+/// Where the collection is kept between invocations.
+///
+/// Files ending in `.db` or `.sqlite` are SQLite stores, and files ending in `.enc` are JSON
+/// collections encrypted with the passphrase in `$HBT_PASSPHRASE`.  A directory is a git
+/// repository, committed to on each save, which [`Store::init_git`] creates.  Anything else is a
+/// JSON collection.
+pub enum Store {
+    Json(PathBuf),
+    #[cfg(feature = "encrypt")]
+    Encrypted {
+        path: PathBuf,
+        passphrase: String,
+    },
+    #[cfg(feature = "sqlite")]
+    Sqlite(sqlite::Store),
+    #[cfg(feature = "git")]
+    Git(Box<git::Store>),
+}
+
+impl Store {
+    /// Opens the store at the given path, or at `$HBT_STORE` if no path is given.
+    pub fn open(path: Option<&Path>) -> Result<Store, Error> {
+        let path = Store::resolve(path)?;
+        #[cfg(feature = "git")]
+        if path.is_dir() {
+            return match git::Store::open(&path) {
+                Ok(store) => Ok(Store::Git(Box::new(store))),
+                Err(git::Error::NotARepository(_)) => Err(Error::msg(format!(
+                    "Not a git repository: {}: run `git init` in it, or pass --git-init",
+                    path.display()
+                ))),
+                Err(err) => Err(err.into()),
+            };
+        }
+        match path.extension() {
+            #[cfg(feature = "sqlite")]
+            Some(ext) if ext.as_encoded_bytes() == b"db" || ext.as_encoded_bytes() == b"sqlite" => {
+                Ok(Store::Sqlite(sqlite::Store::open(path)?))
+            }
+            #[cfg(feature = "encrypt")]
+            Some(ext) if ext.as_encoded_bytes() == b"enc" => {
+                let passphrase = env::var(PASSPHRASE_VAR).map_err(|_| {
+                    Error::msg(format!("No passphrase for encrypted store: set {}", PASSPHRASE_VAR))
+                })?;
+                Ok(Store::Encrypted { path, passphrase })
+            }
+            _ => Ok(Store::Json(path)),
+        }
+    }
+
+    /// Opens the git store at the given path, or at `$HBT_STORE`, creating the directory and a
+    /// repository in it if there is none yet.
+    #[cfg(feature = "git")]
+    pub fn init_git(path: Option<&Path>) -> Result<Store, Error> {
+        let path = Store::resolve(path)?;
+        if path.is_file() {
+            return Err(Error::msg(format!("Not a directory: {}", path.display())));
+        }
+        Ok(Store::Git(Box::new(git::Store::init(path)?)))
+    }
+
+    /// Returns the given path, or `$HBT_STORE` if no path is given.
+    pub fn resolve(path: Option<&Path>) -> Result<PathBuf, Error> {
+        match path {
+            Some(path) => Ok(path.to_owned()),
+            None => env::var_os(STORE_VAR).map(PathBuf::from).ok_or_else(|| {
+                Error::msg(format!("No store given: use --store or set {}", STORE_VAR))
+            }),
+        }
+    }
+
+    /// Loads the collection, which is empty if the store does not exist yet.
+    pub fn load(&self) -> Result<Collection, Error> {
+        match self {
+            Store::Json(path) => match File::open(path) {
+                Ok(file) => Ok(json::from_reader(BufReader::new(file))?),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(Collection::new()),
+                Err(err) => Err(err.into()),
+            },
+            #[cfg(feature = "encrypt")]
+            Store::Encrypted { path, passphrase } => match fs::read(path) {
+                Ok(contents) => Ok(encrypted::open(&contents, passphrase)?),
+                Err(err) if err.kind() == ErrorKind::NotFound => Ok(Collection::new()),
+                Err(err) => Err(err.into()),
+            },
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => Ok(store.load()?),
+            #[cfg(feature = "git")]
+            Store::Git(store) => Ok(store.load()?),
+        }
+    }
+
+    pub fn save(&mut self, collection: &Collection) -> Result<(), Error> {
+        match self {
+            Store::Json(path) => replace(path, |writer| Ok(json::to_writer(collection, writer)?)),
+            #[cfg(feature = "encrypt")]
+            Store::Encrypted { path, passphrase } => {
+                let sealed =
+                    encrypted::seal(collection, passphrase, &encrypted::Options::default())?;
+                replace(path, |writer| Ok(writer.write_all(&sealed)?))
+            }
+            #[cfg(feature = "sqlite")]
+            Store::Sqlite(store) => {
+                store.save(collection)?;
+                Ok(())
+            }
+            #[cfg(feature = "git")]
+            Store::Git(store) => {
+                store.save(collection)?;
+                Ok(())
+            }
+        }
+    }
+}
+
+/// Writes the file alongside and renames it over the old one, so an interrupted save leaves the
+/// old store intact.
+fn replace(
+    path: &Path,
+    write: impl FnOnce(&mut BufWriter<File>) -> Result<(), Error>,
+) -> Result<(), Error> {
+    let mut tmp = path.to_path_buf().into_os_string();
+    tmp.push(".tmp");
+    let tmp = PathBuf::from(tmp);
+    let mut writer = BufWriter::new(File::create(&tmp)?);
+    write(&mut writer)?;
+    writer.flush()?;
+    drop(writer);
+    fs::rename(tmp, path)?;
+    Ok(())
+}
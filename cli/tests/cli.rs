@@ -130,3 +130,1128 @@ fn test_tags_with_mappings() {
         .success()
         .stdout_eq(file!("cli/snapshots/basic.mapped.tags.stdout"));
 }
+
+#[cfg(feature = "pinboard")]
+#[test]
+fn test_dump_json() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["--dump", "tests/cli/fixtures/basic.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/basic.dump.stdout"));
+}
+
+fn temp_store(name: &str) -> std::path::PathBuf {
+    let path = std::env::temp_dir().join(format!("hbt-{}-{}.json", name, std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    path
+}
+
+#[test]
+fn test_add() {
+    let store = temp_store("add");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "--name", "Example", "--label", "foo", "https://example.com"])
+        .arg("--store")
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Added: https://example.com/\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "-l", "bar", "--note", "Worth a look", "https://example.com"])
+        .env("HBT_STORE", &store)
+        .assert()
+        .success()
+        .stdout_eq("Updated: https://example.com/\n");
+    let contents = std::fs::read_to_string(&store).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    let entity = &json["value"][0]["entity"];
+    assert_eq!(json["length"], 1);
+    assert_eq!(entity["names"], serde_json::json!(["Example"]));
+    assert_eq!(entity["labels"], serde_json::json!(["bar", "foo"]));
+    assert_eq!(entity["notes"][0]["text"], "Worth a look");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_read() {
+    let store = temp_store("read");
+    for url in ["https://example.com/a", "https://example.com/b"] {
+        Command::new(cargo_bin!("hbt"))
+            .args(["add", url, "--store"])
+            .arg(&store)
+            .assert()
+            .success();
+    }
+    Command::new(cargo_bin!("hbt"))
+        .args(["read", "https://example.com/a", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Read: https://example.com/a\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--unread", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("1\t[..]\thttps://example.com/b\t\t\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["read", "https://example.com/c", "--store"])
+        .arg(&store)
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: Not in store: https://example.com/c\n");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_star() {
+    let store = temp_store("star");
+    for url in ["https://example.com/a", "https://example.com/b"] {
+        Command::new(cargo_bin!("hbt"))
+            .args(["add", url, "--store"])
+            .arg(&store)
+            .assert()
+            .success();
+    }
+    Command::new(cargo_bin!("hbt"))
+        .args(["star", "https://example.com/b", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Starred: https://example.com/b\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("1\t[..]\thttps://example.com/b\t\t\n0\t[..]\thttps://example.com/a\t\t\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["star", "--unstar", "https://example.com/b", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Unstarred: https://example.com/b\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--starred", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_rename_label() {
+    let store = temp_store("rename-label");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["rename-label", "programming", "code", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Renamed programming to code on 3 entries\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--label", "code", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("0\t[..]\n1\t[..]\n2\t[..]\n");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_merge_label() {
+    let store = temp_store("merge-label");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "-l", "rustlang", "https://example.com/a", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["merge-label", "rustlang", "rust", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Merged rustlang into rust on 1 entries\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "-l", "rustlang", "https://example.com/b", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--label", "rust", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "0\t[..]\thttps://example.com/a\t\trust\n1\t[..]\thttps://example.com/b\t\trust\n",
+        );
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_list_nested_label() {
+    let store = temp_store("nested-label");
+    for (label, url) in [
+        ("lang/rust/async", "https://example.com/a"),
+        ("lang/rustic", "https://example.com/b"),
+        ("lang/rust", "https://example.com/c"),
+    ] {
+        Command::new(cargo_bin!("hbt"))
+            .args(["add", "-l", label, url, "--store"])
+            .arg(&store)
+            .assert()
+            .success();
+    }
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--label", "lang/rust", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "0\t[..]\thttps://example.com/a\t\tlang/rust/async\n2\t[..]\thttps://example.com/c\t\tlang/rust\n",
+        );
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let store = temp_store("snapshot");
+    let snapshot = temp_store("snapshot-file");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "https://example.com/a", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["snapshot", "--store"])
+        .arg(&store)
+        .arg(&snapshot)
+        .assert()
+        .success()
+        .stdout_eq("[..]: 1 entities\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["restore", "--store"])
+        .arg(&store)
+        .arg(&snapshot)
+        .assert()
+        .success()
+        .stdout_eq("Restored 1 entities from snapshot taken [..]\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("0\t[..]\thttps://example.com/a\t\t\n");
+    std::fs::remove_file(&store).unwrap();
+    std::fs::remove_file(&snapshot).unwrap();
+}
+
+#[test]
+fn test_add_no_store() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "https://example.com"])
+        .env_remove("HBT_STORE")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No store given: use --store or set HBT_STORE\n");
+}
+
+#[test]
+fn test_git_store() {
+    let dir = std::env::temp_dir().join(format!("hbt-git-store-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let git = |args: &[&str]| {
+        let output = std::process::Command::new("git").arg("-C").arg(&dir).args(args).output();
+        String::from_utf8(output.unwrap().stdout).unwrap()
+    };
+    git(&["init", "--quiet"]);
+    git(&["config", "user.name", "hbt"]);
+    git(&["config", "user.email", "hbt@example.com"]);
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "https://example.com"])
+        .env("HBT_STORE", &dir)
+        .assert()
+        .success()
+        .stdout_eq("Added: https://example.com/\n");
+    Command::new(cargo_bin!("hbt"))
+        .arg("list")
+        .env("HBT_STORE", &dir)
+        .assert()
+        .success()
+        .stdout_eq("0\t[..]\thttps://example.com/\t\t\n");
+    assert_eq!(git(&["log", "--format=%s"]), "Add https://example.com/\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_git_init() {
+    let dir = std::env::temp_dir().join(format!("hbt-git-init-{}", std::process::id()));
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(&dir).unwrap();
+    let add = |url: &str| {
+        Command::new(cargo_bin!("hbt"))
+            .args(["add", url, "--store"])
+            .arg(&dir)
+            .env("GIT_AUTHOR_NAME", "hbt")
+            .env("GIT_AUTHOR_EMAIL", "hbt@example.com")
+            .env("GIT_COMMITTER_NAME", "hbt")
+            .env("GIT_COMMITTER_EMAIL", "hbt@example.com")
+    };
+    // A directory is only made a repository when asked
+    add("https://example.com/a").assert().failure().stderr_eq(format!(
+        "Error: Not a git repository: {}: run `git init` in it, or pass --git-init\n",
+        dir.display()
+    ));
+    assert!(!dir.join(".git").exists());
+    add("https://example.com/a").arg("--git-init").assert().success();
+    assert!(dir.join(".git").is_dir());
+    add("https://example.com/b").assert().success().stdout_eq("Added: https://example.com/b\n");
+    let contents = std::fs::read_to_string(dir.join("collection.json")).unwrap();
+    let json: serde_json::Value = serde_json::from_str(&contents).unwrap();
+    assert_eq!(json["length"], 2);
+    std::fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_encrypted_store() {
+    let store = temp_store("encrypted").with_extension("enc");
+    let _ = std::fs::remove_file(&store);
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "https://example.com"])
+        .env("HBT_STORE", &store)
+        .env("HBT_PASSPHRASE", "hunter2")
+        .assert()
+        .success()
+        .stdout_eq("Added: https://example.com/\n");
+    let contents = std::fs::read(&store).unwrap();
+    assert!(!contents.windows(7).any(|window| window == b"example"));
+    Command::new(cargo_bin!("hbt"))
+        .arg("list")
+        .env("HBT_STORE", &store)
+        .env("HBT_PASSPHRASE", "hunter2")
+        .assert()
+        .success()
+        .stdout_eq("0\t[..]\thttps://example.com/\t\t\n");
+    Command::new(cargo_bin!("hbt"))
+        .arg("list")
+        .env("HBT_STORE", &store)
+        .env("HBT_PASSPHRASE", "hunter3")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: wrong passphrase, or the collection is corrupt\n");
+    Command::new(cargo_bin!("hbt"))
+        .arg("list")
+        .env("HBT_STORE", &store)
+        .env_remove("HBT_PASSPHRASE")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No passphrase for encrypted store: set HBT_PASSPHRASE\n");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_search() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["search", "--store", "tests/cli/fixtures/store.json", "RUST"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.search.stdout"));
+}
+
+#[test]
+fn test_search_ranked() {
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "search",
+            "--ranked",
+            "--store",
+            "tests/cli/fixtures/store.json",
+            "code programming",
+        ])
+        .assert()
+        .success()
+        .stdout_eq(
+            "2\t2023-11-15\thttps://code.visualstudio.com/\tVS Code\teditor,programming,tools\n",
+        );
+}
+
+#[test]
+fn test_search_fuzzy() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["search", "--fuzzy", "--store", "tests/cli/fixtures/store.json", "edtior"])
+        .assert()
+        .success()
+        .stdout_eq(
+            "2\t2023-11-15\thttps://code.visualstudio.com/\tVS Code\teditor,programming,tools\n",
+        );
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_grep() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["grep", "--store", "tests/cli/fixtures/store.json", r"^https://[a-z]+\.rust-lang"])
+        .assert()
+        .success()
+        .stdout_eq("1\t2023-11-15\thttps://users.rust-lang.org/\t\tprogramming,rust\n");
+}
+
+#[test]
+fn test_search_json() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["search", "--json", "--store", "tests/cli/fixtures/store.json", "editor"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.search.json.stdout"));
+}
+
+#[test]
+fn test_query() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["query", "--store", "tests/cli/fixtures/store.json"])
+        .arg(r#"label:programming AND NOT (label:rust OR name:"emacs")"#)
+        .assert()
+        .success()
+        .stdout_eq(
+            "2\t2023-11-15\thttps://code.visualstudio.com/\tVS Code\teditor,programming,tools\n",
+        );
+}
+
+#[test]
+fn test_query_invalid() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["query", "--store", "tests/cli/fixtures/store.json", "label>rust"])
+        .assert()
+        .failure()
+        .stderr_eq("Error: invalid comparison for label: >\n");
+}
+
+#[test]
+fn test_domains() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["domains", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq("1\tcode.visualstudio.com\n1\trust-lang.org\n1\tusers.rust-lang.org\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["domains", "--domain", "rust-lang.org", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq("0\t2023-11-15\thttps://rust-lang.org/\tRust Website\tprogramming,rust\n");
+}
+
+#[test]
+fn test_diff() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["diff", "tests/cli/fixtures/graph.md", "tests/cli/fixtures/graph-edited.md"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/diff.stdout"));
+}
+
+#[test]
+fn test_diff_json() {
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "diff",
+            "--json",
+            "tests/cli/fixtures/graph.md",
+            "tests/cli/fixtures/graph-edited.md",
+        ])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/diff.json.stdout"));
+}
+
+#[test]
+fn test_merge3() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["merge3", "tests/cli/fixtures/merge-base.md", "tests/cli/fixtures/merge-ours.md"])
+        .arg("tests/cli/fixtures/merge-theirs.md")
+        .assert()
+        .failure()
+        .stdout_eq(file!("cli/snapshots/merge3.stdout"))
+        .stderr_eq(file!("cli/snapshots/merge3.stderr"));
+}
+
+#[test]
+fn test_stats() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["stats", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/stats.stdout"));
+}
+
+#[test]
+fn test_stats_json() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["stats", "--json", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/stats.json.stdout"));
+}
+
+#[test]
+fn test_orphans() {
+    let store = temp_store("orphans");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/graph.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["orphans", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/orphans.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_list() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store", "tests/cli/fixtures/list.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/list.stdout"));
+}
+
+#[test]
+fn test_list_filters() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store", "tests/cli/fixtures/list.json"])
+        .args(["--label", "programming", "--since", "2023-11-15", "--until", "2023-11-30"])
+        .args(["--domain", "rust-lang.org"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/list.filtered.stdout"));
+}
+
+#[test]
+fn test_list_sort() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store", "tests/cli/fixtures/list.json", "--sort", "updated"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/list.updated.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store", "tests/cli/fixtures/list.json", "--sort", "name"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/list.name.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store", "tests/cli/fixtures/list.json", "--sort", "url"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/list.url.stdout"));
+}
+
+#[test]
+fn test_random() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["random", "--store", "tests/cli/fixtures/list.json", "--label", "docs", "--unread"])
+        .assert()
+        .success()
+        .stdout_eq("[..]\t2023-11-20\thttps://doc[..]\t[..]\tdocs\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["random", "--store", "tests/cli/fixtures/list.json", "--label", "nowhere"])
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No entries to pick from\n");
+}
+
+#[test]
+fn test_cooccurrence() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["cooccurrence", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(
+            "2\tprogramming\trust\n1\teditor\tprogramming\n1\teditor\ttools\n1\tprogramming\ttools\n",
+        );
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "cooccurrence",
+            "--store",
+            "tests/cli/fixtures/store.json",
+            "--label",
+            "programming",
+        ])
+        .assert()
+        .success()
+        .stdout_eq("0.67\t2\trust\n0.33\t1\teditor\n0.33\t1\ttools\n");
+}
+
+#[test]
+fn test_similar() {
+    let store = temp_store("similar");
+    std::fs::copy("tests/cli/fixtures/store.json", &store).unwrap();
+    let similar = || {
+        Command::new(cargo_bin!("hbt"))
+            .args(["similar", "https://rust-lang.org/", "-n", "1", "--store"])
+            .arg(&store)
+    };
+    similar()
+        .assert()
+        .success()
+        .stdout_eq("1\t2023-11-15\thttps://users.rust-lang.org/\t\tprogramming,rust\n");
+    assert!(!std::fs::read_to_string(&store).unwrap().contains("\"similar\""));
+    similar().arg("--link").assert().success();
+    assert!(std::fs::read_to_string(&store).unwrap().contains("\"similar\""));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_centrality() {
+    let store = temp_store("centrality");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/graph.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["centrality", "--save", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+0.4633\t1\thttps://doc.rust-lang.org/book/
+0.2445\t0\thttps://rust-lang.org/
+0.2445\t2\thttps://doc.rust-lang.org/book/ch04-00-understanding-ownership.html
+0.0476\t3\thttps://code.visualstudio.com/
+",
+        );
+    assert!(std::fs::read_to_string(&store).unwrap().contains("\"pagerank\""));
+    Command::new(cargo_bin!("hbt"))
+        .args(["centrality", "--degree", "-n", "2", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "0.6667\t1\thttps://doc.rust-lang.org/book/\n0.3333\t0\thttps://rust-lang.org/\n",
+        );
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_path() {
+    let store = temp_store("path");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/graph.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    let ownership = "https://doc.rust-lang.org/book/ch04-00-understanding-ownership.html";
+    Command::new(cargo_bin!("hbt"))
+        .args(["path", "https://rust-lang.org/", ownership, "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+0\t2023-11-15\thttps://rust-lang.org/\tRust Website\t
+1\t2023-11-15\thttps://doc.rust-lang.org/book/\tThe Book\t
+2\t2023-11-15\thttps://doc.rust-lang.org/book/ch04-00-understanding-ownership.html\tOwnership\t
+",
+        );
+    Command::new(cargo_bin!("hbt"))
+        .args(["path", ownership, "https://code.visualstudio.com/", "--store"])
+        .arg(&store)
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq(format!(
+            "Error: No path from {} to https://code.visualstudio.com/\n",
+            ownership
+        ));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_export_label() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "markdown", "--label", "docs", "--store"])
+        .arg("tests/cli/fixtures/list.json")
+        .assert()
+        .success()
+        .stdout_eq(
+            "\
+# November 20, 2023
+
+## docs
+
+- [Standard Library](https://doc.rust-lang.org/std/)
+- [Python Docs](https://docs.python.org/3/)
+",
+        );
+}
+
+#[test]
+fn test_import_export() {
+    let store = temp_store("import");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/basic.md: 3 entities, 3 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/list.json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/list.json: 5 entities, 3 added, 2 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.json.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "markdown", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.md.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_write_back() {
+    let store = temp_store("write-back");
+    let file = store.with_extension("md");
+    std::fs::write(&file, "# November 15, 2023\n\n- [Foo](https://foo.com)\n").unwrap();
+    Command::new(cargo_bin!("hbt"))
+        .arg("import")
+        .arg(&file)
+        .arg("--store")
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "--label", "Go", "https://foo.com", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["write-back", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Wrote: [..].md\n");
+    let contents = std::fs::read_to_string(&file).unwrap();
+    assert_eq!(contents, "# November 15, 2023\n\n## Go\n\n- [Foo](https://foo.com)\n");
+    std::fs::remove_file(&file).unwrap();
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_import_many() {
+    let store = temp_store("import-many");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "tests/cli/fixtures/list.json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(
+            "tests/cli/fixtures/basic.md: 3 entities, 3 added, 0 merged\n\
+             tests/cli/fixtures/list.json: 5 entities, 3 added, 2 merged\n",
+        );
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.json.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_import_incremental() {
+    let store = temp_store("import-incremental");
+    let dir = store.with_extension("d");
+    let _ = std::fs::remove_dir_all(&dir);
+    std::fs::create_dir_all(dir.join("notes")).unwrap();
+    let foo = dir.join("notes").join("foo.md");
+    std::fs::write(&foo, "# November 15, 2023\n\n- [Foo](https://foo.com)\n").unwrap();
+    std::fs::write(
+        dir.join("notes").join("bar.md"),
+        "# November 16, 2023\n\n- <https://bar.com>\n",
+    )
+    .unwrap();
+    let import = || {
+        Command::new(cargo_bin!("hbt"))
+            .args(["import", "--incremental"])
+            .arg(dir.join("notes"))
+            .arg("--store")
+            .arg(&store)
+            .env("XDG_CACHE_HOME", dir.join("cache"))
+    };
+    import().assert().success().stdout_eq(
+        "[..]bar.md: 1 entities, 1 added, 0 merged\n[..]foo.md: 1 entities, 1 added, 0 merged\n",
+    );
+    import().assert().success().stdout_eq("Skipped 2 unchanged files\n");
+    std::fs::write(&foo, "# November 15, 2023\n\n- [Foo](https://foo.com)\n- <https://baz.com>\n")
+        .unwrap();
+    import()
+        .assert()
+        .success()
+        .stdout_eq("[..]foo.md: 2 entities, 1 added, 1 merged\nSkipped 1 unchanged files\n");
+    std::fs::remove_dir_all(&dir).unwrap();
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_import_prefer_newer() {
+    let store = temp_store("import-prefer-newer");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "tests/cli/fixtures/list.json"])
+        .args(["--prefer", "newer", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.newer.json.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_import_org() {
+    let store = temp_store("import-org");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.org", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/basic.org: 3 entities, 3 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.org.stdout"));
+}
+
+#[test]
+fn test_import_chrome() {
+    let store = temp_store("import-chrome");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/chrome.json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/chrome.json: 3 entities, 3 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.chrome.stdout"));
+}
+
+#[test]
+fn test_import_safari() {
+    let store = temp_store("import-safari");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/Bookmarks.plist", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/Bookmarks.plist: 4 entities, 4 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.safari.stdout"));
+}
+
+#[cfg(feature = "buku")]
+#[test]
+fn test_export_import_buku() {
+    let db = temp_store("buku").with_extension("db");
+    let _ = std::fs::remove_file(&db);
+    Command::new(cargo_bin!("hbt"))
+        .args([
+            "export",
+            "--format",
+            "buku",
+            "--store",
+            "tests/cli/fixtures/store.json",
+            "--output",
+        ])
+        .arg(&db)
+        .assert()
+        .success();
+    let store = temp_store("import-buku");
+    Command::new(cargo_bin!("hbt"))
+        .arg("import")
+        .arg(&db)
+        .arg("--store")
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("[..]: 3 entities, 3 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.buku.stdout"));
+    std::fs::remove_file(&db).unwrap();
+}
+
+#[cfg(feature = "firefox")]
+#[test]
+fn test_import_firefox() {
+    let store = temp_store("import-firefox");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/places.sqlite", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/places.sqlite: 3 entities, 3 added, 0 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/import.firefox.stdout"));
+}
+
+#[test]
+fn test_import_many_error() {
+    let store = temp_store("import-many-error");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.md", "tests/cli/fixtures/missing.md", "--store"])
+        .arg(&store)
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stdout_eq("");
+    assert!(!store.exists());
+}
+
+#[cfg(feature = "pinboard")]
+#[test]
+fn test_import_pinboard_json() {
+    let store = temp_store("import-pinboard");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/basic.json", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/basic.json: 3 entities, 3 added, 0 merged\n");
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_export_dot() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "dot", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.dot.stdout"));
+}
+
+#[test]
+fn test_export_org() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "org", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.org.stdout"));
+}
+
+#[test]
+fn test_export_atom() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "atom", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.atom.stdout"));
+}
+
+#[test]
+fn test_export_opml() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--format", "opml", "--store", "tests/cli/fixtures/store.json"])
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/store.opml.stdout"));
+}
+
+#[cfg(feature = "native-messaging")]
+#[test]
+fn test_native_host() {
+    let store = temp_store("native-host");
+    let message = r#"{"type": "save", "url": "https://zig.dev/", "title": "Zig"}"#;
+    let mut input = (message.len() as u32).to_ne_bytes().to_vec();
+    input.extend_from_slice(message.as_bytes());
+    let response = r#"{"added":true,"id":0,"ok":true}"#;
+    let mut output = (response.len() as u32).to_ne_bytes().to_vec();
+    output.extend_from_slice(response.as_bytes());
+    // Browsers pass the extension's origin or id
+    Command::new(cargo_bin!("hbt"))
+        .args(["native-host", "--store"])
+        .arg(&store)
+        .arg("chrome-extension://abcdefghijklmnopabcdefghijklmnop/")
+        .stdin(input)
+        .assert()
+        .success()
+        .stdout_eq(output);
+    Command::new(cargo_bin!("hbt"))
+        .args(["search", "zig", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("0\t[..]\thttps://zig.dev/\tZig\t\n");
+}
+
+#[cfg(feature = "rpc")]
+#[test]
+fn test_rpc() {
+    let store = temp_store("rpc");
+    std::fs::copy("tests/cli/fixtures/store.json", &store).unwrap();
+    let input = r#"{"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"url": "https://zig.dev/", "createdAt": "2023-11-16"}}
+{"jsonrpc": "2.0", "id": 2, "method": "query", "params": {"query": "created>2023-11-15"}}
+"#;
+    Command::new(cargo_bin!("hbt"))
+        .args(["rpc", "--store"])
+        .arg(&store)
+        .stdin(input)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/rpc.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["search", "zig", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("3\t2023-11-16\thttps://zig.dev/\t\t\n");
+}
+
+#[cfg(feature = "linkding")]
+#[test]
+fn test_linkding_no_token() {
+    let store = temp_store("linkding-no-token");
+    Command::new(cargo_bin!("hbt"))
+        .args(["linkding", "http://127.0.0.1:9/", "--store"])
+        .arg(&store)
+        .env_remove("HBT_LINKDING_TOKEN")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No token given: use --token or set HBT_LINKDING_TOKEN\n");
+    assert!(!store.exists());
+}
+
+#[cfg(feature = "wallabag")]
+#[test]
+fn test_wallabag_no_password() {
+    let store = temp_store("wallabag-no-password");
+    Command::new(cargo_bin!("hbt"))
+        .args(["wallabag", "http://127.0.0.1:9/", "--client-id", "id", "--client-secret", "secret"])
+        .args(["--username", "user", "--store"])
+        .arg(&store)
+        .env_remove("HBT_WALLABAG_PASSWORD")
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No password given: use --password or set HBT_WALLABAG_PASSWORD\n");
+    assert!(!store.exists());
+}
+
+#[test]
+fn test_export_no_format() {
+    Command::new(cargo_bin!("hbt"))
+        .args(["export", "--store", "tests/cli/fixtures/store.json"])
+        .env("RUST_BACKTRACE", "0")
+        .assert()
+        .failure()
+        .stderr_eq("Error: No format given: use --format\n");
+}
+
+#[test]
+fn test_dedupe() {
+    let store = temp_store("dedupe");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/duplicates.md", "--store"])
+        .arg(&store)
+        .assert()
+        .success();
+    Command::new(cargo_bin!("hbt"))
+        .args(["dedupe", "--dry-run", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/dedupe.dry-run.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["dedupe", "--interactive", "--store"])
+        .arg(&store)
+        .stdin("n\ny\n")
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/dedupe.interactive.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["dedupe", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/dedupe.stdout"));
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/dedupe.list.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
+
+#[test]
+fn test_import_strip_tracking() {
+    let store = temp_store("strip");
+    Command::new(cargo_bin!("hbt"))
+        .args(["import", "tests/cli/fixtures/duplicates.md", "--strip-tracking", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("tests/cli/fixtures/duplicates.md: 5 entities, 4 added, 1 merged\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["add", "https://rust-lang.org/?ref=hn", "--strip-param", "ref", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq("Updated: https://rust-lang.org/\n");
+    Command::new(cargo_bin!("hbt"))
+        .args(["list", "--store"])
+        .arg(&store)
+        .assert()
+        .success()
+        .stdout_eq(file!("cli/snapshots/strip.list.stdout"));
+    std::fs::remove_file(&store).unwrap();
+}
@@ -0,0 +1,84 @@
+//! Times [`import_all`] over many Markdown files, parsing them on a single thread and then on the
+//! whole rayon thread pool.
+//!
+//! Run with `cargo bench -p hbt-core --bench import`.
+
+use std::time::{Duration, Instant};
+
+use hbt_core::{
+    collection::{Collection, Union},
+    import::import_all,
+    markdown,
+};
+use rayon::ThreadPoolBuilder;
+
+const FILES: usize = 2_000;
+const ENTRIES: usize = 50;
+const RUNS: usize = 5;
+
+/// A file of entries under a few headings, each with a URL of its own.
+fn file(index: usize) -> String {
+    let mut ret = format!("# November {}, 2023\n", index % 28 + 1);
+    for entry in 0..ENTRIES {
+        if entry % 10 == 0 {
+            ret.push_str(&format!("\n## Topic {}\n\n", entry / 10));
+        }
+        ret.push_str(&format!(
+            "- [Entry {entry} of {index}](https://example.com/{index}/{entry})\n"
+        ));
+    }
+    ret
+}
+
+/// Returns the least time of a few imports of the files on a pool with the given threads.
+fn time(files: &[String], threads: usize) -> Duration {
+    let pool = ThreadPoolBuilder::new().num_threads(threads).build().expect("thread pool");
+    (0..RUNS)
+        .map(|_| {
+            pool.install(|| {
+                let mut collection = Collection::new();
+                let start = Instant::now();
+                let imported = import_all(
+                    &mut collection,
+                    files,
+                    |file| markdown::parse(file),
+                    |_| None,
+                    &mut Union,
+                );
+                let elapsed = start.elapsed();
+                assert!(imported.iter().all(Result::is_ok));
+                assert_eq!(collection.len(), FILES * ENTRIES);
+                elapsed
+            })
+        })
+        .min()
+        .expect("runs")
+}
+
+/// Returns the least time of a few parses of the files, without merging, on this thread.
+fn time_parse(files: &[String]) -> Duration {
+    (0..RUNS)
+        .map(|_| {
+            let start = Instant::now();
+            let parsed: Vec<_> = files.iter().map(|file| markdown::parse(file)).collect();
+            let elapsed = start.elapsed();
+            assert!(parsed.iter().all(Result::is_ok));
+            elapsed
+        })
+        .min()
+        .expect("runs")
+}
+
+fn main() {
+    let files: Vec<String> = (0..FILES).map(file).collect();
+    let threads = rayon::current_num_threads();
+    let parse = time_parse(&files);
+    let serial = time(&files, 1);
+    let parallel = time(&files, threads);
+    let ms = |time: Duration| time.as_secs_f64() * 1e3;
+    println!("{} files of {} entries, best of {} runs", FILES, ENTRIES, RUNS);
+    println!("parsing alone:      {:>8.1} ms", ms(parse));
+    println!("import, 1 thread:   {:>8.1} ms", ms(serial));
+    println!("import, {:>2} threads: {:>8.1} ms", threads, ms(parallel));
+    println!("speedup:            {:>8.2}x", serial.as_secs_f64() / parallel.as_secs_f64());
+}
@@ -0,0 +1,100 @@
+//! Shared plumbing for modules that let other programs read and change a collection.
+
+#[cfg(test)]
+pub(crate) mod tests;
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::{
+    collection::{Collection, Entity, Id, Label, Time},
+    query::Query,
+};
+
+/// An entity together with its id, as the entities in a collection are listed.
+#[derive(Serialize)]
+pub(crate) struct Entry<'a> {
+    id: Id,
+    entity: &'a Entity,
+}
+
+/// Returns the entries for the ids, after skipping `offset` of them and keeping at most `limit`.
+pub(crate) fn entries(
+    collection: &Collection,
+    ids: impl IntoIterator<Item = Id>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+) -> Vec<Entry<'_>> {
+    ids.into_iter()
+        .skip(offset.unwrap_or(0))
+        .take(limit.unwrap_or(usize::MAX))
+        .map(|id| Entry { id, entity: collection.entity(id) })
+        .collect()
+}
+
+/// Returns the ids of the entities with the label or one nested under it, if one is given, that
+/// match the query, if one is given, in ascending order.
+pub(crate) fn select<'a>(
+    collection: &'a Collection,
+    label: Option<Label>,
+    query: Option<&'a Query>,
+) -> impl Iterator<Item = Id> + 'a {
+    collection
+        .iter()
+        .filter(move |(_, entity)| {
+            label
+                .as_ref()
+                .is_none_or(|label| entity.labels().iter().any(|other| other.is_within(label)))
+                && query.is_none_or(|query| query.matches(entity))
+        })
+        .map(|(id, _)| id)
+}
+
+/// An entity as other programs give it.
+#[derive(Debug, Deserialize)]
+#[serde(rename_all = "camelCase", deny_unknown_fields)]
+pub(crate) struct NewEntity {
+    url: String,
+    name: Option<String>,
+    #[serde(default)]
+    labels: Vec<String>,
+    /// Defaults to when the entity is given.
+    created_at: Option<Time>,
+    #[serde(default)]
+    toread: bool,
+    #[serde(default)]
+    shared: bool,
+}
+
+impl NewEntity {
+    pub(crate) fn into_entity(self) -> Result<Entity, url::ParseError> {
+        let mut builder = Entity::builder(Url::parse(&self.url)?)
+            .created(self.created_at.unwrap_or_else(Time::now))
+            .labels(self.labels)
+            .toread(self.toread)
+            .shared(self.shared);
+        if let Some(name) = self.name.filter(|name| !name.trim().is_empty()) {
+            builder = builder.name(name.trim());
+        }
+        Ok(builder.build())
+    }
+}
+
+/// One [`NewEntity`], or an array of them.
+#[derive(Debug, Deserialize)]
+#[serde(untagged)]
+pub(crate) enum NewEntities {
+    One(NewEntity),
+    Many(Vec<NewEntity>),
+}
+
+impl NewEntities {
+    pub(crate) fn into_entities(self) -> Result<Vec<Entity>, url::ParseError> {
+        match self {
+            NewEntities::One(entity) => Ok(vec![entity.into_entity()?]),
+            NewEntities::Many(entities) => {
+                entities.into_iter().map(NewEntity::into_entity).collect()
+            }
+        }
+    }
+}
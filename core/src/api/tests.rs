@@ -0,0 +1,51 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Name;
+
+/// The collection the tests of the modules built on this one serve.
+pub(crate) fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let entities = [
+        ("https://rust-lang.org/", "Rust", &["lang/rust"][..], datetime!(2023-11-15 0:00 UTC)),
+        ("https://tokio.rs/", "Tokio", &["lang/rust", "async"][..], datetime!(2023-11-16 0:00 UTC)),
+        ("https://go.dev/", "Go", &["lang/go"][..], datetime!(2023-11-17 0:00 UTC)),
+    ];
+    for (url, name, labels, created_at) in entities {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            created_at.into(),
+            Some(Name::new(name.to_string())),
+            labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+        ));
+    }
+    collection
+}
+
+#[test]
+fn test_select() {
+    let collection = create_test_collection();
+    let urls = |label: Option<&str>, query: Option<&Query>| -> Vec<&str> {
+        let ids = select(&collection, label.map(Label::from), query);
+        ids.map(|id| collection.entity(id).url().as_str()).collect()
+    };
+    let all = ["https://rust-lang.org/", "https://tokio.rs/", "https://go.dev/"];
+    assert_eq!(urls(None, None), all);
+    assert_eq!(urls(Some("lang"), None), all);
+    assert_eq!(urls(Some("lang/rust"), None), ["https://rust-lang.org/", "https://tokio.rs/"]);
+    let query = Query::parse("label:async").unwrap();
+    assert_eq!(urls(None, Some(&query)), ["https://tokio.rs/"]);
+    assert!(urls(Some("lang/go"), Some(&query)).is_empty());
+}
+
+#[test]
+fn test_entries() {
+    let collection = create_test_collection();
+    let ids = collection.iter().map(|(id, _)| id);
+    let entries = entries(&collection, ids, Some(1), Some(1));
+    assert_eq!(entries.len(), 1);
+    assert_eq!(entries[0].entity.url().as_str(), "https://tokio.rs/");
+}
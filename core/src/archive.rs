@@ -0,0 +1,324 @@
+//! Keeping local copies of pages in a content-addressed directory.
+//!
+//! Every file is stored under the SHA-256 hash of its contents, as in `ab/cdef….html`, so a file
+//! shared by many pages, such as a stylesheet, is kept once.  A page is kept either as its HTML
+//! next to the images, scripts, stylesheets and icons it links to, with the links pointing at the
+//! stored copies, or as a single file with those assets inlined as `data:` URLs.  Assets that
+//! stylesheets link to in turn are not fetched.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use base64::{engine::general_purpose::STANDARD, Engine};
+use scraper::{Html, Selector};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id},
+    http,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("HTML selector error: {0}")]
+    HtmlSelector(String),
+    #[error("not HTML: {0}, {1}")]
+    NotHtml(Url, String),
+}
+
+/// How a page's assets are kept.
+#[derive(Debug, Clone, Copy, Default, PartialEq, Eq)]
+pub enum Mode {
+    /// As files of their own, next to the page.
+    #[default]
+    Assets,
+    /// Inlined into the page, as `data:` URLs.
+    SingleFile,
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number of pages downloaded at once.
+    pub concurrency: usize,
+    pub timeout: Duration,
+    pub mode: Mode,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { concurrency: 4, timeout: Duration::from_secs(30), mode: Mode::default() }
+    }
+}
+
+/// The metadata key under which the key of an entity's archived copy is kept.
+pub const METADATA_KEY: &str = "archive";
+
+/// Pages and assets are read no further than this.
+const MAX_BODY: u64 = 16 * 1024 * 1024;
+
+const MAX_REDIRECTS: u32 = 5;
+
+/// The elements whose assets are kept, and the attribute linking to each.
+const ASSETS: &[(&str, &str)] = &[
+    ("img[src]", "src"),
+    ("script[src]", "src"),
+    ("link[rel~=stylesheet][href]", "href"),
+    ("link[rel~=icon][href]", "href"),
+];
+
+/// A content-addressed directory of pages and their assets.
+#[derive(Debug, Clone)]
+pub struct Archive {
+    dir: PathBuf,
+}
+
+impl Archive {
+    pub fn new(dir: impl Into<PathBuf>) -> Archive {
+        Archive { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns where the file with the given key is kept.
+    pub fn path(&self, key: &str) -> PathBuf {
+        self.dir.join(key)
+    }
+
+    /// Returns the archived copy of the entity with the given id, if it has been downloaded.
+    pub fn get(&self, collection: &Collection, id: Id) -> Option<PathBuf> {
+        let key = collection.entity(id).metadata_value(METADATA_KEY)?;
+        Some(self.path(key)).filter(|path| path.is_file())
+    }
+
+    /// Stores the contents, unless a file with the same contents is already stored, returning
+    /// their key.
+    pub fn put(&self, contents: &[u8], extension: &str) -> Result<String, Error> {
+        let hash: String = sha256(contents).iter().map(|byte| format!("{:02x}", byte)).collect();
+        let key = format!("{}/{}.{}", &hash[..2], &hash[2..], extension);
+        let path = self.path(&key);
+        if !path.is_file() {
+            fs::create_dir_all(path.parent().expect("key has a directory"))?;
+            // Write to a temporary file first, so that the archive never holds a partial file
+            let tmp = path.with_extension(format!("{}.tmp", extension));
+            fs::write(&tmp, contents)?;
+            fs::rename(&tmp, &path)?;
+        }
+        Ok(key)
+    }
+}
+
+/// Returns the extension of files with the given content type.
+fn extension(content_type: &str) -> &'static str {
+    match content_type.to_ascii_lowercase().as_str() {
+        "text/html" | "application/xhtml+xml" => "html",
+        "text/css" => "css",
+        "text/javascript" | "application/javascript" | "application/x-javascript" => "js",
+        "image/png" => "png",
+        "image/jpeg" => "jpg",
+        "image/gif" => "gif",
+        "image/svg+xml" => "svg",
+        "image/webp" => "webp",
+        "image/x-icon" | "image/vnd.microsoft.icon" => "ico",
+        _ => "bin",
+    }
+}
+
+/// Escapes text for an HTML attribute value.
+fn escape(value: &str) -> String {
+    value.replace('&', "&amp;").replace('"', "&quot;").replace('\'', "&#39;")
+}
+
+/// Returns the assets the page links to, as their attribute values and the URLs those resolve to.
+fn assets(html: &str, base: &Url) -> Result<BTreeMap<String, Url>, Error> {
+    let document = Html::parse_document(html);
+    let mut ret = BTreeMap::new();
+    for (selector, attr) in ASSETS {
+        let selector =
+            Selector::parse(selector).map_err(|err| Error::HtmlSelector(err.to_string()))?;
+        for element in document.select(&selector) {
+            let Some(value) = element.value().attr(attr) else {
+                continue;
+            };
+            if value.starts_with("data:") {
+                continue;
+            }
+            if let Ok(url) = base.join(value) {
+                if matches!(url.scheme(), "http" | "https") {
+                    ret.insert(value.to_string(), url);
+                }
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Replaces the attribute values linking to assets in the page, as they are written with either
+/// kind of quote.
+fn rewrite(html: &str, links: &BTreeMap<String, String>) -> String {
+    let mut ret = html.to_string();
+    for (value, replacement) in links {
+        let replacement = escape(replacement);
+        let mut written = vec![value.clone(), value.replace('&', "&amp;")];
+        written.dedup();
+        for value in written {
+            for quote in ['"', '\''] {
+                let from = format!("={}{}{}", quote, value, quote);
+                let to = format!("={}{}{}", quote, replacement, quote);
+                ret = ret.replace(&from, &to);
+            }
+        }
+    }
+    ret
+}
+
+pub struct Archiver {
+    agent: ureq::Agent,
+    concurrency: usize,
+    mode: Mode,
+    archive: Archive,
+}
+
+impl Archiver {
+    pub fn new(options: &Options, archive: Archive) -> Archiver {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let concurrency = options.concurrency.max(1);
+        Archiver { agent, concurrency, mode: options.mode, archive }
+    }
+
+    /// Downloads the resource at the given URL, returning its content type and contents.
+    fn download(&self, url: &Url) -> Result<(String, Vec<u8>), Error> {
+        let response = self.agent.request_url("GET", url).call().map_err(Box::new)?;
+        let content_type = response.content_type().to_ascii_lowercase();
+        let mut body = Vec::new();
+        response.into_reader().take(MAX_BODY).read_to_end(&mut body)?;
+        Ok((content_type, body))
+    }
+
+    /// Downloads the page at the given URL along with its assets and stores them, returning the
+    /// page's key.
+    ///
+    /// An asset that cannot be downloaded is left linked to where it was.
+    pub fn archive_page(&self, url: &Url) -> Result<String, Error> {
+        let (content_type, body) = self.download(url)?;
+        if extension(&content_type) != "html" {
+            return Err(Error::NotHtml(url.clone(), content_type));
+        }
+        let html = String::from_utf8_lossy(&body);
+        let mut links = BTreeMap::new();
+        for (value, asset) in assets(&html, url)? {
+            let Ok((content_type, contents)) = self.download(&asset) else {
+                continue;
+            };
+            let link = match self.mode {
+                // Pages and assets are both one directory down from the archive
+                Mode::Assets => {
+                    format!("../{}", self.archive.put(&contents, extension(&content_type))?)
+                }
+                Mode::SingleFile => {
+                    format!("data:{};base64,{}", content_type, STANDARD.encode(&contents))
+                }
+            };
+            links.insert(value, link);
+        }
+        self.archive.put(rewrite(&html, &links).as_bytes(), "html")
+    }
+
+    /// Archives every entity that has no archived copy, keeping the key of each copy in the
+    /// entity's metadata under [`METADATA_KEY`].
+    ///
+    /// Returns the outcome for each of those entities in [`Id`] order.
+    pub fn archive_pages(&self, collection: &mut Collection) -> Vec<(Id, Result<String, Error>)> {
+        let urls: Vec<(Id, &Url)> = collection
+            .iter()
+            .filter(|(id, _)| self.archive.get(collection, *id).is_none())
+            .map(|(id, entity)| (id, entity.url()))
+            .collect();
+        let results = http::map_concurrent(&urls, self.concurrency, |(id, url)| {
+            (*id, self.archive_page(url))
+        });
+        for (id, result) in &results {
+            if let Ok(key) = result {
+                collection.entity_mut(*id).set_metadata(METADATA_KEY.to_string(), key.clone());
+            }
+        }
+        results
+    }
+}
+
+/// Returns the SHA-256 hash of the data.
+fn sha256(data: &[u8]) -> [u8; 32] {
+    const K: [u32; 64] = [
+        0x428a2f98, 0x71374491, 0xb5c0fbcf, 0xe9b5dba5, 0x3956c25b, 0x59f111f1, 0x923f82a4,
+        0xab1c5ed5, 0xd807aa98, 0x12835b01, 0x243185be, 0x550c7dc3, 0x72be5d74, 0x80deb1fe,
+        0x9bdc06a7, 0xc19bf174, 0xe49b69c1, 0xefbe4786, 0x0fc19dc6, 0x240ca1cc, 0x2de92c6f,
+        0x4a7484aa, 0x5cb0a9dc, 0x76f988da, 0x983e5152, 0xa831c66d, 0xb00327c8, 0xbf597fc7,
+        0xc6e00bf3, 0xd5a79147, 0x06ca6351, 0x14292967, 0x27b70a85, 0x2e1b2138, 0x4d2c6dfc,
+        0x53380d13, 0x650a7354, 0x766a0abb, 0x81c2c92e, 0x92722c85, 0xa2bfe8a1, 0xa81a664b,
+        0xc24b8b70, 0xc76c51a3, 0xd192e819, 0xd6990624, 0xf40e3585, 0x106aa070, 0x19a4c116,
+        0x1e376c08, 0x2748774c, 0x34b0bcb5, 0x391c0cb3, 0x4ed8aa4a, 0x5b9cca4f, 0x682e6ff3,
+        0x748f82ee, 0x78a5636f, 0x84c87814, 0x8cc70208, 0x90befffa, 0xa4506ceb, 0xbef9a3f7,
+        0xc67178f2,
+    ];
+    let mut state: [u32; 8] = [
+        0x6a09e667, 0xbb67ae85, 0x3c6ef372, 0xa54ff53a, 0x510e527f, 0x9b05688c, 0x1f83d9ab,
+        0x5be0cd19,
+    ];
+    // Pad with a one bit, zeroes, and the length in bits, to a whole number of blocks
+    let mut message = data.to_vec();
+    message.push(0x80);
+    while message.len() % 64 != 56 {
+        message.push(0);
+    }
+    message.extend_from_slice(&(data.len() as u64).wrapping_mul(8).to_be_bytes());
+    for block in message.chunks_exact(64) {
+        let mut w = [0u32; 64];
+        for (i, word) in block.chunks_exact(4).enumerate() {
+            w[i] = u32::from_be_bytes(word.try_into().expect("four bytes"));
+        }
+        for i in 16..64 {
+            let s0 = w[i - 15].rotate_right(7) ^ w[i - 15].rotate_right(18) ^ (w[i - 15] >> 3);
+            let s1 = w[i - 2].rotate_right(17) ^ w[i - 2].rotate_right(19) ^ (w[i - 2] >> 10);
+            w[i] = w[i - 16].wrapping_add(s0).wrapping_add(w[i - 7]).wrapping_add(s1);
+        }
+        let [mut a, mut b, mut c, mut d, mut e, mut f, mut g, mut h] = state;
+        for i in 0..64 {
+            let s1 = e.rotate_right(6) ^ e.rotate_right(11) ^ e.rotate_right(25);
+            let ch = (e & f) ^ (!e & g);
+            let t1 = h.wrapping_add(s1).wrapping_add(ch).wrapping_add(K[i]).wrapping_add(w[i]);
+            let s0 = a.rotate_right(2) ^ a.rotate_right(13) ^ a.rotate_right(22);
+            let maj = (a & b) ^ (a & c) ^ (b & c);
+            let t2 = s0.wrapping_add(maj);
+            h = g;
+            g = f;
+            f = e;
+            e = d.wrapping_add(t1);
+            d = c;
+            c = b;
+            b = a;
+            a = t1.wrapping_add(t2);
+        }
+        for (word, value) in state.iter_mut().zip([a, b, c, d, e, f, g, h]) {
+            *word = word.wrapping_add(value);
+        }
+    }
+    let mut ret = [0; 32];
+    for (bytes, word) in ret.chunks_exact_mut(4).zip(state) {
+        bytes.copy_from_slice(&word.to_be_bytes());
+    }
+    ret
+}
@@ -0,0 +1,137 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+const PAGE: &str = r#"<!DOCTYPE html>
+<html>
+<head>
+<title>Page</title>
+<link rel="stylesheet" href="style.css">
+<link rel='icon' href='/favicon.ico'>
+<script src="/missing.js"></script>
+</head>
+<body>
+<img src="/image.png?size=1&amp;dpr=2">
+<img src="data:image/gif;base64,R0lGODlhAQABAAAAACw=">
+<a href="/other">Other</a>
+</body>
+</html>
+"#;
+
+fn route(_: &str, path: &str) -> (&'static str, &'static str) {
+    match path {
+        "/page" | "/again" => ("200 OK\r\nContent-Type: text/html\r\n", PAGE),
+        "/style.css" => ("200 OK\r\nContent-Type: text/css\r\n", "body { color: red; }"),
+        "/favicon.ico" => ("200 OK\r\nContent-Type: image/x-icon\r\n", "ICON"),
+        "/image.png" => ("200 OK\r\nContent-Type: image/png\r\n", "PNG"),
+        "/plain" => ("200 OK\r\nContent-Type: text/plain\r\n", "plain"),
+        _ => ("404 Not Found\r\n", ""),
+    }
+}
+
+fn temp_archive(name: &str) -> Archive {
+    let dir = std::env::temp_dir().join(format!("hbt-archive-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Archive::new(dir)
+}
+
+fn hex(hash: [u8; 32]) -> String {
+    hash.iter().map(|byte| format!("{:02x}", byte)).collect()
+}
+
+#[test]
+fn test_sha256() {
+    let empty = "e3b0c44298fc1c149afbf4c8996fb92427ae41e4649b934ca495991b7852b855";
+    assert_eq!(hex(sha256(b"")), empty);
+    let abc = "ba7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad";
+    assert_eq!(hex(sha256(b"abc")), abc);
+    // Long enough that the length is padded into a second block
+    let input = b"abcdbcdecdefdefgefghfghighijhijkijkljklmklmnlmnomnopnopq";
+    let expected = "248d6a61d20638b8e5c026930c3e6039a33ce45964ff2167f6ecedd419db06c1";
+    assert_eq!(hex(sha256(input)), expected);
+}
+
+#[test]
+fn test_put() {
+    let archive = temp_archive("put");
+    let key = archive.put(b"abc", "txt").unwrap();
+    assert_eq!(key, "ba/7816bf8f01cfea414140de5dae2223b00361a396177a9cb410ff61f20015ad.txt");
+    assert_eq!(fs::read(archive.path(&key)).unwrap(), b"abc");
+    assert_eq!(archive.put(b"abc", "txt").unwrap(), key);
+    fs::remove_dir_all(archive.dir()).unwrap();
+}
+
+#[test]
+fn test_archive_page() {
+    let base = http::serve(route);
+    let archive = temp_archive("page");
+    let archiver = Archiver::new(&Options::default(), archive.clone());
+    let key = archiver.archive_page(&base.join("/page").unwrap()).unwrap();
+    assert!(key.ends_with(".html"));
+    let html = fs::read_to_string(archive.path(&key)).unwrap();
+
+    let style = archive.put(b"body { color: red; }", "css").unwrap();
+    let icon = archive.put(b"ICON", "ico").unwrap();
+    let image = archive.put(b"PNG", "png").unwrap();
+    assert!(html.contains(&format!(r#"<link rel="stylesheet" href="../{}">"#, style)));
+    assert!(html.contains(&format!("<link rel='icon' href='../{}'>", icon)));
+    assert!(html.contains(&format!(r#"<img src="../{}">"#, image)));
+    // Assets that could not be downloaded, inline assets and links to other pages are left alone
+    assert!(html.contains(r#"<script src="/missing.js"></script>"#));
+    assert!(html.contains(r#"<img src="data:image/gif;base64,R0lGODlhAQABAAAAACw=">"#));
+    assert!(html.contains(r#"<a href="/other">"#));
+
+    // The same page is stored once
+    assert_eq!(archiver.archive_page(&base.join("/again").unwrap()).unwrap(), key);
+
+    let result = archiver.archive_page(&base.join("/plain").unwrap());
+    assert!(matches!(result, Err(Error::NotHtml(_, _))));
+    fs::remove_dir_all(archive.dir()).unwrap();
+}
+
+#[test]
+fn test_archive_single_file() {
+    let base = http::serve(route);
+    let archive = temp_archive("single");
+    let options = Options { mode: Mode::SingleFile, ..Options::default() };
+    let archiver = Archiver::new(&options, archive.clone());
+    let key = archiver.archive_page(&base.join("/page").unwrap()).unwrap();
+    let html = fs::read_to_string(archive.path(&key)).unwrap();
+    let css = STANDARD.encode("body { color: red; }");
+    assert!(html.contains(&format!(r#"href="data:text/css;base64,{}""#, css)));
+    assert!(html.contains(r#"<img src="data:image/png;base64,UE5H">"#));
+    // Only the page itself is stored
+    let files = fs::read_dir(archive.dir()).unwrap().count();
+    assert_eq!(files, 1);
+    fs::remove_dir_all(archive.dir()).unwrap();
+}
+
+#[test]
+fn test_archive_pages() {
+    let base = http::serve(route);
+    let archive = temp_archive("pages");
+    let mut collection = Collection::new();
+    for path in ["/page", "/plain"] {
+        let created_at = datetime!(2023-11-15 0:00 UTC).into();
+        let url = base.join(path).unwrap();
+        collection.insert(Entity::new(url, created_at, None, BTreeSet::new()));
+    }
+    let archiver = Archiver::new(&Options::default(), archive.clone());
+    let results = archiver.archive_pages(&mut collection);
+    assert_eq!(results.len(), 2);
+    let (id, key) = &results[0];
+    let key = key.as_ref().unwrap();
+    assert_eq!(collection.entity(*id).metadata_value(METADATA_KEY), Some(key.as_str()));
+    assert_eq!(archive.get(&collection, *id), Some(archive.path(key)));
+    assert!(results[1].1.is_err());
+
+    // Only pages without a copy are downloaded again
+    let results = archiver.archive_pages(&mut collection);
+    let ids: Vec<Id> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![results[0].0]);
+    assert_eq!(collection.entity(ids[0]).url().path(), "/plain");
+    fs::remove_dir_all(archive.dir()).unwrap();
+}
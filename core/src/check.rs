@@ -0,0 +1,90 @@
+//! Checking whether the URLs in a collection still resolve.
+//!
+//! Each URL is requested with `HEAD`, falling back to `GET` for servers that do not support it.
+//! Redirects are reported rather than followed.  Requests are spread over a fixed number of
+//! threads, so at most that many are in flight at once.
+//!
+//! The client is the blocking [`ureq`], not an async one: the crate has no async runtime, and
+//! taking one on for this alone would outweigh what it buys, as a thread per request in flight
+//! costs little at the concurrency a link check runs at.
+
+#[cfg(test)]
+mod tests;
+
+use std::time::Duration;
+
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id, LinkCheck, LinkStatus, Time},
+    http,
+};
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number of requests in flight at once.
+    pub concurrency: usize,
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { concurrency: 8, timeout: Duration::from_secs(10) }
+    }
+}
+
+pub struct Checker {
+    agent: ureq::Agent,
+    concurrency: usize,
+}
+
+impl Checker {
+    pub fn new(options: &Options) -> Checker {
+        let agent = http::agent(options.timeout, 0);
+        let concurrency = options.concurrency.max(1);
+        Checker { agent, concurrency }
+    }
+
+    /// Requests the given URL once.
+    pub fn check_url(&self, url: &Url) -> LinkCheck {
+        let status = match self.request("HEAD", url) {
+            // Some servers reject HEAD outright
+            LinkStatus::Dead { code: 405 | 501 } => self.request("GET", url),
+            status => status,
+        };
+        LinkCheck { checked_at: Time::now(), status }
+    }
+
+    fn request(&self, method: &str, url: &Url) -> LinkStatus {
+        let response = match self.agent.request_url(method, url).call() {
+            Ok(response) => response,
+            Err(ureq::Error::Status(code, _)) => return LinkStatus::Dead { code },
+            Err(ureq::Error::Transport(err)) => {
+                return LinkStatus::Failed { error: err.to_string() }
+            }
+        };
+        let code = response.status();
+        match response.header("location") {
+            Some(location) if (300..400).contains(&code) => {
+                let location =
+                    url.join(location).map_or_else(|_| location.to_string(), String::from);
+                LinkStatus::Redirected { code, location }
+            }
+            _ => LinkStatus::Ok { code },
+        }
+    }
+
+    /// Checks every entity in the collection, recording each result as the entity's last check.
+    ///
+    /// Returns the results in [`Id`] order.
+    pub fn check(&self, collection: &mut Collection) -> Vec<(Id, LinkCheck)> {
+        let urls: Vec<(Id, &Url)> =
+            collection.iter().map(|(id, entity)| (id, entity.url())).collect();
+        let results =
+            http::map_concurrent(&urls, self.concurrency, |(id, url)| (*id, self.check_url(url)));
+        for (id, check) in &results {
+            collection.entity_mut(*id).set_last_check(Some(check.clone()));
+        }
+        results
+    }
+}
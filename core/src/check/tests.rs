@@ -0,0 +1,78 @@
+use std::{collections::BTreeSet, net::TcpListener};
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+fn route(method: &str, path: &str) -> (&'static str, &'static str) {
+    let head = match (method, path) {
+        (_, "/ok") => "200 OK\r\n",
+        (_, "/gone") => "404 Not Found\r\n",
+        (_, "/moved") => "301 Moved Permanently\r\nLocation: /ok\r\n",
+        ("HEAD", "/nohead") => "405 Method Not Allowed\r\n",
+        ("GET", "/nohead") => "200 OK\r\n",
+        _ => "500 Internal Server Error\r\n",
+    };
+    (head, "")
+}
+
+fn serve() -> Url {
+    http::serve(route)
+}
+
+fn create_test_collection(base: &Url, paths: &[&str]) -> Collection {
+    let mut collection = Collection::new();
+    for path in paths {
+        let url = base.join(path).unwrap();
+        collection.insert(Entity::new(
+            url,
+            datetime!(2023-11-15 0:00 UTC).into(),
+            None,
+            BTreeSet::new(),
+        ));
+    }
+    collection
+}
+
+#[test]
+fn test_check_url() {
+    let base = serve();
+    let checker = Checker::new(&Options::default());
+    let status = |path| checker.check_url(&base.join(path).unwrap()).status;
+    assert_eq!(status("/ok"), LinkStatus::Ok { code: 200 });
+    assert_eq!(status("/gone"), LinkStatus::Dead { code: 404 });
+    assert_eq!(status("/nohead"), LinkStatus::Ok { code: 200 });
+    let location = base.join("/ok").unwrap().to_string();
+    assert_eq!(status("/moved"), LinkStatus::Redirected { code: 301, location });
+}
+
+#[test]
+fn test_check_failed() {
+    // Bind then drop a listener so nothing is listening on the port
+    let addr = TcpListener::bind("127.0.0.1:0").unwrap().local_addr().unwrap();
+    let checker = Checker::new(&Options::default());
+    let url = Url::parse(&format!("http://{}/", addr)).unwrap();
+    assert!(matches!(checker.check_url(&url).status, LinkStatus::Failed { .. }));
+}
+
+#[test]
+fn test_check_collection() {
+    let base = serve();
+    let paths = ["/ok", "/gone", "/moved", "/nohead", "/ok?again", "/gone?again"];
+    let mut collection = create_test_collection(&base, &paths);
+    let checker = Checker::new(&Options { concurrency: 3, ..Options::default() });
+    let results = checker.check(&mut collection);
+    let ids: Vec<Id> = results.iter().map(|(id, _)| *id).collect();
+    let expected: Vec<Id> = collection.iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, expected);
+    for (id, check) in results {
+        assert_eq!(collection.entity(id).last_check(), Some(&check));
+    }
+    let dead = collection
+        .entities()
+        .iter()
+        .filter(|entity| matches!(entity.last_check().unwrap().status, LinkStatus::Dead { .. }))
+        .count();
+    assert_eq!(dead, 2);
+}
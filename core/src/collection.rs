@@ -1,21 +1,68 @@
+mod builder;
+mod centrality;
+mod columns;
+mod cooccurrence;
+mod diff;
+mod graph;
+mod merge3;
+mod policy;
+#[cfg(feature = "random")]
+mod random;
+mod search;
+mod shared;
+mod similar;
+mod snapshot;
+mod sort;
+mod stats;
 #[cfg(test)]
 mod tests;
+mod undo;
 
 use std::{
+    borrow::{Borrow, Cow},
     collections::{BTreeMap, BTreeSet, HashMap},
     fmt,
     hash::{Hash, Hasher},
+    iter,
     ops::{Index, IndexMut},
+    path::{Path, PathBuf},
+    slice,
+    str::FromStr,
+    vec,
 };
 
+#[cfg(feature = "regex")]
+use regex::Regex;
 use serde::{Deserialize, Serialize};
 use serde_json::Value;
 use thiserror::Error;
-use time::{serde::timestamp, OffsetDateTime};
+use time::{
+    format_description::well_known::Rfc3339, macros::format_description, serde::rfc3339, Date,
+    OffsetDateTime,
+};
 use url::Url;
 
+use self::columns::Columns;
+#[cfg(feature = "random")]
+pub use self::random::{Weight, UNREAD_WEIGHT};
+pub use self::{
+    builder::EntityBuilder,
+    centrality::{PageRankOptions, DEGREE_KEY, PAGERANK_KEY},
+    cooccurrence::Related,
+    diff::{Change, Diff},
+    graph::{Bfs, Dfs},
+    merge3::{Conflict, Merge, Side},
+    policy::{Field, MergePolicy, PreferExisting, PreferNewer, Resolution, Union},
+    shared::SharedCollection,
+    similar::SAME_HOST,
+    snapshot::Snapshot,
+    sort::{SortKey, SortValue},
+    stats::Stats,
+    undo::UndoStack,
+};
 #[cfg(feature = "pinboard")]
 use crate::pinboard::Post;
+use crate::{hash, import::Imported, intern::Symbol, url_norm};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -31,6 +78,24 @@ pub enum Error {
     ParseInt(#[from] std::num::ParseIntError),
     #[error("time parsing error: {0}")]
     ParseTime(#[from] time::error::ComponentRange),
+    #[error("time parsing error: {0}")]
+    ParseTimeFormat(#[from] time::error::Parse),
+    #[error("invalid id: {0}, expected: {1}")]
+    InvalidId(usize, usize),
+    #[error("invalid edge: {0} -> {1}")]
+    InvalidEdge(usize, usize),
+    #[error("invalid edge kinds: {0}, length: {1}, expected: {2}")]
+    InvalidEdgeKinds(usize, usize, usize),
+    #[error("cycle: {0:?}")]
+    Cycle(Vec<usize>),
+    #[error("no such entity: {0}, length: {1}")]
+    NoSuchEntity(usize, usize),
+    #[error("stale id: {0}, generation: {1}")]
+    StaleId(usize, u32),
+    #[error("duplicate URL: {0}")]
+    DuplicateUrl(String),
+    #[error("cannot merge an entity into itself: {0}")]
+    SelfMerge(usize),
 }
 
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
@@ -57,28 +122,118 @@ impl fmt::Display for Version {
 }
 
 /// An [`Id`] is a unique identifier for an [`Entity`].
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Id(usize);
+///
+/// Ids are generational.  Removing an entity moves another into its slot, which starts a new
+/// generation of the slot, so an [`Id`] held from before the removal matches no entity rather than
+/// whichever entity took its place.  Only the slot is serialized.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct Id {
+    index: usize,
+    generation: u32,
+}
 
 impl Id {
-    const fn new(id: usize) -> Id {
-        Id(id)
+    const fn new(index: usize) -> Id {
+        Id { index, generation: 0 }
+    }
+
+    pub const fn index(&self) -> usize {
+        self.index
+    }
+
+    pub const fn generation(&self) -> u32 {
+        self.generation
     }
 }
 
 impl From<Id> for usize {
     fn from(id: Id) -> usize {
-        id.0
+        id.index
+    }
+}
+
+impl Serialize for Id {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        self.index.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Id {
+    fn deserialize<D>(deserializer: D) -> Result<Id, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        usize::deserialize(deserializer).map(Id::new)
+    }
+}
+
+/// A [`StableId`] identifies an [`Entity`] across runs and serializations, unlike an [`Id`],
+/// which changes as entities are removed.
+///
+/// An entity's stable id is derived from its URL when it is created, and kept from then on.  A
+/// collection gives each of its entities a distinct one, deriving another if an entity's stable id
+/// is already taken.  Stable ids are written as 16 hexadecimal digits.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord)]
+pub struct StableId(u64);
+
+impl StableId {
+    /// Returns the stable id derived from the URL: its [FNV-1a hash](crate::hash), which, unlike
+    /// the hashers in the standard library, is the same on every platform and in every release.
+    pub fn for_url(url: &Url) -> StableId {
+        StableId(hash::fnv1a(url.as_str().as_bytes()))
+    }
+
+    /// Returns the stable id to try when this one is taken.
+    fn next(self) -> StableId {
+        StableId(self.0.wrapping_add(1))
+    }
+}
+
+impl fmt::Display for StableId {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        write!(f, "{:016x}", self.0)
+    }
+}
+
+impl FromStr for StableId {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<StableId, Error> {
+        Ok(StableId(u64::from_str_radix(s, 16)?))
+    }
+}
+
+impl Serialize for StableId {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for StableId {
+    fn deserialize<D>(deserializer: D) -> Result<StableId, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = <Cow<'de, str>>::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
     }
 }
 
 /// A [`Name`] describes an [`Entity`].
+///
+/// Names are [interned](crate::intern), so they are cheap to clone and to compare.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Name(String);
+pub struct Name(Symbol);
 
 impl Name {
-    pub const fn new(name: String) -> Name {
-        Name(name)
+    pub fn new(name: String) -> Name {
+        Name(Symbol::from(name))
     }
 
     pub fn as_str(&self) -> &str {
@@ -92,31 +247,71 @@ impl Hash for Name {
     }
 }
 
+impl Borrow<str> for Name {
+    fn borrow(&self) -> &str {
+        self.0.as_str()
+    }
+}
+
 impl From<String> for Name {
     fn from(name: String) -> Name {
-        Name(name)
+        Name::new(name)
     }
 }
 
 #[cfg(test)]
 impl From<&str> for Name {
     fn from(name: &str) -> Name {
-        Name(name.into())
+        Name(Symbol::new(name))
     }
 }
 
 /// A [`Label`] is text that can be attached to an [`Entity`].
+///
+/// Labels may form a hierarchy, with levels separated by [`Label::SEPARATOR`]: `lang/rust/async`
+/// is a child of `lang/rust`, which is in turn a child of `lang`.  Like [`Name`]s, labels are
+/// interned.
 #[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Label(String);
+pub struct Label(Symbol);
 
 impl Label {
-    pub const fn new(label: String) -> Label {
-        Label(label)
+    pub fn new(label: String) -> Label {
+        Label(Symbol::from(label))
     }
 
     pub fn as_str(&self) -> &str {
         self.0.as_str()
     }
+
+    pub const SEPARATOR: char = '/';
+
+    /// Returns the levels of the label's hierarchy, outermost first.  Empty levels, as in
+    /// `lang//rust/`, are skipped.
+    pub fn segments(&self) -> impl Iterator<Item = &str> {
+        self.as_str().split(Label::SEPARATOR).filter(|segment| !segment.is_empty())
+    }
+
+    /// Returns the label one level up the hierarchy, if any.
+    pub fn parent(&self) -> Option<Label> {
+        let mut segments: Vec<&str> = self.segments().collect();
+        segments.pop()?;
+        if segments.is_empty() {
+            return None;
+        }
+        Some(Label::new(segments.join(Label::SEPARATOR.encode_utf8(&mut [0; 4]))))
+    }
+
+    /// Returns the label's ancestors, nearest first.
+    pub fn ancestors(&self) -> impl Iterator<Item = Label> {
+        std::iter::successors(self.parent(), Label::parent)
+    }
+
+    /// Returns `true` if the label is `other` or one of its descendants.
+    pub fn is_within(&self, other: &Label) -> bool {
+        let mut segments = self.segments();
+        other.segments().all(|segment| segments.next() == Some(segment))
+            && other.segments().next().is_some()
+    }
 }
 
 impl Hash for Label {
@@ -127,38 +322,85 @@ impl Hash for Label {
 
 impl From<String> for Label {
     fn from(label: String) -> Label {
-        Label(label)
+        Label::new(label)
     }
 }
 
 impl From<&str> for Label {
     fn from(label: &str) -> Label {
-        Label(label.into())
+        Label(Symbol::new(label))
     }
 }
 
-#[derive(Debug, Copy, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
-pub struct Time(#[serde(with = "timestamp")] OffsetDateTime);
+/// A point in time.
+///
+/// Times are serialized as RFC 3339 date-times, keeping their offset and fractional seconds.  When
+/// deserializing, Unix timestamps, as written before, and date-only `YYYY-MM-DD` values are
+/// accepted as well, as read by [`Time::parse`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize)]
+pub struct Time(#[serde(serialize_with = "rfc3339::serialize")] OffsetDateTime);
 
 impl Time {
     pub const fn new(time: OffsetDateTime) -> Time {
         Time(time)
     }
 
-    #[cfg(feature = "pinboard")]
-    fn parse(time: &str) -> Result<Time, Error> {
-        let timestamp: i64 = time.parse()?;
-        let time = OffsetDateTime::from_unix_timestamp(timestamp)?;
+    pub fn now() -> Time {
+        Time(OffsetDateTime::now_utc())
+    }
+
+    pub const fn unix_timestamp(&self) -> i64 {
+        self.0.unix_timestamp()
+    }
+
+    /// Parses a Unix timestamp, an RFC 3339 date-time, or a `YYYY-MM-DD` date, which is taken as
+    /// midnight UTC.
+    pub fn parse(time: &str) -> Result<Time, Error> {
+        if let Ok(timestamp) = time.parse::<i64>() {
+            let time = OffsetDateTime::from_unix_timestamp(timestamp)?;
+            return Ok(Time(time));
+        }
+        if let Ok(date) = Date::parse(time, format_description!("[year]-[month]-[day]")) {
+            return Ok(Time(date.midnight().assume_utc()));
+        }
+        let time = OffsetDateTime::parse(time, &Rfc3339)?;
         Ok(Time(time))
     }
 }
 
+impl<'de> Deserialize<'de> for Time {
+    fn deserialize<D>(deserializer: D) -> Result<Time, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        #[derive(Deserialize)]
+        #[serde(untagged)]
+        enum Repr {
+            Timestamp(i64),
+            Text(String),
+        }
+
+        match Repr::deserialize(deserializer)? {
+            Repr::Timestamp(timestamp) => OffsetDateTime::from_unix_timestamp(timestamp)
+                .map(Time)
+                .map_err(serde::de::Error::custom),
+            Repr::Text(text) => Time::parse(&text).map_err(serde::de::Error::custom),
+        }
+    }
+}
+
 impl From<OffsetDateTime> for Time {
     fn from(time: OffsetDateTime) -> Time {
         Time(time)
     }
 }
 
+impl From<Time> for OffsetDateTime {
+    fn from(time: Time) -> OffsetDateTime {
+        time.0
+    }
+}
+
 impl Default for Time {
     fn default() -> Time {
         Time(OffsetDateTime::UNIX_EPOCH)
@@ -198,20 +440,171 @@ impl From<&str> for Extended {
     }
 }
 
+/// A change to an [`Entity`]: when it happened, what it added, and where it came from.
+///
+/// Updates that only record a time are serialized as a bare timestamp, as they were before
+/// updates carried anything else.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "SerializedUpdate", into = "SerializedUpdate")]
+pub struct Update {
+    pub at: Time,
+    pub names_added: BTreeSet<Name>,
+    pub labels_added: BTreeSet<Label>,
+    pub source: Option<String>,
+}
+
+impl Update {
+    pub fn new(at: Time) -> Update {
+        Update { at, names_added: BTreeSet::new(), labels_added: BTreeSet::new(), source: None }
+    }
+
+    fn is_bare(&self) -> bool {
+        self.names_added.is_empty() && self.labels_added.is_empty() && self.source.is_none()
+    }
+}
+
+#[derive(Serialize, Deserialize)]
+#[serde(untagged)]
+enum SerializedUpdate {
+    Bare(Time),
+    #[serde(rename_all = "camelCase")]
+    Full {
+        at: Time,
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        names_added: BTreeSet<Name>,
+        #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+        labels_added: BTreeSet<Label>,
+        #[serde(default, skip_serializing_if = "Option::is_none")]
+        source: Option<String>,
+    },
+}
+
+impl From<SerializedUpdate> for Update {
+    fn from(update: SerializedUpdate) -> Update {
+        match update {
+            SerializedUpdate::Bare(at) => Update::new(at),
+            SerializedUpdate::Full { at, names_added, labels_added, source } => {
+                Update { at, names_added, labels_added, source }
+            }
+        }
+    }
+}
+
+impl From<Update> for SerializedUpdate {
+    fn from(update: Update) -> SerializedUpdate {
+        if update.is_bare() {
+            return SerializedUpdate::Bare(update.at);
+        }
+        let Update { at, names_added, labels_added, source } = update;
+        SerializedUpdate::Full { at, names_added, labels_added, source }
+    }
+}
+
+/// A free-text annotation on an [`Entity`], with when it was written.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Note {
+    pub created_at: Time,
+    pub text: String,
+}
+
+impl Note {
+    pub fn new(created_at: Time, text: String) -> Note {
+        Note { created_at, text }
+    }
+}
+
+/// A place in a source file that lists an [`Entity`], with lines counted from 1.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+pub struct Location {
+    pub path: PathBuf,
+    pub line: usize,
+}
+
+impl Location {
+    pub fn new(path: impl Into<PathBuf>, line: usize) -> Location {
+        Location { path: path.into(), line }
+    }
+}
+
+/// Where an [`Entity`] is in a read-it-later workflow.
+///
+/// Statuses are ordered by progress, from [`ReadStatus::Unread`] to [`ReadStatus::Archived`].
+#[derive(
+    Debug, Clone, Copy, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize,
+)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum ReadStatus {
+    #[default]
+    Unread,
+    Read {
+        on: Time,
+    },
+    Archived,
+}
+
+impl ReadStatus {
+    pub fn is_unread(&self) -> bool {
+        matches!(self, ReadStatus::Unread)
+    }
+}
+
+/// The outcome of requesting an [`Entity`]'s URL.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(tag = "status", rename_all = "camelCase")]
+pub enum LinkStatus {
+    Ok { code: u16 },
+    Redirected { code: u16, location: String },
+    Dead { code: u16 },
+    Failed { error: String },
+}
+
+/// A [`LinkStatus`] together with when it was determined.
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+pub struct LinkCheck {
+    pub checked_at: Time,
+    #[serde(flatten)]
+    pub status: LinkStatus,
+}
+
 /// An [`Entity`] is a page in the collection.
-#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+#[derive(Debug, Clone, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 pub struct Entity {
     #[serde(rename = "uri")]
     url: Url,
+    /// Absent from entities saved before stable ids, which are given one when they are added to a
+    /// collection.
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    stable_id: Option<StableId>,
     created_at: Time,
-    updated_at: Vec<Time>,
+    #[serde(rename = "updatedAt")]
+    history: Vec<Update>,
     names: BTreeSet<Name>,
     labels: BTreeSet<Label>,
     #[serde(skip_serializing_if = "Option::is_none")]
     extended: Option<Extended>,
     shared: bool,
     toread: bool,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    last_check: Option<LinkCheck>,
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    notes: Vec<Note>,
+    #[serde(default, skip_serializing_if = "ReadStatus::is_unread")]
+    read_status: ReadStatus,
+    #[serde(default, skip_serializing_if = "std::ops::Not::not")]
+    starred: bool,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    metadata: BTreeMap<String, String>,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    favicon: Option<PathBuf>,
+    /// Other URLs of the entity, such as those it was found at before it moved.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    aliases: BTreeSet<Url>,
+    /// The places in source files the entity was parsed from.
+    #[serde(default, skip_serializing_if = "BTreeSet::is_empty")]
+    sources: BTreeSet<Location>,
 }
 
 impl Entity {
@@ -221,45 +614,133 @@ impl Entity {
         maybe_name: Option<Name>,
         labels: BTreeSet<Label>,
     ) -> Entity {
-        let updated_at = Vec::new();
+        let stable_id = Some(StableId::for_url(&url));
+        let history = Vec::new();
         let names = maybe_name.into_iter().collect();
         let extended = None;
         let shared = false;
         let toread = false;
-        Entity { url, created_at, updated_at, names, labels, extended, shared, toread }
+        let last_check = None;
+        let notes = Vec::new();
+        let read_status = ReadStatus::default();
+        let starred = false;
+        let metadata = BTreeMap::new();
+        let favicon = None;
+        let aliases = BTreeSet::new();
+        let sources = BTreeSet::new();
+        Entity {
+            url,
+            stable_id,
+            created_at,
+            history,
+            names,
+            labels,
+            extended,
+            shared,
+            toread,
+            last_check,
+            notes,
+            read_status,
+            starred,
+            metadata,
+            favicon,
+            aliases,
+            sources,
+        }
     }
 
-    pub fn update(
+    pub(crate) fn update(
         &mut self,
         updated_at: Time,
         names: BTreeSet<Name>,
         labels: BTreeSet<Label>,
     ) -> &mut Entity {
-        if updated_at < self.created_at {
-            self.updated_at.push(self.created_at);
-            self.created_at = updated_at;
+        self.update_from(updated_at, names, labels, None)
+    }
+
+    /// Like [`Entity::update`], recording `source` on the resulting [`Update`].
+    pub(crate) fn update_from(
+        &mut self,
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        labels: BTreeSet<Label>,
+        source: Option<String>,
+    ) -> &mut Entity {
+        let at = if updated_at < self.created_at {
+            std::mem::replace(&mut self.created_at, updated_at)
         } else {
-            self.updated_at.push(updated_at);
-        }
-        self.names.extend(names);
-        self.labels.extend(labels);
+            updated_at
+        };
+        let names_added =
+            names.into_iter().filter(|name| self.names.insert(name.clone())).collect();
+        let labels_added =
+            labels.into_iter().filter(|label| self.labels.insert(label.clone())).collect();
+        self.history.push(Update { at, names_added, labels_added, source });
         self
     }
 
-    pub fn merge(&mut self, other: Entity) -> &mut Entity {
-        self.update(other.created_at, other.names, other.labels)
+    /// Merges `other` into this entity, keeping the notes of both and the further along of their
+    /// read statuses.  The merged entity is starred if either was.  Metadata from `other` is added
+    /// for keys this entity does not already have, and its source locations are kept.
+    pub(crate) fn merge(&mut self, other: Entity) -> &mut Entity {
+        self.merge_from(other, None)
+    }
+
+    /// Like [`Entity::merge`], recording `source` on the resulting [`Update`].
+    pub(crate) fn merge_from(&mut self, other: Entity, source: Option<String>) -> &mut Entity {
+        for note in other.notes {
+            self.add_note(note);
+        }
+        self.read_status = self.read_status.max(other.read_status);
+        self.starred |= other.starred;
+        for (key, value) in other.metadata {
+            self.metadata.entry(key).or_insert(value);
+        }
+        if self.favicon.is_none() {
+            self.favicon = other.favicon;
+        }
+        for alias in other.aliases {
+            self.add_alias(alias);
+        }
+        self.sources.extend(other.sources);
+        self.update_from(other.created_at, other.names, other.labels, source)
     }
 
     pub fn url(&self) -> &Url {
         &self.url
     }
 
+    /// Returns the entity's [`StableId`], deriving it from the URL if the entity has none yet.
+    pub fn stable_id(&self) -> StableId {
+        self.stable_id.unwrap_or_else(|| StableId::for_url(&self.url))
+    }
+
     pub fn created_at(&self) -> &Time {
         &self.created_at
     }
 
-    pub fn updated_at(&self) -> &[Time] {
-        &self.updated_at
+    /// Returns the times the entity was updated, in the order the updates were recorded.
+    pub fn updated_at(&self) -> impl ExactSizeIterator<Item = &Time> + DoubleEndedIterator + '_ {
+        self.history.iter().map(|update| &update.at)
+    }
+
+    /// Returns the entity's updates, in the order they were recorded.
+    pub fn history(&self) -> &[Update] {
+        &self.history
+    }
+
+    /// Returns the time of the latest update, if there has been one.
+    pub fn last_updated_at(&self) -> Option<&Time> {
+        self.updated_at().max()
+    }
+
+    /// Returns when the given label was added to the entity: at creation, or by an update.
+    pub fn label_added_at(&self, label: &Label) -> Option<&Time> {
+        if !self.labels.contains(label) {
+            return None;
+        }
+        let update = self.history.iter().find(|update| update.labels_added.contains(label));
+        Some(update.map_or(&self.created_at, |update| &update.at))
     }
 
     pub fn names(&self) -> &BTreeSet<Name> {
@@ -270,19 +751,174 @@ impl Entity {
         &self.labels
     }
 
-    pub fn labels_mut(&mut self) -> &mut BTreeSet<Label> {
+    pub(crate) fn labels_mut(&mut self) -> &mut BTreeSet<Label> {
         &mut self.labels
     }
+
+    pub fn shared(&self) -> bool {
+        self.shared
+    }
+
+    pub fn extended(&self) -> Option<&Extended> {
+        self.extended.as_ref()
+    }
+
+    pub fn set_extended(&mut self, extended: Option<Extended>) -> &mut Entity {
+        self.extended = extended;
+        self
+    }
+
+    pub fn toread(&self) -> bool {
+        self.toread
+    }
+
+    pub fn set_toread(&mut self, toread: bool) -> &mut Entity {
+        self.toread = toread;
+        self
+    }
+
+    pub fn last_check(&self) -> Option<&LinkCheck> {
+        self.last_check.as_ref()
+    }
+
+    pub fn set_last_check(&mut self, last_check: Option<LinkCheck>) -> &mut Entity {
+        self.last_check = last_check;
+        self
+    }
+
+    /// Returns the entity's notes, oldest first.
+    pub fn notes(&self) -> &[Note] {
+        &self.notes
+    }
+
+    /// Adds a note, unless the entity already has an identical one.
+    ///
+    /// Returns whether the note was added.
+    pub(crate) fn add_note(&mut self, note: Note) -> bool {
+        if self.notes.contains(&note) {
+            return false;
+        }
+        let index = self.notes.partition_point(|other| other.created_at <= note.created_at);
+        self.notes.insert(index, note);
+        true
+    }
+
+    pub(crate) fn clear_notes(&mut self) -> &mut Entity {
+        self.notes.clear();
+        self
+    }
+
+    pub fn read_status(&self) -> &ReadStatus {
+        &self.read_status
+    }
+
+    pub fn set_read_status(&mut self, read_status: ReadStatus) -> &mut Entity {
+        self.read_status = read_status;
+        self
+    }
+
+    pub fn mark_read(&mut self, on: Time) -> &mut Entity {
+        self.set_read_status(ReadStatus::Read { on })
+    }
+
+    pub fn mark_unread(&mut self) -> &mut Entity {
+        self.set_read_status(ReadStatus::Unread)
+    }
+
+    pub fn archive(&mut self) -> &mut Entity {
+        self.set_read_status(ReadStatus::Archived)
+    }
+
+    pub fn starred(&self) -> bool {
+        self.starred
+    }
+
+    pub fn set_starred(&mut self, starred: bool) -> &mut Entity {
+        self.starred = starred;
+        self
+    }
+
+    /// Returns the entity's metadata, such as source-specific fields preserved by importers.
+    pub fn metadata(&self) -> &BTreeMap<String, String> {
+        &self.metadata
+    }
+
+    pub fn metadata_value(&self, key: &str) -> Option<&str> {
+        self.metadata.get(key).map(String::as_str)
+    }
+
+    /// Sets a metadata value, returning the previous value for `key`, if any.
+    pub fn set_metadata(&mut self, key: String, value: String) -> Option<String> {
+        self.metadata.insert(key, value)
+    }
+
+    pub fn remove_metadata(&mut self, key: &str) -> Option<String> {
+        self.metadata.remove(key)
+    }
+
+    /// Returns the path of the cached favicon for the entity's host, if one has been fetched.
+    pub fn favicon(&self) -> Option<&Path> {
+        self.favicon.as_deref()
+    }
+
+    pub fn set_favicon(&mut self, favicon: Option<PathBuf>) -> &mut Entity {
+        self.favicon = favicon;
+        self
+    }
+
+    /// Returns the entity's other URLs, such as mirrors, or those it had before
+    /// [`Collection::rehome`].
+    pub fn aliases(&self) -> &BTreeSet<Url> {
+        &self.aliases
+    }
+
+    /// Adds an alias, unless it is the entity's URL.
+    ///
+    /// Returns whether the alias was new.
+    pub(crate) fn add_alias(&mut self, url: Url) -> bool {
+        url != self.url && self.aliases.insert(url)
+    }
+
+    /// Returns the places in source files the entity was parsed from, in path and line order.
+    pub fn sources(&self) -> &BTreeSet<Location> {
+        &self.sources
+    }
+
+    /// Records a place in a source file the entity was parsed from.
+    ///
+    /// Returns whether the location was new.
+    pub fn add_source(&mut self, location: Location) -> bool {
+        self.sources.insert(location)
+    }
+
+    /// Forgets the locations in the given source file, as before it is parsed again.
+    pub fn remove_sources(&mut self, path: &Path) -> &mut Entity {
+        self.sources.retain(|location| location.path != path);
+        self
+    }
+
+    /// Expects `query` to already be lowercase.
+    pub(crate) fn matches(&self, query: &str) -> bool {
+        let contains = |s: &str| s.to_lowercase().contains(query);
+        contains(self.url.as_str())
+            || self.names.iter().any(|name| contains(name.as_str()))
+            || self.labels.iter().any(|label| contains(label.as_str()))
+    }
 }
 
+/// The metadata key under which a Pinboard bookmark's `hash` is kept.
+#[cfg(feature = "pinboard")]
+pub const META_PINBOARD_HASH: &str = "pinboard.hash";
+
 #[cfg(feature = "pinboard")]
 impl TryFrom<Post> for Entity {
     type Error = Error;
 
     fn try_from(post: Post) -> Result<Entity, Self::Error> {
         let url = Url::parse(&post.href)?;
+        let stable_id = Some(StableId::for_url(&url));
         let created_at = Time::parse(&post.time)?;
-        let updated_at: Vec<Time> = Vec::new();
+        let history = Vec::new();
         let names = {
             let mut tmp = BTreeSet::new();
             if let Some(name) = post.description.map(Name::new) {
@@ -298,34 +934,178 @@ impl TryFrom<Post> for Entity {
         let extended = post.extended.map(Extended::new);
         let shared = post.shared;
         let toread = post.toread;
-        Ok(Entity { url, created_at, updated_at, names, labels, extended, shared, toread })
+        let last_check = None;
+        let notes = Vec::new();
+        let read_status = ReadStatus::default();
+        let starred = false;
+        let metadata =
+            post.hash.into_iter().map(|hash| (META_PINBOARD_HASH.into(), hash)).collect();
+        let favicon = None;
+        let aliases = BTreeSet::new();
+        let sources = BTreeSet::new();
+        Ok(Entity {
+            url,
+            stable_id,
+            created_at,
+            history,
+            names,
+            labels,
+            extended,
+            shared,
+            toread,
+            last_check,
+            notes,
+            read_status,
+            starred,
+            metadata,
+            favicon,
+            aliases,
+            sources,
+        })
     }
 }
 
 pub type Edges = Vec<Id>;
 
+/// The relationship an edge expresses between two entities.
+///
+/// Kinds are serialized as strings, with [`EdgeKind::Custom`] kinds written as-is.
+#[derive(Debug, Clone, Default, PartialEq, Eq, Hash, PartialOrd, Ord, Serialize, Deserialize)]
+#[serde(from = "String", into = "String")]
+pub enum EdgeKind {
+    #[default]
+    RelatedTo,
+    Supersedes,
+    PartOf,
+    /// Added by [`Collection::link_similar`].
+    Similar,
+    Custom(String),
+}
+
+impl EdgeKind {
+    const RELATED_TO: &'static str = "relatedTo";
+    const SUPERSEDES: &'static str = "supersedes";
+    const PART_OF: &'static str = "partOf";
+    const SIMILAR: &'static str = "similar";
+
+    pub fn as_str(&self) -> &str {
+        match self {
+            EdgeKind::RelatedTo => EdgeKind::RELATED_TO,
+            EdgeKind::Supersedes => EdgeKind::SUPERSEDES,
+            EdgeKind::PartOf => EdgeKind::PART_OF,
+            EdgeKind::Similar => EdgeKind::SIMILAR,
+            EdgeKind::Custom(kind) => kind,
+        }
+    }
+
+    pub fn is_default(&self) -> bool {
+        *self == EdgeKind::default()
+    }
+}
+
+impl fmt::Display for EdgeKind {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        f.write_str(self.as_str())
+    }
+}
+
+impl From<&str> for EdgeKind {
+    fn from(kind: &str) -> EdgeKind {
+        match kind {
+            EdgeKind::RELATED_TO => EdgeKind::RelatedTo,
+            EdgeKind::SUPERSEDES => EdgeKind::Supersedes,
+            EdgeKind::PART_OF => EdgeKind::PartOf,
+            EdgeKind::SIMILAR => EdgeKind::Similar,
+            _ => EdgeKind::Custom(kind.to_string()),
+        }
+    }
+}
+
+impl From<String> for EdgeKind {
+    fn from(kind: String) -> EdgeKind {
+        match kind.as_str() {
+            EdgeKind::RELATED_TO | EdgeKind::SUPERSEDES | EdgeKind::PART_OF | EdgeKind::SIMILAR => {
+                EdgeKind::from(kind.as_str())
+            }
+            _ => EdgeKind::Custom(kind),
+        }
+    }
+}
+
+impl From<EdgeKind> for String {
+    fn from(kind: EdgeKind) -> String {
+        match kind {
+            EdgeKind::Custom(kind) => kind,
+            kind => kind.as_str().to_string(),
+        }
+    }
+}
+
 /// A collection of entities.
 ///
 /// This is a graph structure where a nodes are represented by a vector of entities and edges are
-/// represented by an adjacency list.
-#[derive(Debug, Default, PartialEq, Eq)]
+/// represented by an adjacency list.  Each edge has an [`EdgeKind`], kept in a list parallel to
+/// the adjacency list, and a reverse adjacency list records the sources of the edges to each
+/// entity.
+///
+/// The collection maintains indexes from each [`Name`] and [`Label`] to the entities carrying it,
+/// and from the terms in names, labels and notes for [`Collection::search_ranked`], so mutations
+/// that affect names, labels or notes go through the collection rather than through an [`Entity`]
+/// directly.  An [`Entity`] borrowed mutably, as from [`Collection::entity_mut`], offers no way to
+/// change them.
+#[derive(Debug, Default, Clone)]
 pub struct Collection {
+    name: Option<String>,
     nodes: Vec<Entity>,
+    /// The generation of each slot, including slots vacated by removals.
+    generations: Vec<u32>,
     edges: Vec<Edges>,
+    kinds: Vec<Vec<EdgeKind>>,
+    /// The sources of the edges to each entity, in ascending order.
+    incoming: Vec<Edges>,
+    /// Labels that are replaced by another as entities are added or updated.
+    aliases: BTreeMap<Label, Label>,
     urls: HashMap<Url, Id>,
+    /// Like `urls`, for the aliases of entities, which are only found if no entity has the URL.
+    url_aliases: HashMap<Url, Id>,
+    stable_ids: HashMap<StableId, Id>,
+    names: HashMap<Name, BTreeSet<Id>>,
+    labels: HashMap<Label, BTreeSet<Id>>,
+    text: search::TextIndex,
+    /// Copies of the dates and labels of the entities, for scans.
+    columns: Columns,
+    normalization: Option<url_norm::Options>,
+    url_filter: Option<url_norm::TrackingFilter>,
+}
+
+/// Collections are equal if they have equal names and aliases, equal entities in the same order,
+/// and the same edges.  The generations of their [`Id`]s are not compared.
+impl PartialEq for Collection {
+    fn eq(&self, other: &Collection) -> bool {
+        let indexes = |edges: &Edges| edges.iter().map(|id| id.index).collect::<Vec<_>>();
+        self.name == other.name
+            && self.aliases == other.aliases
+            && self.nodes == other.nodes
+            && self.edges.iter().map(indexes).eq(other.edges.iter().map(indexes))
+            && self.kinds == other.kinds
+            && self.normalization == other.normalization
+            && self.url_filter == other.url_filter
+    }
 }
 
+impl Eq for Collection {}
+
 impl Index<Id> for Vec<Entity> {
     type Output = Entity;
 
     fn index(&self, id: Id) -> &Self::Output {
-        &self[id.0]
+        &self[id.index]
     }
 }
 
 impl IndexMut<Id> for Vec<Entity> {
     fn index_mut(&mut self, id: Id) -> &mut Self::Output {
-        &mut self[id.0]
+        &mut self[id.index]
     }
 }
 
@@ -333,76 +1113,780 @@ impl Index<Id> for Vec<Edges> {
     type Output = Edges;
 
     fn index(&self, id: Id) -> &Self::Output {
-        &self[id.0]
+        &self[id.index]
     }
 }
 
 impl IndexMut<Id> for Vec<Edges> {
     fn index_mut(&mut self, id: Id) -> &mut Self::Output {
-        &mut self[id.0]
+        &mut self[id.index]
     }
 }
 
-impl Collection {
+impl Index<Id> for Vec<Vec<EdgeKind>> {
+    type Output = Vec<EdgeKind>;
+
+    fn index(&self, id: Id) -> &Self::Output {
+        &self[id.index]
+    }
+}
+
+impl IndexMut<Id> for Vec<Vec<EdgeKind>> {
+    fn index_mut(&mut self, id: Id) -> &mut Self::Output {
+        &mut self[id.index]
+    }
+}
+
+impl Collection {
     pub fn new() -> Collection {
         let nodes = Vec::new();
+        let generations = Vec::new();
         let edges = Vec::new();
+        let kinds = Vec::new();
+        let incoming = Vec::new();
+        let aliases = BTreeMap::new();
         let urls = HashMap::new();
-        Collection { nodes, edges, urls }
+        let url_aliases = HashMap::new();
+        let stable_ids = HashMap::new();
+        let names = HashMap::new();
+        let labels = HashMap::new();
+        let text = search::TextIndex::default();
+        let columns = Columns::default();
+        let normalization = None;
+        let url_filter = None;
+        Collection {
+            name: None,
+            nodes,
+            generations,
+            edges,
+            kinds,
+            incoming,
+            aliases,
+            urls,
+            url_aliases,
+            stable_ids,
+            names,
+            labels,
+            text,
+            columns,
+            normalization,
+            url_filter,
+        }
     }
 
     pub fn with_capacity(capacity: usize) -> Collection {
         let nodes = Vec::with_capacity(capacity);
+        let generations = Vec::with_capacity(capacity);
         let edges = Vec::with_capacity(capacity);
+        let kinds = Vec::with_capacity(capacity);
+        let incoming = Vec::with_capacity(capacity);
+        let aliases = BTreeMap::new();
         let urls = HashMap::with_capacity(capacity);
-        Collection { nodes, edges, urls }
+        let url_aliases = HashMap::new();
+        let stable_ids = HashMap::with_capacity(capacity);
+        let names = HashMap::with_capacity(capacity);
+        let labels = HashMap::new();
+        let text = search::TextIndex::default();
+        let columns = Columns::with_capacity(capacity);
+        let normalization = None;
+        let url_filter = None;
+        Collection {
+            name: None,
+            nodes,
+            generations,
+            edges,
+            kinds,
+            incoming,
+            aliases,
+            urls,
+            url_aliases,
+            stable_ids,
+            names,
+            labels,
+            text,
+            columns,
+            normalization,
+            url_filter,
+        }
+    }
+
+    pub fn name(&self) -> Option<&str> {
+        self.name.as_deref()
+    }
+
+    pub fn set_name(&mut self, name: Option<String>) {
+        self.name = name;
     }
 
     pub fn len(&self) -> usize {
         let len = self.nodes.len();
-        assert_eq!(len, self.edges.len());
+        debug_assert_eq!(len, self.edges.len());
         len
     }
 
     pub fn is_empty(&self) -> bool {
         let is_empty = self.nodes.is_empty();
-        assert_eq!(is_empty, self.edges.is_empty());
+        debug_assert_eq!(is_empty, self.edges.is_empty());
         is_empty
     }
 
+    /// Returns `id` if it refers to an entity of the collection.
+    fn check(&self, id: Id) -> Result<Id, Error> {
+        if id.index >= self.len() {
+            Err(Error::NoSuchEntity(id.index, self.len()))
+        } else if id.generation != self.generations[id.index] {
+            Err(Error::StaleId(id.index, id.generation))
+        } else {
+            Ok(id)
+        }
+    }
+
+    /// Like [`Collection::check`], panicking if `id` does not refer to an entity.
+    fn checked(&self, id: Id) -> Id {
+        self.check(id).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Returns the [`Id`] of the entity in the given slot.
+    fn id_at(&self, index: usize) -> Id {
+        Id { index, generation: self.generations[index] }
+    }
+
+    pub fn normalization(&self) -> Option<&url_norm::Options> {
+        self.normalization.as_ref()
+    }
+
+    /// Sets whether URLs are looked up by their normalized form.
+    ///
+    /// With normalization, [`Collection::contains`], [`Collection::id`] and therefore
+    /// [`Collection::upsert`] treat URLs that normalize alike as the same, so near-identical URLs
+    /// merge.  Entities keep the URL they were created with.  If existing entities already
+    /// normalize alike, lookups find the one with the lowest [`Id`].
+    pub fn set_normalization(&mut self, normalization: Option<url_norm::Options>) {
+        self.normalization = normalization;
+        self.rebuild_urls();
+    }
+
+    pub fn url_filter(&self) -> Option<&url_norm::TrackingFilter> {
+        self.url_filter.as_ref()
+    }
+
+    /// Sets a filter applied to the URLs of entities as they are inserted, and to URLs being
+    /// looked up.
+    ///
+    /// Entities already in the collection keep their URLs, but are found by their filtered URL.
+    pub fn set_url_filter(&mut self, url_filter: Option<url_norm::TrackingFilter>) {
+        self.url_filter = url_filter;
+        self.rebuild_urls();
+    }
+
+    fn rebuild_urls(&mut self) {
+        self.urls.clear();
+        self.url_aliases.clear();
+        for index in 0..self.len() {
+            let id = self.id_at(index);
+            let key = self.key(self.nodes[id].url()).into_owned();
+            self.urls.entry(key).or_insert(id);
+            self.index_aliases(id);
+        }
+    }
+
+    fn key<'a>(&self, url: &'a Url) -> Cow<'a, Url> {
+        let url = match &self.url_filter {
+            Some(filter) => Cow::Owned(filter.strip(url)),
+            None => Cow::Borrowed(url),
+        };
+        match &self.normalization {
+            Some(options) => Cow::Owned(url_norm::normalize(&url, options)),
+            None => url,
+        }
+    }
+
+    /// Returns whether an entity has the URL, or has it as an alias.
     pub fn contains(&self, url: &Url) -> bool {
-        self.urls.contains_key(url)
+        self.id(url).is_some()
     }
 
+    /// Returns the [`Id`] of the entity with the URL, or else of an entity with the URL as an
+    /// alias.
     pub fn id(&self, url: &Url) -> Option<Id> {
-        self.urls.get(url).copied()
+        let key = self.key(url);
+        self.urls.get(&key).or_else(|| self.url_aliases.get(&key)).copied()
+    }
+
+    /// Adds an alias to the entity with the given [`Id`], so that [`Collection::id`] finds the
+    /// entity by it too.
+    ///
+    /// Returns whether the alias was new.
+    pub fn add_alias(&mut self, id: Id, url: Url) -> bool {
+        let id = self.checked(id);
+        let added = self.nodes[id].add_alias(url);
+        if added {
+            self.index_aliases(id);
+        }
+        added
     }
 
-    pub fn insert(&mut self, entity: Entity) -> Id {
-        let id = Id::new(self.len());
+    /// Removes an alias from the entity with the given [`Id`].
+    ///
+    /// Returns whether the entity had the alias.
+    pub fn remove_alias(&mut self, id: Id, url: &Url) -> bool {
+        let id = self.checked(id);
+        let removed = self.nodes[id].aliases.remove(url);
+        if removed {
+            let key = self.key(url);
+            if self.url_aliases.get(&key) == Some(&id) {
+                self.url_aliases.remove(&key);
+            }
+            self.restore_alias(url);
+        }
+        removed
+    }
+
+    /// Points the alias at an entity that has it, if it no longer points at one.
+    fn restore_alias(&mut self, alias: &Url) {
+        let key = self.key(alias).into_owned();
+        if self.url_aliases.contains_key(&key) {
+            return;
+        }
+        let other = (0..self.len()).map(|index| self.id_at(index)).find(|&other| {
+            self.nodes[other].aliases.iter().any(|alias| self.key(alias).as_ref() == &key)
+        });
+        if let Some(other) = other {
+            self.url_aliases.insert(key, other);
+        }
+    }
+
+    /// Returns the [`Id`] of the entity with the given [`StableId`].
+    pub fn by_stable_id(&self, stable_id: StableId) -> Option<Id> {
+        self.stable_ids.get(&stable_id).copied()
+    }
+
+    /// Gives the entity with the given [`Id`] a stable id no other entity has, keeping its own if
+    /// it is free.
+    fn claim_stable_id(&mut self, id: Id) {
+        let mut stable_id = self.nodes[id].stable_id();
+        while self.stable_ids.get(&stable_id).is_some_and(|&other| other != id) {
+            stable_id = stable_id.next();
+        }
+        self.nodes[id].stable_id = Some(stable_id);
+        self.stable_ids.insert(stable_id, id);
+    }
+
+    pub fn insert(&mut self, mut entity: Entity) -> Id {
+        if let Some(filter) = &self.url_filter {
+            entity.url = filter.strip(&entity.url);
+        }
+        self.apply_aliases(&mut entity.labels);
+        if self.len() == self.generations.len() {
+            self.generations.push(0);
+        }
+        let id = self.id_at(self.len());
         self.nodes.push(entity);
         self.edges.push(Vec::new());
-        let url = self.nodes[id].url().to_owned();
-        self.urls.insert(url, id);
+        self.kinds.push(Vec::new());
+        self.incoming.push(Vec::new());
+        let key = self.key(self.nodes[id].url()).into_owned();
+        self.urls.entry(key).or_insert(id);
+        self.claim_stable_id(id);
+        self.index(id);
         id
     }
 
+    /// Like [`Collection::insert`], returning an error rather than adding the entity if another
+    /// entity already has its URL, or has it as an alias.
+    pub fn try_insert(&mut self, entity: Entity) -> Result<Id, Error> {
+        if self.contains(entity.url()) {
+            return Err(Error::DuplicateUrl(entity.url().to_string()));
+        }
+        Ok(self.insert(entity))
+    }
+
+    /// Reserves space for at least `additional` more entities.
+    pub fn reserve(&mut self, additional: usize) {
+        self.nodes.reserve(additional);
+        self.generations.reserve(additional.saturating_sub(self.generations.len() - self.len()));
+        self.edges.reserve(additional);
+        self.kinds.reserve(additional);
+        self.incoming.reserve(additional);
+        self.urls.reserve(additional);
+        self.stable_ids.reserve(additional);
+        self.columns.reserve(additional);
+    }
+
+    /// Upserts each of the entities, reserving space for them first, and reports how many were
+    /// added and how many merged into entities already present.
+    pub fn merge_all(&mut self, entities: impl IntoIterator<Item = Entity>) -> Imported {
+        self.merge_all_from(entities, None, &mut Union)
+    }
+
+    /// Like [`Collection::merge_all`], merging with the policy and recording `source` on any
+    /// updates, as with [`Collection::merge`].
+    pub fn merge_all_from(
+        &mut self,
+        entities: impl IntoIterator<Item = Entity>,
+        source: Option<String>,
+        policy: &mut dyn MergePolicy,
+    ) -> Imported {
+        let entities = entities.into_iter();
+        self.reserve(entities.size_hint().0);
+        let mut ret = Imported::default();
+        for entity in entities {
+            ret.entities += 1;
+            if self.contains(entity.url()) {
+                ret.merged += 1;
+            } else {
+                ret.added += 1;
+            }
+            self.merge(entity, source.clone(), policy);
+        }
+        ret
+    }
+
     pub fn upsert(&mut self, other: Entity) -> Id {
-        if let Some(id) = self.id(other.url()) {
-            let entity = &mut self.nodes[id];
-            entity.merge(other);
-            id
-        } else {
-            self.insert(other)
+        self.upsert_from(other, None)
+    }
+
+    /// Like [`Collection::upsert`], recording `source` on the update if the entity was already
+    /// present.
+    pub fn upsert_from(&mut self, other: Entity, source: Option<String>) -> Id {
+        self.merge(other, source, &mut Union)
+    }
+
+    /// Updates the entity with the given [`Id`], adding the given names and labels.
+    pub fn update(
+        &mut self,
+        id: Id,
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        mut labels: BTreeSet<Label>,
+    ) -> &Entity {
+        let id = self.checked(id);
+        self.apply_aliases(&mut labels);
+        self.nodes[id].update(updated_at, names, labels);
+        self.index(id);
+        &self.nodes[id]
+    }
+
+    /// Like [`Collection::update`], returning an error rather than panicking if there is no entity
+    /// with the given [`Id`].
+    pub fn try_update(
+        &mut self,
+        id: Id,
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        labels: BTreeSet<Label>,
+    ) -> Result<&Entity, Error> {
+        let id = self.check(id)?;
+        Ok(self.update(id, updated_at, names, labels))
+    }
+
+    /// Adds a name to the entity with the given [`Id`], without recording an update.
+    ///
+    /// Returns whether the name was new.
+    pub fn add_name(&mut self, id: Id, name: Name) -> bool {
+        let id = self.checked(id);
+        let added = self.nodes[id].names.insert(name.clone());
+        if added {
+            self.names.entry(name).or_default().insert(id);
+            self.text.insert(id, &self.nodes[id]);
+        }
+        added
+    }
+
+    /// Adds a note to the entity with the given [`Id`], among its notes in order of creation,
+    /// unless the entity already has an identical one.
+    ///
+    /// Returns whether the note was new.
+    pub fn add_note(&mut self, id: Id, note: Note) -> bool {
+        let id = self.checked(id);
+        let added = self.nodes[id].add_note(note);
+        if added {
+            self.text.insert(id, &self.nodes[id]);
+        }
+        added
+    }
+
+    /// Removes every note from the entity with the given [`Id`].
+    ///
+    /// Returns whether the entity had any notes.
+    pub fn clear_notes(&mut self, id: Id) -> bool {
+        let id = self.checked(id);
+        if self.nodes[id].notes.is_empty() {
+            return false;
+        }
+        self.nodes[id].clear_notes();
+        self.text.insert(id, &self.nodes[id]);
+        true
+    }
+
+    /// Removes the entity with the given [`Id`], along with any edges to or from it.
+    ///
+    /// The last entity in the collection is moved into the vacated slot, taking a new [`Id`] with
+    /// the index of `id`.  [`Id`]s held from before for either entity no longer match.
+    pub fn remove(&mut self, id: Id) -> Entity {
+        let id = self.checked(id);
+        let last = self.id_at(self.len() - 1);
+        self.unindex(id);
+        if id != last {
+            self.unindex(last);
+        }
+        self.clear_edges(id);
+        for source in std::mem::take(&mut self.incoming[id]) {
+            let index = self.edges[source].iter().position(|&to| to == id).expect("edge");
+            self.edges[source].remove(index);
+            self.kinds[source].remove(index);
+        }
+        let entity = self.nodes.swap_remove(id.index);
+        self.columns.swap_remove(id);
+        self.edges.swap_remove(id.index);
+        self.kinds.swap_remove(id.index);
+        self.incoming.swap_remove(id.index);
+        self.generations[id.index] = self.generations[id.index].wrapping_add(1);
+        let key = self.key(entity.url()).into_owned();
+        if self.urls.get(&key) == Some(&id) {
+            self.urls.remove(&key);
+        }
+        if id != last {
+            self.generations[last.index] = self.generations[last.index].wrapping_add(1);
+            let moved = self.id_at(id.index);
+            // Point the edges to and from the last entity at its new slot
+            let remap = |other: Id| if other == last { moved } else { other };
+            let sources: Edges = self.incoming[moved].iter().copied().map(remap).collect();
+            for &source in sources.iter() {
+                for to in self.edges[source].iter_mut().filter(|to| **to == last) {
+                    *to = moved;
+                }
+            }
+            self.incoming[moved] = sources;
+            self.incoming[moved].sort();
+            for to in self.edges[moved].clone() {
+                let incoming = &mut self.incoming[to];
+                for source in incoming.iter_mut().filter(|source| **source == last) {
+                    *source = moved;
+                }
+                incoming.sort();
+            }
+            let key = self.key(self.nodes[moved].url()).into_owned();
+            if self.urls.get(&key) == Some(&last) {
+                self.urls.insert(key, moved);
+            }
+            self.index(moved);
+        }
+        if (self.normalization.is_some() || self.url_filter.is_some())
+            && !self.urls.contains_key(&key)
+        {
+            // Another entity may have shared the removed entity's normalized URL
+            let other = (0..self.len())
+                .map(|index| self.id_at(index))
+                .find(|&other| self.key(self.nodes[other].url()).as_ref() == &key);
+            if let Some(other) = other {
+                self.urls.insert(key, other);
+            }
+        }
+        // Other entities may have shared the removed entity's aliases
+        for alias in entity.aliases.iter() {
+            self.restore_alias(alias);
+        }
+        entity
+    }
+
+    /// Like [`Collection::remove`], returning an error rather than panicking if there is no entity
+    /// with the given [`Id`].
+    pub fn try_remove(&mut self, id: Id) -> Result<Entity, Error> {
+        let id = self.check(id)?;
+        Ok(self.remove(id))
+    }
+
+    pub fn remove_by_url(&mut self, url: &Url) -> Option<Entity> {
+        let id = self.id(url)?;
+        Some(self.remove(id))
+    }
+
+    /// Keeps only the entities for which `keep` returns true, along with the edges between them.
+    ///
+    /// Unlike [`Collection::remove`], the kept entities stay in order.  Those that move to fill
+    /// the gaps get new [`Id`]s; the ids of those before the first entity dropped are unchanged.
+    pub fn retain(&mut self, mut keep: impl FnMut(Id, &Entity) -> bool) {
+        let keep: Vec<bool> = self.iter().map(|(id, entity)| keep(id, entity)).collect();
+        self.compact(&keep);
+    }
+
+    /// Removes the entities for which `extract` returns true, as with [`Collection::retain`], and
+    /// returns them, in order, in a collection of their own, along with the edges between them.
+    /// The new collection has the same label aliases and URL handling as this one.
+    pub fn extract_if(&mut self, mut extract: impl FnMut(Id, &Entity) -> bool) -> Collection {
+        let keep: Vec<bool> = self.iter().map(|(id, entity)| !extract(id, entity)).collect();
+        let extracted = self.compact(&keep);
+        let mut ret = Collection::with_capacity(extracted.len());
+        ret.aliases = self.aliases.clone();
+        ret.normalization = self.normalization;
+        ret.url_filter = self.url_filter.clone();
+        let mut ids = HashMap::with_capacity(extracted.len());
+        let mut extracted_edges = Vec::with_capacity(extracted.len());
+        for (index, entity, edges, kinds) in extracted {
+            ids.insert(index, ret.insert(entity));
+            extracted_edges.push((edges, kinds));
+        }
+        for (from, (edges, kinds)) in extracted_edges.into_iter().enumerate() {
+            let from = ret.id_at(from);
+            for (to, kind) in edges.into_iter().zip(kinds) {
+                if let Some(&to) = ids.get(&to.index) {
+                    ret.push_edge(from, to, kind);
+                }
+            }
+        }
+        ret
+    }
+
+    /// Returns a copy of the entities for which `keep` returns true, in order, in a collection of
+    /// their own, along with the edges between them, leaving this one as it is.  The new collection
+    /// has the same name, label aliases and URL handling as this one.
+    ///
+    /// Also returns the [`Id`] each kept entity has in the new collection, by its [`Id`] here.
+    pub fn subgraph(
+        &self,
+        mut keep: impl FnMut(Id, &Entity) -> bool,
+    ) -> (Collection, BTreeMap<Id, Id>) {
+        let mut ret = Collection::new();
+        ret.name = self.name.clone();
+        ret.aliases = self.aliases.clone();
+        ret.normalization = self.normalization;
+        ret.url_filter = self.url_filter.clone();
+        let mut ids = BTreeMap::new();
+        for (id, entity) in self.iter().filter(|(id, entity)| keep(*id, entity)) {
+            ids.insert(id, ret.insert(entity.clone()));
+        }
+        for (&from, &new) in &ids {
+            for (to, kind) in self.edges[from].iter().zip(&self.kinds[from]) {
+                if let Some(&to) = ids.get(to) {
+                    ret.push_edge(new, to, kind.clone());
+                }
+            }
+        }
+        (ret, ids)
+    }
+
+    /// Like [`Collection::subgraph`], keeping the entities carrying the label or one nested under
+    /// it.
+    pub fn subgraph_within(&self, label: &Label) -> (Collection, BTreeMap<Id, Id>) {
+        let label = self.resolve_label(label).clone();
+        self.subgraph(|_, entity| entity.labels.iter().any(|other| other.is_within(&label)))
+    }
+
+    /// Drops the entities whose slots are not marked in `keep`, moving the rest down in order, and
+    /// returns the dropped entities by their old slots, with their edges.
+    fn compact(&mut self, keep: &[bool]) -> Vec<(usize, Entity, Edges, Vec<EdgeKind>)> {
+        let mut slots = vec![None; keep.len()];
+        let mut old_indexes = Vec::with_capacity(keep.len());
+        for (index, _) in keep.iter().enumerate().filter(|(_, &keep)| keep) {
+            slots[index] = Some(old_indexes.len());
+            old_indexes.push(index);
+        }
+        if old_indexes.len() == keep.len() {
+            return Vec::new();
+        }
+        // A slot starts a new generation if its entity moves or goes
+        for (slot, generation) in self.generations.iter_mut().enumerate().take(keep.len()) {
+            if old_indexes.get(slot) != Some(&slot) {
+                *generation = generation.wrapping_add(1);
+            }
         }
+        let generations = &self.generations;
+        let moved =
+            |to: Id| slots[to.index].map(|slot| Id { index: slot, generation: generations[slot] });
+        let nodes = std::mem::take(&mut self.nodes).into_iter();
+        let rows = nodes.zip(std::mem::take(&mut self.edges)).zip(std::mem::take(&mut self.kinds));
+        let mut ret = Vec::with_capacity(keep.len() - old_indexes.len());
+        for (index, ((entity, edges), kinds)) in rows.enumerate() {
+            if slots[index].is_none() {
+                ret.push((index, entity, edges, kinds));
+                continue;
+            }
+            let (edges, kinds): (Edges, Vec<EdgeKind>) = edges
+                .into_iter()
+                .zip(kinds)
+                .filter_map(|(to, kind)| moved(to).map(|to| (to, kind)))
+                .unzip();
+            self.nodes.push(entity);
+            self.edges.push(edges);
+            self.kinds.push(kinds);
+        }
+        self.incoming = vec![Vec::new(); self.nodes.len()];
+        // Sources come out in ascending order, as the edges are visited in order of their sources
+        for (from, edges) in self.edges.iter().enumerate() {
+            for &to in edges {
+                self.incoming[to].push(Id { index: from, generation: self.generations[from] });
+            }
+        }
+        self.rebuild_urls();
+        self.reindex();
+        ret
     }
 
+    /// Merges the entity with [`Id`] `from` into the entity with [`Id`] `into`, then removes
+    /// `from`.
+    ///
+    /// Edges to or from `from` are moved to `into`.  As with [`Collection::remove`], the last
+    /// entity takes the vacated slot, so the returned [`Id`] of the merged entity may differ from
+    /// `into`.
+    ///
+    /// Panics if `into` and `from` are the same entity, or if either is not an entity of the
+    /// collection.
+    pub fn merge_entities(&mut self, into: Id, from: Id) -> Id {
+        self.try_merge_entities(into, from).unwrap_or_else(|err| panic!("{err}"))
+    }
+
+    /// Like [`Collection::merge_entities`], returning an error rather than panicking.
+    pub fn try_merge_entities(&mut self, into: Id, from: Id) -> Result<Id, Error> {
+        let (into, from) = (self.check(into)?, self.check(from)?);
+        if into == from {
+            return Err(Error::SelfMerge(into.index));
+        }
+        let last = self.id_at(self.len() - 1);
+        let outgoing: Vec<(Id, EdgeKind)> =
+            self.edges[from].iter().copied().zip(self.kinds[from].iter().cloned()).collect();
+        let incoming: Vec<(Id, EdgeKind)> = self.incoming[from]
+            .iter()
+            .map(|&source| (source, self.edge_kind(source, from).expect("edge").clone()))
+            .collect();
+        let entity = self.remove(from);
+        // Account for the last entity having moved into the vacated slot
+        let moved = self.id_at(from.index);
+        let remap = |id: Id| if id == last { moved } else { id };
+        let into = remap(into);
+        self.nodes[into].merge(entity);
+        self.index(into);
+        for (to, kind) in outgoing.into_iter().filter(|(to, _)| *to != from) {
+            let to = remap(to);
+            if to != into && !self.edges[into].contains(&to) {
+                self.push_edge(into, to, kind);
+            }
+        }
+        for (source, kind) in incoming.into_iter().filter(|(source, _)| *source != from) {
+            let source = remap(source);
+            if source != into && !self.edges[source].contains(&into) {
+                self.push_edge(source, into, kind);
+            }
+        }
+        Ok(into)
+    }
+
+    /// Changes the URL of the entity with the given [`Id`], keeping the old URL as an alias.
+    ///
+    /// If another entity already has the new URL, or has it as an alias, the entity is merged
+    /// into that one, as with
+    /// [`Collection::merge_entities`], and the merged entity's [`Id`] is returned.
+    pub fn rehome(&mut self, id: Id, url: Url) -> Id {
+        let id = self.checked(id);
+        let url = match &self.url_filter {
+            Some(filter) => filter.strip(&url),
+            None => url,
+        };
+        let old = self.nodes[id].url.clone();
+        if let Some(other) = self.id(&url).filter(|&other| other != id) {
+            let into = self.merge_entities(other, id);
+            self.add_alias(into, old);
+            return into;
+        }
+        self.remove_alias(id, &url);
+        let key = self.key(&url).into_owned();
+        let old_key = self.key(&old).into_owned();
+        if self.urls.get(&old_key) == Some(&id) {
+            self.urls.remove(&old_key);
+            // Another entity may share the old normalized URL
+            let other = (0..self.len()).map(|index| self.id_at(index)).find(|&other| {
+                other != id && self.key(self.nodes[other].url()).as_ref() == &old_key
+            });
+            if let Some(other) = other {
+                self.urls.insert(old_key, other);
+            }
+        }
+        self.urls.insert(key, id);
+        self.nodes[id].url = url;
+        self.add_alias(id, old);
+        id
+    }
+
+    /// Adds an edge of the default [`EdgeKind`], unless there is already an edge between the two.
     pub fn add_edge(&mut self, from: Id, to: Id) {
-        let from_edges = &mut self.edges[from];
-        if from_edges.contains(&to) {
+        let (from, to) = (self.checked(from), self.checked(to));
+        if self.edges[from].contains(&to) {
             return;
         }
-        from_edges.push(to);
+        self.push_edge(from, to, EdgeKind::default());
+    }
+
+    /// Like [`Collection::add_edge`], returning an error rather than panicking if either end is
+    /// not an entity of the collection.
+    pub fn try_add_edge(&mut self, from: Id, to: Id) -> Result<(), Error> {
+        let (from, to) = (self.check(from)?, self.check(to)?);
+        self.add_edge(from, to);
+        Ok(())
+    }
+
+    /// Adds an edge of the given kind, or changes the kind of an existing edge.
+    pub fn add_edge_with_kind(&mut self, from: Id, to: Id, kind: EdgeKind) {
+        let (from, to) = (self.checked(from), self.checked(to));
+        match self.edges[from].iter().position(|&other| other == to) {
+            Some(index) => self.kinds[from][index] = kind,
+            None => self.push_edge(from, to, kind),
+        }
+    }
+
+    /// Like [`Collection::add_edge_with_kind`], returning an error rather than panicking if either
+    /// end is not an entity of the collection.
+    pub fn try_add_edge_with_kind(
+        &mut self,
+        from: Id,
+        to: Id,
+        kind: EdgeKind,
+    ) -> Result<(), Error> {
+        let (from, to) = (self.check(from)?, self.check(to)?);
+        self.add_edge_with_kind(from, to, kind);
+        Ok(())
+    }
+
+    /// Removes the edge from `from` to `to`, returning its kind, or [`None`] if there was no such
+    /// edge.
+    pub fn remove_edge(&mut self, from: Id, to: Id) -> Option<EdgeKind> {
+        let (from, to) = (self.checked(from), self.checked(to));
+        let index = self.edges[from].iter().position(|&other| other == to)?;
+        self.edges[from].remove(index);
+        self.unlink(from, to);
+        Some(self.kinds[from].remove(index))
+    }
+
+    /// Like [`Collection::remove_edge`], returning an error rather than panicking if either end is
+    /// not an entity of the collection.
+    pub fn try_remove_edge(&mut self, from: Id, to: Id) -> Result<Option<EdgeKind>, Error> {
+        let (from, to) = (self.check(from)?, self.check(to)?);
+        Ok(self.remove_edge(from, to))
+    }
+
+    /// Removes every edge from the given entity, leaving edges to it in place.
+    pub fn clear_edges(&mut self, id: Id) {
+        let id = self.checked(id);
+        for to in std::mem::take(&mut self.edges[id]) {
+            self.unlink(id, to);
+        }
+        self.kinds[id].clear();
+    }
+
+    fn push_edge(&mut self, from: Id, to: Id, kind: EdgeKind) {
+        self.edges[from].push(to);
+        self.kinds[from].push(kind);
+        let incoming = &mut self.incoming[to];
+        if let Err(index) = incoming.binary_search(&from) {
+            incoming.insert(index, from);
+        }
+    }
+
+    /// Removes `from` from the sources of the edges to `to`.
+    fn unlink(&mut self, from: Id, to: Id) {
+        let incoming = &mut self.incoming[to];
+        if let Ok(index) = incoming.binary_search(&from) {
+            incoming.remove(index);
+        }
     }
 
     pub fn add_edges(&mut self, from: Id, to: Id) {
@@ -410,22 +1894,301 @@ impl Collection {
         self.add_edge(to, from)
     }
 
+    /// Adds edges of the default [`EdgeKind`] in both directions between `a` and `b`.
+    pub fn add_undirected_edge(&mut self, a: Id, b: Id) {
+        self.add_undirected_edge_with_kind(a, b, EdgeKind::default())
+    }
+
+    /// Adds edges of the given kind in both directions between `a` and `b`, changing the kind of
+    /// either edge if it already exists.
+    pub fn add_undirected_edge_with_kind(&mut self, a: Id, b: Id, kind: EdgeKind) {
+        self.add_edge_with_kind(a, b, kind.clone());
+        self.add_edge_with_kind(b, a, kind)
+    }
+
+    /// Returns whether there are edges of the same kind in both directions between `a` and `b`.
+    pub fn is_undirected_edge(&self, a: Id, b: Id) -> bool {
+        match (self.edge_kind(a, b), self.edge_kind(b, a)) {
+            (Some(there), Some(back)) => there == back,
+            _ => false,
+        }
+    }
+
+    /// Returns the [`Id`]s of entities with an edge to or from the given entity, in ascending
+    /// order, treating every edge as undirected.
+    pub fn neighbors(&self, id: Id) -> impl Iterator<Item = Id> + '_ {
+        let id = self.checked(id);
+        let mut ret: BTreeSet<Id> = self.edges[id].iter().copied().collect();
+        ret.extend(self.incoming[id].iter().copied());
+        ret.remove(&id);
+        ret.into_iter()
+    }
+
+    /// Returns the entity with the given [`Id`].  Panics if there is none, as when the [`Id`] is
+    /// from before a removal; see [`Collection::get`] and [`Collection::try_entity`].
     pub fn entity(&self, id: Id) -> &Entity {
-        &self.nodes[id]
+        &self.nodes[self.checked(id)]
+    }
+
+    /// Returns the entity with the given [`Id`], or [`None`] if it has been removed or replaced.
+    pub fn get(&self, id: Id) -> Option<&Entity> {
+        self.check(id).ok().map(|id| &self.nodes[id])
+    }
+
+    pub fn get_mut(&mut self, id: Id) -> Option<&mut Entity> {
+        let id = self.check(id).ok()?;
+        Some(&mut self.nodes[id])
+    }
+
+    /// Like [`Collection::entity`], returning an error rather than panicking if there is no entity
+    /// with the given [`Id`].
+    pub fn try_entity(&self, id: Id) -> Result<&Entity, Error> {
+        Ok(&self.nodes[self.check(id)?])
+    }
+
+    /// Returns the [`Id`]s of all entities with the given name, in ascending order.
+    pub fn by_name(&self, name: &str) -> impl Iterator<Item = Id> + '_ {
+        self.names.get(name).into_iter().flatten().copied()
+    }
+
+    /// Returns the [`Id`]s of all entities carrying the given [`Label`], in ascending order.
+    pub fn by_label(&self, label: &Label) -> impl Iterator<Item = Id> + '_ {
+        self.labels.get(label).into_iter().flatten().copied()
+    }
+
+    /// Returns the [`Id`]s of all entities carrying the given [`Label`] or one of its descendants,
+    /// in ascending order.
+    pub fn by_label_within(&self, label: &Label) -> impl Iterator<Item = Id> + '_ {
+        let mut ret = BTreeSet::new();
+        for (candidate, ids) in &self.labels {
+            if candidate.is_within(label) {
+                ret.extend(ids);
+            }
+        }
+        ret.into_iter()
+    }
+
+    /// Returns the [`Id`]s of all entities whose URL, names or labels contain the given query,
+    /// ignoring case.
+    pub fn search<'a>(&'a self, query: &str) -> impl Iterator<Item = Id> + 'a {
+        let query = query.to_lowercase();
+        self.iter().filter(move |(_, entity)| entity.matches(&query)).map(|(id, _)| id)
+    }
+
+    /// Returns the [`Id`]s of all entities whose URL, names or labels match the given regular
+    /// expression, in ascending order.
+    #[cfg(feature = "regex")]
+    pub fn grep<'a>(&'a self, regex: &'a Regex) -> impl Iterator<Item = Id> + 'a {
+        self.iter()
+            .filter(|(_, entity)| {
+                regex.is_match(entity.url.as_str())
+                    || entity.names.iter().any(|name| regex.is_match(name.as_str()))
+                    || entity.labels.iter().any(|label| regex.is_match(label.as_str()))
+            })
+            .map(|(id, _)| id)
+    }
+
+    /// Returns the [`Id`]s of starred entities, in ascending order.
+    pub fn starred(&self) -> Vec<Id> {
+        self.iter().filter(|(_, entity)| entity.starred).map(|(id, _)| id).collect()
+    }
+
+    /// Returns the [`Id`]s of entities parsed from the given source file, in ascending order.
+    pub fn by_source<'a>(&'a self, path: &'a Path) -> impl Iterator<Item = Id> + 'a {
+        self.iter()
+            .filter(move |(_, entity)| entity.sources.iter().any(|location| location.path == path))
+            .map(|(id, _)| id)
+    }
+
+    /// Returns the [`Id`]s of all entities, starred ones first, otherwise in ascending order.
+    pub fn starred_first(&self) -> Vec<Id> {
+        let mut ret: Vec<Id> = self.iter().map(|(id, _)| id).collect();
+        ret.sort_by_key(|&id| !self.nodes[id].starred);
+        ret
+    }
+
+    /// Returns the [`Id`]s of unread entities, oldest first.
+    pub fn unread(&self) -> Vec<Id> {
+        let mut ret: Vec<Id> = self
+            .iter()
+            .filter(|(_, entity)| entity.read_status.is_unread())
+            .map(|(id, _)| id)
+            .collect();
+        ret.sort_by_key(|&id| (self.columns.created_at()[id.index], id));
+        ret
+    }
+
+    /// Returns the [`Id`]s of the entities created from `start` up to but not including `end`,
+    /// oldest first.
+    pub fn created_between(
+        &self,
+        start: Time,
+        end: Time,
+    ) -> impl DoubleEndedIterator<Item = Id> + '_ {
+        self.columns.created_within(start..end.max(start)).map(|index| self.id_at(index))
+    }
+
+    /// Returns the [`Id`]s of the entities last updated at or after `since`, least recently
+    /// updated first.  Entities that were never updated are left out.
+    pub fn updated_since(&self, since: Time) -> impl DoubleEndedIterator<Item = Id> + '_ {
+        self.columns.updated_within(since..).map(|index| self.id_at(index))
     }
 
     pub fn entity_mut(&mut self, id: Id) -> &mut Entity {
+        let id = self.checked(id);
         &mut self.nodes[id]
     }
 
+    /// Like [`Collection::entity_mut`], returning an error rather than panicking if there is no
+    /// entity with the given [`Id`].
+    pub fn try_entity_mut(&mut self, id: Id) -> Result<&mut Entity, Error> {
+        let id = self.check(id)?;
+        Ok(&mut self.nodes[id])
+    }
+
     pub fn edges(&self, id: Id) -> &[Id] {
-        &self.edges[id]
+        &self.edges[self.checked(id)]
+    }
+
+    /// Like [`Collection::edges`], returning an error rather than panicking if there is no entity
+    /// with the given [`Id`].
+    pub fn try_edges(&self, id: Id) -> Result<&[Id], Error> {
+        Ok(&self.edges[self.check(id)?])
+    }
+
+    /// Returns the [`Id`]s of entities with an edge to the given entity, in ascending order.
+    pub fn incoming(&self, id: Id) -> &[Id] {
+        &self.incoming[self.checked(id)]
+    }
+
+    /// Returns the kinds of the edges from the given entity, in the same order as
+    /// [`Collection::edges`].
+    pub fn edge_kinds(&self, id: Id) -> &[EdgeKind] {
+        &self.kinds[self.checked(id)]
+    }
+
+    pub fn edge_kind(&self, from: Id, to: Id) -> Option<&EdgeKind> {
+        let (from, to) = (self.checked(from), self.checked(to));
+        let index = self.edges[from].iter().position(|&other| other == to)?;
+        Some(&self.kinds[from][index])
+    }
+
+    /// Returns the [`Id`]s that the given entity has edges of the given kind to.
+    pub fn edges_of_kind<'a>(
+        &'a self,
+        id: Id,
+        kind: &'a EdgeKind,
+    ) -> impl Iterator<Item = Id> + 'a {
+        let id = self.checked(id);
+        self.edges[id]
+            .iter()
+            .zip(self.kinds[id].iter())
+            .filter(move |(_, other)| *other == kind)
+            .map(|(to, _)| *to)
     }
 
     pub fn entities(&self) -> &[Entity] {
         &self.nodes
     }
 
+    pub fn iter(&self) -> Iter<'_> {
+        Iter(self.nodes.iter().zip(self.generations.iter()).enumerate())
+    }
+
+    pub fn iter_mut(&mut self) -> IterMut<'_> {
+        IterMut(self.nodes.iter_mut().zip(self.generations.iter()).enumerate())
+    }
+
+    /// Returns an iterator over all edges in the collection as `(from, to)` pairs.
+    pub fn edges_iter(&self) -> impl Iterator<Item = (Id, Id)> + '_ {
+        self.edges
+            .iter()
+            .enumerate()
+            .flat_map(move |(from, edges)| edges.iter().map(move |to| (self.id_at(from), *to)))
+    }
+
+    /// Returns an iterator over all edges in the collection as `(from, to, kind)` triples.
+    pub fn edges_with_kinds(&self) -> impl Iterator<Item = (Id, Id, &EdgeKind)> + '_ {
+        self.edges.iter().zip(self.kinds.iter()).enumerate().flat_map(
+            move |(from, (edges, kinds))| {
+                edges.iter().zip(kinds.iter()).map(move |(to, kind)| (self.id_at(from), *to, kind))
+            },
+        )
+    }
+
+    /// Renames the label `old` to `new` on every entity carrying it, returning how many entities
+    /// were touched.
+    ///
+    /// Entities that already carry `new` simply lose `old`.
+    pub fn rename_label(&mut self, old: &Label, new: Label) -> usize {
+        if *old == new {
+            return self.by_label(old).count();
+        }
+        let Some(ids) = self.labels.remove(old) else {
+            return 0;
+        };
+        for &id in ids.iter() {
+            let labels = self.nodes[id].labels_mut();
+            labels.remove(old);
+            labels.insert(new.clone());
+            self.text.insert(id, &self.nodes[id]);
+            self.columns.set(id, &self.nodes[id]);
+        }
+        let count = ids.len();
+        self.labels.entry(new).or_default().extend(ids);
+        count
+    }
+
+    /// Folds the label `from` into `into` across the collection, and records `from` as an alias of
+    /// `into`, so that entities added or updated later with `from` get `into` instead.
+    ///
+    /// Returns how many entities were touched.
+    pub fn merge_label(&mut self, from: &Label, into: Label) -> usize {
+        let into = self.resolve_label(&into).clone();
+        if *from == into {
+            return 0;
+        }
+        self.add_label_alias(from.clone(), into.clone());
+        self.rename_label(from, into)
+    }
+
+    /// Records `alias` as standing for `target` in entities added or updated from now on.
+    ///
+    /// Aliases are followed, so that `target` may itself be an alias, and existing aliases of
+    /// `alias` are pointed at `target`.  Returns `false`, recording nothing, if `target` resolves
+    /// to `alias`.
+    pub fn add_label_alias(&mut self, alias: Label, target: Label) -> bool {
+        let target = self.resolve_label(&target).clone();
+        if target == alias {
+            return false;
+        }
+        for other in self.aliases.values_mut().filter(|other| **other == alias) {
+            *other = target.clone();
+        }
+        self.aliases.insert(alias, target);
+        true
+    }
+
+    pub fn remove_label_alias(&mut self, alias: &Label) -> Option<Label> {
+        self.aliases.remove(alias)
+    }
+
+    pub fn label_aliases(&self) -> &BTreeMap<Label, Label> {
+        &self.aliases
+    }
+
+    /// Returns the label that `label` stands for, which is `label` itself unless it is an alias.
+    pub fn resolve_label<'a>(&'a self, label: &'a Label) -> &'a Label {
+        self.aliases.get(label).unwrap_or(label)
+    }
+
+    fn apply_aliases(&self, labels: &mut BTreeSet<Label>) {
+        if self.aliases.is_empty() || !labels.iter().any(|label| self.aliases.contains_key(label)) {
+            return;
+        }
+        *labels = labels.iter().map(|label| self.resolve_label(label).clone()).collect();
+    }
+
     pub fn update_labels(&mut self, json: Value) -> Result<(), Error> {
         let mapping = json_to_map(json)?;
         for node in self.nodes.iter_mut() {
@@ -435,8 +2198,155 @@ impl Collection {
             labels.retain(|label| !mapping.contains_key(label));
             labels.extend(to_add);
         }
+        self.reindex();
         Ok(())
     }
+
+    fn index_aliases(&mut self, id: Id) {
+        for alias in self.nodes[id].aliases.iter() {
+            let key = self.key(alias).into_owned();
+            self.url_aliases.entry(key).or_insert(id);
+        }
+    }
+
+    fn unindex_aliases(&mut self, id: Id) {
+        for alias in self.nodes[id].aliases.iter() {
+            let key = self.key(alias);
+            if self.url_aliases.get(&key) == Some(&id) {
+                self.url_aliases.remove(&key);
+            }
+        }
+    }
+
+    fn index(&mut self, id: Id) {
+        self.index_aliases(id);
+        self.stable_ids.insert(self.nodes[id].stable_id(), id);
+        for name in self.nodes[id].names.iter() {
+            self.names.entry(name.clone()).or_default().insert(id);
+        }
+        for label in self.nodes[id].labels.iter() {
+            self.labels.entry(label.clone()).or_default().insert(id);
+        }
+        self.text.insert(id, &self.nodes[id]);
+        self.columns.set(id, &self.nodes[id]);
+    }
+
+    fn unindex(&mut self, id: Id) {
+        self.unindex_aliases(id);
+        let stable_id = self.nodes[id].stable_id();
+        if self.stable_ids.get(&stable_id) == Some(&id) {
+            self.stable_ids.remove(&stable_id);
+        }
+        for name in self.nodes[id].names.iter() {
+            if let Some(ids) = self.names.get_mut(name) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.names.remove(name);
+                }
+            }
+        }
+        for label in self.nodes[id].labels.iter() {
+            if let Some(ids) = self.labels.get_mut(label) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.labels.remove(label);
+                }
+            }
+        }
+        self.text.remove(id);
+    }
+
+    fn reindex(&mut self) {
+        self.url_aliases.clear();
+        self.stable_ids.clear();
+        self.names.clear();
+        self.labels.clear();
+        self.text.clear();
+        self.columns.clear();
+        for index in 0..self.nodes.len() {
+            self.index(self.id_at(index));
+        }
+    }
+}
+
+/// An iterator over the entities of a [`Collection`] and their [`Id`]s.
+pub struct Iter<'a>(iter::Enumerate<iter::Zip<slice::Iter<'a, Entity>, slice::Iter<'a, u32>>>);
+
+impl<'a> Iterator for Iter<'a> {
+    type Item = (Id, &'a Entity);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(index, (entity, &generation))| (Id { index, generation }, entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for Iter<'_> {}
+
+/// A mutable iterator over the entities of a [`Collection`] and their [`Id`]s.
+pub struct IterMut<'a>(
+    iter::Enumerate<iter::Zip<slice::IterMut<'a, Entity>, slice::Iter<'a, u32>>>,
+);
+
+impl<'a> Iterator for IterMut<'a> {
+    type Item = (Id, &'a mut Entity);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(index, (entity, &generation))| (Id { index, generation }, entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IterMut<'_> {}
+
+/// An owning iterator over the entities of a [`Collection`] and their [`Id`]s.
+pub struct IntoIter(iter::Enumerate<iter::Zip<vec::IntoIter<Entity>, vec::IntoIter<u32>>>);
+
+impl Iterator for IntoIter {
+    type Item = (Id, Entity);
+
+    fn next(&mut self) -> Option<Self::Item> {
+        self.0.next().map(|(index, (entity, generation))| (Id { index, generation }, entity))
+    }
+
+    fn size_hint(&self) -> (usize, Option<usize>) {
+        self.0.size_hint()
+    }
+}
+
+impl ExactSizeIterator for IntoIter {}
+
+impl<'a> IntoIterator for &'a Collection {
+    type Item = (Id, &'a Entity);
+    type IntoIter = Iter<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter()
+    }
+}
+
+impl<'a> IntoIterator for &'a mut Collection {
+    type Item = (Id, &'a mut Entity);
+    type IntoIter = IterMut<'a>;
+
+    fn into_iter(self) -> Self::IntoIter {
+        self.iter_mut()
+    }
+}
+
+impl IntoIterator for Collection {
+    type Item = (Id, Entity);
+    type IntoIter = IntoIter;
+
+    fn into_iter(self) -> Self::IntoIter {
+        IntoIter(self.nodes.into_iter().zip(self.generations).enumerate())
+    }
 }
 
 fn json_to_map(json: Value) -> Result<BTreeMap<Label, Label>, Error> {
@@ -459,12 +2369,19 @@ struct SerializedNode {
     id: Id,
     entity: Entity,
     edges: Vec<Id>,
+    /// Parallel to `edges`, and omitted when every edge is of the default kind.
+    #[serde(default, skip_serializing_if = "Vec::is_empty")]
+    kinds: Vec<EdgeKind>,
 }
 
 #[derive(Debug, Serialize, Deserialize)]
 #[serde(rename_all = "camelCase")]
 struct SerializedCollection {
     version: Version,
+    #[serde(default, skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(default, skip_serializing_if = "BTreeMap::is_empty")]
+    label_aliases: BTreeMap<Label, Label>,
     length: usize,
     value: Vec<SerializedNode>,
 }
@@ -473,18 +2390,28 @@ impl From<&Collection> for SerializedCollection {
     fn from(collection: &Collection) -> SerializedCollection {
         let version = Version::EXPECTED;
 
+        let name = collection.name.clone();
+
+        let label_aliases = collection.aliases.clone();
+
         let length = collection.len();
 
         let value: Vec<_> = (0..length)
             .map(|i| {
-                let id = Id::new(i);
+                let id = collection.id_at(i);
                 let entity = collection.entity(id).clone();
                 let edges = collection.edges(id).to_vec();
-                SerializedNode { id, entity, edges }
+                let kinds = collection.edge_kinds(id);
+                let kinds = if kinds.iter().all(EdgeKind::is_default) {
+                    Vec::new()
+                } else {
+                    kinds.to_vec()
+                };
+                SerializedNode { id, entity, edges, kinds }
             })
             .collect();
 
-        SerializedCollection { version, length, value }
+        SerializedCollection { version, name, label_aliases, length, value }
     }
 }
 
@@ -492,7 +2419,8 @@ impl TryFrom<SerializedCollection> for Collection {
     type Error = Error;
 
     fn try_from(serialized_collection: SerializedCollection) -> Result<Collection, Self::Error> {
-        let SerializedCollection { version, length, mut value } = serialized_collection;
+        let SerializedCollection { version, name, label_aliases, length, mut value } =
+            serialized_collection;
 
         let is_compatible_version = version.matches_requirement()?;
 
@@ -504,15 +2432,57 @@ impl TryFrom<SerializedCollection> for Collection {
         }
 
         let mut ret = Collection::with_capacity(length);
+        ret.name = name;
+        ret.aliases = label_aliases;
 
         value.sort();
 
-        for SerializedNode { id, entity, edges } in value {
-            assert_eq!(id.0, ret.len());
-            let url = entity.url.clone();
+        let length = value.len();
+
+        for SerializedNode { id, entity, edges, mut kinds } in value {
+            if id.index != ret.len() {
+                return Err(Error::InvalidId(id.index, ret.len()));
+            }
+            if let Some(to) = edges.iter().find(|to| to.index >= length) {
+                return Err(Error::InvalidEdge(id.index, to.index));
+            }
+            if kinds.is_empty() {
+                kinds = vec![EdgeKind::default(); edges.len()];
+            } else if kinds.len() != edges.len() {
+                return Err(Error::InvalidEdgeKinds(id.index, kinds.len(), edges.len()));
+            }
+            if ret.urls.contains_key(&entity.url) {
+                return Err(Error::DuplicateUrl(entity.url.to_string()));
+            }
+            ret.urls.insert(entity.url.clone(), id);
             ret.nodes.push(entity);
+            ret.generations.push(0);
             ret.edges.push(edges);
-            ret.urls.insert(url, id);
+            ret.kinds.push(kinds);
+            ret.incoming.push(Vec::new());
+        }
+
+        // Saved stable ids are claimed before any are derived for entities saved without one
+        for saved in [true, false] {
+            for index in 0..ret.len() {
+                let id = ret.id_at(index);
+                if ret.nodes[id].stable_id.is_some() == saved {
+                    ret.claim_stable_id(id);
+                }
+            }
+        }
+        for index in 0..ret.len() {
+            ret.index(ret.id_at(index));
+        }
+
+        // Sources come out in ascending order, as the nodes were sorted by id
+        for (from, edges) in ret.edges.iter().enumerate() {
+            for &to in edges {
+                let incoming = &mut ret.incoming[to];
+                if incoming.last() != Some(&Id::new(from)) {
+                    incoming.push(Id::new(from));
+                }
+            }
         }
 
         Ok(ret)
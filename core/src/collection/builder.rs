@@ -0,0 +1,97 @@
+//! Building an [`Entity`] a field at a time.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use url::Url;
+
+use super::{Collection, Entity, Id, Label, Name, Note, ReadStatus, Time};
+
+/// Builds an [`Entity`], created by [`Entity::builder`].
+///
+/// Fields that are not set keep the defaults of [`Entity::new`], except for the creation time,
+/// which defaults to when the builder was created.
+#[derive(Debug, Clone)]
+pub struct EntityBuilder {
+    entity: Entity,
+}
+
+impl EntityBuilder {
+    pub fn created(mut self, created_at: impl Into<Time>) -> EntityBuilder {
+        self.entity.created_at = created_at.into();
+        self
+    }
+
+    /// Adds a name.  May be called more than once.
+    pub fn name(mut self, name: impl Into<String>) -> EntityBuilder {
+        self.entity.names.insert(Name::new(name.into()));
+        self
+    }
+
+    /// Adds a label.  May be called more than once.
+    pub fn label(mut self, label: impl Into<Label>) -> EntityBuilder {
+        self.entity.labels.insert(label.into());
+        self
+    }
+
+    pub fn labels<L: Into<Label>>(mut self, labels: impl IntoIterator<Item = L>) -> EntityBuilder {
+        self.entity.labels.extend(labels.into_iter().map(Into::into));
+        self
+    }
+
+    pub fn note(mut self, note: Note) -> EntityBuilder {
+        self.entity.add_note(note);
+        self
+    }
+
+    pub fn shared(mut self, shared: bool) -> EntityBuilder {
+        self.entity.shared = shared;
+        self
+    }
+
+    pub fn toread(mut self, toread: bool) -> EntityBuilder {
+        self.entity.toread = toread;
+        self
+    }
+
+    pub fn starred(mut self, starred: bool) -> EntityBuilder {
+        self.entity.starred = starred;
+        self
+    }
+
+    pub fn read_status(mut self, read_status: ReadStatus) -> EntityBuilder {
+        self.entity.read_status = read_status;
+        self
+    }
+
+    /// Adds an alias, another URL the entity is found at.  May be called more than once.
+    pub fn alias(mut self, url: Url) -> EntityBuilder {
+        self.entity.add_alias(url);
+        self
+    }
+
+    pub fn metadata(mut self, key: impl Into<String>, value: impl Into<String>) -> EntityBuilder {
+        self.entity.metadata.insert(key.into(), value.into());
+        self
+    }
+
+    pub fn build(self) -> Entity {
+        self.entity
+    }
+}
+
+impl Entity {
+    /// Returns a builder for an entity with the given URL.
+    pub fn builder(url: Url) -> EntityBuilder {
+        EntityBuilder { entity: Entity::new(url, Time::now(), None, BTreeSet::new()) }
+    }
+}
+
+impl Collection {
+    /// Builds an entity with the given URL and upserts it, as with [`Collection::upsert`].
+    pub fn add_with(&mut self, url: Url, build: impl FnOnce(EntityBuilder) -> EntityBuilder) -> Id {
+        self.upsert(build(Entity::builder(url)).build())
+    }
+}
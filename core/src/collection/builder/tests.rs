@@ -0,0 +1,55 @@
+use time::macros::datetime;
+
+use super::*;
+
+fn url(s: &str) -> Url {
+    Url::parse(s).unwrap()
+}
+
+#[test]
+fn test_builder() {
+    let entity = Entity::builder(url("https://example.com/"))
+        .name("Example")
+        .label("foo")
+        .label(String::from("bar"))
+        .labels(["baz", "foo"])
+        .created(datetime!(2024-01-15 0:00 UTC))
+        .starred(true)
+        .metadata("source", "test")
+        .build();
+    let expected = Entity::new(
+        url("https://example.com/"),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        Some(Name::from("Example")),
+        ["bar", "baz", "foo"].into_iter().map(Label::from).collect(),
+    );
+    assert_eq!(entity.url(), expected.url());
+    assert_eq!(entity.created_at(), expected.created_at());
+    assert_eq!(entity.names(), expected.names());
+    assert_eq!(entity.labels(), expected.labels());
+    assert!(entity.starred());
+    assert_eq!(entity.metadata_value("source"), Some("test"));
+}
+
+#[test]
+fn test_builder_defaults() {
+    let before = Time::now();
+    let entity = Entity::builder(url("https://example.com/")).build();
+    assert!(before <= *entity.created_at() && *entity.created_at() <= Time::now());
+    assert!(entity.names().is_empty());
+    assert!(entity.labels().is_empty());
+    assert!(!entity.shared() && !entity.toread() && !entity.starred());
+    assert!(entity.read_status().is_unread());
+}
+
+#[test]
+fn test_add_with() {
+    let mut collection = Collection::new();
+    let created = datetime!(2024-01-15 0:00 UTC);
+    let id = collection.add_with(url("https://example.com/"), |b| b.name("A").created(created));
+    let again = collection.add_with(url("https://example.com/"), |b| b.label("x").created(created));
+    assert_eq!(id, again);
+    assert_eq!(collection.len(), 1);
+    assert_eq!(collection.by_name("A").collect::<Vec<_>>(), vec![id]);
+    assert_eq!(collection.by_label(&Label::from("x")).collect::<Vec<_>>(), vec![id]);
+}
@@ -0,0 +1,86 @@
+//! How central the entities of a [`Collection`] are to its edges.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Collection, Id};
+
+/// The metadata key under which [`Collection::record_scores`] is usually given PageRank scores.
+pub const PAGERANK_KEY: &str = "pagerank";
+
+/// The metadata key under which [`Collection::record_scores`] is usually given degree centrality.
+pub const DEGREE_KEY: &str = "degree_centrality";
+
+/// Settings for [`Collection::pagerank`].
+#[derive(Debug, Clone, Copy, PartialEq)]
+pub struct PageRankOptions {
+    /// The chance of following an edge rather than jumping to any entity.
+    pub damping: f64,
+    pub max_iterations: usize,
+    /// Iteration stops once no score changes by more than this.
+    pub tolerance: f64,
+}
+
+impl Default for PageRankOptions {
+    fn default() -> PageRankOptions {
+        PageRankOptions { damping: 0.85, max_iterations: 100, tolerance: 1e-9 }
+    }
+}
+
+/// Orders scores from highest, ties broken by [`Id`].
+fn ranked(scores: Vec<f64>, collection: &Collection) -> Vec<(Id, f64)> {
+    let mut ret: Vec<(Id, f64)> =
+        scores.into_iter().enumerate().map(|(i, score)| (collection.id_at(i), score)).collect();
+    ret.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+    ret
+}
+
+impl Collection {
+    /// Returns the PageRank of every entity, highest first, ties broken by [`Id`].  The scores sum
+    /// to 1.
+    ///
+    /// Entities without edges of their own pass their score on to every entity evenly.
+    pub fn pagerank(&self, options: &PageRankOptions) -> Vec<(Id, f64)> {
+        let n = self.len();
+        if n == 0 {
+            return Vec::new();
+        }
+        let uniform = 1.0 / n as f64;
+        let mut scores = vec![uniform; n];
+        for _ in 0..options.max_iterations {
+            let dangling: f64 =
+                (0..n).filter(|&i| self.edges[i].is_empty()).map(|i| scores[i]).sum();
+            let base = (1.0 - options.damping + options.damping * dangling) * uniform;
+            let mut next = vec![base; n];
+            for (i, edges) in self.edges.iter().enumerate() {
+                let share = options.damping * scores[i] / edges.len() as f64;
+                for to in edges.iter() {
+                    next[to.index] += share;
+                }
+            }
+            let delta = scores.iter().zip(&next).map(|(a, b)| (a - b).abs()).fold(0.0, f64::max);
+            scores = next;
+            if delta <= options.tolerance {
+                break;
+            }
+        }
+        ranked(scores, self)
+    }
+
+    /// Returns the degree centrality of every entity, highest first, ties broken by [`Id`]: the
+    /// number of edges to and from it over twice the number of other entities, from 0 to 1.
+    pub fn degree_centrality(&self) -> Vec<(Id, f64)> {
+        let others = 2.0 * self.len().saturating_sub(1).max(1) as f64;
+        let scores = (0..self.len())
+            .map(|i| (self.edges[i].len() + self.incoming[i].len()) as f64 / others)
+            .collect();
+        ranked(scores, self)
+    }
+
+    /// Records each score in its entity's metadata under the key.
+    pub fn record_scores(&mut self, key: &str, scores: &[(Id, f64)]) {
+        for &(id, score) in scores {
+            self.entity_mut(id).set_metadata(key.to_string(), format!("{:.6}", score));
+        }
+    }
+}
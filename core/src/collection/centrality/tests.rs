@@ -0,0 +1,72 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+/// Creates a collection of `n` entities with the given edges.
+fn create_graph(n: usize, edges: &[(usize, usize)]) -> Collection {
+    let mut collection = Collection::new();
+    for i in 0..n {
+        let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+        collection.insert(Entity::new(
+            url,
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            BTreeSet::new(),
+        ));
+    }
+    for &(from, to) in edges {
+        collection.add_edge(Id::new(from), Id::new(to));
+    }
+    collection
+}
+
+fn order(scores: &[(Id, f64)]) -> Vec<usize> {
+    scores.iter().map(|(id, _)| usize::from(*id)).collect()
+}
+
+#[test]
+fn test_pagerank() {
+    assert!(Collection::new().pagerank(&PageRankOptions::default()).is_empty());
+
+    // Everything points to 0, which points on to 1
+    let collection = create_graph(4, &[(1, 0), (2, 0), (3, 0), (0, 1)]);
+    let scores = collection.pagerank(&PageRankOptions::default());
+    assert_eq!(order(&scores), vec![0, 1, 2, 3]);
+    let sum: f64 = scores.iter().map(|(_, score)| score).sum();
+    assert!((sum - 1.0).abs() < 1e-9, "{}", sum);
+    assert!((scores[2].1 - scores[3].1).abs() < 1e-12);
+
+    // A cycle shares its score evenly, as does a graph without edges
+    for collection in [create_graph(3, &[(0, 1), (1, 2), (2, 0)]), create_graph(3, &[])] {
+        let scores = collection.pagerank(&PageRankOptions::default());
+        assert!(scores.iter().all(|(_, score)| (score - 1.0 / 3.0).abs() < 1e-9), "{:?}", scores);
+    }
+}
+
+#[test]
+fn test_degree_centrality() {
+    let collection = create_graph(4, &[(1, 0), (2, 0), (3, 0), (0, 1)]);
+    let scores = collection.degree_centrality();
+    assert_eq!(
+        scores,
+        vec![
+            (Id::new(0), 4.0 / 6.0),
+            (Id::new(1), 2.0 / 6.0),
+            (Id::new(2), 1.0 / 6.0),
+            (Id::new(3), 1.0 / 6.0),
+        ]
+    );
+    assert_eq!(create_graph(1, &[]).degree_centrality(), vec![(Id::new(0), 0.0)]);
+}
+
+#[test]
+fn test_record_scores() {
+    let mut collection = create_graph(2, &[(0, 1)]);
+    let scores = collection.degree_centrality();
+    collection.record_scores(DEGREE_KEY, &scores);
+    assert_eq!(collection.entity(Id::new(1)).metadata_value(DEGREE_KEY), Some("0.500000"));
+}
@@ -0,0 +1,151 @@
+//! A cache of the entity fields that scans read most.
+//!
+//! An [`Entity`] is large, and most of it is rarely looked at, so a scan over the entities of a
+//! collection for their dates or labels jumps between cache lines.  The collection keeps copies of
+//! those fields, one contiguous vector each, indexed by [`Id`], for scans to read instead, updated
+//! wherever the collection changes an entity's dates or labels.  Labels are
+//! [interned](crate::intern), so the copies of them share their strings with the entities.
+//!
+//! This speeds up scans but does not make the collection smaller: it is larger by the copies.
+//! Storing the fields only here would take them out of [`Entity`], which the collection hands out
+//! by reference and which is built, merged and serialized on its own; the entities themselves are
+//! stored whole, as before.
+//!
+//! The dates are also kept in order, so that entities created or updated within a range are found
+//! without a scan.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeSet,
+    ops::{Bound, RangeBounds},
+};
+
+use super::{Entity, Id, Label, Time};
+
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct Columns {
+    created_at: Vec<Time>,
+    /// Each entity's last update, if it has been updated.
+    updated_at: Vec<Option<Time>>,
+    /// Each entity's labels, in order, in a slice sized to fit.
+    labels: Vec<Box<[Label]>>,
+    /// The indexes of the entities by `created_at`.
+    by_created_at: BTreeSet<(Time, usize)>,
+    /// The indexes of the entities that have been updated, by `updated_at`.
+    by_updated_at: BTreeSet<(Time, usize)>,
+}
+
+impl Columns {
+    pub(super) fn with_capacity(capacity: usize) -> Columns {
+        Columns {
+            created_at: Vec::with_capacity(capacity),
+            updated_at: Vec::with_capacity(capacity),
+            labels: Vec::with_capacity(capacity),
+            ..Columns::default()
+        }
+    }
+
+    pub(super) fn reserve(&mut self, additional: usize) {
+        self.created_at.reserve(additional);
+        self.updated_at.reserve(additional);
+        self.labels.reserve(additional);
+    }
+
+    /// Records the fields of the entity with the given [`Id`], which is either already present or
+    /// the next one.
+    pub(super) fn set(&mut self, id: Id, entity: &Entity) {
+        let created_at = entity.created_at;
+        let updated_at = entity.last_updated_at().copied();
+        let labels = entity.labels.iter().cloned().collect();
+        if id.index == self.created_at.len() {
+            self.created_at.push(created_at);
+            self.updated_at.push(updated_at);
+            self.labels.push(labels);
+        } else {
+            self.unorder(id.index);
+            self.created_at[id.index] = created_at;
+            self.updated_at[id.index] = updated_at;
+            self.labels[id.index] = labels;
+        }
+        self.order(id.index);
+    }
+
+    pub(super) fn swap_remove(&mut self, id: Id) {
+        let last = self.created_at.len() - 1;
+        self.unorder(id.index);
+        if id.index != last {
+            self.unorder(last);
+        }
+        self.created_at.swap_remove(id.index);
+        self.updated_at.swap_remove(id.index);
+        self.labels.swap_remove(id.index);
+        if id.index != last {
+            self.order(id.index);
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.created_at.clear();
+        self.updated_at.clear();
+        self.labels.clear();
+        self.by_created_at.clear();
+        self.by_updated_at.clear();
+    }
+
+    fn order(&mut self, index: usize) {
+        self.by_created_at.insert((self.created_at[index], index));
+        if let Some(updated_at) = self.updated_at[index] {
+            self.by_updated_at.insert((updated_at, index));
+        }
+    }
+
+    fn unorder(&mut self, index: usize) {
+        self.by_created_at.remove(&(self.created_at[index], index));
+        if let Some(updated_at) = self.updated_at[index] {
+            self.by_updated_at.remove(&(updated_at, index));
+        }
+    }
+
+    pub(super) fn created_at(&self) -> &[Time] {
+        &self.created_at
+    }
+
+    /// Returns the indexes of the entities created within the range, from the earliest.
+    pub(super) fn created_within(
+        &self,
+        range: impl RangeBounds<Time>,
+    ) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        within(&self.by_created_at, range)
+    }
+
+    /// Returns the indexes of the entities last updated within the range, from the earliest.
+    pub(super) fn updated_within(
+        &self,
+        range: impl RangeBounds<Time>,
+    ) -> impl DoubleEndedIterator<Item = usize> + '_ {
+        within(&self.by_updated_at, range)
+    }
+
+    pub(super) fn labels(&self) -> &[Box<[Label]>] {
+        &self.labels
+    }
+}
+
+fn within(
+    ordered: &BTreeSet<(Time, usize)>,
+    range: impl RangeBounds<Time>,
+) -> impl DoubleEndedIterator<Item = usize> + '_ {
+    let start = match range.start_bound() {
+        Bound::Included(&time) => Bound::Included((time, usize::MIN)),
+        Bound::Excluded(&time) => Bound::Excluded((time, usize::MAX)),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    let end = match range.end_bound() {
+        Bound::Included(&time) => Bound::Included((time, usize::MAX)),
+        Bound::Excluded(&time) => Bound::Excluded((time, usize::MIN)),
+        Bound::Unbounded => Bound::Unbounded,
+    };
+    ordered.range((start, end)).map(|&(_, index)| index)
+}
@@ -0,0 +1,79 @@
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Collection, Name};
+
+fn entity(url: &str, created_at: time::OffsetDateTime, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        created_at.into(),
+        None,
+        labels.iter().copied().map(Label::from).collect(),
+    )
+}
+
+/// Checks that the columns agree with the entities they were taken from.
+fn assert_columns(collection: &Collection) {
+    let columns = &collection.columns;
+    assert_eq!(columns.created_at().len(), collection.len());
+    assert_eq!(columns.labels().len(), collection.len());
+    for (id, entity) in collection.iter() {
+        assert_eq!(columns.created_at()[id.index], *entity.created_at());
+        let labels: Vec<Label> = entity.labels().iter().cloned().collect();
+        assert_eq!(*columns.labels()[id.index], *labels);
+    }
+    let mut by_created_at: Vec<usize> = (0..collection.len()).collect();
+    by_created_at.sort_by_key(|&index| (columns.created_at()[index], index));
+    assert!(columns.created_within(..).eq(by_created_at));
+    let mut by_updated_at: Vec<(Time, usize)> = collection
+        .iter()
+        .filter_map(|(id, entity)| entity.last_updated_at().map(|at| (*at, id.index)))
+        .collect();
+    by_updated_at.sort();
+    assert!(columns.updated_within(..).eq(by_updated_at.into_iter().map(|(_, index)| index)));
+}
+
+#[test]
+fn test_columns_follow_edits() {
+    let mut collection = Collection::new();
+    let a = collection.insert(entity("https://a.com/", datetime!(2024-03-01 0:00 UTC), &["x"]));
+    let b = collection.insert(entity("https://b.com/", datetime!(2024-02-01 0:00 UTC), &[]));
+    collection.insert(entity("https://c.com/", datetime!(2024-01-01 0:00 UTC), &["y"]));
+    assert_columns(&collection);
+
+    // An earlier update moves the creation time back
+    let labels = [Label::from("z")].into_iter().collect();
+    collection.update(b, datetime!(2023-12-01 0:00 UTC).into(), Default::default(), labels);
+    assert_columns(&collection);
+
+    collection.upsert(entity("https://c.com/", datetime!(2024-05-01 0:00 UTC), &["x"]));
+    collection.add_name(a, Name::from("A"));
+    assert_columns(&collection);
+
+    collection.rename_label(&Label::from("x"), Label::from("w"));
+    collection.merge_label(&Label::from("y"), Label::from("z"));
+    assert_columns(&collection);
+
+    collection.remove(a);
+    assert_columns(&collection);
+
+    let into = collection.id(&Url::parse("https://b.com/").unwrap()).unwrap();
+    let from = collection.id(&Url::parse("https://c.com/").unwrap()).unwrap();
+    collection.merge_entities(into, from);
+    assert_columns(&collection);
+
+    collection.update_labels(serde_json::json!({ "z": "v" })).unwrap();
+    assert_columns(&collection);
+}
+
+#[test]
+fn test_columns_deserialize() {
+    let mut collection = Collection::new();
+    collection.insert(entity("https://a.com/", datetime!(2024-03-01 0:00 UTC), &["x", "y"]));
+    collection.insert(entity("https://b.com/", datetime!(2024-02-01 0:00 UTC), &[]));
+    let json = serde_json::to_string(&collection).unwrap();
+    let parsed: Collection = serde_json::from_str(&json).unwrap();
+    assert_columns(&parsed);
+    assert_eq!(parsed, collection);
+}
@@ -0,0 +1,61 @@
+//! Which labels the entities of a [`Collection`] carry together.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+
+use super::{Collection, Label};
+
+/// A label carried alongside another, created by [`Collection::related_labels`].
+#[derive(Debug, Clone, PartialEq, Serialize)]
+pub struct Related {
+    pub label: Label,
+    /// The number of entities carrying both labels.
+    pub together: usize,
+    /// The number of entities carrying both labels over the number carrying either, from 0 to 1.
+    pub score: f64,
+}
+
+impl Collection {
+    /// Returns each pair of labels carried together, with the number of entities carrying both,
+    /// most common first, ties broken by the labels.  The lesser label of a pair comes first.
+    pub fn label_pairs(&self) -> Vec<(Label, Label, usize)> {
+        let mut counts: BTreeMap<(Label, Label), usize> = BTreeMap::new();
+        for labels in self.columns.labels() {
+            for (i, a) in labels.iter().enumerate() {
+                for b in &labels[i + 1..] {
+                    *counts.entry((a.clone(), b.clone())).or_default() += 1;
+                }
+            }
+        }
+        let mut ret: Vec<(Label, Label, usize)> =
+            counts.into_iter().map(|((a, b), count)| (a, b, count)).collect();
+        ret.sort_by(|(a, b, x), (c, d, y)| y.cmp(x).then((a, b).cmp(&(c, d))));
+        ret
+    }
+
+    /// Returns the labels carried alongside the label, most related first, ties broken by label.
+    pub fn related_labels(&self, label: &Label) -> Vec<Related> {
+        let label = self.resolve_label(label);
+        let count = |label: &Label| self.by_label(label).count();
+        let mut together: BTreeMap<Label, usize> = BTreeMap::new();
+        for id in self.by_label(label) {
+            for other in self.columns.labels()[id.index].iter().filter(|other| *other != label) {
+                *together.entry(other.clone()).or_default() += 1;
+            }
+        }
+        let total = count(label);
+        let mut ret: Vec<Related> = together
+            .into_iter()
+            .map(|(other, together)| {
+                let either = total + count(&other) - together;
+                Related { label: other, together, score: together as f64 / either as f64 }
+            })
+            .collect();
+        ret.sort_by(|a, b| b.score.total_cmp(&a.score).then(a.label.cmp(&b.label)));
+        ret
+    }
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+fn create_collection() -> Collection {
+    let mut collection = Collection::new();
+    let entities: [&[&str]; 4] =
+        [&["rust", "async"], &["rust", "async", "web"], &["rust", "cli"], &["web", "css"]];
+    for (i, labels) in entities.into_iter().enumerate() {
+        collection.insert(Entity::new(
+            Url::parse(&format!("https://example.com/{}", i)).unwrap(),
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+        ));
+    }
+    collection
+}
+
+#[test]
+fn test_label_pairs() {
+    let collection = create_collection();
+    let pairs: Vec<(Label, Label, usize)> = [
+        ("async", "rust", 2),
+        ("async", "web", 1),
+        ("cli", "rust", 1),
+        ("css", "web", 1),
+        ("rust", "web", 1),
+    ]
+    .into_iter()
+    .map(|(a, b, count)| (Label::from(a), Label::from(b), count))
+    .collect();
+    assert_eq!(collection.label_pairs(), pairs);
+}
+
+#[test]
+fn test_related_labels() {
+    let mut collection = create_collection();
+    let related = collection.related_labels(&Label::from("rust"));
+    let related: Vec<(&str, usize, f64)> =
+        related.iter().map(|r| (r.label.as_str(), r.together, r.score)).collect();
+    assert_eq!(related, [("async", 2, 2.0 / 3.0), ("cli", 1, 1.0 / 3.0), ("web", 1, 0.25)]);
+
+    // Aliases are resolved, and labels carried alone have nothing related
+    collection.add_label_alias(Label::from("rs"), Label::from("rust"));
+    assert_eq!(collection.related_labels(&Label::from("rs")).len(), 3);
+    collection.insert(Entity::new(
+        Url::parse("https://example.com/alone").unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        None,
+        BTreeSet::from([Label::from("alone")]),
+    ));
+    assert!(collection.related_labels(&Label::from("alone")).is_empty());
+    assert!(collection.related_labels(&Label::from("missing")).is_empty());
+}
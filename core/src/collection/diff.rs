@@ -0,0 +1,83 @@
+//! Comparing the entities of two [`Collection`]s.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use serde::Serialize;
+use url::Url;
+
+use super::{Collection, Label, Name};
+
+/// How the names and labels of an entity present in both collections differ, as part of a
+/// [`Diff`].
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub struct Change {
+    pub url: Url,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub names_added: BTreeSet<Name>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub names_removed: BTreeSet<Name>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub labels_added: BTreeSet<Label>,
+    #[serde(skip_serializing_if = "BTreeSet::is_empty")]
+    pub labels_removed: BTreeSet<Label>,
+}
+
+/// The differences between two collections, created by [`Collection::diff`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Diff {
+    /// URLs only in the other collection, in its order.
+    pub added: Vec<Url>,
+    /// URLs only in this collection, in its order.
+    pub removed: Vec<Url>,
+    /// Entities in both collections whose names or labels differ, in this collection's order.
+    pub changed: Vec<Change>,
+}
+
+impl Diff {
+    pub fn is_empty(&self) -> bool {
+        self.added.is_empty() && self.removed.is_empty() && self.changed.is_empty()
+    }
+}
+
+impl Collection {
+    /// Returns what changed going from this collection to `other`.
+    ///
+    /// Entities are matched by URL, as looked up in `other`, so `other`'s normalization applies.
+    /// Only names and labels are compared.
+    pub fn diff(&self, other: &Collection) -> Diff {
+        let mut ret = Diff::default();
+        let mut matched = vec![false; other.len()];
+        for (_, entity) in self.iter() {
+            let Some(id) = other.id(entity.url()) else {
+                ret.removed.push(entity.url().clone());
+                continue;
+            };
+            matched[id.index] = true;
+            let theirs = other.entity(id);
+            let change = Change {
+                url: entity.url().clone(),
+                names_added: theirs.names.difference(&entity.names).cloned().collect(),
+                names_removed: entity.names.difference(&theirs.names).cloned().collect(),
+                labels_added: theirs.labels.difference(&entity.labels).cloned().collect(),
+                labels_removed: entity.labels.difference(&theirs.labels).cloned().collect(),
+            };
+            if !(change.names_added.is_empty()
+                && change.names_removed.is_empty()
+                && change.labels_added.is_empty()
+                && change.labels_removed.is_empty())
+            {
+                ret.changed.push(change);
+            }
+        }
+        for (id, entity) in other.iter() {
+            if !matched[id.index] {
+                ret.added.push(entity.url().clone());
+            }
+        }
+        ret
+    }
+}
@@ -0,0 +1,59 @@
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+fn collection(entities: &[(&str, Option<&str>, &[&str])]) -> Collection {
+    let mut ret = Collection::new();
+    for (url, name, labels) in entities {
+        ret.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2024-01-15 0:00 UTC).into(),
+            name.map(Name::from),
+            labels.iter().copied().map(Label::from).collect(),
+        ));
+    }
+    ret
+}
+
+#[test]
+fn test_diff() {
+    let old = collection(&[
+        ("https://a.com/", Some("A"), &["foo"]),
+        ("https://b.com/", None, &["bar"]),
+        ("https://c.com/", Some("C"), &[]),
+    ]);
+    let new = collection(&[
+        ("https://d.com/", None, &[]),
+        ("https://c.com/", Some("C"), &[]),
+        ("https://a.com/", Some("A!"), &["foo", "baz"]),
+    ]);
+    let url = |url: &str| Url::parse(url).unwrap();
+    let diff = old.diff(&new);
+    assert_eq!(diff.added, vec![url("https://d.com/")]);
+    assert_eq!(diff.removed, vec![url("https://b.com/")]);
+    assert_eq!(
+        diff.changed,
+        vec![Change {
+            url: url("https://a.com/"),
+            names_added: BTreeSet::from([Name::from("A!")]),
+            names_removed: BTreeSet::from([Name::from("A")]),
+            labels_added: BTreeSet::from([Label::from("baz")]),
+            labels_removed: BTreeSet::new(),
+        }]
+    );
+    let reverse = new.diff(&old);
+    assert_eq!(reverse.added, diff.removed);
+    assert_eq!(reverse.removed, diff.added);
+    assert_eq!(reverse.changed[0].labels_removed, BTreeSet::from([Label::from("baz")]));
+    assert!(old.diff(&old).is_empty());
+}
+
+#[test]
+fn test_diff_normalized() {
+    let old = collection(&[("https://a.com/?utm_source=x", None, &[])]);
+    let mut new = collection(&[("https://a.com/", None, &[])]);
+    assert_eq!(old.diff(&new).added.len(), 1);
+    new.set_url_filter(Some(crate::url_norm::TrackingFilter::default()));
+    assert!(old.diff(&new).is_empty());
+}
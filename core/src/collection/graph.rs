@@ -0,0 +1,269 @@
+//! Graph algorithms over the edges of a [`Collection`].
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cmp::Reverse,
+    collections::{BinaryHeap, VecDeque},
+};
+
+use super::{Collection, EdgeKind, Error, Id};
+
+/// A breadth-first traversal, created by [`Collection::bfs`].
+pub struct Bfs<'a> {
+    collection: &'a Collection,
+    queue: VecDeque<Id>,
+    visited: Vec<bool>,
+}
+
+impl Iterator for Bfs<'_> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Id> {
+        let id = self.queue.pop_front()?;
+        for &to in self.collection.edges(id) {
+            if !self.visited[to.index] {
+                self.visited[to.index] = true;
+                self.queue.push_back(to);
+            }
+        }
+        Some(id)
+    }
+}
+
+/// A depth-first, pre-order traversal, created by [`Collection::dfs`].
+pub struct Dfs<'a> {
+    collection: &'a Collection,
+    stack: Vec<Id>,
+    visited: Vec<bool>,
+}
+
+impl Iterator for Dfs<'_> {
+    type Item = Id;
+
+    fn next(&mut self) -> Option<Id> {
+        loop {
+            let id = self.stack.pop()?;
+            if self.visited[id.index] {
+                continue;
+            }
+            self.visited[id.index] = true;
+            // Reversed, so that edges are followed in the order they were added
+            let unvisited =
+                self.collection.edges(id).iter().rev().filter(|to| !self.visited[to.index]);
+            self.stack.extend(unvisited);
+            return Some(id);
+        }
+    }
+}
+
+/// Finds the root of `i` in a union-find forest, halving paths along the way.
+fn find(parents: &mut [usize], mut i: usize) -> usize {
+    while parents[i] != i {
+        parents[i] = parents[parents[i]];
+        i = parents[i];
+    }
+    i
+}
+
+/// Returns the path to `to` by walking `previous` back to the start.
+fn path(previous: &[Option<Id>], to: Id) -> Vec<Id> {
+    let mut ret = vec![to];
+    while let Some(id) = previous[ret[ret.len() - 1].index] {
+        ret.push(id);
+    }
+    ret.reverse();
+    ret
+}
+
+impl Collection {
+    /// Returns the [`Id`]s reachable from `start` by following edges, in breadth-first order,
+    /// starting with `start` itself.
+    pub fn bfs(&self, start: Id) -> Bfs<'_> {
+        let start = self.checked(start);
+        let mut visited = vec![false; self.len()];
+        visited[start.index] = true;
+        Bfs { collection: self, queue: VecDeque::from([start]), visited }
+    }
+
+    /// Returns the [`Id`]s along a path with the fewest edges from `from` to `to`, following
+    /// edges, starting with `from` and ending with `to`, or `None` if there is none.
+    ///
+    /// Among paths as short, the one found first breadth-first, as by [`Collection::bfs`], is
+    /// returned.
+    pub fn shortest_path(&self, from: Id, to: Id) -> Option<Vec<Id>> {
+        let (from, to) = (self.checked(from), self.checked(to));
+        let mut previous: Vec<Option<Id>> = vec![None; self.len()];
+        let mut visited = vec![false; self.len()];
+        visited[from.index] = true;
+        let mut queue = VecDeque::from([from]);
+        while let Some(id) = queue.pop_front() {
+            if id == to {
+                return Some(path(&previous, to));
+            }
+            for &next in self.edges(id) {
+                if !visited[next.index] {
+                    visited[next.index] = true;
+                    previous[next.index] = Some(id);
+                    queue.push_back(next);
+                }
+            }
+        }
+        None
+    }
+
+    /// Like [`Collection::shortest_path`], finding a path whose edges weigh the least in total,
+    /// each edge weighing what `weight` returns for its source, target and kind.
+    ///
+    /// Among paths as light, the one with the lower [`Id`]s visited first is returned.
+    pub fn shortest_path_weighted(
+        &self,
+        from: Id,
+        to: Id,
+        mut weight: impl FnMut(Id, Id, &EdgeKind) -> u64,
+    ) -> Option<Vec<Id>> {
+        let (from, to) = (self.checked(from), self.checked(to));
+        let mut previous: Vec<Option<Id>> = vec![None; self.len()];
+        let mut distances: Vec<Option<u64>> = vec![None; self.len()];
+        distances[from.index] = Some(0);
+        let mut heap = BinaryHeap::from([Reverse((0, from.index))]);
+        while let Some(Reverse((distance, index))) = heap.pop() {
+            let id = self.id_at(index);
+            if id == to {
+                return Some(path(&previous, to));
+            }
+            if distances[index].is_some_and(|shortest| shortest < distance) {
+                continue;
+            }
+            for (&next, kind) in self.edges(id).iter().zip(self.edge_kinds(id)) {
+                let distance = distance.saturating_add(weight(id, next, kind));
+                if distances[next.index].is_none_or(|shortest| distance < shortest) {
+                    distances[next.index] = Some(distance);
+                    previous[next.index] = Some(id);
+                    heap.push(Reverse((distance, next.index)));
+                }
+            }
+        }
+        None
+    }
+
+    /// Partitions the collection into connected components, treating every edge as undirected.
+    ///
+    /// Each component lists its [`Id`]s in ascending order, and components are ordered by their
+    /// lowest [`Id`].  Entities without edges form components of their own.
+    pub fn connected_components(&self) -> Vec<Vec<Id>> {
+        let mut parents: Vec<usize> = (0..self.len()).collect();
+        for (from, to) in self.edges_iter() {
+            let a = find(&mut parents, from.index);
+            let b = find(&mut parents, to.index);
+            // Keep the lowest index as the root, so components come out in order
+            parents[a.max(b)] = a.min(b);
+        }
+        let mut ret: Vec<Vec<Id>> = Vec::new();
+        let mut components: Vec<usize> = vec![usize::MAX; self.len()];
+        for i in 0..self.len() {
+            let root = find(&mut parents, i);
+            if components[root] == usize::MAX {
+                components[root] = ret.len();
+                ret.push(Vec::new());
+            }
+            ret[components[root]].push(self.id_at(i));
+        }
+        ret
+    }
+
+    /// Returns the [`Id`]s reachable from `start` by following edges, in depth-first order,
+    /// starting with `start` itself.
+    pub fn dfs(&self, start: Id) -> Dfs<'_> {
+        let start = self.checked(start);
+        let visited = vec![false; self.len()];
+        Dfs { collection: self, stack: vec![start], visited }
+    }
+
+    /// Orders the collection so that every entity comes before the entities it has edges to.
+    ///
+    /// Among entities whose order is otherwise unconstrained, lower [`Id`]s come first.  Fails with
+    /// [`Error::Cycle`] listing the [`Id`]s of a cycle, in edge order, if there is one.
+    pub fn toposort(&self) -> Result<Vec<Id>, Error> {
+        let mut in_degrees = self.in_degrees();
+        let mut ready: BinaryHeap<Reverse<usize>> =
+            (0..self.len()).filter(|&i| in_degrees[i] == 0).map(Reverse).collect();
+        let mut ret = Vec::with_capacity(self.len());
+        while let Some(Reverse(i)) = ready.pop() {
+            ret.push(self.id_at(i));
+            for to in self.edges(self.id_at(i)) {
+                in_degrees[to.index] -= 1;
+                if in_degrees[to.index] == 0 {
+                    ready.push(Reverse(to.index));
+                }
+            }
+        }
+        if ret.len() == self.len() {
+            return Ok(ret);
+        }
+        Err(Error::Cycle(self.find_cycle(&in_degrees)))
+    }
+
+    /// Finds a cycle among the entities left over by [`Collection::toposort`].
+    ///
+    /// Each leftover entity has an edge from another leftover entity, so walking those edges
+    /// backwards must eventually revisit one.
+    fn find_cycle(&self, in_degrees: &[usize]) -> Vec<usize> {
+        let leftover = |i: usize| in_degrees[i] > 0;
+        let mut predecessors: Vec<Option<usize>> = vec![None; self.len()];
+        for (from, to) in self.edges_iter() {
+            if leftover(from.index) && leftover(to.index) {
+                predecessors[to.index].get_or_insert(from.index);
+            }
+        }
+        let start = (0..self.len()).find(|&i| leftover(i)).expect("leftover entity");
+        let mut position = vec![usize::MAX; self.len()];
+        let mut path = Vec::new();
+        let mut i = start;
+        while position[i] == usize::MAX {
+            position[i] = path.len();
+            path.push(i);
+            i = predecessors[i].expect("leftover predecessor");
+        }
+        let mut ret = path.split_off(position[i]);
+        ret.reverse();
+        // Start the cycle at its lowest id
+        let min = ret.iter().enumerate().min_by_key(|(_, i)| **i).map(|(n, _)| n).unwrap_or(0);
+        ret.rotate_left(min);
+        ret
+    }
+
+    /// Returns the [`Id`]s of entities that no edge points to, in ascending order.
+    pub fn sources(&self) -> Vec<Id> {
+        (0..self.len())
+            .map(|index| self.id_at(index))
+            .filter(|&id| self.incoming(id).is_empty())
+            .collect()
+    }
+
+    /// Returns the [`Id`]s of entities without edges of their own, in ascending order.
+    pub fn sinks(&self) -> Vec<Id> {
+        (0..self.len())
+            .map(|index| self.id_at(index))
+            .filter(|&id| self.edges(id).is_empty())
+            .collect()
+    }
+
+    /// Returns the [`Id`]s of entities with no edges to or from them, in ascending order.
+    pub fn orphans(&self) -> Vec<Id> {
+        (0..self.len())
+            .map(|index| self.id_at(index))
+            .filter(|&id| self.incoming(id).is_empty() && self.edges(id).is_empty())
+            .collect()
+    }
+
+    fn in_degrees(&self) -> Vec<usize> {
+        (0..self.len()).map(|i| self.incoming(self.id_at(i)).len()).collect()
+    }
+
+    /// Returns whether following edges can lead back to where they started.
+    pub fn has_cycle(&self) -> bool {
+        self.toposort().is_err()
+    }
+}
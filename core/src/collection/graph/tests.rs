@@ -0,0 +1,120 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+/// Creates a collection of `n` entities with the given edges.
+fn create_graph(n: usize, edges: &[(usize, usize)]) -> Collection {
+    let mut collection = Collection::new();
+    for i in 0..n {
+        let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+        collection.insert(Entity::new(
+            url,
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            BTreeSet::new(),
+        ));
+    }
+    for &(from, to) in edges {
+        collection.add_edge(Id::new(from), Id::new(to));
+    }
+    collection
+}
+
+fn ids(ids: impl Iterator<Item = Id>) -> Vec<usize> {
+    ids.map(usize::from).collect()
+}
+
+#[test]
+fn test_bfs() {
+    //   0 -> 1 -> 3
+    //   |         ^
+    //   v         |
+    //   2 --------+    4 (unreachable)
+    let collection = create_graph(5, &[(0, 1), (0, 2), (1, 3), (2, 3), (3, 0)]);
+    assert_eq!(ids(collection.bfs(Id::new(0))), vec![0, 1, 2, 3]);
+    assert_eq!(ids(collection.bfs(Id::new(2))), vec![2, 3, 0, 1]);
+    assert_eq!(ids(collection.bfs(Id::new(4))), vec![4]);
+}
+
+#[test]
+fn test_dfs() {
+    let collection = create_graph(6, &[(0, 1), (0, 4), (1, 2), (1, 3), (4, 3), (3, 0)]);
+    assert_eq!(ids(collection.dfs(Id::new(0))), vec![0, 1, 2, 3, 4]);
+    assert_eq!(ids(collection.dfs(Id::new(4))), vec![4, 3, 0, 1, 2]);
+    assert_eq!(ids(collection.dfs(Id::new(5))), vec![5]);
+}
+
+#[test]
+fn test_connected_components() {
+    let collection = create_graph(7, &[(3, 1), (1, 5), (4, 0), (6, 6)]);
+    let components: Vec<Vec<usize>> =
+        collection.connected_components().into_iter().map(|c| ids(c.into_iter())).collect();
+    assert_eq!(components, vec![vec![0, 4], vec![1, 3, 5], vec![2], vec![6]]);
+    assert!(Collection::new().connected_components().is_empty());
+}
+
+#[test]
+fn test_toposort() {
+    let collection = create_graph(5, &[(3, 1), (1, 0), (4, 0), (2, 3)]);
+    assert_eq!(ids(collection.toposort().unwrap().into_iter()), vec![2, 3, 1, 4, 0]);
+    assert!(!collection.has_cycle());
+    assert!(Collection::new().toposort().unwrap().is_empty());
+}
+
+#[test]
+fn test_toposort_cycle() {
+    let collection = create_graph(5, &[(0, 1), (1, 4), (4, 2), (2, 1), (3, 0)]);
+    let err = collection.toposort().unwrap_err();
+    assert!(matches!(&err, Error::Cycle(cycle) if cycle == &vec![1, 4, 2]));
+    assert_eq!(err.to_string(), "cycle: [1, 4, 2]");
+    assert!(collection.has_cycle());
+
+    let collection = create_graph(2, &[(0, 1), (1, 1)]);
+    assert!(matches!(collection.toposort(), Err(Error::Cycle(cycle)) if cycle == vec![1]));
+}
+
+#[test]
+fn test_sources_sinks_orphans() {
+    //   0 -> 1 -> 2    3    4 -> 4
+    let collection = create_graph(5, &[(0, 1), (1, 2), (4, 4)]);
+    assert_eq!(ids(collection.sources().into_iter()), vec![0, 3]);
+    assert_eq!(ids(collection.sinks().into_iter()), vec![2, 3]);
+    assert_eq!(ids(collection.orphans().into_iter()), vec![3]);
+    assert!(Collection::new().orphans().is_empty());
+}
+
+#[test]
+fn test_shortest_path() {
+    //   0 -> 1 -> 2 -> 3
+    //   |              ^
+    //   +----> 4 ------+    5
+    let collection = create_graph(6, &[(0, 1), (1, 2), (2, 3), (0, 4), (4, 3)]);
+    let path =
+        |from, to| collection.shortest_path(Id::new(from), Id::new(to)).map(|p| ids(p.into_iter()));
+    assert_eq!(path(0, 3), Some(vec![0, 4, 3]));
+    assert_eq!(path(1, 3), Some(vec![1, 2, 3]));
+    assert_eq!(path(2, 2), Some(vec![2]));
+    // Edges are followed one way
+    assert_eq!(path(3, 0), None);
+    assert_eq!(path(0, 5), None);
+}
+
+#[test]
+fn test_shortest_path_weighted() {
+    let mut collection = create_graph(5, &[(0, 1), (1, 2), (2, 3)]);
+    collection.add_edge_with_kind(Id::new(0), Id::new(4), EdgeKind::Similar);
+    collection.add_edge_with_kind(Id::new(4), Id::new(3), EdgeKind::Similar);
+    let path = |weight: fn(&EdgeKind) -> u64| {
+        let path =
+            collection.shortest_path_weighted(Id::new(0), Id::new(3), |_, _, kind| weight(kind));
+        path.map(|p| ids(p.into_iter()))
+    };
+    assert_eq!(path(|_| 1), Some(vec![0, 4, 3]));
+    // Similar edges weigh more than the default, so the longer chain is lighter
+    assert_eq!(path(|kind| if *kind == EdgeKind::Similar { 5 } else { 1 }), Some(vec![0, 1, 2, 3]));
+    assert_eq!(collection.shortest_path_weighted(Id::new(3), Id::new(0), |_, _, _| 1), None);
+}
@@ -0,0 +1,190 @@
+//! Three-way merging of collections edited separately from a common ancestor.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use serde::Serialize;
+use url::Url;
+
+use super::{Collection, EdgeKind, Entity};
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Serialize)]
+#[serde(rename_all = "camelCase")]
+pub enum Side {
+    Ours,
+    Theirs,
+}
+
+/// A change made on one side of a [`Collection::merge3`] that could not be reconciled with the
+/// other side.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize)]
+#[serde(tag = "conflict", rename_all = "camelCase")]
+pub enum Conflict {
+    /// The entity was removed on one side and changed on the other.  The changed entity is kept.
+    #[serde(rename_all = "camelCase")]
+    RemovedAndChanged { url: Url, removed_by: Side },
+}
+
+/// The outcome of a [`Collection::merge3`].
+#[derive(Debug)]
+pub struct Merge {
+    pub collection: Collection,
+    pub conflicts: Vec<Conflict>,
+}
+
+/// Returns the items of `ours` and `theirs` that were either kept by both sides or added by one
+/// of them.
+fn merge_sets<T: Ord + Clone>(
+    base: &BTreeSet<T>,
+    ours: &BTreeSet<T>,
+    theirs: &BTreeSet<T>,
+) -> BTreeSet<T> {
+    ours.union(theirs)
+        .filter(|item| (ours.contains(item) && theirs.contains(item)) || !base.contains(item))
+        .cloned()
+        .collect()
+}
+
+/// Returns the entries of `ours` and `theirs` whose keys were either kept by both sides or added by
+/// one of them, each with its value merged as by [`merge_values`].
+fn merge_maps<K: Ord + Clone, V: PartialEq + Clone>(
+    base: &BTreeMap<K, V>,
+    ours: &BTreeMap<K, V>,
+    theirs: &BTreeMap<K, V>,
+) -> BTreeMap<K, V> {
+    let keys = |map: &BTreeMap<K, V>| map.keys().cloned().collect::<BTreeSet<K>>();
+    merge_sets(&keys(base), &keys(ours), &keys(theirs))
+        .into_iter()
+        .filter_map(|key| {
+            let value = merge_values(&base.get(&key), &ours.get(&key), &theirs.get(&key))?;
+            Some((key, value.clone()))
+        })
+        .collect()
+}
+
+/// Returns the value changed from `base`, preferring `ours` if both sides changed it.
+fn merge_values<T: PartialEq + Clone>(base: &T, ours: &T, theirs: &T) -> T {
+    if ours == base {
+        theirs.clone()
+    } else {
+        ours.clone()
+    }
+}
+
+fn merge_entities(base: &Entity, ours: &Entity, theirs: &Entity) -> Entity {
+    let mut ret = ours.clone();
+    ret.names = merge_sets(&base.names, &ours.names, &theirs.names);
+    ret.labels = merge_sets(&base.labels, &ours.labels, &theirs.labels);
+    for note in theirs.notes.iter() {
+        ret.add_note(note.clone());
+    }
+    for update in theirs.history.iter() {
+        if !ret.history.contains(update) {
+            ret.history.push(update.clone());
+        }
+    }
+    ret.history.sort_by_key(|update| update.at);
+    ret.read_status = merge_values(&base.read_status, &ours.read_status, &theirs.read_status);
+    ret.starred = merge_values(&base.starred, &ours.starred, &theirs.starred);
+    ret.shared = merge_values(&base.shared, &ours.shared, &theirs.shared);
+    ret.toread = merge_values(&base.toread, &ours.toread, &theirs.toread);
+    ret.extended = merge_values(&base.extended, &ours.extended, &theirs.extended);
+    ret.last_check = merge_values(&base.last_check, &ours.last_check, &theirs.last_check);
+    ret.metadata = merge_maps(&base.metadata, &ours.metadata, &theirs.metadata);
+    ret.favicon = merge_values(&base.favicon, &ours.favicon, &theirs.favicon);
+    ret.aliases = merge_sets(&base.aliases, &ours.aliases, &theirs.aliases);
+    ret.sources = merge_sets(&base.sources, &ours.sources, &theirs.sources);
+    ret
+}
+
+fn find<'a>(collection: &'a Collection, url: &Url) -> Option<&'a Entity> {
+    collection.id(url).map(|id| &collection.nodes[id])
+}
+
+/// Returns the edges of the collection by the URLs of their ends, in order.
+fn edges(collection: &Collection) -> Vec<((&Url, &Url), &EdgeKind)> {
+    collection
+        .edges_with_kinds()
+        .map(|(from, to, kind)| ((collection.nodes[from].url(), collection.nodes[to].url()), kind))
+        .collect()
+}
+
+impl Collection {
+    /// Merges the changes made in `ours` and in `theirs` since `base`, which both descend from.
+    ///
+    /// Entities are matched by URL.  Entities, names, labels, aliases, sources, metadata keys and
+    /// edges added on either side are kept, as are those kept by both; those removed on either
+    /// side are dropped.  Notes and history are combined, and other fields take the value from
+    /// whichever side changed them, preferring `ours`.  An entity removed on one side and changed
+    /// on the other is kept and reported as a [`Conflict`].
+    ///
+    /// The collection's name, label aliases and URL handling are merged the same way.  The merged
+    /// collection lists the entities of `ours` before those added in `theirs`.
+    pub fn merge3(base: &Collection, ours: &Collection, theirs: &Collection) -> Merge {
+        let mut collection = Collection::new();
+        collection.name = merge_values(&base.name, &ours.name, &theirs.name);
+        collection.aliases = merge_maps(&base.aliases, &ours.aliases, &theirs.aliases);
+        collection.normalization =
+            merge_values(&base.normalization, &ours.normalization, &theirs.normalization);
+        collection.url_filter =
+            merge_values(&base.url_filter, &ours.url_filter, &theirs.url_filter);
+        let mut conflicts = Vec::new();
+        for (_, entity) in ours.iter() {
+            let url = entity.url();
+            let merged = match (find(base, url), find(theirs, url)) {
+                (Some(base), Some(theirs)) => merge_entities(base, entity, theirs),
+                (Some(base), None) if base == entity => continue,
+                (Some(_), None) => {
+                    let url = url.clone();
+                    conflicts.push(Conflict::RemovedAndChanged { url, removed_by: Side::Theirs });
+                    entity.clone()
+                }
+                (None, Some(theirs)) => {
+                    let mut ret = entity.clone();
+                    ret.merge(theirs.clone());
+                    ret
+                }
+                (None, None) => entity.clone(),
+            };
+            collection.insert(merged);
+        }
+        for (_, entity) in theirs.iter() {
+            let url = entity.url();
+            if ours.contains(url) {
+                continue;
+            }
+            match find(base, url) {
+                Some(base) if base == entity => continue,
+                Some(_) => {
+                    let url = url.clone();
+                    conflicts.push(Conflict::RemovedAndChanged { url, removed_by: Side::Ours });
+                }
+                None => {}
+            }
+            collection.insert(entity.clone());
+        }
+        let (our_edges, their_edges) = (edges(ours), edges(theirs));
+        let base_edges: HashMap<_, _> = edges(base).into_iter().collect();
+        let ours_by_ends: HashMap<_, _> = our_edges.iter().copied().collect();
+        let theirs_by_ends: HashMap<_, _> = their_edges.iter().copied().collect();
+        let mut merged_edges = Vec::new();
+        for (ends, kind) in our_edges {
+            if theirs_by_ends.contains_key(&ends) || !base_edges.contains_key(&ends) {
+                merged_edges.push((ends, kind));
+            }
+        }
+        for (ends, kind) in their_edges {
+            if !ours_by_ends.contains_key(&ends) && !base_edges.contains_key(&ends) {
+                merged_edges.push((ends, kind));
+            }
+        }
+        for ((from, to), kind) in merged_edges {
+            if let (Some(from), Some(to)) = (collection.id(from), collection.id(to)) {
+                collection.add_edge_with_kind(from, to, kind.clone());
+            }
+        }
+        Merge { collection, conflicts }
+    }
+}
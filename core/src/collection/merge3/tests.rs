@@ -0,0 +1,185 @@
+use std::path::Path;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Label, Name};
+
+fn entity(url: &str, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        None,
+        labels.iter().copied().map(Label::from).collect(),
+    )
+}
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+fn labels(collection: &Collection, url: &str) -> BTreeSet<Label> {
+    let id = collection.id(&Url::parse(url).unwrap()).unwrap();
+    collection.entity(id).labels().clone()
+}
+
+fn ancestor() -> Collection {
+    let mut ret = Collection::new();
+    let a = ret.insert(entity("https://a.com/", &["foo", "bar"]));
+    let b = ret.insert(entity("https://b.com/", &[]));
+    ret.insert(entity("https://c.com/", &[]));
+    ret.add_edge(a, b);
+    ret
+}
+
+#[test]
+fn test_merge3() {
+    let base = ancestor();
+
+    let mut ours = ancestor();
+    let a = ours.id(&url("https://a.com/")).unwrap();
+    ours.entity_mut(a).labels_mut().remove(&Label::from("foo"));
+    ours.add_name(a, Name::from("A"));
+    let d = ours.insert(entity("https://d.com/", &[]));
+    ours.add_edge(d, a);
+
+    let mut theirs = ancestor();
+    let a = theirs.id(&url("https://a.com/")).unwrap();
+    theirs.entity_mut(a).labels_mut().insert(Label::from("baz"));
+    theirs.entity_mut(a).set_starred(true);
+    let b = theirs.id(&url("https://b.com/")).unwrap();
+    theirs.remove_edge(a, b);
+    theirs.remove_by_url(&url("https://c.com/"));
+    theirs.insert(entity("https://e.com/", &["new"]));
+
+    let Merge { collection, conflicts } = Collection::merge3(&base, &ours, &theirs);
+    assert_eq!(conflicts, vec![]);
+    let urls: Vec<&str> =
+        collection.entities().iter().map(|entity| entity.url().as_str()).collect();
+    assert_eq!(urls, vec!["https://a.com/", "https://b.com/", "https://d.com/", "https://e.com/"]);
+    assert_eq!(
+        labels(&collection, "https://a.com/"),
+        BTreeSet::from([Label::from("bar"), Label::from("baz")])
+    );
+    let a = collection.id(&url("https://a.com/")).unwrap();
+    assert_eq!(collection.entity(a).names(), &BTreeSet::from([Name::from("A")]));
+    assert!(collection.entity(a).starred());
+    let d = collection.id(&url("https://d.com/")).unwrap();
+    assert_eq!(collection.edges(a), &[]);
+    assert_eq!(collection.edges(d), &[a]);
+    assert_eq!(
+        labels(&Collection::merge3(&base, &ours, &ours).collection, "https://a.com/"),
+        labels(&ours, "https://a.com/")
+    );
+}
+
+#[test]
+fn test_merge3_conflicts() {
+    let base = ancestor();
+
+    let mut ours = ancestor();
+    ours.remove_by_url(&url("https://a.com/"));
+    let b = ours.id(&url("https://b.com/")).unwrap();
+    ours.entity_mut(b).labels_mut().insert(Label::from("mine"));
+
+    let mut theirs = ancestor();
+    let a = theirs.id(&url("https://a.com/")).unwrap();
+    theirs.entity_mut(a).labels_mut().insert(Label::from("relabeled"));
+    theirs.remove_by_url(&url("https://b.com/"));
+
+    let Merge { collection, conflicts } = Collection::merge3(&base, &ours, &theirs);
+    assert_eq!(
+        conflicts,
+        vec![
+            Conflict::RemovedAndChanged { url: url("https://b.com/"), removed_by: Side::Theirs },
+            Conflict::RemovedAndChanged { url: url("https://a.com/"), removed_by: Side::Ours },
+        ]
+    );
+    assert_eq!(labels(&collection, "https://b.com/"), BTreeSet::from([Label::from("mine")]));
+    assert!(labels(&collection, "https://a.com/").contains(&Label::from("relabeled")));
+    assert_eq!(collection.len(), 3);
+    let json = serde_json::to_value(&conflicts[0]).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({"conflict": "removedAndChanged", "url": "https://b.com/", "removedBy": "theirs"})
+    );
+}
+
+#[test]
+fn test_merge3_other_fields() {
+    use std::path::PathBuf;
+
+    use crate::collection::{LinkCheck, LinkStatus, Location};
+
+    let mut base = ancestor();
+    let a = base.id(&url("https://a.com/")).unwrap();
+    base.entity_mut(a).set_metadata(String::from("kept"), String::from("base"));
+    base.entity_mut(a).set_metadata(String::from("dropped"), String::from("base"));
+    base.add_alias(a, url("https://old.a.com/"));
+    base.entity_mut(a).add_source(Location::new("base.md", 1));
+
+    let mut ours = base.clone();
+    ours.entity_mut(a).set_metadata(String::from("ours"), String::from("added"));
+    ours.add_alias(a, url("https://mirror.a.com/"));
+
+    let mut theirs = base.clone();
+    let check = LinkCheck {
+        checked_at: datetime!(2024-01-16 0:00 UTC).into(),
+        status: LinkStatus::Ok { code: 200 },
+    };
+    theirs.entity_mut(a).set_last_check(Some(check.clone()));
+    theirs.entity_mut(a).set_metadata(String::from("kept"), String::from("theirs"));
+    theirs.entity_mut(a).remove_metadata("dropped");
+    theirs.entity_mut(a).set_favicon(Some(PathBuf::from("a.com.ico")));
+    theirs.remove_alias(a, &url("https://old.a.com/"));
+    theirs.entity_mut(a).remove_sources(Path::new("base.md"));
+    theirs.entity_mut(a).add_source(Location::new("theirs.md", 2));
+
+    let Merge { collection, conflicts } = Collection::merge3(&base, &ours, &theirs);
+    assert_eq!(conflicts, vec![]);
+    let entity = collection.entity(collection.id(&url("https://a.com/")).unwrap());
+    assert_eq!(entity.last_check(), Some(&check));
+    assert_eq!(
+        entity.metadata(),
+        &BTreeMap::from([
+            (String::from("kept"), String::from("theirs")),
+            (String::from("ours"), String::from("added")),
+        ])
+    );
+    assert_eq!(entity.favicon(), Some(Path::new("a.com.ico")));
+    assert_eq!(entity.aliases(), &BTreeSet::from([url("https://mirror.a.com/")]));
+    assert_eq!(entity.sources(), &BTreeSet::from([Location::new("theirs.md", 2)]));
+    assert_eq!(collection.id(&url("https://mirror.a.com/")), collection.id(&url("https://a.com/")));
+    assert_eq!(collection.id(&url("https://old.a.com/")), None);
+}
+
+#[test]
+fn test_merge3_collection_settings() {
+    use crate::url_norm::{Options, TrackingFilter};
+
+    let mut base = ancestor();
+    base.set_name(Some(String::from("Base")));
+    base.add_label_alias(Label::from("old"), Label::from("foo"));
+    base.add_label_alias(Label::from("gone"), Label::from("foo"));
+
+    let mut ours = base.clone();
+    ours.set_url_filter(Some(TrackingFilter::default()));
+    ours.add_label_alias(Label::from("mine"), Label::from("bar"));
+
+    let mut theirs = base.clone();
+    theirs.set_name(Some(String::from("Theirs")));
+    theirs.set_normalization(Some(Options::default()));
+    theirs.remove_label_alias(&Label::from("gone"));
+
+    let Merge { collection, .. } = Collection::merge3(&base, &ours, &theirs);
+    assert_eq!(collection.name(), Some("Theirs"));
+    assert_eq!(collection.normalization(), Some(&Options::default()));
+    assert_eq!(collection.url_filter(), Some(&TrackingFilter::default()));
+    assert_eq!(
+        collection.label_aliases(),
+        &BTreeMap::from([
+            (Label::from("mine"), Label::from("bar")),
+            (Label::from("old"), Label::from("foo")),
+        ])
+    );
+}
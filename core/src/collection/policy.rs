@@ -0,0 +1,142 @@
+//! Resolving conflicts between an entity and an incoming version of it.
+
+#[cfg(test)]
+mod tests;
+
+use super::{Collection, Entity, Id};
+
+/// A part of an entity that an incoming version of it may disagree on.
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Field {
+    CreatedAt,
+    Names,
+    Labels,
+}
+
+/// How a [`MergePolicy`] resolves a conflicting [`Field`].
+#[derive(Debug, Copy, Clone, PartialEq, Eq, Hash)]
+pub enum Resolution {
+    /// Keep both: the names or labels of both entities, or the earlier creation time.
+    Union,
+    /// Keep the existing entity's value.
+    Existing,
+    /// Take the incoming entity's value.
+    Incoming,
+}
+
+/// Decides, field by field, how an incoming entity is merged into the existing entity with its URL.
+///
+/// A policy is only asked about fields on which the two entities differ.  Fields other than those
+/// named by [`Field`] are always merged as by [`Collection::upsert`].  Closures taking the field,
+/// the existing entity and the incoming entity are policies too, so a caller can ask its user.
+pub trait MergePolicy {
+    fn resolve(&mut self, field: Field, existing: &Entity, incoming: &Entity) -> Resolution;
+}
+
+impl<F> MergePolicy for F
+where
+    F: FnMut(Field, &Entity, &Entity) -> Resolution,
+{
+    fn resolve(&mut self, field: Field, existing: &Entity, incoming: &Entity) -> Resolution {
+        self(field, existing, incoming)
+    }
+}
+
+/// Keeps the names and labels of both entities, and the earlier creation time.  This is what
+/// [`Collection::upsert`] does.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct Union;
+
+impl MergePolicy for Union {
+    fn resolve(&mut self, _: Field, _: &Entity, _: &Entity) -> Resolution {
+        Resolution::Union
+    }
+}
+
+/// Keeps the existing entity's fields.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PreferExisting;
+
+impl MergePolicy for PreferExisting {
+    fn resolve(&mut self, _: Field, _: &Entity, _: &Entity) -> Resolution {
+        Resolution::Existing
+    }
+}
+
+/// Takes the fields of whichever entity was updated more recently, or created more recently if
+/// neither has been updated, preferring the existing entity if they tie.
+#[derive(Debug, Default, Copy, Clone)]
+pub struct PreferNewer;
+
+impl MergePolicy for PreferNewer {
+    fn resolve(&mut self, _: Field, existing: &Entity, incoming: &Entity) -> Resolution {
+        let touched = |entity: &Entity| *entity.last_updated_at().unwrap_or(entity.created_at());
+        if touched(incoming) > touched(existing) {
+            Resolution::Incoming
+        } else {
+            Resolution::Existing
+        }
+    }
+}
+
+impl Entity {
+    /// Like [`Entity::merge_from`], resolving conflicting names, labels and creation times with
+    /// the policy.
+    pub(crate) fn merge_with(
+        &mut self,
+        mut other: Entity,
+        source: Option<String>,
+        policy: &mut dyn MergePolicy,
+    ) -> &mut Entity {
+        let created_at = if self.created_at == other.created_at {
+            self.created_at
+        } else {
+            match policy.resolve(Field::CreatedAt, self, &other) {
+                Resolution::Union => self.created_at.min(other.created_at),
+                Resolution::Existing => self.created_at,
+                Resolution::Incoming => other.created_at,
+            }
+        };
+        if self.names != other.names {
+            match policy.resolve(Field::Names, self, &other) {
+                Resolution::Union => {}
+                Resolution::Existing => other.names.clear(),
+                Resolution::Incoming => self.names.retain(|name| other.names.contains(name)),
+            }
+        }
+        if self.labels != other.labels {
+            match policy.resolve(Field::Labels, self, &other) {
+                Resolution::Union => {}
+                Resolution::Existing => other.labels.clear(),
+                Resolution::Incoming => self.labels.retain(|label| other.labels.contains(label)),
+            }
+        }
+        // The update is recorded at the later of the two creation times, as by `update_from`
+        let later = self.created_at.max(other.created_at);
+        other.created_at = later;
+        self.merge_from(other, source);
+        self.created_at = created_at;
+        self
+    }
+}
+
+impl Collection {
+    /// Like [`Collection::upsert_from`], resolving conflicts with the existing entity, if there
+    /// is one, with the policy.
+    pub fn merge(
+        &mut self,
+        mut other: Entity,
+        source: Option<String>,
+        policy: &mut dyn MergePolicy,
+    ) -> Id {
+        let Some(id) = self.id(other.url()) else {
+            return self.insert(other);
+        };
+        self.apply_aliases(&mut other.labels);
+        // Names and labels the policy drops must leave the indexes too
+        self.unindex(id);
+        self.nodes[id].merge_with(other, source, policy);
+        self.index(id);
+        id
+    }
+}
@@ -0,0 +1,124 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Label, Name, Time};
+
+fn url() -> Url {
+    Url::parse("https://example.com/").unwrap()
+}
+
+fn existing() -> Entity {
+    Entity::builder(url())
+        .created(datetime!(2024-01-15 0:00 UTC))
+        .name("Existing")
+        .labels(["shared", "existing"])
+        .build()
+}
+
+fn incoming() -> Entity {
+    Entity::builder(url())
+        .created(datetime!(2024-01-10 0:00 UTC))
+        .name("Incoming")
+        .labels(["shared", "incoming"])
+        .starred(true)
+        .build()
+}
+
+fn names(entity: &Entity) -> Vec<&str> {
+    entity.names().iter().map(Name::as_str).collect()
+}
+
+fn labels(entity: &Entity) -> Vec<&str> {
+    entity.labels().iter().map(Label::as_str).collect()
+}
+
+fn merged(policy: &mut dyn MergePolicy) -> (Collection, Id) {
+    let mut collection = Collection::new();
+    collection.insert(existing());
+    let id = collection.merge(incoming(), None, policy);
+    (collection, id)
+}
+
+#[test]
+fn test_union() {
+    let (collection, id) = merged(&mut Union);
+    let entity = collection.entity(id);
+    assert_eq!(names(entity), vec!["Existing", "Incoming"]);
+    assert_eq!(labels(entity), vec!["existing", "incoming", "shared"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-01-10 0:00 UTC)));
+    assert_eq!(
+        entity.updated_at().collect::<Vec<_>>(),
+        vec![&datetime!(2024-01-15 0:00 UTC).into()]
+    );
+
+    // The same as an upsert
+    let mut upserted = Collection::new();
+    upserted.insert(existing());
+    upserted.upsert(incoming());
+    assert_eq!(collection, upserted);
+}
+
+#[test]
+fn test_prefer_existing() {
+    let (collection, id) = merged(&mut PreferExisting);
+    let entity = collection.entity(id);
+    assert_eq!(names(entity), vec!["Existing"]);
+    assert_eq!(labels(entity), vec!["existing", "shared"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-01-15 0:00 UTC)));
+    // Fields without a policy are still merged
+    assert!(entity.starred());
+    assert_eq!(collection.by_label(&Label::from("incoming")).count(), 0);
+}
+
+#[test]
+fn test_prefer_newer() {
+    // The existing entity was created later, and neither has been updated
+    let (collection, id) = merged(&mut PreferNewer);
+    let entity = collection.entity(id);
+    assert_eq!(names(entity), vec!["Existing"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-01-15 0:00 UTC)));
+
+    let mut collection = Collection::new();
+    let id = collection.insert(existing());
+    let mut newer = incoming();
+    newer.update(datetime!(2024-01-20 0:00 UTC).into(), BTreeSet::new(), BTreeSet::new());
+    collection.merge(newer, None, &mut PreferNewer);
+    let entity = collection.entity(id);
+    assert_eq!(names(entity), vec!["Incoming"]);
+    assert_eq!(labels(entity), vec!["incoming", "shared"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-01-10 0:00 UTC)));
+    assert_eq!(collection.by_name("Existing").count(), 0);
+    assert_eq!(collection.by_name("Incoming").collect::<Vec<_>>(), vec![id]);
+}
+
+#[test]
+fn test_callback() {
+    let mut asked = Vec::new();
+    let mut policy = |field, _: &Entity, _: &Entity| {
+        asked.push(field);
+        match field {
+            Field::Names => Resolution::Incoming,
+            Field::Labels => Resolution::Union,
+            Field::CreatedAt => Resolution::Existing,
+        }
+    };
+    let (collection, id) = merged(&mut policy);
+    assert_eq!(asked, vec![Field::CreatedAt, Field::Names, Field::Labels]);
+    let entity = collection.entity(id);
+    assert_eq!(names(entity), vec!["Incoming"]);
+    assert_eq!(labels(entity), vec!["existing", "incoming", "shared"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-01-15 0:00 UTC)));
+
+    // Fields that agree are not asked about
+    let mut asked = 0;
+    let mut collection = Collection::new();
+    collection.insert(existing());
+    collection.merge(existing(), None, &mut |_, _: &Entity, _: &Entity| {
+        asked += 1;
+        Resolution::Union
+    });
+    assert_eq!(asked, 0);
+}
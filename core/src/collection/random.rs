@@ -0,0 +1,63 @@
+//! Picking entities of a [`Collection`] at random.
+
+#[cfg(test)]
+mod tests;
+
+use rand::{
+    distributions::{Distribution, WeightedIndex},
+    seq::SliceRandom,
+    Rng,
+};
+use time::OffsetDateTime;
+
+use super::{Collection, Entity, Id};
+
+/// How likely [`Collection::random_with`] is to pick each entity.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum Weight {
+    /// Every entity is as likely as any other.
+    #[default]
+    Uniform,
+    /// An entity is more likely the longer before the newest entity it was created, by one more
+    /// than the number of days between them.
+    Older,
+    /// An unread entity is [`UNREAD_WEIGHT`] times as likely as one that has been read.
+    Unread,
+}
+
+/// How much likelier [`Weight::Unread`] makes unread entities.
+pub const UNREAD_WEIGHT: u64 = 4;
+
+impl Collection {
+    /// Returns the [`Id`] of an entity picked at random, or `None` if the collection is empty.
+    pub fn random<R: Rng + ?Sized>(&self, rng: &mut R) -> Option<Id> {
+        self.random_with(rng, Weight::Uniform, |_| true)
+    }
+
+    /// Returns the [`Id`] of an entity picked at random from those for which `filter` returns
+    /// true, as weighted, or `None` if there are none.
+    pub fn random_with<R: Rng + ?Sized>(
+        &self,
+        rng: &mut R,
+        weight: Weight,
+        filter: impl Fn(&Entity) -> bool,
+    ) -> Option<Id> {
+        let candidates: Vec<(Id, &Entity)> =
+            self.iter().filter(|(_, entity)| filter(entity)).collect();
+        let weights: Vec<u64> = match weight {
+            Weight::Uniform => return candidates.choose(rng).map(|(id, _)| *id),
+            Weight::Older => {
+                let created_at = |entity: &Entity| OffsetDateTime::from(*entity.created_at());
+                let newest = candidates.iter().map(|(_, entity)| created_at(entity)).max()?;
+                let days = |entity| (newest - created_at(entity)).whole_days().unsigned_abs();
+                candidates.iter().map(|(_, entity)| days(entity) + 1).collect()
+            }
+            Weight::Unread => candidates
+                .iter()
+                .map(|(_, entity)| if entity.read_status().is_unread() { UNREAD_WEIGHT } else { 1 })
+                .collect(),
+        };
+        let index = WeightedIndex::new(weights).ok()?;
+        Some(candidates[index.sample(rng)].0)
+    }
+}
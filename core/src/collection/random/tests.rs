@@ -0,0 +1,55 @@
+use std::collections::BTreeSet;
+
+use rand::{rngs::StdRng, SeedableRng};
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Time;
+
+fn create_collection() -> Collection {
+    let mut collection = Collection::new();
+    let created = [datetime!(2020-01-01 0:00 UTC), datetime!(2024-01-01 0:00 UTC)];
+    for (i, created_at) in created.into_iter().enumerate() {
+        let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+        collection.insert(Entity::new(url, created_at.into(), None, BTreeSet::new()));
+    }
+    collection
+}
+
+/// Counts how often each entity is picked.
+fn counts(collection: &Collection, weight: Weight) -> Vec<usize> {
+    let mut rng = StdRng::seed_from_u64(0);
+    let mut ret = vec![0; collection.len()];
+    for _ in 0..1000 {
+        let id = collection.random_with(&mut rng, weight, |_| true).unwrap();
+        ret[usize::from(id)] += 1;
+    }
+    ret
+}
+
+#[test]
+fn test_random() {
+    let mut rng = StdRng::seed_from_u64(0);
+    assert_eq!(Collection::new().random(&mut rng), None);
+    let collection = create_collection();
+    assert!(collection.random(&mut rng).is_some());
+    let newest = |entity: &Entity| OffsetDateTime::from(*entity.created_at()).year() == 2024;
+    let id = collection.random_with(&mut rng, Weight::Older, newest).unwrap();
+    assert_eq!(usize::from(id), 1);
+    assert_eq!(collection.random_with(&mut rng, Weight::Unread, |_| false), None);
+}
+
+#[test]
+fn test_weights() {
+    let mut collection = create_collection();
+    let uniform = counts(&collection, Weight::Uniform);
+    assert!(uniform.iter().all(|&count| (400..600).contains(&count)), "{:?}", uniform);
+    // The older entity weighs over a thousand days to the newer's one
+    let older = counts(&collection, Weight::Older);
+    assert!(older[0] > 990, "{:?}", older);
+
+    collection.entity_mut(collection.id_at(0)).mark_read(Time::default());
+    let unread = counts(&collection, Weight::Unread);
+    assert!((700..900).contains(&unread[1]), "{:?}", unread);
+}
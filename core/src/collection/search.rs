@@ -0,0 +1,190 @@
+//! Ranked full-text and fuzzy search over a [`Collection`]'s entities.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet, HashMap};
+
+use super::{Collection, Entity, Id};
+
+/// How much an occurrence of a term counts towards an entity's score, by where it occurs.
+const NAME_WEIGHT: u32 = 3;
+const LABEL_WEIGHT: u32 = 2;
+const NOTE_WEIGHT: u32 = 1;
+
+/// The least similarity, from 0 to 1, at which a word matches a term in a fuzzy search.
+const FUZZY_THRESHOLD: f64 = 0.6;
+
+/// Splits text into lowercase alphanumeric terms.
+fn terms(text: &str) -> impl Iterator<Item = String> + '_ {
+    text.split(|c: char| !c.is_alphanumeric())
+        .filter(|term| !term.is_empty())
+        .map(str::to_lowercase)
+}
+
+/// Returns the optimal string alignment distance between `a` and `b`: the number of insertions,
+/// deletions, substitutions and transpositions of adjacent characters turning one into the other.
+fn edit_distance(a: &[char], b: &[char]) -> usize {
+    let mut rows = vec![vec![0; b.len() + 1]; a.len() + 1];
+    for (i, row) in rows.iter_mut().enumerate() {
+        row[0] = i;
+    }
+    for (j, cell) in rows[0].iter_mut().enumerate() {
+        *cell = j;
+    }
+    for i in 1..=a.len() {
+        for j in 1..=b.len() {
+            let cost = usize::from(a[i - 1] != b[j - 1]);
+            let mut distance =
+                (rows[i - 1][j] + 1).min(rows[i][j - 1] + 1).min(rows[i - 1][j - 1] + cost);
+            if i > 1 && j > 1 && a[i - 1] == b[j - 2] && a[i - 2] == b[j - 1] {
+                distance = distance.min(rows[i - 2][j - 2] + 1);
+            }
+            rows[i][j] = distance;
+        }
+    }
+    rows[a.len()][b.len()]
+}
+
+/// Returns how well `word` matches `term`, from 0 to 1.  A word containing the term matches
+/// fully.
+fn similarity(term: &str, word: &str) -> f64 {
+    if word.contains(term) {
+        return 1.0;
+    }
+    let term: Vec<char> = term.chars().collect();
+    let word: Vec<char> = word.chars().collect();
+    let longest = term.len().max(word.len());
+    1.0 - edit_distance(&term, &word) as f64 / longest as f64
+}
+
+/// An inverted index from terms to the entities containing them.
+#[derive(Debug, Default, Clone, PartialEq, Eq)]
+pub(super) struct TextIndex {
+    /// The weighted frequency of each term in each entity.
+    documents: HashMap<Id, BTreeMap<String, u32>>,
+    postings: HashMap<String, BTreeSet<Id>>,
+}
+
+impl TextIndex {
+    /// Indexes the entity, replacing whatever was indexed for `id` before.
+    pub(super) fn insert(&mut self, id: Id, entity: &Entity) {
+        self.remove(id);
+        let mut document: BTreeMap<String, u32> = BTreeMap::new();
+        let mut add = |text: &str, weight: u32| {
+            for term in terms(text) {
+                *document.entry(term).or_default() += weight;
+            }
+        };
+        for name in entity.names.iter() {
+            add(name.as_str(), NAME_WEIGHT);
+        }
+        for label in entity.labels.iter() {
+            label.segments().for_each(|segment| add(segment, LABEL_WEIGHT));
+        }
+        for note in entity.notes.iter() {
+            add(&note.text, NOTE_WEIGHT);
+        }
+        for term in document.keys() {
+            self.postings.entry(term.clone()).or_default().insert(id);
+        }
+        if !document.is_empty() {
+            self.documents.insert(id, document);
+        }
+    }
+
+    pub(super) fn remove(&mut self, id: Id) {
+        let Some(document) = self.documents.remove(&id) else {
+            return;
+        };
+        for term in document.keys() {
+            if let Some(ids) = self.postings.get_mut(term) {
+                ids.remove(&id);
+                if ids.is_empty() {
+                    self.postings.remove(term);
+                }
+            }
+        }
+    }
+
+    pub(super) fn clear(&mut self) {
+        self.documents.clear();
+        self.postings.clear();
+    }
+}
+
+impl Collection {
+    /// Returns the entities whose names, labels or notes contain every term of the query, with
+    /// their scores, best first.
+    ///
+    /// Terms are runs of letters and digits, compared ignoring case.  Each occurrence of a term
+    /// counts most in a name, less in a label and least in a note, and rarer terms count for
+    /// more.  Ties are broken by ascending [`Id`].
+    pub fn search_ranked(&self, query: &str) -> Vec<(Id, f64)> {
+        let query: BTreeSet<String> = terms(query).collect();
+        let Some(first) = query.first() else {
+            return Vec::new();
+        };
+        let postings: Vec<&BTreeSet<Id>> =
+            match query.iter().map(|term| self.text.postings.get(term)).collect() {
+                Some(postings) => postings,
+                None => return Vec::new(),
+            };
+        let total = self.text.documents.len() as f64;
+        let mut ret: Vec<(Id, f64)> = self.text.postings[first]
+            .iter()
+            .filter(|id| postings.iter().all(|ids| ids.contains(id)))
+            .map(|&id| {
+                let document = &self.text.documents[&id];
+                let score = query
+                    .iter()
+                    .zip(postings.iter())
+                    .map(|(term, ids)| {
+                        let idf = (1.0 + total / ids.len() as f64).ln();
+                        document[term] as f64 * idf
+                    })
+                    .sum();
+                (id, score)
+            })
+            .collect();
+        ret.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        ret
+    }
+
+    /// Returns the entities whose URLs, names or labels approximately match every term of the
+    /// query, with their scores from 0 to 1, best first.
+    ///
+    /// A term matches the most similar word, so that `tokoi` still finds `tokio`, and counts as an
+    /// exact match in a word that contains it.  Ties are broken by ascending [`Id`].
+    pub fn search_fuzzy(&self, query: &str) -> Vec<(Id, f64)> {
+        let query: Vec<String> = terms(query).collect();
+        if query.is_empty() {
+            return Vec::new();
+        }
+        let mut ret: Vec<(Id, f64)> = self
+            .iter()
+            .filter_map(|(id, entity)| {
+                let mut words: BTreeSet<String> = BTreeSet::new();
+                words.extend(entity.url.host_str().into_iter().flat_map(terms));
+                words.extend(terms(entity.url.path()));
+                for name in entity.names.iter() {
+                    words.extend(terms(name.as_str()));
+                }
+                for label in entity.labels.iter() {
+                    words.extend(terms(label.as_str()));
+                }
+                let mut total = 0.0;
+                for term in query.iter() {
+                    let best = words.iter().map(|word| similarity(term, word)).fold(0.0, f64::max);
+                    if best < FUZZY_THRESHOLD {
+                        return None;
+                    }
+                    total += best;
+                }
+                Some((id, total / query.len() as f64))
+            })
+            .collect();
+        ret.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        ret
+    }
+}
@@ -0,0 +1,123 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Label, Name, Note};
+
+fn entity(url: &str, name: Option<&str>, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        name.map(Name::from),
+        labels.iter().copied().map(Label::from).collect(),
+    )
+}
+
+fn ids(results: Vec<(Id, f64)>) -> Vec<Id> {
+    results.into_iter().map(|(id, _)| id).collect()
+}
+
+#[test]
+fn test_terms() {
+    let ret: Vec<String> = terms("Async I/O in Rust, 2024!").collect();
+    assert_eq!(ret, vec!["async", "i", "o", "in", "rust", "2024"]);
+}
+
+#[test]
+fn test_search_ranked() {
+    let mut collection = Collection::new();
+    let name = collection.insert(entity("https://a.com", Some("Async Rust"), &[]));
+    let label = collection.insert(entity("https://b.com", None, &["lang/rust", "async"]));
+    let note = collection.insert(entity("https://c.com", Some("Tokio"), &[]));
+    collection
+        .add_note(note, Note::new(datetime!(2024-01-16 0:00 UTC).into(), "async rust".into()));
+    let _ = collection.insert(entity("https://d.com", Some("Rust"), &[]));
+    assert_eq!(ids(collection.search_ranked("RUST async")), vec![name, label, note]);
+    assert_eq!(collection.search_ranked("tokio").len(), 1);
+    assert!(collection.search_ranked("python").is_empty());
+    assert!(collection.search_ranked("rust python").is_empty());
+    assert!(collection.search_ranked("").is_empty());
+
+    // Rarer terms count for more
+    let results = collection.search_ranked("rust");
+    assert_eq!(results.len(), 4);
+    let tokio = collection.search_ranked("tokio");
+    assert!(tokio[0].1 > results.iter().find(|(id, _)| *id == note).unwrap().1);
+}
+
+#[test]
+fn test_search_ranked_incremental() {
+    let mut collection = Collection::new();
+    let a = collection.insert(entity("https://a.com", None, &["rust"]));
+    let b = collection.insert(entity("https://b.com", Some("Serde"), &[]));
+    assert!(collection.search_ranked("serialization").is_empty());
+
+    // Merged entities are reindexed
+    let mut other = entity("https://a.com", Some("Serialization"), &[]);
+    other.add_note(Note::new(datetime!(2024-01-16 0:00 UTC).into(), "serde docs".into()));
+    collection.upsert(other);
+    assert_eq!(ids(collection.search_ranked("serialization")), vec![a]);
+    assert_eq!(ids(collection.search_ranked("serde")), vec![b, a]);
+
+    collection.update(
+        a,
+        datetime!(2024-01-17 0:00 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::from([Label::from("json")]),
+    );
+    assert_eq!(ids(collection.search_ranked("json")), vec![a]);
+    collection.rename_label(&Label::from("json"), Label::from("yaml"));
+    assert!(collection.search_ranked("json").is_empty());
+    assert_eq!(ids(collection.search_ranked("yaml")), vec![a]);
+
+    // The last entity takes the slot of a removed one
+    collection.remove(a);
+    let moved = collection.id(&Url::parse("https://b.com").unwrap()).unwrap();
+    assert_eq!(moved.index(), a.index());
+    assert!(collection.search_ranked("serialization").is_empty());
+    assert_eq!(ids(collection.search_ranked("serde")), vec![moved]);
+    let merged = collection.insert(entity("https://c.com", Some("Serde JSON"), &[]));
+    let into = collection.merge_entities(moved, merged);
+    assert_eq!(ids(collection.search_ranked("serde json")), vec![into]);
+}
+
+#[test]
+fn test_search_ranked_clear_notes() {
+    let mut collection = Collection::new();
+    let id = collection.insert(entity("https://a.com", None, &[]));
+    assert!(!collection.clear_notes(id));
+    collection.add_note(id, Note::new(datetime!(2024-01-16 0:00 UTC).into(), "zebra".into()));
+    assert_eq!(ids(collection.search_ranked("zebra")), vec![id]);
+    assert!(collection.clear_notes(id));
+    assert!(collection.entity(id).notes().is_empty());
+    assert!(collection.search_ranked("zebra").is_empty());
+}
+
+#[test]
+fn test_edit_distance() {
+    let distance = |a: &str, b: &str| {
+        edit_distance(&a.chars().collect::<Vec<_>>(), &b.chars().collect::<Vec<_>>())
+    };
+    assert_eq!(distance("tokio", "tokio"), 0);
+    assert_eq!(distance("tokoi", "tokio"), 1);
+    assert_eq!(distance("kitten", "sitting"), 3);
+    assert_eq!(distance("", "abc"), 3);
+}
+
+#[test]
+fn test_search_fuzzy() {
+    let mut collection = Collection::new();
+    let tokio = collection.insert(entity("https://tokio.rs", Some("Tokio"), &["rust/async"]));
+    let toml = collection.insert(entity("https://toml.io", Some("TOML"), &[]));
+    let serde = collection.insert(entity("https://serde.rs/derive.html", None, &["rust"]));
+    let results = collection.search_fuzzy("tokoi");
+    assert_eq!(ids(results.clone()), vec![tokio]);
+    assert!(results[0].1 < 1.0);
+    assert_eq!(collection.search_fuzzy("TOK")[0], (tokio, 1.0));
+    assert_eq!(ids(collection.search_fuzzy("derive rust")), vec![serde]);
+    assert_eq!(ids(collection.search_fuzzy("tomll")), vec![toml]);
+    assert!(collection.search_fuzzy("python").is_empty());
+    assert!(collection.search_fuzzy("").is_empty());
+}
@@ -0,0 +1,306 @@
+//! A [`Collection`] that can be read and written from many threads.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    sync::{
+        atomic::{AtomicU64, Ordering},
+        Arc, RwLock, RwLockReadGuard, RwLockWriteGuard,
+    },
+};
+
+use url::Url;
+
+use super::{Collection, EdgeKind, Entity, Label, Name, Time};
+use crate::hash;
+
+const DEFAULT_SHARDS: usize = 16;
+
+/// Some of the entities, each with the position it was added in.
+#[derive(Debug)]
+struct Shard {
+    /// Holds entities only: edges between them are kept apart, as they cross shards.
+    collection: Collection,
+    order: HashMap<Url, u64>,
+}
+
+/// The edges between entities, by the URLs the entities have.
+#[derive(Debug, Default)]
+struct Edges {
+    outgoing: HashMap<Url, Vec<(Url, EdgeKind)>>,
+    incoming: HashMap<Url, BTreeSet<Url>>,
+}
+
+#[derive(Debug)]
+struct Inner {
+    shards: Box<[RwLock<Shard>]>,
+    edges: RwLock<Edges>,
+    next: AtomicU64,
+    /// The collection's name and settings, without entities.
+    template: Collection,
+}
+
+/// A handle to a [`Collection`] shared between threads.  Clones of the handle refer to the same
+/// collection.
+///
+/// Entities are spread over shards by URL, each behind its own lock, so writes to different
+/// shards proceed in parallel and readers wait only for writes to the shard they read.  Edges are
+/// kept behind a lock of their own; a write to an edge holds the shards of both entities for
+/// reading, so neither can be removed meanwhile.  An entity looked up by one of its aliases may be
+/// in any shard, which takes a look at each.
+///
+/// Entities are handled by URL, since an [`Id`](super::Id) only means something within a single
+/// collection.  Reading a single entity or its edges sees it whole, but [`SharedCollection::len`]
+/// and the like may see some writes to other shards and not others; [`SharedCollection::snapshot`]
+/// waits for a moment no write is in progress and copies the whole collection.
+///
+/// The collection's name, aliases and URL settings are those it had when shared.
+#[derive(Debug, Clone)]
+pub struct SharedCollection {
+    inner: Arc<Inner>,
+}
+
+impl SharedCollection {
+    pub fn new(collection: Collection) -> SharedCollection {
+        SharedCollection::with_shards(collection, DEFAULT_SHARDS)
+    }
+
+    /// Shares the collection, spreading its entities over the given number of shards.
+    pub fn with_shards(mut collection: Collection, shards: usize) -> SharedCollection {
+        let edges: Vec<_> = collection
+            .edges_with_kinds()
+            .map(|(from, to, kind)| {
+                (
+                    collection.entity(from).url().clone(),
+                    collection.entity(to).url().clone(),
+                    kind.clone(),
+                )
+            })
+            .collect();
+        let entities = collection.extract_if(|_, _| true);
+        let shards = (0..shards.max(1))
+            .map(|_| {
+                let collection = collection.clone();
+                RwLock::new(Shard { collection, order: HashMap::new() })
+            })
+            .collect();
+        let inner = Inner {
+            shards,
+            edges: RwLock::default(),
+            next: AtomicU64::new(0),
+            template: collection,
+        };
+        let shared = SharedCollection { inner: Arc::new(inner) };
+        for entity in entities.entities() {
+            shared.upsert(entity.clone());
+        }
+        for (from, to, kind) in edges {
+            shared.add_edge_with_kind(&from, &to, kind);
+        }
+        shared
+    }
+
+    fn home(&self, url: &Url) -> usize {
+        let key = self.inner.template.key(url);
+        (hash::fnv1a(key.as_str().as_bytes()) % self.inner.shards.len() as u64) as usize
+    }
+
+    fn read(&self, index: usize) -> RwLockReadGuard<'_, Shard> {
+        self.inner.shards[index].read().expect("lock")
+    }
+
+    fn write(&self, index: usize) -> RwLockWriteGuard<'_, Shard> {
+        self.inner.shards[index].write().expect("lock")
+    }
+
+    /// Returns the shard with an entity with the URL, or else the one such an entity would be
+    /// added to.
+    fn locate(&self, url: &Url) -> usize {
+        let home = self.home(url);
+        if self.read(home).collection.contains(url) {
+            return home;
+        }
+        (0..self.inner.shards.len())
+            .filter(|&index| index != home)
+            .find(|&index| self.read(index).collection.contains(url))
+            .unwrap_or(home)
+    }
+
+    pub fn len(&self) -> usize {
+        (0..self.inner.shards.len()).map(|index| self.read(index).collection.len()).sum()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.len() == 0
+    }
+
+    /// Returns whether an entity has the URL, or has it as an alias.
+    pub fn contains(&self, url: &Url) -> bool {
+        self.read(self.locate(url)).collection.contains(url)
+    }
+
+    /// Returns a copy of the entity with the URL, or with the URL as an alias.
+    pub fn get(&self, url: &Url) -> Option<Entity> {
+        let shard = self.read(self.locate(url));
+        let id = shard.collection.id(url)?;
+        Some(shard.collection.entity(id).clone())
+    }
+
+    /// Adds the entity, or merges it into the entity that has its URL, as with
+    /// [`Collection::upsert`].
+    ///
+    /// Returns whether the entity was added.
+    pub fn upsert(&self, entity: Entity) -> bool {
+        let mut shard = self.write(self.locate(entity.url()));
+        let Shard { collection, order } = &mut *shard;
+        let added = !collection.contains(entity.url());
+        let id = collection.upsert(entity);
+        if added {
+            let index = self.inner.next.fetch_add(1, Ordering::Relaxed);
+            order.insert(collection.entity(id).url().clone(), index);
+        }
+        added
+    }
+
+    /// Updates the entity with the URL, as with [`Collection::update`].
+    ///
+    /// Returns whether there was such an entity.
+    pub fn update(
+        &self,
+        url: &Url,
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        labels: BTreeSet<Label>,
+    ) -> bool {
+        let mut shard = self.write(self.locate(url));
+        let Some(id) = shard.collection.id(url) else {
+            return false;
+        };
+        shard.collection.update(id, updated_at, names, labels);
+        true
+    }
+
+    /// Applies a change to the entity with the URL, returning its result, or [`None`] if there is
+    /// no such entity.
+    pub fn modify<T>(&self, url: &Url, modify: impl FnOnce(&mut Entity) -> T) -> Option<T> {
+        let mut shard = self.write(self.locate(url));
+        let id = shard.collection.id(url)?;
+        Some(modify(shard.collection.entity_mut(id)))
+    }
+
+    /// Removes the entity with the URL, along with the edges from and to it.
+    pub fn remove(&self, url: &Url) -> Option<Entity> {
+        let mut shard = self.write(self.locate(url));
+        let id = shard.collection.id(url)?;
+        let entity = shard.collection.remove(id);
+        shard.order.remove(entity.url());
+        let mut edges = self.inner.edges.write().expect("lock");
+        for (to, _) in edges.outgoing.remove(entity.url()).unwrap_or_default() {
+            if let Some(sources) = edges.incoming.get_mut(&to) {
+                sources.remove(entity.url());
+            }
+        }
+        for from in edges.incoming.remove(entity.url()).unwrap_or_default() {
+            if let Some(targets) = edges.outgoing.get_mut(&from) {
+                targets.retain(|(to, _)| to != entity.url());
+            }
+        }
+        Some(entity)
+    }
+
+    /// Holds the shards of both entities for reading while applying `f` to the URLs the entities
+    /// have, if both are present.
+    fn endpoints<T>(&self, from: &Url, to: &Url, f: impl FnOnce(&Url, &Url) -> T) -> Option<T> {
+        let (a, b) = (self.locate(from), self.locate(to));
+        let first = self.read(a.min(b));
+        let second = (a != b).then(|| self.read(a.max(b)));
+        let resolve = |url, index: usize| {
+            let shard = if index == a.min(b) { &first } else { second.as_ref().expect("shard") };
+            let id = shard.collection.id(url)?;
+            Some(shard.collection.entity(id).url().clone())
+        };
+        Some(f(&resolve(from, a)?, &resolve(to, b)?))
+    }
+
+    pub fn add_edge(&self, from: &Url, to: &Url) -> bool {
+        self.add_edge_with_kind(from, to, EdgeKind::default())
+    }
+
+    /// Adds an edge of the given kind, or changes the kind of the edge already there.
+    ///
+    /// Returns whether both entities are present.
+    pub fn add_edge_with_kind(&self, from: &Url, to: &Url, kind: EdgeKind) -> bool {
+        self.endpoints(from, to, |from, to| {
+            let mut edges = self.inner.edges.write().expect("lock");
+            let targets = edges.outgoing.entry(from.clone()).or_default();
+            match targets.iter_mut().find(|(other, _)| other == to) {
+                Some((_, other)) => *other = kind,
+                None => targets.push((to.clone(), kind)),
+            }
+            edges.incoming.entry(to.clone()).or_default().insert(from.clone());
+        })
+        .is_some()
+    }
+
+    /// Removes the edge from `from` to `to`, returning its kind, or [`None`] if there was no such
+    /// edge.
+    pub fn remove_edge(&self, from: &Url, to: &Url) -> Option<EdgeKind> {
+        self.endpoints(from, to, |from, to| {
+            let mut edges = self.inner.edges.write().expect("lock");
+            let targets = edges.outgoing.get_mut(from)?;
+            let index = targets.iter().position(|(other, _)| other == to)?;
+            let (_, kind) = targets.remove(index);
+            if let Some(sources) = edges.incoming.get_mut(to) {
+                sources.remove(from);
+            }
+            Some(kind)
+        })
+        .flatten()
+    }
+
+    /// Returns the edges from the entity with the URL, in the order they were added, by the URLs
+    /// of their targets.
+    pub fn edges(&self, url: &Url) -> Vec<(Url, EdgeKind)> {
+        let shard = self.read(self.locate(url));
+        let Some(id) = shard.collection.id(url) else {
+            return Vec::new();
+        };
+        let edges = self.inner.edges.read().expect("lock");
+        edges.outgoing.get(shard.collection.entity(id).url()).cloned().unwrap_or_default()
+    }
+
+    /// Returns a copy of the whole collection, with its entities in the order they were added.
+    ///
+    /// This copies every entity, holding every shard for reading while it does.
+    pub fn snapshot(&self) -> Collection {
+        let shards: Vec<_> = (0..self.inner.shards.len()).map(|index| self.read(index)).collect();
+        let edges = self.inner.edges.read().expect("lock");
+        let mut entities: Vec<_> = shards
+            .iter()
+            .flat_map(|shard| {
+                shard.collection.entities().iter().map(|entity| (shard.order[entity.url()], entity))
+            })
+            .collect();
+        entities.sort_unstable_by_key(|&(index, _)| index);
+        let mut collection = self.inner.template.clone();
+        collection.reserve(entities.len());
+        for (_, entity) in entities {
+            collection.insert(entity.clone());
+        }
+        for (from, targets) in &edges.outgoing {
+            let from = collection.id(from).expect("source");
+            for (to, kind) in targets {
+                let to = collection.id(to).expect("target");
+                collection.add_edge_with_kind(from, to, kind.clone());
+            }
+        }
+        collection
+    }
+
+    /// Returns the collection, as [`SharedCollection::snapshot`] does.
+    pub fn into_inner(self) -> Collection {
+        self.snapshot()
+    }
+}
@@ -0,0 +1,123 @@
+use std::thread;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+fn entity(url: &str) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        None,
+        Default::default(),
+    )
+}
+
+#[test]
+fn test_concurrent_writes() {
+    let shared = SharedCollection::new(Collection::new());
+    let handles: Vec<_> = (0..4)
+        .map(|thread| {
+            let shared = shared.clone();
+            thread::spawn(move || {
+                for i in 0..25 {
+                    let url = Url::parse(&format!("https://example.com/{}/{}", thread, i)).unwrap();
+                    assert!(shared.upsert(entity(url.as_str())));
+                    assert!(shared.contains(&url));
+                    if i > 0 {
+                        let previous = format!("https://example.com/{}/{}", thread, i - 1);
+                        assert!(shared.add_edge(&url, &Url::parse(&previous).unwrap()));
+                    }
+                    // Other threads' entities may come and go meanwhile
+                    let other = Url::parse(&format!("https://example.com/{}/0", (thread + 1) % 4));
+                    shared.get(&other.unwrap());
+                }
+            })
+        })
+        .collect();
+    for handle in handles {
+        handle.join().unwrap();
+    }
+    assert_eq!(shared.len(), 100);
+    let collection = shared.into_inner();
+    assert_eq!(collection.len(), 100);
+    assert_eq!(collection.edges_iter().count(), 96);
+}
+
+#[test]
+fn test_round_trip() {
+    let mut collection = Collection::new();
+    collection.set_name(Some(String::from("shared")));
+    let ids: Vec<_> =
+        (0..20).map(|i| collection.insert(entity(&format!("https://example.com/{}", i)))).collect();
+    for pair in ids.windows(2) {
+        collection.add_edge(pair[1], pair[0]);
+        collection.add_edge_with_kind(pair[0], pair[1], EdgeKind::PartOf);
+    }
+    collection.add_edge(ids[3], ids[3]);
+    collection.add_alias(ids[7], url("https://alias.com/"));
+    let shared = SharedCollection::with_shards(collection.clone(), 4);
+    assert_eq!(shared.len(), 20);
+    assert_eq!(shared.into_inner(), collection);
+}
+
+#[test]
+fn test_aliases() {
+    let mut collection = Collection::new();
+    let id = collection.insert(entity("https://a.com/"));
+    collection.add_alias(id, url("https://alias.com/"));
+    let shared = SharedCollection::with_shards(collection, 8);
+    assert!(shared.contains(&url("https://alias.com/")));
+    assert!(!shared.upsert(entity("https://alias.com/")));
+    shared.upsert(entity("https://b.com/"));
+    assert!(shared.add_edge(&url("https://b.com/"), &url("https://alias.com/")));
+    assert_eq!(
+        shared.edges(&url("https://b.com/")),
+        vec![(url("https://a.com/"), EdgeKind::RelatedTo)]
+    );
+    assert_eq!(shared.len(), 2);
+}
+
+#[test]
+fn test_edges() {
+    let shared = SharedCollection::new(Collection::new());
+    let (a, b, c) = (url("https://a.com/"), url("https://b.com/"), url("https://c.com/"));
+    for url in [&a, &b, &c] {
+        shared.upsert(entity(url.as_str()));
+    }
+    assert!(shared.add_edge(&a, &b));
+    assert!(shared.add_edge_with_kind(&a, &c, EdgeKind::Supersedes));
+    assert!(shared.add_edge(&c, &a));
+    assert!(!shared.add_edge(&a, &url("https://missing.com/")));
+    assert_eq!(shared.remove_edge(&a, &b), Some(EdgeKind::RelatedTo));
+    assert_eq!(shared.remove_edge(&a, &b), None);
+
+    // Removing an entity removes the edges from and to it
+    let removed = shared.remove(&c).unwrap();
+    assert_eq!(removed.url(), &c);
+    assert_eq!(shared.edges(&a), vec![]);
+    assert!(shared.upsert(entity(c.as_str())));
+    assert_eq!(shared.edges(&c), vec![]);
+    let collection = shared.snapshot();
+    assert_eq!(collection.edges_iter().count(), 0);
+}
+
+#[test]
+fn test_update() {
+    let shared = SharedCollection::new(Collection::new());
+    let a = url("https://a.com/");
+    shared.upsert(entity(a.as_str()));
+    let updated_at = datetime!(2024-01-16 0:00 UTC).into();
+    assert!(shared.update(&a, updated_at, [Name::from("A")].into(), [].into()));
+    assert!(!shared.update(&url("https://b.com/"), updated_at, [].into(), [].into()));
+    assert_eq!(shared.modify(&a, |entity| entity.set_starred(true).starred()), Some(true));
+    let entity = shared.get(&a).unwrap();
+    assert!(entity.starred());
+    assert_eq!(shared.snapshot().by_name("A").count(), 1);
+}
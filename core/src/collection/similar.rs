@@ -0,0 +1,76 @@
+//! Suggesting entities like another, from the labels and host they share.
+
+#[cfg(test)]
+mod tests;
+
+use std::cmp::Ordering;
+
+use super::{Collection, EdgeKind, Id, Label};
+
+/// What sharing a host adds to the score of [`Collection::similar`], as much as sharing half of
+/// all labels.
+pub const SAME_HOST: f64 = 0.5;
+
+impl Collection {
+    /// Returns up to `k` of the other entities most like the entity with the given [`Id`], with
+    /// their scores, highest first, ties broken by [`Id`].
+    ///
+    /// An entity scores the number of labels it shares with the given entity over the number
+    /// either carries, plus [`SAME_HOST`] if their URLs have the same host.  Entities that share
+    /// neither are left out.
+    pub fn similar(&self, id: Id, k: usize) -> Vec<(Id, f64)> {
+        let id = self.checked(id);
+        let labels = &self.columns.labels()[id.index];
+        let host = self.nodes[id].url.host_str();
+        let mut ret: Vec<(Id, f64)> = self
+            .iter()
+            .filter(|(other, _)| *other != id)
+            .filter_map(|(other, entity)| {
+                let mut score = jaccard(labels, &self.columns.labels()[other.index]);
+                if host.is_some() && entity.url.host_str() == host {
+                    score += SAME_HOST;
+                }
+                (score > 0.0).then_some((other, score))
+            })
+            .collect();
+        ret.sort_by(|(a, x), (b, y)| y.total_cmp(x).then(a.cmp(b)));
+        ret.truncate(k);
+        ret
+    }
+
+    /// Adds an edge of kind [`EdgeKind::Similar`] in both directions between the entity with the
+    /// given [`Id`] and each of the entities [`Collection::similar`] suggests, where there is no
+    /// edge already.
+    ///
+    /// Returns the number of edges added.
+    pub fn link_similar(&mut self, id: Id, k: usize) -> usize {
+        let mut ret = 0;
+        for (other, _) in self.similar(id, k) {
+            for (from, to) in [(id, other), (other, id)] {
+                if self.edge_kind(from, to).is_none() {
+                    self.add_edge_with_kind(from, to, EdgeKind::Similar);
+                    ret += 1;
+                }
+            }
+        }
+        ret
+    }
+}
+
+/// Returns the number of labels in both over the number in either.  Both are in order.
+fn jaccard(a: &[Label], b: &[Label]) -> f64 {
+    let (mut i, mut j, mut both) = (0, 0, 0);
+    while i < a.len() && j < b.len() {
+        match a[i].cmp(&b[j]) {
+            Ordering::Less => i += 1,
+            Ordering::Greater => j += 1,
+            Ordering::Equal => (i, j, both) = (i + 1, j + 1, both + 1),
+        }
+    }
+    let either = a.len() + b.len() - both;
+    if either == 0 {
+        0.0
+    } else {
+        both as f64 / either as f64
+    }
+}
@@ -0,0 +1,53 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+fn create_collection() -> (Collection, Vec<Id>) {
+    let mut collection = Collection::new();
+    let entities: [(&str, &[&str]); 5] = [
+        ("https://tokio.rs/", &["rust", "async"]),
+        ("https://docs.rs/tokio", &["rust", "async", "docs"]),
+        ("https://docs.rs/serde", &["rust"]),
+        ("https://go.dev/", &["go"]),
+        ("https://tokio.rs/blog", &[]),
+    ];
+    let ids = entities
+        .into_iter()
+        .map(|(url, labels)| {
+            collection.insert(Entity::new(
+                Url::parse(url).unwrap(),
+                datetime!(2024-01-15 0:00 UTC).into(),
+                None,
+                labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+            ))
+        })
+        .collect();
+    (collection, ids)
+}
+
+#[test]
+fn test_similar() {
+    let (collection, ids) = create_collection();
+    let similar = collection.similar(ids[0], 10);
+    assert_eq!(similar, vec![(ids[1], 2.0 / 3.0), (ids[2], 0.5), (ids[4], SAME_HOST)]);
+    assert_eq!(collection.similar(ids[0], 1), vec![(ids[1], 2.0 / 3.0)]);
+    // Sharing a host adds to sharing labels
+    assert_eq!(collection.similar(ids[2], 1), vec![(ids[1], 1.0 / 3.0 + SAME_HOST)]);
+    assert!(collection.similar(ids[3], 10).is_empty());
+}
+
+#[test]
+fn test_link_similar() {
+    let (mut collection, ids) = create_collection();
+    collection.add_edge_with_kind(ids[0], ids[1], EdgeKind::Supersedes);
+    assert_eq!(collection.link_similar(ids[0], 2), 3);
+    assert_eq!(collection.edge_kind(ids[0], ids[1]), Some(&EdgeKind::Supersedes));
+    assert_eq!(collection.edge_kind(ids[1], ids[0]), Some(&EdgeKind::Similar));
+    assert!(collection.is_undirected_edge(ids[0], ids[2]));
+    assert_eq!(collection.link_similar(ids[0], 2), 0);
+    assert_eq!(EdgeKind::from("similar"), EdgeKind::Similar);
+}
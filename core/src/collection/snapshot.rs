@@ -0,0 +1,104 @@
+//! Immutable, point-in-time copies of a [`Collection`].
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    fs::File,
+    io::{self, BufReader, BufWriter, Write},
+    ops::Deref,
+    path::Path,
+    sync::Arc,
+};
+
+use serde::{Deserialize, Serialize};
+use time::OffsetDateTime;
+
+use super::{Collection, Time};
+
+/// A copy of a [`Collection`] as it was when [`Collection::snapshot`] was called.
+///
+/// Cloning a snapshot is cheap, as clones share the copied collection.  A snapshot dereferences to
+/// the collection, so it can be queried or exported like one.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    taken_at: Time,
+    collection: Arc<Collection>,
+}
+
+impl Snapshot {
+    pub fn taken_at(&self) -> &Time {
+        &self.taken_at
+    }
+
+    /// Writes the snapshot to the given path as JSON.
+    pub fn save(&self, path: impl AsRef<Path>) -> io::Result<()> {
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()
+    }
+
+    /// Reads a snapshot written by [`Snapshot::save`].
+    pub fn load(path: impl AsRef<Path>) -> io::Result<Snapshot> {
+        let reader = BufReader::new(File::open(path)?);
+        Ok(serde_json::from_reader(reader)?)
+    }
+}
+
+impl Deref for Snapshot {
+    type Target = Collection;
+
+    fn deref(&self) -> &Collection {
+        &self.collection
+    }
+}
+
+#[derive(Serialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSnapshotRef<'a> {
+    taken_at: &'a Time,
+    collection: &'a Collection,
+}
+
+#[derive(Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct SerializedSnapshot {
+    taken_at: Time,
+    collection: Collection,
+}
+
+impl Serialize for Snapshot {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        let snapshot =
+            SerializedSnapshotRef { taken_at: &self.taken_at, collection: &self.collection };
+        snapshot.serialize(serializer)
+    }
+}
+
+impl<'de> Deserialize<'de> for Snapshot {
+    fn deserialize<D>(deserializer: D) -> Result<Snapshot, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let SerializedSnapshot { taken_at, collection } =
+            SerializedSnapshot::deserialize(deserializer)?;
+        Ok(Snapshot { taken_at, collection: Arc::new(collection) })
+    }
+}
+
+impl Collection {
+    /// Returns a copy of the collection as it is now.
+    pub fn snapshot(&self) -> Snapshot {
+        // Times are saved to the second
+        let now = OffsetDateTime::now_utc().replace_nanosecond(0).expect("valid nanosecond");
+        Snapshot { taken_at: Time::new(now), collection: Arc::new(self.clone()) }
+    }
+
+    /// Replaces the contents of the collection with those of the snapshot.
+    pub fn restore(&mut self, snapshot: &Snapshot) {
+        *self = Collection::clone(&snapshot.collection);
+    }
+}
@@ -0,0 +1,50 @@
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Entity, Label};
+
+fn entity(url: &str) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        None,
+        [Label::from("foo")].into_iter().collect(),
+    )
+}
+
+#[test]
+fn test_snapshot_restore() {
+    let mut collection = Collection::new();
+    let a = collection.insert(entity("https://a.com/"));
+    let snapshot = collection.snapshot();
+    let clone = snapshot.clone();
+    assert!(Arc::ptr_eq(&snapshot.collection, &clone.collection));
+
+    // A bad import
+    let b = collection.insert(entity("https://b.com/"));
+    collection.add_edge(a, b);
+    collection.rename_label(&Label::from("foo"), Label::from("bar"));
+    assert_eq!(snapshot.len(), 1);
+    assert_eq!(snapshot.by_label(&Label::from("foo")).count(), 1);
+
+    collection.restore(&snapshot);
+    assert_eq!(collection, *clone);
+    assert_eq!(collection.by_label(&Label::from("bar")).count(), 0);
+    assert_eq!(collection.search_ranked("foo").len(), 1);
+}
+
+#[test]
+fn test_snapshot_save_load() {
+    let mut collection = Collection::new();
+    let a = collection.insert(entity("https://a.com/"));
+    let b = collection.insert(entity("https://b.com/"));
+    collection.add_edge(a, b);
+    let snapshot = collection.snapshot();
+    let path = std::env::temp_dir().join(format!("hbt-snapshot-test-{}.json", std::process::id()));
+    snapshot.save(&path).unwrap();
+    let loaded = Snapshot::load(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(loaded, snapshot);
+    assert_eq!(loaded.taken_at(), snapshot.taken_at());
+}
@@ -0,0 +1,105 @@
+//! Orderings of the entities in a [`Collection`].
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cmp::{Ordering, Reverse},
+    collections::BTreeSet,
+};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use super::{Collection, Entity, Id, Name, Time};
+
+/// What [`Collection::sorted_ids`] orders entities by.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Hash)]
+pub enum SortKey {
+    /// Oldest first.
+    CreatedAt,
+    /// Least recently updated first, taking entities that were never updated as updated when
+    /// they were created.
+    LastUpdated,
+    /// By name, comparing entities with several names first by their least name, then by the
+    /// next, and so on.  Unnamed entities come last, by URL.
+    Name,
+    Url,
+    /// Most edges to and from first.
+    Degree,
+}
+
+/// What an entity has for a [`SortKey`], ordered as [`Collection::sorted_ids`] orders entities
+/// by the key.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash, Serialize, Deserialize)]
+#[serde(rename_all = "snake_case")]
+pub enum SortValue {
+    CreatedAt(Time),
+    LastUpdated(Time),
+    Name { unnamed: bool, names: BTreeSet<Name>, url: Url },
+    Url(Url),
+    Degree(Reverse<usize>),
+}
+
+impl SortValue {
+    /// Returns the key the value is for.
+    pub fn key(&self) -> SortKey {
+        match self {
+            SortValue::CreatedAt(_) => SortKey::CreatedAt,
+            SortValue::LastUpdated(_) => SortKey::LastUpdated,
+            SortValue::Name { .. } => SortKey::Name,
+            SortValue::Url(_) => SortKey::Url,
+            SortValue::Degree(_) => SortKey::Degree,
+        }
+    }
+}
+
+impl Collection {
+    /// Returns the [`Id`]s of all entities ordered by the key, ties broken by [`Id`].
+    pub fn sorted_ids(&self, key: SortKey) -> Vec<Id> {
+        let mut ret: Vec<Id> = self.iter().map(|(id, _)| id).collect();
+        ret.sort_by(|&a, &b| self.compare(key, a, b));
+        ret
+    }
+
+    /// Compares two entities by the key alone.
+    pub(crate) fn compare(&self, key: SortKey, a: Id, b: Id) -> Ordering {
+        let (x, y) = (self.entity(a), self.entity(b));
+        match key {
+            SortKey::CreatedAt => x.created_at().cmp(y.created_at()),
+            SortKey::LastUpdated => last_updated(x).cmp(&last_updated(y)),
+            SortKey::Name => by_name(x).cmp(&by_name(y)),
+            SortKey::Url => x.url().cmp(y.url()),
+            SortKey::Degree => self.degree(b).cmp(&self.degree(a)),
+        }
+    }
+
+    /// Returns what the entity has for the key, which orders it among the values of other
+    /// entities as [`Collection::sorted_ids`] orders the entities themselves.
+    pub fn sort_value(&self, key: SortKey, id: Id) -> SortValue {
+        let entity = self.entity(id);
+        match key {
+            SortKey::CreatedAt => SortValue::CreatedAt(*entity.created_at()),
+            SortKey::LastUpdated => SortValue::LastUpdated(last_updated(entity)),
+            SortKey::Name => {
+                let (unnamed, names, url) = by_name(entity);
+                SortValue::Name { unnamed, names: names.clone(), url: url.clone() }
+            }
+            SortKey::Url => SortValue::Url(entity.url().clone()),
+            SortKey::Degree => SortValue::Degree(Reverse(self.degree(id))),
+        }
+    }
+
+    fn degree(&self, id: Id) -> usize {
+        self.edges(id).len() + self.incoming(id).len()
+    }
+}
+
+fn last_updated(entity: &Entity) -> Time {
+    entity.last_updated_at().copied().unwrap_or(*entity.created_at())
+}
+
+/// Unnamed entities sort last.
+fn by_name(entity: &Entity) -> (bool, &BTreeSet<Name>, &Url) {
+    (entity.names().is_empty(), entity.names(), entity.url())
+}
@@ -0,0 +1,81 @@
+use std::collections::BTreeSet;
+
+use time::{macros::datetime, OffsetDateTime};
+use url::Url;
+
+use super::*;
+use crate::collection::Name;
+
+fn insert(
+    collection: &mut Collection,
+    url: &str,
+    created_at: OffsetDateTime,
+    names: &[&str],
+) -> Id {
+    let mut entity =
+        Entity::new(Url::parse(url).unwrap(), created_at.into(), None, BTreeSet::new());
+    entity.names = names.iter().map(|name| Name::new(name.to_string())).collect();
+    collection.insert(entity)
+}
+
+fn create_collection() -> (Collection, [Id; 4]) {
+    let mut collection = Collection::new();
+    let a = insert(&mut collection, "https://example.com/d", datetime!(2024-03-01 0:00 UTC), &[]);
+    let b = insert(
+        &mut collection,
+        "https://example.com/c",
+        datetime!(2024-01-01 0:00 UTC),
+        &["Rust", "Cargo"],
+    );
+    let c = insert(
+        &mut collection,
+        "https://example.com/b",
+        datetime!(2024-02-01 0:00 UTC),
+        &["Cargo", "Crates"],
+    );
+    let d = insert(&mut collection, "https://example.com/a", datetime!(2024-01-01 0:00 UTC), &[]);
+    (collection, [a, b, c, d])
+}
+
+#[test]
+fn test_sorted_ids() {
+    let (mut collection, [a, b, c, d]) = create_collection();
+    // Ties are broken by id
+    assert_eq!(collection.sorted_ids(SortKey::CreatedAt), vec![b, d, c, a]);
+    assert_eq!(collection.sorted_ids(SortKey::Url), vec![d, c, b, a]);
+
+    collection.entity_mut(b).update(
+        datetime!(2024-04-01 0:00 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::new(),
+    );
+    assert_eq!(collection.sorted_ids(SortKey::LastUpdated), vec![d, c, a, b]);
+
+    collection.add_edge(a, b);
+    collection.add_edge(c, b);
+    collection.add_edge(b, d);
+    assert_eq!(collection.sorted_ids(SortKey::Degree), vec![b, a, c, d]);
+}
+
+#[test]
+fn test_sorted_ids_by_name() {
+    let (collection, [a, b, c, d]) = create_collection();
+    // Both named entities share the least name "Cargo", so their next names decide, and unnamed
+    // entities come last by URL
+    assert_eq!(collection.sorted_ids(SortKey::Name), vec![c, b, d, a]);
+}
+
+#[test]
+fn test_sort_value() {
+    let (mut collection, [a, b, c, d]) = create_collection();
+    collection.add_edge(a, b);
+    collection.add_edge(c, b);
+    for key in
+        [SortKey::CreatedAt, SortKey::LastUpdated, SortKey::Name, SortKey::Url, SortKey::Degree]
+    {
+        let mut ids = vec![a, b, c, d];
+        ids.sort_by_key(|&id| (collection.sort_value(key, id), id));
+        assert_eq!(ids, collection.sorted_ids(key), "{:?}", key);
+        assert_eq!(collection.sort_value(key, a).key(), key);
+    }
+}
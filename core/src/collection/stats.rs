@@ -0,0 +1,60 @@
+//! Summary statistics over a [`Collection`].
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeMap;
+
+use serde::Serialize;
+use time::OffsetDateTime;
+
+use super::{Collection, Id, Label};
+
+/// Counts of the entities in a [`Collection`], created by [`Collection::stats`].
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize)]
+pub struct Stats {
+    pub entities: usize,
+    pub edges: usize,
+    /// The number of entities on each host.  Entities whose URLs have no host are not counted.
+    pub domains: BTreeMap<String, usize>,
+    pub labels: BTreeMap<Label, usize>,
+    /// The number of entities created in each year, in UTC.
+    pub years: BTreeMap<i32, usize>,
+}
+
+impl Collection {
+    pub fn stats(&self) -> Stats {
+        let mut ret = Stats { entities: self.len(), ..Stats::default() };
+        ret.edges = self.edges.iter().map(Vec::len).sum();
+        for label in self.columns.labels().iter().flat_map(|labels| labels.iter()) {
+            *ret.labels.entry(label.clone()).or_default() += 1;
+        }
+        for created_at in self.columns.created_at() {
+            let year = OffsetDateTime::from(*created_at).year();
+            *ret.years.entry(year).or_default() += 1;
+        }
+        ret.domains = self.by_domain().into_iter().map(|(host, ids)| (host, ids.len())).collect();
+        ret
+    }
+
+    /// Returns the [`Id`]s of the entities on each host, in ascending order.  Entities whose URLs
+    /// have no host are left out.
+    pub fn by_domain(&self) -> BTreeMap<String, Vec<Id>> {
+        let mut ret: BTreeMap<String, Vec<Id>> = BTreeMap::new();
+        for (id, entity) in self.iter() {
+            if let Some(host) = entity.url.host_str() {
+                ret.entry(host.to_string()).or_default().push(id);
+            }
+        }
+        ret
+    }
+
+    /// Returns each host with the number of entities on it, most common first, ties broken by
+    /// host.
+    pub fn domains_by_count(&self) -> Vec<(String, usize)> {
+        let mut ret: Vec<(String, usize)> =
+            self.by_domain().into_iter().map(|(host, ids)| (host, ids.len())).collect();
+        ret.sort_by(|(a, x), (b, y)| y.cmp(x).then(a.cmp(b)));
+        ret
+    }
+}
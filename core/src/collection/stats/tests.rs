@@ -0,0 +1,69 @@
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+#[test]
+fn test_stats() {
+    let mut collection = Collection::new();
+    let mut insert = |url: &str, created_at: OffsetDateTime, labels: &[&str]| {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            created_at.into(),
+            None,
+            labels.iter().copied().map(Label::from).collect(),
+        ))
+    };
+    let a = insert("https://example.com/a", datetime!(2023-12-31 23:00 UTC), &["rust"]);
+    let b = insert("https://example.com/b", datetime!(2024-01-01 0:00 UTC), &["rust", "web"]);
+    let c = insert("https://docs.rs/", datetime!(2024-06-01 0:00 -02), &[]);
+    let _ = insert("mailto:someone@example.com", datetime!(2024-06-01 0:00 UTC), &[]);
+    collection.add_edges(a, b);
+    collection.add_edge(c, a);
+
+    let stats = collection.stats();
+    assert_eq!(stats.entities, 4);
+    assert_eq!(stats.edges, 3);
+    assert_eq!(
+        stats.domains,
+        BTreeMap::from([(String::from("docs.rs"), 1), (String::from("example.com"), 2)])
+    );
+    assert_eq!(stats.labels, BTreeMap::from([(Label::from("rust"), 2), (Label::from("web"), 1)]));
+    assert_eq!(stats.years, BTreeMap::from([(2023, 1), (2024, 3)]));
+    assert_eq!(Collection::new().stats(), Stats::default());
+}
+
+#[test]
+fn test_by_domain() {
+    let mut collection = Collection::new();
+    let mut insert = |url: &str| {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2024-01-01 0:00 UTC).into(),
+            None,
+            Default::default(),
+        ))
+    };
+    let a = insert("https://example.com/a");
+    let b = insert("https://docs.rs/serde");
+    let c = insert("https://example.com/b");
+    let d = insert("https://www.example.com/");
+    let _ = insert("mailto:someone@example.com");
+    assert_eq!(
+        collection.by_domain(),
+        BTreeMap::from([
+            (String::from("docs.rs"), vec![b]),
+            (String::from("example.com"), vec![a, c]),
+            (String::from("www.example.com"), vec![d]),
+        ])
+    );
+    assert_eq!(
+        collection.domains_by_count(),
+        vec![
+            (String::from("example.com"), 2),
+            (String::from("docs.rs"), 1),
+            (String::from("www.example.com"), 1),
+        ]
+    );
+}
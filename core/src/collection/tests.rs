@@ -108,8 +108,8 @@ fn test_entity_updates() {
     let mut deserialized: Collection = serde_json::from_str(&json).unwrap();
 
     // Update the deserialized entity
-    let updated_entity = deserialized.entity_mut(id);
-    updated_entity.update(
+    deserialized.update(
+        id,
         datetime!(2024-01-16 0:00 UTC).into(),
         vec![Name::from("Updated")].into_iter().collect(),
         vec![Label::from("tag2")].into_iter().collect(),
@@ -227,3 +227,1385 @@ fn test_update_labels_invalid_json() {
     assert!(labels.contains(&Label::from("valid-update")));
     assert!(!labels.contains(&Label::from("tag2")));
 }
+
+#[test]
+fn test_remove() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(
+        url3.clone(),
+        datetime!(2024-01-17 0:00 UTC).into(),
+        Some(Name::from("Page Three")),
+        BTreeSet::new(),
+    );
+    let id1 = Id::new(0);
+    let id3 = collection.insert(entity3);
+    collection.add_edges(id1, id3);
+
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let removed = collection.remove(id1);
+    assert_eq!(removed.url(), &url1);
+    assert_eq!(collection.len(), 2);
+    assert!(!collection.contains(&url1));
+
+    // The last entity takes the removed entity's place, in a new generation
+    let moved = collection.id(&url3).unwrap();
+    assert_eq!(moved, Id { index: 0, generation: 1 });
+    assert_eq!(collection.entity(moved).url(), &url3);
+    assert!(collection.get(id1).is_none());
+    assert!(collection.get(id3).is_none());
+
+    // Edges to the removed entity are gone
+    let id2 = collection.id(&Url::parse("https://example.com/page2").unwrap()).unwrap();
+    assert!(collection.edges(id2).is_empty());
+    assert!(collection.edges(moved).is_empty());
+}
+
+#[test]
+fn test_remove_remaps_edges() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 =
+        Entity::new(url3.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id2 = Id::new(1);
+    let id3 = collection.insert(entity3);
+    collection.add_edges(id2, id3);
+
+    collection.remove(Id::new(0));
+
+    let id2 = collection.id(&Url::parse("https://example.com/page2").unwrap()).unwrap();
+    let id3 = collection.id(&url3).unwrap();
+    assert_eq!(collection.edges(id2), &[id3]);
+    assert_eq!(collection.edges(id3), &[id2]);
+}
+
+#[test]
+fn test_merge_entities() {
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(
+        url3.clone(),
+        datetime!(2024-01-17 0:00 UTC).into(),
+        Some(Name::from("Page Three")),
+        BTreeSet::from([Label::from("tag4")]),
+    );
+    let id3 = collection.insert(entity3);
+
+    let merged = collection.merge_entities(id3, Id::new(0));
+    assert_eq!(collection.len(), 2);
+    assert!(!collection.contains(&url1));
+    assert_eq!(collection.id(&url3), Some(merged));
+
+    let entity = collection.entity(merged);
+    assert_eq!(entity.created_at(), &datetime!(2024-01-15 0:00 UTC).into());
+    let names: Vec<&str> = entity.names().iter().map(Name::as_str).collect();
+    assert_eq!(names, vec!["Page One", "Page Three"]);
+    assert_eq!(collection.by_label(&Label::from("tag1")).collect::<Vec<_>>(), vec![merged]);
+
+    // Edges to and from the merged-away entity now belong to the merged one
+    let id2 = collection.id(&url2).unwrap();
+    assert_eq!(collection.edges(merged), &[id2]);
+    assert_eq!(collection.edges(id2), &[merged]);
+}
+
+#[test]
+fn test_rehome() {
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let moved = Url::parse("https://example.org/page1").unwrap();
+    let id1 = Id::new(0);
+    let stable_id = collection.entity(id1).stable_id();
+
+    assert_eq!(collection.rehome(id1, moved.clone()), id1);
+    assert_eq!(collection.len(), 2);
+    assert_eq!(collection.id(&moved), Some(id1));
+    assert_eq!(collection.id(&url1), Some(id1));
+    let entity = collection.entity(id1);
+    assert_eq!(entity.url(), &moved);
+    assert_eq!(entity.aliases(), &BTreeSet::from([url1.clone()]));
+    assert_eq!(entity.stable_id(), stable_id);
+    assert_eq!(collection.by_stable_id(stable_id), Some(id1));
+
+    // Moving back drops the alias for the URL moved to
+    collection.rehome(id1, url1.clone());
+    assert_eq!(collection.entity(id1).aliases(), &BTreeSet::from([moved.clone()]));
+    assert_eq!(collection.id(&url1), Some(id1));
+    assert_eq!(collection.id(&moved), Some(id1));
+}
+
+#[test]
+fn test_rehome_merge() {
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 =
+        Entity::new(url3.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id3 = collection.insert(entity3);
+    collection.add_edge(id3, Id::new(0));
+
+    // Moving onto another entity's URL merges the two
+    let merged = collection.rehome(Id::new(0), url3.clone());
+    assert_eq!(collection.len(), 2);
+    assert_eq!(collection.id(&url3), Some(merged));
+    assert_eq!(collection.id(&url1), Some(merged));
+    let entity = collection.entity(merged);
+    assert_eq!(entity.url(), &url3);
+    assert_eq!(entity.aliases(), &BTreeSet::from([url1]));
+    assert_eq!(entity.stable_id(), StableId::for_url(&url3));
+    assert!(entity.names().contains(&Name::from("Page One")));
+    let id2 = collection.id(&url2).unwrap();
+    assert_eq!(collection.edges(merged), &[id2]);
+    assert_eq!(collection.incoming(merged), &[id2]);
+}
+
+#[test]
+fn test_aliases() {
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let mirror = Url::parse("https://mirror.example.com/page1").unwrap();
+    let (id1, id2) = (Id::new(0), Id::new(1));
+
+    assert!(collection.add_alias(id1, mirror.clone()));
+    assert!(!collection.add_alias(id1, mirror.clone()));
+    assert!(!collection.add_alias(id1, url1.clone()));
+    assert_eq!(collection.id(&mirror), Some(id1));
+
+    // URLs of entities are found before aliases
+    assert!(collection.add_alias(id1, url2.clone()));
+    assert_eq!(collection.id(&url2), Some(id2));
+
+    // Upserting by an alias updates the entity it belongs to
+    let labels = BTreeSet::from([Label::from("mirrored")]);
+    let entity = Entity::new(mirror.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, labels);
+    assert_eq!(collection.upsert(entity), id1);
+    assert_eq!(collection.len(), 2);
+    assert!(collection.entity(id1).labels().contains(&Label::from("mirrored")));
+
+    // An alias shared by two entities still finds one after the other loses it
+    assert!(collection.add_alias(id2, mirror.clone()));
+    assert!(collection.remove_alias(id1, &mirror));
+    assert!(!collection.remove_alias(id1, &mirror));
+    assert_eq!(collection.id(&mirror), Some(id2));
+    collection.add_alias(id1, mirror.clone());
+    collection.remove(id2);
+    assert_eq!(collection.id(&mirror), Some(id1));
+
+    let json = serde_json::to_string(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_str(&json).unwrap();
+    assert_eq!(deserialized.id(&mirror), Some(id1));
+}
+
+#[test]
+fn test_merge_aliases() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let alias = |s: &str| Url::parse(s).unwrap();
+    let mut entity = Entity::builder(url.clone()).alias(alias("https://a.example.com/")).build();
+    let other = Entity::builder(url)
+        .alias(alias("https://a.example.com/"))
+        .alias(alias("https://b.example.com/"))
+        .build();
+    entity.merge(other);
+    assert_eq!(
+        entity.aliases(),
+        &BTreeSet::from([alias("https://a.example.com/"), alias("https://b.example.com/")])
+    );
+}
+
+fn create_labeled_collection(labels: &[&str]) -> Collection {
+    let mut collection = Collection::new();
+    for (i, label) in labels.iter().enumerate() {
+        let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+        let labels = BTreeSet::from([Label::from(*label)]);
+        collection.insert(Entity::new(url, datetime!(2024-01-15 0:00 UTC).into(), None, labels));
+    }
+    for (from, to) in [(0, 1), (1, 2), (2, 3), (3, 4), (4, 0), (1, 3)] {
+        collection.add_edge(Id::new(from), Id::new(to));
+    }
+    collection
+}
+
+#[test]
+fn test_retain() {
+    let mut collection = create_labeled_collection(&["keep", "junk", "keep", "junk", "keep"]);
+    let junk = Label::from("junk");
+    collection.retain(|_, entity| !entity.labels().contains(&junk));
+    assert_eq!(collection.len(), 3);
+    let urls: Vec<&str> = collection.iter().map(|(_, entity)| entity.url().as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/0", "https://example.com/2", "https://example.com/4"]
+    );
+
+    // Entities before the first one dropped keep their ids; those moved get new ones
+    let ids: Vec<Id> = collection.iter().map(|(id, _)| id).collect();
+    assert_eq!(
+        ids,
+        vec![Id::new(0), Id { index: 1, generation: 1 }, Id { index: 2, generation: 1 }]
+    );
+    assert!(collection.get(Id::new(1)).is_none());
+    assert!(collection.get(Id::new(4)).is_none());
+    for (id, entity) in collection.iter() {
+        assert_eq!(collection.id(entity.url()), Some(id));
+    }
+    assert_eq!(collection.by_label(&junk).count(), 0);
+    assert_eq!(collection.by_label(&Label::from("keep")).collect::<Vec<_>>(), ids);
+
+    // Only the edges between kept entities remain
+    assert_eq!(collection.edges(ids[0]), &[] as &[Id]);
+    assert_eq!(collection.edges(ids[2]), &[ids[0]]);
+    assert_eq!(collection.incoming(ids[0]), &[ids[2]]);
+    assert_incoming_consistent(&collection);
+
+    let json = serde_json::to_string(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_str(&json).unwrap();
+    assert_eq!(collection, deserialized);
+
+    // Keeping everything changes nothing
+    let before = collection.clone();
+    collection.retain(|_, _| true);
+    assert_eq!(collection, before);
+    assert_eq!(collection.iter().map(|(id, _)| id).collect::<Vec<_>>(), ids);
+}
+
+#[test]
+fn test_extract_if() {
+    let mut collection = create_labeled_collection(&["junk", "keep", "keep", "junk", "junk"]);
+    collection.add_label_alias(Label::from("rubbish"), Label::from("junk"));
+    let junk = Label::from("junk");
+    let extracted = collection.extract_if(|_, entity| entity.labels().contains(&junk));
+    assert_eq!(collection.len(), 2);
+    assert_eq!(extracted.len(), 3);
+    let urls: Vec<&str> = extracted.iter().map(|(_, entity)| entity.url().as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/0", "https://example.com/3", "https://example.com/4"]
+    );
+    assert_eq!(extracted.label_aliases(), collection.label_aliases());
+
+    // Each side keeps the edges between its own entities
+    fn edges(collection: &Collection) -> Vec<(&str, &str)> {
+        collection
+            .edges_with_kinds()
+            .map(|(from, to, _)| {
+                (collection.entity(from).url().path(), collection.entity(to).url().path())
+            })
+            .collect()
+    }
+    assert_eq!(edges(&collection), vec![("/1", "/2")]);
+    assert_eq!(edges(&extracted), vec![("/3", "/4"), ("/4", "/0")]);
+    assert_incoming_consistent(&collection);
+    assert_incoming_consistent(&extracted);
+}
+
+#[test]
+fn test_subgraph() {
+    let mut collection = create_labeled_collection(&["work", "home", "work/a", "work", "home"]);
+    collection.add_edge_with_kind(Id::new(2), Id::new(0), EdgeKind::PartOf);
+    collection.add_label_alias(Label::from("job"), Label::from("work"));
+    let (subgraph, ids) = collection.subgraph_within(&Label::from("job"));
+    assert_eq!(collection.len(), 5);
+    let urls: Vec<&str> = subgraph.iter().map(|(_, entity)| entity.url().as_str()).collect();
+    assert_eq!(
+        urls,
+        vec!["https://example.com/0", "https://example.com/2", "https://example.com/3"]
+    );
+    let expected: BTreeMap<Id, Id> =
+        [(0, 0), (2, 1), (3, 2)].into_iter().map(|(a, b)| (Id::new(a), Id::new(b))).collect();
+    assert_eq!(ids, expected);
+    assert_eq!(subgraph.label_aliases(), collection.label_aliases());
+
+    // Only the edges between kept entities are copied, with their kinds
+    let edges: Vec<(usize, usize, &EdgeKind)> = subgraph
+        .edges_with_kinds()
+        .map(|(from, to, kind)| (from.index(), to.index(), kind))
+        .collect();
+    assert_eq!(edges, vec![(1, 2, &EdgeKind::RelatedTo), (1, 0, &EdgeKind::PartOf)]);
+    assert_incoming_consistent(&subgraph);
+
+    let (empty, ids) = collection.subgraph(|_, _| false);
+    assert!(empty.is_empty() && ids.is_empty());
+}
+
+#[test]
+fn test_merge_all() {
+    let mut collection = create_test_collection();
+    let entities = ["page2", "page3", "page4", "page3"].map(|path| {
+        let url = Url::parse(&format!("https://example.com/{}", path)).unwrap();
+        let labels = BTreeSet::from([Label::from(path)]);
+        Entity::new(url, datetime!(2024-01-17 0:00 UTC).into(), None, labels)
+    });
+    let merged = collection.merge_all(entities);
+    assert_eq!(merged, Imported { entities: 4, added: 2, merged: 2 });
+    assert_eq!(collection.len(), 4);
+    assert!(collection.nodes.capacity() >= 4);
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let id3 = collection.id(&url3).unwrap();
+    assert_eq!(collection.entity(id3).history().len(), 1);
+    assert_eq!(collection.by_label(&Label::from("page2")).count(), 1);
+
+    let mut empty = Collection::new();
+    assert_eq!(empty.merge_all([]), Imported::default());
+}
+
+#[test]
+fn test_remove_last() {
+    let mut collection = create_test_collection();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let removed = collection.remove_by_url(&url2).unwrap();
+    assert_eq!(removed.url(), &url2);
+    assert_eq!(collection.len(), 1);
+    assert!(collection.edges(Id::new(0)).is_empty());
+    assert!(collection.remove_by_url(&url2).is_none());
+}
+
+#[test]
+fn test_stale_id() {
+    let mut collection = create_test_collection();
+    let stale = Id::new(1);
+    collection.remove(stale);
+    assert!(collection.try_entity(Id::new(0)).is_ok());
+    let err = collection.try_entity(stale).unwrap_err();
+    assert_eq!(err.to_string(), "no such entity: 1, length: 1");
+    assert!(matches!(collection.try_entity_mut(stale), Err(Error::NoSuchEntity(1, 1))));
+    assert!(matches!(collection.try_add_edge(Id::new(0), stale), Err(Error::NoSuchEntity(1, 1))));
+    assert!(collection.try_add_edge(Id::new(0), Id::new(0)).is_ok());
+    assert!(matches!(collection.try_remove(stale), Err(Error::NoSuchEntity(1, 1))));
+    assert!(collection.try_remove(Id::new(0)).is_ok());
+    assert!(matches!(collection.try_remove(Id::new(0)), Err(Error::NoSuchEntity(0, 0))));
+}
+
+#[test]
+fn test_stale_id_everywhere() {
+    let mut collection = create_test_collection();
+    let (first, second) = (Id::new(0), Id::new(1));
+    collection.add_edge(second, first);
+    collection.remove(first);
+    let moved = collection.id_at(0);
+    let at = datetime!(2024-01-16 0:00 UTC).into();
+    let before = collection.entity(moved).clone();
+    // The stale id shares its slot with the moved entity, which must not be touched
+    let err = collection.try_update(first, at, [Name::from("wrong")].into(), [].into());
+    assert!(matches!(err, Err(Error::StaleId(0, 0))));
+    assert_eq!(collection.entity(moved), &before);
+    assert!(matches!(collection.try_edges(first), Err(Error::StaleId(0, 0))));
+    assert!(matches!(collection.try_edges(second), Err(Error::NoSuchEntity(1, 1))));
+    let err = collection.try_add_edge_with_kind(moved, first, EdgeKind::PartOf);
+    assert!(matches!(err, Err(Error::StaleId(0, 0))));
+    assert!(matches!(collection.try_remove_edge(first, moved), Err(Error::StaleId(0, 0))));
+    assert!(matches!(collection.try_merge_entities(moved, first), Err(Error::StaleId(0, 0))));
+    assert_eq!(collection.edges(moved), &[] as &[Id]);
+    assert!(collection.try_update(moved, at, [].into(), [].into()).is_ok());
+}
+
+#[test]
+#[should_panic(expected = "stale id: 0, generation: 0")]
+fn test_stale_id_update() {
+    let mut collection = create_test_collection();
+    collection.remove(Id::new(0));
+    collection.update(Id::new(0), datetime!(2024-01-16 0:00 UTC).into(), [].into(), [].into());
+}
+
+#[test]
+#[should_panic(expected = "stale id: 0, generation: 0")]
+fn test_stale_id_edges() {
+    let mut collection = create_test_collection();
+    collection.remove(Id::new(0));
+    collection.edges(Id::new(0));
+}
+
+#[test]
+#[should_panic(expected = "stale id: 0, generation: 0")]
+fn test_stale_id_merge_entities() {
+    let mut collection = create_test_collection();
+    collection.remove(Id::new(0));
+    let moved = collection.id_at(0);
+    collection.merge_entities(moved, Id::new(0));
+}
+
+#[test]
+fn test_try_insert() {
+    let mut collection = create_test_collection();
+    let url = Url::parse("https://example.com/page1").unwrap();
+    let entity = Entity::new(url.clone(), datetime!(2024-01-15 0:00 UTC).into(), None, [].into());
+    let err = collection.try_insert(entity.clone()).unwrap_err();
+    assert_eq!(err.to_string(), "duplicate URL: https://example.com/page1");
+    assert_eq!(collection.len(), 2);
+
+    // An alias counts as a URL the entity has
+    collection.remove_by_url(&url).unwrap();
+    let id = collection.id(&Url::parse("https://example.com/page2").unwrap()).unwrap();
+    let alias = Url::parse("https://example.com/alias").unwrap();
+    collection.add_alias(id, alias.clone());
+    let aliased = Entity::new(alias, datetime!(2024-01-15 0:00 UTC).into(), None, [].into());
+    assert!(matches!(collection.try_insert(aliased), Err(Error::DuplicateUrl(_))));
+    let id = collection.try_insert(entity).unwrap();
+    assert_eq!(collection.entity(id).url(), &url);
+}
+
+#[test]
+fn test_try_merge_entities() {
+    let mut collection = create_test_collection();
+    let (a, b) = (Id::new(0), Id::new(1));
+    let err = collection.try_merge_entities(a, a).unwrap_err();
+    assert_eq!(err.to_string(), "cannot merge an entity into itself: 0");
+    let into = collection.try_merge_entities(b, a).unwrap();
+    assert_eq!(collection.len(), 1);
+    assert!(matches!(collection.try_merge_entities(into, a), Err(Error::StaleId(0, 0))));
+    assert!(matches!(collection.try_merge_entities(b, into), Err(Error::NoSuchEntity(1, 1))));
+}
+
+#[test]
+fn test_generational_id() {
+    let mut collection = create_test_collection();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let first = Id::new(0);
+    collection.remove(first);
+    // The slot is reused by the moved entity, under a new generation
+    let moved = collection.id(&url2).unwrap();
+    assert_eq!(moved, Id { index: 0, generation: 1 });
+    assert!(collection.get(first).is_none());
+    assert_eq!(collection.get(moved).map(Entity::url), Some(&url2));
+    let err = collection.try_entity(first).unwrap_err();
+    assert_eq!(err.to_string(), "stale id: 0, generation: 0");
+    assert!(matches!(collection.try_remove(first), Err(Error::StaleId(0, 0))));
+    assert_eq!(collection.edges(moved), &[] as &[Id]);
+
+    // A slot emptied and filled again does not revive old ids
+    collection.remove(moved);
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let id3 = collection.insert(Entity::new(
+        url3,
+        datetime!(2024-01-17 0:00 UTC).into(),
+        None,
+        BTreeSet::new(),
+    ));
+    assert_eq!(id3, Id { index: 0, generation: 2 });
+    assert!(collection.get(first).is_none());
+    assert!(collection.get(moved).is_none());
+}
+
+#[test]
+fn test_stable_id() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let stable_id = StableId::for_url(&url);
+    assert_eq!(stable_id.to_string(), "0c8b41cfdcb3c914");
+    assert_eq!("0c8b41cfdcb3c914".parse::<StableId>().unwrap(), stable_id);
+    assert!(matches!("example".parse::<StableId>(), Err(Error::ParseInt(_))));
+    assert_eq!(serde_json::to_value(stable_id).unwrap(), json!("0c8b41cfdcb3c914"));
+
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let url2 = Url::parse("https://example.com/page2").unwrap();
+    let stable1 = collection.entity(Id::new(0)).stable_id();
+    let stable2 = collection.entity(Id::new(1)).stable_id();
+    assert_eq!(stable1, StableId::for_url(&url1));
+    assert_eq!(collection.by_stable_id(stable1), Some(Id::new(0)));
+    assert_eq!(collection.by_stable_id(stable2), Some(Id::new(1)));
+    assert_eq!(collection.by_stable_id(stable_id), None);
+
+    // Removals leave the other stable ids pointing at their entities
+    collection.remove(Id::new(0));
+    assert_eq!(collection.by_stable_id(stable1), None);
+    assert_eq!(collection.by_stable_id(stable2), collection.id(&url2));
+
+    // An entity whose stable id is taken is given another
+    let entity = |url: &Url| {
+        Entity::new(url.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new())
+    };
+    let duplicate = collection.insert(entity(&url2));
+    assert_eq!(collection.entity(duplicate).stable_id(), stable2.next());
+    assert_eq!(collection.by_stable_id(stable2.next()), Some(duplicate));
+    assert_eq!(collection.by_stable_id(stable2), collection.id(&url2));
+}
+
+#[test]
+fn test_stable_id_serialization() {
+    let collection = create_test_collection();
+    let mut value = serde_json::to_value(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_value(value.clone()).unwrap();
+    assert_eq!(collection, deserialized);
+
+    // Entities saved without a stable id are given one, after the saved ones are claimed
+    let url1 = Url::parse("https://example.com/page1").unwrap();
+    let stable1 = StableId::for_url(&url1);
+    let nodes = value["value"].as_array_mut().unwrap();
+    nodes[0]["entity"].as_object_mut().unwrap().remove("stableId");
+    nodes[1]["entity"]["stableId"] = json!(stable1.to_string());
+    let deserialized: Collection = serde_json::from_value(value).unwrap();
+    assert_eq!(deserialized.entity(Id::new(1)).stable_id(), stable1);
+    assert_eq!(deserialized.entity(Id::new(0)).stable_id(), stable1.next());
+    assert_eq!(deserialized.by_stable_id(stable1), Some(Id::new(1)));
+}
+
+#[test]
+fn test_iter() {
+    let collection = create_test_collection();
+    let urls: Vec<(Id, &str)> =
+        collection.iter().map(|(id, entity)| (id, entity.url().as_str())).collect();
+    assert_eq!(
+        urls,
+        vec![(Id::new(0), "https://example.com/page1"), (Id::new(1), "https://example.com/page2")]
+    );
+    assert_eq!(collection.iter().len(), collection.len());
+    assert_eq!((&collection).into_iter().count(), 2);
+}
+
+#[test]
+fn test_into_iter() {
+    let collection = create_test_collection();
+    let ids: Vec<Id> = collection.into_iter().map(|(id, _)| id).collect();
+    assert_eq!(ids, vec![Id::new(0), Id::new(1)]);
+}
+
+#[test]
+fn test_edges_iter() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id3 = collection.insert(entity3);
+    collection.add_edge(Id::new(1), id3);
+
+    let edges: Vec<(Id, Id)> = collection.edges_iter().collect();
+    assert_eq!(
+        edges,
+        vec![(Id::new(0), Id::new(1)), (Id::new(1), Id::new(0)), (Id::new(1), Id::new(2))]
+    );
+}
+
+#[test]
+fn test_by_label() {
+    let mut collection = create_test_collection();
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag2")).collect();
+    assert_eq!(ids, vec![id1, id2]);
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag1")).collect();
+    assert_eq!(ids, vec![id1]);
+    assert_eq!(collection.by_label(&Label::from("missing")).count(), 0);
+
+    // Merging adds new labels to the index
+    let other = Entity::new(
+        Url::parse("https://example.com/page2").unwrap(),
+        datetime!(2024-01-17 0:00 UTC).into(),
+        None,
+        vec![Label::from("tag4")].into_iter().collect(),
+    );
+    collection.upsert(other);
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag4")).collect();
+    assert_eq!(ids, vec![id2]);
+
+    // So does updating through the collection
+    collection.update(
+        id1,
+        datetime!(2024-01-18 0:00 UTC).into(),
+        BTreeSet::new(),
+        vec![Label::from("tag4")].into_iter().collect(),
+    );
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag4")).collect();
+    assert_eq!(ids, vec![id1, id2]);
+
+    // Removal drops the entity and remaps the moved one
+    collection.remove(id1);
+    let moved = Id { index: 0, generation: 1 };
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag4")).collect();
+    assert_eq!(ids, vec![moved]);
+    assert_eq!(collection.by_label(&Label::from("tag1")).count(), 0);
+}
+
+#[test]
+fn test_by_label_after_update_labels() {
+    let mut collection = create_test_collection();
+    collection.update_labels(json!({ "tag2": "tag2-updated" })).unwrap();
+
+    assert_eq!(collection.by_label(&Label::from("tag2")).count(), 0);
+    let ids: Vec<Id> = collection.by_label(&Label::from("tag2-updated")).collect();
+    assert_eq!(ids, vec![Id::new(0), Id::new(1)]);
+}
+
+#[test]
+fn test_by_label_after_deserialization() {
+    let collection = create_test_collection();
+    let json = serde_json::to_string(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_str(&json).unwrap();
+
+    let ids: Vec<Id> = deserialized.by_label(&Label::from("tag3")).collect();
+    assert_eq!(ids, vec![Id::new(1)]);
+}
+
+#[test]
+fn test_normalization() {
+    let mut collection = create_test_collection();
+    let url = Url::parse("https://EXAMPLE.com/a/../page1?").unwrap();
+    assert!(!collection.contains(&url));
+
+    collection.set_normalization(Some(url_norm::Options::default()));
+    assert_eq!(collection.id(&url), Some(Id::new(0)));
+
+    let entity = Entity::new(url, datetime!(2024-01-18 0:00 UTC).into(), None, BTreeSet::new());
+    let id = collection.upsert(entity);
+    assert_eq!(id, Id::new(0));
+    assert_eq!(collection.len(), 2);
+    assert_eq!(collection.entity(id).url().as_str(), "https://example.com/page1");
+
+    collection.set_normalization(None);
+    assert!(!collection.contains(&Url::parse("https://example.com/page1?").unwrap()));
+}
+
+#[test]
+fn test_normalization_collisions() {
+    let mut collection = create_test_collection();
+    let url1 = Url::parse("https://example.com/page?a=1&b=2").unwrap();
+    let url2 = Url::parse("https://example.com/page?b=2&a=1").unwrap();
+    for url in [&url1, &url2] {
+        let entity =
+            Entity::new(url.clone(), datetime!(2024-01-18 0:00 UTC).into(), None, BTreeSet::new());
+        collection.insert(entity);
+    }
+    collection.set_normalization(Some(url_norm::Options::default()));
+    assert_eq!(collection.id(&url2), Some(Id::new(2)));
+
+    // Removing the entity found by lookup falls back to the other
+    collection.remove(Id::new(2));
+    let id = collection.id(&url1).unwrap();
+    assert_eq!(collection.entity(id).url(), &url2);
+}
+
+#[test]
+fn test_url_filter() {
+    let mut collection = create_test_collection();
+    collection.set_url_filter(Some(url_norm::TrackingFilter::default().with_param("ref")));
+
+    let url = Url::parse("https://example.com/page3?id=3&utm_source=feed&ref=hn").unwrap();
+    let entity =
+        Entity::new(url.clone(), datetime!(2024-01-18 0:00 UTC).into(), None, BTreeSet::new());
+    let id = collection.upsert(entity);
+    assert_eq!(collection.entity(id).url().as_str(), "https://example.com/page3?id=3");
+    assert_eq!(collection.id(&url), Some(id));
+
+    let url = Url::parse("https://example.com/page1?fbclid=abc").unwrap();
+    let entity = Entity::new(url, datetime!(2024-01-18 0:00 UTC).into(), None, BTreeSet::new());
+    assert_eq!(collection.upsert(entity), Id::new(0));
+    assert_eq!(collection.len(), 3);
+}
+
+#[test]
+fn test_add_name() {
+    let mut collection = create_test_collection();
+    let id = Id::new(1);
+    assert!(collection.add_name(id, Name::from("Second")));
+    assert!(!collection.add_name(id, Name::from("Second")));
+    assert_eq!(collection.entity(id).updated_at().len(), 0);
+    assert_eq!(collection.by_name("Second").collect::<Vec<_>>(), vec![id]);
+}
+
+#[test]
+fn test_search() {
+    let collection = create_test_collection();
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+
+    let ids: Vec<Id> = collection.search("page two").collect();
+    assert_eq!(ids, vec![id2]);
+    let ids: Vec<Id> = collection.search("TAG2").collect();
+    assert_eq!(ids, vec![id1, id2]);
+    let ids: Vec<Id> = collection.search("page1").collect();
+    assert_eq!(ids, vec![id1]);
+    assert_eq!(collection.search("nothing").count(), 0);
+}
+
+#[cfg(feature = "regex")]
+#[test]
+fn test_grep() {
+    let collection = create_test_collection();
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+
+    let regex = |pattern: &str| regex::Regex::new(pattern).unwrap();
+    let ids: Vec<Id> = collection.grep(&regex(r"page\d$")).collect();
+    assert_eq!(ids, vec![id1, id2]);
+    let ids: Vec<Id> = collection.grep(&regex("^Page T")).collect();
+    assert_eq!(ids, vec![id2]);
+    let ids: Vec<Id> = collection.grep(&regex("^tag[13]$")).collect();
+    assert_eq!(ids, vec![id1, id2]);
+    assert_eq!(collection.grep(&regex("(?i)^PAGE ONE$")).count(), 1);
+    assert_eq!(collection.grep(&regex("^page one$")).count(), 0);
+}
+
+#[test]
+fn test_by_name() {
+    let mut collection = create_test_collection();
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+
+    let ids: Vec<Id> = collection.by_name("Page One").collect();
+    assert_eq!(ids, vec![id1]);
+    assert_eq!(collection.by_name("Page Three").count(), 0);
+
+    collection.update(
+        id2,
+        datetime!(2024-01-17 0:00 UTC).into(),
+        vec![Name::from("Page One")].into_iter().collect(),
+        BTreeSet::new(),
+    );
+    let ids: Vec<Id> = collection.by_name("Page One").collect();
+    assert_eq!(ids, vec![id1, id2]);
+
+    collection.remove(id1);
+    let moved = Id { index: 0, generation: 1 };
+    let ids: Vec<Id> = collection.by_name("Page One").collect();
+    assert_eq!(ids, vec![moved]);
+    let ids: Vec<Id> = collection.by_name("Page Two").collect();
+    assert_eq!(ids, vec![moved]);
+}
+
+#[test]
+fn test_serialization_preserves_ids() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 =
+        Entity::new(url3.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id3 = collection.insert(entity3);
+    collection.add_edge(id3, Id::new(1));
+    collection.remove(Id::new(0));
+
+    let json = serde_json::to_string(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_str(&json).unwrap();
+
+    // Generations are not serialized, so the ids keep their indexes
+    assert_eq!(collection, deserialized);
+    for ((id, entity), (other, _)) in collection.iter().zip(deserialized.iter()) {
+        assert_eq!(deserialized.id(entity.url()), Some(other));
+        assert_eq!(id.index(), other.index());
+        let indexes = |edges: &[Id]| edges.iter().map(Id::index).collect::<Vec<_>>();
+        assert_eq!(indexes(deserialized.edges(other)), indexes(collection.edges(id)));
+    }
+}
+
+#[test]
+fn test_deserialization_invalid_id() {
+    let json = json!({
+        "version": "0.1.0",
+        "length": 1,
+        "value": [{
+            "id": 1,
+            "entity": serde_json::to_value(create_test_collection().entity(Id::new(0))).unwrap(),
+            "edges": []
+        }]
+    });
+    let err = serde_json::from_value::<Collection>(json).unwrap_err();
+    assert_eq!(err.to_string(), "invalid id: 1, expected: 0");
+}
+
+#[test]
+fn test_deserialization_invalid_edge() {
+    let json = json!({
+        "version": "0.1.0",
+        "length": 1,
+        "value": [{
+            "id": 0,
+            "entity": serde_json::to_value(create_test_collection().entity(Id::new(0))).unwrap(),
+            "edges": [3]
+        }]
+    });
+    let err = serde_json::from_value::<Collection>(json).unwrap_err();
+    assert_eq!(err.to_string(), "invalid edge: 0 -> 3");
+}
+
+#[test]
+fn test_deserialization_duplicate_url() {
+    let entity = serde_json::to_value(create_test_collection().entity(Id::new(0))).unwrap();
+    let json = json!({
+        "version": "0.1.0",
+        "length": 2,
+        "value": [
+            { "id": 0, "entity": entity, "edges": [] },
+            { "id": 1, "entity": entity, "edges": [] }
+        ]
+    });
+    let err = serde_json::from_value::<Collection>(json).unwrap_err();
+    assert_eq!(err.to_string(), format!("duplicate URL: {}", entity["uri"].as_str().unwrap()));
+}
+
+#[test]
+fn test_edge_kinds() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+    let id3 = collection.insert(entity3);
+
+    assert_eq!(collection.edge_kind(id1, id2), Some(&EdgeKind::RelatedTo));
+    assert_eq!(collection.edge_kind(id1, id3), None);
+
+    collection.add_edge_with_kind(id1, id3, EdgeKind::Supersedes);
+    collection.add_edge_with_kind(id3, id2, EdgeKind::from("cites"));
+    // Adding an untyped edge leaves an existing kind alone
+    collection.add_edge(id1, id3);
+    assert_eq!(collection.edge_kinds(id1), &[EdgeKind::RelatedTo, EdgeKind::Supersedes]);
+    assert_eq!(collection.edge_kind(id3, id2), Some(&EdgeKind::Custom(String::from("cites"))));
+
+    collection.add_edge_with_kind(id1, id2, EdgeKind::PartOf);
+    let part_of: Vec<Id> = collection.edges_of_kind(id1, &EdgeKind::PartOf).collect();
+    assert_eq!(part_of, vec![id2]);
+
+    let edges: Vec<(Id, Id, &EdgeKind)> = collection.edges_with_kinds().collect();
+    assert_eq!(edges.len(), 4);
+    assert_eq!(edges[1], (id1, id3, &EdgeKind::Supersedes));
+}
+
+#[test]
+fn test_edge_kinds_remove() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 =
+        Entity::new(url3.clone(), datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id3 = collection.insert(entity3);
+    collection.add_edge_with_kind(Id::new(1), id3, EdgeKind::PartOf);
+
+    collection.remove(Id::new(0));
+    let id2 = collection.id(&Url::parse("https://example.com/page2").unwrap()).unwrap();
+    let id3 = collection.id(&url3).unwrap();
+    assert_eq!(collection.edges(id2), &[id3]);
+    assert_eq!(collection.edge_kinds(id2), &[EdgeKind::PartOf]);
+}
+
+#[test]
+fn test_edge_kinds_serialization() {
+    let mut collection = create_test_collection();
+    let plain = serde_json::to_value(&collection).unwrap();
+    assert!(plain["value"][0].get("kinds").is_none());
+
+    collection.add_edge_with_kind(Id::new(0), Id::new(1), EdgeKind::from("cites"));
+    let value = serde_json::to_value(&collection).unwrap();
+    assert_eq!(value["value"][0]["kinds"], json!(["cites"]));
+    let deserialized: Collection = serde_json::from_value(value).unwrap();
+    assert_eq!(collection, deserialized);
+}
+
+#[test]
+fn test_deserialization_invalid_edge_kinds() {
+    let json = json!({
+        "version": "0.1.0",
+        "length": 1,
+        "value": [{
+            "id": 0,
+            "entity": serde_json::to_value(create_test_collection().entity(Id::new(0))).unwrap(),
+            "edges": [0],
+            "kinds": ["partOf", "supersedes"]
+        }]
+    });
+    let err = serde_json::from_value::<Collection>(json).unwrap_err();
+    assert_eq!(err.to_string(), "invalid edge kinds: 0, length: 2, expected: 1");
+}
+
+#[test]
+fn test_undirected_edges() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+    let id3 = collection.insert(entity3);
+    assert!(collection.is_undirected_edge(id1, id2));
+
+    collection.add_edge(id3, id1);
+    assert!(!collection.is_undirected_edge(id1, id3));
+    collection.add_undirected_edge_with_kind(id1, id3, EdgeKind::PartOf);
+    assert!(collection.is_undirected_edge(id3, id1));
+    assert_eq!(collection.edge_kind(id3, id1), Some(&EdgeKind::PartOf));
+
+    collection.add_edge_with_kind(id1, id2, EdgeKind::Supersedes);
+    assert!(!collection.is_undirected_edge(id1, id2));
+}
+
+#[test]
+fn test_neighbors() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+    let id3 = collection.insert(entity3);
+    collection.add_edge(id3, id2);
+    collection.add_edge(id3, id3);
+
+    assert_eq!(collection.neighbors(id1).collect::<Vec<_>>(), vec![id2]);
+    // Incoming edges count too
+    assert_eq!(collection.neighbors(id2).collect::<Vec<_>>(), vec![id1, id3]);
+    assert_eq!(collection.neighbors(id3).collect::<Vec<_>>(), vec![id2]);
+}
+
+#[test]
+fn test_remove_edge() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+    let id3 = collection.insert(entity3);
+    collection.add_edge_with_kind(id1, id3, EdgeKind::PartOf);
+
+    assert_eq!(collection.remove_edge(id1, id2), Some(EdgeKind::RelatedTo));
+    assert_eq!(collection.remove_edge(id1, id2), None);
+    assert_eq!(collection.edges(id1), &[id3]);
+    assert_eq!(collection.edge_kinds(id1), &[EdgeKind::PartOf]);
+    assert_eq!(collection.edges(id2), &[id1]);
+}
+
+#[test]
+fn test_clear_edges() {
+    let mut collection = create_test_collection();
+    let id1 = Id::new(0);
+    let id2 = Id::new(1);
+    collection.clear_edges(id1);
+    assert!(collection.edges(id1).is_empty());
+    assert!(collection.edge_kinds(id1).is_empty());
+    assert_eq!(collection.edges(id2), &[id1]);
+}
+
+/// Checks that [`Collection::incoming`] agrees with the edges it is derived from.
+fn assert_incoming_consistent(collection: &Collection) {
+    for (id, _) in collection.iter() {
+        let expected: Vec<Id> = collection
+            .iter()
+            .map(|(from, _)| from)
+            .filter(|&from| collection.edges(from).contains(&id))
+            .collect();
+        assert_eq!(collection.incoming(id), expected.as_slice(), "incoming edges of {}", id.index);
+    }
+}
+
+#[test]
+fn test_incoming() {
+    let mut collection = Collection::new();
+    let ids: Vec<Id> = (0..5)
+        .map(|i| {
+            let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+            collection.insert(Entity::new(
+                url,
+                datetime!(2024-01-15 0:00 UTC).into(),
+                None,
+                BTreeSet::new(),
+            ))
+        })
+        .collect();
+    for (from, to) in [(3, 0), (1, 0), (4, 4), (0, 4), (4, 1), (2, 4)] {
+        collection.add_edge(ids[from], ids[to]);
+    }
+    assert_eq!(collection.incoming(ids[0]), &[ids[1], ids[3]]);
+    assert_eq!(collection.incoming(ids[4]), &[ids[0], ids[2], ids[4]]);
+    assert_incoming_consistent(&collection);
+
+    collection.remove_edge(ids[1], ids[0]);
+    assert_eq!(collection.incoming(ids[0]), &[ids[3]]);
+    collection.clear_edges(ids[4]);
+    assert_eq!(collection.incoming(ids[1]), &[] as &[Id]);
+    assert_incoming_consistent(&collection);
+
+    collection.add_edge(ids[4], ids[4]);
+    collection.add_edge(ids[4], ids[2]);
+    collection.remove(ids[1]);
+    assert_incoming_consistent(&collection);
+    collection.remove(ids[0]);
+    assert_incoming_consistent(&collection);
+
+    let json = serde_json::to_string(&collection).unwrap();
+    let deserialized: Collection = serde_json::from_str(&json).unwrap();
+    assert_eq!(collection, deserialized);
+}
+
+#[test]
+fn test_merge_entities_incoming() {
+    let mut collection = create_test_collection();
+    let url3 = Url::parse("https://example.com/page3").unwrap();
+    let entity3 = Entity::new(url3, datetime!(2024-01-17 0:00 UTC).into(), None, BTreeSet::new());
+    let id3 = collection.insert(entity3);
+    collection.add_edge(id3, Id::new(1));
+    collection.merge_entities(Id::new(0), Id::new(1));
+    assert_incoming_consistent(&collection);
+}
+
+#[test]
+fn test_notes() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let mut entity =
+        Entity::new(url.clone(), datetime!(2024-01-15 0:00 UTC).into(), None, BTreeSet::new());
+    let value = serde_json::to_value(&entity).unwrap();
+    assert!(value.get("notes").is_none());
+
+    let later = Note::new(datetime!(2024-01-17 0:00 UTC).into(), String::from("Read again"));
+    let earlier = Note::new(datetime!(2024-01-16 0:00 UTC).into(), String::from("Skimmed"));
+    assert!(entity.add_note(later.clone()));
+    assert!(entity.add_note(earlier.clone()));
+    assert!(!entity.add_note(later.clone()));
+    assert_eq!(entity.notes(), &[earlier.clone(), later.clone()]);
+
+    let value = serde_json::to_value(&entity).unwrap();
+    assert_eq!(
+        value["notes"],
+        json!([
+            {"createdAt": "2024-01-16T00:00:00Z", "text": "Skimmed"},
+            {"createdAt": "2024-01-17T00:00:00Z", "text": "Read again"},
+        ])
+    );
+    assert_eq!(serde_json::from_value::<Entity>(value).unwrap(), entity);
+
+    let mut collection = Collection::new();
+    collection.insert(entity);
+    let mut other =
+        Entity::new(url.clone(), datetime!(2024-01-18 0:00 UTC).into(), None, BTreeSet::new());
+    let newest = Note::new(datetime!(2024-01-18 0:00 UTC).into(), String::from("Outdated"));
+    other.add_note(later.clone());
+    other.add_note(newest.clone());
+    let id = collection.upsert(other);
+    assert_eq!(collection.entity(id).notes(), &[earlier, later, newest]);
+}
+
+#[test]
+fn test_read_status() {
+    let mut collection = Collection::new();
+    let ids: Vec<Id> = [
+        ("https://example.com/a", datetime!(2024-01-17 0:00 UTC)),
+        ("https://example.com/b", datetime!(2024-01-15 0:00 UTC)),
+        ("https://example.com/c", datetime!(2024-01-16 0:00 UTC)),
+    ]
+    .into_iter()
+    .map(|(url, created_at)| {
+        let url = Url::parse(url).unwrap();
+        collection.insert(Entity::new(url, created_at.into(), None, BTreeSet::new()))
+    })
+    .collect();
+    assert_eq!(collection.unread(), vec![ids[1], ids[2], ids[0]]);
+
+    let on: Time = datetime!(2024-01-18 0:00 UTC).into();
+    collection.entity_mut(ids[2]).mark_read(on);
+    collection.entity_mut(ids[0]).archive();
+    assert_eq!(collection.unread(), vec![ids[1]]);
+    assert_eq!(collection.entity(ids[2]).read_status(), &ReadStatus::Read { on });
+
+    let value = serde_json::to_value(collection.entity(ids[2])).unwrap();
+    assert_eq!(value["readStatus"], json!({"status": "read", "on": "2024-01-18T00:00:00Z"}));
+    assert!(serde_json::to_value(collection.entity(ids[1])).unwrap().get("readStatus").is_none());
+
+    // Merging keeps the further along status
+    let url = collection.entity(ids[0]).url().clone();
+    let mut other = Entity::new(url, datetime!(2024-01-19 0:00 UTC).into(), None, BTreeSet::new());
+    other.mark_read(on);
+    let id = collection.upsert(other);
+    assert_eq!(collection.entity(id).read_status(), &ReadStatus::Archived);
+
+    collection.entity_mut(ids[0]).mark_unread();
+    assert_eq!(collection.unread(), vec![ids[1], ids[0]]);
+}
+
+#[test]
+fn test_date_ranges() {
+    let mut collection = Collection::new();
+    let ids: Vec<Id> = [
+        ("https://example.com/a", datetime!(2024-01-17 0:00 UTC)),
+        ("https://example.com/b", datetime!(2024-01-15 0:00 UTC)),
+        ("https://example.com/c", datetime!(2024-01-16 0:00 UTC)),
+        ("https://example.com/d", datetime!(2024-01-15 0:00 UTC)),
+    ]
+    .into_iter()
+    .map(|(url, created_at)| {
+        let url = Url::parse(url).unwrap();
+        collection.insert(Entity::new(url, created_at.into(), None, BTreeSet::new()))
+    })
+    .collect();
+    let between = |collection: &Collection, start, end| {
+        collection.created_between(Time::from(start), Time::from(end)).collect::<Vec<_>>()
+    };
+    let (jan15, jan17) = (datetime!(2024-01-15 0:00 UTC), datetime!(2024-01-17 0:00 UTC));
+    assert_eq!(between(&collection, jan15, jan17), vec![ids[1], ids[3], ids[2]]);
+    assert_eq!(between(&collection, jan17, jan15), vec![]);
+    assert!(collection.updated_since(Time::from(jan15)).next().is_none());
+
+    let at = |day| Time::from(datetime!(2024-01-01 0:00 UTC) + time::Duration::days(day));
+    collection.update(ids[0], at(20), BTreeSet::new(), BTreeSet::new());
+    collection.update(ids[3], at(18), BTreeSet::new(), BTreeSet::new());
+    // An update from before an entity was created moves its creation back
+    collection.update(ids[2], at(9), BTreeSet::new(), BTreeSet::new());
+    assert_eq!(collection.updated_since(at(17)).collect::<Vec<_>>(), vec![ids[3], ids[0]]);
+    assert_eq!(between(&collection, jan15, jan17), vec![ids[1], ids[3]]);
+
+    // Removing an entity moves the last into its slot
+    collection.remove(ids[1]);
+    let d = collection.id(&Url::parse("https://example.com/d").unwrap()).unwrap();
+    assert_eq!(between(&collection, jan15, jan17), vec![d]);
+    assert_eq!(collection.updated_since(at(0)).count(), 3);
+}
+
+#[test]
+fn test_starred() {
+    let mut collection = Collection::new();
+    let ids: Vec<Id> = (0..4)
+        .map(|i| {
+            let url = Url::parse(&format!("https://example.com/{}", i)).unwrap();
+            let created_at = datetime!(2024-01-15 0:00 UTC).into();
+            collection.insert(Entity::new(url, created_at, None, BTreeSet::new()))
+        })
+        .collect();
+    assert!(collection.starred().is_empty());
+    collection.entity_mut(ids[3]).set_starred(true);
+    collection.entity_mut(ids[1]).set_starred(true);
+    assert_eq!(collection.starred(), vec![ids[1], ids[3]]);
+    assert_eq!(collection.starred_first(), vec![ids[1], ids[3], ids[0], ids[2]]);
+
+    let value = serde_json::to_value(collection.entity(ids[1])).unwrap();
+    assert_eq!(value["starred"], json!(true));
+    assert!(serde_json::to_value(collection.entity(ids[0])).unwrap().get("starred").is_none());
+
+    let url = collection.entity(ids[0]).url().clone();
+    let mut other = Entity::new(url, datetime!(2024-01-16 0:00 UTC).into(), None, BTreeSet::new());
+    other.set_starred(true);
+    let id = collection.upsert(other);
+    assert!(collection.entity(id).starred());
+}
+
+#[test]
+fn test_metadata() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let mut entity =
+        Entity::new(url.clone(), datetime!(2024-01-15 0:00 UTC).into(), None, BTreeSet::new());
+    assert!(serde_json::to_value(&entity).unwrap().get("metadata").is_none());
+    assert_eq!(entity.set_metadata("source".into(), "pocket".into()), None);
+    assert_eq!(entity.set_metadata("id".into(), "1".into()), None);
+    assert_eq!(entity.metadata_value("source"), Some("pocket"));
+
+    let value = serde_json::to_value(&entity).unwrap();
+    assert_eq!(value["metadata"], json!({"id": "1", "source": "pocket"}));
+    assert_eq!(serde_json::from_value::<Entity>(value).unwrap(), entity);
+
+    let mut collection = Collection::new();
+    collection.insert(entity);
+    let mut other = Entity::new(url, datetime!(2024-01-16 0:00 UTC).into(), None, BTreeSet::new());
+    other.set_metadata("source".into(), "pinboard".into());
+    other.set_metadata("hash".into(), "abc".into());
+    let id = collection.upsert(other);
+    let entity = collection.entity_mut(id);
+    // Existing values win
+    assert_eq!(entity.metadata_value("source"), Some("pocket"));
+    assert_eq!(entity.metadata_value("hash"), Some("abc"));
+    assert_eq!(entity.remove_metadata("id"), Some("1".into()));
+    assert_eq!(entity.metadata().len(), 2);
+}
+
+#[test]
+fn test_time_parse() {
+    let parse = |time| Time::parse(time).unwrap();
+    assert_eq!(parse("1705276800"), Time::from(datetime!(2024-01-15 0:00 UTC)));
+    assert_eq!(parse("2024-01-15"), Time::from(datetime!(2024-01-15 0:00 UTC)));
+    assert_eq!(parse("2024-01-15T10:30:00+02:00"), Time::from(datetime!(2024-01-15 8:30 UTC)));
+    assert!(Time::parse("January 15").is_err());
+}
+
+#[test]
+fn test_time_deserialization() {
+    let entity = |created_at: Value| {
+        let value = json!({
+            "uri": "https://example.com/",
+            "createdAt": created_at,
+            "updatedAt": ["2024-01-16T12:00:00Z"],
+            "names": [],
+            "labels": [],
+            "shared": false,
+            "toread": false,
+        });
+        serde_json::from_value::<Entity>(value)
+    };
+    let expected = Time::from(datetime!(2024-01-15 0:00 UTC));
+    assert_eq!(entity(json!(1705276800)).unwrap().created_at(), &expected);
+    assert_eq!(entity(json!("2024-01-15")).unwrap().created_at(), &expected);
+    let parsed = entity(json!("2024-01-15T09:00:00+09:00")).unwrap();
+    assert_eq!(parsed.created_at(), &expected);
+    assert_eq!(parsed.last_updated_at(), Some(&Time::from(datetime!(2024-01-16 12:00 UTC))));
+    // Times are written as RFC 3339, keeping their offset
+    assert_eq!(
+        serde_json::to_value(&parsed).unwrap()["createdAt"],
+        json!("2024-01-15T09:00:00+09:00")
+    );
+    let precise = entity(json!("2024-01-15T09:00:00.25-05:00")).unwrap();
+    let written = serde_json::to_value(&precise).unwrap();
+    assert_eq!(written["createdAt"], json!("2024-01-15T09:00:00.25-05:00"));
+    assert_eq!(serde_json::from_value::<Entity>(written).unwrap(), precise);
+    assert!(entity(json!("soon")).is_err());
+}
+
+#[test]
+fn test_history() {
+    let url = Url::parse("https://example.com/").unwrap();
+    let mut collection = Collection::new();
+    let id = collection.insert(Entity::new(
+        url.clone(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        Some(Name::from("Example")),
+        BTreeSet::from([Label::from("foo")]),
+    ));
+    collection.update(
+        id,
+        datetime!(2024-01-16 0:00 UTC).into(),
+        BTreeSet::from([Name::from("Example"), Name::from("Example Domain")]),
+        BTreeSet::from([Label::from("foo"), Label::from("bar")]),
+    );
+    let other = Entity::new(
+        url,
+        datetime!(2024-01-17 0:00 UTC).into(),
+        None,
+        BTreeSet::from([Label::from("baz")]),
+    );
+    collection.upsert_from(other, Some(String::from("import.md")));
+
+    let entity = collection.entity(id);
+    let history = entity.history();
+    assert_eq!(history.len(), 2);
+    assert_eq!(history[0].at, Time::from(datetime!(2024-01-16 0:00 UTC)));
+    assert_eq!(history[0].names_added, BTreeSet::from([Name::from("Example Domain")]));
+    assert_eq!(history[0].labels_added, BTreeSet::from([Label::from("bar")]));
+    assert_eq!(history[0].source, None);
+    assert_eq!(history[1].labels_added, BTreeSet::from([Label::from("baz")]));
+    assert_eq!(history[1].source.as_deref(), Some("import.md"));
+    assert_eq!(entity.last_updated_at(), Some(&Time::from(datetime!(2024-01-17 0:00 UTC))));
+    assert_eq!(
+        entity.label_added_at(&Label::from("foo")),
+        Some(&Time::from(datetime!(2024-01-15 0:00 UTC)))
+    );
+    assert_eq!(
+        entity.label_added_at(&Label::from("baz")),
+        Some(&Time::from(datetime!(2024-01-17 0:00 UTC)))
+    );
+    assert_eq!(entity.label_added_at(&Label::from("qux")), None);
+
+    let value = serde_json::to_value(entity).unwrap();
+    assert_eq!(
+        value["updatedAt"],
+        json!([
+            {"at": "2024-01-16T00:00:00Z", "namesAdded": ["Example Domain"], "labelsAdded": ["bar"]},
+            {"at": "2024-01-17T00:00:00Z", "labelsAdded": ["baz"], "source": "import.md"},
+        ])
+    );
+    assert_eq!(&serde_json::from_value::<Entity>(value).unwrap(), entity);
+}
+
+#[test]
+fn test_history_bare_updates() {
+    let update = Update::new(datetime!(2024-01-16 0:00 UTC).into());
+    assert_eq!(serde_json::to_value(&update).unwrap(), json!("2024-01-16T00:00:00Z"));
+    let parsed: Vec<Update> =
+        serde_json::from_value(json!([1705363200, {"at": 1705363200}])).unwrap();
+    assert_eq!(parsed, vec![update.clone(), update]);
+}
+
+#[test]
+fn test_rename_label() {
+    let mut collection = Collection::new();
+    for (url, labels) in [
+        ("https://example.com/a", &["rustlang", "async"][..]),
+        ("https://example.com/b", &["rustlang", "rust"][..]),
+        ("https://example.com/c", &["go"][..]),
+    ] {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            labels.iter().copied().map(Label::from).collect(),
+        ));
+    }
+    let rustlang = Label::from("rustlang");
+    let rust = Label::from("rust");
+    assert_eq!(collection.rename_label(&rustlang, rust.clone()), 2);
+    assert_eq!(collection.by_label(&rustlang).count(), 0);
+    assert_eq!(collection.by_label(&rust).collect::<Vec<_>>(), vec![Id::new(0), Id::new(1)]);
+    assert_eq!(collection.entity(Id::new(1)).labels(), &BTreeSet::from([Label::from("rust")]));
+    assert_eq!(collection.rename_label(&rustlang, rust.clone()), 0);
+    assert_eq!(collection.rename_label(&rust, rust.clone()), 2);
+}
+
+#[test]
+fn test_merge_label() {
+    let mut collection = Collection::new();
+    let entity = |url: &str, labels: &[&str]| {
+        Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            labels.iter().copied().map(Label::from).collect(),
+        )
+    };
+    let a = collection.insert(entity("https://example.com/a", &["rustlang"]));
+    let rust = Label::from("rust");
+    let rustlang = Label::from("rustlang");
+    assert_eq!(collection.merge_label(&rustlang, rust.clone()), 1);
+    assert_eq!(collection.entity(a).labels(), &BTreeSet::from([rust.clone()]));
+    assert_eq!(collection.resolve_label(&rustlang), &rust);
+
+    // Later additions and updates with the old label get the new one
+    let b = collection.insert(entity("https://example.com/b", &["rustlang", "web"]));
+    assert_eq!(collection.entity(b).labels(), &BTreeSet::from([rust.clone(), Label::from("web")]));
+    let c = collection.upsert(entity("https://example.com/a", &["rustlang"]));
+    assert_eq!(collection.entity(c).labels(), &BTreeSet::from([rust.clone()]));
+    collection.update(
+        c,
+        datetime!(2024-01-16 0:00 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::from([rustlang.clone()]),
+    );
+    assert_eq!(collection.by_label(&rustlang).count(), 0);
+
+    // Chains of aliases are followed
+    assert_eq!(collection.merge_label(&rust, Label::from("rs")), 2);
+    assert_eq!(collection.resolve_label(&rustlang), &Label::from("rs"));
+    assert!(!collection.add_label_alias(Label::from("rs"), rustlang));
+
+    let json = serde_json::to_value(&collection).unwrap();
+    assert_eq!(json["labelAliases"], json!({"rust": "rs", "rustlang": "rs"}));
+    let deserialized: Collection = serde_json::from_value(json).unwrap();
+    assert_eq!(deserialized.label_aliases(), collection.label_aliases());
+}
+
+#[test]
+fn test_label_hierarchy() {
+    let label = Label::from("lang/rust/async");
+    assert_eq!(label.segments().collect::<Vec<_>>(), vec!["lang", "rust", "async"]);
+    assert_eq!(label.parent(), Some(Label::from("lang/rust")));
+    assert_eq!(
+        label.ancestors().collect::<Vec<_>>(),
+        vec![Label::from("lang/rust"), Label::from("lang")]
+    );
+    assert_eq!(Label::from("lang").parent(), None);
+    assert_eq!(Label::from("lang//rust/").parent(), Some(Label::from("lang")));
+    assert!(label.is_within(&Label::from("lang/rust")));
+    assert!(label.is_within(&label));
+    assert!(!label.is_within(&Label::from("lang/ru")));
+    assert!(!Label::from("lang").is_within(&label));
+    assert!(!label.is_within(&Label::from("")));
+}
+
+#[test]
+fn test_by_label_within() {
+    let mut collection = Collection::new();
+    let mut insert = |url: &str, label: &str| {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2024-01-15 0:00 UTC).into(),
+            None,
+            BTreeSet::from([Label::from(label)]),
+        ))
+    };
+    let a = insert("https://example.com/a", "lang/rust/async");
+    let b = insert("https://example.com/b", "lang/rust");
+    let _ = insert("https://example.com/c", "lang/rustic");
+    let d = insert("https://example.com/d", "lang/go");
+    let ids: Vec<Id> = collection.by_label_within(&Label::from("lang/rust")).collect();
+    assert_eq!(ids, vec![a, b]);
+    assert_eq!(collection.by_label_within(&Label::from("lang")).count(), 4);
+    let ids: Vec<Id> = collection.by_label_within(&Label::from("lang/go")).collect();
+    assert_eq!(ids, vec![d]);
+    assert_eq!(collection.by_label(&Label::from("lang")).count(), 0);
+}
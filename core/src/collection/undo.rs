@@ -0,0 +1,383 @@
+//! Undoing and redoing edits to a [`Collection`].
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeSet, VecDeque},
+    ops::Deref,
+};
+
+use super::{Collection, EdgeKind, Entity, Id, Label, Name, StableId, Time};
+
+/// An entity taken out of a collection, with what it takes to put it back where it was.
+#[derive(Debug, Clone)]
+struct Removed {
+    index: usize,
+    entity: Entity,
+    /// The entity's edges, in order.
+    edges: Vec<(StableId, EdgeKind)>,
+    /// The edges to the entity from others, with their positions among the edges of their sources.
+    incoming: Vec<(StableId, usize, EdgeKind)>,
+}
+
+/// A change to a collection that [`Op::apply`] can make, returning the change that reverts it.
+///
+/// Entities are referred to by [`StableId`], since reverting a removal gives the entities involved
+/// new [`Id`]s.
+#[derive(Debug, Clone)]
+enum Op {
+    Remove(StableId),
+    Restore(Box<Removed>),
+    /// Puts the entity in place of the one with the same stable id and URL.
+    Replace(Box<Entity>),
+    /// Gives the edge the kind, at the position among the edges of `from`, or removes it.
+    SetEdge {
+        from: StableId,
+        to: StableId,
+        edge: Option<(usize, EdgeKind)>,
+    },
+    SetName(Option<String>),
+    Batch(Vec<Op>),
+}
+
+impl Removed {
+    /// Records what it takes to put the entity with the given [`Id`] back once it is removed.
+    fn of(collection: &Collection, id: Id) -> Removed {
+        let stable_id = |id: Id| collection.nodes[id].stable_id();
+        let edges = collection.edges[id]
+            .iter()
+            .zip(&collection.kinds[id])
+            .map(|(&to, kind)| (stable_id(to), kind.clone()))
+            .collect();
+        // An edge from the entity to itself is among its edges already
+        let incoming = collection.incoming[id]
+            .iter()
+            .filter(|&&source| source != id)
+            .map(|&source| {
+                let index = collection.edges[source].iter().position(|&to| to == id).expect("edge");
+                (stable_id(source), index, collection.kinds[source][index].clone())
+            })
+            .collect();
+        Removed { index: id.index, entity: collection.nodes[id].clone(), edges, incoming }
+    }
+}
+
+impl Op {
+    fn apply(self, collection: &mut Collection) -> Op {
+        let id = |collection: &Collection, stable_id| {
+            collection.by_stable_id(stable_id).expect("entity with stable id")
+        };
+        match self {
+            Op::Remove(stable_id) => {
+                let id = id(collection, stable_id);
+                let removed = Removed::of(collection, id);
+                collection.remove(id);
+                Op::Restore(Box::new(removed))
+            }
+            Op::Restore(removed) => Op::Remove(collection.restore_removed(*removed)),
+            Op::Replace(entity) => {
+                let id = id(collection, entity.stable_id());
+                Op::Replace(Box::new(collection.replace_entity(id, *entity)))
+            }
+            Op::SetEdge { from, to, edge } => {
+                let (from_id, to_id) = (id(collection, from), id(collection, to));
+                let index = collection.edges[from_id].iter().position(|&other| other == to_id);
+                let previous = index.map(|index| (index, collection.kinds[from_id][index].clone()));
+                if index.is_some() {
+                    collection.remove_edge(from_id, to_id);
+                }
+                if let Some((index, kind)) = edge {
+                    collection.insert_edge_at(from_id, to_id, kind, index);
+                }
+                Op::SetEdge { from, to, edge: previous }
+            }
+            Op::SetName(name) => Op::SetName(std::mem::replace(&mut collection.name, name)),
+            Op::Batch(ops) => {
+                let mut inverses: Vec<Op> =
+                    ops.into_iter().map(|op| op.apply(collection)).collect();
+                inverses.reverse();
+                Op::Batch(inverses)
+            }
+        }
+    }
+}
+
+impl Collection {
+    /// Puts a removed entity back where it was, returning its stable id.  The entity and the one
+    /// that had moved into its slot get new [`Id`]s.
+    fn restore_removed(&mut self, removed: Removed) -> StableId {
+        let Removed { index, entity, edges, incoming } = removed;
+        let last = self.insert(entity).index;
+        self.swap_slots(index, last);
+        let id = self.id_at(index);
+        for (to, kind) in edges {
+            let to = self.by_stable_id(to).expect("entity with stable id");
+            self.push_edge(id, to, kind);
+        }
+        for (source, position, kind) in incoming {
+            let source = self.by_stable_id(source).expect("entity with stable id");
+            self.insert_edge_at(source, id, kind, position);
+        }
+        self.nodes[id].stable_id()
+    }
+
+    /// Puts `entity` in place of the entity with the given [`Id`], which has the same URL, and
+    /// returns the entity replaced.
+    fn replace_entity(&mut self, id: Id, entity: Entity) -> Entity {
+        debug_assert_eq!(self.nodes[id].url, entity.url);
+        self.unindex(id);
+        let old = std::mem::replace(&mut self.nodes[id], entity);
+        self.index(id);
+        for alias in old.aliases.iter() {
+            self.restore_alias(alias);
+        }
+        old
+    }
+
+    fn insert_edge_at(&mut self, from: Id, to: Id, kind: EdgeKind, index: usize) {
+        self.edges[from].insert(index, to);
+        self.kinds[from].insert(index, kind);
+        let incoming = &mut self.incoming[to];
+        if let Err(index) = incoming.binary_search(&from) {
+            incoming.insert(index, from);
+        }
+    }
+
+    /// Swaps the entities in two slots, along with their edges.  Both slots start a new
+    /// generation.
+    fn swap_slots(&mut self, a: usize, b: usize) {
+        if a == b {
+            return;
+        }
+        let (old_a, old_b) = (self.id_at(a), self.id_at(b));
+        let keys = [old_a, old_b].map(|id| self.key(self.nodes[id].url()).into_owned());
+        self.unindex(old_a);
+        self.unindex(old_b);
+        self.nodes.swap(a, b);
+        self.edges.swap(a, b);
+        self.kinds.swap(a, b);
+        self.incoming.swap(a, b);
+        for index in [a, b] {
+            self.generations[index] = self.generations[index].wrapping_add(1);
+        }
+        let (new_a, new_b) = (self.id_at(a), self.id_at(b));
+        let remap = |id: Id| match id {
+            id if id == old_a => new_b,
+            id if id == old_b => new_a,
+            id => id,
+        };
+        // The sources of edges to either entity, and the targets of edges from either, refer to
+        // them by their old ids
+        let sources: BTreeSet<usize> = self.incoming[a]
+            .iter()
+            .chain(&self.incoming[b])
+            .map(|&source| remap(source).index)
+            .chain([a, b])
+            .collect();
+        for source in sources {
+            for to in self.edges[source].iter_mut() {
+                *to = remap(*to);
+            }
+        }
+        let targets: BTreeSet<usize> =
+            self.edges[a].iter().chain(&self.edges[b]).map(|to| to.index).chain([a, b]).collect();
+        for target in targets {
+            let incoming = &mut self.incoming[target];
+            for source in incoming.iter_mut() {
+                *source = remap(*source);
+            }
+            incoming.sort();
+        }
+        for (key, old) in keys.into_iter().zip([old_a, old_b]) {
+            if self.urls.get(&key) == Some(&old) {
+                self.urls.insert(key, remap(old));
+            }
+        }
+        self.index(new_a);
+        self.index(new_b);
+    }
+}
+
+/// A [`Collection`] whose edits can be undone and redone.
+///
+/// Each edit records the change that reverts it, such as the entity an insertion added or the
+/// entity as it was before an update, so that an edit costs memory in proportion to what it
+/// changed.  Undoing restores the entities, their order and their edges exactly, but entities whose
+/// removal is undone get new [`Id`]s, as does the entity that had moved into the vacated slot;
+/// [`StableId`]s are better handles to keep across undos.  Only the most recent edits, up to a
+/// limit, can be undone.
+#[derive(Debug, Clone)]
+pub struct UndoStack {
+    collection: Collection,
+    undo: VecDeque<Op>,
+    redo: Vec<Op>,
+    limit: usize,
+}
+
+impl UndoStack {
+    pub const DEFAULT_LIMIT: usize = 100;
+
+    pub fn new(collection: Collection) -> UndoStack {
+        UndoStack::with_limit(collection, UndoStack::DEFAULT_LIMIT)
+    }
+
+    /// Creates a stack that can undo up to `limit` edits.
+    pub fn with_limit(collection: Collection, limit: usize) -> UndoStack {
+        UndoStack { collection, undo: VecDeque::new(), redo: Vec::new(), limit }
+    }
+
+    pub fn into_inner(self) -> Collection {
+        self.collection
+    }
+
+    /// Records the change that reverts an edit.  Any edits undone before are forgotten.
+    fn record(&mut self, inverse: Op) {
+        self.undo.push_back(inverse);
+        if self.undo.len() > self.limit {
+            self.undo.pop_front();
+        }
+        self.redo.clear();
+    }
+
+    fn stable_id(&self, id: Id) -> StableId {
+        self.collection.entity(id).stable_id()
+    }
+
+    /// Inserts an entity, as with [`Collection::insert`].
+    pub fn insert(&mut self, entity: Entity) -> Id {
+        let id = self.collection.insert(entity);
+        self.record(Op::Remove(self.stable_id(id)));
+        id
+    }
+
+    /// Inserts or merges an entity, as with [`Collection::upsert`].
+    pub fn upsert(&mut self, entity: Entity) -> Id {
+        let existing = self.collection.id(entity.url());
+        let old = existing.map(|id| self.collection.nodes[id].clone());
+        let id = self.collection.upsert(entity);
+        self.record(match old {
+            Some(old) => Op::Replace(Box::new(old)),
+            None => Op::Remove(self.stable_id(id)),
+        });
+        id
+    }
+
+    /// Adds names and labels to an entity, as with [`Collection::update`].
+    pub fn update(
+        &mut self,
+        id: Id,
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        labels: BTreeSet<Label>,
+    ) -> &Entity {
+        let old = self.collection.entity(id).clone();
+        self.collection.update(id, updated_at, names, labels);
+        self.record(Op::Replace(Box::new(old)));
+        self.collection.entity(id)
+    }
+
+    /// Removes an entity, as with [`Collection::remove`].
+    pub fn remove(&mut self, id: Id) -> Entity {
+        let id = self.collection.checked(id);
+        let removed = Removed::of(&self.collection, id);
+        let entity = self.collection.remove(id);
+        self.record(Op::Restore(Box::new(removed)));
+        entity
+    }
+
+    /// Merges one entity into another, as with [`Collection::merge_entities`].
+    pub fn merge_entities(&mut self, into: Id, from: Id) -> Id {
+        let collection = &self.collection;
+        let old = collection.entity(into).clone();
+        let removed = Removed::of(collection, collection.checked(from));
+        let stable_ids = |collection: &Collection, ids: &[Id]| -> BTreeSet<StableId> {
+            ids.iter().map(|&id| collection.nodes[id].stable_id()).collect()
+        };
+        let targets = stable_ids(collection, collection.edges(into));
+        let sources = stable_ids(collection, collection.incoming(into));
+        let into = self.collection.merge_entities(into, from);
+        let collection = &self.collection;
+        let stable_id = old.stable_id();
+        let mut ops = Vec::new();
+        for to in stable_ids(collection, collection.edges(into)).difference(&targets) {
+            ops.push(Op::SetEdge { from: stable_id, to: *to, edge: None });
+        }
+        for source in stable_ids(collection, collection.incoming(into)).difference(&sources) {
+            ops.push(Op::SetEdge { from: *source, to: stable_id, edge: None });
+        }
+        ops.push(Op::Replace(Box::new(old)));
+        ops.push(Op::Restore(Box::new(removed)));
+        self.record(Op::Batch(ops));
+        into
+    }
+
+    /// Adds an edge of the default [`EdgeKind`], as with [`Collection::add_edge`].
+    pub fn add_edge(&mut self, from: Id, to: Id) {
+        if !self.collection.edges(from).contains(&to) {
+            self.add_edge_with_kind(from, to, EdgeKind::default());
+        }
+    }
+
+    /// Adds an edge or changes its kind, as with [`Collection::add_edge_with_kind`].
+    pub fn add_edge_with_kind(&mut self, from: Id, to: Id, kind: EdgeKind) {
+        let edge = self.edge(from, to);
+        self.collection.add_edge_with_kind(from, to, kind);
+        let (from, to) = (self.stable_id(from), self.stable_id(to));
+        self.record(Op::SetEdge { from, to, edge });
+    }
+
+    /// Removes an edge, as with [`Collection::remove_edge`].
+    pub fn remove_edge(&mut self, from: Id, to: Id) -> Option<EdgeKind> {
+        let edge = self.edge(from, to);
+        let ret = self.collection.remove_edge(from, to)?;
+        let (from, to) = (self.stable_id(from), self.stable_id(to));
+        self.record(Op::SetEdge { from, to, edge });
+        Some(ret)
+    }
+
+    /// Returns the position among the edges of `from` and the kind of the edge to `to`, if any.
+    fn edge(&self, from: Id, to: Id) -> Option<(usize, EdgeKind)> {
+        let index = self.collection.edges(from).iter().position(|&other| other == to)?;
+        Some((index, self.collection.edge_kinds(from)[index].clone()))
+    }
+
+    pub fn set_name(&mut self, name: Option<String>) {
+        let old = std::mem::replace(&mut self.collection.name, name);
+        self.record(Op::SetName(old));
+    }
+
+    /// Reverts the last edit, returning whether there was one.
+    pub fn undo(&mut self) -> bool {
+        let Some(op) = self.undo.pop_back() else {
+            return false;
+        };
+        self.redo.push(op.apply(&mut self.collection));
+        true
+    }
+
+    /// Reapplies the last edit undone, returning whether there was one.
+    pub fn redo(&mut self) -> bool {
+        let Some(op) = self.redo.pop() else {
+            return false;
+        };
+        self.undo.push_back(op.apply(&mut self.collection));
+        true
+    }
+
+    pub fn can_undo(&self) -> bool {
+        !self.undo.is_empty()
+    }
+
+    pub fn can_redo(&self) -> bool {
+        !self.redo.is_empty()
+    }
+}
+
+impl Deref for UndoStack {
+    type Target = Collection;
+
+    fn deref(&self) -> &Collection {
+        &self.collection
+    }
+}
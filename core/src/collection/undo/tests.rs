@@ -0,0 +1,168 @@
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Entity, Label, Name};
+
+fn entity(url: &str, name: Option<&str>) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        name.map(Name::from),
+        [Label::from("foo")].into_iter().collect(),
+    )
+}
+
+fn id(stack: &UndoStack, url: &str) -> Id {
+    stack.id(&Url::parse(url).unwrap()).unwrap()
+}
+
+/// Builds a collection whose entities have edges to and from each other, in various orders.
+fn graph() -> UndoStack {
+    let mut stack = UndoStack::new(Collection::new());
+    let ids: Vec<Id> = ["https://a.com/", "https://b.com/", "https://c.com/", "https://d.com/"]
+        .into_iter()
+        .map(|url| stack.insert(entity(url, None)))
+        .collect();
+    let (a, b, c, d) = (ids[0], ids[1], ids[2], ids[3]);
+    stack.add_edge(a, b);
+    stack.add_edge_with_kind(a, d, EdgeKind::PartOf);
+    stack.add_edge(a, c);
+    stack.add_edge(b, a);
+    stack.add_edge(b, b);
+    stack.add_edge(d, b);
+    stack.add_edge(d, a);
+    stack.add_edge(c, d);
+    UndoStack::new(stack.into_inner())
+}
+
+#[test]
+fn test_undo_redo() {
+    let mut stack = UndoStack::new(Collection::new());
+    assert!(!stack.undo());
+    let a = stack.insert(entity("https://a.com/", None));
+    let b = stack.insert(entity("https://b.com/", None));
+    stack.add_edge(a, b);
+    stack.upsert(entity("https://a.com/", Some("A")));
+    let after: Collection = Collection::clone(&stack);
+
+    assert!(stack.undo());
+    assert!(stack.entity(a).names().is_empty());
+    assert_eq!(stack.by_name("A").count(), 0);
+    assert!(stack.undo());
+    assert_eq!(stack.edges(a), &[]);
+    assert!(stack.redo());
+    assert!(stack.redo());
+    assert!(!stack.redo());
+    assert_eq!(*stack, after);
+    assert_eq!(stack.by_name("A").collect::<Vec<_>>(), vec![a]);
+
+    // A new edit forgets what was undone
+    stack.undo();
+    stack.set_name(Some(String::from("Edited")));
+    assert!(!stack.can_redo());
+    assert!(stack.undo());
+    assert_eq!(stack.name(), None);
+    while stack.undo() {}
+    assert!(stack.is_empty());
+    assert_eq!(stack.into_inner(), Collection::new());
+}
+
+#[test]
+fn test_undo_remove() {
+    for url in ["https://a.com/", "https://b.com/", "https://c.com/", "https://d.com/"] {
+        let mut stack = graph();
+        let before: Collection = Collection::clone(&stack);
+        let removed = stack.remove(id(&stack, url));
+        assert_eq!(removed.url().as_str(), url);
+        let after: Collection = Collection::clone(&stack);
+
+        // Entities, their order and their edges are restored exactly
+        assert!(stack.undo());
+        assert_eq!(*stack, before, "undoing removal of {url}");
+        let a = id(&stack, "https://a.com/");
+        let d = id(&stack, "https://d.com/");
+        assert_eq!(stack.edge_kind(a, d), Some(&EdgeKind::PartOf));
+        assert_eq!(stack.incoming(a).len(), 2);
+        assert!(stack.redo());
+        assert_eq!(*stack, after, "redoing removal of {url}");
+        assert!(!stack.contains(removed.url()));
+    }
+}
+
+#[test]
+fn test_undo_remove_ids() {
+    let mut stack = graph();
+    let a = id(&stack, "https://a.com/");
+    stack.remove(a);
+    let moved = id(&stack, "https://d.com/");
+    assert_eq!(moved.index(), a.index());
+    stack.undo();
+    // Both entities have new ids, and handles from before match neither
+    assert!(stack.get(a).is_none());
+    assert!(stack.get(moved).is_none());
+    assert_eq!(id(&stack, "https://a.com/").index(), 0);
+    assert_eq!(id(&stack, "https://d.com/").index(), 3);
+    let a = id(&stack, "https://a.com/");
+    assert_eq!(stack.by_label(&Label::from("foo")).count(), 4);
+    assert_eq!(stack.by_stable_id(stack.entity(a).stable_id()), Some(a));
+}
+
+#[test]
+fn test_undo_merge_entities() {
+    let mut stack = graph();
+    let before: Collection = Collection::clone(&stack);
+    let (a, c) = (id(&stack, "https://a.com/"), id(&stack, "https://c.com/"));
+    stack.update(a, datetime!(2024-01-16 0:00 UTC).into(), [Name::from("A")].into(), [].into());
+    let updated: Collection = Collection::clone(&stack);
+    let into = stack.merge_entities(c, a);
+    assert_eq!(stack.entity(into).names(), &[Name::from("A")].into());
+    let merged: Collection = Collection::clone(&stack);
+
+    assert!(stack.undo());
+    assert_eq!(*stack, updated);
+    assert!(stack.undo());
+    assert_eq!(*stack, before);
+    assert!(stack.redo());
+    assert!(stack.redo());
+    assert_eq!(*stack, merged);
+}
+
+#[test]
+fn test_undo_edges() {
+    let mut stack = graph();
+    let before: Collection = Collection::clone(&stack);
+    let (a, b, c, d) = (
+        id(&stack, "https://a.com/"),
+        id(&stack, "https://b.com/"),
+        id(&stack, "https://c.com/"),
+        id(&stack, "https://d.com/"),
+    );
+    assert_eq!(stack.remove_edge(a, d), Some(EdgeKind::PartOf));
+    assert_eq!(stack.remove_edge(a, d), None);
+    stack.add_edge_with_kind(a, b, EdgeKind::Supersedes);
+    stack.add_edge(c, a);
+    // Edges already there are not recorded again
+    stack.add_edge(c, a);
+
+    assert!(stack.undo());
+    assert!(stack.undo());
+    assert_eq!(stack.edge_kind(a, b), Some(&EdgeKind::RelatedTo));
+    assert!(stack.undo());
+    // The edge is back in its place among the others
+    assert_eq!(stack.edges(a), &[b, d, c]);
+    assert_eq!(*stack, before);
+    assert!(!stack.undo());
+}
+
+#[test]
+fn test_undo_limit() {
+    let mut stack = UndoStack::with_limit(Collection::new(), 2);
+    for url in ["https://a.com/", "https://b.com/", "https://c.com/"] {
+        stack.insert(entity(url, None));
+    }
+    assert!(stack.undo());
+    assert!(stack.undo());
+    assert!(!stack.undo());
+    assert_eq!(stack.len(), 1);
+}
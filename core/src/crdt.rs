@@ -0,0 +1,175 @@
+//! A conflict-free replicated collection.
+//!
+//! A [`Replica`] can be edited independently on each machine and merged with any other replica in
+//! any order, always reaching the same state, without a common ancestor.  Each entity's names and
+//! labels only ever grow, and its creation time is the earliest seen.  Entities and edges are kept
+//! in observed-remove sets: a removal only cancels the additions the removing replica had seen, so
+//! an addition concurrent with a removal wins.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use serde::{Deserialize, Serialize};
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+/// A tag identifying one addition: the replica that made it, and that replica's count of additions
+/// so far.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Serialize, Deserialize)]
+struct Dot(String, u64);
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(rename_all = "camelCase")]
+struct EntityState {
+    created_at: Time,
+    names: BTreeSet<Name>,
+    labels: BTreeSet<Label>,
+    dots: BTreeSet<Dot>,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Replica {
+    id: String,
+    counter: u64,
+    entities: BTreeMap<Url, EntityState>,
+    edges: BTreeMap<Url, BTreeMap<Url, BTreeSet<Dot>>>,
+    /// The additions cancelled by a removal.
+    removed: BTreeSet<Dot>,
+}
+
+impl Replica {
+    /// Creates an empty replica.  Each replica that will be merged needs a distinct `id`.
+    pub fn new(id: impl Into<String>) -> Replica {
+        Replica {
+            id: id.into(),
+            counter: 0,
+            entities: BTreeMap::new(),
+            edges: BTreeMap::new(),
+            removed: BTreeSet::new(),
+        }
+    }
+
+    /// Creates a replica holding the entities and edges of the collection.  Edges are kept without
+    /// their kinds.
+    pub fn from_collection(id: impl Into<String>, collection: &Collection) -> Replica {
+        let mut ret = Replica::new(id);
+        for (_, entity) in collection {
+            ret.insert(entity);
+        }
+        for (from, to) in collection.edges_with_kinds().map(|(from, to, _)| (from, to)) {
+            ret.add_edge(collection.entity(from).url(), collection.entity(to).url());
+        }
+        ret
+    }
+
+    pub fn id(&self) -> &str {
+        &self.id
+    }
+
+    fn dot(&mut self) -> Dot {
+        self.counter += 1;
+        Dot(self.id.clone(), self.counter)
+    }
+
+    fn is_live(&self, dots: &BTreeSet<Dot>) -> bool {
+        dots.iter().any(|dot| !self.removed.contains(dot))
+    }
+
+    /// Adds the entity, or adds its names and labels to the entity already present.
+    pub fn insert(&mut self, entity: &Entity) {
+        let dot = self.dot();
+        let state = self.entities.entry(entity.url().clone()).or_insert_with(|| EntityState {
+            created_at: *entity.created_at(),
+            names: BTreeSet::new(),
+            labels: BTreeSet::new(),
+            dots: BTreeSet::new(),
+        });
+        state.created_at = state.created_at.min(*entity.created_at());
+        state.names.extend(entity.names().iter().cloned());
+        state.labels.extend(entity.labels().iter().cloned());
+        state.dots.insert(dot);
+    }
+
+    /// Removes the entity with the given URL, returning whether it was present.
+    pub fn remove(&mut self, url: &Url) -> bool {
+        let Some(state) = self.entities.get(url) else {
+            return false;
+        };
+        let live = self.is_live(&state.dots);
+        self.removed.extend(state.dots.iter().cloned());
+        live
+    }
+
+    pub fn contains(&self, url: &Url) -> bool {
+        self.entities.get(url).is_some_and(|state| self.is_live(&state.dots))
+    }
+
+    /// Adds an edge between the entities with the given URLs.
+    pub fn add_edge(&mut self, from: &Url, to: &Url) {
+        let dot = self.dot();
+        self.edges.entry(from.clone()).or_default().entry(to.clone()).or_default().insert(dot);
+    }
+
+    /// Removes the edge between the entities with the given URLs, returning whether it was present.
+    pub fn remove_edge(&mut self, from: &Url, to: &Url) -> bool {
+        let Some(dots) = self.edges.get(from).and_then(|edges| edges.get(to)) else {
+            return false;
+        };
+        let live = self.is_live(dots);
+        self.removed.extend(dots.iter().cloned());
+        live
+    }
+
+    /// Merges everything `other` has seen into this replica.
+    pub fn merge(&mut self, other: &Replica) {
+        for (url, theirs) in other.entities.iter() {
+            match self.entities.get_mut(url) {
+                Some(ours) => {
+                    ours.created_at = ours.created_at.min(theirs.created_at);
+                    ours.names.extend(theirs.names.iter().cloned());
+                    ours.labels.extend(theirs.labels.iter().cloned());
+                    ours.dots.extend(theirs.dots.iter().cloned());
+                }
+                None => {
+                    self.entities.insert(url.clone(), theirs.clone());
+                }
+            }
+        }
+        for (from, edges) in other.edges.iter() {
+            let ours = self.edges.entry(from.clone()).or_default();
+            for (to, dots) in edges.iter() {
+                ours.entry(to.clone()).or_default().extend(dots.iter().cloned());
+            }
+        }
+        self.removed.extend(other.removed.iter().cloned());
+        // Keep our own dots unique if we later add what `other` has seen from us
+        let seen = other.entities.values().flat_map(|state| state.dots.iter());
+        let seen = seen.chain(other.edges.values().flat_map(|edges| edges.values().flatten()));
+        if let Some(counter) = seen.filter(|dot| dot.0 == self.id).map(|dot| dot.1).max() {
+            self.counter = self.counter.max(counter);
+        }
+    }
+
+    /// Returns the present entities, in order of URL, and the edges between them.
+    pub fn to_collection(&self) -> Collection {
+        let mut ret = Collection::new();
+        for (url, state) in self.entities.iter().filter(|(_, state)| self.is_live(&state.dots)) {
+            let entity = Entity::new(url.clone(), state.created_at, None, state.labels.clone());
+            let id = ret.insert(entity);
+            for name in state.names.iter() {
+                ret.add_name(id, name.clone());
+            }
+        }
+        for (from, edges) in self.edges.iter() {
+            for (to, _) in edges.iter().filter(|(_, dots)| self.is_live(dots)) {
+                if let (Some(from), Some(to)) = (ret.id(from), ret.id(to)) {
+                    ret.add_edge(from, to);
+                }
+            }
+        }
+        ret
+    }
+}
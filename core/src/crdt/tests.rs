@@ -0,0 +1,108 @@
+use time::macros::datetime;
+
+use super::*;
+
+fn entity(url: &str, name: Option<&str>, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2024-01-15 0:00 UTC).into(),
+        name.map(Name::from),
+        labels.iter().copied().map(Label::from).collect(),
+    )
+}
+
+fn url(url: &str) -> Url {
+    Url::parse(url).unwrap()
+}
+
+fn merged(a: &Replica, b: &Replica) -> Replica {
+    let mut ret = a.clone();
+    ret.merge(b);
+    ret
+}
+
+#[test]
+fn test_merge_converges() {
+    let mut a = Replica::new("a");
+    a.insert(&entity("https://foo.com/", Some("Foo"), &["x"]));
+    let mut b = a.clone();
+    b.id = String::from("b");
+
+    a.insert(&entity("https://foo.com/", Some("Foo!"), &[]));
+    a.insert(&entity("https://bar.com/", None, &["y"]));
+    a.add_edge(&url("https://foo.com/"), &url("https://bar.com/"));
+    b.insert(&entity("https://foo.com/", None, &["z"]));
+    b.insert(&entity("https://baz.com/", None, &[]));
+
+    let ab = merged(&a, &b).to_collection();
+    let ba = merged(&b, &a).to_collection();
+    assert_eq!(ab, ba);
+    assert_eq!(ab, merged(&merged(&a, &b), &b).to_collection());
+    assert_eq!(ab.len(), 3);
+    let foo = ab.id(&url("https://foo.com/")).unwrap();
+    let bar = ab.id(&url("https://bar.com/")).unwrap();
+    assert_eq!(ab.entity(foo).names(), &BTreeSet::from([Name::from("Foo"), Name::from("Foo!")]));
+    assert_eq!(ab.entity(foo).labels(), &BTreeSet::from([Label::from("x"), Label::from("z")]));
+    assert_eq!(ab.edges(foo), &[bar]);
+}
+
+#[test]
+fn test_concurrent_add_wins() {
+    let mut a = Replica::new("a");
+    a.insert(&entity("https://foo.com/", None, &[]));
+    a.insert(&entity("https://bar.com/", None, &[]));
+    a.add_edge(&url("https://foo.com/"), &url("https://bar.com/"));
+    let mut b = Replica::new("b");
+    b.merge(&a);
+
+    // Removals only cancel the additions they observed
+    assert!(a.remove(&url("https://foo.com/")));
+    assert!(!a.remove(&url("https://qux.com/")));
+    b.insert(&entity("https://foo.com/", None, &["again"]));
+    assert!(b.remove_edge(&url("https://foo.com/"), &url("https://bar.com/")));
+    assert!(!a.contains(&url("https://foo.com/")));
+
+    let ab = merged(&a, &b);
+    assert_eq!(ab.to_collection(), merged(&b, &a).to_collection());
+    assert!(ab.contains(&url("https://foo.com/")));
+    let collection = ab.to_collection();
+    let foo = collection.id(&url("https://foo.com/")).unwrap();
+    assert_eq!(collection.entity(foo).labels(), &BTreeSet::from([Label::from("again")]));
+    assert!(collection.edges(foo).is_empty());
+
+    // A removal after observing everything sticks
+    let mut b = ab.clone();
+    b.id = String::from("b");
+    b.remove(&url("https://foo.com/"));
+    let ab = merged(&ab, &b);
+    assert!(!ab.contains(&url("https://foo.com/")));
+    assert_eq!(ab.to_collection().len(), 1);
+}
+
+#[test]
+fn test_counter_after_merge() {
+    let mut a = Replica::new("a");
+    a.insert(&entity("https://foo.com/", None, &[]));
+    let mut stale = Replica::new("a");
+    stale.merge(&a);
+    stale.insert(&entity("https://bar.com/", None, &[]));
+    assert_eq!(stale.counter, 2);
+}
+
+#[test]
+fn test_from_collection() {
+    let mut collection = Collection::new();
+    let foo = collection.insert(entity("https://foo.com/", Some("Foo"), &["x"]));
+    let bar = collection.insert(entity("https://bar.com/", None, &[]));
+    collection.add_edge(foo, bar);
+    let replica = Replica::from_collection("a", &collection);
+    let json = serde_json::to_string(&replica).unwrap();
+    let replica: Replica = serde_json::from_str(&json).unwrap();
+    let restored = replica.to_collection();
+    assert_eq!(restored.len(), 2);
+    let foo = restored.id(&url("https://foo.com/")).unwrap();
+    let bar = restored.id(&url("https://bar.com/")).unwrap();
+    assert_eq!(restored.entity(foo).names(), &BTreeSet::from([Name::from("Foo")]));
+    assert_eq!(restored.entity(foo).labels(), &BTreeSet::from([Label::from("x")]));
+    assert_eq!(restored.edges(foo), &[bar]);
+}
@@ -0,0 +1,79 @@
+//! Finding and merging entities whose URLs point at the same page.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::HashMap;
+
+use url::Url;
+
+use crate::{collection::Collection, url_norm};
+
+/// Returns a key that is equal for URLs that [normalize](url_norm::normalize) alike, or that differ
+/// only in `http` vs `https`, a trailing slash on the path, or the parameters stripped by the
+/// default [`TrackingFilter`](url_norm::TrackingFilter).
+pub fn canonical_key(url: &Url) -> String {
+    let url = &url_norm::normalize(url, &url_norm::Options::default());
+    let url = &url_norm::TrackingFilter::default().strip(url);
+    let mut ret = String::new();
+    if !matches!(url.scheme(), "http" | "https") {
+        ret.push_str(url.scheme());
+        ret.push(':');
+    }
+    if let Some(host) = url.host_str() {
+        ret.push_str(host);
+    }
+    if let Some(port) = url.port() {
+        ret.push(':');
+        ret.push_str(&port.to_string());
+    }
+    ret.push_str(url.path().trim_end_matches('/'));
+    if let Some(query) = url.query() {
+        ret.push('?');
+        ret.push_str(query);
+    }
+    if let Some(fragment) = url.fragment() {
+        ret.push('#');
+        ret.push_str(fragment);
+    }
+    ret
+}
+
+/// Returns groups of URLs in the collection sharing a [`canonical_key`], in order of first
+/// appearance.  Only groups with more than one URL are returned.
+pub fn duplicates(collection: &Collection) -> Vec<Vec<Url>> {
+    let mut groups: Vec<Vec<Url>> = Vec::new();
+    let mut keys: HashMap<String, usize> = HashMap::new();
+    for (_, entity) in collection {
+        let key = canonical_key(entity.url());
+        match keys.get(&key) {
+            Some(&index) => groups[index].push(entity.url().clone()),
+            None => {
+                keys.insert(key, groups.len());
+                groups.push(vec![entity.url().clone()]);
+            }
+        }
+    }
+    groups.retain(|group| group.len() > 1);
+    groups
+}
+
+/// Merges each URL after the first in `group` into the first.
+///
+/// URLs no longer in the collection are skipped.  Returns the URLs that were merged away.
+pub fn merge_group(collection: &mut Collection, group: &[Url]) -> Vec<Url> {
+    let mut ret = Vec::new();
+    let Some((first, rest)) = group.split_first() else {
+        return ret;
+    };
+    for url in rest {
+        let (Some(into), Some(from)) = (collection.id(first), collection.id(url)) else {
+            continue;
+        };
+        if into != from {
+            collection.merge_entities(into, from);
+            ret.push(url.clone());
+        }
+    }
+    ret
+}
@@ -0,0 +1,79 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Entity, Name};
+
+fn key(url: &str) -> String {
+    canonical_key(&Url::parse(url).unwrap())
+}
+
+fn create_test_collection(urls: &[&str]) -> Collection {
+    let mut collection = Collection::new();
+    for (i, url) in urls.iter().enumerate() {
+        let entity = Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            Some(Name::new(format!("Page {}", i))),
+            BTreeSet::new(),
+        );
+        collection.insert(entity);
+    }
+    collection
+}
+
+#[test]
+fn test_canonical_key() {
+    assert_eq!(key("https://example.com/page/"), key("http://example.com/page"));
+    assert_eq!(key("https://example.com/"), key("https://example.com"));
+    assert_eq!(
+        key("https://example.com/page?utm_source=x&id=1&fbclid=abc"),
+        key("https://example.com/page?id=1&gclid=def")
+    );
+    assert_eq!(key("https://example.com/page?b=2&a=1"), key("https://example.com/page?a=1&b=2"));
+    assert_ne!(key("https://example.com/page?id=1"), key("https://example.com/page?id=2"));
+    assert_ne!(key("https://example.com/page#a"), key("https://example.com/page"));
+    assert_ne!(key("https://example.com:8080/"), key("https://example.com/"));
+    assert_ne!(key("ftp://example.com/"), key("https://example.com/"));
+}
+
+#[test]
+fn test_duplicates() {
+    let collection = create_test_collection(&[
+        "https://example.com/a",
+        "https://example.com/b",
+        "http://example.com/a/",
+        "https://example.com/a?utm_medium=email",
+    ]);
+    let groups = duplicates(&collection);
+    assert_eq!(groups.len(), 1);
+    let urls: Vec<&str> = groups[0].iter().map(Url::as_str).collect();
+    assert_eq!(
+        urls,
+        vec![
+            "https://example.com/a",
+            "http://example.com/a/",
+            "https://example.com/a?utm_medium=email"
+        ]
+    );
+}
+
+#[test]
+fn test_merge_group() {
+    let mut collection = create_test_collection(&[
+        "https://example.com/a",
+        "https://example.com/b",
+        "http://example.com/a/",
+        "https://example.com/a?utm_medium=email",
+    ]);
+    for group in duplicates(&collection) {
+        let merged = merge_group(&mut collection, &group);
+        assert_eq!(merged.len(), 2);
+    }
+    assert_eq!(collection.len(), 2);
+    assert!(duplicates(&collection).is_empty());
+    let id = collection.id(&Url::parse("https://example.com/a").unwrap()).unwrap();
+    let names: Vec<&str> = collection.entity(id).names().iter().map(Name::as_str).collect();
+    assert_eq!(names, vec!["Page 0", "Page 2", "Page 3"]);
+}
@@ -0,0 +1,156 @@
+//! Downloading favicons and caching them on disk, keyed by host.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    fs,
+    io::Read,
+    path::{Path, PathBuf},
+    time::Duration,
+};
+
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id},
+    http,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("no host: {0}")]
+    NoHost(Url),
+    #[error("not an image: {0}, {1}")]
+    NotImage(Url, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number of requests in flight at once.
+    pub concurrency: usize,
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { concurrency: 8, timeout: Duration::from_secs(10) }
+    }
+}
+
+/// Favicons larger than this are truncated.
+const MAX_BODY: u64 = 256 * 1024;
+
+const MAX_REDIRECTS: u32 = 5;
+
+/// A directory of favicons, one file per host.
+#[derive(Debug, Clone)]
+pub struct Cache {
+    dir: PathBuf,
+}
+
+impl Cache {
+    pub fn new(dir: impl Into<PathBuf>) -> Cache {
+        Cache { dir: dir.into() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Returns where the favicon for the given URL's host is kept, whether or not it has been
+    /// downloaded.
+    ///
+    /// Hosts on a port other than their scheme's default are cached separately.
+    pub fn path(&self, url: &Url) -> Option<PathBuf> {
+        let host = url.host_str()?;
+        let key = match url.port() {
+            Some(port) => format!("{}_{}", host, port),
+            None => host.to_string(),
+        };
+        let key: String = key
+            .chars()
+            .map(|c| if c.is_ascii_alphanumeric() || c == '.' || c == '-' { c } else { '_' })
+            .collect();
+        Some(self.dir.join(format!("{}.ico", key)))
+    }
+
+    /// Returns the cached favicon for the given URL's host, if it has been downloaded.
+    pub fn get(&self, url: &Url) -> Option<PathBuf> {
+        self.path(url).filter(|path| path.is_file())
+    }
+}
+
+pub struct Fetcher {
+    agent: ureq::Agent,
+    concurrency: usize,
+    cache: Cache,
+}
+
+impl Fetcher {
+    pub fn new(options: &Options, cache: Cache) -> Fetcher {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let concurrency = options.concurrency.max(1);
+        Fetcher { agent, concurrency, cache }
+    }
+
+    /// Returns the path of the favicon for the given URL's host, downloading `/favicon.ico` from
+    /// the host unless it is already cached.
+    pub fn fetch_favicon(&self, url: &Url) -> Result<PathBuf, Error> {
+        let path = self.cache.path(url).ok_or_else(|| Error::NoHost(url.clone()))?;
+        if path.is_file() {
+            return Ok(path);
+        }
+        let icon = url.join("/favicon.ico").map_err(|_| Error::NoHost(url.clone()))?;
+        let response = self.agent.request_url("GET", &icon).call().map_err(Box::new)?;
+        let content_type = response.content_type().to_ascii_lowercase();
+        if !content_type.starts_with("image/") {
+            return Err(Error::NotImage(icon, content_type));
+        }
+        let mut body = Vec::new();
+        response.into_reader().take(MAX_BODY).read_to_end(&mut body)?;
+        fs::create_dir_all(&self.cache.dir)?;
+        // Write to a temporary file first, so that the cache never holds a partial favicon
+        let tmp = path.with_extension("ico.tmp");
+        fs::write(&tmp, &body)?;
+        fs::rename(&tmp, &path)?;
+        Ok(path)
+    }
+
+    /// Fetches the favicon of every host in the collection, recording each cached path on the
+    /// entities on that host.
+    ///
+    /// Each host is requested once.  Returns the outcome for each host, keyed by the first entity
+    /// on it, in [`Id`] order.
+    pub fn fetch_favicons(&self, collection: &mut Collection) -> Vec<(Id, Result<PathBuf, Error>)> {
+        let mut hosts: BTreeMap<PathBuf, Vec<Id>> = BTreeMap::new();
+        for (id, entity) in collection.iter() {
+            if let Some(path) = self.cache.path(entity.url()) {
+                hosts.entry(path).or_default().push(id);
+            }
+        }
+        let mut groups: Vec<Vec<Id>> = hosts.into_values().collect();
+        groups.sort();
+        let urls: Vec<(&[Id], &Url)> =
+            groups.iter().map(|ids| (ids.as_slice(), collection.entity(ids[0]).url())).collect();
+        let results = http::map_concurrent(&urls, self.concurrency, |(ids, url)| {
+            (*ids, self.fetch_favicon(url))
+        });
+        let mut ret = Vec::with_capacity(results.len());
+        for (ids, result) in results {
+            if let Ok(path) = &result {
+                for &id in ids {
+                    collection.entity_mut(id).set_favicon(Some(path.clone()));
+                }
+            }
+            ret.push((ids[0], result));
+        }
+        ret
+    }
+}
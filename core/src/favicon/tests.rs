@@ -0,0 +1,62 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+fn route(_: &str, path: &str) -> (&'static str, &'static str) {
+    match path {
+        "/favicon.ico" => ("200 OK\r\nContent-Type: image/x-icon\r\n", "ICON"),
+        _ => ("404 Not Found\r\n", ""),
+    }
+}
+
+fn temp_cache(name: &str) -> Cache {
+    let dir = std::env::temp_dir().join(format!("hbt-favicon-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    Cache::new(dir)
+}
+
+#[test]
+fn test_cache_path() {
+    let cache = Cache::new("/cache");
+    let path = |url| cache.path(&Url::parse(url).unwrap());
+    assert_eq!(path("https://example.com/page"), Some(PathBuf::from("/cache/example.com.ico")));
+    assert_eq!(path("https://example.com:443/"), Some(PathBuf::from("/cache/example.com.ico")));
+    assert_eq!(path("http://[::1]:8080/"), Some(PathBuf::from("/cache/___1__8080.ico")));
+    assert_eq!(path("mailto:someone@example.com"), None);
+}
+
+#[test]
+fn test_fetch_favicon() {
+    let base = http::serve(route);
+    let cache = temp_cache("fetch");
+    let fetcher = Fetcher::new(&Options::default(), cache.clone());
+    let url = base.join("/some/page").unwrap();
+    assert_eq!(cache.get(&url), None);
+    let path = fetcher.fetch_favicon(&url).unwrap();
+    assert_eq!(fs::read_to_string(&path).unwrap(), "ICON");
+    assert_eq!(cache.get(&url), Some(path));
+    fs::remove_dir_all(cache.dir()).unwrap();
+}
+
+#[test]
+fn test_fetch_favicons() {
+    let base = http::serve(route);
+    let cache = temp_cache("fetch-all");
+    let mut collection = Collection::new();
+    for url in [base.join("/a").unwrap(), base.join("/b").unwrap()] {
+        let created_at = datetime!(2023-11-15 0:00 UTC).into();
+        collection.insert(Entity::new(url, created_at, None, BTreeSet::new()));
+    }
+    let fetcher = Fetcher::new(&Options::default(), cache.clone());
+    let results = fetcher.fetch_favicons(&mut collection);
+    // Both entities are on the same host
+    assert_eq!(results.len(), 1);
+    let path = results[0].1.as_ref().unwrap();
+    for entity in collection.entities() {
+        assert_eq!(entity.favicon(), Some(path.as_path()));
+    }
+    fs::remove_dir_all(cache.dir()).unwrap();
+}
@@ -0,0 +1,107 @@
+//! Fetching page titles for entities that have no name.
+//!
+//! Pages are fetched with the blocking [`ureq`] client, spread over a fixed number of threads,
+//! rather than with an async client: the crate has no async runtime, and a thread per page in
+//! flight costs little at the concurrency titles are fetched at.
+
+#[cfg(test)]
+mod tests;
+
+use std::{io::Read, time::Duration};
+
+use scraper::{Html, Selector};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id, Name},
+    http,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("HTML selector error: {0}")]
+    HtmlSelector(String),
+    #[error("no title: {0}")]
+    NoTitle(Url),
+    #[error("not HTML: {0}, {1}")]
+    NotHtml(Url, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number of requests in flight at once.
+    pub concurrency: usize,
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { concurrency: 8, timeout: Duration::from_secs(10) }
+    }
+}
+
+/// Pages are read no further than this when looking for a title.
+const MAX_BODY: u64 = 1024 * 1024;
+
+const MAX_REDIRECTS: u32 = 5;
+
+pub struct Fetcher {
+    agent: ureq::Agent,
+    concurrency: usize,
+}
+
+impl Fetcher {
+    pub fn new(options: &Options) -> Fetcher {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let concurrency = options.concurrency.max(1);
+        Fetcher { agent, concurrency }
+    }
+
+    /// Downloads the page at the given URL and returns its `<title>`.
+    pub fn fetch_title(&self, url: &Url) -> Result<Name, Error> {
+        let response = self.agent.request_url("GET", url).call().map_err(Box::new)?;
+        let content_type = response.content_type().to_ascii_lowercase();
+        if !matches!(content_type.as_str(), "text/html" | "application/xhtml+xml") {
+            return Err(Error::NotHtml(url.clone(), content_type));
+        }
+        let mut body = String::new();
+        response.into_reader().take(MAX_BODY).read_to_string(&mut body)?;
+        extract_title(&body)?.ok_or_else(|| Error::NoTitle(url.clone()))
+    }
+
+    /// Fetches a title for every entity without a name, adding each as the entity's name.
+    ///
+    /// Returns the outcome for each of those entities in [`Id`] order.
+    pub fn fetch_titles(&self, collection: &mut Collection) -> Vec<(Id, Result<Name, Error>)> {
+        let urls: Vec<(Id, &Url)> = collection
+            .iter()
+            .filter(|(_, entity)| entity.names().is_empty())
+            .map(|(id, entity)| (id, entity.url()))
+            .collect();
+        let results =
+            http::map_concurrent(&urls, self.concurrency, |(id, url)| (*id, self.fetch_title(url)));
+        for (id, name) in &results {
+            if let Ok(name) = name {
+                collection.add_name(*id, name.clone());
+            }
+        }
+        results
+    }
+}
+
+/// Returns the text of the document's `<title>`, with whitespace collapsed.
+pub fn extract_title(html: &str) -> Result<Option<Name>, Error> {
+    let document = Html::parse_document(html);
+    let selector = Selector::parse("title").map_err(|err| Error::HtmlSelector(err.to_string()))?;
+    let Some(element) = document.select(&selector).next() else {
+        return Ok(None);
+    };
+    let text: String = element.text().collect();
+    let title = text.split_whitespace().collect::<Vec<_>>().join(" ");
+    Ok(Some(title).filter(|title| !title.is_empty()).map(Name::new))
+}
@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+fn route(_: &str, path: &str) -> (&'static str, &'static str) {
+    const HTML: &str = "200 OK\r\nContent-Type: text/html; charset=utf-8\r\n";
+    match path {
+        "/page" => (HTML, "<html><head><title>\n  A   Page\n</title></head></html>"),
+        "/untitled" => (HTML, "<html><body>Nothing here</body></html>"),
+        "/moved" => ("302 Found\r\nLocation: /page\r\n", ""),
+        "/text" => ("200 OK\r\nContent-Type: text/plain\r\n", "<title>Text</title>"),
+        _ => ("404 Not Found\r\n", ""),
+    }
+}
+
+#[test]
+fn test_extract_title() {
+    let title = extract_title("<title>Hello &amp; welcome</title>").unwrap();
+    assert_eq!(title, Some(Name::from("Hello & welcome")));
+    assert_eq!(extract_title("<title>  </title>").unwrap(), None);
+    assert_eq!(extract_title("<p>No title</p>").unwrap(), None);
+}
+
+#[test]
+fn test_fetch_title() {
+    let base = http::serve(route);
+    let fetcher = Fetcher::new(&Options::default());
+    let fetch = |path| fetcher.fetch_title(&base.join(path).unwrap());
+    assert_eq!(fetch("/page").unwrap(), Name::from("A Page"));
+    assert_eq!(fetch("/moved").unwrap(), Name::from("A Page"));
+    assert!(matches!(fetch("/untitled"), Err(Error::NoTitle(_))));
+    assert!(matches!(fetch("/text"), Err(Error::NotHtml(_, _))));
+    assert!(matches!(fetch("/missing"), Err(Error::Http(_))));
+}
+
+#[test]
+fn test_fetch_titles() {
+    let base = http::serve(route);
+    let mut collection = Collection::new();
+    for (path, name) in [("/page", None), ("/moved", Some("Moved")), ("/missing", None)] {
+        let entity = Entity::new(
+            base.join(path).unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            name.map(Name::from),
+            BTreeSet::new(),
+        );
+        collection.insert(entity);
+    }
+    let fetcher = Fetcher::new(&Options::default());
+    let results = fetcher.fetch_titles(&mut collection);
+    // Only entities without a name are fetched
+    assert_eq!(results.len(), 2);
+    assert!(results[1].1.is_err());
+    let id = collection.id(&base.join("/page").unwrap()).unwrap();
+    assert_eq!(collection.by_name("A Page").collect::<Vec<_>>(), vec![id]);
+}
@@ -0,0 +1,18 @@
+pub mod atom;
+#[cfg(feature = "buku")]
+pub mod buku;
+pub mod chrome;
+pub mod dot;
+#[cfg(feature = "firefox")]
+pub mod firefox;
+pub mod graphml;
+pub mod json;
+#[cfg(feature = "netscape")]
+pub mod netscape;
+pub mod opml;
+pub mod org;
+#[cfg(feature = "pocket")]
+pub mod pocket;
+#[cfg(feature = "raindrop")]
+pub mod raindrop;
+pub mod safari;
@@ -0,0 +1,122 @@
+//! An [Atom](https://www.rfc-editor.org/rfc/rfc4287) feed of the entities added most recently.
+
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Write};
+
+use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use url::Url;
+
+use crate::collection::{Collection, Id, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("time formatting error: {0}")]
+    FormatTime(#[from] time::error::Format),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    /// The number of entities in the feed.
+    pub limit: usize,
+    pub title: String,
+    /// Where the feed's collection can be seen, which also serves as the feed's id.
+    pub link: Option<Url>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { limit: 50, title: "hbt".to_string(), link: None }
+    }
+}
+
+/// The feed's id when it has no link.
+const FEED_ID: &str = "urn:hbt:collection";
+
+/// Returns the ids of the `limit` entities created most recently, newest first.
+fn recent(collection: &Collection, limit: usize) -> Vec<Id> {
+    let mut ret: Vec<Id> = collection.iter().map(|(id, _)| id).collect();
+    // Among entities created at the same time, those added to the collection later come first
+    ret.sort_by(|a, b| {
+        let created_at = |id: &Id| collection.entity(*id).created_at();
+        created_at(b).cmp(created_at(a)).then(b.cmp(a))
+    });
+    ret.truncate(limit);
+    ret
+}
+
+/// Writes the [`Options::limit`] entities of a [`Collection`] created most recently as an Atom
+/// feed, newest first.
+///
+/// Each entity becomes an entry titled with its first name, or its URL if it has none, linking
+/// to its URL, published when it was created and updated when it was last updated, with its
+/// labels as categories.  Entries are identified by the entity's stable id, so they keep their
+/// identity if its URL changes.  The feed is updated when its newest entry was.
+pub fn to_writer(
+    collection: &Collection,
+    options: &Options,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let ids = recent(collection, options.limit);
+    let updated = ids
+        .iter()
+        .map(|id| {
+            let entity = collection.entity(*id);
+            entity.last_updated_at().unwrap_or(entity.created_at())
+        })
+        .max()
+        .copied()
+        .unwrap_or_default();
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<feed xmlns="http://www.w3.org/2005/Atom">"#)?;
+    writeln!(writer, "  <title>{}</title>", escape(&options.title))?;
+    match &options.link {
+        Some(link) => {
+            writeln!(writer, "  <id>{}</id>", escape(link.as_str()))?;
+            writeln!(writer, r#"  <link href="{}"/>"#, escape(link.as_str()))?;
+        }
+        None => writeln!(writer, "  <id>{}</id>", FEED_ID)?,
+    }
+    writeln!(writer, "  <updated>{}</updated>", format_time(&updated)?)?;
+    writeln!(writer, "  <generator>hbt</generator>")?;
+    for id in ids {
+        let entity = collection.entity(id);
+        let url = entity.url().as_str();
+        let title = entity.names().first().map(Name::as_str).unwrap_or(url);
+        let updated = entity.last_updated_at().unwrap_or(entity.created_at());
+        writeln!(writer, "  <entry>")?;
+        writeln!(writer, "    <id>urn:hbt:{}</id>", entity.stable_id())?;
+        writeln!(writer, "    <title>{}</title>", escape(title))?;
+        writeln!(writer, r#"    <link href="{}"/>"#, escape(url))?;
+        writeln!(writer, "    <published>{}</published>", format_time(entity.created_at())?)?;
+        writeln!(writer, "    <updated>{}</updated>", format_time(updated)?)?;
+        for label in entity.labels() {
+            writeln!(writer, r#"    <category term="{}"/>"#, escape(label.as_str()))?;
+        }
+        writeln!(writer, "  </entry>")?;
+    }
+    writeln!(writer, "</feed>")?;
+    Ok(())
+}
+
+fn format_time(time: &Time) -> Result<String, time::error::Format> {
+    OffsetDateTime::from(*time).format(&Rfc3339)
+}
+
+fn escape(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,109 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Entity, Label};
+
+fn to_string(collection: &Collection, options: &Options) -> String {
+    let mut ret = Vec::new();
+    to_writer(collection, options, &mut ret).unwrap();
+    String::from_utf8(ret).unwrap()
+}
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    collection.insert(Entity::new(
+        Url::parse("https://rust-lang.org/").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new("Rust".to_string())),
+        BTreeSet::from([Label::from("programming"), Label::from("rust")]),
+    ));
+    let tom = collection.insert(Entity::new(
+        Url::parse("https://example.com/?a=1&b=2").unwrap(),
+        datetime!(2023-11-17 0:00 UTC).into(),
+        Some(Name::new("Tom & Jerry".to_string())),
+        BTreeSet::new(),
+    ));
+    collection.update(
+        tom,
+        datetime!(2023-11-18 12:30 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::from([Label::from("cartoons")]),
+    );
+    collection.insert(Entity::new(
+        Url::parse("https://example.org/").unwrap(),
+        datetime!(2023-11-16 0:00 UTC).into(),
+        None,
+        BTreeSet::new(),
+    ));
+    collection
+}
+
+#[test]
+fn test_empty() {
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>hbt</title>
+  <id>urn:hbt:collection</id>
+  <updated>1970-01-01T00:00:00Z</updated>
+  <generator>hbt</generator>
+</feed>
+"#;
+    assert_eq!(to_string(&Collection::new(), &Options::default()), expected);
+}
+
+#[test]
+fn test_sample() {
+    let collection = create_test_collection();
+    let options = Options {
+        limit: 2,
+        title: "Links & more".to_string(),
+        link: Some(Url::parse("https://links.example.com/").unwrap()),
+    };
+    let stable_id = |url: &str| {
+        let id = collection.id(&Url::parse(url).unwrap()).unwrap();
+        collection.entity(id).stable_id()
+    };
+    let expected = format!(
+        r#"<?xml version="1.0" encoding="UTF-8"?>
+<feed xmlns="http://www.w3.org/2005/Atom">
+  <title>Links &amp; more</title>
+  <id>https://links.example.com/</id>
+  <link href="https://links.example.com/"/>
+  <updated>2023-11-18T12:30:00Z</updated>
+  <generator>hbt</generator>
+  <entry>
+    <id>urn:hbt:{}</id>
+    <title>Tom &amp; Jerry</title>
+    <link href="https://example.com/?a=1&amp;b=2"/>
+    <published>2023-11-17T00:00:00Z</published>
+    <updated>2023-11-18T12:30:00Z</updated>
+    <category term="cartoons"/>
+  </entry>
+  <entry>
+    <id>urn:hbt:{}</id>
+    <title>https://example.org/</title>
+    <link href="https://example.org/"/>
+    <published>2023-11-16T00:00:00Z</published>
+    <updated>2023-11-16T00:00:00Z</updated>
+  </entry>
+</feed>
+"#,
+        stable_id("https://example.com/?a=1&b=2"),
+        stable_id("https://example.org/"),
+    );
+    assert_eq!(to_string(&collection, &options), expected);
+}
+
+#[test]
+fn test_limit() {
+    let collection = create_test_collection();
+    let count = |limit| {
+        let options = Options { limit, ..Options::default() };
+        to_string(&collection, &options).matches("<entry>").count()
+    };
+    assert_eq!(count(0), 0);
+    assert_eq!(count(3), 3);
+    assert_eq!(count(50), 3);
+}
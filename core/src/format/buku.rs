@@ -0,0 +1,104 @@
+//! The SQLite database of [buku](https://github.com/jarun/buku).
+//!
+//! buku keeps its bookmarks in a single table, with each bookmark's tags in one column, delimited
+//! by commas, as in `,rust,programming,`.  It records no times.
+
+#[cfg(test)]
+mod tests;
+
+use std::{collections::BTreeSet, path::Path};
+
+use rusqlite::{params, Connection, OpenFlags};
+use thiserror::Error;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Extended, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+}
+
+/// The table buku creates, as buku writes it.
+const SCHEMA: &str = "CREATE TABLE if not exists bookmarks (id integer PRIMARY KEY, URL text NOT \
+                      NULL UNIQUE, metadata text default '', tags text default ',', desc text \
+                      default '', flags integer default 0)";
+
+const DELIMITER: char = ',';
+
+/// Reads a buku database into a [`Collection`].
+///
+/// Each bookmark's title becomes its name, its tags its labels, and its description its extended
+/// text.  As buku records no times, entities are created at the default time.
+pub fn open(path: &Path) -> Result<Collection, Error> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = Connection::open_with_flags(path, flags)?;
+    read(&conn)
+}
+
+/// Writes a [`Collection`] to a buku database, creating it if need be.
+///
+/// Each entity's first name becomes its bookmark's title, its labels its tags, with any commas
+/// replaced by spaces, and its extended text its description.  Bookmarks already in the database
+/// are updated in place, keeping their flags, and bookmarks not in the collection are kept.
+pub fn save(collection: &Collection, path: &Path) -> Result<(), Error> {
+    let mut conn = Connection::open(path)?;
+    write(collection, &mut conn)
+}
+
+fn read(conn: &Connection) -> Result<Collection, Error> {
+    let mut ret = Collection::new();
+    let mut stmt = conn.prepare(
+        "SELECT URL, IFNULL(metadata, ''), IFNULL(tags, ''), IFNULL(desc, '')
+         FROM bookmarks ORDER BY id",
+    )?;
+    let mut rows = stmt.query([])?;
+    while let Some(row) = rows.next()? {
+        let url: String = row.get(0)?;
+        let url = Url::parse(&url).map_err(|err| Error::ParseUrl(err, url))?;
+        let title: String = row.get(1)?;
+        let title = title.trim();
+        let name = if title.is_empty() { None } else { Some(Name::new(title.to_string())) };
+        let tags: String = row.get(2)?;
+        let labels: BTreeSet<Label> = tags
+            .split(DELIMITER)
+            .map(str::trim)
+            .filter(|tag| !tag.is_empty())
+            .map(Label::from)
+            .collect();
+        let desc: String = row.get(3)?;
+        let mut entity = Entity::new(url, Time::default(), name, labels);
+        if !desc.is_empty() {
+            entity.set_extended(Some(Extended::new(desc)));
+        }
+        ret.upsert(entity);
+    }
+    Ok(ret)
+}
+
+fn write(collection: &Collection, conn: &mut Connection) -> Result<(), Error> {
+    let tx = conn.transaction()?;
+    tx.execute(SCHEMA, [])?;
+    {
+        let mut stmt = tx.prepare(
+            "INSERT INTO bookmarks (URL, metadata, tags, desc) VALUES (?1, ?2, ?3, ?4)
+             ON CONFLICT (URL) DO UPDATE
+             SET metadata = excluded.metadata, tags = excluded.tags, desc = excluded.desc",
+        )?;
+        for (_, entity) in collection {
+            let title = entity.names().first().map(Name::as_str).unwrap_or_default();
+            let mut tags = String::from(DELIMITER);
+            for label in entity.labels() {
+                tags.push_str(&label.as_str().replace(DELIMITER, " "));
+                tags.push(DELIMITER);
+            }
+            let desc = entity.extended().map(Extended::as_str).unwrap_or_default();
+            stmt.execute(params![entity.url().as_str(), title, tags, desc])?;
+        }
+    }
+    tx.commit()?;
+    Ok(())
+}
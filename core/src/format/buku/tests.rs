@@ -0,0 +1,80 @@
+use super::*;
+
+const TEST_SAMPLE: &str = "
+INSERT INTO bookmarks (URL, metadata, tags, desc, flags) VALUES
+    ('https://www.rust-lang.org/', 'Rust', ',programming,rust,', 'A language', 0),
+    ('https://example.com/', '', ',', '', 1);
+";
+
+fn sample() -> Connection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute(SCHEMA, []).unwrap();
+    conn.execute_batch(TEST_SAMPLE).unwrap();
+    conn
+}
+
+#[test]
+fn test_read() {
+    let collection = read(&sample()).unwrap();
+    assert_eq!(collection.len(), 2);
+
+    let expected = Entity::builder(Url::parse("https://www.rust-lang.org/").unwrap())
+        .name("Rust")
+        .labels(["programming", "rust"])
+        .build();
+    let id = collection.id(expected.url()).unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.names(), expected.names());
+    assert_eq!(entity.labels(), expected.labels());
+    assert_eq!(entity.created_at(), &Time::default());
+    assert_eq!(entity.extended(), Some(&Extended::from("A language")));
+
+    let id = collection.id(&Url::parse("https://example.com/").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert!(entity.names().is_empty());
+    assert!(entity.labels().is_empty());
+    assert_eq!(entity.extended(), None);
+}
+
+#[test]
+fn test_write() {
+    let mut conn = sample();
+    let mut collection = Collection::new();
+    collection.insert(
+        Entity::builder(Url::parse("https://example.com/").unwrap())
+            .name("Example")
+            .labels(["web, dev"])
+            .build(),
+    );
+    collection.insert(Entity::builder(Url::parse("https://docs.rs/").unwrap()).build());
+    write(&collection, &mut conn).unwrap();
+
+    let rows: Vec<(String, String, String, i64)> = conn
+        .prepare("SELECT URL, metadata, tags, flags FROM bookmarks ORDER BY id")
+        .unwrap()
+        .query_map([], |row| Ok((row.get(0)?, row.get(1)?, row.get(2)?, row.get(3)?)))
+        .unwrap()
+        .collect::<Result<_, _>>()
+        .unwrap();
+    let rows: Vec<(&str, &str, &str, i64)> =
+        rows.iter().map(|(a, b, c, d)| (a.as_str(), b.as_str(), c.as_str(), *d)).collect();
+    // Bookmarks are updated in place, keeping their flags, and others are kept
+    assert_eq!(
+        rows,
+        vec![
+            ("https://www.rust-lang.org/", "Rust", ",programming,rust,", 0),
+            ("https://example.com/", "Example", ",web  dev,", 1),
+            ("https://docs.rs/", "", ",", 0),
+        ]
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let path = std::env::temp_dir().join(format!("hbt-buku-test-{}.db", std::process::id()));
+    let expected = read(&sample()).unwrap();
+    save(&expected, &path).unwrap();
+    let actual = open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(actual, expected);
+}
@@ -0,0 +1,145 @@
+//! The `Bookmarks` file of a Chrome or Chromium profile.
+//!
+//! Bookmarks are kept as a tree of folders under a few roots:
+//!
+//! ```json
+//! {
+//!   "checksum": "...",
+//!   "roots": {
+//!     "bookmark_bar": {
+//!       "type": "folder",
+//!       "name": "Bookmarks bar",
+//!       "date_added": "13344480000000000",
+//!       "children": [
+//!         {
+//!           "type": "url",
+//!           "name": "Example",
+//!           "date_added": "13344480000000000",
+//!           "url": "https://example.com/"
+//!         }
+//!       ]
+//!     },
+//!     "other": { "type": "folder", "name": "Other bookmarks", "children": [] },
+//!     "synced": { "type": "folder", "name": "Mobile bookmarks", "children": [] }
+//!   },
+//!   "version": 1
+//! }
+//! ```
+//!
+//! Timestamps are microseconds since 1601-01-01 UTC, written as strings.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use serde::Deserialize;
+use thiserror::Error;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("integer parsing error: {0}, {1}")]
+    ParseInt(#[source] std::num::ParseIntError, String),
+    #[error("time parsing error: {0}")]
+    ParseTime(#[from] time::error::ComponentRange),
+}
+
+/// Seconds from 1601-01-01, where Chrome's timestamps start, to the Unix epoch.
+const EPOCH_OFFSET: i64 = 11_644_473_600;
+
+#[derive(Debug, Deserialize)]
+struct Bookmarks {
+    roots: Roots,
+}
+
+#[derive(Debug, Deserialize)]
+struct Roots {
+    bookmark_bar: Option<Node>,
+    other: Option<Node>,
+    synced: Option<Node>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "lowercase")]
+enum Node {
+    Folder {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        children: Vec<Node>,
+    },
+    Url {
+        #[serde(default)]
+        name: String,
+        #[serde(default)]
+        date_added: Option<String>,
+        url: String,
+    },
+}
+
+/// Parses a Chrome or Chromium `Bookmarks` file into a [`Collection`].
+///
+/// Each bookmark's name becomes its name and its `date_added` its creation time.  The path of
+/// folders enclosing it becomes a single hierarchical label, as in `programming/rust`, with any
+/// [`Label::SEPARATOR`] in a folder's name replaced by `-`.  The roots, such as the bookmarks bar,
+/// are not part of the path.
+pub fn parse(input: &str) -> Result<Collection, Error> {
+    let Bookmarks { roots } = serde_json::from_str(input)?;
+    let mut ret = Collection::new();
+    for root in [roots.bookmark_bar, roots.other, roots.synced].into_iter().flatten() {
+        if let Node::Folder { children, .. } = root {
+            for child in children {
+                add(&mut ret, child, &mut Vec::new())?;
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Adds the bookmarks at and below the node, within the given folders.
+fn add(collection: &mut Collection, node: Node, folders: &mut Vec<String>) -> Result<(), Error> {
+    match node {
+        Node::Folder { name, children } => {
+            let name = name.trim().replace(Label::SEPARATOR, "-");
+            let pushed = !name.is_empty();
+            if pushed {
+                folders.push(name);
+            }
+            for child in children {
+                add(collection, child, folders)?;
+            }
+            if pushed {
+                folders.pop();
+            }
+        }
+        Node::Url { name, date_added, url } => {
+            let url = Url::parse(&url).map_err(|err| Error::ParseUrl(err, url))?;
+            let created_at = match date_added {
+                Some(date_added) => {
+                    let micros: i64 =
+                        date_added.parse().map_err(|err| Error::ParseInt(err, date_added))?;
+                    let seconds = micros / 1_000_000 - EPOCH_OFFSET;
+                    Time::new(OffsetDateTime::from_unix_timestamp(seconds)?)
+                }
+                None => Time::default(),
+            };
+            let name = name.trim();
+            let name = if name.is_empty() { None } else { Some(Name::new(name.to_string())) };
+            let labels: BTreeSet<Label> = if folders.is_empty() {
+                BTreeSet::new()
+            } else {
+                BTreeSet::from([Label::new(folders.join("/"))])
+            };
+            collection.upsert(Entity::new(url, created_at, name, labels));
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,112 @@
+use time::macros::datetime;
+
+use super::*;
+
+const TEST_SAMPLE: &str = r#"{
+   "checksum": "0123456789abcdef0123456789abcdef",
+   "roots": {
+      "bookmark_bar": {
+         "children": [ {
+            "children": [ {
+               "children": [ {
+                  "date_added": "13344480000000000",
+                  "id": "3",
+                  "name": "Rust",
+                  "type": "url",
+                  "url": "https://www.rust-lang.org/"
+               } ],
+               "date_added": "13344480000000000",
+               "id": "2",
+               "name": "Rust/Cargo",
+               "type": "folder"
+            }, {
+               "date_added": "13344566400000000",
+               "id": "4",
+               "name": "",
+               "type": "url",
+               "url": "https://users.rust-lang.org/"
+            } ],
+            "date_added": "13344480000000000",
+            "id": "1",
+            "name": "Programming",
+            "type": "folder"
+         }, {
+            "date_added": "13344652800000000",
+            "id": "5",
+            "name": "Example",
+            "type": "url",
+            "url": "https://example.com/"
+         } ],
+         "date_added": "13344480000000000",
+         "id": "6",
+         "name": "Bookmarks bar",
+         "type": "folder"
+      },
+      "other": {
+         "children": [ {
+            "date_added": "13344739200000000",
+            "id": "7",
+            "name": "Example, again",
+            "type": "url",
+            "url": "https://example.com/"
+         } ],
+         "date_added": "13344480000000000",
+         "id": "8",
+         "name": "Other bookmarks",
+         "type": "folder"
+      },
+      "synced": {
+         "children": [  ],
+         "date_added": "13344480000000000",
+         "id": "9",
+         "name": "Mobile bookmarks",
+         "type": "folder"
+      }
+   },
+   "version": 1
+}
+"#;
+
+fn labels(entity: &Entity) -> Vec<&str> {
+    entity.labels().iter().map(Label::as_str).collect()
+}
+
+fn names(entity: &Entity) -> Vec<&str> {
+    entity.names().iter().map(Name::as_str).collect()
+}
+
+#[test]
+fn test_sample() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 3);
+
+    let get = |url: &str| collection.entity(collection.id(&Url::parse(url).unwrap()).unwrap());
+
+    let entity = get("https://www.rust-lang.org/");
+    assert_eq!(names(entity), vec!["Rust"]);
+    assert_eq!(labels(entity), vec!["Programming/Rust-Cargo"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 0:00 UTC)));
+
+    let entity = get("https://users.rust-lang.org/");
+    assert!(entity.names().is_empty());
+    assert_eq!(labels(entity), vec!["Programming"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-16 0:00 UTC)));
+
+    // Bookmarks directly under a root have no label, and duplicates are merged
+    let entity = get("https://example.com/");
+    assert_eq!(names(entity), vec!["Example", "Example, again"]);
+    assert!(entity.labels().is_empty());
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-17 0:00 UTC)));
+
+    let programming = Label::from("Programming");
+    assert_eq!(collection.by_label_within(&programming).count(), 2);
+}
+
+#[test]
+fn test_invalid() {
+    assert!(matches!(parse("{}"), Err(Error::Json(_))));
+    let input = r#"{"roots": {"other": {"type": "folder", "children": [
+        {"type": "url", "name": "Bad", "date_added": "soon", "url": "https://example.com/"}
+    ]}}}"#;
+    assert!(matches!(parse(input), Err(Error::ParseInt(_, _))));
+}
@@ -0,0 +1,107 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use thiserror::Error;
+
+use crate::collection::{Collection, Entity, Id, Label, Name};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Draws the entities sharing a label inside a `cluster` subgraph for that label.
+    ///
+    /// GraphViz draws each node in at most one cluster, so an entity with several labels is
+    /// placed in the cluster of its first label.
+    pub cluster_by_label: bool,
+}
+
+/// Renders a [`Collection`] as a GraphViz DOT digraph.
+///
+/// See [`to_writer`].
+pub fn export(collection: &Collection, options: &Options) -> String {
+    let mut ret = Vec::new();
+    to_writer(collection, options, &mut ret).expect("writing to a Vec cannot fail");
+    String::from_utf8(ret).expect("DOT output is UTF-8")
+}
+
+/// Writes a [`Collection`] as a GraphViz DOT digraph.
+///
+/// Each entity becomes a node `n<id>` labeled with its first name, or its URL if it has none, and
+/// each edge becomes an arc, labeled with its kind unless that is the default.
+pub fn to_writer(
+    collection: &Collection,
+    options: &Options,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    writeln!(writer, "digraph {{")?;
+    if options.cluster_by_label {
+        let mut clusters: BTreeMap<&Label, Vec<Id>> = BTreeMap::new();
+        let mut unclustered = Vec::new();
+        for (id, entity) in collection.iter() {
+            match entity.labels().first() {
+                Some(label) => clusters.entry(label).or_default().push(id),
+                None => unclustered.push(id),
+            }
+        }
+        for (index, (label, ids)) in clusters.into_iter().enumerate() {
+            writeln!(writer, "    subgraph cluster_{index} {{")?;
+            writeln!(writer, "        label=\"{}\";", escape(label.as_str()))?;
+            for id in ids {
+                write_node(&mut writer, "        ", id, collection.entity(id))?;
+            }
+            writeln!(writer, "    }}")?;
+        }
+        for id in unclustered {
+            write_node(&mut writer, "    ", id, collection.entity(id))?;
+        }
+    } else {
+        for (id, entity) in collection.iter() {
+            write_node(&mut writer, "    ", id, entity)?;
+        }
+    }
+    for (from, to, kind) in collection.edges_with_kinds() {
+        let (from, to) = (usize::from(from), usize::from(to));
+        if kind.is_default() {
+            writeln!(writer, "    n{from} -> n{to};")?;
+        } else {
+            writeln!(writer, "    n{from} -> n{to} [label=\"{}\"];", escape(kind.as_str()))?;
+        }
+    }
+    writeln!(writer, "}}")?;
+    Ok(())
+}
+
+fn write_node(writer: &mut impl Write, indent: &str, id: Id, entity: &Entity) -> io::Result<()> {
+    let url = entity.url().as_str();
+    let label = entity.names().first().map(Name::as_str).unwrap_or(url);
+    writeln!(
+        writer,
+        "{indent}n{} [label=\"{}\", URL=\"{}\"];",
+        usize::from(id),
+        escape(label),
+        escape(url)
+    )
+}
+
+fn escape(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '"' => ret.push_str("\\\""),
+            '\\' => ret.push_str("\\\\"),
+            '\n' => ret.push_str("\\n"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
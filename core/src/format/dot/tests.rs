@@ -0,0 +1,65 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::EdgeKind;
+
+fn entity(url: &str, name: Option<&str>, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        name.map(|name| Name::new(name.to_string())),
+        labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+    )
+}
+
+fn sample() -> Collection {
+    let mut ret = Collection::new();
+    let rust = ret.insert(entity("https://rust-lang.org/", Some("Rust"), &["rust"]));
+    let users = ret.insert(entity("https://users.rust-lang.org/", None, &["forum", "rust"]));
+    let quoted = ret.insert(entity("https://example.com/", Some(r#"Say "hi""#), &[]));
+    ret.add_edge(users, rust);
+    ret.add_edge_with_kind(quoted, rust, EdgeKind::PartOf);
+    ret
+}
+
+#[test]
+fn test_empty() {
+    let collection = Collection::new();
+    assert_eq!(export(&collection, &Options::default()), "digraph {\n}\n");
+}
+
+#[test]
+fn test_export() {
+    let expected = r#"digraph {
+    n0 [label="Rust", URL="https://rust-lang.org/"];
+    n1 [label="https://users.rust-lang.org/", URL="https://users.rust-lang.org/"];
+    n2 [label="Say \"hi\"", URL="https://example.com/"];
+    n1 -> n0;
+    n2 -> n0 [label="partOf"];
+}
+"#;
+    assert_eq!(export(&sample(), &Options::default()), expected);
+}
+
+#[test]
+fn test_cluster_by_label() {
+    let expected = r#"digraph {
+    subgraph cluster_0 {
+        label="forum";
+        n1 [label="https://users.rust-lang.org/", URL="https://users.rust-lang.org/"];
+    }
+    subgraph cluster_1 {
+        label="rust";
+        n0 [label="Rust", URL="https://rust-lang.org/"];
+    }
+    n2 [label="Say \"hi\"", URL="https://example.com/"];
+    n1 -> n0;
+    n2 -> n0 [label="partOf"];
+}
+"#;
+    let options = Options { cluster_by_label: true };
+    assert_eq!(export(&sample(), &options), expected);
+}
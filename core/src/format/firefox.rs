@@ -0,0 +1,132 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    path::Path,
+};
+
+use rusqlite::{Connection, OpenFlags};
+use thiserror::Error;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("time parsing error: {0}")]
+    ParseTime(#[from] time::error::ComponentRange),
+}
+
+/// The `type` of a bookmark in `moz_bookmarks`.
+const TYPE_BOOKMARK: i64 = 1;
+const TYPE_FOLDER: i64 = 2;
+
+/// The `guid` of the folder holding the other roots.
+const GUID_ROOT: &str = "root________";
+/// The `guid` of the folder holding a folder for each tag.
+const GUID_TAGS: &str = "tags________";
+
+/// A folder in `moz_bookmarks`.
+#[derive(Debug)]
+struct Folder {
+    parent: i64,
+    title: String,
+    guid: String,
+}
+
+/// Reads the bookmarks in a Firefox profile's `places.sqlite` into a [`Collection`].
+///
+/// Each bookmark's title becomes its name, its `dateAdded` its creation time, and its tags and the
+/// titles of the folders enclosing it its labels.  The built-in roots, such as the bookmarks menu
+/// and toolbar, are not labels.  Queries, with `place:` URLs, are skipped.
+///
+/// The database is opened read-only.  Firefox keeps it locked while it runs, so quit Firefox or
+/// read a copy.
+pub fn open(path: &Path) -> Result<Collection, Error> {
+    let flags = OpenFlags::SQLITE_OPEN_READ_ONLY | OpenFlags::SQLITE_OPEN_NO_MUTEX;
+    let conn = Connection::open_with_flags(path, flags)?;
+    read(&conn)
+}
+
+fn read(conn: &Connection) -> Result<Collection, Error> {
+    let mut folders: HashMap<i64, Folder> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT id, parent, IFNULL(title, ''), IFNULL(guid, '') FROM moz_bookmarks WHERE type = ?1",
+    )?;
+    let rows = stmt.query_map([TYPE_FOLDER], |row| {
+        let folder = Folder { parent: row.get(1)?, title: row.get(2)?, guid: row.get(3)? };
+        Ok((row.get(0)?, folder))
+    })?;
+    for row in rows {
+        let (id, folder) = row?;
+        folders.insert(id, folder);
+    }
+    let root = folders.iter().find(|(_, folder)| folder.guid == GUID_ROOT).map(|(id, _)| *id);
+    let tags = folders.iter().find(|(_, folder)| folder.guid == GUID_TAGS).map(|(id, _)| *id);
+
+    // The titles of the folders from the outermost below the roots to the given folder, or the
+    // tag it names if it is a tag folder
+    let place = |mut id: i64| -> Result<Vec<Label>, Label> {
+        let mut labels = Vec::new();
+        while let Some(folder) = folders.get(&id) {
+            if Some(folder.parent) == tags {
+                return Err(Label::new(folder.title.clone()));
+            }
+            if folder.parent == id || Some(folder.parent) == root || Some(id) == root {
+                break;
+            }
+            if !folder.title.is_empty() {
+                labels.push(Label::new(folder.title.clone()));
+            }
+            id = folder.parent;
+        }
+        labels.reverse();
+        Ok(labels)
+    };
+
+    let mut bookmarks: Vec<(Url, Time, Option<Name>, BTreeSet<Label>)> = Vec::new();
+    let mut tagged: HashMap<Url, BTreeSet<Label>> = HashMap::new();
+    let mut stmt = conn.prepare(
+        "SELECT b.parent, IFNULL(b.title, ''), IFNULL(p.title, ''), IFNULL(b.dateAdded, 0), p.url
+         FROM moz_bookmarks b JOIN moz_places p ON p.id = b.fk
+         WHERE b.type = ?1
+         ORDER BY b.id",
+    )?;
+    let mut rows = stmt.query([TYPE_BOOKMARK])?;
+    while let Some(row) = rows.next()? {
+        let url: String = row.get(4)?;
+        if url.starts_with("place:") {
+            continue;
+        }
+        let url = Url::parse(&url).map_err(|err| Error::ParseUrl(err, url))?;
+        let labels = match place(row.get(0)?) {
+            Ok(labels) => labels,
+            Err(tag) => {
+                tagged.entry(url).or_default().insert(tag);
+                continue;
+            }
+        };
+        let title: String = row.get(1)?;
+        let title = if title.is_empty() { row.get(2)? } else { title };
+        let name = if title.is_empty() { None } else { Some(Name::new(title)) };
+        // Microseconds since the epoch
+        let date_added: i64 = row.get(3)?;
+        let created_at = Time::new(OffsetDateTime::from_unix_timestamp(date_added / 1_000_000)?);
+        bookmarks.push((url, created_at, name, labels.into_iter().collect()));
+    }
+
+    let mut ret = Collection::new();
+    for (url, created_at, name, mut labels) in bookmarks {
+        if let Some(tags) = tagged.get(&url) {
+            labels.extend(tags.iter().cloned());
+        }
+        ret.upsert(Entity::new(url, created_at, name, labels));
+    }
+    Ok(ret)
+}
@@ -0,0 +1,93 @@
+use time::macros::datetime;
+
+use super::*;
+
+/// A cut-down `places.sqlite`, with the built-in roots and a few bookmarks.
+const TEST_SAMPLE: &str = "
+CREATE TABLE moz_places (
+    id INTEGER PRIMARY KEY,
+    url LONGVARCHAR,
+    title LONGVARCHAR
+);
+CREATE TABLE moz_bookmarks (
+    id INTEGER PRIMARY KEY,
+    type INTEGER,
+    fk INTEGER DEFAULT NULL,
+    parent INTEGER,
+    position INTEGER,
+    title LONGVARCHAR,
+    dateAdded INTEGER,
+    guid TEXT
+);
+INSERT INTO moz_places (id, url, title) VALUES
+    (1, 'https://www.rust-lang.org/', 'Rust Programming Language'),
+    (2, 'https://doc.rust-lang.org/book/', 'The Rust Programming Language'),
+    (3, 'https://example.com/', NULL),
+    (4, 'place:sort=8&maxResults=10', NULL);
+INSERT INTO moz_bookmarks (id, type, fk, parent, position, title, dateAdded, guid) VALUES
+    (1, 2, NULL, 0, 0, '', 0, 'root________'),
+    (2, 2, NULL, 1, 0, 'menu', 0, 'menu________'),
+    (3, 2, NULL, 1, 1, 'toolbar', 0, 'toolbar_____'),
+    (4, 2, NULL, 1, 2, 'tags', 0, 'tags________'),
+    (5, 2, NULL, 1, 3, 'unfiled', 0, 'unfiled_____'),
+    (6, 2, NULL, 2, 0, 'Programming', 1700000000000000, 'AAAAAAAAAAAA'),
+    (7, 2, NULL, 6, 0, 'Rust', 1700000000000000, 'BBBBBBBBBBBB'),
+    (8, 1, 1, 7, 0, 'Rust', 1700006400000000, 'CCCCCCCCCCCC'),
+    (9, 1, 2, 7, 1, NULL, 1700092800123456, 'DDDDDDDDDDDD'),
+    (10, 1, 3, 3, 0, NULL, 1700179200000000, 'EEEEEEEEEEEE'),
+    (11, 1, 4, 3, 1, 'Most Visited', 1700179200000000, 'FFFFFFFFFFFF'),
+    (12, 2, NULL, 4, 0, 'lang', 1700000000000000, 'GGGGGGGGGGGG'),
+    (13, 1, 1, 12, 0, NULL, 1700000000000000, 'HHHHHHHHHHHH'),
+    (14, 1, 3, 5, 0, 'Example', 1700265600000000, 'IIIIIIIIIIII');
+";
+
+fn sample() -> Collection {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(TEST_SAMPLE).unwrap();
+    read(&conn).unwrap()
+}
+
+fn labels(entity: &Entity) -> Vec<&str> {
+    entity.labels().iter().map(Label::as_str).collect()
+}
+
+fn names(entity: &Entity) -> Vec<&str> {
+    entity.names().iter().map(Name::as_str).collect()
+}
+
+#[test]
+fn test_sample() {
+    let collection = sample();
+    assert_eq!(collection.len(), 3);
+
+    let get = |url: &str| collection.entity(collection.id(&Url::parse(url).unwrap()).unwrap());
+
+    let entity = get("https://www.rust-lang.org/");
+    assert_eq!(names(entity), vec!["Rust"]);
+    // Folders below the roots and tags are both labels
+    assert_eq!(labels(entity), vec!["Programming", "Rust", "lang"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 0:00 UTC)));
+
+    // Untitled bookmarks take the page's title
+    let entity = get("https://doc.rust-lang.org/book/");
+    assert_eq!(names(entity), vec!["The Rust Programming Language"]);
+    assert_eq!(labels(entity), vec!["Programming", "Rust"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-16 0:00 UTC)));
+
+    // A page bookmarked twice is one entity
+    let entity = get("https://example.com/");
+    assert_eq!(names(entity), vec!["Example"]);
+    assert!(entity.labels().is_empty());
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-17 0:00 UTC)));
+}
+
+#[test]
+fn test_open() {
+    let path = std::env::temp_dir().join(format!("hbt-firefox-test-{}.sqlite", std::process::id()));
+    let conn = Connection::open(&path).unwrap();
+    conn.execute_batch(TEST_SAMPLE).unwrap();
+    drop(conn);
+    let collection = open(&path).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(collection, sample());
+}
@@ -0,0 +1,88 @@
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Write};
+
+use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+
+use crate::collection::{Collection, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("time formatting error: {0}")]
+    FormatTime(#[from] time::error::Format),
+}
+
+/// The `<key>` declarations for node and edge data, as `(id, for, attr.name)`.
+const KEYS: [(&str, &str, &str); 6] = [
+    ("d0", "node", "url"),
+    ("d1", "node", "names"),
+    ("d2", "node", "labels"),
+    ("d3", "node", "createdAt"),
+    ("d4", "node", "updatedAt"),
+    ("d5", "edge", "kind"),
+];
+
+/// Writes a [`Collection`] as a GraphML document.
+///
+/// Each entity becomes a node `n<id>` carrying its URL, names, labels and dates as string data.
+/// Names are joined with `" | "`, labels with `","`, and update times, in RFC 3339, with `" "`.
+/// Each edge carries its kind.
+pub fn to_writer(collection: &Collection, mut writer: impl Write) -> Result<(), Error> {
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<graphml xmlns="http://graphml.graphdrawing.org/xmlns">"#)?;
+    for (id, target, name) in KEYS {
+        writeln!(
+            writer,
+            r#"  <key id="{id}" for="{target}" attr.name="{name}" attr.type="string"/>"#
+        )?;
+    }
+    writeln!(writer, r#"  <graph id="G" edgedefault="directed">"#)?;
+    for (id, entity) in collection.iter() {
+        let names: Vec<&str> = entity.names().iter().map(Name::as_str).collect();
+        let labels: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+        let updated_at: Vec<String> =
+            entity.updated_at().map(format_time).collect::<Result<_, _>>()?;
+        writeln!(writer, r#"    <node id="n{}">"#, usize::from(id))?;
+        write_data(&mut writer, "d0", entity.url().as_str())?;
+        write_data(&mut writer, "d1", &names.join(" | "))?;
+        write_data(&mut writer, "d2", &labels.join(","))?;
+        write_data(&mut writer, "d3", &format_time(entity.created_at())?)?;
+        write_data(&mut writer, "d4", &updated_at.join(" "))?;
+        writeln!(writer, "    </node>")?;
+    }
+    for (from, to, kind) in collection.edges_with_kinds() {
+        let (from, to) = (usize::from(from), usize::from(to));
+        writeln!(writer, r#"    <edge source="n{from}" target="n{to}">"#)?;
+        write_data(&mut writer, "d5", kind.as_str())?;
+        writeln!(writer, "    </edge>")?;
+    }
+    writeln!(writer, "  </graph>")?;
+    writeln!(writer, "</graphml>")?;
+    Ok(())
+}
+
+fn write_data(writer: &mut impl Write, key: &str, value: &str) -> io::Result<()> {
+    writeln!(writer, r#"      <data key="{key}">{}</data>"#, escape(value))
+}
+
+fn format_time(time: &Time) -> Result<String, time::error::Format> {
+    OffsetDateTime::from(*time).format(&Rfc3339)
+}
+
+fn escape(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
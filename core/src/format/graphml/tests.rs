@@ -0,0 +1,81 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{EdgeKind, Entity};
+
+fn to_string(collection: &Collection) -> String {
+    let mut ret = Vec::new();
+    to_writer(collection, &mut ret).unwrap();
+    String::from_utf8(ret).unwrap()
+}
+
+#[test]
+fn test_empty() {
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<graphml xmlns="http://graphml.graphdrawing.org/xmlns">
+  <key id="d0" for="node" attr.name="url" attr.type="string"/>
+  <key id="d1" for="node" attr.name="names" attr.type="string"/>
+  <key id="d2" for="node" attr.name="labels" attr.type="string"/>
+  <key id="d3" for="node" attr.name="createdAt" attr.type="string"/>
+  <key id="d4" for="node" attr.name="updatedAt" attr.type="string"/>
+  <key id="d5" for="edge" attr.name="kind" attr.type="string"/>
+  <graph id="G" edgedefault="directed">
+  </graph>
+</graphml>
+"#;
+    assert_eq!(to_string(&Collection::new()), expected);
+}
+
+#[test]
+fn test_sample() {
+    let mut collection = Collection::new();
+    let rust = collection.insert(Entity::new(
+        Url::parse("https://rust-lang.org/").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new("Rust".to_string())),
+        BTreeSet::from([Label::from("programming"), Label::from("rust")]),
+    ));
+    let tom = collection.insert(Entity::new(
+        Url::parse("https://example.com/?a=1&b=2").unwrap(),
+        datetime!(2023-11-16 0:00 UTC).into(),
+        Some(Name::new("Tom & Jerry".to_string())),
+        BTreeSet::new(),
+    ));
+    collection.update(
+        tom,
+        datetime!(2023-11-17 12:30 UTC).into(),
+        BTreeSet::from([Name::new("<Tom>".to_string())]),
+        BTreeSet::new(),
+    );
+    collection.add_edge(tom, rust);
+    collection.add_edge_with_kind(rust, tom, EdgeKind::Supersedes);
+
+    let actual = to_string(&collection);
+    let expected = r#"  <graph id="G" edgedefault="directed">
+    <node id="n0">
+      <data key="d0">https://rust-lang.org/</data>
+      <data key="d1">Rust</data>
+      <data key="d2">programming,rust</data>
+      <data key="d3">2023-11-15T00:00:00Z</data>
+      <data key="d4"></data>
+    </node>
+    <node id="n1">
+      <data key="d0">https://example.com/?a=1&amp;b=2</data>
+      <data key="d1">&lt;Tom&gt; | Tom &amp; Jerry</data>
+      <data key="d2"></data>
+      <data key="d3">2023-11-16T00:00:00Z</data>
+      <data key="d4">2023-11-17T12:30:00Z</data>
+    </node>
+    <edge source="n0" target="n1">
+      <data key="d5">supersedes</data>
+    </edge>
+    <edge source="n1" target="n0">
+      <data key="d5">relatedTo</data>
+    </edge>
+  </graph>
+"#;
+    assert!(actual.contains(expected), "{actual}");
+}
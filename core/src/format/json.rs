@@ -0,0 +1,55 @@
+//! The JSON interchange format.
+//!
+//! A collection is written as a versioned object:
+//!
+//! ```json
+//! {
+//!   "version": "0.1.0",
+//!   "length": 1,
+//!   "value": [
+//!     {
+//!       "id": 0,
+//!       "entity": {
+//!         "uri": "https://example.com/",
+//!         "createdAt": "2023-11-15T00:00:00Z",
+//!         "updatedAt": [],
+//!         "names": ["Example"],
+//!         "labels": ["example"],
+//!         "shared": false,
+//!         "toread": false
+//!       },
+//!       "edges": []
+//!     }
+//!   ]
+//! }
+//! ```
+//!
+//! Times are RFC 3339 date-times, though Unix timestamps, as written before, are read as well, and
+//! `edges` lists the ids of the nodes that a node links to. A node with edges of other than the default kind also has `kinds`, listing the kind
+//! of each edge in the same order. Reading checks `version` against the versions this crate
+//! understands.
+
+#[cfg(test)]
+mod tests;
+
+use std::io::{Read, Write};
+
+use thiserror::Error;
+
+use crate::collection::Collection;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+pub fn to_writer(collection: &Collection, writer: impl Write) -> Result<(), Error> {
+    serde_json::to_writer_pretty(writer, collection)?;
+    Ok(())
+}
+
+pub fn from_reader(reader: impl Read) -> Result<Collection, Error> {
+    let collection = serde_json::from_reader(reader)?;
+    Ok(collection)
+}
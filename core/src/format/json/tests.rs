@@ -0,0 +1,59 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::{Entity, Label, Name};
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let foo = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new(String::from("Foo"))),
+        BTreeSet::from([Label::from("foo")]),
+    );
+    let bar = Entity::new(
+        Url::parse("https://bar.com").unwrap(),
+        datetime!(2023-11-16 0:00 UTC).into(),
+        None,
+        BTreeSet::from([Label::from("bar")]),
+    );
+    let id_foo = collection.insert(foo);
+    let id_bar = collection.insert(bar);
+    collection.add_edge(id_foo, id_bar);
+    collection
+}
+
+#[test]
+fn test_round_trip() {
+    let collection = create_test_collection();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let actual = from_reader(buf.as_slice()).unwrap();
+    assert_eq!(collection, actual);
+}
+
+#[test]
+fn test_schema() {
+    let collection = create_test_collection();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let value: serde_json::Value = serde_json::from_slice(&buf).unwrap();
+    assert_eq!(value["version"], "0.1.0");
+    assert_eq!(value["length"], 2);
+    let node = &value["value"][0];
+    assert_eq!(node["id"], 0);
+    assert_eq!(node["edges"], serde_json::json!([1]));
+    assert_eq!(node["entity"]["uri"], "https://foo.com/");
+    assert_eq!(node["entity"]["createdAt"], "2023-11-15T00:00:00Z");
+    assert_eq!(node["entity"]["names"], serde_json::json!(["Foo"]));
+    assert_eq!(node["entity"]["labels"], serde_json::json!(["foo"]));
+}
+
+#[test]
+fn test_invalid() {
+    let err = from_reader("[]".as_bytes()).unwrap_err();
+    assert!(err.to_string().starts_with("JSON error"));
+}
@@ -0,0 +1,324 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cell::RefCell,
+    collections::{BTreeSet, VecDeque},
+    io::{self, Read, Write},
+    mem,
+};
+
+use html5ever::{
+    tendril::StrTendril,
+    tokenizer::{
+        BufferQueue, Tag, TagKind, Token, TokenSink, TokenSinkResult, Tokenizer, TokenizerOpts,
+    },
+};
+use thiserror::Error;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("integer parsing error: {0}, {1}")]
+    ParseInt(#[source] std::num::ParseIntError, String),
+    #[error("time parsing error: {0}")]
+    ParseTime(#[from] time::error::ComponentRange),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+const ELEMENT_ANCHOR: &str = "a";
+const ELEMENT_DESCRIPTION_TERM: &str = "dt";
+const ELEMENT_FOLDER: &str = "h3";
+const ELEMENT_LIST: &str = "dl";
+const ATTR_HREF: &str = "href";
+const ATTR_ADD_DATE: &str = "add_date";
+const ATTR_TAGS: &str = "tags";
+
+/// How many bytes [`Reader`] reads at a time.
+const CHUNK_SIZE: usize = 64 * 1024;
+
+/// Parses a `NETSCAPE-Bookmark-file-1` document into a [`Collection`].
+///
+/// Each bookmark's `TAGS` and the names of the folders enclosing it become its labels, and its
+/// `ADD_DATE` becomes its creation time.
+pub fn parse(input: &str) -> Result<Collection, Error> {
+    let mut ret = Collection::new();
+    for entity in Reader::new(input.as_bytes()) {
+        ret.upsert(entity?);
+    }
+    Ok(ret)
+}
+
+/// Reads the bookmarks of a `NETSCAPE-Bookmark-file-1` document one at a time, as with
+/// [`parse`].
+///
+/// The document is read a chunk at a time as bookmarks are asked for, so memory use does not grow
+/// with the size of the document.  Duplicate bookmarks are yielded as they appear.
+pub struct Reader<R> {
+    reader: R,
+    tokenizer: Tokenizer<Sink>,
+    queue: BufferQueue,
+    /// The bytes of a character split across chunks.
+    partial: Vec<u8>,
+    done: bool,
+}
+
+impl<R: Read> Reader<R> {
+    pub fn new(reader: R) -> Reader<R> {
+        Reader {
+            reader,
+            tokenizer: Tokenizer::new(Sink::default(), TokenizerOpts::default()),
+            queue: BufferQueue::default(),
+            partial: Vec::new(),
+            done: false,
+        }
+    }
+
+    /// Feeds the next chunk of the document to the tokenizer.
+    fn fill(&mut self) -> Result<(), Error> {
+        let mut chunk = mem::take(&mut self.partial);
+        let start = chunk.len();
+        chunk.resize(start + CHUNK_SIZE, 0);
+        let read = self.reader.read(&mut chunk[start..])?;
+        chunk.truncate(start + read);
+        if read == 0 {
+            self.done = true;
+            if !chunk.is_empty() {
+                return Err(invalid_utf8());
+            }
+            self.tokenizer.end();
+            return Ok(());
+        }
+        let valid = match std::str::from_utf8(&chunk) {
+            Ok(text) => text,
+            Err(err) if err.error_len().is_none() => {
+                let (valid, partial) = chunk.split_at(err.valid_up_to());
+                self.partial = partial.to_vec();
+                std::str::from_utf8(valid).expect("valid UTF-8")
+            }
+            Err(_) => {
+                self.done = true;
+                return Err(invalid_utf8());
+            }
+        };
+        self.queue.push_back(StrTendril::from(valid));
+        let _ = self.tokenizer.feed(&self.queue);
+        Ok(())
+    }
+}
+
+fn invalid_utf8() -> Error {
+    Error::Io(io::Error::new(io::ErrorKind::InvalidData, "stream did not contain valid UTF-8"))
+}
+
+impl<R: Read> Iterator for Reader<R> {
+    type Item = Result<Entity, Error>;
+
+    fn next(&mut self) -> Option<Self::Item> {
+        loop {
+            if let Some(entity) = self.tokenizer.sink.state.borrow_mut().entities.pop_front() {
+                return Some(entity);
+            }
+            if self.done {
+                return None;
+            }
+            if let Err(err) = self.fill() {
+                return Some(Err(err));
+            }
+        }
+    }
+}
+
+/// A bookmark whose closing tag has not been reached.
+#[derive(Debug, Default)]
+struct Anchor {
+    href: Option<String>,
+    add_date: Option<String>,
+    tags: Option<String>,
+    text: String,
+}
+
+impl Anchor {
+    fn new(tag: &Tag) -> Anchor {
+        let mut ret = Anchor::default();
+        for attr in tag.attrs.iter() {
+            let value = Some(attr.value.to_string());
+            match &*attr.name.local {
+                ATTR_HREF => ret.href = value,
+                ATTR_ADD_DATE => ret.add_date = value,
+                ATTR_TAGS => ret.tags = value,
+                _ => (),
+            }
+        }
+        ret
+    }
+
+    fn into_entity<'a>(
+        self,
+        folders: impl Iterator<Item = &'a Label>,
+    ) -> Result<Option<Entity>, Error> {
+        let Some(href) = self.href else {
+            return Ok(None);
+        };
+        let url = Url::parse(&href).map_err(|err| Error::ParseUrl(err, href.clone()))?;
+        let created_at = match self.add_date {
+            Some(add_date) => {
+                let timestamp: i64 =
+                    add_date.parse().map_err(|err| Error::ParseInt(err, add_date.clone()))?;
+                Time::new(OffsetDateTime::from_unix_timestamp(timestamp)?)
+            }
+            None => Time::default(),
+        };
+        let name = {
+            let text = self.text.trim();
+            if text.is_empty() {
+                None
+            } else {
+                Some(Name::new(text.to_string()))
+            }
+        };
+        let mut labels: BTreeSet<Label> = folders.cloned().collect();
+        if let Some(tags) = self.tags {
+            let tags = tags.split(',').map(str::trim).filter(|tag| !tag.is_empty());
+            labels.extend(tags.map(Label::from));
+        }
+        Ok(Some(Entity::new(url, created_at, name, labels)))
+    }
+}
+
+#[derive(Debug, Default)]
+struct State {
+    /// The folder of each open list, innermost last, or `None` for a list outside any folder.
+    folders: Vec<Option<Label>>,
+    /// A folder whose list has not been opened yet.
+    folder: Option<Label>,
+    /// The text of a folder name being read.
+    heading: Option<String>,
+    anchor: Option<Anchor>,
+    entities: VecDeque<Result<Entity, Error>>,
+}
+
+impl State {
+    fn finish_anchor(&mut self) {
+        let Some(anchor) = self.anchor.take() else {
+            return;
+        };
+        match anchor.into_entity(self.folders.iter().flatten()) {
+            Ok(Some(entity)) => self.entities.push_back(Ok(entity)),
+            Ok(None) => (),
+            Err(err) => self.entities.push_back(Err(err)),
+        }
+    }
+
+    fn tag(&mut self, tag: Tag) {
+        match (tag.kind, &*tag.name) {
+            (TagKind::StartTag, ELEMENT_ANCHOR) => {
+                self.finish_anchor();
+                self.anchor = Some(Anchor::new(&tag));
+            }
+            (TagKind::EndTag, ELEMENT_ANCHOR) => self.finish_anchor(),
+            (TagKind::StartTag, ELEMENT_FOLDER) => {
+                self.finish_anchor();
+                self.heading = Some(String::new());
+            }
+            (TagKind::EndTag, ELEMENT_FOLDER) => {
+                if let Some(name) = self.heading.take() {
+                    self.folder = Some(Label::new(name.trim().to_string()));
+                }
+            }
+            (TagKind::StartTag, ELEMENT_LIST) => {
+                self.finish_anchor();
+                self.folders.push(self.folder.take());
+            }
+            (TagKind::EndTag, ELEMENT_LIST) => {
+                self.finish_anchor();
+                self.folders.pop();
+                self.folder = None;
+            }
+            (TagKind::StartTag, ELEMENT_DESCRIPTION_TERM) => {
+                self.finish_anchor();
+                self.folder = None;
+            }
+            _ => (),
+        }
+    }
+
+    fn text(&mut self, text: &str) {
+        if let Some(anchor) = &mut self.anchor {
+            anchor.text.push_str(text);
+        } else if let Some(heading) = &mut self.heading {
+            heading.push_str(text);
+        }
+    }
+}
+
+#[derive(Debug, Default)]
+struct Sink {
+    state: RefCell<State>,
+}
+
+impl TokenSink for Sink {
+    type Handle = ();
+
+    fn process_token(&self, token: Token, _line_number: u64) -> TokenSinkResult<()> {
+        let mut state = self.state.borrow_mut();
+        match token {
+            Token::TagToken(tag) => state.tag(tag),
+            Token::CharacterTokens(text) => state.text(&text),
+            Token::EOFToken => state.finish_anchor(),
+            _ => (),
+        }
+        TokenSinkResult::Continue
+    }
+}
+
+/// Writes a [`Collection`] as a `NETSCAPE-Bookmark-file-1` document.
+///
+/// Each entity's labels become its `TAGS`, its creation time becomes its `ADD_DATE`, and its first
+/// name becomes the link text.
+pub fn to_writer(collection: &Collection, mut writer: impl Write) -> Result<(), Error> {
+    writeln!(writer, "<!DOCTYPE NETSCAPE-Bookmark-file-1>")?;
+    writeln!(writer, r#"<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">"#)?;
+    writeln!(writer, "<TITLE>Bookmarks</TITLE>")?;
+    writeln!(writer, "<H1>Bookmarks</H1>")?;
+    writeln!(writer, "<DL><p>")?;
+    for entity in collection.entities() {
+        let href = escape(entity.url().as_str());
+        let add_date = entity.created_at().unix_timestamp();
+        let private = if entity.shared() { 0 } else { 1 };
+        let toread = if entity.toread() { 1 } else { 0 };
+        let tags: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+        let tags = escape(&tags.join(","));
+        write!(
+            writer,
+            r#"    <DT><A HREF="{href}" ADD_DATE="{add_date}" PRIVATE="{private}" TOREAD="{toread}" TAGS="{tags}""#
+        )?;
+        if let Some(last_modified) = entity.last_updated_at() {
+            write!(writer, r#" LAST_MODIFIED="{}""#, last_modified.unix_timestamp())?;
+        }
+        let name = entity.names().first().map(Name::as_str).unwrap_or_default();
+        writeln!(writer, ">{}</A>", escape(name))?;
+    }
+    writeln!(writer, "</DL><p>")?;
+    Ok(())
+}
+
+fn escape(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
@@ -0,0 +1,213 @@
+use time::macros::datetime;
+
+use super::*;
+
+const TEST_EMPTY: &str = "";
+
+#[test]
+fn test_empty() {
+    let collection = parse(TEST_EMPTY).unwrap();
+    assert!(collection.is_empty());
+}
+
+const TEST_SAMPLE: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<!-- This is an automatically generated file.
+     It will be read and overwritten.
+     DO NOT EDIT! -->
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://example.com/" ADD_DATE="1700006400">Example</A>
+    <DT><H3 ADD_DATE="1700006400" LAST_MODIFIED="1700006400">Programming</H3>
+    <DL><p>
+        <DT><A HREF="https://rust-lang.org/" ADD_DATE="1700092800" TAGS="rust,languages">Rust</A>
+        <DT><H3>Tools</H3>
+        <DL><p>
+            <DT><A HREF="https://code.visualstudio.com/" ADD_DATE="1700179200">VS Code</A>
+        </DL><p>
+        <DT><A HREF="https://users.rust-lang.org/" ADD_DATE="1700179200" TAGS="rust"></A>
+    </DL><p>
+    <HR>
+    <DT><A HREF="https://foo.com/">Foo</A>
+</DL><p>
+"#;
+
+#[test]
+fn test_sample() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 5);
+
+    let expected = [
+        Entity::new(
+            Url::parse("https://example.com/").unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            Some(Name::new(String::from("Example"))),
+            BTreeSet::new(),
+        ),
+        Entity::new(
+            Url::parse("https://rust-lang.org/").unwrap(),
+            datetime!(2023-11-16 0:00 UTC).into(),
+            Some(Name::new(String::from("Rust"))),
+            BTreeSet::from([
+                Label::from("Programming"),
+                Label::from("languages"),
+                Label::from("rust"),
+            ]),
+        ),
+        Entity::new(
+            Url::parse("https://code.visualstudio.com/").unwrap(),
+            datetime!(2023-11-17 0:00 UTC).into(),
+            Some(Name::new(String::from("VS Code"))),
+            BTreeSet::from([Label::from("Programming"), Label::from("Tools")]),
+        ),
+        Entity::new(
+            Url::parse("https://users.rust-lang.org/").unwrap(),
+            datetime!(2023-11-17 0:00 UTC).into(),
+            None,
+            BTreeSet::from([Label::from("Programming"), Label::from("rust")]),
+        ),
+        Entity::new(
+            Url::parse("https://foo.com/").unwrap(),
+            Time::default(),
+            Some(Name::new(String::from("Foo"))),
+            BTreeSet::new(),
+        ),
+    ];
+
+    for expected in expected {
+        let id = collection.id(expected.url()).unwrap();
+        assert_eq!(&expected, collection.entity(id));
+    }
+}
+
+const TEST_DUPLICATE: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<DL><p>
+    <DT><A HREF="https://foo.com/" ADD_DATE="1700092800" TAGS="foo">Foo</A>
+    <DT><A HREF="https://foo.com/" ADD_DATE="1700006400" TAGS="bar">Foo Again</A>
+</DL><p>
+"#;
+
+#[test]
+fn test_duplicate() {
+    let collection = parse(TEST_DUPLICATE).unwrap();
+    assert_eq!(collection.len(), 1);
+    let id = collection.id(&Url::parse("https://foo.com/").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 0:00 UTC)));
+    assert_eq!(entity.labels(), &BTreeSet::from([Label::from("bar"), Label::from("foo")]));
+    assert_eq!(entity.names().len(), 2);
+}
+
+const TEST_INVALID_URL: &str =
+    r#"<DL><p><DT><A HREF="not a url" ADD_DATE="1700006400">Foo</A></DL>"#;
+
+#[test]
+fn test_invalid_url() {
+    let err = parse(TEST_INVALID_URL).unwrap_err();
+    assert_eq!(err.to_string(), "URL parsing error: relative URL without a base, not a url");
+}
+
+const TEST_INVALID_DATE: &str =
+    r#"<DL><p><DT><A HREF="https://foo.com/" ADD_DATE="yesterday">Foo</A></DL>"#;
+
+#[test]
+fn test_invalid_date() {
+    let err = parse(TEST_INVALID_DATE).unwrap_err();
+    assert!(err.to_string().starts_with("integer parsing error"));
+}
+
+/// Reads one byte at a time, splitting every character and tag across reads.
+struct Trickle<'a>(&'a [u8]);
+
+impl Read for Trickle<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let Some((first, rest)) = self.0.split_first() else {
+            return Ok(0);
+        };
+        buf[0] = *first;
+        self.0 = rest;
+        Ok(1)
+    }
+}
+
+#[test]
+fn test_reader() {
+    let input = TEST_SAMPLE.replace("VS Code", "VS Cödé");
+    let expected: Vec<Entity> = Reader::new(input.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(expected.len(), 5);
+    assert_eq!(expected[2].names().first().unwrap().as_str(), "VS Cödé");
+    let actual: Vec<Entity> =
+        Reader::new(Trickle(input.as_bytes())).collect::<Result<_, _>>().unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_reader_duplicates() {
+    let entities: Vec<Entity> =
+        Reader::new(TEST_DUPLICATE.as_bytes()).collect::<Result<_, _>>().unwrap();
+    assert_eq!(entities.len(), 2);
+}
+
+#[test]
+fn test_reader_invalid_utf8() {
+    let input = b"<DL><p><DT><A HREF=\"https://foo.com/\">Foo \xff</A></DL>";
+    let err = Reader::new(&input[..]).find_map(Result::err).unwrap();
+    assert_eq!(err.to_string(), "IO error: stream did not contain valid UTF-8");
+
+    let input = b"<DL><p><DT><A HREF=\"https://foo.com/\">Foo \xc3";
+    let err = Reader::new(&input[..]).find_map(Result::err).unwrap();
+    assert_eq!(err.to_string(), "IO error: stream did not contain valid UTF-8");
+}
+
+#[test]
+fn test_to_writer() {
+    let mut collection = Collection::new();
+    let foo = Entity::new(
+        Url::parse("https://foo.com/?a=1&b=2").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new(String::from("Foo & <Bar>"))),
+        BTreeSet::from([Label::from("bar"), Label::from("foo")]),
+    );
+    let baz = Entity::new(
+        Url::parse("https://baz.com/").unwrap(),
+        datetime!(2023-11-16 0:00 UTC).into(),
+        None,
+        BTreeSet::new(),
+    );
+    collection.insert(foo);
+    let id = collection.insert(baz);
+    collection.update(id, datetime!(2023-11-17 0:00 UTC).into(), BTreeSet::new(), BTreeSet::new());
+
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let expected = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
+<META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
+<TITLE>Bookmarks</TITLE>
+<H1>Bookmarks</H1>
+<DL><p>
+    <DT><A HREF="https://foo.com/?a=1&amp;b=2" ADD_DATE="1700006400" PRIVATE="1" TOREAD="0" TAGS="bar,foo">Foo &amp; &lt;Bar&gt;</A>
+    <DT><A HREF="https://baz.com/" ADD_DATE="1700092800" PRIVATE="1" TOREAD="0" TAGS="" LAST_MODIFIED="1700179200"></A>
+</DL><p>
+"#;
+    assert_eq!(expected, String::from_utf8(buf).unwrap());
+}
+
+#[test]
+fn test_round_trip() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let actual = parse(&String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!(collection, actual);
+}
+
+#[cfg(feature = "pinboard")]
+#[test]
+fn test_pinboard_compatible() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let posts = crate::pinboard::Post::from_html(&String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!(posts.len(), collection.len());
+}
@@ -0,0 +1,132 @@
+//! An [OPML](http://opml.org/spec2.opml) subscription list, for entities that are feeds.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, Write},
+};
+
+use thiserror::Error;
+
+use crate::collection::{Collection, Id, Label, Name};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Options {
+    pub title: String,
+    /// Writes only the entities with this label or one nested under it, grouped by their labels
+    /// below it.
+    pub label: Option<Label>,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { title: "hbt".to_string(), label: None }
+    }
+}
+
+/// The outlines for one level of the label hierarchy.
+#[derive(Debug, Default)]
+struct Group<'a> {
+    groups: BTreeMap<&'a str, Group<'a>>,
+    ids: Vec<Id>,
+}
+
+impl<'a> Group<'a> {
+    fn add(&mut self, mut segments: impl Iterator<Item = &'a str>, id: Id) {
+        match segments.next() {
+            Some(segment) => self.groups.entry(segment).or_default().add(segments, id),
+            None => self.ids.push(id),
+        }
+    }
+}
+
+/// Writes a [`Collection`] as an OPML document of subscriptions, taking each entity's URL to be
+/// that of a feed.
+///
+/// Each entity becomes an `rss` outline titled with its first name, or its URL if it has none,
+/// inside an outline for each level of each of its labels, so an entity with several labels is
+/// listed under each.  Entities without a label are listed at the top.
+pub fn to_writer(
+    collection: &Collection,
+    options: &Options,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let depth = options.label.as_ref().map_or(0, |label| label.segments().count());
+    let mut top = Group::default();
+    for (id, entity) in collection.iter() {
+        let labels: Vec<&Label> = match &options.label {
+            Some(within) => {
+                let labels: Vec<&Label> =
+                    entity.labels().iter().filter(|label| label.is_within(within)).collect();
+                if labels.is_empty() {
+                    continue;
+                }
+                labels
+            }
+            None => entity.labels().iter().collect(),
+        };
+        if labels.is_empty() {
+            top.ids.push(id);
+        }
+        for label in labels {
+            top.add(label.segments().skip(depth), id);
+        }
+    }
+    writeln!(writer, r#"<?xml version="1.0" encoding="UTF-8"?>"#)?;
+    writeln!(writer, r#"<opml version="2.0">"#)?;
+    writeln!(writer, "  <head>")?;
+    writeln!(writer, "    <title>{}</title>", escape(&options.title))?;
+    writeln!(writer, "  </head>")?;
+    writeln!(writer, "  <body>")?;
+    write_group(&mut writer, collection, &top, 2)?;
+    writeln!(writer, "  </body>")?;
+    writeln!(writer, "</opml>")?;
+    Ok(())
+}
+
+fn write_group(
+    writer: &mut impl Write,
+    collection: &Collection,
+    group: &Group<'_>,
+    level: usize,
+) -> io::Result<()> {
+    let indent = "  ".repeat(level);
+    for (segment, inner) in &group.groups {
+        let segment = escape(segment);
+        writeln!(writer, r#"{indent}<outline text="{segment}" title="{segment}">"#)?;
+        write_group(writer, collection, inner, level + 1)?;
+        writeln!(writer, "{indent}</outline>")?;
+    }
+    for id in &group.ids {
+        let entity = collection.entity(*id);
+        let url = escape(entity.url().as_str());
+        let title = entity.names().first().map(Name::as_str).map_or_else(|| url.clone(), escape);
+        writeln!(
+            writer,
+            r#"{indent}<outline type="rss" text="{title}" title="{title}" xmlUrl="{url}"/>"#
+        )?;
+    }
+    Ok(())
+}
+
+fn escape(input: &str) -> String {
+    let mut ret = String::with_capacity(input.len());
+    for c in input.chars() {
+        match c {
+            '&' => ret.push_str("&amp;"),
+            '<' => ret.push_str("&lt;"),
+            '>' => ret.push_str("&gt;"),
+            '"' => ret.push_str("&quot;"),
+            _ => ret.push(c),
+        }
+    }
+    ret
+}
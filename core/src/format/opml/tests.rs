@@ -0,0 +1,78 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::collection::Entity;
+
+fn to_string(collection: &Collection, options: &Options) -> String {
+    let mut ret = Vec::new();
+    to_writer(collection, options, &mut ret).unwrap();
+    String::from_utf8(ret).unwrap()
+}
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let feeds = [
+        ("https://blog.rust-lang.org/feed.xml", Some("Rust Blog"), &["feeds/tech", "rust"][..]),
+        ("https://example.com/rss?a=1&b=2", Some("News & Views"), &["feeds/news"][..]),
+        ("https://example.org/atom.xml", None, &["feeds"][..]),
+        ("https://example.net/", Some("Unlabeled"), &[][..]),
+    ];
+    for (url, name, labels) in feeds {
+        collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            name.map(|name| Name::new(name.to_string())),
+            labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+        ));
+    }
+    collection
+}
+
+#[test]
+fn test_all() {
+    let actual = to_string(&create_test_collection(), &Options::default());
+    let expected = r#"<?xml version="1.0" encoding="UTF-8"?>
+<opml version="2.0">
+  <head>
+    <title>hbt</title>
+  </head>
+  <body>
+    <outline text="feeds" title="feeds">
+      <outline text="news" title="news">
+        <outline type="rss" text="News &amp; Views" title="News &amp; Views" xmlUrl="https://example.com/rss?a=1&amp;b=2"/>
+      </outline>
+      <outline text="tech" title="tech">
+        <outline type="rss" text="Rust Blog" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+      </outline>
+      <outline type="rss" text="https://example.org/atom.xml" title="https://example.org/atom.xml" xmlUrl="https://example.org/atom.xml"/>
+    </outline>
+    <outline text="rust" title="rust">
+      <outline type="rss" text="Rust Blog" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+    </outline>
+    <outline type="rss" text="Unlabeled" title="Unlabeled" xmlUrl="https://example.net/"/>
+  </body>
+</opml>
+"#;
+    assert_eq!(actual, expected);
+}
+
+#[test]
+fn test_within_label() {
+    let options = Options { title: "Feeds".to_string(), label: Some(Label::from("feeds")) };
+    let actual = to_string(&create_test_collection(), &options);
+    let expected = r#"  <body>
+    <outline text="news" title="news">
+      <outline type="rss" text="News &amp; Views" title="News &amp; Views" xmlUrl="https://example.com/rss?a=1&amp;b=2"/>
+    </outline>
+    <outline text="tech" title="tech">
+      <outline type="rss" text="Rust Blog" title="Rust Blog" xmlUrl="https://blog.rust-lang.org/feed.xml"/>
+    </outline>
+    <outline type="rss" text="https://example.org/atom.xml" title="https://example.org/atom.xml" xmlUrl="https://example.org/atom.xml"/>
+  </body>
+"#;
+    assert!(actual.contains("<title>Feeds</title>"), "{actual}");
+    assert!(actual.contains(expected), "{actual}");
+}
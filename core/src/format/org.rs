@@ -0,0 +1,462 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeMap, BTreeSet},
+    io::{self, Write},
+};
+
+use thiserror::Error;
+use time::{
+    format_description::BorrowedFormatItem, macros::format_description, Date, OffsetDateTime,
+    UtcOffset,
+};
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("time formatting error: {0}")]
+    FormatTime(#[from] time::error::Format),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("missing date for link on line {0}")]
+    MissingDate(usize),
+}
+
+/// An inactive org timestamp, such as `[2023-11-15 Wed 00:00]`.
+const TIMESTAMP_FORMAT: &[BorrowedFormatItem<'_>] =
+    format_description!("[[[year]-[month]-[day] [weekday repr:short] [hour]:[minute]]");
+
+const DATE_FORMAT: &[BorrowedFormatItem<'_>] = format_description!("[year]-[month]-[day]");
+
+const TIME_FORMAT: &[BorrowedFormatItem<'_>] = format_description!("[hour]:[minute]");
+
+/// A link found in a section, with the line it was found on.
+#[derive(Debug)]
+struct Link {
+    url: Url,
+    name: Option<Name>,
+    line: usize,
+}
+
+/// A headline and the text up to the next headline, or the text before the first headline.
+#[derive(Debug, Default)]
+struct Section {
+    level: usize,
+    /// The headline's tags, along with those it inherits.
+    labels: BTreeSet<Label>,
+    /// The headline's text, without its stars or tags.
+    title: String,
+    links: Vec<Link>,
+    /// The `URL` property, which makes an entity of a headline without links.
+    url: Option<(Url, usize)>,
+    /// The `CREATED` property.
+    created: Option<Time>,
+    /// The first timestamp in the headline or its text.
+    timestamp: Option<Time>,
+}
+
+/// An enclosing headline, for what its subheadlines inherit.
+#[derive(Debug)]
+struct Ancestor {
+    level: usize,
+    labels: BTreeSet<Label>,
+    date: Option<Time>,
+}
+
+/// Parses an org document into a [`Collection`].
+///
+/// Each `[[url][description]]` link to a web page, in a headline or in the text below it, becomes
+/// an entity named by its description and labeled with the headline's tags, including those it
+/// inherits and any `#+FILETAGS:`.  A headline without links but with a `URL` property becomes an
+/// entity named by the headline.  An entity is created at the time in its headline's `CREATED`
+/// property, or else at the first timestamp in the headline or its text, or else at that of the
+/// nearest enclosing headline, or else at the `#+DATE:`.  Links to anything but web pages, such as
+/// files or other headlines, are skipped.  `#+TITLE:` names the collection.
+///
+/// This reads what [`to_writer`] writes.
+pub fn parse(input: &str) -> Result<Collection, Error> {
+    let mut ret = Collection::new();
+    let mut file_labels: BTreeSet<Label> = BTreeSet::new();
+    let mut file_date: Option<Time> = None;
+    let mut ancestors: Vec<Ancestor> = Vec::new();
+    let mut section = Section::default();
+    let mut in_drawer = false;
+
+    for (index, line) in input.lines().enumerate() {
+        let number = index + 1;
+        let trimmed = line.trim();
+        if let Some((level, rest)) = headline(line) {
+            finish(
+                &mut ret,
+                &mut ancestors,
+                std::mem::take(&mut section),
+                &file_labels,
+                file_date,
+            )?;
+            in_drawer = false;
+            while ancestors.last().is_some_and(|ancestor| ancestor.level >= level) {
+                ancestors.pop();
+            }
+            let (title, tags) = split_tags(rest);
+            let mut labels = ancestors.last().map(|ancestor| ancestor.labels.clone());
+            let labels = labels.get_or_insert_with(BTreeSet::new);
+            labels.extend(tags.into_iter().map(Label::from));
+            section = Section { level, labels: labels.clone(), ..Section::default() };
+            scan(&mut section, title, number)?;
+            section.title = strip_links(title);
+        } else if in_drawer {
+            if trimmed.eq_ignore_ascii_case(":END:") {
+                in_drawer = false;
+            } else if let Some((key, value)) = property(trimmed) {
+                if key.eq_ignore_ascii_case("URL") && section.level > 0 {
+                    section.url = Some((parse_url(value)?, number));
+                } else if key.eq_ignore_ascii_case("CREATED") {
+                    section.created = find_timestamp(value);
+                }
+            }
+        } else if trimmed.eq_ignore_ascii_case(":PROPERTIES:") {
+            in_drawer = true;
+        } else if let Some((key, value)) = keyword(trimmed) {
+            if key.eq_ignore_ascii_case("TITLE") {
+                ret.set_name(Some(value.to_string()));
+            } else if key.eq_ignore_ascii_case("FILETAGS") {
+                let tags = value.split(':').filter(|tag| !tag.is_empty());
+                file_labels.extend(tags.map(Label::from));
+            } else if key.eq_ignore_ascii_case("DATE") {
+                file_date = find_timestamp(value).or(parse_date(value));
+            }
+        } else {
+            scan(&mut section, line, number)?;
+        }
+    }
+    finish(&mut ret, &mut ancestors, section, &file_labels, file_date)?;
+
+    Ok(ret)
+}
+
+/// Adds the entities of a section to the collection, and records what its subheadlines inherit.
+fn finish(
+    collection: &mut Collection,
+    ancestors: &mut Vec<Ancestor>,
+    section: Section,
+    file_labels: &BTreeSet<Label>,
+    file_date: Option<Time>,
+) -> Result<(), Error> {
+    let inherited = ancestors.iter().rev().find_map(|ancestor| ancestor.date);
+    let date = section.created.or(section.timestamp).or(inherited);
+    let Section { level, labels, title, mut links, url, .. } = section;
+    if links.is_empty() {
+        if let Some((url, line)) = url {
+            let name = Some(title.trim()).filter(|title| !title.is_empty());
+            let name = name.map(|title| Name::new(title.to_string()));
+            links.push(Link { url, name, line });
+        }
+    }
+    for Link { url, name, line } in links {
+        let created_at = date.or(file_date).ok_or(Error::MissingDate(line))?;
+        let labels = labels.iter().chain(file_labels).cloned().collect();
+        collection.upsert(Entity::new(url, created_at, name, labels));
+    }
+    if level > 0 {
+        ancestors.push(Ancestor { level, labels, date });
+    }
+    Ok(())
+}
+
+/// Splits a headline into its level and the rest.
+fn headline(line: &str) -> Option<(usize, &str)> {
+    let level = line.bytes().take_while(|&b| b == b'*').count();
+    let rest = line[level..].strip_prefix([' ', '\t'])?;
+    (level > 0).then_some((level, rest))
+}
+
+/// Splits the tags, as in `Title :a:b:`, from the end of a headline.
+fn split_tags(rest: &str) -> (&str, Vec<&str>) {
+    let rest = rest.trim_end();
+    let (title, last) = match rest.rfind([' ', '\t']) {
+        Some(index) => (&rest[..index], &rest[index + 1..]),
+        None => ("", rest),
+    };
+    let is_tags = last.len() > 1
+        && last.starts_with(':')
+        && last.ends_with(':')
+        && last[1..last.len() - 1]
+            .split(':')
+            .all(|tag| !tag.is_empty() && tag.chars().all(is_tag_char));
+    if is_tags {
+        (title, last.split(':').filter(|tag| !tag.is_empty()).collect())
+    } else {
+        (rest, Vec::new())
+    }
+}
+
+fn is_tag_char(c: char) -> bool {
+    c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%')
+}
+
+/// Splits a `:KEY: value` drawer line.
+fn property(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.strip_prefix(':')?.split_once(':')?;
+    Some((key, value.trim()))
+}
+
+/// Splits a `#+KEY: value` line.
+fn keyword(line: &str) -> Option<(&str, &str)> {
+    let (key, value) = line.strip_prefix("#+")?.split_once(':')?;
+    Some((key, value.trim()))
+}
+
+/// Records the links in the text, and its first timestamp if the section has none yet.
+fn scan(section: &mut Section, text: &str, line: usize) -> Result<(), Error> {
+    if section.timestamp.is_none() {
+        section.timestamp = find_timestamp(text);
+    }
+    for (target, description) in links(text) {
+        if !target.starts_with("http://") && !target.starts_with("https://") {
+            continue;
+        }
+        let url = parse_url(&target)?;
+        let name = description
+            .filter(|text| !text.is_empty())
+            .map(|text| Name::new(text.trim().to_string()));
+        section.links.push(Link { url, name, line });
+    }
+    Ok(())
+}
+
+/// Returns the targets and descriptions of the `[[target][description]]` links in the text, with
+/// backslash escapes in targets undone.
+fn links(text: &str) -> Vec<(String, Option<&str>)> {
+    let mut ret = Vec::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        rest = &rest[start + 2..];
+        let mut target = String::new();
+        let mut chars = rest.char_indices();
+        let mut end = None;
+        while let Some((i, c)) = chars.next() {
+            match c {
+                '\\' => match chars.next() {
+                    Some((_, c @ ('[' | ']' | '\\'))) => target.push(c),
+                    Some((_, c)) => {
+                        target.push('\\');
+                        target.push(c);
+                    }
+                    None => target.push('\\'),
+                },
+                ']' => {
+                    end = Some(i);
+                    break;
+                }
+                '[' => break,
+                c => target.push(c),
+            }
+        }
+        let Some(end) = end else {
+            continue;
+        };
+        rest = &rest[end + 1..];
+        if let Some(after) = rest.strip_prefix(']') {
+            rest = after;
+            ret.push((target, None));
+        } else if let Some(after) = rest.strip_prefix('[') {
+            let Some(close) = after.find("]]") else {
+                continue;
+            };
+            ret.push((target, Some(&after[..close])));
+            rest = &after[close + 2..];
+        }
+    }
+    ret
+}
+
+/// Strips the links in a headline down to their descriptions, or their targets if they have none.
+fn strip_links(text: &str) -> String {
+    let mut ret = String::new();
+    let mut rest = text;
+    while let Some(start) = rest.find("[[") {
+        ret.push_str(&rest[..start]);
+        let after = &rest[start..];
+        match after.find("]]") {
+            Some(end) => {
+                let inner = &after[2..end];
+                let shown = inner.split_once("][").map_or(inner, |(_, description)| description);
+                ret.push_str(shown);
+                rest = &after[end + 2..];
+            }
+            None => {
+                ret.push_str(after);
+                rest = "";
+            }
+        }
+    }
+    ret.push_str(rest);
+    ret
+}
+
+fn parse_url(target: &str) -> Result<Url, Error> {
+    let target = target.replace("%5B", "[").replace("%5D", "]");
+    Url::parse(&target).map_err(|err| Error::ParseUrl(err, target))
+}
+
+fn parse_date(text: &str) -> Option<Time> {
+    let date = Date::parse(text.get(..10)?, DATE_FORMAT).ok()?;
+    Some(OffsetDateTime::new_utc(date, time::Time::MIDNIGHT).into())
+}
+
+/// Returns the first org timestamp in the text, active as in `<2023-11-15 Wed 09:30>` or
+/// inactive as in `[2023-11-15 Wed]`, taken to be in UTC.
+fn find_timestamp(text: &str) -> Option<Time> {
+    for (start, open) in text.match_indices(['[', '<']) {
+        let close = if open == "[" { ']' } else { '>' };
+        let rest = &text[start + 1..];
+        let Some(end) = rest.find(close) else {
+            continue;
+        };
+        let inner = &rest[..end];
+        let Some(date) = inner.get(..10).and_then(|date| Date::parse(date, DATE_FORMAT).ok())
+        else {
+            continue;
+        };
+        let time = inner[10..]
+            .split_whitespace()
+            .find_map(|word| time::Time::parse(word.get(..5).unwrap_or(word), TIME_FORMAT).ok())
+            .unwrap_or(time::Time::MIDNIGHT);
+        return Some(OffsetDateTime::new_utc(date, time).into());
+    }
+    None
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Under a headline for each date an entity was created on.
+    #[default]
+    Date,
+    /// Under a headline for each label, listing an entity once for each of its labels.  Entities
+    /// without labels come first, as top-level headlines.
+    Label,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub group_by: GroupBy,
+}
+
+/// Renders a [`Collection`] as an org document.
+///
+/// See [`to_writer`].
+pub fn export(collection: &Collection, options: &Options) -> Result<String, Error> {
+    let mut ret = Vec::new();
+    to_writer(collection, options, &mut ret)?;
+    Ok(String::from_utf8(ret).expect("org output is UTF-8"))
+}
+
+/// Writes a [`Collection`] as an org document.
+///
+/// Each entity becomes a headline linking to its URL, described by its first name, and tagged
+/// with its labels, followed by a `:PROPERTIES:` drawer holding its URL and creation time in
+/// UTC.  Characters that org does not allow in tags are replaced with `_`.  The collection's name,
+/// if it has one, becomes the document's `#+TITLE:`.
+pub fn to_writer(
+    collection: &Collection,
+    options: &Options,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    if let Some(name) = collection.name() {
+        writeln!(writer, "#+TITLE: {}", single_line(name))?;
+        writeln!(writer)?;
+    }
+    match options.group_by {
+        GroupBy::Date => {
+            let mut sections: BTreeMap<Date, Vec<&Entity>> = BTreeMap::new();
+            for entity in collection.entities() {
+                sections.entry(date(entity.created_at())).or_default().push(entity);
+            }
+            for (date, entities) in sections {
+                writeln!(writer, "* {}", date.format(DATE_FORMAT)?)?;
+                for entity in entities {
+                    write_entity(&mut writer, 2, entity)?;
+                }
+            }
+        }
+        GroupBy::Label => {
+            let mut sections: BTreeMap<&Label, Vec<&Entity>> = BTreeMap::new();
+            for entity in collection.entities() {
+                if entity.labels().is_empty() {
+                    write_entity(&mut writer, 1, entity)?;
+                }
+                for label in entity.labels() {
+                    sections.entry(label).or_default().push(entity);
+                }
+            }
+            for (label, entities) in sections {
+                writeln!(writer, "* {}", single_line(label.as_str()))?;
+                for entity in entities {
+                    write_entity(&mut writer, 2, entity)?;
+                }
+            }
+        }
+    }
+    Ok(())
+}
+
+fn write_entity(writer: &mut impl Write, level: usize, entity: &Entity) -> Result<(), Error> {
+    let url = entity.url();
+    write!(writer, "{} {}", "*".repeat(level), link(url, entity.names().first()))?;
+    if !entity.labels().is_empty() {
+        write!(writer, " :")?;
+        for label in entity.labels() {
+            write!(writer, "{}:", tag(label))?;
+        }
+    }
+    writeln!(writer)?;
+    writeln!(writer, ":PROPERTIES:")?;
+    writeln!(writer, ":URL: {}", url)?;
+    writeln!(writer, ":CREATED: {}", timestamp(entity.created_at())?)?;
+    writeln!(writer, ":END:")?;
+    Ok(())
+}
+
+fn utc(time: &Time) -> OffsetDateTime {
+    OffsetDateTime::from(*time).to_offset(UtcOffset::UTC)
+}
+
+fn date(time: &Time) -> Date {
+    utc(time).date()
+}
+
+fn timestamp(time: &Time) -> Result<String, time::error::Format> {
+    utc(time).format(TIMESTAMP_FORMAT)
+}
+
+/// Returns an org link to the URL, described by the name if there is one.  Brackets, which would
+/// end the link early, are percent-encoded in the URL and replaced with braces in the name.
+fn link(url: &Url, name: Option<&Name>) -> String {
+    let target = url.as_str().replace('[', "%5B").replace(']', "%5D");
+    match name {
+        Some(name) => {
+            let description = single_line(name.as_str()).replace('[', "{").replace(']', "}");
+            format!("[[{}][{}]]", target, description)
+        }
+        None => format!("[[{}]]", target),
+    }
+}
+
+/// Returns the label as an org tag, which may only hold letters, digits, `_`, `@`, `#` and `%`.
+fn tag(label: &Label) -> String {
+    label
+        .as_str()
+        .chars()
+        .map(|c| if c.is_alphanumeric() || matches!(c, '_' | '@' | '#' | '%') { c } else { '_' })
+        .collect()
+}
+
+/// Joins the lines of the text, which would otherwise end a headline.
+fn single_line(text: &str) -> String {
+    text.lines().map(str::trim).collect::<Vec<_>>().join(" ")
+}
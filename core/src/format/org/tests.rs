@@ -0,0 +1,165 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+
+fn entity(url: &str, created_at: Time, name: Option<&str>, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        created_at,
+        name.map(|name| Name::new(name.to_string())),
+        labels.iter().copied().map(Label::from).collect::<BTreeSet<_>>(),
+    )
+}
+
+fn sample() -> Collection {
+    let mut ret = Collection::new();
+    let first = datetime!(2023-11-15 9:30 UTC).into();
+    let second = datetime!(2023-11-16 0:00 UTC).into();
+    ret.insert(entity("https://rust-lang.org/", first, Some("Rust [lang]"), &["rust"]));
+    ret.insert(entity("https://users.rust-lang.org/", first, None, &["forum", "rust"]));
+    ret.insert(entity("https://example.com/a[1]", second, Some("Example"), &["web dev"]));
+    ret.insert(entity("https://example.org/", second, Some("Multi\nline"), &[]));
+    ret
+}
+
+#[test]
+fn test_empty() {
+    let collection = Collection::new();
+    assert_eq!(export(&collection, &Options::default()).unwrap(), "");
+}
+
+#[test]
+fn test_export() {
+    let mut collection = sample();
+    collection.set_name(Some(String::from("Links")));
+    let expected = "#+TITLE: Links
+
+* 2023-11-15
+** [[https://rust-lang.org/][Rust {lang}]] :rust:
+:PROPERTIES:
+:URL: https://rust-lang.org/
+:CREATED: [2023-11-15 Wed 09:30]
+:END:
+** [[https://users.rust-lang.org/]] :forum:rust:
+:PROPERTIES:
+:URL: https://users.rust-lang.org/
+:CREATED: [2023-11-15 Wed 09:30]
+:END:
+* 2023-11-16
+** [[https://example.com/a%5B1%5D][Example]] :web_dev:
+:PROPERTIES:
+:URL: https://example.com/a[1]
+:CREATED: [2023-11-16 Thu 00:00]
+:END:
+** [[https://example.org/][Multi line]]
+:PROPERTIES:
+:URL: https://example.org/
+:CREATED: [2023-11-16 Thu 00:00]
+:END:
+";
+    assert_eq!(export(&collection, &Options::default()).unwrap(), expected);
+}
+
+#[test]
+fn test_group_by_label() {
+    let options = Options { group_by: GroupBy::Label };
+    let output = export(&sample(), &options).unwrap();
+    let headlines: Vec<&str> = output.lines().filter(|line| line.starts_with('*')).collect();
+    assert_eq!(
+        headlines,
+        vec![
+            "* [[https://example.org/][Multi line]]",
+            "* forum",
+            "** [[https://users.rust-lang.org/]] :forum:rust:",
+            "* rust",
+            "** [[https://rust-lang.org/][Rust {lang}]] :rust:",
+            "** [[https://users.rust-lang.org/]] :forum:rust:",
+            "* web dev",
+            "** [[https://example.com/a%5B1%5D][Example]] :web_dev:",
+        ]
+    );
+}
+
+#[test]
+fn test_roundtrip() {
+    let mut collection = sample();
+    collection.set_name(Some(String::from("Links")));
+    let parsed = parse(&export(&collection, &Options::default()).unwrap()).unwrap();
+    assert_eq!(parsed.name(), Some("Links"));
+    assert_eq!(parsed.len(), collection.len());
+    for (_, entity) in &collection {
+        let id = parsed.id(entity.url()).unwrap();
+        let parsed = parsed.entity(id);
+        assert_eq!(parsed.created_at(), entity.created_at());
+        let labels: Vec<&str> = parsed.labels().iter().map(Label::as_str).collect();
+        let expected: Vec<String> =
+            entity.labels().iter().map(|label| label.as_str().replace(' ', "_")).collect();
+        assert_eq!(labels, expected);
+    }
+}
+
+#[test]
+fn test_parse() {
+    let input = "#+TITLE: Reading
+#+FILETAGS: :reading:
+#+DATE: 2024-01-01
+
+Notes before the first headline, see [[https://example.net/][Example]].
+
+* Rust :rust:
+** Posts <2024-02-03 Sat 10:15>
+- [[https://blog.rust-lang.org/][Rust Blog]] and [[https://this-week-in-rust.org/]]
+- [[file:notes.org][Notes]] and [[*Rust][Rust]] are skipped
+*** Async :async:
+[[https://rust-lang.github.io/async-book/][Async Book]]
+** Tools
+:PROPERTIES:
+:URL: https://crates.io/
+:CREATED: [2024-03-04 Mon 08:00]
+:END:
+* Undated
+[[https://example.org/][Example]]
+";
+    let collection = parse(input).unwrap();
+    assert_eq!(collection.name(), Some("Reading"));
+    assert_eq!(collection.len(), 6);
+
+    let get = |url: &str| collection.entity(collection.id(&Url::parse(url).unwrap()).unwrap());
+    fn labels(entity: &Entity) -> Vec<&str> {
+        entity.labels().iter().map(Label::as_str).collect()
+    }
+    fn names(entity: &Entity) -> Vec<&str> {
+        entity.names().iter().map(Name::as_str).collect()
+    }
+    let posted = Time::from(datetime!(2024-02-03 10:15 UTC));
+    let default = Time::from(datetime!(2024-01-01 0:00 UTC));
+
+    let entity = get("https://example.net/");
+    assert_eq!(labels(entity), vec!["reading"]);
+    assert_eq!(entity.created_at(), &default);
+
+    let entity = get("https://blog.rust-lang.org/");
+    assert_eq!(names(entity), vec!["Rust Blog"]);
+    assert_eq!(labels(entity), vec!["reading", "rust"]);
+    assert_eq!(entity.created_at(), &posted);
+    assert!(get("https://this-week-in-rust.org/").names().is_empty());
+
+    // Tags and dates are inherited from enclosing headlines
+    let entity = get("https://rust-lang.github.io/async-book/");
+    assert_eq!(labels(entity), vec!["async", "reading", "rust"]);
+    assert_eq!(entity.created_at(), &posted);
+
+    let entity = get("https://crates.io/");
+    assert_eq!(names(entity), vec!["Tools"]);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2024-03-04 8:00 UTC)));
+
+    assert_eq!(get("https://example.org/").created_at(), &default);
+}
+
+#[test]
+fn test_parse_missing_date() {
+    let input = "* Links\n\n[[https://example.com/][Example]]\n";
+    assert!(matches!(parse(input), Err(Error::MissingDate(3))));
+}
@@ -0,0 +1,124 @@
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use scraper::{Html, Selector};
+use serde::Deserialize;
+use thiserror::Error;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTML selector error: {0}")]
+    HtmlSelector(String),
+    #[error("CSV parsing error: {0}")]
+    ParseCsv(#[from] csv::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("integer parsing error: {0}, {1}")]
+    ParseInt(#[source] std::num::ParseIntError, String),
+    #[error("time parsing error: {0}")]
+    ParseTime(#[from] time::error::ComponentRange),
+}
+
+const STATUS_UNREAD: &str = "unread";
+
+/// The metadata key under which a Pocket CSV item's `status` is kept.
+pub const META_STATUS: &str = "pocket.status";
+const HEADING_UNREAD: &str = "Unread";
+
+/// Parses a Pocket HTML export (`ril_export.html`) into a [`Collection`].
+///
+/// Items listed under the "Unread" heading are marked to-read.
+pub fn parse_html(input: &str) -> Result<Collection, Error> {
+    const SELECTOR: &str = "h1, a";
+    const ELEMENT_HEADING: &str = "h1";
+    const ATTR_HREF: &str = "href";
+    const ATTR_TIME_ADDED: &str = "time_added";
+    const ATTR_TAGS: &str = "tags";
+
+    let document = Html::parse_document(input);
+    let selector = Selector::parse(SELECTOR).map_err(|err| Error::HtmlSelector(err.to_string()))?;
+
+    let mut ret = Collection::new();
+    let mut unread = false;
+
+    for element in document.select(&selector) {
+        let text = element.text().collect::<String>();
+        let attrs = element.value();
+        if attrs.name() == ELEMENT_HEADING {
+            unread = text.trim() == HEADING_UNREAD;
+            continue;
+        }
+        let Some(href) = attrs.attr(ATTR_HREF) else {
+            continue;
+        };
+        let time_added = attrs.attr(ATTR_TIME_ADDED).unwrap_or_default();
+        let tags = attrs.attr(ATTR_TAGS).unwrap_or_default().split(',');
+        let entity = create_entity(href, time_added, &text, tags, unread)?;
+        ret.upsert(entity);
+    }
+
+    Ok(ret)
+}
+
+#[derive(Debug, Deserialize)]
+struct Record {
+    title: String,
+    url: String,
+    time_added: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    status: String,
+}
+
+/// Parses a Pocket CSV export into a [`Collection`].
+///
+/// The export has `title`, `url`, `time_added`, `tags` and `status` columns, with tags separated by
+/// `|`.  Items whose status is `unread` are marked to-read, and each item's status is kept as
+/// [`META_STATUS`] metadata.
+pub fn parse_csv(input: &str) -> Result<Collection, Error> {
+    let mut reader = csv::Reader::from_reader(input.as_bytes());
+    let mut ret = Collection::new();
+    for record in reader.deserialize() {
+        let Record { title, url, time_added, tags, status } = record?;
+        let unread = status == STATUS_UNREAD;
+        let mut entity = create_entity(&url, &time_added, &title, tags.split('|'), unread)?;
+        if !status.is_empty() {
+            entity.set_metadata(META_STATUS.to_string(), status);
+        }
+        ret.upsert(entity);
+    }
+    Ok(ret)
+}
+
+fn create_entity<'a>(
+    href: &str,
+    time_added: &str,
+    title: &str,
+    tags: impl Iterator<Item = &'a str>,
+    unread: bool,
+) -> Result<Entity, Error> {
+    let url = Url::parse(href).map_err(|err| Error::ParseUrl(err, href.to_string()))?;
+    let created_at = if time_added.is_empty() {
+        Time::default()
+    } else {
+        let timestamp: i64 =
+            time_added.parse().map_err(|err| Error::ParseInt(err, time_added.to_string()))?;
+        Time::new(OffsetDateTime::from_unix_timestamp(timestamp)?)
+    };
+    let title = title.trim();
+    // Pocket uses the URL itself as the title of items it could not fetch
+    let name =
+        if title.is_empty() || title == href { None } else { Some(Name::new(title.to_string())) };
+    let labels: BTreeSet<Label> =
+        tags.map(str::trim).filter(|tag| !tag.is_empty()).map(Label::from).collect();
+    let mut entity = Entity::new(url, created_at, name, labels);
+    entity.set_toread(unread);
+    Ok(entity)
+}
@@ -0,0 +1,114 @@
+use time::macros::datetime;
+
+use super::*;
+
+const TEST_HTML_SAMPLE: &str = r#"<!DOCTYPE html>
+<html>
+	<!--So long and thanks for all the fish-->
+	<head>
+		<meta http-equiv="Content-Type" content="text/html; charset=UTF-8" />
+		<title>Pocket Export</title>
+	</head>
+	<body>
+		<h1>Unread</h1>
+		<ul>
+			<li><a href="https://foo.com/article" time_added="1700006400" tags="foo,bar">Foo Article</a></li>
+			<li><a href="https://bar.com/" time_added="1700092800" tags="">https://bar.com/</a></li>
+		</ul>
+
+		<h1>Read Archive</h1>
+		<ul>
+			<li><a href="https://baz.com/post" time_added="1700179200" tags="baz">Baz Post</a></li>
+		</ul>
+	</body>
+</html>
+"#;
+
+#[test]
+fn test_html_sample() {
+    let collection = parse_html(TEST_HTML_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 3);
+
+    let expected = {
+        let mut tmp = Entity::new(
+            Url::parse("https://foo.com/article").unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            Some(Name::new(String::from("Foo Article"))),
+            BTreeSet::from([Label::from("bar"), Label::from("foo")]),
+        );
+        tmp.set_toread(true);
+        tmp
+    };
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+
+    let expected = {
+        let mut tmp = Entity::new(
+            Url::parse("https://bar.com/").unwrap(),
+            datetime!(2023-11-16 0:00 UTC).into(),
+            None,
+            BTreeSet::new(),
+        );
+        tmp.set_toread(true);
+        tmp
+    };
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+
+    let expected = Entity::new(
+        Url::parse("https://baz.com/post").unwrap(),
+        datetime!(2023-11-17 0:00 UTC).into(),
+        Some(Name::new(String::from("Baz Post"))),
+        BTreeSet::from([Label::from("baz")]),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+}
+
+const TEST_CSV_SAMPLE: &str = "\
+title,url,time_added,tags,status
+Foo Article,https://foo.com/article,1700006400,foo|bar,unread
+https://bar.com/,https://bar.com/,1700092800,,unread
+\"Baz, Post\",https://baz.com/post,1700179200,baz,archive
+";
+
+#[test]
+fn test_csv_sample() {
+    let collection = parse_csv(TEST_CSV_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 3);
+
+    let id = collection.id(&Url::parse("https://foo.com/article").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.labels(), &BTreeSet::from([Label::from("bar"), Label::from("foo")]));
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 0:00 UTC)));
+    assert!(entity.toread());
+
+    let id = collection.id(&Url::parse("https://bar.com/").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert!(entity.names().is_empty());
+    assert!(entity.labels().is_empty());
+
+    let id = collection.id(&Url::parse("https://baz.com/post").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.names().first().unwrap().as_str(), "Baz, Post");
+    assert!(!entity.toread());
+    assert_eq!(entity.metadata_value(META_STATUS), Some("archive"));
+}
+
+#[test]
+fn test_csv_and_html_agree() {
+    let html = parse_html(TEST_HTML_SAMPLE).unwrap();
+    let csv = parse_csv(TEST_CSV_SAMPLE).unwrap();
+    for (_, entity) in html.iter() {
+        let id = csv.id(entity.url()).unwrap();
+        assert_eq!(entity.labels(), csv.entity(id).labels());
+        assert_eq!(entity.toread(), csv.entity(id).toread());
+    }
+}
+
+#[test]
+fn test_csv_invalid_time() {
+    let input = "title,url,time_added,tags,status\nFoo,https://foo.com/,soon,,unread\n";
+    let err = parse_csv(input).unwrap_err();
+    assert!(err.to_string().starts_with("integer parsing error"));
+}
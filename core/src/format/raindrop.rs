@@ -0,0 +1,79 @@
+#[cfg(test)]
+mod tests;
+
+use std::{collections::BTreeSet, io::Write};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("CSV error: {0}")]
+    Csv(#[from] csv::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("time parsing error: {0}, {1}")]
+    ParseTime(#[source] time::error::Parse, String),
+    #[error("time formatting error: {0}")]
+    FormatTime(#[from] time::error::Format),
+}
+
+/// A row of a Raindrop.io CSV export.
+///
+/// Raindrop exports more columns than these, which are ignored when reading.
+#[derive(Debug, Deserialize, Serialize)]
+struct Record {
+    #[serde(default)]
+    title: String,
+    url: String,
+    #[serde(default)]
+    tags: String,
+    #[serde(default)]
+    created: String,
+}
+
+/// Parses a Raindrop.io CSV export into a [`Collection`].
+///
+/// Tags are separated by commas and `created` is an RFC 3339 date-time.
+pub fn parse(input: &str) -> Result<Collection, Error> {
+    let mut reader = csv::Reader::from_reader(input.as_bytes());
+    let mut ret = Collection::new();
+    for record in reader.deserialize() {
+        let Record { title, url, tags, created } = record?;
+        let url = Url::parse(&url).map_err(|err| Error::ParseUrl(err, url))?;
+        let created_at = if created.is_empty() {
+            Time::default()
+        } else {
+            let time = OffsetDateTime::parse(&created, &Rfc3339)
+                .map_err(|err| Error::ParseTime(err, created))?;
+            Time::new(time)
+        };
+        let name = if title.is_empty() { None } else { Some(Name::new(title)) };
+        let labels: BTreeSet<Label> =
+            tags.split(',').map(str::trim).filter(|tag| !tag.is_empty()).map(Label::from).collect();
+        let entity = Entity::new(url, created_at, name, labels);
+        ret.upsert(entity);
+    }
+    Ok(ret)
+}
+
+/// Writes a [`Collection`] as CSV that Raindrop.io can import.
+///
+/// Entities with several names are written with their first name.
+pub fn to_writer(collection: &Collection, writer: impl Write) -> Result<(), Error> {
+    let mut writer = csv::Writer::from_writer(writer);
+    for entity in collection.entities() {
+        let title = entity.names().first().map(Name::as_str).unwrap_or_default().to_string();
+        let url = entity.url().to_string();
+        let tags: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+        let tags = tags.join(",");
+        let created = OffsetDateTime::from(*entity.created_at()).format(&Rfc3339)?;
+        writer.serialize(Record { title, url, tags, created })?;
+    }
+    writer.flush().map_err(csv::Error::from)?;
+    Ok(())
+}
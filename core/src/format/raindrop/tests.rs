@@ -0,0 +1,67 @@
+use time::macros::datetime;
+
+use super::*;
+
+const TEST_SAMPLE: &str = "\
+id,title,note,excerpt,url,folder,tags,created,cover,highlights,favorite
+1,Foo Article,,,https://foo.com/article,Unsorted,\"foo, bar\",2023-11-15T00:00:00.000Z,,,
+2,,,,https://bar.com/,Unsorted,,2023-11-16T12:30:00.000Z,,,
+3,\"Baz, Post\",a note,,https://baz.com/post,Reading,baz,2023-11-17T00:00:00.000Z,,,true
+";
+
+#[test]
+fn test_sample() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 3);
+
+    let expected = Entity::new(
+        Url::parse("https://foo.com/article").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new(String::from("Foo Article"))),
+        BTreeSet::from([Label::from("bar"), Label::from("foo")]),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+
+    let expected = Entity::new(
+        Url::parse("https://bar.com/").unwrap(),
+        datetime!(2023-11-16 12:30 UTC).into(),
+        None,
+        BTreeSet::new(),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+
+    let id = collection.id(&Url::parse("https://baz.com/post").unwrap()).unwrap();
+    assert_eq!(collection.entity(id).names().first().unwrap().as_str(), "Baz, Post");
+}
+
+#[test]
+fn test_to_writer() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let expected = "\
+title,url,tags,created
+Foo Article,https://foo.com/article,\"bar,foo\",2023-11-15T00:00:00Z
+,https://bar.com/,,2023-11-16T12:30:00Z
+\"Baz, Post\",https://baz.com/post,baz,2023-11-17T00:00:00Z
+";
+    assert_eq!(expected, String::from_utf8(buf).unwrap());
+}
+
+#[test]
+fn test_round_trip() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    let mut buf = Vec::new();
+    to_writer(&collection, &mut buf).unwrap();
+    let actual = parse(&String::from_utf8(buf).unwrap()).unwrap();
+    assert_eq!(collection, actual);
+}
+
+#[test]
+fn test_invalid_created() {
+    let input = "title,url,tags,created\nFoo,https://foo.com/,,yesterday\n";
+    let err = parse(input).unwrap_err();
+    assert!(err.to_string().starts_with("time parsing error"));
+}
@@ -0,0 +1,260 @@
+//! Safari's `Bookmarks.plist`, a binary property list.
+//!
+//! The bookmarks form a tree of dictionaries, each with a `WebBookmarkType`: lists have a `Title`
+//! and `Children`, and leaves have a `URLString` and a `URIDictionary` holding their `title`.
+//! Items in the Reading List also have a `ReadingList` dictionary holding their `DateAdded`.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::{BTreeMap, BTreeSet};
+
+use thiserror::Error;
+use time::OffsetDateTime;
+use url::Url;
+
+use crate::collection::{Collection, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("invalid property list: {0}")]
+    Plist(&'static str),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("time parsing error: {0}")]
+    ParseTime(#[from] time::error::ComponentRange),
+}
+
+/// Seconds from the Unix epoch to 2001-01-01, where property list dates start.
+const EPOCH_OFFSET: f64 = 978_307_200.0;
+
+/// How deeply values may nest, which also stops a list that contains itself.
+const MAX_DEPTH: usize = 256;
+
+const TYPE_LIST: &str = "WebBookmarkTypeList";
+const TYPE_LEAF: &str = "WebBookmarkTypeLeaf";
+
+/// The titles of the built-in lists, which are not labels.
+const BOOKMARKS_BAR: &str = "BookmarksBar";
+const BOOKMARKS_MENU: &str = "BookmarksMenu";
+const READING_LIST: &str = "com.apple.ReadingList";
+
+/// A value in a property list, keeping only what bookmarks are made of.
+#[derive(Debug, Clone, PartialEq)]
+enum Value {
+    /// Seconds since 2001-01-01 UTC.
+    Date(f64),
+    String(String),
+    Array(Vec<Value>),
+    Dict(BTreeMap<String, Value>),
+    /// Booleans, numbers, data and the rest.
+    Other,
+}
+
+impl Value {
+    fn get(&self, key: &str) -> Option<&Value> {
+        match self {
+            Value::Dict(dict) => dict.get(key),
+            _ => None,
+        }
+    }
+
+    fn as_str(&self) -> Option<&str> {
+        match self {
+            Value::String(string) => Some(string),
+            _ => None,
+        }
+    }
+}
+
+/// Parses the objects of a `bplist00` document.
+struct Reader<'a> {
+    bytes: &'a [u8],
+    offsets: Vec<usize>,
+    ref_size: usize,
+}
+
+/// Reads a big-endian unsigned integer of up to eight bytes.
+fn uint(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0, |acc, &byte| acc << 8 | u64::from(byte))
+}
+
+impl<'a> Reader<'a> {
+    fn new(bytes: &'a [u8]) -> Result<Reader<'a>, Error> {
+        if bytes.len() < 40 || !bytes.starts_with(b"bplist00") {
+            return Err(Error::Plist("not a binary property list"));
+        }
+        let trailer = &bytes[bytes.len() - 32..];
+        let offset_size = usize::from(trailer[6]);
+        let ref_size = usize::from(trailer[7]);
+        if !(1..=8).contains(&offset_size) || !(1..=8).contains(&ref_size) {
+            return Err(Error::Plist("bad integer size"));
+        }
+        let count = usize::try_from(uint(&trailer[8..16])).unwrap_or(usize::MAX);
+        let table = usize::try_from(uint(&trailer[24..32])).unwrap_or(usize::MAX);
+        let end = count
+            .checked_mul(offset_size)
+            .and_then(|len| table.checked_add(len))
+            .filter(|&end| end <= bytes.len() - 32)
+            .ok_or(Error::Plist("bad offset table"))?;
+        let offsets = bytes[table..end]
+            .chunks(offset_size)
+            .map(|chunk| usize::try_from(uint(chunk)).unwrap_or(usize::MAX))
+            .collect();
+        Ok(Reader { bytes, offsets, ref_size })
+    }
+
+    /// Returns the top object.
+    fn top(&self) -> Result<Value, Error> {
+        let trailer = &self.bytes[self.bytes.len() - 32..];
+        self.value(uint(&trailer[16..24]), 0)
+    }
+
+    fn slice(&self, start: usize, len: usize) -> Result<&'a [u8], Error> {
+        let end = start.checked_add(len).ok_or(Error::Plist("object out of bounds"))?;
+        self.bytes.get(start..end).ok_or(Error::Plist("object out of bounds"))
+    }
+
+    /// Returns the count of an object with the given marker at the offset, and where its contents
+    /// start.  A count of 15 or more follows the marker as an integer object.
+    fn count(&self, offset: usize, info: u8) -> Result<(usize, usize), Error> {
+        if info != 0xF {
+            return Ok((usize::from(info), offset + 1));
+        }
+        let marker = self.slice(offset + 1, 1)?[0];
+        if marker >> 4 != 0x1 {
+            return Err(Error::Plist("bad count"));
+        }
+        let len = 1 << (marker & 0xF);
+        let count = uint(self.slice(offset + 2, len.min(8))?);
+        let count = usize::try_from(count).map_err(|_| Error::Plist("bad count"))?;
+        Ok((count, offset + 2 + len))
+    }
+
+    fn refs(&self, start: usize, count: usize) -> Result<Vec<u64>, Error> {
+        let len = count.checked_mul(self.ref_size).ok_or(Error::Plist("object out of bounds"))?;
+        Ok(self.slice(start, len)?.chunks(self.ref_size).map(uint).collect())
+    }
+
+    fn value(&self, index: u64, depth: usize) -> Result<Value, Error> {
+        if depth > MAX_DEPTH {
+            return Err(Error::Plist("nested too deeply"));
+        }
+        let offset = usize::try_from(index)
+            .ok()
+            .and_then(|index| self.offsets.get(index))
+            .copied()
+            .ok_or(Error::Plist("bad object reference"))?;
+        let marker = self.slice(offset, 1)?[0];
+        let info = marker & 0xF;
+        let ret = match marker >> 4 {
+            0x0 | 0x1 | 0x2 | 0x4 | 0x8 => Value::Other,
+            0x3 => {
+                let bytes = self.slice(offset + 1, 8)?;
+                Value::Date(f64::from_be_bytes(bytes.try_into().expect("eight bytes")))
+            }
+            0x5 => {
+                let (count, start) = self.count(offset, info)?;
+                let bytes = self.slice(start, count)?;
+                Value::String(bytes.iter().copied().map(char::from).collect())
+            }
+            0x6 => {
+                let (count, start) = self.count(offset, info)?;
+                let len = count.checked_mul(2).ok_or(Error::Plist("object out of bounds"))?;
+                let units: Vec<u16> = self
+                    .slice(start, len)?
+                    .chunks(2)
+                    .map(|pair| u16::from_be_bytes([pair[0], pair[1]]))
+                    .collect();
+                Value::String(String::from_utf16(&units).map_err(|_| Error::Plist("bad string"))?)
+            }
+            0xA => {
+                let (count, start) = self.count(offset, info)?;
+                let refs = self.refs(start, count)?;
+                let values = refs.into_iter().map(|index| self.value(index, depth + 1));
+                Value::Array(values.collect::<Result<_, _>>()?)
+            }
+            0xD => {
+                let (count, start) = self.count(offset, info)?;
+                let refs = self.refs(start, count.saturating_mul(2))?;
+                let (keys, values) = refs.split_at(count);
+                let mut dict = BTreeMap::new();
+                for (&key, &value) in keys.iter().zip(values) {
+                    let Value::String(key) = self.value(key, depth + 1)? else {
+                        return Err(Error::Plist("dictionary key is not a string"));
+                    };
+                    dict.insert(key, self.value(value, depth + 1)?);
+                }
+                Value::Dict(dict)
+            }
+            _ => return Err(Error::Plist("unknown object type")),
+        };
+        Ok(ret)
+    }
+}
+
+/// Parses Safari's binary `Bookmarks.plist` into a [`Collection`].
+///
+/// Each bookmark's title becomes its name and the titles of the folders enclosing it its labels.
+/// The bookmarks bar, the bookmarks menu and the Reading List are not labels.  Items in the Reading
+/// List are marked to read, and created when they were added; Safari records no such time for
+/// other bookmarks, which are created at the default time.  History and other proxies are skipped.
+pub fn parse(input: &[u8]) -> Result<Collection, Error> {
+    let top = Reader::new(input)?.top()?;
+    let mut ret = Collection::new();
+    add(&mut ret, &top, &mut Vec::new(), false)?;
+    Ok(ret)
+}
+
+/// Adds the bookmarks at and below the value, within the given folders.
+fn add(
+    collection: &mut Collection,
+    value: &Value,
+    folders: &mut Vec<Label>,
+    reading_list: bool,
+) -> Result<(), Error> {
+    match value.get("WebBookmarkType").and_then(Value::as_str) {
+        Some(TYPE_LIST) => {
+            let title = value.get("Title").and_then(Value::as_str).map(str::trim);
+            let folder = match title {
+                None | Some("" | BOOKMARKS_BAR | BOOKMARKS_MENU | READING_LIST) => None,
+                Some(title) => Some(Label::new(title.to_string())),
+            };
+            let reading_list = reading_list || title == Some(READING_LIST);
+            let pushed = folder.is_some();
+            folders.extend(folder);
+            if let Some(Value::Array(children)) = value.get("Children") {
+                for child in children {
+                    add(collection, child, folders, reading_list)?;
+                }
+            }
+            if pushed {
+                folders.pop();
+            }
+        }
+        Some(TYPE_LEAF) => {
+            let Some(url) = value.get("URLString").and_then(Value::as_str) else {
+                return Ok(());
+            };
+            let url = Url::parse(url).map_err(|err| Error::ParseUrl(err, url.to_string()))?;
+            let title = value.get("URIDictionary").and_then(|dict| dict.get("title"));
+            let name =
+                title.and_then(Value::as_str).map(str::trim).filter(|title| !title.is_empty());
+            let name = name.map(|title| Name::new(title.to_string()));
+            let added = value.get("ReadingList").and_then(|dict| dict.get("DateAdded"));
+            let created_at = match added {
+                Some(Value::Date(date)) => {
+                    let seconds = (date + EPOCH_OFFSET).floor() as i64;
+                    Time::new(OffsetDateTime::from_unix_timestamp(seconds)?)
+                }
+                _ => Time::default(),
+            };
+            let labels: BTreeSet<Label> = folders.iter().cloned().collect();
+            let mut entity = Entity::new(url, created_at, name, labels);
+            entity.set_toread(reading_list);
+            collection.upsert(entity);
+        }
+        _ => {}
+    }
+    Ok(())
+}
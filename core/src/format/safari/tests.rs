@@ -0,0 +1,72 @@
+use time::macros::datetime;
+
+use super::*;
+
+/// A `Bookmarks.plist` as written by Python's `plistlib`, with the built-in lists, a proxy, a few
+/// bookmarks and an item in the Reading List.
+const TEST_SAMPLE: &[u8] =
+    b"bplist00\xd4\x01\x02\x03\x04\x05\x1d5\x1aXChildrenUTitle_\x10\x16WebBookmarkFileVersion_\x10\
+      \x0fWebBookmarkType\xa4\x06\x0a%(\xd3\x02\x07\x04\x08\x08\x09_\x10\x15WebBookmarkIdentifierW\
+      History_\x10\x14WebBookmarkTypeProxy\xd3\x01\x02\x04\x0b$\x1a\xa2\x0c \xd3\x01\x02\x04\x0d\
+      \x1f\x1a\xa2\x0e\x1b\xd3\x01\x02\x04\x0f\x16\x1a\xa1\x10\xd4\x11\x12\x04\x13\x14\x17\x18\x19\
+      ]URIDictionaryYURLString_\x10\x0fWebBookmarkUUID\xd1\x15\x16UtitleTRust_\x10\x1ahttps://www.\
+      rust-lang.org/_\x10\x13WebBookmarkTypeLeaf_\x10$00000000-0000-0000-0000-000000000000_\x10\
+      \x13WebBookmarkTypeList\xd4\x11\x12\x04\x13\x1c\x1e\x18\x19\xd1\x15\x1dP_\x10\x1chttps://use\
+      rs.rust-lang.org/[Programming\xd4\x11\x12\x04\x13!#\x18\x19\xd1\x15\x22WExample_\x10\x14http\
+      s://example.com/\x5cBookmarksBar\xd3\x01\x02\x04&'\x1a\xa0]BookmarksMenu\xd3\x01\x02\x04)4\
+      \x1a\xa1*\xd5+\x11\x12\x04\x13,13\x18\x19[ReadingList\xd2-./0YDateAdded[PreviewText3A\xc5\
+      \x82c\x8c\x00\x00\x00]The Rust blog\xd1\x152o\x10\x10\x00R\x00u\x00s\x00t\x00 \x00B\x00l\x00\
+      o\x00g\x00  \x14\x00 \x00n\x00e\x00w\x00s_\x10\x1bhttps://blog.rust-lang.org/_\x10\x15com.ap\
+      ple.ReadingList\x10\x01\x00\x08\x00\x11\x00\x1a\x00 \x009\x00K\x00P\x00W\x00o\x00w\x00\x8e\
+      \x00\x95\x00\x98\x00\x9f\x00\xa2\x00\xa9\x00\xab\x00\xb4\x00\xc2\x00\xcc\x00\xde\x00\xe1\x00\
+      \xe7\x00\xec\x01\x09\x01\x1f\x01F\x01\x5c\x01e\x01h\x01i\x01\x88\x01\x94\x01\x9d\x01\xa0\x01\
+      \xa8\x01\xbf\x01\xcc\x01\xd3\x01\xd4\x01\xe2\x01\xe9\x01\xeb\x01\xf6\x02\x02\x02\x07\x02\x11\
+      \x02\x1d\x02&\x024\x027\x02Z\x02x\x02\x90\x00\x00\x00\x00\x00\x00\x02\x01\x00\x00\x00\x00\
+      \x00\x00\x006\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x00\x02\x92";
+
+fn labels(entity: &Entity) -> Vec<&str> {
+    entity.labels().iter().map(Label::as_str).collect()
+}
+
+fn names(entity: &Entity) -> Vec<&str> {
+    entity.names().iter().map(Name::as_str).collect()
+}
+
+#[test]
+fn test_sample() {
+    let collection = parse(TEST_SAMPLE).unwrap();
+    assert_eq!(collection.len(), 4);
+
+    let get = |url: &str| collection.entity(collection.id(&Url::parse(url).unwrap()).unwrap());
+
+    let entity = get("https://www.rust-lang.org/");
+    assert_eq!(names(entity), vec!["Rust"]);
+    assert_eq!(labels(entity), vec!["Programming", "Rust"]);
+    assert_eq!(entity.created_at(), &Time::default());
+    assert!(!entity.toread());
+
+    let entity = get("https://users.rust-lang.org/");
+    assert!(entity.names().is_empty());
+    assert_eq!(labels(entity), vec!["Programming"]);
+
+    let entity = get("https://example.com/");
+    assert_eq!(names(entity), vec!["Example"]);
+    assert!(entity.labels().is_empty());
+
+    // Non-ASCII strings are UTF-16
+    let entity = get("https://blog.rust-lang.org/");
+    assert_eq!(names(entity), vec!["Rust Blog \u{2014} news"]);
+    assert!(entity.labels().is_empty());
+    assert!(entity.toread());
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 9:30 UTC)));
+}
+
+#[test]
+fn test_invalid() {
+    assert!(matches!(parse(b"<?xml version=\"1.0\"?>"), Err(Error::Plist(_))));
+    // The offset table runs past the end
+    let mut truncated = TEST_SAMPLE.to_vec();
+    let len = truncated.len();
+    truncated[len - 1] = 0xff;
+    assert!(matches!(parse(&truncated), Err(Error::Plist(_))));
+}
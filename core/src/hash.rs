@@ -0,0 +1,46 @@
+//! Hashes that are the same on every platform and in every release, for values that are saved.
+//!
+//! The hashers in the standard library may change between releases, and the data a [`Hash`]
+//! implementation feeds a hasher depends on the platform, so neither is fit for hashes that are
+//! written down and compared later.  [`Fnv1a`] takes bytes rather than [`Hash`] values for that
+//! reason.
+//!
+//! [`Hash`]: std::hash::Hash
+
+#[cfg(test)]
+mod tests;
+
+/// The 64-bit FNV-1a hash.
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub struct Fnv1a(u64);
+
+impl Fnv1a {
+    const OFFSET_BASIS: u64 = 0xcbf2_9ce4_8422_2325;
+    const PRIME: u64 = 0x0000_0100_0000_01b3;
+
+    pub const fn new() -> Fnv1a {
+        Fnv1a(Fnv1a::OFFSET_BASIS)
+    }
+
+    pub fn write(&mut self, bytes: &[u8]) -> &mut Fnv1a {
+        self.0 = bytes
+            .iter()
+            .fold(self.0, |hash, &byte| (hash ^ u64::from(byte)).wrapping_mul(Fnv1a::PRIME));
+        self
+    }
+
+    pub const fn finish(&self) -> u64 {
+        self.0
+    }
+}
+
+impl Default for Fnv1a {
+    fn default() -> Fnv1a {
+        Fnv1a::new()
+    }
+}
+
+/// Returns the FNV-1a hash of the bytes.
+pub fn fnv1a(bytes: &[u8]) -> u64 {
+    Fnv1a::new().write(bytes).finish()
+}
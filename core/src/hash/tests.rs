@@ -0,0 +1,15 @@
+use super::*;
+
+#[test]
+fn test_fnv1a() {
+    assert_eq!(fnv1a(b""), 0xcbf2_9ce4_8422_2325);
+    assert_eq!(fnv1a(b"a"), 0xaf63_dc4c_8601_ec8c);
+    assert_eq!(fnv1a(b"foobar"), 0x8594_4171_f739_67e8);
+}
+
+#[test]
+fn test_fnv1a_incremental() {
+    let mut hasher = Fnv1a::new();
+    hasher.write(b"foo").write(b"bar");
+    assert_eq!(hasher.finish(), fnv1a(b"foobar"));
+}
@@ -0,0 +1,91 @@
+//! Shared plumbing for modules that make HTTP requests.
+
+use std::{
+    sync::{
+        atomic::{AtomicUsize, Ordering},
+        Mutex,
+    },
+    thread,
+    time::Duration,
+};
+
+const USER_AGENT: &str = concat!("hbt/", env!("CARGO_PKG_VERSION"));
+
+pub(crate) fn agent(timeout: Duration, redirects: u32) -> ureq::Agent {
+    ureq::AgentBuilder::new().redirects(redirects).timeout(timeout).user_agent(USER_AGENT).build()
+}
+
+/// Applies `f` to every item on up to `concurrency` threads, returning the results in order.
+pub(crate) fn map_concurrent<T, R, F>(items: &[T], concurrency: usize, f: F) -> Vec<R>
+where
+    T: Sync,
+    R: Send,
+    F: Fn(&T) -> R + Sync,
+{
+    let next = AtomicUsize::new(0);
+    let results = Mutex::new(Vec::with_capacity(items.len()));
+    thread::scope(|scope| {
+        for _ in 0..concurrency.max(1).min(items.len()) {
+            scope.spawn(|| loop {
+                let index = next.fetch_add(1, Ordering::Relaxed);
+                let Some(item) = items.get(index) else {
+                    break;
+                };
+                let result = f(item);
+                results.lock().expect("poisoned").push((index, result));
+            });
+        }
+    });
+    let mut results = results.into_inner().expect("poisoned");
+    results.sort_by_key(|(index, _)| *index);
+    results.into_iter().map(|(_, result)| result).collect()
+}
+
+/// Serves responses from `route` on a local port, returning its base URL.
+///
+/// `route` is given the method and path of each request, and returns the status line and headers
+/// (each ending in `\r\n`) along with the body.
+#[cfg(test)]
+pub(crate) fn serve(route: fn(&str, &str) -> (&'static str, &'static str)) -> url::Url {
+    use std::{
+        io::{BufRead, BufReader, Read, Write},
+        net::TcpListener,
+    };
+
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    thread::spawn(move || {
+        for stream in listener.incoming() {
+            let mut stream = stream.unwrap();
+            let mut request = String::new();
+            let mut reader = BufReader::new(&stream);
+            reader.read_line(&mut request).unwrap();
+            let mut line = String::new();
+            let mut length = 0;
+            while reader.read_line(&mut line).unwrap() > 2 {
+                if let Some((name, value)) = line.split_once(':') {
+                    if name.eq_ignore_ascii_case("content-length") {
+                        length = value.trim().parse().unwrap();
+                    }
+                }
+                line.clear();
+            }
+            // Read any body, so that closing the connection does not reset it
+            let mut body = vec![0; length];
+            reader.read_exact(&mut body).unwrap();
+            let mut parts = request.split_whitespace();
+            let method = parts.next().unwrap();
+            let path = parts.next().unwrap().split('?').next().unwrap();
+            let (head, body) = route(method, path);
+            let body = if method == "HEAD" { "" } else { body };
+            let response = format!(
+                "HTTP/1.1 {}Content-Length: {}\r\nConnection: close\r\n\r\n{}",
+                head,
+                body.len(),
+                body
+            );
+            stream.write_all(response.as_bytes()).unwrap();
+        }
+    });
+    url::Url::parse(&format!("http://{}", addr)).unwrap()
+}
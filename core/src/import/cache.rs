@@ -0,0 +1,116 @@
+//! Remembering which files an import has already read, so that importing them again only reads
+//! those that changed.
+//!
+//! Each file is remembered by its modification time, length and a hash of its contents.  A file
+//! whose time and length are unchanged is not read at all; one whose time changed but whose
+//! contents did not is read, but not parsed again.  The hash is [FNV-1a](crate::hash), so a cache
+//! saved by one build is understood by the next.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    time::UNIX_EPOCH,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+
+use crate::hash;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+}
+
+/// What a file looked like when it was last imported.
+#[derive(Debug, Clone, Copy, PartialEq, Eq, Serialize, Deserialize)]
+struct Entry {
+    /// Nanoseconds since the Unix epoch.
+    modified: u128,
+    len: u64,
+    hash: u64,
+}
+
+fn stamp(path: &Path) -> io::Result<(u128, u64)> {
+    let metadata = fs::metadata(path)?;
+    let modified = metadata.modified()?.duration_since(UNIX_EPOCH).unwrap_or_default();
+    Ok((modified.as_nanos(), metadata.len()))
+}
+
+/// The files imported before, by path.
+#[derive(Debug, Default, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Cache {
+    files: BTreeMap<PathBuf, Entry>,
+}
+
+impl Cache {
+    pub fn new() -> Cache {
+        Cache::default()
+    }
+
+    /// Loads the cache saved at the path, which is empty if there is none.
+    pub fn load(path: &Path) -> Result<Cache, Error> {
+        match File::open(path) {
+            Ok(file) => Ok(serde_json::from_reader(BufReader::new(file))?),
+            Err(err) if err.kind() == ErrorKind::NotFound => Ok(Cache::new()),
+            Err(err) => Err(err.into()),
+        }
+    }
+
+    /// Saves the cache to the path, creating the directories it is in.
+    pub fn save(&self, path: &Path) -> Result<(), Error> {
+        if let Some(dir) = path.parent() {
+            fs::create_dir_all(dir)?;
+        }
+        let mut writer = BufWriter::new(File::create(path)?);
+        serde_json::to_writer(&mut writer, self)?;
+        writer.flush()?;
+        Ok(())
+    }
+
+    pub fn len(&self) -> usize {
+        self.files.len()
+    }
+
+    pub fn is_empty(&self) -> bool {
+        self.files.is_empty()
+    }
+
+    /// Returns whether the file changed since it was last [recorded](Cache::record).  A file that
+    /// was touched without changing is remembered by its new time, so it is not read next time.
+    pub fn changed(&mut self, path: &Path) -> io::Result<bool> {
+        let Some(entry) = self.files.get_mut(path) else {
+            return Ok(true);
+        };
+        let (modified, len) = stamp(path)?;
+        if (modified, len) == (entry.modified, entry.len) {
+            return Ok(false);
+        }
+        if len != entry.len || hash::fnv1a(&fs::read(path)?) != entry.hash {
+            return Ok(true);
+        }
+        entry.modified = modified;
+        Ok(false)
+    }
+
+    /// Remembers the file as it is now, as after importing it.
+    pub fn record(&mut self, path: &Path) -> io::Result<()> {
+        let (modified, len) = stamp(path)?;
+        let hash = hash::fnv1a(&fs::read(path)?);
+        self.files.insert(path.to_path_buf(), Entry { modified, len, hash });
+        Ok(())
+    }
+
+    /// Forgets the file, so that it is imported again.
+    pub fn forget(&mut self, path: &Path) -> bool {
+        self.files.remove(path).is_some()
+    }
+}
@@ -0,0 +1,76 @@
+use std::time::{Duration, SystemTime};
+
+use super::*;
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir =
+        std::env::temp_dir().join(format!("hbt-import-cache-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    dir
+}
+
+/// Writes the file with a modification time of its own, as filesystems may keep coarse times.
+fn write(path: &Path, contents: &str, seconds: u64) {
+    fs::write(path, contents).unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds);
+    File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+#[test]
+fn test_changed() {
+    let dir = temp_dir("changed");
+    let path = dir.join("links.md");
+    write(&path, "- [Foo](https://foo.com)\n", 0);
+
+    let mut cache = Cache::new();
+    assert!(cache.changed(&path).unwrap());
+    cache.record(&path).unwrap();
+    assert!(!cache.changed(&path).unwrap());
+
+    // Touched, but the same
+    write(&path, "- [Foo](https://foo.com)\n", 1);
+    assert!(!cache.changed(&path).unwrap());
+    assert_eq!(cache.files[&path].modified, Duration::from_secs(1_700_000_001).as_nanos());
+
+    // The same length, but different
+    write(&path, "- [Bar](https://bar.com)\n", 2);
+    assert!(cache.changed(&path).unwrap());
+    // Still changed until it is recorded
+    assert!(cache.changed(&path).unwrap());
+    cache.record(&path).unwrap();
+    assert!(!cache.changed(&path).unwrap());
+
+    assert!(cache.forget(&path));
+    assert!(cache.changed(&path).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_save_load() {
+    let dir = temp_dir("save");
+    let path = dir.join("links.md");
+    write(&path, "- [Foo](https://foo.com)\n", 0);
+    let cache_path = dir.join("cache").join("imports.json");
+    assert!(Cache::load(&cache_path).unwrap().is_empty());
+
+    let mut cache = Cache::new();
+    cache.record(&path).unwrap();
+    cache.save(&cache_path).unwrap();
+    let mut loaded = Cache::load(&cache_path).unwrap();
+    assert_eq!(loaded, cache);
+    assert!(!loaded.changed(&path).unwrap());
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_hash_is_stable() {
+    let dir = temp_dir("stable");
+    let path = dir.join("links.md");
+    write(&path, "foobar", 0);
+    let mut cache = Cache::new();
+    cache.record(&path).unwrap();
+    // A cache saved by an earlier build reads the same
+    assert_eq!(cache.files[&path].hash, 0x85944171f73967e8);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,64 @@
+use super::*;
+use crate::{
+    collection::{Label, Union},
+    markdown,
+};
+
+fn input(i: usize) -> String {
+    format!(
+        "# November 15, 2023\n\n## label{}\n\n- [Page {}](https://example.com/{})\n  - [Shared](https://example.com/shared)\n",
+        i % 3,
+        i,
+        i
+    )
+}
+
+#[test]
+fn test_import_all() {
+    let inputs: Vec<String> = (0..200).map(input).collect();
+    let mut collection = Collection::new();
+    let results: Vec<Imported> = import_all(
+        &mut collection,
+        &inputs,
+        |input| markdown::parse(input),
+        |input| Some(input.lines().nth(2).unwrap().to_string()),
+        &mut Union,
+    )
+    .into_iter()
+    .map(Result::unwrap)
+    .collect();
+    assert_eq!(results.len(), 200);
+    assert_eq!(results[0], Imported { entities: 2, added: 2, merged: 0 });
+    assert!(results[1..]
+        .iter()
+        .all(|result| *result == Imported { entities: 2, added: 1, merged: 1 }));
+    assert_eq!(collection.len(), 201);
+
+    // Merged in input order, whatever the scheduling
+    let mut serial = Collection::new();
+    for input in inputs.iter() {
+        let imported = markdown::parse(input).unwrap();
+        let source = Some(input.lines().nth(2).unwrap().to_string());
+        merge_into(&mut serial, &imported, source, &mut Union);
+    }
+    assert_eq!(collection, serial);
+    let shared = collection.id(&url::Url::parse("https://example.com/shared").unwrap()).unwrap();
+    assert_eq!(collection.incoming(shared).len(), 200);
+    // One page in three, and the page they share
+    assert_eq!(collection.by_label(&Label::from("label1")).count(), 68);
+}
+
+#[test]
+fn test_import_all_errors() {
+    let inputs = vec![Ok(input(0)), Err("bad"), Ok(input(1))];
+    let mut collection = Collection::new();
+    let results = import_all(
+        &mut collection,
+        &inputs,
+        |input| input.as_ref().map(|input| markdown::parse(input).unwrap()).map_err(|err| *err),
+        |_| None,
+        &mut Union,
+    );
+    assert_eq!(results[1], Err("bad"));
+    assert_eq!(collection.len(), 3);
+}
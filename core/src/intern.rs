@@ -0,0 +1,162 @@
+//! Interned strings.
+//!
+//! The same names and labels recur across the entities of a collection.  Interning stores each
+//! distinct string once, as long as a [`Symbol`] refers to it, and hands out symbols that are
+//! cheap to clone and to compare for equality.  The interner itself only holds weak references,
+//! and forgets strings no symbol refers to as it grows, so storage is reclaimed once a string is
+//! no longer used.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    cmp::Ordering,
+    collections::HashMap,
+    fmt,
+    hash::{Hash, Hasher},
+    sync::{Arc, OnceLock, RwLock, Weak},
+};
+
+use serde::{Deserialize, Serialize};
+
+use crate::hash;
+
+/// The strings interned so far, by hash.  Some may no longer be referred to.
+#[derive(Debug, Default)]
+struct Interner {
+    strings: HashMap<u64, Vec<Weak<str>>>,
+    len: usize,
+    /// The length at which to next forget strings no longer referred to.
+    sweep_at: usize,
+}
+
+impl Interner {
+    fn get(&self, hash: u64, string: &str) -> Option<Arc<str>> {
+        let strings = self.strings.get(&hash)?;
+        strings.iter().filter_map(Weak::upgrade).find(|interned| **interned == *string)
+    }
+
+    fn insert(&mut self, hash: u64, interned: &Arc<str>) {
+        if self.len >= self.sweep_at {
+            self.sweep();
+        }
+        self.strings.entry(hash).or_default().push(Arc::downgrade(interned));
+        self.len += 1;
+    }
+
+    /// Forgets the strings no longer referred to, so that their storage is freed.
+    fn sweep(&mut self) {
+        self.strings.retain(|_, strings| {
+            strings.retain(|string| string.strong_count() > 0);
+            !strings.is_empty()
+        });
+        self.len = self.strings.values().map(Vec::len).sum();
+        self.sweep_at = (self.len * 2).max(64);
+    }
+}
+
+fn interner() -> &'static RwLock<Interner> {
+    static INTERNER: OnceLock<RwLock<Interner>> = OnceLock::new();
+    INTERNER.get_or_init(Default::default)
+}
+
+/// An interned string.
+///
+/// Symbols of equal strings share storage, so equality compares addresses.  Hashing and ordering
+/// use the string itself, so symbols sort like strings and hash like `str`.
+#[derive(Clone)]
+pub struct Symbol(Arc<str>);
+
+impl Symbol {
+    /// Returns the symbol for `string`, storing it if no symbol refers to it.
+    pub fn new(string: &str) -> Symbol {
+        let hash = hash::fnv1a(string.as_bytes());
+        if let Some(interned) = interner().read().expect("lock").get(hash, string) {
+            return Symbol(interned);
+        }
+        let mut interner = interner().write().expect("lock");
+        // Another thread may have stored it since we looked
+        if let Some(interned) = interner.get(hash, string) {
+            return Symbol(interned);
+        }
+        let interned: Arc<str> = string.into();
+        interner.insert(hash, &interned);
+        Symbol(interned)
+    }
+
+    pub fn as_str(&self) -> &str {
+        &self.0
+    }
+}
+
+impl PartialEq for Symbol {
+    fn eq(&self, other: &Symbol) -> bool {
+        Arc::ptr_eq(&self.0, &other.0)
+    }
+}
+
+impl Eq for Symbol {}
+
+impl PartialOrd for Symbol {
+    fn partial_cmp(&self, other: &Symbol) -> Option<Ordering> {
+        Some(self.cmp(other))
+    }
+}
+
+impl Ord for Symbol {
+    fn cmp(&self, other: &Symbol) -> Ordering {
+        if self == other {
+            return Ordering::Equal;
+        }
+        self.0.cmp(&other.0)
+    }
+}
+
+impl Hash for Symbol {
+    fn hash<H: Hasher>(&self, state: &mut H) {
+        self.0.hash(state);
+    }
+}
+
+impl fmt::Debug for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl fmt::Display for Symbol {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        self.0.fmt(f)
+    }
+}
+
+impl From<&str> for Symbol {
+    fn from(string: &str) -> Symbol {
+        Symbol::new(string)
+    }
+}
+
+impl From<String> for Symbol {
+    fn from(string: String) -> Symbol {
+        Symbol::new(&string)
+    }
+}
+
+impl Serialize for Symbol {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.serialize_str(&self.0)
+    }
+}
+
+impl<'de> Deserialize<'de> for Symbol {
+    fn deserialize<D>(deserializer: D) -> Result<Symbol, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let string = <std::borrow::Cow<'de, str>>::deserialize(deserializer)?;
+        Ok(Symbol::new(&string))
+    }
+}
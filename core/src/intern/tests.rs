@@ -0,0 +1,57 @@
+use std::{collections::HashSet, sync::Arc};
+
+use super::*;
+
+#[test]
+fn test_symbol_shares_storage() {
+    let a = Symbol::new("rust");
+    let b = Symbol::from(String::from("rust"));
+    assert_eq!(a, b);
+    assert!(std::ptr::eq(a.as_str(), b.as_str()));
+    assert_ne!(a, Symbol::new("rusty"));
+}
+
+#[test]
+fn test_symbol_ord() {
+    let mut symbols = [Symbol::new("zebra"), Symbol::new("apple"), Symbol::new("mango")];
+    symbols.sort();
+    let strings: Vec<&str> = symbols.iter().map(Symbol::as_str).collect();
+    assert_eq!(strings, vec!["apple", "mango", "zebra"]);
+}
+
+#[test]
+fn test_symbol_hash() {
+    let set: HashSet<Symbol> = [Symbol::new("a"), Symbol::new("a"), Symbol::new("b")].into();
+    assert_eq!(set.len(), 2);
+}
+
+#[test]
+fn test_symbol_serde() {
+    let symbol = Symbol::new("interned");
+    let json = serde_json::to_string(&symbol).unwrap();
+    assert_eq!(json, "\"interned\"");
+    let parsed: Symbol = serde_json::from_str(&json).unwrap();
+    assert_eq!(parsed, symbol);
+}
+
+#[test]
+fn test_symbol_threads() {
+    let symbols: Vec<Symbol> = std::thread::scope(|scope| {
+        let handles: Vec<_> = (0..8).map(|_| scope.spawn(|| Symbol::new("contended"))).collect();
+        handles.into_iter().map(|handle| handle.join().unwrap()).collect()
+    });
+    assert!(symbols.iter().all(|symbol| *symbol == symbols[0]));
+}
+
+#[test]
+fn test_symbol_reclaimed() {
+    let symbol = Symbol::new("reclaimed");
+    let weak = Arc::downgrade(&symbol.0);
+    assert_eq!(Symbol::new("reclaimed"), symbol);
+    drop(symbol);
+    assert!(weak.upgrade().is_none());
+    // Once swept, the interner forgets the string
+    let mut interner = interner().write().expect("lock");
+    interner.sweep();
+    assert!(!interner.strings.contains_key(&hash::fnv1a(b"reclaimed")));
+}
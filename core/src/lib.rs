@@ -1,4 +1,44 @@
+#[cfg(any(feature = "rpc", feature = "serve"))]
+mod api;
+#[cfg(feature = "archive")]
+pub mod archive;
+#[cfg(feature = "check")]
+pub mod check;
 pub mod collection;
+pub mod crdt;
+pub mod dedupe;
+#[cfg(feature = "favicon")]
+pub mod favicon;
+#[cfg(feature = "fetch")]
+pub mod fetch;
+pub mod format;
+pub mod hash;
+#[cfg(any(
+    feature = "archive",
+    feature = "check",
+    feature = "favicon",
+    feature = "fetch",
+    feature = "linkding",
+    feature = "wallabag",
+    feature = "wayback"
+))]
+mod http;
+pub mod import;
+pub mod intern;
 pub mod markdown;
+#[cfg(feature = "native-messaging")]
+pub mod native_messaging;
 #[cfg(feature = "pinboard")]
 pub mod pinboard;
+pub mod query;
+#[cfg(feature = "rpc")]
+pub mod rpc;
+#[cfg(feature = "serve")]
+pub mod serve;
+pub mod store;
+#[cfg(any(feature = "linkding", feature = "wallabag"))]
+pub mod sync;
+pub mod url_norm;
+pub mod watch;
+#[cfg(feature = "wayback")]
+pub mod wayback;
@@ -1,12 +1,32 @@
 #[cfg(test)]
 mod tests;
+mod write_back;
 
-use pulldown_cmark::{Event, HeadingLevel, LinkType, Parser, Tag, TagEnd};
+use std::{
+    cmp::Reverse,
+    collections::{BTreeMap, HashMap, HashSet},
+    fs,
+    io::{self, Write},
+    iter,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{
+    Event, HeadingLevel, LinkType, Options as ParserOptions, Parser, Tag, TagEnd,
+};
+use serde::{Deserialize, Serialize};
 use thiserror::Error;
-use time::{macros::format_description, Date, OffsetDateTime};
+use time::{
+    format_description::BorrowedFormatItem, macros::format_description, Date, OffsetDateTime,
+    UtcOffset,
+};
 use url::Url;
 
-use crate::collection::{Collection, Entity, Id, Label, Name};
+pub use self::write_back::write_back;
+use crate::{
+    collection::{Collection, Entity, Id, Label, Location, Name, Union},
+    import,
+};
 
 #[derive(Debug, Error)]
 pub enum Error {
@@ -18,6 +38,55 @@ pub enum Error {
     ParseUrl(#[source] url::ParseError, String),
     #[error("date parsing error: {0}, {1}")]
     ParseDate(#[source] time::error::Parse, String),
+    #[error("frontmatter parsing error: {0}")]
+    ParseFrontmatter(#[from] serde_norway::Error),
+    #[error("date formatting error: {0}")]
+    FormatDate(#[from] time::error::Format),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("{0}: {1}")]
+    File(PathBuf, #[source] Box<Error>),
+}
+
+const DATE_FORMAT: &[BorrowedFormatItem<'_>] =
+    format_description!("[month repr:long] [day padding:none], [year]");
+
+const FRONTMATTER_DATE_FORMAT: &[BorrowedFormatItem<'_>] =
+    format_description!("[year]-[month]-[day]");
+
+/// File-wide metadata given in a YAML block at the top of a markdown file.
+///
+/// ```markdown
+/// ---
+/// name: Reading list
+/// labels: [reading]
+/// date: 2023-11-15
+/// ---
+/// ```
+///
+/// `name` names the resulting [`Collection`], `labels` are added to every entity in the file, and
+/// `date` overrides the date of every link in the file, including those under a dated heading.
+#[derive(Debug, Default, Deserialize, Serialize)]
+#[serde(default)]
+struct Frontmatter {
+    #[serde(skip_serializing_if = "Option::is_none")]
+    name: Option<String>,
+    #[serde(alias = "tags", skip_serializing_if = "Vec::is_empty")]
+    labels: Vec<String>,
+    #[serde(skip_serializing_if = "Option::is_none")]
+    date: Option<String>,
+}
+
+impl Frontmatter {
+    fn parse_date(&self) -> Result<Option<Date>, Error> {
+        let Some(text) = &self.date else {
+            return Ok(None);
+        };
+        let parsed = Date::parse(text, FRONTMATTER_DATE_FORMAT)
+            .or_else(|_| Date::parse(text, DATE_FORMAT))
+            .map_err(|err| Error::ParseDate(err, text.to_string()))?;
+        Ok(Some(parsed))
+    }
 }
 
 struct HeadingLevelExt(HeadingLevel);
@@ -42,99 +111,178 @@ impl From<HeadingLevelExt> for usize {
 }
 
 pub fn parse(input: &str) -> Result<Collection, Error> {
-    let parser = Parser::new(input);
+    parse_impl(input, None)
+}
+
+/// Like [`parse`], recording on each entity the line of the given file it was listed on.
+pub fn parse_from(input: &str, path: &Path) -> Result<Collection, Error> {
+    parse_impl(input, Some(path))
+}
+
+/// Parses every Markdown file in the directory and those below it, in path order, merging the
+/// result as with [`import::merge_into`].  Each entity records the files and lines it was listed
+/// on, as with [`parse_from`], and any update the name of the file that made it.
+pub fn parse_dir(dir: &Path) -> Result<Collection, Error> {
+    let mut ret = Collection::new();
+    for path in files(dir)? {
+        let input = fs::read_to_string(&path)?;
+        let parsed =
+            parse_from(&input, &path).map_err(|err| Error::File(path.clone(), err.into()))?;
+        import::merge_into(&mut ret, &parsed, Some(path.display().to_string()), &mut Union);
+    }
+    Ok(ret)
+}
+
+/// Returns whether the path names a Markdown file.
+pub fn is_markdown(path: &Path) -> bool {
+    path.extension().is_some_and(|ext| ext == "md" || ext == "markdown")
+}
+
+/// Returns the Markdown files in the directory and those below it, in path order.
+pub fn files(dir: &Path) -> io::Result<Vec<PathBuf>> {
+    let mut ret = Vec::new();
+    find_files(dir, &mut ret)?;
+    ret.sort();
+    Ok(ret)
+}
+
+fn find_files(dir: &Path, files: &mut Vec<PathBuf>) -> io::Result<()> {
+    for entry in fs::read_dir(dir)? {
+        let entry = entry?;
+        let path = entry.path();
+        if entry.file_type()?.is_dir() {
+            find_files(&path, files)?;
+        } else if is_markdown(&path) {
+            files.push(path);
+        }
+    }
+    Ok(())
+}
 
-    let date_format = format_description!("[month repr:long] [day padding:none], [year]");
+/// Returns the line, counted from 1, of each offset into the input.
+struct Lines(Vec<usize>);
+
+impl Lines {
+    fn new(input: &str) -> Lines {
+        Lines(input.match_indices('\n').map(|(i, _)| i).collect())
+    }
+
+    fn line(&self, offset: usize) -> usize {
+        self.0.partition_point(|&newline| newline < offset) + 1
+    }
+}
+
+fn parse_impl(input: &str, path: Option<&Path>) -> Result<Collection, Error> {
+    let parser = Parser::new_ext(input, ParserOptions::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    let lines = path.map(|_| Lines::new(input));
 
     let mut ret = Collection::new();
 
     let mut name: Option<Name> = None;
     let mut date: Option<Date> = None;
     let mut url: Option<Url> = None;
+    let mut link_text: Option<String> = None;
+    let mut heading_text: Option<String> = None;
+    let mut metadata_text: Option<String> = None;
     let mut labels: Vec<Label> = Vec::new();
 
-    let mut current_tag: Option<Tag> = None;
-    let mut current_heading_level: HeadingLevel = HeadingLevel::H1;
+    let mut date_override: Option<Date> = None;
+    let mut default_labels: Vec<Label> = Vec::new();
+
     let mut maybe_parent: Option<Id> = None;
     let mut parents: Vec<Id> = Vec::new();
+    let mut link_start = 0;
 
-    for event in parser {
+    for (event, range) in parser.into_offset_iter() {
         match event {
             // Start
-            Event::Start(tag @ Tag::Heading { level: HeadingLevel::H1, .. }) => {
+            Event::Start(Tag::Heading { level: HeadingLevel::H1, .. }) => {
                 name = None;
                 date = None;
                 url = None;
                 labels.clear();
-                current_tag = Some(tag);
-                current_heading_level = HeadingLevel::H1;
+                heading_text = Some(String::new());
                 maybe_parent = None;
                 parents.clear();
             }
-            Event::Start(tag @ Tag::Heading { level, .. }) => {
-                current_tag = Some(tag);
-                current_heading_level = level;
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading_text = Some(String::new());
                 let level = usize::from(HeadingLevelExt::from(level));
                 labels.truncate(level - 2);
             }
-            Event::Start(tag @ Tag::List(_)) => {
-                current_tag = Some(tag);
+            Event::Start(Tag::MetadataBlock(_)) => {
+                metadata_text = Some(String::new());
+            }
+            Event::Start(Tag::List(_)) => {
                 if let Some(last_id) = maybe_parent {
                     parents.push(last_id);
                 }
             }
-            Event::Start(
-                ref tag @ Tag::Link { link_type: LinkType::Inline, ref dest_url, ref title, .. },
-            ) => {
-                current_tag = Some(tag.to_owned());
+            Event::Start(Tag::Link {
+                link_type:
+                    LinkType::Inline | LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+                dest_url,
+                ..
+            }) => {
+                link_text = Some(String::new());
+                link_start = range.start;
                 let parsed =
-                    Url::parse(dest_url).map_err(|e| Error::ParseUrl(e, dest_url.to_string()))?;
+                    Url::parse(&dest_url).map_err(|e| Error::ParseUrl(e, dest_url.to_string()))?;
                 url = Some(parsed);
-                assert!(title.is_empty());
-            }
-            Event::Start(
-                ref tag @ Tag::Link {
-                    link_type: LinkType::Autolink, ref dest_url, ref title, ..
-                },
-            ) => {
-                current_tag = Some(tag.to_owned());
+            }
+            Event::Start(Tag::Link { link_type: LinkType::Autolink, dest_url, .. }) => {
                 name = None;
+                link_start = range.start;
                 let parsed =
-                    Url::parse(dest_url).map_err(|e| Error::ParseUrl(e, dest_url.to_string()))?;
+                    Url::parse(&dest_url).map_err(|e| Error::ParseUrl(e, dest_url.to_string()))?;
                 url = Some(parsed);
-                assert!(title.is_empty());
-            }
-            Event::Start(tag) => {
-                current_tag = Some(tag);
             }
             // Text
-            Event::Text(text) => match (&current_tag, current_heading_level) {
-                (Some(Tag::Heading { .. }), HeadingLevel::H1) => {
-                    let parsed = Date::parse(text.as_ref(), date_format)
-                        .map_err(|err| Error::ParseDate(err, text.to_string()))?;
-                    date = Some(parsed);
-                }
-                (Some(Tag::Heading { .. }), _) => {
-                    let label = Label::new(text.to_string());
-                    labels.push(label);
-                }
-                (Some(Tag::Link { link_type: LinkType::Inline, .. }), _) => {
-                    name = Some(Name::new(text.to_string()));
+            Event::Text(text) | Event::Code(text) => {
+                if let Some(link_text) = link_text.as_mut() {
+                    link_text.push_str(&text);
+                } else if let Some(heading_text) = heading_text.as_mut() {
+                    heading_text.push_str(&text);
+                } else if let Some(metadata_text) = metadata_text.as_mut() {
+                    metadata_text.push_str(&text);
                 }
-                _ => {}
-            },
+            }
             // End
+            Event::End(TagEnd::Heading(level)) => {
+                let text = heading_text.take().unwrap_or_default();
+                if level == HeadingLevel::H1 {
+                    let parsed = Date::parse(&text, DATE_FORMAT)
+                        .map_err(|err| Error::ParseDate(err, text))?;
+                    date = Some(parsed);
+                } else if !text.is_empty() {
+                    labels.push(Label::new(text));
+                }
+            }
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                let text = metadata_text.take().unwrap_or_default();
+                let frontmatter: Frontmatter =
+                    serde_norway::from_str::<Option<Frontmatter>>(&text)?.unwrap_or_default();
+                date_override = frontmatter.parse_date()?;
+                default_labels = frontmatter.labels.into_iter().map(Label::new).collect();
+                ret.set_name(frontmatter.name);
+            }
             Event::End(TagEnd::List(_)) => {
                 let _ = parents.pop();
                 maybe_parent = None;
             }
             Event::End(TagEnd::Link) => {
+                if let Some(text) = link_text.take() {
+                    name = if text.is_empty() { None } else { Some(Name::new(text)) };
+                }
                 let url = url.take().ok_or(Error::MissingUrl)?;
-                let date = date.ok_or(Error::MissingDate)?;
+                let date = date_override.or(date).ok_or(Error::MissingDate)?;
                 let datetime = OffsetDateTime::new_utc(date, time::Time::MIDNIGHT);
                 let name = name.take();
-                let labels = labels.iter().cloned().collect();
-                let entity = Entity::new(url, datetime.into(), name, labels);
+                let labels = labels.iter().chain(&default_labels).cloned().collect();
+                let mut entity = Entity::new(url, datetime.into(), name, labels);
+                if let (Some(path), Some(lines)) = (path, &lines) {
+                    entity.add_source(Location::new(path, lines.line(link_start)));
+                }
                 let id = ret.upsert(entity);
                 if let Some(parent) = parents.last() {
                     ret.add_edges(*parent, id);
@@ -147,3 +295,198 @@ pub fn parse(input: &str) -> Result<Collection, Error> {
 
     Ok(ret)
 }
+
+/// How [`to_writer`] arranges entities.
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub enum GroupBy {
+    /// Under a heading for each date, with an entity's labels as nested subheadings.
+    ///
+    /// This is the layout that [`parse`] reads.
+    #[default]
+    Date,
+    /// Under a heading for each label, listing an entity once for each of its labels.
+    ///
+    /// This layout is meant for reading and cannot be parsed back.
+    Label,
+}
+
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    pub group_by: GroupBy,
+}
+
+/// The number of subheading levels available for labels, from `##` to `######`.
+const MAX_LABELS: usize = 5;
+
+/// Writes a [`Collection`] as markdown.
+///
+/// With [`GroupBy::Date`], parsing the output yields the same URLs, names, labels and dates, with
+/// two caveats: times are truncated to their date, and only edges between entities listed in the
+/// same section survive, as nested lists.  An entity is listed once under each of its dates, and
+/// more often if it has several names or more labels than fit in a heading path.  Labels shared by
+/// more entities are written as higher-level headings.  The collection's name, if any, is written as
+/// YAML frontmatter.
+pub fn to_writer(
+    collection: &Collection,
+    options: &Options,
+    mut writer: impl Write,
+) -> Result<(), Error> {
+    let blocks = match options.group_by {
+        GroupBy::Date => by_date(collection)?,
+        GroupBy::Label => by_label(collection)?,
+    };
+    writer.write_all(blocks.join("\n").as_bytes())?;
+    Ok(())
+}
+
+struct Item<'a> {
+    id: Id,
+    name: Option<&'a Name>,
+    home: bool,
+}
+
+type Section<'a> = (Date, Vec<&'a Label>);
+
+fn by_date(collection: &Collection) -> Result<Vec<String>, Error> {
+    let mut sections: BTreeMap<Section, Vec<Item>> = BTreeMap::new();
+    let mut homes: HashMap<Id, Section> = HashMap::new();
+
+    for (id, entity) in collection.iter() {
+        let dates: Vec<Date> = iter::once(entity.created_at())
+            .chain(entity.updated_at())
+            .map(|time| OffsetDateTime::from(*time).to_offset(UtcOffset::UTC).date())
+            .collect();
+        let names: Vec<&Name> = entity.names().iter().collect();
+        let mut labels: Vec<&Label> = entity.labels().iter().collect();
+        labels.sort_by_key(|label| Reverse(collection.by_label(label).count()));
+        let chunks: Vec<&[&Label]> =
+            if labels.is_empty() { vec![&[]] } else { labels.chunks(MAX_LABELS).collect() };
+        let count = dates.len().max(names.len()).max(chunks.len());
+        for i in 0..count {
+            let date = dates.get(i).unwrap_or(&dates[0]);
+            let section = (*date, chunks[i % chunks.len()].to_vec());
+            let name = names.get(i).or(names.first()).copied();
+            let home = i == 0;
+            if home {
+                homes.insert(id, section.clone());
+            }
+            sections.entry(section).or_default().push(Item { id, name, home });
+        }
+    }
+
+    let mut ret = Vec::new();
+    if let Some(name) = collection.name() {
+        let frontmatter = Frontmatter { name: Some(name.to_string()), ..Default::default() };
+        ret.push(format!("---\n{}---\n", serde_norway::to_string(&frontmatter)?));
+    }
+
+    let mut current: Option<&Section> = None;
+    let mut written = HashSet::new();
+
+    for (section, items) in sections.iter() {
+        let (date, labels) = section;
+        let common = match current {
+            Some((current_date, current_labels)) if current_date == date => {
+                labels.iter().zip(current_labels).take_while(|(a, b)| a == b).count()
+            }
+            _ => {
+                ret.push(format!("# {}\n", date.format(DATE_FORMAT)?));
+                0
+            }
+        };
+        for (i, label) in labels.iter().enumerate().skip(common) {
+            ret.push(format!("{} {}\n", "#".repeat(i + 2), escape(label.as_str())));
+        }
+        let mut list = String::new();
+        for item in items {
+            if item.home && !written.insert(item.id) {
+                continue;
+            }
+            write_item(&mut list, collection, item, 0);
+            if item.home {
+                write_children(&mut list, collection, &homes, section, &mut written, item.id, 1);
+            }
+        }
+        ret.push(list);
+        current = Some(section);
+    }
+
+    Ok(ret)
+}
+
+fn write_children(
+    list: &mut String,
+    collection: &Collection,
+    homes: &HashMap<Id, Section>,
+    section: &Section,
+    written: &mut HashSet<Id>,
+    parent: Id,
+    depth: usize,
+) {
+    for to in collection.edges(parent) {
+        if homes.get(to) != Some(section) || !written.insert(*to) {
+            continue;
+        }
+        let name = collection.entity(*to).names().first();
+        let item = Item { id: *to, name, home: true };
+        write_item(list, collection, &item, depth);
+        write_children(list, collection, homes, section, written, *to, depth + 1);
+    }
+}
+
+fn write_item(list: &mut String, collection: &Collection, item: &Item, depth: usize) {
+    let url = collection.entity(item.id).url();
+    list.push_str(&"  ".repeat(depth));
+    list.push_str("- ");
+    list.push_str(&link(url, item.name));
+    list.push('\n');
+}
+
+fn by_label(collection: &Collection) -> Result<Vec<String>, Error> {
+    let mut sections: BTreeMap<Option<&Label>, Vec<&Entity>> = BTreeMap::new();
+    for entity in collection.entities() {
+        if entity.labels().is_empty() {
+            sections.entry(None).or_default().push(entity);
+        }
+        for label in entity.labels() {
+            sections.entry(Some(label)).or_default().push(entity);
+        }
+    }
+
+    let mut ret = Vec::new();
+    for (label, entities) in sections {
+        if let Some(label) = label {
+            ret.push(format!("# {}\n", escape(label.as_str())));
+        }
+        let mut list = String::new();
+        for entity in entities {
+            let date = OffsetDateTime::from(*entity.created_at()).date();
+            let link = link(entity.url(), entity.names().first());
+            list.push_str(&format!("- {} ({})\n", link, date.format(DATE_FORMAT)?));
+        }
+        ret.push(list);
+    }
+
+    Ok(ret)
+}
+
+fn link(url: &Url, name: Option<&Name>) -> String {
+    match name {
+        Some(name) if url.as_str().contains(['(', ')']) => {
+            format!("[{}](<{}>)", escape(name.as_str()), url)
+        }
+        Some(name) => format!("[{}]({})", escape(name.as_str()), url),
+        None => format!("<{}>", url),
+    }
+}
+
+fn escape(text: &str) -> String {
+    let mut ret = String::with_capacity(text.len());
+    for c in text.chars() {
+        if matches!(c, '\\' | '`' | '*' | '_' | '[' | ']' | '<' | '>' | '#' | '&' | '!') {
+            ret.push('\\');
+        }
+        ret.push(c);
+    }
+    ret
+}
@@ -1,4 +1,4 @@
-use std::vec;
+use std::{collections::BTreeSet, vec};
 
 use time::macros::datetime;
 
@@ -692,7 +692,7 @@ fn test_descending_dates() {
     let actual = collection.entity(id);
     assert_eq!(&expected, actual);
     assert_eq!(actual.created_at(), &Time::new(datetime!(2023-12-5 0:00 UTC)));
-    assert_eq!(actual.updated_at(), &[Time::new(datetime!(2023-12-6 0:00 UTC))]);
+    assert_eq!(actual.last_updated_at(), Some(&Time::new(datetime!(2023-12-6 0:00 UTC))));
 }
 
 const TEST_MIXED_DATES: &str = "\
@@ -744,8 +744,8 @@ fn test_mixed_dates() {
     assert_eq!(&expected, actual);
     assert_eq!(actual.created_at(), &Time::new(datetime!(2023-12-5 0:00 UTC)));
     assert_eq!(
-        actual.updated_at(),
-        &[Time::new(datetime!(2023-12-6 0:00 UTC)), Time::new(datetime!(2023-12-7 0:00 UTC))]
+        actual.updated_at().copied().collect::<Vec<_>>(),
+        [Time::new(datetime!(2023-12-6 0:00 UTC)), Time::new(datetime!(2023-12-7 0:00 UTC))]
     );
 }
 
@@ -892,3 +892,359 @@ fn test_nested() {
     assert_eq!(quux_edges, vec![hello_id]);
     assert_eq!(baz_edges, vec![foo_id]);
 }
+
+const TEST_LINK_TITLE: &str = "\
+# November 15, 2023
+
+- [Foo](https://foo.com \"The Foo Site\")
+";
+
+#[test]
+fn test_link_title() {
+    let collection = parse(TEST_LINK_TITLE).unwrap();
+
+    assert_eq!(collection.len(), 1);
+
+    let expected = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::from("Foo")),
+        Default::default(),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+}
+
+const TEST_REFERENCE_LINKS: &str = "\
+# November 15, 2023
+
+- [Foo][foo]
+- [Bar][]
+- [Baz]
+
+[foo]: https://foo.com
+[bar]: https://bar.com
+[baz]: https://baz.com
+";
+
+#[test]
+fn test_reference_links() {
+    let collection = parse(TEST_REFERENCE_LINKS).unwrap();
+
+    assert_eq!(collection.len(), 3);
+
+    for (url, name) in
+        [("https://foo.com", "Foo"), ("https://bar.com", "Bar"), ("https://baz.com", "Baz")]
+    {
+        let expected = Entity::new(
+            Url::parse(url).unwrap(),
+            datetime!(2023-11-15 0:00 UTC).into(),
+            Some(Name::from(name)),
+            Default::default(),
+        );
+        let id = collection.id(expected.url()).unwrap();
+        assert_eq!(&expected, collection.entity(id));
+    }
+}
+
+const TEST_FORMATTED_NAME: &str = "\
+# November 15, 2023
+
+- [The *Foo* `bar` **Baz**](https://foo.com)
+";
+
+#[test]
+fn test_formatted_name() {
+    let collection = parse(TEST_FORMATTED_NAME).unwrap();
+
+    assert_eq!(collection.len(), 1);
+
+    let expected = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::from("The Foo bar Baz")),
+        Default::default(),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+}
+
+fn render(collection: &Collection, options: &Options) -> String {
+    let mut buf = Vec::new();
+    to_writer(collection, options, &mut buf).unwrap();
+    String::from_utf8(buf).unwrap()
+}
+
+const TEST_RENDER: &str = "\
+# November 15, 2023
+
+- <https://bare.com/>
+
+## programming
+
+### editor
+
+#### tools
+
+- [VS Code](https://code.visualstudio.com/)
+
+### rust
+
+- [Rust Website](https://rust-lang.org/)
+  - [Rust Book](https://doc.rust-lang.org/book/)
+    - [Rust by Example](https://doc.rust-lang.org/rust-by-example/)
+- <https://users.rust-lang.org/>
+
+# November 16, 2023
+
+- [Foo](https://foo.com/)
+";
+
+#[test]
+fn test_render() {
+    let collection = parse(TEST_RENDER).unwrap();
+    let actual = render(&collection, &Options::default());
+    assert_eq!(TEST_RENDER, actual);
+}
+
+#[test]
+fn test_render_round_trip() {
+    for input in [TEST_PARENTS, TEST_SINGLE_PARENT, TEST_MULTIPLE_LABELS, TEST_MIXED_DATES] {
+        let expected = parse(input).unwrap();
+        let actual = parse(&render(&expected, &Options::default())).unwrap();
+        assert_eq!(expected.len(), actual.len());
+        for (id, entity) in expected.iter() {
+            let other_id = actual.id(entity.url()).unwrap();
+            let other = actual.entity(other_id);
+            assert_eq!(entity.names(), other.names());
+            assert_eq!(entity.labels(), other.labels());
+            assert_eq!(entity.created_at(), other.created_at());
+            assert_eq!(entity.updated_at().len(), other.updated_at().len());
+            let edges: BTreeSet<&Url> =
+                expected.edges(id).iter().map(|to| expected.entity(*to).url()).collect();
+            let other_edges: BTreeSet<&Url> =
+                actual.edges(other_id).iter().map(|to| actual.entity(*to).url()).collect();
+            assert_eq!(edges, other_edges);
+        }
+    }
+}
+
+#[test]
+fn test_render_escaped() {
+    let mut collection = Collection::new();
+    let entity = Entity::new(
+        Url::parse("https://foo.com/(bar)").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::from("[Foo] *bar* & <baz> C#")),
+        [Label::from("c#"), Label::from("a_b*c")].into_iter().collect(),
+    );
+    collection.insert(entity.clone());
+
+    let actual = parse(&render(&collection, &Options::default())).unwrap();
+    let id = actual.id(entity.url()).unwrap();
+    assert_eq!(&entity, actual.entity(id));
+}
+
+#[test]
+fn test_render_many_labels() {
+    let mut collection = Collection::new();
+    let labels: BTreeSet<Label> = (0..7).map(|i| Label::from(format!("label{i}"))).collect();
+    let entity = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::from("Foo")),
+        labels.clone(),
+    );
+    collection.insert(entity);
+
+    let actual = parse(&render(&collection, &Options::default())).unwrap();
+    assert_eq!(actual.len(), 1);
+    assert_eq!(actual.entities()[0].labels(), &labels);
+}
+
+#[test]
+fn test_render_by_label() {
+    let collection = parse(TEST_RENDER).unwrap();
+    let options = Options { group_by: GroupBy::Label };
+    let expected = "\
+- <https://bare.com/> (November 15, 2023)
+- [Foo](https://foo.com/) (November 16, 2023)
+
+# editor
+
+- [VS Code](https://code.visualstudio.com/) (November 15, 2023)
+
+# programming
+
+- [VS Code](https://code.visualstudio.com/) (November 15, 2023)
+- [Rust Website](https://rust-lang.org/) (November 15, 2023)
+- [Rust Book](https://doc.rust-lang.org/book/) (November 15, 2023)
+- [Rust by Example](https://doc.rust-lang.org/rust-by-example/) (November 15, 2023)
+- <https://users.rust-lang.org/> (November 15, 2023)
+
+# rust
+
+- [Rust Website](https://rust-lang.org/) (November 15, 2023)
+- [Rust Book](https://doc.rust-lang.org/book/) (November 15, 2023)
+- [Rust by Example](https://doc.rust-lang.org/rust-by-example/) (November 15, 2023)
+- <https://users.rust-lang.org/> (November 15, 2023)
+
+# tools
+
+- [VS Code](https://code.visualstudio.com/) (November 15, 2023)
+";
+    assert_eq!(expected, render(&collection, &options));
+}
+
+const TEST_FRONTMATTER: &str = "\
+---
+name: Reading list
+labels: [reading, web]
+date: 2023-11-14
+---
+
+- [Foo](https://foo.com)
+
+# November 15, 2023
+
+## rust
+
+- [Bar](https://bar.com)
+";
+
+#[test]
+fn test_frontmatter() {
+    let collection = parse(TEST_FRONTMATTER).unwrap();
+    assert_eq!(collection.len(), 2);
+    assert_eq!(collection.name(), Some("Reading list"));
+
+    let expected = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-14 0:00 UTC).into(),
+        Some(Name::from("Foo")),
+        [Label::from("reading"), Label::from("web")].into_iter().collect(),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+
+    // The frontmatter date overrides the date heading
+    let expected = Entity::new(
+        Url::parse("https://bar.com").unwrap(),
+        datetime!(2023-11-14 0:00 UTC).into(),
+        Some(Name::from("Bar")),
+        [Label::from("reading"), Label::from("rust"), Label::from("web")].into_iter().collect(),
+    );
+    let id = collection.id(expected.url()).unwrap();
+    assert_eq!(&expected, collection.entity(id));
+}
+
+const TEST_FRONTMATTER_TAGS: &str = "\
+---
+title: Something else
+tags:
+  - reading
+---
+
+# November 15, 2023
+
+- [Foo](https://foo.com)
+";
+
+#[test]
+fn test_frontmatter_tags() {
+    let collection = parse(TEST_FRONTMATTER_TAGS).unwrap();
+    assert_eq!(collection.name(), None);
+    let entity = &collection.entities()[0];
+    assert_eq!(entity.labels(), &[Label::from("reading")].into_iter().collect());
+}
+
+const TEST_FRONTMATTER_EMPTY: &str = "\
+---
+---
+
+# November 15, 2023
+
+- [Foo](https://foo.com)
+";
+
+#[test]
+fn test_frontmatter_empty() {
+    let collection = parse(TEST_FRONTMATTER_EMPTY).unwrap();
+    assert_eq!(collection.len(), 1);
+}
+
+const TEST_FRONTMATTER_INVALID_DATE: &str = "\
+---
+date: someday
+---
+";
+
+#[test]
+fn test_frontmatter_invalid_date() {
+    let err = parse(TEST_FRONTMATTER_INVALID_DATE).unwrap_err();
+    assert!(err.to_string().starts_with("date parsing error"));
+}
+
+#[test]
+fn test_render_name() {
+    let collection = parse(TEST_FRONTMATTER).unwrap();
+    let actual = render(&collection, &Options::default());
+    assert!(actual.starts_with("---\nname: Reading list\n---\n\n# November 14, 2023\n"));
+    assert_eq!(parse(&actual).unwrap().name(), Some("Reading list"));
+}
+
+const TEST_SOURCES: &str = "\
+# November 15, 2023
+
+- [Foo](https://foo.com)
+  - <https://bar.com>
+
+## Baz
+
+- [Foo, again](https://foo.com)
+";
+
+fn sources(collection: &Collection, url: &str) -> Vec<(String, usize)> {
+    let id = collection.id(&Url::parse(url).unwrap()).unwrap();
+    let sources = collection.entity(id).sources().iter();
+    sources.map(|location| (location.path.display().to_string(), location.line)).collect()
+}
+
+#[test]
+fn test_parse_from() {
+    let collection = parse_from(TEST_SOURCES, Path::new("links.md")).unwrap();
+    let expected = [(String::from("links.md"), 3), (String::from("links.md"), 8)];
+    assert_eq!(sources(&collection, "https://foo.com"), expected);
+    assert_eq!(sources(&collection, "https://bar.com"), [(String::from("links.md"), 4)]);
+    assert!(parse(TEST_SOURCES).unwrap().entities().iter().all(|e| e.sources().is_empty()));
+}
+
+#[test]
+fn test_parse_dir() {
+    let dir = std::env::temp_dir().join(format!("hbt-markdown-dir-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    let a = dir.join("a.md");
+    let b = dir.join("nested").join("b.md");
+    fs::write(&a, TEST_SOURCES).unwrap();
+    fs::write(&b, "# November 16, 2023\n\n- [Foo](https://foo.com)\n").unwrap();
+    fs::write(dir.join("notes.txt"), "- [Qux](https://qux.com)\n").unwrap();
+
+    let collection = parse_dir(&dir).unwrap();
+    assert_eq!(collection.len(), 2);
+    let expected =
+        [(a.display().to_string(), 3), (a.display().to_string(), 8), (b.display().to_string(), 3)];
+    assert_eq!(sources(&collection, "https://foo.com"), expected);
+    assert_eq!(collection.by_source(&b).count(), 1);
+    assert_eq!(collection.by_source(&a).count(), 2);
+    assert_eq!(collection.by_source(Path::new("c.md")).count(), 0);
+    let foo = collection.entity(collection.by_source(&b).next().unwrap());
+    let update = foo.history().last().unwrap();
+    assert_eq!(update.source.as_deref(), Some(b.display().to_string().as_str()));
+
+    fs::write(&b, "- [Foo](https://foo.com)\n").unwrap();
+    let err = parse_dir(&dir).unwrap_err();
+    assert!(matches!(&err, Error::File(path, inner) if path == &b
+        && matches!(**inner, Error::MissingDate)));
+    fs::remove_dir_all(&dir).unwrap();
+}
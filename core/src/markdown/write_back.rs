@@ -0,0 +1,383 @@
+//! Writing changed names and labels back into the Markdown files entities were parsed from.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeSet, HashMap},
+    fs,
+    ops::Range,
+    path::{Path, PathBuf},
+};
+
+use pulldown_cmark::{Event, LinkType, Options as ParserOptions, Parser, Tag, TagEnd};
+use url::Url;
+
+use super::{escape, parse_from, Error, Frontmatter, HeadingLevelExt, MAX_LABELS};
+use crate::collection::{Collection, Id, Label, Name};
+
+struct Heading {
+    /// The heading's lines, with the final newline.
+    range: Range<usize>,
+    level: usize,
+    /// The labels the heading and those enclosing it give, which is empty for a date heading.
+    path: Vec<Label>,
+}
+
+/// Where a link's text is, and what it says.
+type LinkText = (Range<usize>, String);
+
+/// A link to an entity, as it appears in a file.
+struct Listing {
+    url: Url,
+    /// The link text, or none for an autolink.
+    text: Option<LinkText>,
+    /// The list item the link is in, if it is in one.
+    item: Option<Range<usize>>,
+    /// The date heading the link is under, as an index into the headings.
+    section: Option<usize>,
+    path: Vec<Label>,
+}
+
+/// The headings and links of a file, with where each is.
+struct Outline {
+    frontmatter: Vec<Label>,
+    headings: Vec<Heading>,
+    listings: Vec<Listing>,
+}
+
+/// Returns the offset just past the end of the line the offset is on.
+fn line_end(input: &str, offset: usize) -> usize {
+    match input[offset..].find('\n') {
+        Some(i) => offset + i + 1,
+        None => input.len(),
+    }
+}
+
+fn line_start(input: &str, offset: usize) -> usize {
+    input[..offset].rfind('\n').map_or(0, |i| i + 1)
+}
+
+fn scan(input: &str) -> Result<Outline, Error> {
+    let parser = Parser::new_ext(input, ParserOptions::ENABLE_YAML_STYLE_METADATA_BLOCKS);
+    let mut ret = Outline { frontmatter: Vec::new(), headings: Vec::new(), listings: Vec::new() };
+
+    let mut labels: Vec<Label> = Vec::new();
+    let mut section: Option<usize> = None;
+    let mut items: Vec<Range<usize>> = Vec::new();
+    let mut heading_text: Option<String> = None;
+    let mut metadata_text: Option<String> = None;
+    // The destination of the link being read, and its text, if it has any
+    let mut link: Option<(String, Option<LinkText>)> = None;
+
+    for (event, range) in parser.into_offset_iter() {
+        match event {
+            Event::Start(Tag::Heading { level, .. }) => {
+                heading_text = Some(String::new());
+                let level = usize::from(HeadingLevelExt::from(level));
+                labels.truncate(level.saturating_sub(2));
+            }
+            Event::End(TagEnd::Heading(level)) => {
+                let text = heading_text.take().unwrap_or_default();
+                let level = usize::from(HeadingLevelExt::from(level));
+                if level == 1 {
+                    labels.clear();
+                    section = Some(ret.headings.len());
+                } else if !text.is_empty() {
+                    labels.push(Label::new(text));
+                }
+                let start = line_start(input, range.start);
+                let end = line_end(input, range.end.max(1) - 1);
+                ret.headings.push(Heading { range: start..end, level, path: labels.clone() });
+            }
+            Event::Start(Tag::MetadataBlock(_)) => {
+                metadata_text = Some(String::new());
+            }
+            Event::End(TagEnd::MetadataBlock(_)) => {
+                let text = metadata_text.take().unwrap_or_default();
+                let frontmatter: Frontmatter =
+                    serde_norway::from_str::<Option<Frontmatter>>(&text)?.unwrap_or_default();
+                ret.frontmatter = frontmatter.labels.into_iter().map(Label::new).collect();
+            }
+            Event::Start(Tag::Item) => items.push(range),
+            Event::End(TagEnd::Item) => {
+                items.pop();
+            }
+            Event::Start(Tag::Link {
+                link_type:
+                    LinkType::Inline | LinkType::Reference | LinkType::Collapsed | LinkType::Shortcut,
+                dest_url,
+                ..
+            }) => {
+                let empty = range.start + 1..range.start + 1;
+                link = Some((dest_url.to_string(), Some((empty, String::new()))));
+            }
+            Event::Start(Tag::Link { link_type: LinkType::Autolink, dest_url, .. }) => {
+                link = Some((dest_url.to_string(), None));
+            }
+            Event::End(TagEnd::Link) => {
+                let Some((dest_url, text)) = link.take() else {
+                    continue;
+                };
+                let Ok(url) = Url::parse(&dest_url) else {
+                    continue;
+                };
+                let item = items.last().cloned();
+                ret.listings.push(Listing { url, text, item, section, path: labels.clone() });
+            }
+            event => {
+                if let Some((_, Some((text_range, text)))) = link.as_mut() {
+                    if text.is_empty() && text_range.start == text_range.end {
+                        *text_range = range.clone();
+                    }
+                    text_range.end = range.end;
+                    if let Event::Text(s) | Event::Code(s) = &event {
+                        text.push_str(s);
+                    }
+                } else if let Event::Text(s) | Event::Code(s) = &event {
+                    if let Some(heading_text) = heading_text.as_mut() {
+                        heading_text.push_str(s);
+                    } else if let Some(metadata_text) = metadata_text.as_mut() {
+                        metadata_text.push_str(s);
+                    }
+                }
+            }
+        }
+    }
+
+    Ok(ret)
+}
+
+/// Replaces link text naming an entity by a name it no longer has with its first name.
+fn write_names(collection: &Collection, input: &str) -> Result<String, Error> {
+    let outline = scan(input)?;
+    let mut ret = input.to_string();
+    for listing in outline.listings.iter().rev() {
+        let (Some(id), Some((range, text))) = (collection.id(&listing.url), &listing.text) else {
+            continue;
+        };
+        let names = collection.entity(id).names();
+        let Some(first) = names.first() else {
+            continue;
+        };
+        if text.is_empty() || names.contains(&Name::new(text.clone())) {
+            continue;
+        }
+        ret.replace_range(range.clone(), &escape(first.as_str()));
+    }
+    Ok(ret)
+}
+
+fn implied(collection: &Collection, outline: &Outline, listing: &Listing) -> BTreeSet<Label> {
+    let labels = listing.path.iter().chain(&outline.frontmatter);
+    labels.map(|label| collection.resolve_label(label).clone()).collect()
+}
+
+/// Moves a list item under the headings for the given labels, within its date section, adding any
+/// headings that are missing.
+fn move_item(input: &str, listing: &Listing, labels: &[Label]) -> Result<String, Error> {
+    let (Some(item), Some(section)) = (&listing.item, listing.section) else {
+        return Ok(input.to_string());
+    };
+
+    // Items may end with the blank lines after them
+    let start = line_start(input, item.start);
+    let len = input[item.clone()].trim_end().len().max(1);
+    let end = line_end(input, item.start + len - 1);
+    let indent = item.start - start;
+    let mut block = String::new();
+    for line in input[start..end].split_inclusive('\n') {
+        let dedented = match (line.get(..indent), line.get(indent..)) {
+            (Some(prefix), Some(rest)) if !rest.is_empty() && prefix.trim().is_empty() => rest,
+            _ => line.trim_start(),
+        };
+        block.push_str(dedented);
+    }
+    if !block.ends_with('\n') {
+        block.push('\n');
+    }
+
+    // An item alone between blank lines takes one of them with it
+    let mut removed = start..end;
+    if input[..start].ends_with("\n\n") {
+        if input[end..].starts_with('\n') {
+            removed.end += 1;
+        } else if end == input.len() {
+            removed.start -= 1;
+        }
+    }
+    let mut ret = input.to_string();
+    ret.replace_range(removed, "");
+
+    // Removing the item leaves the headings as they were
+    let outline = scan(&ret)?;
+    let section_end = outline.headings[section + 1..]
+        .iter()
+        .find(|heading| heading.level == 1)
+        .map_or(ret.len(), |heading| heading.range.start);
+    let wanted: BTreeSet<&Label> = labels.iter().collect();
+
+    // The deepest heading in the section whose labels are all wanted, else the date heading
+    let (index, parent) = outline
+        .headings
+        .iter()
+        .enumerate()
+        .filter(|(i, heading)| *i == section || (*i > section && heading.range.start < section_end))
+        .filter(|(_, heading)| heading.path.iter().all(|label| wanted.contains(label)))
+        .max_by_key(|(i, heading)| (heading.path.len(), *i))
+        .expect("the date heading");
+    let mut missing: Vec<&Label> =
+        labels.iter().filter(|label| !parent.path.contains(label)).collect();
+    missing.sort_by_key(|label| label.as_str());
+
+    // Add to the end of the parent's own items, or after its subheadings for a new subheading
+    let mut at = outline.headings[index + 1..]
+        .iter()
+        .find(|heading| missing.is_empty() || heading.level <= parent.level)
+        .map_or(ret.len(), |heading| heading.range.start)
+        .min(section_end);
+    while at > parent.range.end {
+        let start = line_start(&ret, at - 1);
+        if !ret[start..at].trim().is_empty() {
+            break;
+        }
+        at = start;
+    }
+
+    let mut insert = String::new();
+    if !missing.is_empty() {
+        insert.push('\n');
+        for (i, label) in missing.iter().enumerate() {
+            let level = parent.path.len() + i + 2;
+            insert.push_str(&format!("{} {}\n", "#".repeat(level), escape(label.as_str())));
+        }
+        insert.push('\n');
+    } else if at == parent.range.end {
+        insert.push('\n');
+    }
+    insert.push_str(&block);
+    ret.insert_str(at, &insert);
+    Ok(ret)
+}
+
+/// Writes the names and labels of the collection's entities back into the Markdown files they
+/// were parsed from, as recorded by [`parse_from`], leaving the rest of each file as it is.
+///
+/// A link naming its entity by a name the entity no longer has is renamed to the entity's first
+/// name.  A list item whose headings give labels the entity no longer has, or whose entity has
+/// labels no listing gives, is moved under the headings for the right labels, within the same date
+/// section, adding headings where there are none.  The first item listing an entity is the one
+/// that gains its labels.  Labels given by frontmatter are kept, as are links outside of lists,
+/// whose prose is not rearranged.  The recorded locations are updated afterwards.
+///
+/// Returns the files that were changed, in path order.
+pub fn write_back(collection: &mut Collection) -> Result<Vec<PathBuf>, Error> {
+    let paths: BTreeSet<PathBuf> = collection
+        .entities()
+        .iter()
+        .flat_map(|entity| entity.sources().iter().map(|location| location.path.clone()))
+        .collect();
+    let mut inputs = Vec::with_capacity(paths.len());
+    let mut outputs = Vec::with_capacity(paths.len());
+    for path in &paths {
+        let input = fs::read_to_string(path)?;
+        let output = write_names(collection, &input).map_err(|err| file_error(path, err))?;
+        inputs.push(input);
+        outputs.push(output);
+    }
+
+    let mut outlines = Vec::with_capacity(paths.len());
+    for (path, output) in paths.iter().zip(&outputs) {
+        outlines.push(scan(output).map_err(|err| file_error(path, err))?);
+    }
+    for (i, path) in paths.iter().enumerate() {
+        // Each move settles at least one listing, so this ends
+        for _ in 0..=outlines[i].listings.len() {
+            let Some((listing, labels)) = next_move(collection, &outlines, i) else {
+                break;
+            };
+            let output = move_item(&outputs[i], &outlines[i].listings[listing], &labels)
+                .map_err(|err| file_error(path, err))?;
+            outlines[i] = scan(&output).map_err(|err| file_error(path, err))?;
+            outputs[i] = output;
+        }
+    }
+
+    let mut ret = Vec::new();
+    for ((path, input), output) in paths.iter().zip(&inputs).zip(&outputs) {
+        if input == output {
+            continue;
+        }
+        fs::write(path, output)?;
+        let parsed = parse_from(output, path).map_err(|err| file_error(path, err))?;
+        let ids: Vec<Id> = collection.by_source(path).collect();
+        for id in ids {
+            collection.entity_mut(id).remove_sources(path);
+        }
+        for (_, entity) in &parsed {
+            if let Some(id) = collection.id(entity.url()) {
+                for location in entity.sources() {
+                    collection.entity_mut(id).add_source(location.clone());
+                }
+            }
+        }
+        ret.push(path.clone());
+    }
+    Ok(ret)
+}
+
+fn file_error(path: &Path, err: Error) -> Error {
+    Error::File(path.to_path_buf(), err.into())
+}
+
+/// Finds the first listing in the file at the given index that should be moved, with the labels
+/// its headings should give.
+fn next_move(
+    collection: &Collection,
+    outlines: &[Outline],
+    file: usize,
+) -> Option<(usize, Vec<Label>)> {
+    // The labels each entity's listings give, and its first listing
+    let mut given: HashMap<Id, BTreeSet<Label>> = HashMap::new();
+    let mut first: HashMap<Id, (usize, usize)> = HashMap::new();
+    for (i, outline) in outlines.iter().enumerate() {
+        for (j, listing) in outline.listings.iter().enumerate() {
+            let Some(id) = collection.id(&listing.url) else {
+                continue;
+            };
+            given.entry(id).or_default().extend(implied(collection, outline, listing));
+            first.entry(id).or_insert((i, j));
+        }
+    }
+
+    let outline = &outlines[file];
+    for (j, listing) in outline.listings.iter().enumerate() {
+        let Some(id) = collection.id(&listing.url) else {
+            continue;
+        };
+        if listing.item.is_none() || listing.section.is_none() {
+            continue;
+        }
+        let labels = collection.entity(id).labels();
+        let implied = implied(collection, outline, listing);
+        let frontmatter: BTreeSet<Label> = outline
+            .frontmatter
+            .iter()
+            .map(|label| collection.resolve_label(label).clone())
+            .collect();
+        let mut wanted: BTreeSet<Label> = implied.intersection(labels).cloned().collect();
+        wanted.extend(frontmatter.iter().cloned());
+        if first.get(&id) == Some(&(file, j)) {
+            wanted.extend(labels.difference(&given[&id]).cloned());
+        }
+        if wanted == implied {
+            continue;
+        }
+        let headings: Vec<Label> = wanted.difference(&frontmatter).cloned().collect();
+        if headings.len() > MAX_LABELS {
+            continue;
+        }
+        return Some((j, headings));
+    }
+    None
+}
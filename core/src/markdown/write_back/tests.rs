@@ -0,0 +1,186 @@
+use std::collections::BTreeSet;
+
+use super::*;
+use crate::collection::{Entity, Location, Resolution, Time};
+
+fn temp_file(name: &str, contents: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hbt-write-back-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    let path = dir.join("links.md");
+    fs::write(&path, contents).unwrap();
+    path
+}
+
+/// Replaces the names and labels of the entity with the URL.
+fn set(collection: &mut Collection, url: &str, names: &[&str], labels: &[&str]) {
+    let mut builder = Entity::builder(Url::parse(url).unwrap()).created(Time::default());
+    for name in names {
+        builder = builder.name(*name);
+    }
+    let entity = builder.labels(labels.iter().map(|label| label.to_string())).build();
+    collection.merge(entity, None, &mut |_, _: &_, _: &_| Resolution::Incoming);
+}
+
+fn lines(collection: &Collection, url: &str) -> Vec<usize> {
+    let id = collection.id(&Url::parse(url).unwrap()).unwrap();
+    collection.entity(id).sources().iter().map(|location| location.line).collect()
+}
+
+const TEST_RENAME_AND_MOVE: &str = "\
+# November 15, 2023
+
+Intro prose.
+
+- [Foo](https://foo.com)
+  - <https://child.com>
+- [Bar](https://bar.com) trailing *text*
+
+## Rust
+
+- [Rust](https://www.rust-lang.org/)
+
+# November 16, 2023
+
+- [Go](https://go.dev/)
+";
+
+#[test]
+fn test_rename_and_move() {
+    let path = temp_file("move", TEST_RENAME_AND_MOVE);
+    let mut collection = parse_from(TEST_RENAME_AND_MOVE, &path).unwrap();
+    set(&mut collection, "https://bar.com", &["Baz"], &[]);
+    set(&mut collection, "https://foo.com", &["Foo"], &["Rust"]);
+    set(&mut collection, "https://child.com", &[], &["Rust"]);
+
+    assert_eq!(write_back(&mut collection).unwrap(), std::slice::from_ref(&path));
+    let expected = "\
+# November 15, 2023
+
+Intro prose.
+
+- [Baz](https://bar.com) trailing *text*
+
+## Rust
+
+- [Rust](https://www.rust-lang.org/)
+- [Foo](https://foo.com)
+  - <https://child.com>
+
+# November 16, 2023
+
+- [Go](https://go.dev/)
+";
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    assert_eq!(lines(&collection, "https://foo.com"), [10]);
+    assert_eq!(lines(&collection, "https://bar.com"), [5]);
+
+    // Nothing is left to write back
+    assert!(write_back(&mut collection).unwrap().is_empty());
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+const TEST_NEW_HEADING: &str = "\
+---
+labels: [reading]
+---
+
+# November 15, 2023
+
+- [Foo](https://foo.com)
+
+## Rust
+
+- [Rust](https://www.rust-lang.org/)
+";
+
+#[test]
+fn test_new_heading() {
+    let path = temp_file("heading", TEST_NEW_HEADING);
+    let mut collection = parse_from(TEST_NEW_HEADING, &path).unwrap();
+    set(&mut collection, "https://foo.com", &["Foo"], &["Go", "reading"]);
+    // Frontmatter labels cannot be removed, and stay
+    set(&mut collection, "https://www.rust-lang.org/", &["Rust"], &[]);
+
+    write_back(&mut collection).unwrap();
+    let expected = "\
+---
+labels: [reading]
+---
+
+# November 15, 2023
+
+- [Rust](https://www.rust-lang.org/)
+
+## Rust
+
+## Go
+
+- [Foo](https://foo.com)
+";
+    let output = fs::read_to_string(&path).unwrap();
+    assert_eq!(output, expected);
+    let labels = |url: &str| {
+        let parsed = crate::markdown::parse(&output).unwrap();
+        let id = parsed.id(&Url::parse(url).unwrap()).unwrap();
+        let labels: BTreeSet<&str> = parsed.entity(id).labels().iter().map(Label::as_str).collect();
+        labels.into_iter().map(String::from).collect::<Vec<_>>()
+    };
+    assert_eq!(labels("https://foo.com"), ["Go", "reading"]);
+    assert_eq!(labels("https://www.rust-lang.org/"), ["reading"]);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+#[test]
+fn test_unchanged() {
+    let path = temp_file("unchanged", TEST_RENAME_AND_MOVE);
+    let mut collection = parse_from(TEST_RENAME_AND_MOVE, &path).unwrap();
+    // Labels given by another listing elsewhere are not moved here
+    let url = Url::parse("https://go.dev/").unwrap();
+    let mut other = Entity::builder(url).created(Time::default()).labels(["Go"]).build();
+    other.add_source(Location::new(path.with_file_name("other.md"), 1));
+    fs::write(
+        path.with_file_name("other.md"),
+        "# November 17, 2023\n\n## Go\n\n- <https://go.dev/>\n",
+    )
+    .unwrap();
+    collection.upsert(other);
+
+    assert!(write_back(&mut collection).unwrap().is_empty());
+    assert_eq!(fs::read_to_string(&path).unwrap(), TEST_RENAME_AND_MOVE);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
+
+const TEST_MULTIBYTE: &str = "\
+# November 15, 2023
+
+## Go
+
+- [Go](https://go.dev/)
+  - [Child](https://child.com/)
+—continued
+";
+
+#[test]
+fn test_multibyte_continuation() {
+    let path = temp_file("multibyte", TEST_MULTIBYTE);
+    let mut collection = parse_from(TEST_MULTIBYTE, &path).unwrap();
+    set(&mut collection, "https://child.com/", &["Child"], &["Go", "Rust"]);
+
+    write_back(&mut collection).unwrap();
+    // The lazy continuation line, not indented as the item is, moves along with it
+    let expected = "\
+# November 15, 2023
+
+## Go
+
+- [Go](https://go.dev/)
+
+### Rust
+
+- [Child](https://child.com/)
+—continued
+";
+    assert_eq!(fs::read_to_string(&path).unwrap(), expected);
+    fs::remove_dir_all(path.parent().unwrap()).unwrap();
+}
@@ -0,0 +1,124 @@
+//! Acting as a native messaging host, so a browser extension can save pages to a collection.
+//!
+//! Browsers start the host as a child process and exchange messages with it on its standard input
+//! and output, each message being JSON preceded by its length, as a 32-bit integer in native byte
+//! order.  The extension sends:
+//!
+//! ```json
+//! {"type": "save", "url": "https://example.com/", "title": "Example", "selection": "Some text"}
+//! ```
+//!
+//! with the `title`, the `selection`, and `labels`, an array, optional.  The host answers each
+//! message with `{"ok": true, "id": 0, "added": true}`, or `{"ok": false, "error": "…"}`.
+
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, Read, Write};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+use url::Url;
+
+use crate::collection::{Collection, Entity, Note, Time, Union};
+
+/// The source recorded on updates made by saving pages.
+pub const SOURCE: &str = "browser";
+
+/// Messages longer than this are refused rather than read.
+const MAX_MESSAGE: usize = 64 * 1024 * 1024;
+
+#[derive(Debug, Deserialize)]
+#[serde(tag = "type", rename_all = "camelCase")]
+enum Message {
+    Save {
+        url: String,
+        title: Option<String>,
+        /// The text selected on the page, kept as a note.
+        selection: Option<String>,
+        #[serde(default)]
+        labels: Vec<String>,
+    },
+}
+
+/// Reads a message, or returns `None` if the browser has closed the stream.
+pub fn read_message(reader: &mut impl Read) -> io::Result<Option<Vec<u8>>> {
+    let mut length = [0; 4];
+    match reader.read_exact(&mut length) {
+        Ok(()) => {}
+        Err(err) if err.kind() == io::ErrorKind::UnexpectedEof => return Ok(None),
+        Err(err) => return Err(err),
+    }
+    let length = u32::from_ne_bytes(length) as usize;
+    if length > MAX_MESSAGE {
+        return Err(io::Error::new(io::ErrorKind::InvalidData, "message too long"));
+    }
+    let mut ret = vec![0; length];
+    reader.read_exact(&mut ret)?;
+    Ok(Some(ret))
+}
+
+pub fn write_message(writer: &mut impl Write, message: &Value) -> io::Result<()> {
+    let message = message.to_string();
+    let length = u32::try_from(message.len())
+        .map_err(|_| io::Error::new(io::ErrorKind::InvalidInput, "message too long"))?;
+    writer.write_all(&length.to_ne_bytes())?;
+    writer.write_all(message.as_bytes())?;
+    writer.flush()
+}
+
+fn save(
+    collection: &mut Collection,
+    url: &str,
+    title: Option<String>,
+    selection: Option<String>,
+    labels: Vec<String>,
+) -> Result<Value, String> {
+    let url = Url::parse(url).map_err(|err| format!("invalid URL: {}: {}", err, url))?;
+    let now = Time::now();
+    let mut builder = Entity::builder(url.clone()).created(now).labels(labels);
+    if let Some(title) = title.filter(|title| !title.trim().is_empty()) {
+        builder = builder.name(title.trim());
+    }
+    let imported =
+        collection.merge_all_from([builder.build()], Some(SOURCE.to_string()), &mut Union);
+    let id = collection.id(&url).expect("just merged");
+    if let Some(selection) = selection.filter(|selection| !selection.trim().is_empty()) {
+        collection.add_note(id, Note::new(now, selection.trim().to_string()));
+    }
+    Ok(json!({ "ok": true, "id": id, "added": imported.added == 1 }))
+}
+
+/// Handles one message, returning the response to send and whether the collection was changed.
+pub fn handle(collection: &mut Collection, message: &[u8]) -> (Value, bool) {
+    let result = match serde_json::from_slice(message) {
+        Ok(Message::Save { url, title, selection, labels }) => {
+            save(collection, &url, title, selection, labels)
+        }
+        Err(err) => Err(format!("invalid message: {}", err)),
+    };
+    match result {
+        Ok(response) => (response, true),
+        Err(error) => (json!({ "ok": false, "error": error }), false),
+    }
+}
+
+/// Answers the messages read from `reader` on `writer` until the browser closes `reader`, calling
+/// `changed` after each message that changes the collection.
+///
+/// An error from `changed` stops the host.
+pub fn run(
+    mut reader: impl Read,
+    mut writer: impl Write,
+    collection: &mut Collection,
+    mut changed: impl FnMut(&Collection) -> io::Result<()>,
+) -> io::Result<()> {
+    while let Some(message) = read_message(&mut reader)? {
+        let (response, was_changed) = handle(collection, &message);
+        if was_changed {
+            changed(collection)?;
+        }
+        write_message(&mut writer, &response)?;
+    }
+    Ok(())
+}
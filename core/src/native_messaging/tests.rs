@@ -0,0 +1,105 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Label, Name};
+
+fn frame(message: &str) -> Vec<u8> {
+    let mut ret = (message.len() as u32).to_ne_bytes().to_vec();
+    ret.extend_from_slice(message.as_bytes());
+    ret
+}
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    collection.insert(Entity::new(
+        Url::parse("https://rust-lang.org/").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new("Rust".to_string())),
+        BTreeSet::from([Label::from("rust")]),
+    ));
+    collection
+}
+
+#[test]
+fn test_messages() {
+    let mut buffer = Vec::new();
+    write_message(&mut buffer, &json!({ "ok": true })).unwrap();
+    assert_eq!(buffer, frame(r#"{"ok":true}"#));
+    let mut reader = buffer.as_slice();
+    assert_eq!(read_message(&mut reader).unwrap(), Some(br#"{"ok":true}"#.to_vec()));
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+
+    let truncated = &frame("{}")[..5];
+    assert!(read_message(&mut &truncated[..]).is_err());
+    let too_long = u32::MAX.to_ne_bytes();
+    assert!(read_message(&mut &too_long[..]).is_err());
+}
+
+#[test]
+fn test_save() {
+    let mut collection = create_test_collection();
+    let message = br#"{"type": "save", "url": "https://rust-lang.org/", "title": "Rust Website",
+                      "selection": "  A language empowering everyone  ", "labels": ["lang"]}"#;
+    let (response, changed) = handle(&mut collection, message);
+    assert!(changed);
+    assert_eq!(response, json!({ "ok": true, "id": 0, "added": false }));
+    let entity =
+        collection.entity(collection.id(&Url::parse("https://rust-lang.org/").unwrap()).unwrap());
+    let names: Vec<&str> = entity.names().iter().map(Name::as_str).collect();
+    assert_eq!(names, ["Rust", "Rust Website"]);
+    let labels: Vec<&str> = entity.labels().iter().map(Label::as_str).collect();
+    assert_eq!(labels, ["lang", "rust"]);
+    let notes: Vec<&str> = entity.notes().iter().map(|note| note.text.as_str()).collect();
+    assert_eq!(notes, ["A language empowering everyone"]);
+
+    let (response, changed) =
+        handle(&mut collection, br#"{"type": "save", "url": "https://zig.dev/"}"#);
+    assert!(changed);
+    assert_eq!(response, json!({ "ok": true, "id": 1, "added": true }));
+    assert!(collection
+        .entity(collection.id(&Url::parse("https://zig.dev/").unwrap()).unwrap())
+        .notes()
+        .is_empty());
+}
+
+#[test]
+fn test_invalid() {
+    let mut collection = create_test_collection();
+    for message in [
+        &br#"{"type": "save", "url": "not a url"}"#[..],
+        br#"{"type": "delete", "url": "https://rust-lang.org/"}"#,
+        br#"{"url": "https://rust-lang.org/"}"#,
+        b"{",
+    ] {
+        let (response, changed) = handle(&mut collection, message);
+        assert!(!changed);
+        assert_eq!(response["ok"], false);
+        assert!(response["error"].is_string());
+    }
+    assert_eq!(collection.len(), 1);
+}
+
+#[test]
+fn test_run() {
+    let mut collection = create_test_collection();
+    let mut input = frame(r#"{"type": "save", "url": "https://zig.dev/"}"#);
+    input.extend(frame(r#"{"type": "nothing"}"#));
+    let mut output = Vec::new();
+    let mut saved = Vec::new();
+    run(input.as_slice(), &mut output, &mut collection, |collection| {
+        saved.push(collection.len());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(saved, [2]);
+    let mut reader = output.as_slice();
+    let first: Value =
+        serde_json::from_slice(&read_message(&mut reader).unwrap().unwrap()).unwrap();
+    assert_eq!(first["added"], true);
+    let second: Value =
+        serde_json::from_slice(&read_message(&mut reader).unwrap().unwrap()).unwrap();
+    assert_eq!(second["ok"], false);
+    assert_eq!(read_message(&mut reader).unwrap(), None);
+}
@@ -1,11 +1,16 @@
 #[cfg(test)]
 mod tests;
 
-use std::collections::{hash_set::Iter, HashSet};
+use std::{
+    collections::{hash_set::Iter, HashSet},
+    io::BufRead,
+};
 
 use serde::Deserialize;
 use thiserror::Error;
 
+use crate::collection::{self, Collection, Entity};
+
 #[derive(Debug, Error)]
 pub enum Error {
     #[error("HTML selector error: {0}")]
@@ -18,6 +23,8 @@ pub enum Error {
     ParseUtf8(#[from] std::string::FromUtf8Error),
     #[error("JSON parsing error: {0}")]
     ParseJson(#[from] serde_json::Error),
+    #[error("entity conversion error: {0}")]
+    Entity(#[from] collection::Error),
 }
 
 #[derive(Debug, PartialEq, Eq)]
@@ -101,11 +108,29 @@ impl Post {
         serde_json::from_str(input).map_err(Into::into)
     }
 
+    /// Reads the posts of a JSON export one at a time, as with [`Post::from_json`], without
+    /// holding the whole export in memory.
+    pub fn read_json<R: BufRead>(reader: R) -> json::Reader<R> {
+        json::Reader::new(reader)
+    }
+
     pub fn from_xml(input: &str) -> Result<Vec<Post>, Error> {
         xml::parse(input)
     }
 }
 
+/// Merges [`Post`]s into a [`Collection`].
+///
+/// Each post's description becomes a name and its tags become labels.  Posts whose URL is already
+/// present in the collection are merged into the existing entity.
+pub fn import(posts: Vec<Post>, collection: &mut Collection) -> Result<(), Error> {
+    for post in posts {
+        let entity = Entity::try_from(post)?;
+        collection.upsert(entity);
+    }
+    Ok(())
+}
+
 mod html {
     use scraper::{Element, Html, Selector};
 
@@ -178,10 +203,115 @@ mod html {
     }
 }
 
-mod json {
-    use serde::{Deserialize, Deserializer};
+pub mod json {
+    use std::io::BufRead;
+
+    use serde::{de::Error as _, Deserialize, Deserializer};
+
+    use super::{Error, Post};
+
+    #[derive(Debug, Copy, Clone, PartialEq, Eq)]
+    enum Position {
+        Start,
+        First,
+        Rest,
+        End,
+    }
+
+    /// A pull parser over the array of posts in a JSON export, created by [`Post::read_json`].
+    ///
+    /// Each post is parsed from the reader as it is asked for, so only one post is held at once.
+    #[derive(Debug)]
+    pub struct Reader<R> {
+        reader: R,
+        position: Position,
+    }
+
+    impl<R: BufRead> Reader<R> {
+        pub(super) fn new(reader: R) -> Reader<R> {
+            Reader { reader, position: Position::Start }
+        }
+
+        /// Skips whitespace, returning the next byte without consuming it.
+        fn peek(&mut self) -> Result<Option<u8>, serde_json::Error> {
+            loop {
+                let buf = self.reader.fill_buf().map_err(serde_json::Error::io)?;
+                if buf.is_empty() {
+                    return Ok(None);
+                }
+                match buf.iter().position(|byte| !byte.is_ascii_whitespace()) {
+                    Some(index) => {
+                        let byte = buf[index];
+                        self.reader.consume(index);
+                        return Ok(Some(byte));
+                    }
+                    None => {
+                        let len = buf.len();
+                        self.reader.consume(len);
+                    }
+                }
+            }
+        }
+
+        fn expect(&mut self, expected: u8) -> Result<(), serde_json::Error> {
+            match self.peek()? {
+                Some(byte) if byte == expected => {
+                    self.reader.consume(1);
+                    Ok(())
+                }
+                Some(byte) => Err(serde_json::Error::custom(format!(
+                    "expected `{}`, found `{}`",
+                    expected as char, byte as char
+                ))),
+                None => Err(serde_json::Error::custom(format!(
+                    "expected `{}`, found end of input",
+                    expected as char
+                ))),
+            }
+        }
+
+        fn next_post(&mut self) -> Result<Option<Post>, serde_json::Error> {
+            if self.position == Position::Start {
+                self.expect(b'[')?;
+                self.position = Position::First;
+            }
+            if self.peek()? == Some(b']') {
+                self.reader.consume(1);
+                self.position = Position::End;
+                return Ok(None);
+            }
+            if self.position == Position::Rest {
+                self.expect(b',')?;
+            }
+            self.position = Position::Rest;
+            // Posts are objects, so the deserializer stops at the closing brace without reading
+            // past it
+            let mut deserializer = serde_json::Deserializer::from_reader(&mut self.reader);
+            Post::deserialize(&mut deserializer).map(Some)
+        }
+    }
+
+    impl<R: BufRead> Iterator for Reader<R> {
+        type Item = Result<Post, Error>;
+
+        fn next(&mut self) -> Option<Self::Item> {
+            if self.position == Position::End {
+                return None;
+            }
+            match self.next_post() {
+                Ok(Some(post)) => Some(Ok(post)),
+                Ok(None) => None,
+                Err(err) => {
+                    self.position = Position::End;
+                    Some(Err(err.into()))
+                }
+            }
+        }
+    }
 
-    pub fn deserialize_empty_string<'de, D>(deserializer: D) -> Result<Option<String>, D::Error>
+    pub(super) fn deserialize_empty_string<'de, D>(
+        deserializer: D,
+    ) -> Result<Option<String>, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -193,7 +323,7 @@ mod json {
         }
     }
 
-    pub fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
+    pub(super) fn deserialize_tags<'de, D>(deserializer: D) -> Result<Vec<String>, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -205,7 +335,7 @@ mod json {
         }
     }
 
-    pub fn deserialize_yes_no<'de, D>(deserializer: D) -> Result<bool, D::Error>
+    pub(super) fn deserialize_yes_no<'de, D>(deserializer: D) -> Result<bool, D::Error>
     where
         D: Deserializer<'de>,
     {
@@ -1,4 +1,8 @@
+use time::macros::datetime;
+use url::Url;
+
 use super::*;
+use crate::collection::{Label, Time, META_PINBOARD_HASH};
 
 const TEST_XML_EMPTY: &str = "";
 
@@ -107,6 +111,33 @@ fn test_json_sample() {
     assert_eq!(expected_tags, actual_tags)
 }
 
+#[test]
+fn test_read_json() {
+    let expected = Post::from_json(TEST_JSON_SAMPLE).unwrap();
+    // A small buffer makes posts straddle refills
+    let reader = std::io::BufReader::with_capacity(16, TEST_JSON_SAMPLE.as_bytes());
+    let actual: Vec<Post> = Post::read_json(reader).collect::<Result<_, _>>().unwrap();
+    assert_eq!(expected, actual);
+}
+
+#[test]
+fn test_read_json_empty() {
+    assert_eq!(Post::read_json(" [ ] ".as_bytes()).count(), 0);
+}
+
+#[test]
+fn test_read_json_errors() {
+    let err = Post::read_json("{}".as_bytes()).next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "JSON parsing error: expected `[`, found `{`");
+
+    let input = TEST_JSON_SAMPLE.trim_end().trim_end_matches(']');
+    let mut reader = Post::read_json(input.as_bytes());
+    assert_eq!(reader.by_ref().take(3).filter(Result::is_ok).count(), 3);
+    let err = reader.next().unwrap().unwrap_err();
+    assert_eq!(err.to_string(), "JSON parsing error: expected `,`, found end of input");
+    assert!(reader.next().is_none());
+}
+
 const TEST_HTML_SAMPLE: &str = r#"<!DOCTYPE NETSCAPE-Bookmark-file-1>
 <META HTTP-EQUIV="Content-Type" CONTENT="text/html; charset=UTF-8">
 <TITLE>Pinboard Bookmarks</TITLE>
@@ -158,3 +189,44 @@ fn test_html_sample() {
     ];
     assert_eq!(actual, expected);
 }
+
+#[test]
+fn test_import_json() {
+    let posts = Post::from_json(TEST_JSON_SAMPLE).unwrap();
+    let mut collection = Collection::new();
+    import(posts, &mut collection).unwrap();
+    assert_eq!(collection.len(), 3);
+
+    let url = Url::parse("https://github.com/janestreet/magic-trace").unwrap();
+    let entity = collection.entity(collection.id(&url).unwrap());
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2022-04-23 00:29:36 UTC)));
+    assert_eq!(entity.names().first().unwrap().as_str(), "janestreet/magic-trace: magic-trace collects and displays high-resolution traces of what a process is doing");
+    assert!(entity.labels().contains(&Label::from("profiling")));
+    assert!(entity.shared());
+    assert_eq!(entity.metadata_value(META_PINBOARD_HASH), Some("54dab27be2409c987bb17fc06e47a729"));
+
+    let url = Url::parse("http://kcachegrind.sourceforge.net/html/Home.html").unwrap();
+    let entity = collection.entity(collection.id(&url).unwrap());
+    assert!(entity.names().is_empty());
+}
+
+#[test]
+fn test_import_merges() {
+    let posts = Post::from_json(TEST_JSON_SAMPLE).unwrap();
+    let mut collection = Collection::new();
+    import(posts, &mut collection).unwrap();
+    let posts = Post::from_xml(TEST_XML_SAMPLE).unwrap();
+    import(posts, &mut collection).unwrap();
+    assert_eq!(collection.len(), 3);
+    for entity in collection.entities() {
+        assert_eq!(entity.updated_at().len(), 1);
+    }
+}
+
+#[test]
+fn test_import_html() {
+    let posts = Post::from_html(TEST_HTML_SAMPLE).unwrap();
+    let mut collection = Collection::new();
+    import(posts, &mut collection).unwrap();
+    assert_eq!(collection.len(), 3);
+}
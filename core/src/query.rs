@@ -0,0 +1,528 @@
+//! A small query language for selecting entities.
+//!
+//! A query is made of terms combined with `AND`, `OR` and `NOT`, grouped with parentheses:
+//!
+//! ```text
+//! label:rust AND (label:async OR name:"tokio") AND created>2023-01-01
+//! ```
+//!
+//! `AND` binds tighter than `OR`, and may be left out: `label:rust label:async` is the same as
+//! `label:rust AND label:async`.  Parentheses and `NOT`s nest at most 128 deep.  The terms are:
+//!
+//! - `label:L` matches entities carrying `L` or a label nested under it, like `L/sub`.
+//! - `name:TEXT` and `url:TEXT` match entities whose names or URL contain `TEXT`, ignoring case.
+//! - `created` and `updated` compare the day of creation or of the last update with a date, using
+//!   one of `:`, `=`, `<`, `<=`, `>` or `>=`.  Dates are read by [`Time::parse`].
+//! - `is:starred`, `is:unread`, `is:read` and `is:archived` match on an entity's flags.
+//! - A bare word or quoted string matches entities whose URL, names or labels contain it, ignoring
+//!   case, as [`Collection::search`] does.
+
+#[cfg(test)]
+mod tests;
+
+use std::{fmt, str::FromStr};
+
+use thiserror::Error;
+use time::{Date, OffsetDateTime};
+
+use serde::{Deserialize, Serialize};
+
+use crate::collection::{
+    Collection, Entity, Id, Label, ReadStatus, SortKey, SortValue, StableId, Time,
+};
+
+#[derive(Debug, Error, PartialEq, Eq)]
+pub enum Error {
+    #[error("unexpected end of query")]
+    UnexpectedEnd,
+    #[error("unexpected {0} at position {1}")]
+    UnexpectedToken(String, usize),
+    #[error("unterminated string at position {0}")]
+    UnterminatedString(usize),
+    #[error("unknown field: {0}")]
+    UnknownField(String),
+    #[error("invalid comparison for {0}: {1}")]
+    InvalidComparison(String, Comparison),
+    #[error("invalid date: {0}")]
+    InvalidDate(String),
+    #[error("unknown flag: {0}")]
+    UnknownFlag(String),
+    #[error("query nested too deeply at position {0}")]
+    TooDeep(usize),
+    #[error("invalid cursor: {0}")]
+    InvalidCursor(String),
+    #[error("cursor is not for sorting by {0:?}")]
+    MismatchedCursor(SortKey),
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Comparison {
+    Eq,
+    Lt,
+    Le,
+    Gt,
+    Ge,
+}
+
+impl Comparison {
+    fn holds<T: Ord>(self, lhs: T, rhs: T) -> bool {
+        match self {
+            Comparison::Eq => lhs == rhs,
+            Comparison::Lt => lhs < rhs,
+            Comparison::Le => lhs <= rhs,
+            Comparison::Gt => lhs > rhs,
+            Comparison::Ge => lhs >= rhs,
+        }
+    }
+}
+
+impl fmt::Display for Comparison {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let op = match self {
+            Comparison::Eq => "=",
+            Comparison::Lt => "<",
+            Comparison::Le => "<=",
+            Comparison::Gt => ">",
+            Comparison::Ge => ">=",
+        };
+        f.write_str(op)
+    }
+}
+
+#[derive(Debug, Copy, Clone, PartialEq, Eq)]
+pub enum Flag {
+    Starred,
+    Unread,
+    Read,
+    Archived,
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Term {
+    Label(Label),
+    /// Expects the text to already be lowercase.
+    Name(String),
+    /// Expects the text to already be lowercase.
+    Url(String),
+    /// Expects the text to already be lowercase.
+    Text(String),
+    Created(Comparison, Date),
+    Updated(Comparison, Date),
+    Is(Flag),
+}
+
+impl Term {
+    fn matches(&self, entity: &Entity) -> bool {
+        let date = |time: &Time| OffsetDateTime::from(*time).date();
+        match self {
+            Term::Label(label) => entity.labels().iter().any(|l| l.is_within(label)),
+            Term::Name(text) => {
+                entity.names().iter().any(|name| name.as_str().to_lowercase().contains(text))
+            }
+            Term::Url(text) => entity.url().as_str().to_lowercase().contains(text),
+            Term::Text(text) => entity.matches(text),
+            Term::Created(comparison, rhs) => comparison.holds(date(entity.created_at()), *rhs),
+            Term::Updated(comparison, rhs) => {
+                let last = entity.last_updated_at().unwrap_or(entity.created_at());
+                comparison.holds(date(last), *rhs)
+            }
+            Term::Is(Flag::Starred) => entity.starred(),
+            Term::Is(Flag::Unread) => entity.read_status().is_unread(),
+            Term::Is(Flag::Read) => matches!(entity.read_status(), ReadStatus::Read { .. }),
+            Term::Is(Flag::Archived) => matches!(entity.read_status(), ReadStatus::Archived),
+        }
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub enum Query {
+    Term(Term),
+    Not(Box<Query>),
+    And(Box<Query>, Box<Query>),
+    Or(Box<Query>, Box<Query>),
+}
+
+impl Query {
+    pub fn parse(query: &str) -> Result<Query, Error> {
+        let tokens = lex(query)?;
+        let mut parser = Parser { tokens, position: 0, depth: 0 };
+        let ret = parser.or()?;
+        match parser.tokens.get(parser.position) {
+            Some((token, offset)) => Err(Error::UnexpectedToken(token.to_string(), *offset)),
+            None => Ok(ret),
+        }
+    }
+
+    pub fn matches(&self, entity: &Entity) -> bool {
+        match self {
+            Query::Term(term) => term.matches(entity),
+            Query::Not(query) => !query.matches(entity),
+            Query::And(lhs, rhs) => lhs.matches(entity) && rhs.matches(entity),
+            Query::Or(lhs, rhs) => lhs.matches(entity) || rhs.matches(entity),
+        }
+    }
+
+    /// Returns the [`Id`]s of all entities in the collection matching the query, in ascending order.
+    pub fn select<'a>(&'a self, collection: &'a Collection) -> impl Iterator<Item = Id> + 'a {
+        collection.iter().filter(|(_, entity)| self.matches(entity)).map(|(id, _)| id)
+    }
+
+    /// Returns a [`Page`] of the entities in the collection matching the query.
+    pub fn page(
+        &self,
+        collection: &Collection,
+        key: SortKey,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page, Error> {
+        Page::of(collection, self.select(collection), key, after, limit)
+    }
+}
+
+/// Up to some number of entities in order, with a cursor to continue from.
+///
+/// Entities are ordered by a [`SortKey`], ties broken by [`StableId`], and a page continues after
+/// the place given by its [`Cursor`]: what the last entity of the previous page had for the key,
+/// and its stable id.  As the cursor does not need that entity to still be there, paging through
+/// a collection that changes between pages shows exactly once each entity that is there
+/// throughout and keeps its place in the order, unlike skipping a number of entities.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Page {
+    pub ids: Vec<Id>,
+    /// The cursor for the next page, or `None` if this is the last.
+    pub next: Option<Cursor>,
+}
+
+impl Page {
+    /// Returns the page of at most `limit` of the ids that follows the cursor, or the first page
+    /// if there is none.
+    ///
+    /// Fails if the cursor is for another key.
+    pub fn of(
+        collection: &Collection,
+        ids: impl IntoIterator<Item = Id>,
+        key: SortKey,
+        after: Option<&Cursor>,
+        limit: usize,
+    ) -> Result<Page, Error> {
+        if let Some(cursor) = after.filter(|cursor| cursor.value.key() != key) {
+            return Err(Error::MismatchedCursor(cursor.value.key()));
+        }
+        let mut places: Vec<(Cursor, Id)> = ids
+            .into_iter()
+            .map(|id| (Cursor::of(collection, key, id), id))
+            .filter(|(place, _)| after.is_none_or(|after| after < place))
+            .collect();
+        places.sort_unstable();
+        let next = if places.len() > limit {
+            places.truncate(limit);
+            places.last().map(|(place, _)| place.clone())
+        } else {
+            None
+        };
+        Ok(Page { ids: places.into_iter().map(|(_, id)| id).collect(), next })
+    }
+}
+
+/// Where a [`Page`] ends and the next begins.
+///
+/// A cursor is written as an opaque string of hexadecimal digits, and serialized as one.
+#[derive(Debug, Clone, PartialEq, Eq, PartialOrd, Ord, Hash)]
+pub struct Cursor {
+    value: SortValue,
+    stable_id: StableId,
+}
+
+impl Cursor {
+    /// Returns the cursor for the place of the entity in the order by the key.
+    pub fn of(collection: &Collection, key: SortKey, id: Id) -> Cursor {
+        let stable_id = collection.entity(id).stable_id();
+        Cursor { value: collection.sort_value(key, id), stable_id }
+    }
+}
+
+impl fmt::Display for Cursor {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        let json = serde_json::to_vec(&(&self.value, self.stable_id)).map_err(|_| fmt::Error)?;
+        json.iter().try_for_each(|byte| write!(f, "{:02x}", byte))
+    }
+}
+
+impl FromStr for Cursor {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Cursor, Error> {
+        let invalid = || Error::InvalidCursor(s.to_string());
+        if !s.len().is_multiple_of(2) || !s.is_ascii() {
+            return Err(invalid());
+        }
+        let json = (0..s.len())
+            .step_by(2)
+            .map(|i| u8::from_str_radix(&s[i..i + 2], 16))
+            .collect::<Result<Vec<u8>, _>>()
+            .map_err(|_| invalid())?;
+        let (value, stable_id) = serde_json::from_slice(&json).map_err(|_| invalid())?;
+        Ok(Cursor { value, stable_id })
+    }
+}
+
+impl Serialize for Cursor {
+    fn serialize<S>(&self, serializer: S) -> Result<S::Ok, S::Error>
+    where
+        S: serde::Serializer,
+    {
+        serializer.collect_str(self)
+    }
+}
+
+impl<'de> Deserialize<'de> for Cursor {
+    fn deserialize<D>(deserializer: D) -> Result<Cursor, D::Error>
+    where
+        D: serde::Deserializer<'de>,
+    {
+        let s = String::deserialize(deserializer)?;
+        s.parse().map_err(serde::de::Error::custom)
+    }
+}
+
+impl FromStr for Query {
+    type Err = Error;
+
+    fn from_str(s: &str) -> Result<Query, Error> {
+        Query::parse(s)
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq)]
+enum Token {
+    And,
+    Or,
+    Not,
+    Open,
+    Close,
+    /// A bare word or quoted string.
+    Word(String),
+    /// A field, a comparison and a value, as in `created>2023-01-01`.
+    Field(String, Comparison, String),
+}
+
+impl fmt::Display for Token {
+    fn fmt(&self, f: &mut fmt::Formatter<'_>) -> fmt::Result {
+        match self {
+            Token::And => f.write_str("AND"),
+            Token::Or => f.write_str("OR"),
+            Token::Not => f.write_str("NOT"),
+            Token::Open => f.write_str("'('"),
+            Token::Close => f.write_str("')'"),
+            Token::Word(word) => write!(f, "{:?}", word),
+            Token::Field(field, comparison, value) => {
+                write!(f, "{}{}{:?}", field, comparison, value)
+            }
+        }
+    }
+}
+
+fn is_delimiter(c: char) -> bool {
+    c.is_whitespace() || c == '(' || c == ')'
+}
+
+/// Reads a quoted string starting at `start`, which must be a `"`.  Backslash escapes the next
+/// character.
+fn lex_string(query: &str, start: usize) -> Result<(String, usize), Error> {
+    let mut ret = String::new();
+    let mut chars = query[start + 1..].char_indices();
+    while let Some((i, c)) = chars.next() {
+        match c {
+            '"' => return Ok((ret, start + 1 + i + 1)),
+            '\\' => match chars.next() {
+                Some((_, c)) => ret.push(c),
+                None => break,
+            },
+            c => ret.push(c),
+        }
+    }
+    Err(Error::UnterminatedString(start))
+}
+
+/// Reads a bare word or quoted string starting at `start`.
+fn lex_value(query: &str, start: usize) -> Result<(String, usize), Error> {
+    if query[start..].starts_with('"') {
+        return lex_string(query, start);
+    }
+    let end = query[start..].find(is_delimiter).map_or(query.len(), |i| start + i);
+    Ok((query[start..end].to_string(), end))
+}
+
+fn lex(query: &str) -> Result<Vec<(Token, usize)>, Error> {
+    let mut ret = Vec::new();
+    let mut position = 0;
+    while let Some(c) = query[position..].chars().next() {
+        let start = position;
+        if c.is_whitespace() {
+            position += c.len_utf8();
+            continue;
+        }
+        if c == '(' || c == ')' {
+            ret.push((if c == '(' { Token::Open } else { Token::Close }, start));
+            position += 1;
+            continue;
+        }
+        if c == '"' {
+            let (word, end) = lex_string(query, start)?;
+            ret.push((Token::Word(word), start));
+            position = end;
+            continue;
+        }
+        let field_end = query[start..]
+            .find(|c: char| !(c.is_ascii_alphanumeric() || c == '_'))
+            .map_or(query.len(), |i| start + i);
+        let rest = &query[field_end..];
+        let comparison = [
+            ("<=", Comparison::Le),
+            (">=", Comparison::Ge),
+            ("<", Comparison::Lt),
+            (">", Comparison::Gt),
+            ("=", Comparison::Eq),
+            (":", Comparison::Eq),
+        ]
+        .into_iter()
+        .find(|(op, _)| rest.starts_with(op));
+        match comparison {
+            Some((op, comparison)) if field_end > start => {
+                let field = query[start..field_end].to_string();
+                let (value, end) = lex_value(query, field_end + op.len())?;
+                ret.push((Token::Field(field, comparison, value), start));
+                position = end;
+            }
+            _ => {
+                let (word, end) = lex_value(query, start)?;
+                let token = match word.as_str() {
+                    "AND" => Token::And,
+                    "OR" => Token::Or,
+                    "NOT" => Token::Not,
+                    _ => Token::Word(word),
+                };
+                ret.push((token, start));
+                position = end;
+            }
+        }
+    }
+    Ok(ret)
+}
+
+/// Parentheses and `NOT`s are nested no deeper than this, as each level of nesting takes a level
+/// of recursion to parse.
+const MAX_DEPTH: usize = 128;
+
+struct Parser {
+    tokens: Vec<(Token, usize)>,
+    position: usize,
+    /// How many parentheses and `NOT`s enclose the current token.
+    depth: usize,
+}
+
+impl Parser {
+    fn peek(&self) -> Option<&Token> {
+        self.tokens.get(self.position).map(|(token, _)| token)
+    }
+
+    fn next(&mut self) -> Result<(Token, usize), Error> {
+        let ret = self.tokens.get(self.position).cloned().ok_or(Error::UnexpectedEnd)?;
+        self.position += 1;
+        Ok(ret)
+    }
+
+    fn or(&mut self) -> Result<Query, Error> {
+        let mut operands = vec![self.and()?];
+        while self.peek() == Some(&Token::Or) {
+            self.position += 1;
+            operands.push(self.and()?);
+        }
+        Ok(balanced(operands, Query::Or))
+    }
+
+    fn and(&mut self) -> Result<Query, Error> {
+        let mut operands = vec![self.unary()?];
+        loop {
+            match self.peek() {
+                Some(Token::And) => self.position += 1,
+                None | Some(Token::Or) | Some(Token::Close) => {
+                    return Ok(balanced(operands, Query::And))
+                }
+                Some(_) => {}
+            }
+            operands.push(self.unary()?);
+        }
+    }
+
+    /// Parses what follows an opening parenthesis or a `NOT` at the offset, one level deeper.
+    fn nested<T>(
+        &mut self,
+        offset: usize,
+        parse: impl FnOnce(&mut Parser) -> Result<T, Error>,
+    ) -> Result<T, Error> {
+        if self.depth == MAX_DEPTH {
+            return Err(Error::TooDeep(offset));
+        }
+        self.depth += 1;
+        let ret = parse(self);
+        self.depth -= 1;
+        ret
+    }
+
+    fn unary(&mut self) -> Result<Query, Error> {
+        match self.next()? {
+            (Token::Not, offset) => {
+                self.nested(offset, |parser| Ok(Query::Not(Box::new(parser.unary()?))))
+            }
+            (Token::Open, offset) => {
+                let ret = self.nested(offset, Parser::or)?;
+                match self.next()? {
+                    (Token::Close, _) => Ok(ret),
+                    (token, offset) => Err(Error::UnexpectedToken(token.to_string(), offset)),
+                }
+            }
+            (Token::Word(word), _) => Ok(Query::Term(Term::Text(word.to_lowercase()))),
+            (Token::Field(field, comparison, value), _) => {
+                term(field, comparison, value).map(Query::Term)
+            }
+            (token, offset) => Err(Error::UnexpectedToken(token.to_string(), offset)),
+        }
+    }
+}
+
+/// Joins the operands of a run of `AND`s or `OR`s, grouping them so that the query is only as deep
+/// as the logarithm of their number, rather than their number, as long runs would otherwise
+/// overflow the stack when matched or dropped.  Runs of two or three operands group to the left.
+fn balanced(mut operands: Vec<Query>, join: fn(Box<Query>, Box<Query>) -> Query) -> Query {
+    if operands.len() == 1 {
+        return operands.pop().expect("operand");
+    }
+    let rhs = operands.split_off(operands.len().div_ceil(2));
+    join(Box::new(balanced(operands, join)), Box::new(balanced(rhs, join)))
+}
+
+fn term(field: String, comparison: Comparison, value: String) -> Result<Term, Error> {
+    let date = |value: &str| {
+        let time = Time::parse(value).map_err(|_| Error::InvalidDate(value.to_string()))?;
+        Ok(OffsetDateTime::from(time).date())
+    };
+    match field.as_str() {
+        "created" => Ok(Term::Created(comparison, date(&value)?)),
+        "updated" => Ok(Term::Updated(comparison, date(&value)?)),
+        "label" | "name" | "url" | "is" if comparison != Comparison::Eq => {
+            Err(Error::InvalidComparison(field, comparison))
+        }
+        "label" => Ok(Term::Label(Label::from(value))),
+        "name" => Ok(Term::Name(value.to_lowercase())),
+        "url" => Ok(Term::Url(value.to_lowercase())),
+        "is" => match value.as_str() {
+            "starred" => Ok(Term::Is(Flag::Starred)),
+            "unread" => Ok(Term::Is(Flag::Unread)),
+            "read" => Ok(Term::Is(Flag::Read)),
+            "archived" => Ok(Term::Is(Flag::Archived)),
+            _ => Err(Error::UnknownFlag(value)),
+        },
+        _ => Err(Error::UnknownField(field)),
+    }
+}
@@ -0,0 +1,188 @@
+use std::collections::BTreeSet;
+
+use time::macros::{date, datetime};
+use url::Url;
+
+use super::*;
+use crate::collection::Name;
+
+fn test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let mut insert =
+        |url: &str, created_at: OffsetDateTime, name: Option<&str>, labels: &[&str]| {
+            collection.insert(Entity::new(
+                Url::parse(url).unwrap(),
+                created_at.into(),
+                name.map(Name::from),
+                labels.iter().copied().map(Label::from).collect(),
+            ))
+        };
+    insert("https://tokio.rs/", datetime!(2023-06-01 0:00 UTC), Some("Tokio"), &["rust/async"]);
+    insert("https://serde.rs/", datetime!(2023-02-01 0:00 UTC), Some("Serde"), &["rust"]);
+    insert("https://go.dev/", datetime!(2023-06-01 0:00 UTC), Some("Go"), &["go"]);
+    insert("https://old.rs/", datetime!(2022-06-01 0:00 UTC), None, &["rust", "async"]);
+    collection
+}
+
+fn select(query: &str) -> Vec<usize> {
+    let collection = test_collection();
+    Query::parse(query).unwrap().select(&collection).map(usize::from).collect()
+}
+
+#[test]
+fn test_parse() {
+    let query = Query::parse(r#"label:rust AND (label:async OR name:"tokio") created>2023-01-01"#);
+    let term = |term| Box::new(Query::Term(term));
+    let expected = Query::And(
+        Box::new(Query::And(
+            term(Term::Label(Label::from("rust"))),
+            Box::new(Query::Or(
+                term(Term::Label(Label::from("async"))),
+                term(Term::Name(String::from("tokio"))),
+            )),
+        )),
+        term(Term::Created(Comparison::Gt, date!(2023 - 01 - 01))),
+    );
+    assert_eq!(query, Ok(expected));
+}
+
+#[test]
+fn test_precedence() {
+    let query = Query::parse("a OR b c").unwrap();
+    let term = |text: &str| Box::new(Query::Term(Term::Text(String::from(text))));
+    assert_eq!(query, Query::Or(term("a"), Box::new(Query::And(term("b"), term("c")))));
+    let query = Query::parse("NOT a b").unwrap();
+    assert_eq!(query, Query::And(Box::new(Query::Not(term("a"))), term("b")));
+}
+
+#[test]
+fn test_select() {
+    assert_eq!(
+        select(r#"label:rust AND (label:async OR name:"tokio") AND created>2023-01-01"#),
+        vec![0]
+    );
+    assert_eq!(select("label:rust"), vec![0, 1, 3]);
+    assert_eq!(select("label:rust NOT label:rust/async"), vec![1, 3]);
+    assert_eq!(select("created>=2023-06-01"), vec![0, 2]);
+    assert_eq!(select("created:2023-02-01"), vec![1]);
+    assert_eq!(select("url:.RS created<2023-01-01"), vec![3]);
+    assert_eq!(select("\"go\" OR serde"), vec![1, 2]);
+    assert_eq!(select("is:unread is:starred"), Vec::<usize>::new());
+}
+
+#[test]
+fn test_errors() {
+    assert_eq!(Query::parse(""), Err(Error::UnexpectedEnd));
+    assert_eq!(Query::parse("label:rust AND"), Err(Error::UnexpectedEnd));
+    assert_eq!(Query::parse("(a b"), Err(Error::UnexpectedEnd));
+    assert_eq!(Query::parse("a )"), Err(Error::UnexpectedToken(String::from("')'"), 2)));
+    assert_eq!(Query::parse("OR a"), Err(Error::UnexpectedToken(String::from("OR"), 0)));
+    assert_eq!(Query::parse("name:\"tok"), Err(Error::UnterminatedString(5)));
+    assert_eq!(Query::parse("colour:red"), Err(Error::UnknownField(String::from("colour"))));
+    assert_eq!(
+        Query::parse("label>rust"),
+        Err(Error::InvalidComparison(String::from("label"), Comparison::Gt))
+    );
+    assert_eq!(Query::parse("created>soon"), Err(Error::InvalidDate(String::from("soon"))));
+    assert_eq!(Query::parse("is:pinned"), Err(Error::UnknownFlag(String::from("pinned"))));
+}
+
+#[test]
+fn test_updated() {
+    let mut collection = test_collection();
+    let serde = collection.id(&Url::parse("https://serde.rs/").unwrap()).unwrap();
+    collection.update(
+        serde,
+        datetime!(2024-01-01 0:00 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::from([Label::from("serde")]),
+    );
+    let query = Query::parse("updated>=2024-01-01").unwrap();
+    assert_eq!(query.select(&collection).collect::<Vec<_>>(), vec![serde]);
+    let query = Query::parse("updated<2023-01-01").unwrap();
+    assert_eq!(query.select(&collection).map(usize::from).collect::<Vec<_>>(), vec![3]);
+}
+
+#[test]
+fn test_page() {
+    let mut collection = test_collection();
+    let url = |id: Id| collection.entity(id).url().as_str().to_string();
+    let query = Query::parse("label:rust").unwrap();
+    let page = query.page(&collection, SortKey::CreatedAt, None, 2).unwrap();
+    assert_eq!(
+        page.ids.iter().map(|&id| url(id)).collect::<Vec<_>>(),
+        ["https://old.rs/", "https://serde.rs/"]
+    );
+    let cursor = page.next.unwrap();
+    assert_eq!(cursor, Cursor::of(&collection, SortKey::CreatedAt, page.ids[1]));
+    assert_eq!(cursor.to_string().parse(), Ok(cursor.clone()));
+
+    // Entities added before the cursor, or removed, do not shift the next page
+    collection.remove_by_url(&Url::parse("https://old.rs/").unwrap());
+    collection.insert(Entity::new(
+        Url::parse("https://older.rs/").unwrap(),
+        datetime!(2021-01-01 0:00 UTC).into(),
+        None,
+        BTreeSet::from([Label::from("rust")]),
+    ));
+    let page = query.page(&collection, SortKey::CreatedAt, Some(&cursor), 2).unwrap();
+    let url = |id: Id| collection.entity(id).url().as_str().to_string();
+    assert_eq!(page.ids.iter().map(|&id| url(id)).collect::<Vec<_>>(), ["https://tokio.rs/"]);
+    assert_eq!(page.next, None);
+
+    // Nor does removing the entity at the cursor
+    collection.remove_by_url(&Url::parse("https://serde.rs/").unwrap());
+    let page = query.page(&collection, SortKey::CreatedAt, Some(&cursor), 2).unwrap();
+    let url = |id: Id| collection.entity(id).url().as_str().to_string();
+    assert_eq!(page.ids.iter().map(|&id| url(id)).collect::<Vec<_>>(), ["https://tokio.rs/"]);
+
+    let page = query.page(&collection, SortKey::Url, Some(&cursor), 2);
+    assert_eq!(page, Err(Error::MismatchedCursor(SortKey::CreatedAt)));
+    assert_eq!("nowhere".parse::<Cursor>(), Err(Error::InvalidCursor("nowhere".to_string())));
+    assert!("0000".parse::<Cursor>().is_err());
+}
+
+#[test]
+fn test_page_moved() {
+    let mut collection = test_collection();
+    let query = Query::parse("label:rust").unwrap();
+    let first = query.page(&collection, SortKey::LastUpdated, None, 1).unwrap();
+    let cursor = first.next.unwrap();
+    // The entity at the cursor moves to the end, and the next page still starts where the first
+    // one ended, so it comes up again there
+    collection.update(
+        first.ids[0],
+        datetime!(2025-01-01 0:00 UTC).into(),
+        BTreeSet::new(),
+        BTreeSet::from([Label::from("rust")]),
+    );
+    let rest = query.page(&collection, SortKey::LastUpdated, Some(&cursor), 10).unwrap();
+    assert_eq!(rest.ids.len(), 3);
+    assert_eq!(rest.ids.last(), first.ids.first());
+}
+
+#[test]
+fn test_page_ties() {
+    let collection = test_collection();
+    let query = Query::parse("created:2023-06-01").unwrap();
+    let first = query.page(&collection, SortKey::CreatedAt, None, 1).unwrap();
+    let second = query.page(&collection, SortKey::CreatedAt, first.next.as_ref(), 1).unwrap();
+    assert_eq!(second.next, None);
+    let mut ids = [first.ids, second.ids].concat();
+    ids.sort();
+    assert_eq!(ids.into_iter().map(usize::from).collect::<Vec<_>>(), [0, 2]);
+}
+
+#[test]
+fn test_parse_depth() {
+    let nested = |depth: usize| format!("{}rust{}", "(".repeat(depth), ")".repeat(depth));
+    assert!(Query::parse(&nested(128)).is_ok());
+    assert_eq!(Query::parse(&nested(3000)), Err(Error::TooDeep(128)));
+    assert!(Query::parse(&format!("{}rust", "NOT ".repeat(128))).is_ok());
+    assert_eq!(Query::parse(&format!("{}rust", "NOT ".repeat(129))), Err(Error::TooDeep(512)));
+
+    // Long runs of terms are not nested
+    let collection = test_collection();
+    let query = Query::parse(&format!("{}go", "rust OR ".repeat(100_000))).unwrap();
+    assert_eq!(query.select(&collection).count(), 4);
+}
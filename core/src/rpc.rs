@@ -0,0 +1,173 @@
+//! Speaking [JSON-RPC 2.0](https://www.jsonrpc.org/specification) over a pair of streams, so that
+//! another program can keep a collection loaded in a child process.
+//!
+//! Each message is one line of JSON.  The methods are:
+//!
+//! - `list`, given an optional `label`, lists the entities with the label or one nested under it.
+//! - `query`, given a `query` in the language of [`crate::query`], lists the entities matching it.
+//! - `add`, given an entity, merges it into the collection and returns its id.
+//! - `merge`, given an array of entities, merges them into the collection and returns what
+//!   changed.
+//!
+//! `list` and `query` take an `offset` and a `limit`.  Params are given by name.  Entities are
+//! given as objects with a `url` and optionally a `name`, `labels`, `createdAt`, `toread` and
+//! `shared`.
+
+#[cfg(test)]
+mod tests;
+
+use std::io::{self, BufRead, Write};
+
+use serde::Deserialize;
+use serde_json::{json, Value};
+
+use crate::{
+    api::{self, NewEntity},
+    collection::{Collection, Label, Union},
+    query::Query,
+};
+
+/// The source recorded on updates made by `add` and `merge`.
+pub const SOURCE: &str = "rpc";
+
+const PARSE_ERROR: i64 = -32700;
+const INVALID_REQUEST: i64 = -32600;
+const METHOD_NOT_FOUND: i64 = -32601;
+const INVALID_PARAMS: i64 = -32602;
+
+#[derive(Debug, Deserialize)]
+struct Request {
+    jsonrpc: String,
+    method: String,
+    #[serde(default)]
+    params: Value,
+    /// Absent from notifications, which get no response.
+    id: Option<Value>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct ListParams {
+    label: Option<String>,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+#[derive(Debug, Deserialize)]
+#[serde(deny_unknown_fields)]
+struct QueryParams {
+    query: String,
+    offset: Option<usize>,
+    limit: Option<usize>,
+}
+
+/// An error to send back, as its code and message.
+type Failure = (i64, String);
+
+fn params<T: for<'de> Deserialize<'de>>(params: Value) -> Result<T, Failure> {
+    // Methods whose params are all optional may be called without any
+    let params = if params.is_null() { json!({}) } else { params };
+    serde_json::from_value(params).map_err(|err| (INVALID_PARAMS, err.to_string()))
+}
+
+fn invalid(err: impl ToString) -> Failure {
+    (INVALID_PARAMS, err.to_string())
+}
+
+/// Calls the method, returning its result and whether the collection was changed.
+fn call(collection: &mut Collection, method: &str, value: Value) -> Result<(Value, bool), Failure> {
+    match method {
+        "list" => {
+            let ListParams { label, offset, limit } = params(value)?;
+            let ids = api::select(collection, label.map(Label::from), None);
+            Ok((json!(api::entries(collection, ids, offset, limit)), false))
+        }
+        "query" => {
+            let QueryParams { query, offset, limit } = params(value)?;
+            let query = Query::parse(&query).map_err(invalid)?;
+            let ids = api::select(collection, None, Some(&query));
+            Ok((json!(api::entries(collection, ids, offset, limit)), false))
+        }
+        "add" => {
+            let entity = params::<NewEntity>(value)?.into_entity().map_err(invalid)?;
+            let url = entity.url().clone();
+            let imported =
+                collection.merge_all_from([entity], Some(SOURCE.to_string()), &mut Union);
+            let id = collection.id(&url).expect("just merged");
+            Ok((json!({ "id": id, "added": imported.added == 1 }), true))
+        }
+        "merge" => {
+            let entities: Vec<NewEntity> = params(value)?;
+            let entities: Vec<_> = entities
+                .into_iter()
+                .map(NewEntity::into_entity)
+                .collect::<Result<_, _>>()
+                .map_err(invalid)?;
+            let imported =
+                collection.merge_all_from(entities, Some(SOURCE.to_string()), &mut Union);
+            let result = json!({
+                "entities": imported.entities,
+                "added": imported.added,
+                "merged": imported.merged,
+            });
+            Ok((result, true))
+        }
+        _ => Err((METHOD_NOT_FOUND, format!("method not found: {}", method))),
+    }
+}
+
+/// Handles one message, returning the response to send, if any, and whether the collection was
+/// changed.
+pub fn handle(collection: &mut Collection, message: &str) -> (Option<Value>, bool) {
+    let request: Request = match serde_json::from_str::<Value>(message) {
+        Err(err) => return (Some(error(Value::Null, PARSE_ERROR, err.to_string())), false),
+        Ok(value) => match serde_json::from_value(value) {
+            Ok(request) => request,
+            Err(err) => {
+                return (Some(error(Value::Null, INVALID_REQUEST, err.to_string())), false);
+            }
+        },
+    };
+    let id = request.id.unwrap_or(Value::Null);
+    if request.jsonrpc != "2.0" {
+        let message = "unsupported version".to_string();
+        return (Some(error(id, INVALID_REQUEST, message)), false);
+    }
+    let notification = id.is_null();
+    let (response, changed) = match call(collection, &request.method, request.params) {
+        Ok((result, changed)) => (json!({ "jsonrpc": "2.0", "id": id, "result": result }), changed),
+        Err((code, message)) => (error(id, code, message), false),
+    };
+    (Some(response).filter(|_| !notification), changed)
+}
+
+fn error(id: Value, code: i64, message: String) -> Value {
+    json!({ "jsonrpc": "2.0", "id": id, "error": { "code": code, "message": message } })
+}
+
+/// Answers the messages read from `reader` on `writer` until `reader` ends, calling `changed`
+/// after each message that changes the collection.
+///
+/// Blank lines are skipped.  An error from `changed` stops the loop.
+pub fn run(
+    reader: impl BufRead,
+    mut writer: impl Write,
+    collection: &mut Collection,
+    mut changed: impl FnMut(&Collection) -> io::Result<()>,
+) -> io::Result<()> {
+    for line in reader.lines() {
+        let line = line?;
+        if line.trim().is_empty() {
+            continue;
+        }
+        let (response, was_changed) = handle(collection, &line);
+        if was_changed {
+            changed(collection)?;
+        }
+        if let Some(response) = response {
+            writeln!(writer, "{}", response)?;
+            writer.flush()?;
+        }
+    }
+    Ok(())
+}
@@ -0,0 +1,120 @@
+use url::Url;
+
+use super::*;
+use crate::api::tests::create_test_collection;
+
+fn result(collection: &mut Collection, message: &str) -> Value {
+    let (response, _) = handle(collection, message);
+    let response = response.unwrap();
+    assert_eq!(response["jsonrpc"], "2.0");
+    assert_eq!(response["id"], 1);
+    assert!(response.get("error").is_none(), "{}", response);
+    response["result"].clone()
+}
+
+fn error_code(collection: &mut Collection, message: &str) -> Value {
+    let (response, changed) = handle(collection, message);
+    assert!(!changed);
+    response.unwrap()["error"]["code"].clone()
+}
+
+fn urls(result: &Value) -> Vec<&str> {
+    let entries = result.as_array().unwrap();
+    entries.iter().map(|entry| entry["entity"]["uri"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_list() {
+    let mut collection = create_test_collection();
+    let all = result(&mut collection, r#"{"jsonrpc": "2.0", "id": 1, "method": "list"}"#);
+    assert_eq!(urls(&all), ["https://rust-lang.org/", "https://tokio.rs/", "https://go.dev/"]);
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "list",
+                      "params": {"label": "lang/rust", "offset": 1}}"#;
+    let page = result(&mut collection, message);
+    assert_eq!(urls(&page), ["https://tokio.rs/"]);
+    assert_eq!(page[0]["id"], 1);
+}
+
+#[test]
+fn test_query() {
+    let mut collection = create_test_collection();
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "query",
+                      "params": {"query": "label:lang AND created>2023-11-15", "limit": 1}}"#;
+    assert_eq!(urls(&result(&mut collection, message)), ["https://tokio.rs/"]);
+    let message =
+        r#"{"jsonrpc": "2.0", "id": 1, "method": "query", "params": {"query": "(label:go"}}"#;
+    assert_eq!(error_code(&mut collection, message), INVALID_PARAMS);
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "query"}"#;
+    assert_eq!(error_code(&mut collection, message), INVALID_PARAMS);
+}
+
+#[test]
+fn test_add() {
+    let mut collection = create_test_collection();
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "add",
+                      "params": {"url": "https://zig.dev/", "name": "Zig", "createdAt": 1700000000}}"#;
+    let (response, changed) = handle(&mut collection, message);
+    assert!(changed);
+    assert_eq!(response.unwrap()["result"], json!({ "id": 3, "added": true }));
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "add",
+                      "params": {"url": "https://go.dev/", "labels": ["google"]}}"#;
+    assert_eq!(result(&mut collection, message), json!({ "id": 2, "added": false }));
+    let id = collection.id(&Url::parse("https://go.dev/").unwrap()).unwrap();
+    let labels: Vec<&str> = collection.entity(id).labels().iter().map(Label::as_str).collect();
+    assert_eq!(labels, ["google", "lang/go"]);
+
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "add", "params": {"url": "not a url"}}"#;
+    assert_eq!(error_code(&mut collection, message), INVALID_PARAMS);
+    assert_eq!(collection.len(), 4);
+}
+
+#[test]
+fn test_merge() {
+    let mut collection = create_test_collection();
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "merge", "params": [
+        {"url": "https://rust-lang.org/", "name": "Rust Website"},
+        {"url": "https://zig.dev/", "toread": true}
+    ]}"#;
+    let expected = json!({ "entities": 2, "added": 1, "merged": 1 });
+    assert_eq!(result(&mut collection, message), expected);
+    assert_eq!(collection.len(), 4);
+}
+
+#[test]
+fn test_errors() {
+    let mut collection = create_test_collection();
+    assert_eq!(error_code(&mut collection, "{"), PARSE_ERROR);
+    assert_eq!(error_code(&mut collection, r#"{"id": 1}"#), INVALID_REQUEST);
+    let message = r#"{"jsonrpc": "1.0", "id": 1, "method": "list"}"#;
+    assert_eq!(error_code(&mut collection, message), INVALID_REQUEST);
+    let message = r#"{"jsonrpc": "2.0", "id": 1, "method": "remove"}"#;
+    assert_eq!(error_code(&mut collection, message), METHOD_NOT_FOUND);
+}
+
+#[test]
+fn test_run() {
+    let mut collection = create_test_collection();
+    let input = r#"{"jsonrpc": "2.0", "id": 1, "method": "list", "params": {"label": "lang/go"}}
+
+{"jsonrpc": "2.0", "method": "add", "params": {"url": "https://zig.dev/"}}
+{"jsonrpc": "2.0", "id": "last", "method": "nothing"}
+"#;
+    let mut output = Vec::new();
+    let mut saved = Vec::new();
+    run(input.as_bytes(), &mut output, &mut collection, |collection| {
+        saved.push(collection.len());
+        Ok(())
+    })
+    .unwrap();
+    assert_eq!(saved, [4]);
+    let lines: Vec<Value> = String::from_utf8(output)
+        .unwrap()
+        .lines()
+        .map(|line| serde_json::from_str(line).unwrap())
+        .collect();
+    // The notification gets no response
+    assert_eq!(lines.len(), 2);
+    assert_eq!(urls(&lines[0]["result"]), ["https://go.dev/"]);
+    assert_eq!(lines[1]["id"], "last");
+    assert_eq!(lines[1]["error"]["code"], METHOD_NOT_FOUND);
+}
@@ -0,0 +1,357 @@
+//! Serving a collection over HTTP as a small JSON API.
+//!
+//! - `GET /entities` lists entities, optionally only those with a `label` or one nested under it,
+//!   or those matching a `query` in the language of [`crate::query`].
+//! - `POST /entities` merges an entity, or an array of them, into the collection.
+//! - `GET /search?q=TEXT` lists the entities matching `TEXT`, as [`Collection::search`] does, or
+//!   as [`Collection::search_ranked`] or [`Collection::search_fuzzy`] do given `mode=ranked` or
+//!   `mode=fuzzy`.
+//! - `GET /labels` counts the entities carrying each label.
+//! - `GET /page` pages through the entities, or those matching a `query`, ordered by `sort`, one
+//!   of `created`, `updated`, `name`, `url` or `degree`, as [`Page`] does.  It takes a `limit`,
+//!   defaulting to [`PAGE_LIMIT`], and the `after` cursor from the previous page, and returns the
+//!   `entities` along with the `next` cursor.
+//!
+//! Other lists take an `offset` and a `limit`.  Requests are handled one at a time, each on its own
+//! connection, so the collection needs no locking.  A client therefore gets a bounded time to send
+//! its request, and bounded room for its request line and headers, so that a slow or oversized
+//! request cannot hold up the others for long.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    io::{self, BufRead, BufReader, Read, Write},
+    net::{TcpListener, TcpStream},
+    time::{Duration, Instant},
+};
+
+use serde_json::json;
+
+use crate::{
+    api::{self, NewEntities},
+    collection::{Collection, Id, Label, SortKey, Union},
+    query::{Cursor, Page, Query},
+};
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// Requests whose request line and headers take more bytes are refused.
+    pub max_header: usize,
+    /// Requests with a larger body are refused.
+    pub max_body: usize,
+    /// How long a client has to send its whole request, and to read the response.
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            max_header: 16 * 1024,
+            max_body: 16 * 1024 * 1024,
+            timeout: Duration::from_secs(10),
+        }
+    }
+}
+
+/// The source recorded on updates made by `POST /entities`.
+pub const SOURCE: &str = "serve";
+
+/// The number of entities on a page from `GET /page` when no `limit` is given.
+pub const PAGE_LIMIT: usize = 100;
+
+/// A request, as read off a connection.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Request {
+    pub method: String,
+    pub path: String,
+    /// The decoded query parameters, in order.
+    pub params: Vec<(String, String)>,
+    pub body: Vec<u8>,
+}
+
+impl Request {
+    /// Returns a request for the target, as in `/entities?label=rust`.
+    pub fn new(method: &str, target: &str, body: impl Into<Vec<u8>>) -> Request {
+        let (path, query) = target.split_once('?').unwrap_or((target, ""));
+        let params = url::form_urlencoded::parse(query.as_bytes()).into_owned().collect();
+        Request { method: method.to_string(), path: path.to_string(), params, body: body.into() }
+    }
+
+    fn param(&self, name: &str) -> Option<&str> {
+        self.params.iter().find(|(key, _)| key == name).map(|(_, value)| value.as_str())
+    }
+
+    /// Reads a request off a connection, refusing it unread if its head is larger than
+    /// `max_header` or its body larger than `max_body`.
+    fn read(reader: &mut impl BufRead, options: &Options) -> io::Result<Incoming> {
+        let mut head = reader.take(options.max_header as u64);
+        let mut line = String::new();
+        if !read_line(&mut head, &mut line)? {
+            return Ok(Incoming::Refused(Response::error(431, "request header too large")));
+        }
+        if line.is_empty() {
+            return Ok(Incoming::Nothing);
+        }
+        let mut parts = line.split_whitespace();
+        let (Some(method), Some(target)) = (parts.next(), parts.next()) else {
+            return Ok(Incoming::Refused(Response::error(400, "bad request line")));
+        };
+        let mut request = Request::new(method, target, Vec::new());
+        let mut length = 0;
+        let mut header = String::new();
+        loop {
+            if !read_line(&mut head, &mut header)? {
+                return Ok(Incoming::Refused(Response::error(431, "request header too large")));
+            }
+            if header.trim().is_empty() {
+                break;
+            }
+            if let Some((name, value)) = header.split_once(':') {
+                if name.trim().eq_ignore_ascii_case("content-length") {
+                    let Ok(value) = value.trim().parse() else {
+                        return Ok(Incoming::Refused(Response::error(400, "bad Content-Length")));
+                    };
+                    length = value;
+                }
+            }
+        }
+        if length > options.max_body {
+            return Ok(Incoming::Refused(Response::error(413, "request body too large")));
+        }
+        request.body = vec![0; length];
+        head.into_inner().read_exact(&mut request.body)?;
+        Ok(Incoming::Request(request))
+    }
+}
+
+/// Reads a line of a request's head into `line`, returning whether it fit in what remains of the
+/// room for the head.  At the end of the input, `line` is left empty.
+fn read_line(head: &mut io::Take<impl BufRead>, line: &mut String) -> io::Result<bool> {
+    line.clear();
+    head.read_line(line)?;
+    Ok(line.ends_with('\n') || head.limit() > 0)
+}
+
+/// What a client sent on a connection.
+#[derive(Debug)]
+enum Incoming {
+    /// The client sent nothing.
+    Nothing,
+    Request(Request),
+    /// The request was refused before it was read in full, with the response to send instead.
+    Refused(Response),
+}
+
+/// A connection that gives up reading once its deadline has passed, however the client spaces out
+/// what it sends.
+struct Deadline<'a> {
+    stream: &'a TcpStream,
+    at: Instant,
+}
+
+impl Read for Deadline<'_> {
+    fn read(&mut self, buf: &mut [u8]) -> io::Result<usize> {
+        let left = self.at.saturating_duration_since(Instant::now());
+        if left.is_zero() {
+            return Err(io::Error::new(io::ErrorKind::TimedOut, "request took too long"));
+        }
+        self.stream.set_read_timeout(Some(left))?;
+        (&mut &*self.stream).read(buf)
+    }
+}
+
+/// A JSON response.
+#[derive(Debug, Clone, PartialEq)]
+pub struct Response {
+    pub status: u16,
+    pub body: serde_json::Value,
+}
+
+impl Response {
+    fn ok(body: serde_json::Value) -> Response {
+        Response { status: 200, body }
+    }
+
+    fn error(status: u16, message: impl ToString) -> Response {
+        Response { status, body: json!({ "error": message.to_string() }) }
+    }
+
+    fn reason(&self) -> &'static str {
+        match self.status {
+            200 => "OK",
+            400 => "Bad Request",
+            404 => "Not Found",
+            405 => "Method Not Allowed",
+            413 => "Content Too Large",
+            431 => "Request Header Fields Too Large",
+            _ => "Internal Server Error",
+        }
+    }
+
+    fn write(&self, writer: &mut impl Write) -> io::Result<()> {
+        let body = self.body.to_string();
+        write!(
+            writer,
+            "HTTP/1.1 {} {}\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+             Connection: close\r\n\r\n{}",
+            self.status,
+            self.reason(),
+            body.len(),
+            body
+        )?;
+        writer.flush()
+    }
+}
+
+/// Returns the entities with the ids, after skipping `offset` of them and keeping at most `limit`,
+/// as those parameters ask.
+fn page(collection: &Collection, request: &Request, ids: impl IntoIterator<Item = Id>) -> Response {
+    let number = |name| request.param(name).map(str::parse::<usize>).transpose();
+    let (Ok(offset), Ok(limit)) = (number("offset"), number("limit")) else {
+        return Response::error(400, "offset and limit must be numbers");
+    };
+    Response::ok(json!(api::entries(collection, ids, offset, limit)))
+}
+
+fn list(collection: &Collection, request: &Request) -> Response {
+    let query = match request.param("query").map(Query::parse).transpose() {
+        Ok(query) => query,
+        Err(err) => return Response::error(400, err),
+    };
+    let label = request.param("label").map(Label::from);
+    page(collection, request, api::select(collection, label, query.as_ref()))
+}
+
+fn sort_key(name: &str) -> Option<SortKey> {
+    match name {
+        "created" => Some(SortKey::CreatedAt),
+        "updated" => Some(SortKey::LastUpdated),
+        "name" => Some(SortKey::Name),
+        "url" => Some(SortKey::Url),
+        "degree" => Some(SortKey::Degree),
+        _ => None,
+    }
+}
+
+fn pages(collection: &Collection, request: &Request) -> Response {
+    let query = match request.param("query").map(Query::parse).transpose() {
+        Ok(query) => query,
+        Err(err) => return Response::error(400, err),
+    };
+    let key = match request.param("sort").map(|name| (name, sort_key(name))) {
+        None => SortKey::CreatedAt,
+        Some((_, Some(key))) => key,
+        Some((name, None)) => return Response::error(400, format!("unknown sort: {}", name)),
+    };
+    let after = match request.param("after").map(str::parse::<Cursor>).transpose() {
+        Ok(after) => after,
+        Err(err) => return Response::error(400, err),
+    };
+    let Ok(limit) = request.param("limit").map(str::parse::<usize>).transpose() else {
+        return Response::error(400, "limit must be a number");
+    };
+    let ids = api::select(collection, None, query.as_ref());
+    match Page::of(collection, ids, key, after.as_ref(), limit.unwrap_or(PAGE_LIMIT)) {
+        Ok(page) => Response::ok(json!({
+            "entities": api::entries(collection, page.ids, None, None),
+            "next": page.next,
+        })),
+        Err(err) => Response::error(400, err),
+    }
+}
+
+fn add(collection: &mut Collection, request: &Request) -> Response {
+    let entities = match serde_json::from_slice::<NewEntities>(&request.body) {
+        Ok(entities) => entities.into_entities(),
+        Err(err) => return Response::error(400, err),
+    };
+    let entities = match entities {
+        Ok(entities) => entities,
+        Err(err) => return Response::error(400, err),
+    };
+    let imported = collection.merge_all_from(entities, Some(SOURCE.to_string()), &mut Union);
+    Response::ok(json!({
+        "entities": imported.entities,
+        "added": imported.added,
+        "merged": imported.merged,
+    }))
+}
+
+fn search(collection: &Collection, request: &Request) -> Response {
+    let Some(text) = request.param("q") else {
+        return Response::error(400, "missing parameter: q");
+    };
+    let ids: Vec<Id> = match request.param("mode") {
+        None | Some("substring") => collection.search(text).collect(),
+        Some("ranked") => collection.search_ranked(text).into_iter().map(|(id, _)| id).collect(),
+        Some("fuzzy") => collection.search_fuzzy(text).into_iter().map(|(id, _)| id).collect(),
+        Some(mode) => return Response::error(400, format!("unknown mode: {}", mode)),
+    };
+    page(collection, request, ids)
+}
+
+fn labels(collection: &Collection) -> Response {
+    let mut labels: BTreeMap<&str, usize> = BTreeMap::new();
+    for (_, entity) in collection {
+        for label in entity.labels() {
+            *labels.entry(label.as_str()).or_default() += 1;
+        }
+    }
+    Response::ok(json!(labels))
+}
+
+/// Handles a request, returning the response and whether the collection was changed.
+pub fn handle(collection: &mut Collection, request: &Request) -> (Response, bool) {
+    match (request.method.as_str(), request.path.trim_end_matches('/')) {
+        ("GET", "/entities") => (list(collection, request), false),
+        ("POST", "/entities") => {
+            let response = add(collection, request);
+            let changed = response.status == 200;
+            (response, changed)
+        }
+        ("GET", "/search") => (search(collection, request), false),
+        ("GET", "/labels") => (labels(collection), false),
+        ("GET", "/page") => (pages(collection, request), false),
+        (_, "/entities" | "/search" | "/labels" | "/page") => {
+            (Response::error(405, "method not allowed"), false)
+        }
+        _ => (Response::error(404, "not found"), false),
+    }
+}
+
+fn connection(
+    stream: TcpStream,
+    collection: &mut Collection,
+    options: &Options,
+) -> io::Result<bool> {
+    stream.set_write_timeout(Some(options.timeout))?;
+    let deadline = Deadline { stream: &stream, at: Instant::now() + options.timeout };
+    let mut reader = BufReader::new(deadline);
+    let (response, changed) = match Request::read(&mut reader, options)? {
+        Incoming::Nothing => return Ok(false),
+        Incoming::Refused(response) => (response, false),
+        Incoming::Request(request) => handle(collection, &request),
+    };
+    response.write(&mut &stream)?;
+    Ok(changed)
+}
+
+/// Serves the collection on the listener until accepting a connection fails, calling `changed`
+/// after each request that changes the collection.
+///
+/// A connection that fails is dropped.  An error from `changed` stops the server.
+pub fn serve(
+    listener: &TcpListener,
+    collection: &mut Collection,
+    options: &Options,
+    mut changed: impl FnMut(&Collection) -> io::Result<()>,
+) -> io::Result<()> {
+    for stream in listener.incoming() {
+        if let Ok(true) = connection(stream?, collection, options) {
+            changed(collection)?;
+        }
+    }
+    Ok(())
+}
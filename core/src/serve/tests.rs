@@ -0,0 +1,221 @@
+use std::{io::Read, thread};
+
+use time::macros::datetime;
+use url::Url;
+
+use super::*;
+use crate::{
+    api::tests::create_test_collection,
+    collection::{Name, Time},
+};
+
+fn get(collection: &mut Collection, target: &str) -> Response {
+    let (response, changed) = handle(collection, &Request::new("GET", target, Vec::new()));
+    assert!(!changed);
+    response
+}
+
+fn urls(response: &Response) -> Vec<&str> {
+    assert_eq!(response.status, 200, "{}", response.body);
+    let entries = response.body.as_array().unwrap();
+    entries.iter().map(|entry| entry["entity"]["uri"].as_str().unwrap()).collect()
+}
+
+#[test]
+fn test_list() {
+    let mut collection = create_test_collection();
+    let all = ["https://rust-lang.org/", "https://tokio.rs/", "https://go.dev/"];
+    assert_eq!(urls(&get(&mut collection, "/entities")), all);
+    assert_eq!(urls(&get(&mut collection, "/entities/")), all);
+    let response = get(&mut collection, "/entities?label=lang%2Frust");
+    assert_eq!(urls(&response), ["https://rust-lang.org/", "https://tokio.rs/"]);
+    let response = get(&mut collection, "/entities?query=label:lang+AND+created%3E2023-11-15");
+    assert_eq!(urls(&response), ["https://tokio.rs/", "https://go.dev/"]);
+    let response = get(&mut collection, "/entities?offset=1&limit=1");
+    assert_eq!(urls(&response), ["https://tokio.rs/"]);
+    assert_eq!(response.body[0]["id"], 1);
+
+    assert_eq!(get(&mut collection, "/entities?query=(label:go").status, 400);
+    assert_eq!(get(&mut collection, "/entities?limit=some").status, 400);
+}
+
+#[test]
+fn test_add() {
+    let mut collection = create_test_collection();
+    let body = r#"[
+        {"url": "https://rust-lang.org/", "name": "Rust Website", "labels": ["rust"]},
+        {"url": "https://zig.dev/", "labels": ["lang/zig"], "createdAt": "2023-11-18", "toread": true}
+    ]"#;
+    let (response, changed) = handle(&mut collection, &Request::new("POST", "/entities", body));
+    assert!(changed);
+    assert_eq!(response, Response::ok(json!({ "entities": 2, "added": 1, "merged": 1 })));
+    assert_eq!(collection.len(), 4);
+    let id = collection.id(&Url::parse("https://rust-lang.org/").unwrap()).unwrap();
+    let names: Vec<&str> = collection.entity(id).names().iter().map(Name::as_str).collect();
+    assert_eq!(names, ["Rust", "Rust Website"]);
+    let id = collection.id(&Url::parse("https://zig.dev/").unwrap()).unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-18 0:00 UTC)));
+    assert!(entity.toread());
+
+    let body = r#"{"url": "https://ziglang.org/"}"#;
+    let (response, changed) = handle(&mut collection, &Request::new("POST", "/entities", body));
+    assert!(changed);
+    assert_eq!(response.body["added"], 1);
+
+    for body in [r#"{"url": "not a url"}"#, r#"{"href": "https://ziglang.org/"}"#, "["] {
+        let (response, changed) = handle(&mut collection, &Request::new("POST", "/entities", body));
+        assert_eq!(response.status, 400);
+        assert!(!changed);
+    }
+    assert_eq!(collection.len(), 5);
+}
+
+#[test]
+fn test_search() {
+    let mut collection = create_test_collection();
+    assert_eq!(urls(&get(&mut collection, "/search?q=TOKIO")), ["https://tokio.rs/"]);
+    assert_eq!(urls(&get(&mut collection, "/search?q=tokoi&mode=fuzzy")), ["https://tokio.rs/"]);
+    assert_eq!(urls(&get(&mut collection, "/search?q=go&mode=ranked")), ["https://go.dev/"]);
+    assert_eq!(get(&mut collection, "/search").status, 400);
+    assert_eq!(get(&mut collection, "/search?q=go&mode=exact").status, 400);
+}
+
+#[test]
+fn test_labels() {
+    let mut collection = create_test_collection();
+    let response = get(&mut collection, "/labels");
+    assert_eq!(response, Response::ok(json!({ "async": 1, "lang/go": 1, "lang/rust": 2 })));
+}
+
+#[test]
+fn test_page() {
+    let mut collection = create_test_collection();
+    let response = get(&mut collection, "/page?sort=url&limit=2");
+    assert_eq!(response.status, 200, "{}", response.body);
+    let entities = &response.body["entities"];
+    assert_eq!(entities[0]["entity"]["uri"], "https://go.dev/");
+    assert_eq!(entities[1]["entity"]["uri"], "https://rust-lang.org/");
+    let next = response.body["next"].as_str().unwrap();
+    let response = get(&mut collection, &format!("/page?sort=url&limit=2&after={}", next));
+    assert_eq!(response.body["entities"][0]["entity"]["uri"], "https://tokio.rs/");
+    assert_eq!(response.body["next"], serde_json::Value::Null);
+
+    let response = get(&mut collection, "/page?query=label:lang/rust");
+    assert_eq!(response.body["entities"].as_array().unwrap().len(), 2);
+    assert_eq!(get(&mut collection, "/page?sort=colour").status, 400);
+    assert_eq!(get(&mut collection, "/page?after=nowhere").status, 400);
+    assert_eq!(get(&mut collection, &format!("/page?sort=name&after={}", next)).status, 400);
+}
+
+#[test]
+fn test_not_found() {
+    let mut collection = create_test_collection();
+    assert_eq!(get(&mut collection, "/").status, 404);
+    let (response, _) = handle(&mut collection, &Request::new("DELETE", "/entities", Vec::new()));
+    assert_eq!(response.status, 405);
+}
+
+fn send(addr: std::net::SocketAddr, request: &str) -> String {
+    let mut stream = TcpStream::connect(addr).unwrap();
+    stream.write_all(request.as_bytes()).unwrap();
+    let mut ret = String::new();
+    stream.read_to_string(&mut ret).unwrap();
+    ret
+}
+
+#[test]
+fn test_serve() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = Options { max_body: 64, ..Options::default() };
+    let (sender, receiver) = std::sync::mpsc::channel();
+    thread::spawn(move || {
+        let mut collection = create_test_collection();
+        serve(&listener, &mut collection, &options, |collection| {
+            sender.send(collection.len()).unwrap();
+            Ok(())
+        })
+    });
+
+    let response = send(addr, "GET /labels HTTP/1.1\r\nHost: localhost\r\n\r\n");
+    let body = r#"{"async":1,"lang/go":1,"lang/rust":2}"#;
+    let expected = format!(
+        "HTTP/1.1 200 OK\r\nContent-Type: application/json\r\nContent-Length: {}\r\n\
+         Connection: close\r\n\r\n{}",
+        body.len(),
+        body
+    );
+    assert_eq!(response, expected);
+
+    let body = r#"{"url": "https://zig.dev/"}"#;
+    let request =
+        format!("POST /entities HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+    assert!(send(addr, &request).starts_with("HTTP/1.1 200 OK\r\n"));
+    assert_eq!(receiver.recv().unwrap(), 4);
+
+    let body = "x".repeat(65);
+    let request =
+        format!("POST /entities HTTP/1.1\r\nContent-Length: {}\r\n\r\n{}", body.len(), body);
+    assert!(send(addr, &request).starts_with("HTTP/1.1 413 Content Too Large\r\n"));
+}
+
+fn read(request: &str, options: &Options) -> Incoming {
+    Request::read(&mut io::Cursor::new(request.as_bytes()), options).unwrap()
+}
+
+fn refused(incoming: Incoming) -> u16 {
+    match incoming {
+        Incoming::Refused(response) => response.status,
+        other => panic!("not refused: {:?}", other),
+    }
+}
+
+#[test]
+fn test_read_limits() {
+    let options = Options { max_header: 64, max_body: 8, ..Options::default() };
+    let Incoming::Request(request) =
+        read("POST /entities HTTP/1.1\r\nContent-Length: 2\r\n\r\n{}", &options)
+    else {
+        panic!("not a request");
+    };
+    assert_eq!(request, Request::new("POST", "/entities", "{}"));
+    assert!(matches!(read("", &options), Incoming::Nothing));
+
+    // A head too large is refused, whether in its headers or its request line
+    let request = format!("GET /labels HTTP/1.1\r\nX-Padding: {}\r\n\r\n", "x".repeat(64));
+    assert_eq!(refused(read(&request, &options)), 431);
+    assert_eq!(refused(read(&format!("GET /{} HTTP/1.1\r\n", "x".repeat(64)), &options)), 431);
+    assert_eq!(
+        refused(read("POST /entities HTTP/1.1\r\nContent-Length: 9\r\n\r\n", &options)),
+        413
+    );
+    assert_eq!(refused(read("GET\r\n\r\n", &options)), 400);
+    assert_eq!(refused(read("GET / HTTP/1.1\r\nContent-Length: x\r\n\r\n", &options)), 400);
+}
+
+#[test]
+fn test_serve_deadline() {
+    let listener = TcpListener::bind("127.0.0.1:0").unwrap();
+    let addr = listener.local_addr().unwrap();
+    let options = Options { timeout: Duration::from_millis(200), ..Options::default() };
+    thread::spawn(move || {
+        let mut collection = create_test_collection();
+        serve(&listener, &mut collection, &options, |_| Ok(()))
+    });
+
+    // A client sending a byte at a time, each well within the timeout, is still cut off
+    let mut stream = TcpStream::connect(addr).unwrap();
+    let mut writer = stream.try_clone().unwrap();
+    thread::spawn(move || {
+        for byte in "GET /labels HTTP/1.1\r\n".bytes().chain(std::iter::repeat(b'x')).take(200) {
+            if writer.write_all(&[byte]).is_err() {
+                break;
+            }
+            thread::sleep(Duration::from_millis(20));
+        }
+    });
+    let start = Instant::now();
+    let _ = stream.read(&mut [0; 64]);
+    assert!(start.elapsed() < Duration::from_secs(2));
+}
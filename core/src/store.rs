@@ -0,0 +1,7 @@
+#[cfg(feature = "encrypt")]
+pub mod encrypted;
+#[cfg(feature = "git")]
+pub mod git;
+pub mod journal;
+#[cfg(feature = "sqlite")]
+pub mod sqlite;
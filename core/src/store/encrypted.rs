@@ -0,0 +1,136 @@
+//! A collection encrypted with a key derived from a passphrase, for keeping it at rest.
+//!
+//! The collection is serialized as in [`format::json`](crate::format::json) and sealed with
+//! ChaCha20-Poly1305, under a key derived from the passphrase by PBKDF2-HMAC-SHA256 with a random
+//! salt.  The sealed file starts with a header, which is authenticated along with the contents:
+//!
+//! | bytes | contents                                 |
+//! |-------|------------------------------------------|
+//! | 8     | [`MAGIC`]                                |
+//! | 4     | PBKDF2 iterations, as a big-endian `u32` |
+//! | 16    | salt                                     |
+//! | 12    | nonce                                    |
+//!
+//! Each save draws a fresh salt and nonce, so no key and nonce are ever used together twice.  The
+//! iterations are read before the header can be authenticated, so files asking for more than
+//! [`MAX_ITERATIONS`] are refused rather than spending hours deriving a key for them.
+
+#[cfg(test)]
+mod tests;
+
+use std::num::NonZeroU32;
+
+use ring::{
+    aead::{self, Aad, LessSafeKey, Nonce, UnboundKey, CHACHA20_POLY1305},
+    pbkdf2,
+    rand::{SecureRandom, SystemRandom},
+};
+use thiserror::Error;
+
+use crate::collection::Collection;
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("not an encrypted collection")]
+    NotEncrypted,
+    #[error("wrong passphrase, or the collection is corrupt")]
+    Decrypt,
+    #[error("no randomness available")]
+    Random,
+    #[error("too many PBKDF2 iterations: {0}, at most {MAX_ITERATIONS} are allowed")]
+    TooManyIterations(u32),
+    #[error("failed to encrypt the collection")]
+    Seal,
+}
+
+/// The bytes an encrypted collection starts with.
+pub const MAGIC: &[u8; 8] = b"hbtenc01";
+
+const DEFAULT_ITERATIONS: u32 = 600_000;
+
+/// The most PBKDF2 iterations a collection is sealed or opened with.
+pub const MAX_ITERATIONS: u32 = 8 * DEFAULT_ITERATIONS;
+
+const SALT_LEN: usize = 16;
+const HEADER_LEN: usize = MAGIC.len() + 4 + SALT_LEN + aead::NONCE_LEN;
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The PBKDF2 iterations used to derive the key, which slow guessing of the passphrase.  No
+    /// more than [`MAX_ITERATIONS`].
+    pub iterations: NonZeroU32,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { iterations: NonZeroU32::new(DEFAULT_ITERATIONS).expect("nonzero") }
+    }
+}
+
+fn key(passphrase: &str, salt: &[u8], iterations: NonZeroU32) -> LessSafeKey {
+    let mut key = [0; 32];
+    pbkdf2::derive(pbkdf2::PBKDF2_HMAC_SHA256, iterations, salt, passphrase.as_bytes(), &mut key);
+    LessSafeKey::new(UnboundKey::new(&CHACHA20_POLY1305, &key).expect("key is the right length"))
+}
+
+/// Returns whether the bytes look like an encrypted collection, without checking the passphrase.
+pub fn is_encrypted(input: &[u8]) -> bool {
+    input.len() >= HEADER_LEN && input.starts_with(MAGIC)
+}
+
+/// Encrypts the collection with the passphrase.
+pub fn seal(
+    collection: &Collection,
+    passphrase: &str,
+    options: &Options,
+) -> Result<Vec<u8>, Error> {
+    if options.iterations.get() > MAX_ITERATIONS {
+        return Err(Error::TooManyIterations(options.iterations.get()));
+    }
+    let rng = SystemRandom::new();
+    let mut salt = [0; SALT_LEN];
+    rng.fill(&mut salt).map_err(|_| Error::Random)?;
+    let mut nonce = [0; aead::NONCE_LEN];
+    rng.fill(&mut nonce).map_err(|_| Error::Random)?;
+
+    let mut ret = Vec::with_capacity(HEADER_LEN);
+    ret.extend_from_slice(MAGIC);
+    ret.extend_from_slice(&options.iterations.get().to_be_bytes());
+    ret.extend_from_slice(&salt);
+    ret.extend_from_slice(&nonce);
+
+    let mut contents = serde_json::to_vec(collection)?;
+    key(passphrase, &salt, options.iterations)
+        .seal_in_place_append_tag(
+            Nonce::assume_unique_for_key(nonce),
+            Aad::from(&ret),
+            &mut contents,
+        )
+        .map_err(|_| Error::Seal)?;
+    ret.append(&mut contents);
+    Ok(ret)
+}
+
+/// Decrypts a collection sealed by [`seal`] with the same passphrase.
+pub fn open(input: &[u8], passphrase: &str) -> Result<Collection, Error> {
+    if !is_encrypted(input) {
+        return Err(Error::NotEncrypted);
+    }
+    let (header, contents) = input.split_at(HEADER_LEN);
+    let (iterations, rest) = header[MAGIC.len()..].split_at(4);
+    let (salt, nonce) = rest.split_at(SALT_LEN);
+    let iterations = u32::from_be_bytes(iterations.try_into().expect("four bytes"));
+    let iterations = NonZeroU32::new(iterations).ok_or(Error::NotEncrypted)?;
+    if iterations.get() > MAX_ITERATIONS {
+        return Err(Error::TooManyIterations(iterations.get()));
+    }
+    let nonce = Nonce::try_assume_unique_for_key(nonce).expect("nonce is the right length");
+
+    let mut contents = contents.to_vec();
+    let plaintext = key(passphrase, salt, iterations)
+        .open_in_place(nonce, Aad::from(header), &mut contents)
+        .map_err(|_| Error::Decrypt)?;
+    Ok(serde_json::from_slice(plaintext)?)
+}
@@ -0,0 +1,61 @@
+use url::Url;
+
+use super::*;
+use crate::collection::{Entity, Time};
+
+/// Few iterations, to keep the tests quick.
+fn options() -> Options {
+    Options { iterations: NonZeroU32::new(1_000).unwrap() }
+}
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let url = Url::parse("https://secret.example.com/").unwrap();
+    collection.upsert(Entity::builder(url).created(Time::default()).name("Secret").build());
+    collection
+}
+
+#[test]
+fn test_round_trip() {
+    let collection = create_test_collection();
+    let sealed = seal(&collection, "hunter2", &options()).unwrap();
+    assert!(is_encrypted(&sealed));
+    assert!(!sealed.windows(6).any(|window| window == b"secret"));
+    assert_eq!(open(&sealed, "hunter2").unwrap(), collection);
+
+    // Each seal draws a new salt and nonce
+    assert_ne!(seal(&collection, "hunter2", &options()).unwrap(), sealed);
+}
+
+#[test]
+fn test_wrong_passphrase() {
+    let sealed = seal(&create_test_collection(), "hunter2", &options()).unwrap();
+    assert!(matches!(open(&sealed, "hunter3"), Err(Error::Decrypt)));
+}
+
+#[test]
+fn test_tampered() {
+    let mut sealed = seal(&create_test_collection(), "hunter2", &options()).unwrap();
+    // The header is authenticated too
+    sealed[MAGIC.len() + 4] ^= 1;
+    assert!(matches!(open(&sealed, "hunter2"), Err(Error::Decrypt)));
+}
+
+#[test]
+fn test_not_encrypted() {
+    let json = serde_json::to_vec(&create_test_collection()).unwrap();
+    assert!(!is_encrypted(&json));
+    assert!(matches!(open(&json, "hunter2"), Err(Error::NotEncrypted)));
+    assert!(matches!(open(MAGIC, "hunter2"), Err(Error::NotEncrypted)));
+}
+
+#[test]
+fn test_too_many_iterations() {
+    let mut sealed = seal(&create_test_collection(), "hunter2", &options()).unwrap();
+    sealed[MAGIC.len()..MAGIC.len() + 4].copy_from_slice(&u32::MAX.to_be_bytes());
+    assert!(matches!(open(&sealed, "hunter2"), Err(Error::TooManyIterations(u32::MAX))));
+
+    let options = Options { iterations: NonZeroU32::new(MAX_ITERATIONS + 1).unwrap() };
+    let sealed = seal(&create_test_collection(), "hunter2", &options);
+    assert!(matches!(sealed, Err(Error::TooManyIterations(_))));
+}
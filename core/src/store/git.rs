@@ -0,0 +1,173 @@
+//! A [`Collection`] kept in a git repository, committed on each save.
+//!
+//! The collection is written to [`FILE`] at the top of the repository, as in
+//! [`format::json`](crate::format::json), and committed with a message listing the entities added,
+//! removed and changed.  This gives a history of the collection, and syncing through remotes with
+//! git itself.  The `git` command must be installed.
+//!
+//! A store is only ever created in a directory by [`Store::init`]: [`Store::open`] refuses a
+//! directory that is not already a repository, so that pointing it at the wrong directory does not
+//! turn that directory into one.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    fs::{self, File},
+    io::{self, BufReader, BufWriter, ErrorKind, Write},
+    path::{Path, PathBuf},
+    process::{Command, Output},
+};
+
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, Entity},
+    format::json,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] json::Error),
+    #[error("git {0} failed: {1}")]
+    Git(String, String),
+    #[error("not a git repository: {0}")]
+    NotARepository(PathBuf),
+}
+
+/// The name of the file the collection is kept in.
+pub const FILE: &str = "collection.json";
+
+/// A [`Collection`] persisted in a git repository.
+pub struct Store {
+    dir: PathBuf,
+    /// The collection as last loaded or saved, to describe what each save changes.
+    last: Collection,
+}
+
+fn git(dir: &Path, args: &[&str]) -> Result<Output, Error> {
+    let output = Command::new("git").arg("-C").arg(dir).args(args).output()?;
+    if !output.status.success() {
+        let stderr = String::from_utf8_lossy(&output.stderr).trim().to_string();
+        return Err(Error::Git(args.join(" "), stderr));
+    }
+    Ok(output)
+}
+
+impl Store {
+    /// Opens the repository at the top of the directory, failing with
+    /// [`Error::NotARepository`] if there is none.
+    pub fn open(dir: impl Into<PathBuf>) -> Result<Store, Error> {
+        let dir = dir.into();
+        if !dir.join(".git").exists() {
+            return Err(Error::NotARepository(dir));
+        }
+        let last = match File::open(dir.join(FILE)) {
+            Ok(file) => json::from_reader(BufReader::new(file))?,
+            Err(err) if err.kind() == ErrorKind::NotFound => Collection::new(),
+            Err(err) => return Err(err.into()),
+        };
+        Ok(Store { dir, last })
+    }
+
+    /// Like [`Store::open`], first creating the directory and a repository in it, if there is
+    /// none yet.
+    pub fn init(dir: impl Into<PathBuf>) -> Result<Store, Error> {
+        let dir = dir.into();
+        if !dir.join(".git").exists() {
+            fs::create_dir_all(&dir)?;
+            git(&dir, &["init", "--quiet"])?;
+        }
+        Store::open(dir)
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Loads the collection, which is empty if none was saved yet.
+    pub fn load(&self) -> Result<Collection, Error> {
+        Ok(self.last.clone())
+    }
+
+    /// Writes the collection and commits it, describing the changes since it was last loaded or
+    /// saved.
+    ///
+    /// Returns whether a commit was made, which it is not if the file did not change.
+    pub fn save(&mut self, collection: &Collection) -> Result<bool, Error> {
+        let path = self.dir.join(FILE);
+        let tmp = self.dir.join(format!("{}.tmp", FILE));
+        let mut writer = BufWriter::new(File::create(&tmp)?);
+        json::to_writer(collection, &mut writer)?;
+        writer.write_all(b"\n")?;
+        writer.flush()?;
+        drop(writer);
+        fs::rename(tmp, path)?;
+
+        git(&self.dir, &["add", "--", FILE])?;
+        let staged = git(&self.dir, &["diff", "--cached", "--quiet", "--", FILE]);
+        if staged.is_ok() {
+            self.last = collection.clone();
+            return Ok(false);
+        }
+        let message = message(&self.last, collection);
+        git(&self.dir, &["commit", "--quiet", "--message", &message, "--", FILE])?;
+        self.last = collection.clone();
+        Ok(true)
+    }
+}
+
+fn entities(collection: &Collection) -> BTreeMap<&Url, &Entity> {
+    collection.into_iter().map(|(_, entity)| (entity.url(), entity)).collect()
+}
+
+/// Describes the changes from one collection to the next, as a commit message: a summary line,
+/// then a line for each entity added (`+`), removed (`-`) or changed (`~`).
+pub fn message(old: &Collection, new: &Collection) -> String {
+    let old = entities(old);
+    let new = entities(new);
+    let mut lines = Vec::new();
+    let (mut added, mut removed, mut changed) = (0, 0, 0);
+    for (url, entity) in &new {
+        match old.get(url) {
+            None => {
+                added += 1;
+                lines.push(format!("+ {}", url));
+            }
+            Some(previous) if previous != entity => {
+                changed += 1;
+                lines.push(format!("~ {}", url));
+            }
+            Some(_) => {}
+        }
+    }
+    for url in old.keys().filter(|url| !new.contains_key(*url)) {
+        removed += 1;
+        lines.push(format!("- {}", url));
+    }
+
+    let summary = match (added, removed, changed) {
+        (0, 0, 0) => String::from("Update collection"),
+        (1, 0, 0) => format!("Add {}", &lines[0][2..]),
+        (0, 1, 0) => format!("Remove {}", &lines[0][2..]),
+        (0, 0, 1) => format!("Update {}", &lines[0][2..]),
+        _ => {
+            format!("Update collection: {} added, {} removed, {} changed", added, removed, changed)
+        }
+    };
+    if lines.len() <= 1 {
+        return summary;
+    }
+    let mut ret = summary;
+    ret.push('\n');
+    for line in lines {
+        ret.push('\n');
+        ret.push_str(&line);
+    }
+    ret
+}
@@ -0,0 +1,88 @@
+use super::*;
+use crate::collection::Time;
+
+fn entity(url: &str) -> Entity {
+    Entity::builder(Url::parse(url).unwrap()).created(Time::default()).build()
+}
+
+fn temp_repo(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hbt-git-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    git(&dir, &["init", "--quiet"]).unwrap();
+    git(&dir, &["config", "user.name", "hbt"]).unwrap();
+    git(&dir, &["config", "user.email", "hbt@example.com"]).unwrap();
+    dir
+}
+
+fn log(dir: &Path) -> String {
+    let output = git(dir, &["log", "--format=%B--"]).unwrap();
+    String::from_utf8(output.stdout).unwrap()
+}
+
+#[test]
+fn test_message() {
+    let mut old = Collection::new();
+    assert_eq!(message(&old, &old), "Update collection");
+
+    let mut new = Collection::new();
+    new.upsert(entity("https://foo.com/"));
+    assert_eq!(message(&old, &new), "Add https://foo.com/");
+    assert_eq!(message(&new, &old), "Remove https://foo.com/");
+
+    old.upsert(entity("https://foo.com/"));
+    old.upsert(entity("https://bar.com/"));
+    let id = new.id(&Url::parse("https://foo.com/").unwrap()).unwrap();
+    new.entity_mut(id).set_toread(true);
+    assert_eq!(
+        message(&old, &new),
+        "Update collection: 0 added, 1 removed, 1 changed\n\n~ https://foo.com/\n- https://bar.com/"
+    );
+    new.upsert(entity("https://baz.com/"));
+    assert_eq!(
+        message(&old, &new),
+        "Update collection: 1 added, 1 removed, 1 changed\n\n+ https://baz.com/\n~ \
+         https://foo.com/\n- https://bar.com/"
+    );
+}
+
+#[test]
+fn test_save() {
+    let dir = temp_repo("save");
+    let mut store = Store::open(&dir).unwrap();
+    let mut collection = store.load().unwrap();
+    assert!(collection.is_empty());
+
+    collection.upsert(entity("https://foo.com/"));
+    assert!(store.save(&collection).unwrap());
+    // Saving again changes nothing, so commits nothing
+    assert!(!store.save(&collection).unwrap());
+    collection.upsert(entity("https://bar.com/"));
+    assert!(store.save(&collection).unwrap());
+    assert_eq!(log(&dir), "Add https://bar.com/\n--\nAdd https://foo.com/\n--\n");
+
+    let store = Store::open(&dir).unwrap();
+    assert_eq!(store.load().unwrap(), collection);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_open_init() {
+    let dir = std::env::temp_dir().join(format!("hbt-git-init-{}", std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // Only an explicit init makes a repository of a directory
+    let err = Store::open(&dir).err().unwrap();
+    assert!(matches!(&err, Error::NotARepository(path) if *path == dir));
+    assert!(!dir.join(".git").exists());
+    Store::init(&dir).unwrap();
+    assert!(dir.join(".git").is_dir());
+    Store::open(&dir).unwrap();
+    Store::init(&dir).unwrap();
+    fs::remove_dir_all(&dir).unwrap();
+
+    // The directory itself is created too
+    Store::init(dir.join("nested")).unwrap();
+    assert!(dir.join("nested/.git").is_dir());
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,248 @@
+//! An append-only journal of collection mutations.
+//!
+//! Each mutation is written as one JSON object per line, tagged with the operation and the time it
+//! was recorded:
+//!
+//! ```json
+//! {"time":"2023-11-15T00:00:00Z","op":"addEdge","from":"https://foo.com/","to":"https://bar.com/"}
+//! ```
+//!
+//! Entities are referred to by URL rather than [`Id`](crate::collection::Id), since ids are not
+//! stable across removals. Replaying a journal from the start reconstructs the collection. A
+//! final line that was only partially written, as after a crash, is ignored, and cut off when the
+//! journal is next opened, so that later records start on a line of their own.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeSet,
+    fs::{File, OpenOptions},
+    io::{self, BufRead, Read, Seek, SeekFrom, Write},
+    path::Path,
+};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use url::Url;
+
+use crate::collection::{Collection, EdgeKind, Entity, Label, Name, Time};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}, line: {1}")]
+    Json(serde_json::Error, usize),
+    #[error("JSON error: {0}")]
+    Serialize(#[from] serde_json::Error),
+    #[error("duplicate URL: {0}")]
+    DuplicateUrl(Url),
+    #[error("unknown URL: {0}")]
+    UnknownUrl(Url),
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+#[serde(tag = "op", rename_all = "camelCase")]
+pub enum Event {
+    Insert {
+        entity: Entity,
+    },
+    Upsert {
+        entity: Entity,
+    },
+    Update {
+        url: Url,
+        #[serde(rename = "updatedAt")]
+        updated_at: Time,
+        names: BTreeSet<Name>,
+        labels: BTreeSet<Label>,
+    },
+    Remove {
+        url: Url,
+    },
+    AddEdge {
+        from: Url,
+        to: Url,
+        #[serde(default, skip_serializing_if = "EdgeKind::is_default")]
+        kind: EdgeKind,
+    },
+    RemoveEdge {
+        from: Url,
+        to: Url,
+    },
+    RenameLabel {
+        from: Label,
+        to: Label,
+    },
+    MergeLabel {
+        from: Label,
+        into: Label,
+    },
+    SetName {
+        name: Option<String>,
+    },
+}
+
+impl Event {
+    /// Applies the event to the given collection.
+    pub fn apply(self, collection: &mut Collection) -> Result<(), Error> {
+        match self {
+            Event::Insert { entity } => {
+                if collection.contains(entity.url()) {
+                    return Err(Error::DuplicateUrl(entity.url().clone()));
+                }
+                collection.insert(entity);
+            }
+            Event::Upsert { entity } => {
+                collection.upsert(entity);
+            }
+            Event::Update { url, updated_at, names, labels } => {
+                let id = collection.id(&url).ok_or(Error::UnknownUrl(url))?;
+                collection.update(id, updated_at, names, labels);
+            }
+            Event::Remove { url } => {
+                collection.remove_by_url(&url).ok_or(Error::UnknownUrl(url))?;
+            }
+            Event::AddEdge { from, to, kind } => {
+                let from = collection.id(&from).ok_or(Error::UnknownUrl(from))?;
+                let to = collection.id(&to).ok_or(Error::UnknownUrl(to))?;
+                collection.add_edge_with_kind(from, to, kind);
+            }
+            Event::RemoveEdge { from, to } => {
+                let from = collection.id(&from).ok_or(Error::UnknownUrl(from))?;
+                let to = collection.id(&to).ok_or(Error::UnknownUrl(to))?;
+                collection.remove_edge(from, to);
+            }
+            Event::RenameLabel { from, to } => {
+                collection.rename_label(&from, to);
+            }
+            Event::MergeLabel { from, into } => {
+                collection.merge_label(&from, into);
+            }
+            Event::SetName { name } => collection.set_name(name),
+        }
+        Ok(())
+    }
+}
+
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+pub struct Record {
+    pub time: Time,
+    #[serde(flatten)]
+    pub event: Event,
+}
+
+pub struct Journal<W> {
+    writer: W,
+}
+
+impl Journal<File> {
+    /// Opens the journal at the given path for appending, creating it if necessary.
+    ///
+    /// A partially written final line is cut off first.  A final record that is complete but for
+    /// its newline, which [`read`] accepts, is kept and given one.
+    pub fn open(path: impl AsRef<Path>) -> Result<Journal<File>, Error> {
+        let mut file = OpenOptions::new().create(true).read(true).append(true).open(path)?;
+        repair_tail(&mut file)?;
+        Ok(Journal::new(file))
+    }
+}
+
+/// Makes the file end with a newline, cutting off whatever follows its last one unless it is a
+/// complete record.
+fn repair_tail(file: &mut File) -> Result<(), Error> {
+    let len = file.metadata()?.len();
+    let start = last_line_start(file, len)?;
+    if start == len {
+        return Ok(());
+    }
+    let mut tail = Vec::new();
+    file.seek(SeekFrom::Start(start))?;
+    file.read_to_end(&mut tail)?;
+    if serde_json::from_slice::<Record>(&tail).is_ok() {
+        file.write_all(b"\n")?;
+    } else {
+        file.set_len(start)?;
+    }
+    Ok(())
+}
+
+/// Returns the offset just past the last newline in the first `len` bytes of the file, or 0 if
+/// there is none.
+fn last_line_start(file: &mut File, len: u64) -> io::Result<u64> {
+    let mut buf = [0; 4096];
+    let mut end = len;
+    while end > 0 {
+        let start = end.saturating_sub(buf.len() as u64);
+        let chunk = &mut buf[..(end - start) as usize];
+        file.seek(SeekFrom::Start(start))?;
+        file.read_exact(chunk)?;
+        if let Some(index) = chunk.iter().rposition(|&byte| byte == b'\n') {
+            return Ok(start + index as u64 + 1);
+        }
+        end = start;
+    }
+    Ok(0)
+}
+
+impl<W: Write> Journal<W> {
+    pub fn new(writer: W) -> Journal<W> {
+        Journal { writer }
+    }
+
+    pub fn into_inner(self) -> W {
+        self.writer
+    }
+
+    /// Appends the event to the journal, stamped with the current time.
+    pub fn append(&mut self, event: Event) -> Result<Record, Error> {
+        let record = Record { time: Time::now(), event };
+        let mut line = serde_json::to_vec(&record)?;
+        line.push(b'\n');
+        self.writer.write_all(&line)?;
+        self.writer.flush()?;
+        Ok(record)
+    }
+
+    /// Applies the event to the collection, then appends it to the journal.
+    ///
+    /// Nothing is recorded if the event cannot be applied.
+    pub fn apply(&mut self, collection: &mut Collection, event: Event) -> Result<Record, Error> {
+        event.clone().apply(collection)?;
+        self.append(event)
+    }
+}
+
+/// Reads every complete record in the journal.
+pub fn read(mut reader: impl BufRead) -> Result<Vec<Record>, Error> {
+    let mut ret = Vec::new();
+    let mut line = String::new();
+    let mut number = 0;
+    loop {
+        line.clear();
+        if reader.read_line(&mut line)? == 0 {
+            break;
+        }
+        number += 1;
+        let complete = line.ends_with('\n');
+        if line.trim().is_empty() {
+            continue;
+        }
+        match serde_json::from_str(&line) {
+            Ok(record) => ret.push(record),
+            Err(_) if !complete => break,
+            Err(err) => return Err(Error::Json(err, number)),
+        }
+    }
+    Ok(ret)
+}
+
+/// Reconstructs a collection by applying every record in the journal in order.
+pub fn replay(reader: impl BufRead) -> Result<Collection, Error> {
+    let mut ret = Collection::new();
+    for record in read(reader)? {
+        record.event.apply(&mut ret)?;
+    }
+    Ok(ret)
+}
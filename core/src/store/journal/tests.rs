@@ -0,0 +1,166 @@
+use std::io::Cursor;
+
+use time::macros::datetime;
+
+use super::*;
+
+fn entity(url: &str, name: Option<&str>, labels: &[&str]) -> Entity {
+    Entity::new(
+        Url::parse(url).unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        name.map(Name::from),
+        labels.iter().copied().map(Label::from).collect(),
+    )
+}
+
+fn record_events(events: Vec<Event>) -> (Collection, Vec<u8>) {
+    let mut collection = Collection::new();
+    let mut journal = Journal::new(Vec::new());
+    for event in events {
+        journal.apply(&mut collection, event).unwrap();
+    }
+    (collection, journal.into_inner())
+}
+
+fn test_events() -> Vec<Event> {
+    let foo = Url::parse("https://foo.com").unwrap();
+    let bar = Url::parse("https://bar.com").unwrap();
+    vec![
+        Event::SetName { name: Some(String::from("Test")) },
+        Event::Insert { entity: entity("https://foo.com", Some("Foo"), &["foo"]) },
+        Event::Upsert { entity: entity("https://bar.com", None, &["bar"]) },
+        Event::Upsert { entity: entity("https://bar.com", Some("Bar"), &["baz"]) },
+        Event::AddEdge { from: foo.clone(), to: bar.clone(), kind: EdgeKind::default() },
+        Event::AddEdge { from: bar.clone(), to: foo.clone(), kind: EdgeKind::PartOf },
+        Event::Update {
+            url: foo,
+            updated_at: datetime!(2023-11-16 0:00 UTC).into(),
+            names: BTreeSet::from([Name::from("Foo!")]),
+            labels: BTreeSet::new(),
+        },
+    ]
+}
+
+#[test]
+fn test_replay() {
+    let (collection, journal) = record_events(test_events());
+    assert_eq!(journal.iter().filter(|b| **b == b'\n').count(), 7);
+    let replayed = replay(Cursor::new(journal)).unwrap();
+    assert_eq!(collection, replayed);
+    assert_eq!(replayed.name(), Some("Test"));
+    let foo = replayed.id(&Url::parse("https://foo.com").unwrap()).unwrap();
+    let bar = replayed.id(&Url::parse("https://bar.com").unwrap()).unwrap();
+    assert_eq!(replayed.edge_kind(bar, foo), Some(&EdgeKind::PartOf));
+}
+
+#[test]
+fn test_replay_remove() {
+    let mut events = test_events();
+    events.push(Event::Insert { entity: entity("https://baz.com", None, &[]) });
+    events.push(Event::Remove { url: Url::parse("https://foo.com").unwrap() });
+    let (collection, journal) = record_events(events);
+    assert_eq!(collection.len(), 2);
+    assert_eq!(collection, replay(Cursor::new(journal)).unwrap());
+}
+
+#[test]
+fn test_replay_remove_edge() {
+    let foo = Url::parse("https://foo.com").unwrap();
+    let bar = Url::parse("https://bar.com").unwrap();
+    let mut events = test_events();
+    events.push(Event::RemoveEdge { from: bar.clone(), to: foo.clone() });
+    let (collection, journal) = record_events(events);
+    let replayed = replay(Cursor::new(journal)).unwrap();
+    assert_eq!(collection, replayed);
+    let foo_id = replayed.id(&foo).unwrap();
+    let bar_id = replayed.id(&bar).unwrap();
+    assert_eq!(replayed.edge_kind(bar_id, foo_id), None);
+    assert_eq!(replayed.edges(foo_id), &[bar_id]);
+}
+
+#[test]
+fn test_replay_rename_label() {
+    let mut events = test_events();
+    events.push(Event::RenameLabel { from: Label::from("baz"), to: Label::from("qux") });
+    let (collection, journal) = record_events(events);
+    let replayed = replay(Cursor::new(journal)).unwrap();
+    assert_eq!(collection, replayed);
+    assert_eq!(replayed.by_label(&Label::from("baz")).count(), 0);
+    assert_eq!(replayed.by_label(&Label::from("qux")).count(), 1);
+}
+
+#[test]
+fn test_replay_merge_label() {
+    let mut events = test_events();
+    events.push(Event::MergeLabel { from: Label::from("foo"), into: Label::from("baz") });
+    events.push(Event::Insert { entity: entity("https://qux.com", None, &["foo"]) });
+    let (collection, journal) = record_events(events);
+    let replayed = replay(Cursor::new(journal)).unwrap();
+    assert_eq!(collection, replayed);
+    assert_eq!(replayed.by_label(&Label::from("foo")).count(), 0);
+    assert_eq!(replayed.by_label(&Label::from("baz")).count(), 3);
+}
+
+#[test]
+fn test_failed_event_not_recorded() {
+    let mut collection = Collection::new();
+    let mut journal = Journal::new(Vec::new());
+    let event = Event::Remove { url: Url::parse("https://foo.com").unwrap() };
+    let err = journal.apply(&mut collection, event).unwrap_err();
+    assert!(matches!(err, Error::UnknownUrl(_)));
+    let event = Event::Insert { entity: entity("https://foo.com", None, &[]) };
+    journal.apply(&mut collection, event.clone()).unwrap();
+    let err = journal.apply(&mut collection, event).unwrap_err();
+    assert!(matches!(err, Error::DuplicateUrl(_)));
+    assert_eq!(read(Cursor::new(journal.into_inner())).unwrap().len(), 1);
+}
+
+#[test]
+fn test_torn_final_line() {
+    let (collection, mut journal) = record_events(test_events());
+    journal.extend_from_slice(br#"{"time":1700006400,"op":"rem"#);
+    assert_eq!(collection, replay(Cursor::new(journal)).unwrap());
+}
+
+fn temp_path(name: &str) -> std::path::PathBuf {
+    std::env::temp_dir().join(format!("hbt-journal-{}-{}.jsonl", name, std::process::id()))
+}
+
+#[test]
+fn test_open_after_torn_final_line() {
+    let path = temp_path("torn");
+    let (_, mut contents) = record_events(test_events()[..2].to_vec());
+    contents.extend_from_slice(br#"{"time":1700006400,"op":"rem"#);
+    std::fs::write(&path, contents).unwrap();
+
+    let mut journal = Journal::open(&path).unwrap();
+    journal.append(Event::SetName { name: None }).unwrap();
+    drop(journal);
+    let records = read(Cursor::new(std::fs::read(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(records.len(), 3);
+    assert_eq!(records[2].event, Event::SetName { name: None });
+}
+
+#[test]
+fn test_open_after_missing_newline() {
+    let path = temp_path("newline");
+    let (_, mut contents) = record_events(test_events()[..2].to_vec());
+    assert_eq!(contents.pop(), Some(b'\n'));
+    std::fs::write(&path, contents).unwrap();
+
+    let mut journal = Journal::open(&path).unwrap();
+    journal.append(Event::SetName { name: None }).unwrap();
+    drop(journal);
+    let records = read(Cursor::new(std::fs::read(&path).unwrap())).unwrap();
+    std::fs::remove_file(&path).unwrap();
+    assert_eq!(records.len(), 3);
+}
+
+#[test]
+fn test_corrupt_line() {
+    let (_, mut journal) = record_events(test_events());
+    journal.extend_from_slice(b"garbage\n");
+    let err = replay(Cursor::new(journal)).unwrap_err();
+    assert!(matches!(err, Error::Json(_, 8)));
+}
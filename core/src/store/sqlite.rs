@@ -0,0 +1,305 @@
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::{BTreeMap, HashMap, HashSet},
+    path::Path,
+};
+
+use rusqlite::{params, Connection, OptionalExtension};
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, EdgeKind, Entity, Id, Label},
+    hash::Fnv1a,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("SQLite error: {0}")]
+    Sqlite(#[from] rusqlite::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("URL parsing error: {0}")]
+    ParseUrl(#[from] url::ParseError),
+    #[error("unknown entity row: {0}")]
+    UnknownRow(i64),
+    #[error("incompatible schema version: {0}, expected: {1}")]
+    IncompatibleVersion(i64, i64),
+}
+
+const SCHEMA_VERSION: i64 = 2;
+
+const SCHEMA: &str = "
+CREATE TABLE metadata (
+    key TEXT PRIMARY KEY,
+    value TEXT NOT NULL
+);
+CREATE TABLE entities (
+    id INTEGER PRIMARY KEY,
+    url TEXT NOT NULL UNIQUE,
+    position INTEGER NOT NULL,
+    created_at INTEGER NOT NULL,
+    fingerprint INTEGER NOT NULL,
+    entity TEXT NOT NULL
+);
+CREATE TABLE names (
+    entity_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    name TEXT NOT NULL,
+    PRIMARY KEY (entity_id, name)
+);
+CREATE INDEX names_name ON names (name);
+CREATE TABLE labels (
+    entity_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    label TEXT NOT NULL,
+    PRIMARY KEY (entity_id, label)
+);
+CREATE INDEX labels_label ON labels (label);
+CREATE TABLE edges (
+    from_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    to_id INTEGER NOT NULL REFERENCES entities (id) ON DELETE CASCADE,
+    position INTEGER NOT NULL,
+    kind TEXT NOT NULL DEFAULT 'relatedTo',
+    PRIMARY KEY (from_id, to_id)
+);
+";
+
+/// Upgrades each schema version to the next, starting from version 1.
+const MIGRATIONS: [&str; 1] =
+    ["ALTER TABLE edges ADD COLUMN kind TEXT NOT NULL DEFAULT 'relatedTo';"];
+
+const KEY_NAME: &str = "name";
+const KEY_LABEL_ALIASES: &str = "labelAliases";
+
+#[derive(Debug, Clone, Copy)]
+struct Row {
+    id: i64,
+    position: i64,
+    fingerprint: i64,
+}
+
+/// Counts of the rows touched by [`Store::save`].
+#[derive(Debug, Default, Clone, Copy, PartialEq, Eq)]
+pub struct Saved {
+    pub written: usize,
+    pub removed: usize,
+}
+
+/// A [`Collection`] persisted in a SQLite database.
+///
+/// Each entity is stored whole, alongside tables of its names, labels and edges that can be
+/// queried directly.  Saving only rewrites the entities that changed since the last save.
+pub struct Store {
+    conn: Connection,
+    rows: HashMap<Url, Row>,
+}
+
+impl Store {
+    pub fn open(path: impl AsRef<Path>) -> Result<Store, Error> {
+        let conn = Connection::open(path)?;
+        Store::from_connection(conn)
+    }
+
+    pub fn open_in_memory() -> Result<Store, Error> {
+        let conn = Connection::open_in_memory()?;
+        Store::from_connection(conn)
+    }
+
+    fn from_connection(conn: Connection) -> Result<Store, Error> {
+        conn.pragma_update(None, "foreign_keys", true)?;
+        let version: i64 = conn.pragma_query_value(None, "user_version", |row| row.get(0))?;
+        match version {
+            0 => {
+                conn.execute_batch(SCHEMA)?;
+                conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            1..SCHEMA_VERSION => {
+                for migration in &MIGRATIONS[(version - 1) as usize..] {
+                    conn.execute_batch(migration)?;
+                }
+                conn.pragma_update(None, "user_version", SCHEMA_VERSION)?;
+            }
+            SCHEMA_VERSION => {}
+            _ => return Err(Error::IncompatibleVersion(version, SCHEMA_VERSION)),
+        }
+        let mut rows = HashMap::new();
+        {
+            let mut stmt = conn.prepare("SELECT url, id, position, fingerprint FROM entities")?;
+            let mut query = stmt.query([])?;
+            while let Some(row) = query.next()? {
+                let url = Url::parse(&row.get::<_, String>(0)?)?;
+                rows.insert(
+                    url,
+                    Row { id: row.get(1)?, position: row.get(2)?, fingerprint: row.get(3)? },
+                );
+            }
+        }
+        Ok(Store { conn, rows })
+    }
+
+    pub fn load(&self) -> Result<Collection, Error> {
+        let mut ret = Collection::with_capacity(self.rows.len());
+        let mut ids: HashMap<i64, Id> = HashMap::with_capacity(self.rows.len());
+
+        let name = self
+            .conn
+            .query_row("SELECT value FROM metadata WHERE key = ?1", [KEY_NAME], |row| row.get(0))
+            .optional()?;
+        ret.set_name(name);
+
+        let mut stmt = self.conn.prepare("SELECT id, entity FROM entities ORDER BY position")?;
+        let mut query = stmt.query([])?;
+        while let Some(row) = query.next()? {
+            let entity: Entity = serde_json::from_str(&row.get::<_, String>(1)?)?;
+            let id = ret.insert(entity);
+            ids.insert(row.get(0)?, id);
+        }
+
+        let mut stmt = self
+            .conn
+            .prepare("SELECT from_id, to_id, kind FROM edges ORDER BY from_id, position")?;
+        let mut query = stmt.query([])?;
+        while let Some(row) = query.next()? {
+            let from: i64 = row.get(0)?;
+            let to: i64 = row.get(1)?;
+            let kind = EdgeKind::from(row.get::<_, String>(2)?);
+            let from = *ids.get(&from).ok_or(Error::UnknownRow(from))?;
+            let to = *ids.get(&to).ok_or(Error::UnknownRow(to))?;
+            ret.add_edge_with_kind(from, to, kind);
+        }
+
+        // Aliases are restored last, as the stored entities already have them applied
+        let aliases: Option<String> = self
+            .conn
+            .query_row("SELECT value FROM metadata WHERE key = ?1", [KEY_LABEL_ALIASES], |row| {
+                row.get(0)
+            })
+            .optional()?;
+        if let Some(aliases) = aliases {
+            let aliases: BTreeMap<Label, Label> = serde_json::from_str(&aliases)?;
+            for (alias, target) in aliases {
+                ret.add_label_alias(alias, target);
+            }
+        }
+
+        Ok(ret)
+    }
+
+    pub fn save(&mut self, collection: &Collection) -> Result<Saved, Error> {
+        let mut rows = self.rows.clone();
+        let mut saved = Saved::default();
+        let mut dirty = Vec::new();
+        let tx = self.conn.transaction()?;
+
+        match collection.name() {
+            Some(name) => tx.execute(
+                "INSERT INTO metadata (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![KEY_NAME, name],
+            )?,
+            None => tx.execute("DELETE FROM metadata WHERE key = ?1", [KEY_NAME])?,
+        };
+        if collection.label_aliases().is_empty() {
+            tx.execute("DELETE FROM metadata WHERE key = ?1", [KEY_LABEL_ALIASES])?;
+        } else {
+            tx.execute(
+                "INSERT INTO metadata (key, value) VALUES (?1, ?2)
+                 ON CONFLICT (key) DO UPDATE SET value = excluded.value",
+                params![KEY_LABEL_ALIASES, serde_json::to_string(collection.label_aliases())?],
+            )?;
+        }
+
+        let urls: HashSet<&Url> = collection.entities().iter().map(Entity::url).collect();
+        let removed: Vec<Url> = rows.keys().filter(|url| !urls.contains(url)).cloned().collect();
+        for url in removed {
+            if let Some(row) = rows.remove(&url) {
+                tx.execute("DELETE FROM entities WHERE id = ?1", [row.id])?;
+                saved.removed += 1;
+            }
+        }
+
+        for (id, entity) in collection.iter() {
+            let position = usize::from(id) as i64;
+            let json = serde_json::to_string(entity)?;
+            let fingerprint = fingerprint(collection, id, &json);
+            match rows.get_mut(entity.url()) {
+                Some(row) if row.fingerprint == fingerprint => {
+                    if row.position != position {
+                        tx.execute(
+                            "UPDATE entities SET position = ?1 WHERE id = ?2",
+                            params![position, row.id],
+                        )?;
+                        row.position = position;
+                    }
+                }
+                _ => {
+                    let row_id: i64 = tx.query_row(
+                        "INSERT INTO entities (url, position, created_at, fingerprint, entity)
+                         VALUES (?1, ?2, ?3, ?4, ?5)
+                         ON CONFLICT (url) DO UPDATE SET
+                             position = excluded.position,
+                             created_at = excluded.created_at,
+                             fingerprint = excluded.fingerprint,
+                             entity = excluded.entity
+                         RETURNING id",
+                        params![
+                            entity.url().as_str(),
+                            position,
+                            entity.created_at().unix_timestamp(),
+                            fingerprint,
+                            json
+                        ],
+                        |row| row.get(0),
+                    )?;
+                    tx.execute("DELETE FROM names WHERE entity_id = ?1", [row_id])?;
+                    tx.execute("DELETE FROM labels WHERE entity_id = ?1", [row_id])?;
+                    let mut insert_name =
+                        tx.prepare_cached("INSERT INTO names (entity_id, name) VALUES (?1, ?2)")?;
+                    for name in entity.names() {
+                        insert_name.execute(params![row_id, name.as_str()])?;
+                    }
+                    let mut insert_label =
+                        tx.prepare_cached("INSERT INTO labels (entity_id, label) VALUES (?1, ?2)")?;
+                    for label in entity.labels() {
+                        insert_label.execute(params![row_id, label.as_str()])?;
+                    }
+                    let row = Row { id: row_id, position, fingerprint };
+                    rows.insert(entity.url().clone(), row);
+                    dirty.push(id);
+                    saved.written += 1;
+                }
+            }
+        }
+
+        for from in dirty {
+            let from_row = rows[collection.entity(from).url()].id;
+            tx.execute("DELETE FROM edges WHERE from_id = ?1", [from_row])?;
+            let mut insert_edge = tx.prepare_cached(
+                "INSERT INTO edges (from_id, to_id, position, kind) VALUES (?1, ?2, ?3, ?4)",
+            )?;
+            let edges = collection.edges(from).iter().zip(collection.edge_kinds(from));
+            for (position, (to, kind)) in edges.enumerate() {
+                let to_row = rows[collection.entity(*to).url()].id;
+                insert_edge.execute(params![from_row, to_row, position as i64, kind.as_str()])?;
+            }
+        }
+
+        tx.commit()?;
+        self.rows = rows;
+        Ok(saved)
+    }
+}
+
+/// Hashes an entity's JSON together with the URLs it links to and the kinds of those links, so
+/// that a change to any of them is detected.  The hash is [stable](crate::hash), so that rows are
+/// not rewritten just because the program was built with another release of Rust.
+fn fingerprint(collection: &Collection, id: Id, json: &str) -> i64 {
+    let mut hasher = Fnv1a::new();
+    hasher.write(json.as_bytes());
+    for (to, kind) in collection.edges(id).iter().zip(collection.edge_kinds(id)) {
+        hasher.write(&[0]).write(collection.entity(*to).url().as_str().as_bytes());
+        hasher.write(&[0]).write(kind.as_str().as_bytes());
+    }
+    hasher.finish() as i64
+}
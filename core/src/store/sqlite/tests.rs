@@ -0,0 +1,192 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Label, Name};
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    collection.set_name(Some(String::from("Test")));
+    let foo = Entity::new(
+        Url::parse("https://foo.com").unwrap(),
+        datetime!(2023-11-15 0:00 UTC).into(),
+        Some(Name::new(String::from("Foo"))),
+        BTreeSet::from([Label::from("foo")]),
+    );
+    let bar = Entity::new(
+        Url::parse("https://bar.com").unwrap(),
+        datetime!(2023-11-16 0:00 UTC).into(),
+        None,
+        BTreeSet::from([Label::from("bar"), Label::from("foo")]),
+    );
+    let baz = Entity::new(
+        Url::parse("https://baz.com").unwrap(),
+        datetime!(2023-11-17 0:00 UTC).into(),
+        Some(Name::new(String::from("Baz"))),
+        BTreeSet::new(),
+    );
+    let id_foo = collection.insert(foo);
+    let id_bar = collection.insert(bar);
+    let id_baz = collection.insert(baz);
+    collection.add_edges(id_foo, id_bar);
+    collection.add_edge(id_baz, id_foo);
+    collection
+}
+
+#[test]
+fn test_empty() {
+    let store = Store::open_in_memory().unwrap();
+    let collection = store.load().unwrap();
+    assert!(collection.is_empty());
+    assert_eq!(collection.name(), None);
+}
+
+#[test]
+fn test_round_trip() {
+    let collection = create_test_collection();
+    let mut store = Store::open_in_memory().unwrap();
+    let saved = store.save(&collection).unwrap();
+    assert_eq!(saved, Saved { written: 3, removed: 0 });
+    assert_eq!(collection, store.load().unwrap());
+}
+
+#[test]
+fn test_incremental() {
+    let mut collection = create_test_collection();
+    let mut store = Store::open_in_memory().unwrap();
+    store.save(&collection).unwrap();
+
+    let saved = store.save(&collection).unwrap();
+    assert_eq!(saved, Saved { written: 0, removed: 0 });
+
+    let id = collection.id(&Url::parse("https://bar.com").unwrap()).unwrap();
+    collection.update(
+        id,
+        datetime!(2023-11-18 0:00 UTC).into(),
+        BTreeSet::from([Name::new(String::from("Bar"))]),
+        BTreeSet::new(),
+    );
+    let saved = store.save(&collection).unwrap();
+    assert_eq!(saved, Saved { written: 1, removed: 0 });
+    assert_eq!(collection, store.load().unwrap());
+}
+
+#[test]
+fn test_remove() {
+    let mut collection = create_test_collection();
+    let mut store = Store::open_in_memory().unwrap();
+    store.save(&collection).unwrap();
+
+    collection.remove_by_url(&Url::parse("https://foo.com").unwrap()).unwrap();
+    let saved = store.save(&collection).unwrap();
+    // Both remaining entities linked to the removed one
+    assert_eq!(saved, Saved { written: 2, removed: 1 });
+    assert_eq!(collection, store.load().unwrap());
+}
+
+#[test]
+fn test_queryable_tables() {
+    let collection = create_test_collection();
+    let mut store = Store::open_in_memory().unwrap();
+    store.save(&collection).unwrap();
+
+    let count: i64 = store
+        .conn
+        .query_row("SELECT COUNT(*) FROM labels WHERE label = 'foo'", [], |row| row.get(0))
+        .unwrap();
+    assert_eq!(count, 2);
+    let count: i64 =
+        store.conn.query_row("SELECT COUNT(*) FROM edges", [], |row| row.get(0)).unwrap();
+    assert_eq!(count, 3);
+}
+
+#[test]
+fn test_reopen() {
+    let path = std::env::temp_dir().join(format!("hbt-sqlite-test-{}.db", std::process::id()));
+    let _ = std::fs::remove_file(&path);
+    let collection = create_test_collection();
+    {
+        let mut store = Store::open(&path).unwrap();
+        store.save(&collection).unwrap();
+    }
+    let mut store = Store::open(&path).unwrap();
+    assert_eq!(collection, store.load().unwrap());
+    assert_eq!(store.save(&collection).unwrap(), Saved::default());
+    drop(store);
+    std::fs::remove_file(&path).unwrap();
+}
+
+#[test]
+fn test_label_aliases() {
+    let mut collection = create_test_collection();
+    collection.add_label_alias(Label::from("rustlang"), Label::from("rust"));
+    let mut store = Store::open_in_memory().unwrap();
+    store.save(&collection).unwrap();
+    let loaded = store.load().unwrap();
+    assert_eq!(loaded.label_aliases(), collection.label_aliases());
+    assert_eq!(collection, loaded);
+
+    collection.remove_label_alias(&Label::from("rustlang"));
+    store.save(&collection).unwrap();
+    assert!(store.load().unwrap().label_aliases().is_empty());
+}
+
+#[test]
+fn test_edge_kinds() {
+    let mut collection = create_test_collection();
+    let foo = collection.id(&Url::parse("https://foo.com").unwrap()).unwrap();
+    let bar = collection.id(&Url::parse("https://bar.com").unwrap()).unwrap();
+    let mut store = Store::open_in_memory().unwrap();
+    store.save(&collection).unwrap();
+
+    collection.add_edge_with_kind(foo, bar, EdgeKind::Supersedes);
+    let saved = store.save(&collection).unwrap();
+    assert_eq!(saved, Saved { written: 1, removed: 0 });
+    let loaded = store.load().unwrap();
+    assert_eq!(loaded.edge_kind(foo, bar), Some(&EdgeKind::Supersedes));
+    assert_eq!(collection, loaded);
+}
+
+#[test]
+fn test_fingerprint_is_stable() {
+    let collection = create_test_collection();
+    let bar = collection.id(&Url::parse("https://bar.com").unwrap()).unwrap();
+    let json = serde_json::to_string(collection.entity(bar)).unwrap();
+    let mut expected = Fnv1a::new();
+    expected.write(json.as_bytes()).write(b"\0https://foo.com/\0relatedTo");
+    assert_eq!(fingerprint(&collection, bar, &json), expected.finish() as i64);
+}
+
+#[test]
+fn test_migrate_v1() {
+    let conn = Connection::open_in_memory().unwrap();
+    conn.execute_batch(
+        "CREATE TABLE metadata (key TEXT PRIMARY KEY, value TEXT NOT NULL);
+         CREATE TABLE entities (
+             id INTEGER PRIMARY KEY,
+             url TEXT NOT NULL UNIQUE,
+             position INTEGER NOT NULL,
+             created_at INTEGER NOT NULL,
+             fingerprint INTEGER NOT NULL,
+             entity TEXT NOT NULL
+         );
+         CREATE TABLE names (entity_id INTEGER NOT NULL, name TEXT NOT NULL);
+         CREATE TABLE labels (entity_id INTEGER NOT NULL, label TEXT NOT NULL);
+         CREATE TABLE edges (
+             from_id INTEGER NOT NULL,
+             to_id INTEGER NOT NULL,
+             position INTEGER NOT NULL,
+             PRIMARY KEY (from_id, to_id)
+         );
+         PRAGMA user_version = 1;",
+    )
+    .unwrap();
+    let collection = create_test_collection();
+    let mut store = Store::from_connection(conn).unwrap();
+    store.save(&collection).unwrap();
+    assert_eq!(collection, store.load().unwrap());
+    let version: i64 =
+        store.conn.pragma_query_value(None, "user_version", |row| row.get(0)).unwrap();
+    assert_eq!(version, SCHEMA_VERSION);
+}
@@ -0,0 +1,6 @@
+//! Keeping a collection in step with bookmarking services.
+
+#[cfg(feature = "linkding")]
+pub mod linkding;
+#[cfg(feature = "wallabag")]
+pub mod wallabag;
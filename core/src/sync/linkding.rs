@@ -0,0 +1,227 @@
+//! Syncing with a [linkding](https://github.com/sissbruecker/linkding) server through its REST API.
+
+#[cfg(test)]
+mod tests;
+
+use std::{io, time::Duration};
+
+use serde::{Deserialize, Serialize};
+use thiserror::Error;
+use time::{format_description::well_known::Rfc3339, OffsetDateTime};
+use url::Url;
+
+use crate::{
+    collection::{Collection, Entity, Extended, Name, Time, Union},
+    http,
+    import::Imported,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("time parsing error: {0}, {1}")]
+    ParseTime(#[source] time::error::Parse, String),
+    #[error("time formatting error: {0}")]
+    FormatTime(#[from] time::error::Format),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub timeout: Duration,
+    /// The number of bookmarks asked for at a time.
+    pub page_size: usize,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { timeout: Duration::from_secs(10), page_size: 100 }
+    }
+}
+
+/// The source recorded on updates made by [`Client::pull`].
+pub const SOURCE: &str = "linkding";
+
+const MAX_REDIRECTS: u32 = 5;
+
+/// A page of bookmarks from `GET /api/bookmarks/`.
+#[derive(Debug, Deserialize)]
+struct Page {
+    next: Option<String>,
+    results: Vec<Bookmark>,
+}
+
+/// A bookmark as linkding's API reads and writes it.
+#[derive(Debug, Clone, PartialEq, Eq, Serialize, Deserialize)]
+struct Bookmark {
+    url: String,
+    #[serde(default)]
+    title: String,
+    #[serde(default)]
+    description: String,
+    #[serde(default)]
+    tag_names: Vec<String>,
+    #[serde(default)]
+    unread: bool,
+    #[serde(default)]
+    shared: bool,
+    /// The title of the page, which linkding fetches itself.
+    #[serde(default, skip_serializing)]
+    website_title: Option<String>,
+    #[serde(default, skip_serializing)]
+    date_added: Option<String>,
+}
+
+impl Bookmark {
+    fn from_entity(entity: &Entity) -> Bookmark {
+        Bookmark {
+            url: entity.url().to_string(),
+            title: entity.names().first().map(Name::as_str).unwrap_or_default().to_string(),
+            description: entity.extended().map(Extended::as_str).unwrap_or_default().to_string(),
+            tag_names: entity.labels().iter().map(|label| label.as_str().to_string()).collect(),
+            unread: entity.toread(),
+            shared: entity.shared(),
+            website_title: None,
+            date_added: None,
+        }
+    }
+
+    fn into_entity(self) -> Result<Entity, Error> {
+        let url = Url::parse(&self.url).map_err(|err| Error::ParseUrl(err, self.url))?;
+        let created_at = match self.date_added {
+            Some(date_added) => {
+                let time = OffsetDateTime::parse(&date_added, &Rfc3339)
+                    .map_err(|err| Error::ParseTime(err, date_added))?;
+                // Times are kept to the second
+                time.replace_nanosecond(0).expect("zero is in range").into()
+            }
+            None => Time::default(),
+        };
+        let title = Some(self.title).filter(|title| !title.trim().is_empty());
+        let title = title.or(self.website_title).filter(|title| !title.trim().is_empty());
+        let mut builder = Entity::builder(url)
+            .created(created_at)
+            .labels(self.tag_names)
+            .toread(self.unread)
+            .shared(self.shared);
+        if let Some(title) = title {
+            builder = builder.name(title.trim());
+        }
+        let mut ret = builder.build();
+        if !self.description.is_empty() {
+            ret.set_extended(Some(Extended::new(self.description)));
+        }
+        Ok(ret)
+    }
+}
+
+/// What [`Client::sync`] changed.
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Synced {
+    /// The bookmarks pulled from the server and merged into the collection.
+    pub pulled: Imported,
+    /// The number of entities pushed to the server.
+    pub pushed: usize,
+    /// When the sync started, to sync changes since on the next run.
+    pub at: Time,
+}
+
+/// A linkding server, authenticated with an API token.
+pub struct Client {
+    agent: ureq::Agent,
+    base: Url,
+    token: String,
+    page_size: usize,
+}
+
+impl Client {
+    /// Creates a client for the server at `base`, as in `https://links.example.com/`, with the
+    /// token from its settings page.
+    pub fn new(base: Url, token: impl Into<String>, options: &Options) -> Client {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let page_size = options.page_size.max(1);
+        Client { agent, base, token: token.into(), page_size }
+    }
+
+    fn request(&self, method: &str, url: &Url) -> ureq::Request {
+        self.agent
+            .request_url(method, url)
+            .set("Authorization", &format!("Token {}", self.token))
+            .set("Accept", "application/json")
+    }
+
+    fn endpoint(&self) -> Result<Url, Error> {
+        let path = "api/bookmarks/";
+        self.base.join(path).map_err(|err| Error::ParseUrl(err, path.to_string()))
+    }
+
+    /// Returns the server's bookmarks as entities, or only those modified since the given time.
+    pub fn bookmarks(&self, since: Option<&Time>) -> Result<Vec<Entity>, Error> {
+        let mut url = self.endpoint()?;
+        url.query_pairs_mut().append_pair("limit", &self.page_size.to_string());
+        if let Some(since) = since {
+            let since = OffsetDateTime::from(*since).format(&Rfc3339)?;
+            url.query_pairs_mut().append_pair("modified_since", &since);
+        }
+        let mut ret = Vec::new();
+        let mut next = Some(url);
+        while let Some(url) = next.take() {
+            let response = self.request("GET", &url).call().map_err(Box::new)?;
+            let page: Page = serde_json::from_reader(response.into_reader())?;
+            for bookmark in page.results {
+                ret.push(bookmark.into_entity()?);
+            }
+            if let Some(link) = page.next {
+                next = Some(url.join(&link).map_err(|err| Error::ParseUrl(err, link))?);
+            }
+        }
+        Ok(ret)
+    }
+
+    /// Merges the server's bookmarks modified since the given time, or all of them, into the
+    /// collection, recording [`SOURCE`] on any updates.
+    pub fn pull(
+        &self,
+        collection: &mut Collection,
+        since: Option<&Time>,
+    ) -> Result<Imported, Error> {
+        let entities = self.bookmarks(since)?;
+        Ok(collection.merge_all_from(entities, Some(SOURCE.to_string()), &mut Union))
+    }
+
+    /// Sends the entities created or updated since the given time, or all of them, to the server,
+    /// which updates any bookmark it already has with the same URL.
+    ///
+    /// Returns the number of entities sent.
+    pub fn push(&self, collection: &Collection, since: Option<&Time>) -> Result<usize, Error> {
+        let url = self.endpoint()?;
+        let mut ret = 0;
+        for (_, entity) in collection {
+            let touched = entity.last_updated_at().unwrap_or(entity.created_at());
+            if since.is_some_and(|since| touched <= since) {
+                continue;
+            }
+            let body = serde_json::to_string(&Bookmark::from_entity(entity))?;
+            self.request("POST", &url)
+                .set("Content-Type", "application/json")
+                .send_string(&body)
+                .map_err(Box::new)?;
+            ret += 1;
+        }
+        Ok(ret)
+    }
+
+    /// Pulls then pushes the changes made on either side since the given time, or everything.
+    pub fn sync(&self, collection: &mut Collection, since: Option<&Time>) -> Result<Synced, Error> {
+        let at = Time::now();
+        let pulled = self.pull(collection, since)?;
+        let pushed = self.push(collection, since)?;
+        Ok(Synced { pulled, pushed, at })
+    }
+}
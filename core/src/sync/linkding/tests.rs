@@ -0,0 +1,148 @@
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Label;
+
+fn route(method: &str, path: &str) -> (&'static str, &'static str) {
+    const JSON: &str = "200 OK\r\nContent-Type: application/json\r\n";
+    match (method, path) {
+        ("GET", "/api/bookmarks/") => (
+            JSON,
+            r#"{
+                "count": 3,
+                "next": "/api/bookmarks/page/2/",
+                "previous": null,
+                "results": [
+                    {
+                        "id": 1,
+                        "url": "https://www.rust-lang.org/",
+                        "title": "Rust",
+                        "description": "A language",
+                        "website_title": "Rust Programming Language",
+                        "tag_names": ["programming", "rust"],
+                        "unread": false,
+                        "shared": true,
+                        "date_added": "2023-11-15T09:30:00.123456Z",
+                        "date_modified": "2023-11-16T00:00:00Z"
+                    },
+                    {
+                        "id": 2,
+                        "url": "https://example.com/",
+                        "title": "",
+                        "description": "",
+                        "website_title": "Example Domain",
+                        "tag_names": [],
+                        "unread": true,
+                        "shared": false,
+                        "date_added": "2023-11-17T00:00:00Z",
+                        "date_modified": "2023-11-17T00:00:00Z"
+                    }
+                ]
+            }"#,
+        ),
+        ("GET", "/api/bookmarks/page/2/") => (
+            JSON,
+            r#"{
+                "count": 3,
+                "next": null,
+                "previous": "/api/bookmarks/",
+                "results": [
+                    {
+                        "id": 3,
+                        "url": "https://docs.rs/",
+                        "date_added": "2023-11-18T00:00:00Z"
+                    }
+                ]
+            }"#,
+        ),
+        ("POST", "/api/bookmarks/") => ("201 Created\r\nContent-Type: application/json\r\n", "{}"),
+        _ => ("404 Not Found\r\n", ""),
+    }
+}
+
+fn client(base: Url) -> Client {
+    Client::new(base, "token", &Options { page_size: 2, ..Options::default() })
+}
+
+#[test]
+fn test_bookmarks() {
+    let entities = client(http::serve(route)).bookmarks(None).unwrap();
+    assert_eq!(entities.len(), 3);
+
+    let expected = Entity::builder(Url::parse("https://www.rust-lang.org/").unwrap())
+        .created(datetime!(2023-11-15 9:30 UTC))
+        .name("Rust")
+        .labels(["programming", "rust"])
+        .shared(true)
+        .build();
+    assert_eq!(entities[0].names(), expected.names());
+    assert_eq!(entities[0].labels(), expected.labels());
+    assert_eq!(entities[0].created_at(), expected.created_at());
+    assert!(entities[0].shared());
+    assert_eq!(entities[0].extended(), Some(&Extended::from("A language")));
+
+    // Untitled bookmarks take the page's title
+    assert_eq!(entities[1].names().first(), Some(&Name::from("Example Domain")));
+    assert!(entities[1].toread());
+    assert!(entities[2].names().is_empty());
+}
+
+#[test]
+fn test_pull() {
+    let mut collection = Collection::new();
+    let url = Url::parse("https://www.rust-lang.org/").unwrap();
+    collection.insert(Entity::builder(url).created(datetime!(2023-11-20 0:00 UTC)).build());
+    let pulled = client(http::serve(route)).pull(&mut collection, None).unwrap();
+    assert_eq!(pulled, Imported { entities: 3, added: 2, merged: 1 });
+    let id = collection.by_label(&Label::from("rust")).next().unwrap();
+    let entity = collection.entity(id);
+    assert_eq!(entity.created_at(), &Time::from(datetime!(2023-11-15 9:30 UTC)));
+    assert_eq!(entity.history().last().unwrap().source.as_deref(), Some(SOURCE));
+}
+
+#[test]
+fn test_push() {
+    let client = client(http::serve(route));
+    let mut collection = Collection::new();
+    let old = Url::parse("https://example.com/old").unwrap();
+    collection.insert(Entity::builder(old).created(datetime!(2023-11-01 0:00 UTC)).build());
+    let new = Url::parse("https://example.com/new").unwrap();
+    collection.insert(Entity::builder(new).created(datetime!(2023-11-20 0:00 UTC)).build());
+    assert_eq!(client.push(&collection, None).unwrap(), 2);
+    let since = Time::from(datetime!(2023-11-10 0:00 UTC));
+    assert_eq!(client.push(&collection, Some(&since)).unwrap(), 1);
+}
+
+#[test]
+fn test_from_entity() {
+    let mut entity = Entity::builder(Url::parse("https://example.com/").unwrap())
+        .name("Example")
+        .labels(["web"])
+        .toread(true)
+        .build();
+    entity.set_extended(Some(Extended::from("Notes")));
+    let bookmark = Bookmark::from_entity(&entity);
+    let json = serde_json::to_value(&bookmark).unwrap();
+    assert_eq!(
+        json,
+        serde_json::json!({
+            "url": "https://example.com/",
+            "title": "Example",
+            "description": "Notes",
+            "tag_names": ["web"],
+            "unread": true,
+            "shared": false,
+        })
+    );
+    let entity = bookmark.into_entity().unwrap();
+    assert_eq!(entity.names().first(), Some(&Name::from("Example")));
+}
+
+#[test]
+fn test_unauthorized() {
+    fn route(_: &str, _: &str) -> (&'static str, &'static str) {
+        ("401 Unauthorized\r\n", "")
+    }
+    let result = client(http::serve(route)).bookmarks(None);
+    assert!(matches!(result, Err(Error::Http(_))));
+}
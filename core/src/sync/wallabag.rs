@@ -0,0 +1,137 @@
+//! Saving articles to a [Wallabag](https://wallabag.org/) instance through its API.
+
+#[cfg(test)]
+mod tests;
+
+use std::{io, time::Duration};
+
+use serde::Deserialize;
+use thiserror::Error;
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id, Label},
+    http,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    pub timeout: Duration,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { timeout: Duration::from_secs(30) }
+    }
+}
+
+/// The API client and the user to log in as, from the instance's "API clients management" page.
+#[derive(Debug, Clone)]
+pub struct Credentials {
+    pub client_id: String,
+    pub client_secret: String,
+    pub username: String,
+    pub password: String,
+}
+
+/// The metadata key under which [`Client::push`] keeps the id of an entity's article.
+pub const METADATA_KEY: &str = "wallabag_id";
+
+/// The label [`Client::push`] is usually given.
+pub const DEFAULT_LABEL: &str = "to-read";
+
+const MAX_REDIRECTS: u32 = 5;
+
+#[derive(Debug, Deserialize)]
+struct Token {
+    access_token: String,
+}
+
+#[derive(Debug, Deserialize)]
+struct Article {
+    id: u64,
+}
+
+/// A Wallabag instance, logged in to.
+pub struct Client {
+    agent: ureq::Agent,
+    base: Url,
+    token: String,
+}
+
+impl Client {
+    /// Logs in to the instance at `base`, as in `https://app.wallabag.it/`.
+    pub fn login(base: Url, credentials: &Credentials, options: &Options) -> Result<Client, Error> {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let url = join(&base, "oauth/v2/token")?;
+        let response = agent
+            .request_url("POST", &url)
+            .send_form(&[
+                ("grant_type", "password"),
+                ("client_id", &credentials.client_id),
+                ("client_secret", &credentials.client_secret),
+                ("username", &credentials.username),
+                ("password", &credentials.password),
+            ])
+            .map_err(Box::new)?;
+        let Token { access_token } = serde_json::from_reader(response.into_reader())?;
+        Ok(Client { agent, base, token: access_token })
+    }
+
+    /// Saves the page at the given URL as an article, returning the article's id.  Wallabag
+    /// returns the existing article if the page was saved before.
+    pub fn save(&self, url: &Url) -> Result<u64, Error> {
+        let endpoint = join(&self.base, "api/entries.json")?;
+        let response = self
+            .agent
+            .request_url("POST", &endpoint)
+            .set("Authorization", &format!("Bearer {}", self.token))
+            .send_form(&[("url", url.as_str())])
+            .map_err(Box::new)?;
+        let Article { id } = serde_json::from_reader(response.into_reader())?;
+        Ok(id)
+    }
+
+    /// Saves an article for every entity with the label, or a label nested under it, that has
+    /// not been saved before, keeping the article's id in the entity's metadata under
+    /// [`METADATA_KEY`].
+    ///
+    /// Returns the outcome for each of those entities in [`Id`] order.
+    pub fn push(
+        &self,
+        collection: &mut Collection,
+        label: &Label,
+    ) -> Vec<(Id, Result<u64, Error>)> {
+        let ids: Vec<Id> = collection
+            .by_label_within(label)
+            .filter(|id| collection.entity(*id).metadata_value(METADATA_KEY).is_none())
+            .collect();
+        let mut ret = Vec::with_capacity(ids.len());
+        for id in ids {
+            let result = self.save(collection.entity(id).url());
+            if let Ok(article) = &result {
+                collection
+                    .entity_mut(id)
+                    .set_metadata(METADATA_KEY.to_string(), article.to_string());
+            }
+            ret.push((id, result));
+        }
+        ret
+    }
+}
+
+fn join(base: &Url, path: &str) -> Result<Url, Error> {
+    base.join(path).map_err(|err| Error::ParseUrl(err, path.to_string()))
+}
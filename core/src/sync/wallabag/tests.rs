@@ -0,0 +1,63 @@
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::Entity;
+
+fn route(method: &str, path: &str) -> (&'static str, &'static str) {
+    const JSON: &str = "200 OK\r\nContent-Type: application/json\r\n";
+    match (method, path) {
+        ("POST", "/oauth/v2/token") => {
+            (JSON, r#"{"access_token": "token", "expires_in": 3600, "token_type": "bearer"}"#)
+        }
+        ("POST", "/api/entries.json") => (JSON, r#"{"id": 42, "url": "https://example.com/"}"#),
+        _ => ("404 Not Found\r\n", ""),
+    }
+}
+
+fn credentials() -> Credentials {
+    Credentials {
+        client_id: String::from("id"),
+        client_secret: String::from("secret"),
+        username: String::from("user"),
+        password: String::from("password"),
+    }
+}
+
+#[test]
+fn test_save() {
+    let client = Client::login(http::serve(route), &credentials(), &Options::default()).unwrap();
+    assert_eq!(client.save(&Url::parse("https://example.com/").unwrap()).unwrap(), 42);
+}
+
+#[test]
+fn test_push() {
+    let client = Client::login(http::serve(route), &credentials(), &Options::default()).unwrap();
+    let mut collection = Collection::new();
+    let created = datetime!(2023-11-15 0:00 UTC);
+    let entity = |url: &str, label: &str| {
+        Entity::builder(Url::parse(url).unwrap()).created(created).label(label).build()
+    };
+    let a = collection.insert(entity("https://example.com/a", "to-read"));
+    let b = collection.insert(entity("https://example.com/b", "to-read/later"));
+    collection.insert(entity("https://example.com/c", "done"));
+    let saved = collection.insert(entity("https://example.com/d", "to-read"));
+    collection.entity_mut(saved).set_metadata(METADATA_KEY.to_string(), String::from("7"));
+
+    let results = client.push(&mut collection, &Label::from(DEFAULT_LABEL));
+    let ids: Vec<Id> = results.iter().map(|(id, _)| *id).collect();
+    assert_eq!(ids, vec![a, b]);
+    assert_eq!(collection.entity(a).metadata_value(METADATA_KEY), Some("42"));
+    assert_eq!(collection.entity(saved).metadata_value(METADATA_KEY), Some("7"));
+
+    // Entities are saved once
+    assert!(client.push(&mut collection, &Label::from(DEFAULT_LABEL)).is_empty());
+}
+
+#[test]
+fn test_login_failed() {
+    fn route(_: &str, _: &str) -> (&'static str, &'static str) {
+        ("400 Bad Request\r\nContent-Type: application/json\r\n", r#"{"error": "invalid_grant"}"#)
+    }
+    let result = Client::login(http::serve(route), &credentials(), &Options::default());
+    assert!(matches!(result, Err(Error::Http(_))));
+}
@@ -0,0 +1,155 @@
+//! URL canonicalization.
+//!
+//! URLs parsed with [`Url::parse`] already have some of these applied for the special schemes
+//! (`http`, `https` and friends), but not for others, nor for URLs whose parts have been set
+//! piecemeal.  [`normalize`] applies all of them regardless.
+
+#[cfg(test)]
+mod tests;
+
+use std::collections::BTreeSet;
+
+use url::Url;
+
+#[derive(Debug, Clone, Copy, PartialEq, Eq)]
+pub struct Options {
+    /// Sort query parameters by key, then value.
+    pub sort_query: bool,
+    /// Remove the fragment.
+    pub drop_fragment: bool,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options { sort_query: true, drop_fragment: false }
+    }
+}
+
+fn default_port(scheme: &str) -> Option<u16> {
+    match scheme {
+        "http" | "ws" => Some(80),
+        "https" | "wss" => Some(443),
+        "ftp" => Some(21),
+        _ => None,
+    }
+}
+
+/// Removes `.` and `..` segments from an absolute path, per RFC 3986 section 5.2.4.
+fn remove_dot_segments(path: &str) -> String {
+    let mut segments: Vec<&str> = Vec::new();
+    let mut parts = path.split('/').skip(1).peekable();
+    while let Some(segment) = parts.next() {
+        let last = parts.peek().is_none();
+        match segment {
+            "." | "%2e" | "%2E" => {
+                if last {
+                    segments.push("");
+                }
+            }
+            ".." | ".%2e" | ".%2E" | "%2e." | "%2E." | "%2e%2e" | "%2E%2E" => {
+                segments.pop();
+                if last {
+                    segments.push("");
+                }
+            }
+            _ => segments.push(segment),
+        }
+    }
+    format!("/{}", segments.join("/"))
+}
+
+/// Returns the canonical form of the given URL.
+///
+/// The host is lowercased, a port that is the scheme's default is removed, and `.` and `..` path
+/// segments are resolved.  Depending on `options`, query parameters are sorted and the fragment
+/// is dropped.
+pub fn normalize(url: &Url, options: &Options) -> Url {
+    let mut ret = url.clone();
+    if let Some(host) = url.host_str() {
+        let lower = host.to_lowercase();
+        if lower != host {
+            // Only fails for URLs that cannot have a host, which this one has
+            let _ = ret.set_host(Some(&lower));
+        }
+    }
+    if ret.port().is_some() && ret.port() == default_port(ret.scheme()) {
+        let _ = ret.set_port(None);
+    }
+    if !ret.cannot_be_a_base() && ret.path().starts_with('/') {
+        let path = remove_dot_segments(ret.path());
+        ret.set_path(&path);
+    }
+    if options.sort_query && ret.query().is_some() {
+        let mut pairs: Vec<(String, String)> =
+            ret.query_pairs().map(|(key, value)| (key.into_owned(), value.into_owned())).collect();
+        pairs.sort();
+        if pairs.is_empty() {
+            ret.set_query(None);
+        } else {
+            ret.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+    }
+    if options.drop_fragment {
+        ret.set_fragment(None);
+    }
+    ret
+}
+
+/// Removes tracking parameters from the query of URLs.
+///
+/// By default this strips `utm_*`, `fbclid` and `gclid`.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct TrackingFilter {
+    prefixes: BTreeSet<String>,
+    params: BTreeSet<String>,
+}
+
+impl Default for TrackingFilter {
+    fn default() -> TrackingFilter {
+        let prefixes = BTreeSet::from([String::from("utm_")]);
+        let params = BTreeSet::from([String::from("fbclid"), String::from("gclid")]);
+        TrackingFilter { prefixes, params }
+    }
+}
+
+impl TrackingFilter {
+    /// Returns a filter that strips nothing.
+    pub fn empty() -> TrackingFilter {
+        TrackingFilter { prefixes: BTreeSet::new(), params: BTreeSet::new() }
+    }
+
+    /// Also strips parameters named `param`.
+    pub fn with_param(mut self, param: impl Into<String>) -> TrackingFilter {
+        self.params.insert(param.into());
+        self
+    }
+
+    /// Also strips parameters whose names start with `prefix`.
+    pub fn with_prefix(mut self, prefix: impl Into<String>) -> TrackingFilter {
+        self.prefixes.insert(prefix.into());
+        self
+    }
+
+    pub fn is_tracking(&self, param: &str) -> bool {
+        self.params.contains(param) || self.prefixes.iter().any(|prefix| param.starts_with(prefix))
+    }
+
+    /// Returns the URL without tracking parameters, leaving the order of the rest alone.
+    pub fn strip(&self, url: &Url) -> Url {
+        let mut ret = url.clone();
+        if !url.query_pairs().any(|(key, _)| self.is_tracking(&key)) {
+            return ret;
+        }
+        let pairs: Vec<(String, String)> = url
+            .query_pairs()
+            .filter(|(key, _)| !self.is_tracking(key))
+            .map(|(key, value)| (key.into_owned(), value.into_owned()))
+            .collect();
+        if pairs.is_empty() {
+            ret.set_query(None);
+        } else {
+            ret.query_pairs_mut().clear().extend_pairs(pairs);
+        }
+        ret
+    }
+}
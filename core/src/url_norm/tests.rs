@@ -0,0 +1,60 @@
+use super::*;
+
+fn norm(url: &str) -> String {
+    normalize(&Url::parse(url).unwrap(), &Options::default()).to_string()
+}
+
+#[test]
+fn test_host_and_port() {
+    assert_eq!(norm("HTTPS://Example.COM:443/Page"), "https://example.com/Page");
+    assert_eq!(norm("http://example.com:8080/"), "http://example.com:8080/");
+    assert_eq!(norm("foo://Example.COM/"), "foo://example.com/");
+}
+
+#[test]
+fn test_dot_segments() {
+    assert_eq!(norm("https://example.com/a/./b/../c"), "https://example.com/a/c");
+    assert_eq!(norm("foo://example.com/a/b/.."), "foo://example.com/a/");
+    assert_eq!(norm("foo://example.com/../a"), "foo://example.com/a");
+}
+
+#[test]
+fn test_query() {
+    assert_eq!(norm("https://example.com/?b=2&a=1&a=0"), "https://example.com/?a=0&a=1&b=2");
+    assert_eq!(norm("https://example.com/?"), "https://example.com/");
+    let options = Options { sort_query: false, ..Options::default() };
+    let url = Url::parse("https://example.com/?b=2&a=1").unwrap();
+    assert_eq!(normalize(&url, &options), url);
+}
+
+#[test]
+fn test_fragment() {
+    assert_eq!(norm("https://example.com/#top"), "https://example.com/#top");
+    let options = Options { drop_fragment: true, ..Options::default() };
+    let url = Url::parse("https://example.com/#top").unwrap();
+    assert_eq!(normalize(&url, &options).as_str(), "https://example.com/");
+}
+
+#[test]
+fn test_opaque() {
+    assert_eq!(norm("mailto:Someone@Example.com"), "mailto:Someone@Example.com");
+}
+
+#[test]
+fn test_tracking_filter() {
+    let strip =
+        |filter: &TrackingFilter, url: &str| filter.strip(&Url::parse(url).unwrap()).to_string();
+    let filter = TrackingFilter::default();
+    assert_eq!(
+        strip(&filter, "https://example.com/?utm_source=x&id=1&fbclid=y&gclid=z"),
+        "https://example.com/?id=1"
+    );
+    assert_eq!(strip(&filter, "https://example.com/?utm_medium=email"), "https://example.com/");
+    assert_eq!(strip(&filter, "https://example.com/?b=2&a=1"), "https://example.com/?b=2&a=1");
+
+    let filter = TrackingFilter::empty().with_param("ref").with_prefix("mc_");
+    assert_eq!(
+        strip(&filter, "https://example.com/?ref=hn&mc_cid=1&utm_source=x"),
+        "https://example.com/?utm_source=x"
+    );
+}
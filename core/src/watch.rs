@@ -0,0 +1,144 @@
+//! Watching a directory of Markdown files, merging each file that changes into a collection.
+//!
+//! Changes are found by polling rather than by the platform's file notifications, which would need
+//! a dependency per platform: each file's contents are [hashed](crate::hash) and compared with
+//! those seen last time.  Unlike comparing modification times, this finds every edit however
+//! coarse the filesystem's times, at the cost of reading each file on every poll.
+//!
+//! A file is parsed anew in full when it changes, and its entities merged as by
+//! [`import::merge_into`], recording its path as the source, and the lines they are listed on as
+//! with [`markdown::parse_from`].  Entities that disappear from a file, or a file that is removed,
+//! are left in the collection.
+
+#[cfg(test)]
+mod tests;
+
+use std::{
+    collections::BTreeMap,
+    fs, io,
+    path::{Path, PathBuf},
+    sync::atomic::{AtomicBool, Ordering},
+    thread,
+    time::Duration,
+};
+
+use thiserror::Error;
+
+use crate::{
+    collection::{Collection, Union},
+    hash,
+    import::{self, Imported},
+    markdown,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("IO error: {0}")]
+    Io(#[from] io::Error),
+    #[error("Markdown error: {0}")]
+    Markdown(#[from] markdown::Error),
+}
+
+/// The outcome of merging each file that changed, in path order.
+pub type Changes = Vec<(PathBuf, Result<Imported, Error>)>;
+
+/// A directory of Markdown files, and what its files looked like when last polled.
+#[derive(Debug, Clone)]
+pub struct Watcher {
+    dir: PathBuf,
+    /// The hash of each file's contents.
+    seen: BTreeMap<PathBuf, u64>,
+}
+
+impl Watcher {
+    /// Creates a watcher that has seen nothing, so that it first finds every file changed.
+    pub fn new(dir: impl Into<PathBuf>) -> Watcher {
+        Watcher { dir: dir.into(), seen: BTreeMap::new() }
+    }
+
+    pub fn dir(&self) -> &Path {
+        &self.dir
+    }
+
+    /// Adds the Markdown files in the directory and those below it to `files`, with the hashes of
+    /// their contents.
+    fn scan(dir: &Path, files: &mut BTreeMap<PathBuf, u64>) -> io::Result<()> {
+        for entry in fs::read_dir(dir)? {
+            let entry = entry?;
+            let path = entry.path();
+            let file_type = entry.file_type()?;
+            if file_type.is_dir() {
+                Watcher::scan(&path, files)?;
+            } else if markdown::is_markdown(&path) {
+                // A file removed since it was listed is not there to see
+                let contents = match fs::read(&path) {
+                    Ok(contents) => contents,
+                    Err(err) if err.kind() == io::ErrorKind::NotFound => continue,
+                    Err(err) => return Err(err),
+                };
+                files.insert(path, hash::fnv1a(&contents));
+            }
+        }
+        Ok(())
+    }
+
+    /// Returns the Markdown files in the directory, and those below it, that are new or have
+    /// changed since the last poll, in path order.
+    pub fn poll(&mut self) -> io::Result<Vec<PathBuf>> {
+        let mut files = BTreeMap::new();
+        Watcher::scan(&self.dir, &mut files)?;
+        let ret = files
+            .iter()
+            .filter(|(path, hash)| self.seen.get(*path) != Some(hash))
+            .map(|(path, _)| path.clone())
+            .collect();
+        self.seen = files;
+        Ok(ret)
+    }
+
+    /// Merges every file that changed since the last poll into the collection.
+    ///
+    /// Locations in a file are replaced by those it lists now.  A file that fails to be read or
+    /// parsed leaves the collection untouched, and is tried again once it changes.
+    pub fn update(&mut self, collection: &mut Collection) -> io::Result<Changes> {
+        let mut ret = Vec::new();
+        for path in self.poll()? {
+            let result = fs::read_to_string(&path)
+                .map_err(Error::from)
+                .and_then(|input| Ok(markdown::parse_from(&input, &path)?))
+                .map(|parsed| {
+                    let ids: Vec<_> = collection.by_source(&path).collect();
+                    for id in ids {
+                        collection.entity_mut(id).remove_sources(&path);
+                    }
+                    let source = Some(path.display().to_string());
+                    import::merge_into(collection, &parsed, source, &mut Union)
+                });
+            ret.push((path, result));
+        }
+        Ok(ret)
+    }
+
+    /// Updates the collection every `interval`, calling `changed` with what changed after each
+    /// update that merged any file, until `stop` is set, or `changed` or polling fails.
+    ///
+    /// `stop` is looked at after each update, so setting it stops the watch within an interval.
+    pub fn watch(
+        &mut self,
+        collection: &mut Collection,
+        interval: Duration,
+        stop: &AtomicBool,
+        mut changed: impl FnMut(&Collection, &Changes) -> io::Result<()>,
+    ) -> io::Result<()> {
+        loop {
+            let changes = self.update(collection)?;
+            if !changes.is_empty() {
+                changed(collection, &changes)?;
+            }
+            if stop.load(Ordering::Relaxed) {
+                return Ok(());
+            }
+            thread::sleep(interval);
+        }
+    }
+}
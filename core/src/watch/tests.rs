@@ -0,0 +1,147 @@
+use std::{fs::File, time::SystemTime};
+
+use url::Url;
+
+use super::*;
+use crate::collection::Location;
+
+const RUST: &str = "\
+# November 15, 2023
+
+## Rust
+
+- [Rust](https://www.rust-lang.org/)
+";
+
+const RUST_EDITED: &str = "\
+# November 15, 2023
+
+## Rust
+
+- [Rust](https://www.rust-lang.org/)
+- [Tokio](https://tokio.rs/)
+";
+
+const GO: &str = "\
+# November 16, 2023
+
+- [Go](https://go.dev/)
+";
+
+fn temp_dir(name: &str) -> PathBuf {
+    let dir = std::env::temp_dir().join(format!("hbt-watch-{}-{}", name, std::process::id()));
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(dir.join("nested")).unwrap();
+    dir
+}
+
+/// Writes the file with a modification time of its own, as filesystems may keep coarse times.
+fn write(path: &Path, contents: &str, seconds: u64) {
+    fs::write(path, contents).unwrap();
+    let modified = SystemTime::UNIX_EPOCH + Duration::from_secs(1_700_000_000 + seconds);
+    File::options().write(true).open(path).unwrap().set_modified(modified).unwrap();
+}
+
+fn paths(changes: &Changes) -> Vec<&Path> {
+    changes.iter().map(|(path, _)| path.as_path()).collect()
+}
+
+#[test]
+fn test_update() {
+    let dir = temp_dir("update");
+    let rust = dir.join("rust.md");
+    let go = dir.join("nested").join("go.md");
+    write(&rust, RUST, 0);
+    write(&go, GO, 0);
+    write(&dir.join("notes.txt"), "- [Zig](https://ziglang.org/)", 0);
+
+    let mut watcher = Watcher::new(&dir);
+    let mut collection = Collection::new();
+    let changes = watcher.update(&mut collection).unwrap();
+    assert_eq!(paths(&changes), [go.as_path(), rust.as_path()]);
+    assert_eq!(changes[1].1.as_ref().unwrap(), &Imported { entities: 1, added: 1, merged: 0 });
+    assert_eq!(collection.len(), 2);
+
+    // Nothing changed
+    assert!(watcher.update(&mut collection).unwrap().is_empty());
+
+    write(&rust, RUST_EDITED, 1);
+    let changes = watcher.update(&mut collection).unwrap();
+    assert_eq!(paths(&changes), [rust.as_path()]);
+    assert_eq!(changes[0].1.as_ref().unwrap(), &Imported { entities: 2, added: 1, merged: 1 });
+    let id = collection.id(&Url::parse("https://tokio.rs/").unwrap()).unwrap();
+    let labels: Vec<&str> = collection.entity(id).labels().iter().map(|l| l.as_str()).collect();
+    assert_eq!(labels, ["Rust"]);
+
+    // Moving an entry within a file moves its location
+    write(&rust, "# November 15, 2023\n\n- [Tokio](https://tokio.rs/)\n- [Rust](https://www.rust-lang.org/)\n", 2);
+    watcher.update(&mut collection).unwrap();
+    let id = collection.id(&Url::parse("https://www.rust-lang.org/").unwrap()).unwrap();
+    let sources: Vec<_> = collection.entity(id).sources().iter().cloned().collect();
+    assert_eq!(sources, [Location::new(&rust, 4)]);
+
+    // Removing a file leaves its entities
+    fs::remove_file(&go).unwrap();
+    assert!(watcher.update(&mut collection).unwrap().is_empty());
+    assert_eq!(collection.len(), 3);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_update_invalid() {
+    let dir = temp_dir("invalid");
+    let path = dir.join("undated.md");
+    write(&path, "- [Foo](https://foo.com)\n", 0);
+    let mut watcher = Watcher::new(&dir);
+    let mut collection = Collection::new();
+    let changes = watcher.update(&mut collection).unwrap();
+    assert!(matches!(changes[0].1, Err(Error::Markdown(markdown::Error::MissingDate))));
+    assert!(collection.is_empty());
+
+    // Tried once more after it changes
+    assert!(watcher.update(&mut collection).unwrap().is_empty());
+    write(&path, GO, 1);
+    let changes = watcher.update(&mut collection).unwrap();
+    assert!(changes[0].1.is_ok());
+    assert_eq!(collection.len(), 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_update_same_stamp() {
+    let dir = temp_dir("stamp");
+    let path = dir.join("rust.md");
+    write(&path, RUST, 0);
+    let mut watcher = Watcher::new(&dir);
+    let mut collection = Collection::new();
+    watcher.update(&mut collection).unwrap();
+
+    // An edit keeping the length, within the same tick of the modification time
+    let edited = RUST.replace("rust-lang.org", "rust-lang.net");
+    write(&path, &edited, 0);
+    let changes = watcher.update(&mut collection).unwrap();
+    assert_eq!(paths(&changes), [path.as_path()]);
+    assert!(collection.contains(&Url::parse("https://www.rust-lang.net/").unwrap()));
+    fs::remove_dir_all(&dir).unwrap();
+}
+
+#[test]
+fn test_watch_stop() {
+    let dir = temp_dir("stop");
+    write(&dir.join("rust.md"), RUST, 0);
+    let mut watcher = Watcher::new(&dir);
+    let mut collection = Collection::new();
+    let stop = AtomicBool::new(false);
+    let mut calls = 0;
+    watcher
+        .watch(&mut collection, Duration::ZERO, &stop, |collection, changes| {
+            calls += 1;
+            assert_eq!(changes.len(), 1);
+            assert_eq!(collection.len(), 1);
+            stop.store(true, Ordering::Relaxed);
+            Ok(())
+        })
+        .unwrap();
+    assert_eq!(calls, 1);
+    fs::remove_dir_all(&dir).unwrap();
+}
@@ -0,0 +1,200 @@
+//! Finding and requesting snapshots of pages in the [Wayback Machine](https://web.archive.org/).
+//!
+//! Snapshots are looked up with the Internet Archive's availability API, which returns the one
+//! closest to a given time, or the latest one when no time is given.  New snapshots are requested
+//! with Save Page Now, which redirects to the snapshot once it has been taken.
+
+#[cfg(test)]
+mod tests;
+
+use std::time::Duration;
+
+use serde::Deserialize;
+use thiserror::Error;
+use time::{macros::format_description, PrimitiveDateTime};
+use url::Url;
+
+use crate::{
+    collection::{Collection, Id, LinkStatus, Time},
+    http,
+};
+
+#[derive(Debug, Error)]
+pub enum Error {
+    #[error("HTTP error: {0}")]
+    Http(#[from] Box<ureq::Error>),
+    #[error("IO error: {0}")]
+    Io(#[from] std::io::Error),
+    #[error("JSON error: {0}")]
+    Json(#[from] serde_json::Error),
+    #[error("URL parsing error: {0}, {1}")]
+    ParseUrl(#[source] url::ParseError, String),
+    #[error("time parsing error: {0}, {1}")]
+    ParseTime(#[source] time::error::Parse, String),
+    #[error("no snapshot taken: {0}")]
+    NotSaved(Url),
+}
+
+#[derive(Debug, Clone)]
+pub struct Options {
+    /// The number of requests in flight at once.
+    pub concurrency: usize,
+    pub timeout: Duration,
+    /// The endpoint of the availability API.
+    pub availability_url: Url,
+    /// The prefix of Save Page Now requests, which the URL to save is appended to.
+    pub save_url: Url,
+}
+
+impl Default for Options {
+    fn default() -> Options {
+        Options {
+            concurrency: 4,
+            timeout: Duration::from_secs(60),
+            availability_url: Url::parse("https://archive.org/wayback/available")
+                .expect("valid URL"),
+            save_url: Url::parse("https://web.archive.org/save/").expect("valid URL"),
+        }
+    }
+}
+
+/// The metadata key under which the URL of an entity's latest snapshot is kept.
+pub const METADATA_KEY: &str = "wayback_url";
+
+const MAX_REDIRECTS: u32 = 5;
+
+/// A snapshot of a page.
+#[derive(Debug, Clone, PartialEq, Eq)]
+pub struct Snapshot {
+    /// Where the snapshot can be viewed.
+    pub url: Url,
+    pub taken_at: Time,
+}
+
+#[derive(Debug, Deserialize)]
+struct Availability {
+    archived_snapshots: ArchivedSnapshots,
+}
+
+#[derive(Debug, Deserialize)]
+struct ArchivedSnapshots {
+    closest: Option<Closest>,
+}
+
+#[derive(Debug, Deserialize)]
+struct Closest {
+    #[serde(default)]
+    available: bool,
+    url: String,
+    /// As in `20231115000000`.
+    timestamp: String,
+}
+
+impl Closest {
+    fn into_snapshot(self) -> Result<Snapshot, Error> {
+        let url = Url::parse(&self.url).map_err(|err| Error::ParseUrl(err, self.url))?;
+        let format = format_description!("[year][month][day][hour][minute][second]");
+        let taken_at = PrimitiveDateTime::parse(&self.timestamp, format)
+            .map_err(|err| Error::ParseTime(err, self.timestamp))?;
+        Ok(Snapshot { url, taken_at: taken_at.assume_utc().into() })
+    }
+}
+
+/// Returns whether the last check of an entity's URL found it gone or unreachable.
+fn is_dead(status: &LinkStatus) -> bool {
+    matches!(status, LinkStatus::Dead { .. } | LinkStatus::Failed { .. })
+}
+
+pub struct Archiver {
+    agent: ureq::Agent,
+    concurrency: usize,
+    availability_url: Url,
+    save_url: Url,
+}
+
+impl Archiver {
+    pub fn new(options: &Options) -> Archiver {
+        let agent = http::agent(options.timeout, MAX_REDIRECTS);
+        let concurrency = options.concurrency.max(1);
+        let availability_url = options.availability_url.clone();
+        let save_url = options.save_url.clone();
+        Archiver { agent, concurrency, availability_url, save_url }
+    }
+
+    /// Returns the latest snapshot of the page at the given URL, if there is one.
+    pub fn latest(&self, url: &Url) -> Result<Option<Snapshot>, Error> {
+        let mut endpoint = self.availability_url.clone();
+        endpoint.query_pairs_mut().append_pair("url", url.as_str());
+        let response = self.agent.request_url("GET", &endpoint).call().map_err(Box::new)?;
+        let availability: Availability = serde_json::from_reader(response.into_reader())?;
+        match availability.archived_snapshots.closest {
+            Some(closest) if closest.available => closest.into_snapshot().map(Some),
+            _ => Ok(None),
+        }
+    }
+
+    /// Asks for a new snapshot of the page at the given URL, returning where it can be viewed.
+    pub fn save(&self, url: &Url) -> Result<Url, Error> {
+        let request = format!("{}{}", self.save_url, url);
+        let request = Url::parse(&request).map_err(|err| Error::ParseUrl(err, request))?;
+        let response = self.agent.request_url("GET", &request).call().map_err(Box::new)?;
+        // Without a redirect, the snapshot may still be named in the response's headers
+        let final_url = Url::parse(response.get_url())
+            .map_err(|err| Error::ParseUrl(err, response.get_url().to_string()))?;
+        let ret = match response.header("content-location") {
+            Some(location) => final_url
+                .join(location)
+                .map_err(|err| Error::ParseUrl(err, location.to_string()))?,
+            None => final_url,
+        };
+        if ret.path().starts_with(request.path()) {
+            return Err(Error::NotSaved(url.clone()));
+        }
+        Ok(ret)
+    }
+
+    /// Looks up the latest snapshot of every entity, keeping the URL of each one found in the
+    /// entity's metadata under [`METADATA_KEY`].
+    ///
+    /// Returns the outcome for each entity in [`Id`] order.
+    pub fn lookup(
+        &self,
+        collection: &mut Collection,
+    ) -> Vec<(Id, Result<Option<Snapshot>, Error>)> {
+        let urls: Vec<(Id, &Url)> =
+            collection.iter().map(|(id, entity)| (id, entity.url())).collect();
+        let results =
+            http::map_concurrent(&urls, self.concurrency, |(id, url)| (*id, self.latest(url)));
+        for (id, result) in &results {
+            if let Ok(Some(snapshot)) = result {
+                collection
+                    .entity_mut(*id)
+                    .set_metadata(METADATA_KEY.to_string(), snapshot.url.to_string());
+            }
+        }
+        results
+    }
+
+    /// Asks for a new snapshot of every entity whose last check found its URL dead or
+    /// unreachable, keeping the URL of each one taken in the entity's metadata under
+    /// [`METADATA_KEY`].
+    ///
+    /// Returns the outcome for each of those entities in [`Id`] order.
+    pub fn save_dead(&self, collection: &mut Collection) -> Vec<(Id, Result<Url, Error>)> {
+        let urls: Vec<(Id, &Url)> = collection
+            .iter()
+            .filter(|(_, entity)| entity.last_check().is_some_and(|check| is_dead(&check.status)))
+            .map(|(id, entity)| (id, entity.url()))
+            .collect();
+        let results =
+            http::map_concurrent(&urls, self.concurrency, |(id, url)| (*id, self.save(url)));
+        for (id, result) in &results {
+            if let Ok(snapshot) = result {
+                collection
+                    .entity_mut(*id)
+                    .set_metadata(METADATA_KEY.to_string(), snapshot.to_string());
+            }
+        }
+        results
+    }
+}
@@ -0,0 +1,130 @@
+use std::collections::BTreeSet;
+
+use time::macros::datetime;
+
+use super::*;
+use crate::collection::{Entity, LinkCheck};
+
+const AVAILABLE: &str = r#"{
+  "url": "example.com",
+  "archived_snapshots": {
+    "closest": {
+      "status": "200",
+      "available": true,
+      "url": "http://web.archive.org/web/20231115000000/https://example.com/",
+      "timestamp": "20231115000000"
+    }
+  }
+}"#;
+
+const UNAVAILABLE: &str = r#"{"url": "example.com", "archived_snapshots": {}}"#;
+
+fn route(_method: &str, path: &str) -> (&'static str, &'static str) {
+    const JSON: &str = "200 OK\r\nContent-Type: application/json\r\n";
+    match path {
+        "/available" => (JSON, AVAILABLE),
+        "/unavailable" => (JSON, UNAVAILABLE),
+        _ if path.starts_with("/save/") => {
+            ("302 Found\r\nLocation: /web/20231116000000/https://example.com/\r\n", "")
+        }
+        _ if path.starts_with("/located/") => {
+            ("200 OK\r\nContent-Location: /web/20231117000000/https://example.com/\r\n", "")
+        }
+        _ => ("200 OK\r\n", ""),
+    }
+}
+
+fn options(base: &Url, availability: &str, save: &str) -> Options {
+    Options {
+        availability_url: base.join(availability).unwrap(),
+        save_url: base.join(save).unwrap(),
+        ..Options::default()
+    }
+}
+
+fn example() -> Url {
+    Url::parse("https://example.com/").unwrap()
+}
+
+#[test]
+fn test_latest() {
+    let base = http::serve(route);
+    let archiver = Archiver::new(&options(&base, "/available", "/save/"));
+    let snapshot = archiver.latest(&example()).unwrap().unwrap();
+    let expected = "http://web.archive.org/web/20231115000000/https://example.com/";
+    assert_eq!(snapshot.url.as_str(), expected);
+    assert_eq!(snapshot.taken_at, Time::from(datetime!(2023-11-15 0:00 UTC)));
+
+    let archiver = Archiver::new(&options(&base, "/unavailable", "/save/"));
+    assert_eq!(archiver.latest(&example()).unwrap(), None);
+}
+
+#[test]
+fn test_save() {
+    let base = http::serve(route);
+    let archiver = Archiver::new(&options(&base, "/available", "/save/"));
+    let snapshot = archiver.save(&example()).unwrap();
+    assert_eq!(snapshot, base.join("/web/20231116000000/https://example.com/").unwrap());
+
+    let archiver = Archiver::new(&options(&base, "/available", "/located/"));
+    let snapshot = archiver.save(&example()).unwrap();
+    assert_eq!(snapshot, base.join("/web/20231117000000/https://example.com/").unwrap());
+
+    // Neither redirected nor told where the snapshot is
+    let archiver = Archiver::new(&options(&base, "/available", "/other/"));
+    assert!(matches!(archiver.save(&example()), Err(Error::NotSaved(_))));
+}
+
+fn create_test_collection() -> Collection {
+    let mut collection = Collection::new();
+    let statuses = [
+        ("https://example.com/ok", Some(LinkStatus::Ok { code: 200 })),
+        ("https://example.com/gone", Some(LinkStatus::Dead { code: 404 })),
+        ("https://example.com/down", Some(LinkStatus::Failed { error: "refused".to_string() })),
+        ("https://example.com/unchecked", None),
+    ];
+    for (url, status) in statuses {
+        let created_at = datetime!(2023-11-15 0:00 UTC).into();
+        let id = collection.insert(Entity::new(
+            Url::parse(url).unwrap(),
+            created_at,
+            None,
+            BTreeSet::new(),
+        ));
+        let check = status.map(|status| LinkCheck { checked_at: created_at, status });
+        collection.entity_mut(id).set_last_check(check);
+    }
+    collection
+}
+
+#[test]
+fn test_lookup() {
+    let base = http::serve(route);
+    let archiver = Archiver::new(&options(&base, "/available", "/save/"));
+    let mut collection = create_test_collection();
+    let results = archiver.lookup(&mut collection);
+    assert_eq!(results.len(), collection.len());
+    let expected = "http://web.archive.org/web/20231115000000/https://example.com/";
+    for (id, result) in results {
+        assert!(result.unwrap().is_some());
+        assert_eq!(collection.entity(id).metadata_value(METADATA_KEY), Some(expected));
+    }
+}
+
+#[test]
+fn test_save_dead() {
+    let base = http::serve(route);
+    let archiver = Archiver::new(&options(&base, "/available", "/save/"));
+    let mut collection = create_test_collection();
+    let results = archiver.save_dead(&mut collection);
+    let saved: Vec<&str> =
+        results.iter().map(|(id, _)| collection.entity(*id).url().path()).collect();
+    assert_eq!(saved, vec!["/gone", "/down"]);
+    let expected = base.join("/web/20231116000000/https://example.com/").unwrap();
+    for (id, result) in &results {
+        assert_eq!(result.as_ref().unwrap(), &expected);
+        assert_eq!(collection.entity(*id).metadata_value(METADATA_KEY), Some(expected.as_str()));
+    }
+    let unsaved = collection.iter().filter(|(_, entity)| entity.metadata().is_empty()).count();
+    assert_eq!(unsaved, 2);
+}